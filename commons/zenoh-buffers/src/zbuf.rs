@@ -17,7 +17,12 @@ use crate::{
     SplitBuffer, ZSlice, ZSliceBuffer,
 };
 use alloc::{sync::Arc, vec::Vec};
-use core::{cmp, iter, mem, num::NonZeroUsize, slice};
+use core::{
+    cmp, iter, mem,
+    num::NonZeroUsize,
+    ops::{Bound, RangeBounds},
+    slice,
+};
 use zenoh_collections::SingleOrVec;
 
 fn get_mut_unchecked<T>(arc: &mut Arc<T>) -> &mut T {
@@ -45,6 +50,74 @@ impl ZBuf {
     pub fn push_zslice(&mut self, zslice: ZSlice) {
         self.slices.push(zslice);
     }
+
+    /// Returns the content of the buffer as a contiguous slice without copying it, which is
+    /// possible only if the buffer is not fragmented. See [`SplitBuffer::contiguous`] for a
+    /// fallback copying the content of a fragmented buffer.
+    pub fn as_contiguous(&self) -> Option<&[u8]> {
+        match self.slices.as_ref() {
+            [] => Some(&[]),
+            [slice] => Some(slice.as_slice()),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable view over the content of the buffer. The content is copied only if it
+    /// is shared with other buffers (e.g. clones of this buffer), fragmented or not allocated on
+    /// the heap (e.g. in shared memory), so that cloning a buffer is cheap and the copy is
+    /// deferred to its first mutation.
+    pub fn to_mut(&mut self) -> &mut [u8] {
+        let is_unique = match self.slices.as_mut() {
+            [] => true,
+            [slice] => slice.as_mut_slice().is_some(),
+            _ => false,
+        };
+        if !is_unique {
+            let bytes = self.contiguous().into_owned();
+            self.slices.clear();
+            self.slices.push(bytes.into());
+        }
+        match self.slices.as_mut() {
+            [slice] => slice.as_mut_slice().unwrap_or_default(),
+            _ => &mut [],
+        }
+    }
+
+    /// Returns a view over the bytes of the buffer in `range`, sharing the memory of this
+    /// buffer (i.e. without copying). Returns `None` if `range` is out of bounds.
+    pub fn sub_buffer<R: RangeBounds<usize>>(&self, range: R) -> Option<ZBuf> {
+        let len = self.len();
+        let start = match range.start_bound() {
+            Bound::Included(s) => *s,
+            Bound::Excluded(s) => s.checked_add(1)?,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(e) => e.checked_add(1)?,
+            Bound::Excluded(e) => *e,
+            Bound::Unbounded => len,
+        };
+        if start > end || end > len {
+            return None;
+        }
+
+        let mut zbuf = ZBuf::default();
+        let mut offset = 0;
+        for slice in self.slices.as_ref() {
+            let (s_start, s_end) = (offset, offset + slice.len());
+            offset = s_end;
+            if s_end <= start {
+                continue;
+            }
+            if s_start >= end {
+                break;
+            }
+            let from = start.saturating_sub(s_start);
+            let to = end.min(s_end) - s_start;
+            zbuf.push_zslice(slice.new_sub_slice(from, to)?);
+        }
+        Some(zbuf)
+    }
 }
 
 impl<'a> SplitBuffer<'a> for ZBuf {
@@ -522,4 +595,120 @@ mod tests {
 
         assert_eq!(zbuf1, zbuf2);
     }
+
+    #[test]
+    fn zbuf_as_contiguous() {
+        use super::{ZBuf, ZSlice};
+
+        let slice: ZSlice = [0u8, 1, 2, 3, 4, 5, 6, 7].to_vec().into();
+
+        let zbuf = ZBuf::default();
+        assert_eq!(zbuf.as_contiguous(), Some(&[][..]));
+
+        let mut zbuf = ZBuf::default();
+        zbuf.push_zslice(slice.clone());
+        assert_eq!(zbuf.as_contiguous(), Some(&[0u8, 1, 2, 3, 4, 5, 6, 7][..]));
+        assert_eq!(
+            zbuf.as_contiguous().unwrap().as_ptr(),
+            slice.as_slice().as_ptr()
+        );
+
+        zbuf.push_zslice(slice);
+        assert!(zbuf.as_contiguous().is_none());
+    }
+
+    #[test]
+    fn zbuf_to_mut() {
+        use super::{ZBuf, ZSlice};
+        use crate::SplitBuffer;
+
+        let slice: ZSlice = [0u8, 1, 2, 3].to_vec().into();
+
+        // A unique buffer is modified in place
+        let mut zbuf = ZBuf::default();
+        zbuf.push_zslice(slice);
+        let ptr = zbuf.as_contiguous().unwrap().as_ptr();
+        zbuf.to_mut()[0] = 10;
+        assert_eq!(zbuf.as_contiguous().unwrap().as_ptr(), ptr);
+        assert_eq!(&zbuf.contiguous()[..], &[10u8, 1, 2, 3]);
+
+        // A shared buffer is copied on the first mutation
+        let copy = zbuf.clone();
+        zbuf.to_mut()[1] = 11;
+        assert_ne!(zbuf.as_contiguous().unwrap().as_ptr(), ptr);
+        assert_eq!(&zbuf.contiguous()[..], &[10u8, 11, 2, 3]);
+        assert_eq!(&copy.contiguous()[..], &[10u8, 1, 2, 3]);
+
+        // A fragmented buffer is made contiguous
+        let mut zbuf = copy.clone();
+        zbuf.push_zslice([4u8, 5].to_vec().into());
+        zbuf.to_mut()[5] = 15;
+        assert_eq!(zbuf.as_contiguous(), Some(&[10u8, 1, 2, 3, 4, 15][..]));
+
+        assert!(ZBuf::default().to_mut().is_empty());
+    }
+
+    #[test]
+    fn zbuf_to_mut_segment() {
+        use super::{ZBuf, ZSlice};
+        use crate::{SplitBuffer, ZSliceBuffer};
+        use std::sync::Arc;
+
+        // A buffer mapped out of the heap, like a shared memory segment
+        #[derive(Debug)]
+        struct Segment(Vec<u8>);
+
+        impl ZSliceBuffer for Segment {
+            fn as_slice(&self) -> &[u8] {
+                &self.0
+            }
+            fn as_mut_slice(&mut self) -> &mut [u8] {
+                &mut self.0
+            }
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+        }
+
+        let segment: Arc<dyn ZSliceBuffer> = Arc::new(Segment(vec![0u8, 1, 2, 3]));
+        let mut zbuf = ZBuf::default();
+        zbuf.push_zslice(ZSlice::make(segment.clone(), 0, 4).unwrap());
+        let ptr = zbuf.as_contiguous().unwrap().as_ptr();
+
+        // A uniquely held segment is copied rather than written in place
+        drop(segment);
+        zbuf.to_mut()[0] = 10;
+        assert_ne!(zbuf.as_contiguous().unwrap().as_ptr(), ptr);
+        assert_eq!(&zbuf.contiguous()[..], &[10u8, 1, 2, 3]);
+    }
+
+    #[test]
+    fn zbuf_sub_buffer() {
+        use super::{ZBuf, ZSlice};
+        use crate::SplitBuffer;
+
+        let slice: ZSlice = [0u8, 1, 2, 3, 4, 5, 6, 7].to_vec().into();
+
+        let mut zbuf = ZBuf::default();
+        zbuf.push_zslice(slice.new_sub_slice(0, 3).unwrap());
+        zbuf.push_zslice(slice.new_sub_slice(3, 5).unwrap());
+        zbuf.push_zslice(slice.new_sub_slice(5, 8).unwrap());
+
+        let sub = zbuf.sub_buffer(2..6).unwrap();
+        assert_eq!(&sub.contiguous()[..], &[2u8, 3, 4, 5]);
+        assert_eq!(sub.zslices().count(), 3);
+
+        let sub = zbuf.sub_buffer(3..5).unwrap();
+        assert_eq!(sub.as_contiguous(), Some(&[3u8, 4][..]));
+        assert_eq!(
+            sub.as_contiguous().unwrap().as_ptr(),
+            slice.as_slice()[3..].as_ptr()
+        );
+
+        assert_eq!(zbuf.sub_buffer(..), Some(zbuf.clone()));
+        assert_eq!(zbuf.sub_buffer(..=7), Some(zbuf.clone()));
+        assert!(zbuf.sub_buffer(4..4).unwrap().is_empty());
+        assert!(zbuf.sub_buffer(..9).is_none());
+        assert!(zbuf.sub_buffer(5..4).is_none());
+    }
 }
@@ -110,6 +110,18 @@ impl ZSlice {
         &self.buf.as_slice()[self.range()]
     }
 
+    /// Returns a mutable view over the slice, unless its buffer is shared with other slices or
+    /// is not allocated on the heap (e.g. a shared memory segment read by other processes).
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> Option<&mut [u8]> {
+        let any = self.buf.as_any();
+        if !any.is::<Vec<u8>>() && !any.is::<Box<[u8]>>() {
+            return None;
+        }
+        let range = self.range();
+        Arc::get_mut(&mut self.buf).map(|buf| &mut buf.as_mut_slice()[range])
+    }
+
     pub(crate) fn new_sub_slice(&self, start: usize, end: usize) -> Option<ZSlice> {
         if end <= self.len() {
             Some(ZSlice {
@@ -12,13 +12,14 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 use crate::{RCodec, WCodec, Zenoh060, Zenoh060Header};
+use alloc::vec::Vec;
 use zenoh_buffers::{
-    reader::{DidntRead, Reader},
-    writer::{DidntWrite, Writer},
+    reader::{DidntRead, HasReader, Reader},
+    writer::{DidntWrite, HasWriter, Writer},
     ZBuf,
 };
 use zenoh_protocol::{
-    common::{imsg, Attachment},
+    common::{imsg, Attachment, ZExtension},
     transport::tmsg,
 };
 #[cfg(feature = "shared-memory")]
@@ -46,17 +47,36 @@ where
             }
         }
 
+        if !x.extensions.is_empty() {
+            header |= tmsg::flag::N;
+        }
+
         self.write(&mut *writer, header)?;
 
         // Body
+        let mut zbuf = ZBuf::default();
+        let buffer = if imsg::has_flag(header, tmsg::flag::N) {
+            // Extensions are prepended to the buffer, within the same length-prefixed buffer
+            {
+                let mut zwriter = zbuf.writer();
+                self.write(&mut zwriter, x.extensions.as_slice())?;
+                for s in x.buffer.zslices() {
+                    zwriter.write_zslice(s)?;
+                }
+            }
+            &zbuf
+        } else {
+            &x.buffer
+        };
+
         #[cfg(feature = "shared-memory")]
         {
             let codec = Zenoh060Condition::new(imsg::has_flag(header, tmsg::flag::Z));
-            codec.write(&mut *writer, &x.buffer)
+            codec.write(&mut *writer, buffer)
         }
         #[cfg(not(feature = "shared-memory"))]
         {
-            self.write(&mut *writer, &x.buffer)
+            self.write(&mut *writer, buffer)
         }
     }
 }
@@ -99,6 +119,21 @@ where
             }
         };
 
-        Ok(Attachment { buffer })
+        if !imsg::has_flag(self.header, tmsg::flag::N) {
+            return Ok(Attachment::new(buffer));
+        }
+
+        let mut zreader = buffer.reader();
+        let extensions: Vec<ZExtension> = self.codec.read(&mut zreader)?;
+        let mut rest = ZBuf::default();
+        let len = zreader.remaining();
+        if len > 0 {
+            zreader.read_zslices(len, |s| rest.push_zslice(s))?;
+        }
+
+        Ok(Attachment {
+            buffer: rest,
+            extensions,
+        })
     }
 }
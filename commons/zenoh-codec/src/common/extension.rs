@@ -0,0 +1,84 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use crate::{RCodec, WCodec, Zenoh060};
+use alloc::vec::Vec;
+use zenoh_buffers::{
+    reader::{DidntRead, Reader},
+    writer::{DidntWrite, Writer},
+    ZBuf,
+};
+use zenoh_protocol::{common::ZExtension, core::ZInt};
+
+impl<W> WCodec<&ZExtension, &mut W> for Zenoh060
+where
+    W: Writer,
+{
+    type Output = Result<(), DidntWrite>;
+
+    fn write(self, writer: &mut W, x: &ZExtension) -> Self::Output {
+        self.write(&mut *writer, x.id)?;
+        self.write(&mut *writer, &x.value)?;
+        Ok(())
+    }
+}
+
+impl<R> RCodec<ZExtension, &mut R> for Zenoh060
+where
+    R: Reader,
+{
+    type Error = DidntRead;
+
+    fn read(self, reader: &mut R) -> Result<ZExtension, Self::Error> {
+        let id: ZInt = self.read(&mut *reader)?;
+        let value: ZBuf = self.read(&mut *reader)?;
+
+        Ok(ZExtension { id, value })
+    }
+}
+
+impl<W> WCodec<&[ZExtension], &mut W> for Zenoh060
+where
+    W: Writer,
+{
+    type Output = Result<(), DidntWrite>;
+
+    fn write(self, writer: &mut W, x: &[ZExtension]) -> Self::Output {
+        self.write(&mut *writer, x.len())?;
+        for e in x.iter() {
+            self.write(&mut *writer, e)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<R> RCodec<Vec<ZExtension>, &mut R> for Zenoh060
+where
+    R: Reader,
+{
+    type Error = DidntRead;
+
+    fn read(self, reader: &mut R) -> Result<Vec<ZExtension>, Self::Error> {
+        let num: usize = self.read(&mut *reader)?;
+
+        // Do not trust the announced number of extensions for the allocation
+        let mut es = Vec::with_capacity(num.min(reader.remaining()));
+        for _ in 0..num {
+            let e: ZExtension = self.read(&mut *reader)?;
+            es.push(e);
+        }
+
+        Ok(es)
+    }
+}
@@ -12,4 +12,5 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 mod attachment;
+mod extension;
 mod priority;
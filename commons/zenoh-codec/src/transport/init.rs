@@ -25,6 +25,14 @@ use zenoh_protocol::{
 };
 
 // InitSyn
+fn read_features(options: ZInt) -> ZInt {
+    if imsg::has_option(options, tmsg::init_options::FEATURES) {
+        options >> tmsg::features::FEATURES_SHIFT
+    } else {
+        tmsg::features::LEGACY
+    }
+}
+
 impl<W> WCodec<&InitSyn, &mut W> for Zenoh060
 where
     W: Writer,
@@ -33,7 +41,7 @@ where
 
     fn write(self, writer: &mut W, x: &InitSyn) -> Self::Output {
         fn has_options(x: &InitSyn) -> bool {
-            x.is_qos
+            x.is_qos || x.features != tmsg::features::LEGACY
         }
 
         fn options(x: &InitSyn) -> ZInt {
@@ -41,6 +49,10 @@ where
             if x.is_qos {
                 options |= tmsg::init_options::QOS;
             }
+            if x.features != tmsg::features::LEGACY {
+                options |= tmsg::init_options::FEATURES;
+                options |= x.features << tmsg::features::FEATURES_SHIFT;
+            }
             options
         }
 
@@ -110,6 +122,7 @@ where
             SEQ_NUM_RES
         };
         let is_qos = imsg::has_option(options, tmsg::init_options::QOS);
+        let features = read_features(options);
 
         Ok(InitSyn {
             version,
@@ -117,6 +130,7 @@ where
             zid,
             sn_resolution,
             is_qos,
+            features,
         })
     }
 }
@@ -130,7 +144,7 @@ where
 
     fn write(self, writer: &mut W, x: &InitAck) -> Self::Output {
         fn has_options(x: &InitAck) -> bool {
-            x.is_qos
+            x.is_qos || x.features != tmsg::features::LEGACY
         }
 
         fn options(x: &InitAck) -> ZInt {
@@ -138,6 +152,10 @@ where
             if x.is_qos {
                 options |= tmsg::init_options::QOS;
             }
+            if x.features != tmsg::features::LEGACY {
+                options |= tmsg::init_options::FEATURES;
+                options |= x.features << tmsg::features::FEATURES_SHIFT;
+            }
             options
         }
 
@@ -208,6 +226,7 @@ where
             None
         };
         let is_qos = imsg::has_option(options, tmsg::init_options::QOS);
+        let features = read_features(options);
         let cookie: ZSlice = self.codec.read(&mut *reader)?;
 
         Ok(InitAck {
@@ -215,6 +234,7 @@ where
             zid,
             sn_resolution,
             is_qos,
+            features,
             cookie,
         })
     }
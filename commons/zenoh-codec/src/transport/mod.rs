@@ -12,6 +12,7 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 mod close;
+mod credit;
 mod frame;
 mod init;
 mod join;
@@ -48,6 +49,7 @@ where
             TransportBody::Close(b) => self.write(&mut *writer, b),
             TransportBody::KeepAlive(b) => self.write(&mut *writer, b),
             TransportBody::Frame(b) => self.write(&mut *writer, b),
+            TransportBody::Credit(b) => self.write(&mut *writer, b),
         }
     }
 }
@@ -88,6 +90,7 @@ where
             tmsg::id::CLOSE => TransportBody::Close(codec.read(&mut *reader)?),
             tmsg::id::KEEP_ALIVE => TransportBody::KeepAlive(codec.read(&mut *reader)?),
             tmsg::id::PRIORITY | tmsg::id::FRAME => TransportBody::Frame(codec.read(&mut *reader)?),
+            tmsg::id::CREDIT => TransportBody::Credit(codec.read(&mut *reader)?),
             _ => return Err(DidntRead),
         };
 
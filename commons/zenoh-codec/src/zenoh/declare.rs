@@ -22,7 +22,7 @@ use zenoh_protocol::{
     core::{QueryableInfo, Reliability, SubInfo, SubMode, WireExpr, ZInt},
     zenoh::{
         zmsg, Declaration, Declare, ForgetPublisher, ForgetQueryable, ForgetResource,
-        ForgetSubscriber, Publisher, Queryable, Resource, Subscriber,
+        ForgetSubscriber, Interest, Publisher, Queryable, Resource, Subscriber,
     },
 };
 
@@ -43,6 +43,7 @@ where
             Declaration::ForgetSubscriber(r) => self.write(&mut *writer, r)?,
             Declaration::Queryable(r) => self.write(&mut *writer, r)?,
             Declaration::ForgetQueryable(r) => self.write(&mut *writer, r)?,
+            Declaration::Interest(r) => self.write(&mut *writer, r)?,
         }
 
         Ok(())
@@ -72,6 +73,7 @@ where
             FORGET_SUBSCRIBER => Declaration::ForgetSubscriber(codec.read(&mut *reader)?),
             QUERYABLE => Declaration::Queryable(codec.read(&mut *reader)?),
             FORGET_QUERYABLE => Declaration::ForgetQueryable(codec.read(&mut *reader)?),
+            INTEREST => Declaration::Interest(codec.read(&mut *reader)?),
             _ => return Err(DidntRead),
         };
 
@@ -482,7 +484,12 @@ where
 
         Ok(Subscriber {
             key,
-            info: SubInfo { reliability, mode },
+            info: SubInfo {
+                reliability,
+                mode,
+                filter: None,
+                aggregation: None,
+            },
         })
     }
 }
@@ -701,3 +708,61 @@ where
         Ok(ForgetQueryable { key })
     }
 }
+
+// Interest
+impl<W> WCodec<&Interest, &mut W> for Zenoh060
+where
+    W: Writer,
+{
+    type Output = Result<(), DidntWrite>;
+
+    fn write(self, writer: &mut W, x: &Interest) -> Self::Output {
+        // Header
+        let mut header = zmsg::declaration::id::INTEREST;
+        if x.key.has_suffix() {
+            header |= zmsg::flag::K
+        }
+        self.write(&mut *writer, header)?;
+
+        // Body
+        self.write(&mut *writer, &x.key)?;
+
+        Ok(())
+    }
+}
+
+impl<R> RCodec<Interest, &mut R> for Zenoh060
+where
+    R: Reader,
+{
+    type Error = DidntRead;
+
+    fn read(self, reader: &mut R) -> Result<Interest, Self::Error> {
+        let codec = Zenoh060Header {
+            header: self.read(&mut *reader)?,
+            ..Default::default()
+        };
+        codec.read(reader)
+    }
+}
+
+impl<R> RCodec<Interest, &mut R> for Zenoh060Header
+where
+    R: Reader,
+{
+    type Error = DidntRead;
+
+    fn read(self, reader: &mut R) -> Result<Interest, Self::Error> {
+        if imsg::mid(self.header) != zmsg::declaration::id::INTEREST {
+            return Err(DidntRead);
+        }
+
+        let ccond = Zenoh060Condition {
+            condition: imsg::has_flag(self.header, zmsg::flag::K),
+            codec: self.codec,
+        };
+        let key: WireExpr<'static> = ccond.read(&mut *reader)?;
+
+        Ok(Interest { key })
+    }
+}
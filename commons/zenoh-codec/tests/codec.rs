@@ -20,7 +20,7 @@ use std::sync::Arc;
 use zenoh_buffers::{
     reader::{HasReader, Reader},
     writer::HasWriter,
-    BBuf, ZBuf, ZSlice,
+    BBuf, SplitBuffer, ZBuf, ZSlice,
 };
 use zenoh_codec::*;
 use zenoh_protocol::{common::*, core::*, scouting::*, transport::*, zenoh::*};
@@ -173,6 +173,84 @@ fn codec_attachment() {
     run!(Attachment, Attachment::rand());
 }
 
+#[test]
+fn codec_extension() {
+    run!(ZExtension, ZExtension::rand());
+}
+
+#[test]
+fn codec_attachment_extensions_opaque() {
+    // Receivers unaware of extensions must read the attachment as a single buffer
+    let codec = Zenoh060::default();
+    for _ in 0..NUM_ITER {
+        let mut x = Attachment::rand();
+        x.insert_extension(ZExtension::rand());
+
+        let mut vbuf = vec![];
+        let mut writer = vbuf.writer();
+        codec.write(&mut writer, &x).unwrap();
+
+        let mut reader = vbuf.reader();
+        let header: u8 = codec.read(&mut reader).unwrap();
+        assert_eq!(imsg::mid(header), imsg::id::ATTACHMENT);
+        assert!(imsg::has_flag(header, tmsg::flag::N));
+        let buffer: ZBuf = codec.read(&mut reader).unwrap();
+        assert!(!reader.can_read());
+        assert!(buffer.len() > x.buffer.len());
+    }
+}
+
+#[test]
+fn codec_subscriber_filters() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..NUM_ITER {
+        let n = rng.gen_range(0..8);
+        let x = SubscriberFilters(
+            (0..n)
+                .map(|_| {
+                    let len = rng.gen_range(0..32);
+                    (rng.gen(), Alphanumeric.sample_string(&mut rng, len))
+                })
+                .collect(),
+        );
+        let mut attachment = Attachment::new(ZBuf::default());
+        attachment.set(&x);
+        assert_eq!(attachment.get::<SubscriberFilters>(), Some(x));
+    }
+    assert_eq!(
+        SubscriberFilters::decode(&ZBuf::from(vec![0x01, 0x02])),
+        None
+    );
+}
+
+#[test]
+fn codec_subscriber_aggregations() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..NUM_ITER {
+        let n = rng.gen_range(0..8);
+        let x = SubscriberAggregations(
+            (0..n)
+                .map(|_| {
+                    (
+                        rng.gen(),
+                        SubAggregation {
+                            window: rng.gen(),
+                            max_rate: rng.gen(),
+                        },
+                    )
+                })
+                .collect(),
+        );
+        let mut attachment = Attachment::new(ZBuf::default());
+        attachment.set(&x);
+        assert_eq!(attachment.get::<SubscriberAggregations>(), Some(x));
+    }
+    assert_eq!(
+        SubscriberAggregations::decode(&ZBuf::from(vec![0x01, 0x02])),
+        None
+    );
+}
+
 // Scouting
 #[test]
 fn codec_hello() {
@@ -225,6 +303,11 @@ fn codec_keep_alive() {
     run!(KeepAlive, KeepAlive::rand());
 }
 
+#[test]
+fn codec_credit() {
+    run!(Credit, Credit::rand());
+}
+
 #[test]
 fn codec_frame_header() {
     run!(FrameHeader, FrameHeader::rand());
@@ -316,6 +399,11 @@ fn codec_declaration_forget_queryable() {
     run!(ForgetQueryable, ForgetQueryable::rand());
 }
 
+#[test]
+fn codec_declaration_interest() {
+    run!(Interest, Interest::rand());
+}
+
 #[test]
 fn codec_declaration() {
     run!(Declaration, Declaration::rand());
@@ -0,0 +1,374 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// Conformance of the wire format against golden byte vectors.
+//
+// The golden vectors below pin the serialization of each message kind, so that any change of
+// the wire format is deliberate.
+use std::convert::TryFrom;
+use zenoh_buffers::{
+    reader::{DidntRead, HasReader, Reader},
+    writer::HasWriter,
+    ZBuf, ZSlice,
+};
+use zenoh_codec::*;
+use zenoh_protocol::{
+    common::{imsg, Attachment, ZExtension, ZExtensionCodec},
+    core::*,
+    defaults,
+    scouting::*,
+    transport::*,
+    zenoh::*,
+};
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn from_hex(s: &str) -> Vec<u8> {
+    s.split_whitespace()
+        .map(|b| u8::from_str_radix(b, 16).unwrap_or_else(|_| panic!("Invalid hex byte: {}", b)))
+        .collect()
+}
+
+macro_rules! serialize {
+    ($x:expr) => {{
+        let mut buffer = vec![];
+        let mut writer = buffer.writer();
+        Zenoh060::default().write(&mut writer, $x).unwrap();
+        buffer
+    }};
+}
+
+macro_rules! deserialize {
+    ($type:ty, $bytes:expr) => {{
+        let mut reader = $bytes.reader();
+        let res: Result<$type, DidntRead> = Zenoh060::default().read(&mut reader);
+        res.and_then(|x| {
+            if reader.can_read() {
+                Err(DidntRead)
+            } else {
+                Ok(x)
+            }
+        })
+    }};
+}
+
+fn zid(bytes: &[u8]) -> ZenohId {
+    ZenohId::try_from(bytes).unwrap()
+}
+
+fn reliable() -> Channel {
+    Channel {
+        priority: Priority::default(),
+        reliability: Reliability::Reliable,
+    }
+}
+
+fn transport_vectors() -> Vec<(&'static str, TransportMessage, &'static str)> {
+    vec![
+        (
+            "keep_alive",
+            TransportMessage::make_keep_alive(None, None),
+            "08",
+        ),
+        (
+            "keep_alive_zid",
+            TransportMessage::make_keep_alive(Some(zid(&[0x01, 0x02])), None),
+            "28 02 01 02",
+        ),
+        (
+            "init_syn_domain",
+            TransportMessage::make_init_syn(
+                0x06,
+                WhatAmI::Router,
+                zid(&[0x01, 0x02]),
+                defaults::SEQ_NUM_RES,
+                false,
+                defaults::BATCH_SIZE,
+                tmsg::features::LEGACY,
+                42,
+                None,
+            ),
+            "5f 0b 01 11 08 2a 00 00 00 00 00 00 00 03 06 01 02 01 02",
+        ),
+        (
+            "init_syn_batch_size",
+            TransportMessage::make_init_syn(
+                0x06,
+                WhatAmI::Router,
+                zid(&[0x01, 0x02]),
+                defaults::SEQ_NUM_RES,
+                false,
+                512,
+                tmsg::features::LEGACY,
+                0,
+                None,
+            ),
+            "5f 05 01 10 02 00 02 03 06 01 02 01 02",
+        ),
+        (
+            // The InitAck answering an InitSyn that did not advertise its batch size
+            "init_ack_legacy",
+            TransportMessage::make_init_ack(
+                WhatAmI::Router,
+                zid(&[0x01, 0x02]),
+                None,
+                false,
+                None,
+                tmsg::features::LEGACY,
+                0,
+                ZSlice::from(vec![0xaa, 0xbb]),
+                None,
+            ),
+            "23 01 02 01 02 02 aa bb",
+        ),
+        (
+            "init_ack_batch_size",
+            TransportMessage::make_init_ack(
+                WhatAmI::Router,
+                zid(&[0x01, 0x02]),
+                None,
+                false,
+                Some(512),
+                tmsg::features::LEGACY,
+                0,
+                ZSlice::from(vec![0xaa, 0xbb]),
+                None,
+            ),
+            "5f 05 01 10 02 00 02 23 01 02 01 02 02 aa bb",
+        ),
+        (
+            "credit",
+            TransportMessage::make_credit(Priority::RealTime, 200, None),
+            "31 c8 01",
+        ),
+        (
+            "close_link",
+            TransportMessage::make_close(Some(zid(&[0x01, 0x02])), 0x02, true, None),
+            "65 02 01 02 02",
+        ),
+        (
+            "frame_data",
+            TransportMessage::make_frame(
+                reliable(),
+                1,
+                FramePayload::Messages {
+                    messages: vec![ZenohMessage::make_data(
+                        "a/b".into(),
+                        ZBuf::from(b"hi".to_vec()),
+                        reliable(),
+                        CongestionControl::Block,
+                        None,
+                        None,
+                        None,
+                        None,
+                    )],
+                },
+                None,
+            ),
+            "2a 01 8c 00 03 61 2f 62 02 68 69",
+        ),
+        (
+            "frame_data_priority",
+            TransportMessage::make_frame(
+                Channel {
+                    priority: Priority::RealTime,
+                    reliability: Reliability::Reliable,
+                },
+                0,
+                FramePayload::Messages {
+                    messages: vec![ZenohMessage::make_data(
+                        "x".into(),
+                        ZBuf::default(),
+                        Channel {
+                            priority: Priority::RealTime,
+                            reliability: Reliability::Reliable,
+                        },
+                        CongestionControl::Block,
+                        None,
+                        None,
+                        None,
+                        None,
+                    )],
+                },
+                None,
+            ),
+            "3c 2a 00 3c 8c 00 01 78 00",
+        ),
+        (
+            "frame_unit_best_effort",
+            TransportMessage::make_frame(
+                Channel::default(),
+                128,
+                FramePayload::Messages {
+                    messages: vec![ZenohMessage::make_unit(
+                        Channel::default(),
+                        CongestionControl::Drop,
+                        None,
+                        None,
+                    )],
+                },
+                None,
+            ),
+            "0a 80 01 2f",
+        ),
+    ]
+}
+
+fn scouting_vectors() -> Vec<(&'static str, ScoutingMessage, &'static str)> {
+    vec![
+        (
+            "scout_zid_request",
+            ScoutingMessage::make_scout(None, true, None),
+            "21",
+        ),
+        (
+            "scout_domain",
+            {
+                let mut scout = ScoutingMessage::make_scout(None, true, None);
+                scout.set_domain(42);
+                scout
+            },
+            "5f 0b 01 01 08 2a 00 00 00 00 00 00 00 21",
+        ),
+    ]
+}
+
+#[test]
+fn conformance_golden_vectors() {
+    let mut failures = vec![];
+    for (name, msg, hex) in transport_vectors() {
+        let expected = from_hex(hex);
+        let bytes = serialize!(&msg);
+        if bytes != expected {
+            failures.push(format!(
+                "{}: expected {}, got {}",
+                name,
+                hex,
+                to_hex(&bytes)
+            ));
+        }
+        assert_eq!(
+            deserialize!(TransportMessage, expected.as_slice()).unwrap(),
+            msg
+        );
+    }
+    for (name, msg, hex) in scouting_vectors() {
+        let expected = from_hex(hex);
+        let bytes = serialize!(&msg);
+        if bytes != expected {
+            failures.push(format!(
+                "{}: expected {}, got {}",
+                name,
+                hex,
+                to_hex(&bytes)
+            ));
+        }
+        assert_eq!(
+            deserialize!(ScoutingMessage, expected.as_slice()).unwrap(),
+            msg
+        );
+    }
+    assert!(
+        failures.is_empty(),
+        "The wire format diverged:\n{}",
+        failures.join("\n")
+    );
+}
+
+// Decodes an InitSyn like the peers not supporting the extensions of the attachments (i.e. zenoh 0.7):
+// the attachment is an opaque buffer of establishment properties, and the message ends after the
+// sequence number resolution.
+fn legacy_init_syn(bytes: &[u8]) -> Result<(ZenohId, Vec<Property>), DidntRead> {
+    let codec = Zenoh060::default();
+    let mut reader = bytes.reader();
+    let mut header: u8 = codec.read(&mut reader)?;
+    let mut properties = vec![];
+    if imsg::mid(header) == imsg::id::ATTACHMENT {
+        let buffer: ZBuf = codec.read(&mut reader)?;
+        properties = codec.read(&mut buffer.reader())?;
+        header = codec.read(&mut reader)?;
+    }
+    if imsg::mid(header) != tmsg::id::INIT || imsg::has_flag(header, tmsg::flag::A) {
+        return Err(DidntRead);
+    }
+    if imsg::has_flag(header, tmsg::flag::O) {
+        let _options: ZInt = codec.read(&mut reader)?;
+    }
+    let _version: u8 = codec.read(&mut reader)?;
+    let _whatami: ZInt = codec.read(&mut reader)?;
+    let zid: ZenohId = codec.read(&mut reader)?;
+    if imsg::has_flag(header, tmsg::flag::S) {
+        let _sn_resolution: ZInt = codec.read(&mut reader)?;
+    }
+    if reader.can_read() {
+        return Err(DidntRead);
+    }
+    Ok((zid, properties))
+}
+
+#[test]
+fn conformance_init_syn_legacy_decoder() {
+    let init_syn = |batch_size, domain, attachment| {
+        TransportMessage::make_init_syn(
+            0x06,
+            WhatAmI::Router,
+            zid(&[0x01, 0x02]),
+            defaults::SEQ_NUM_RES,
+            true,
+            batch_size,
+            tmsg::features::FRAGMENTATION | tmsg::features::ATTACHMENTS,
+            domain,
+            attachment,
+        )
+    };
+
+    // The batch size is skipped by the peers that do not negotiate it
+    let bytes = serialize!(&init_syn(512, 0, None));
+    let (decoded, properties) = legacy_init_syn(&bytes).unwrap();
+    assert_eq!(decoded, zid(&[0x01, 0x02]));
+    assert!(properties.iter().all(|p| p.key == InitBatchSize::ID));
+
+    // The establishment properties, carried as extensions, are still read by those peers
+    let mut attachment = Attachment::new(ZBuf::default());
+    attachment.insert_extension(ZExtension::new(0x02, ZBuf::from(vec![0xaa, 0xbb])));
+    let bytes = serialize!(&init_syn(512, 0, Some(attachment)));
+    let (_, properties) = legacy_init_syn(&bytes).unwrap();
+    assert!(properties.contains(&Property {
+        key: 0x02,
+        value: vec![0xaa, 0xbb]
+    }));
+
+    // The new peers read all of them
+    let msg = deserialize!(TransportMessage, bytes.as_slice()).unwrap();
+    assert_eq!(msg.init_batch_size(), Some(512));
+    assert_eq!(msg.init_domain(), 0);
+    let att = msg.attachment.unwrap();
+    assert_eq!(
+        att.extension(0x02).unwrap().value,
+        ZBuf::from(vec![0xaa, 0xbb])
+    );
+
+    // As well as the domain, on a link with the default batch size
+    let bytes = serialize!(&init_syn(defaults::BATCH_SIZE, 42, None));
+    assert!(legacy_init_syn(&bytes).is_ok());
+    let msg = deserialize!(TransportMessage, bytes.as_slice()).unwrap();
+    assert_eq!(msg.init_batch_size(), None);
+    assert_eq!(msg.init_domain(), 42);
+}
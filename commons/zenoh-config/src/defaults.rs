@@ -30,6 +30,14 @@ macro_rules! mode_accessor {
 #[allow(dead_code)]
 pub const mode: WhatAmI = WhatAmI::Peer;
 
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub mod connect {
+    pub const timeout_ms: u64 = 10000;
+    pub const retries: u32 = 0;
+    pub const wait_for_router: bool = false;
+}
+
 #[allow(non_upper_case_globals)]
 #[allow(dead_code)]
 pub mod scouting {
@@ -39,6 +47,7 @@ pub mod scouting {
         pub const enabled: bool = true;
         pub const address: ([u8; 4], u16) = ([224, 0, 0, 224], 7446);
         pub const interface: &str = "auto";
+        pub const ttl: u32 = 1;
         pub mod autoconnect {
             pub const router: &crate::WhatAmIMatcher = // ""
                 &crate::WhatAmIMatcher(unsafe { std::num::NonZeroU8::new_unchecked(128) });
@@ -80,6 +89,7 @@ pub mod timestamping {
         mode_accessor!(bool);
     }
     pub const drop_future_timestamp: bool = false;
+    pub const max_delta_ms: u64 = 500;
 }
 
 #[allow(non_upper_case_globals)]
@@ -91,10 +101,27 @@ pub const queries_default_timeout: u64 = 10000;
 pub mod routing {
     pub mod router {
         pub const peers_failover_brokering: bool = true;
+        pub const client_interests: bool = false;
     }
     pub mod peer {
         pub const mode: &str = "peer_to_peer";
     }
+    pub mod mirror {
+        pub const max_rate: usize = 100;
+    }
+    pub mod talkers {
+        pub const depth: usize = 0;
+        pub const max_prefixes: usize = 1_000;
+    }
+}
+
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+pub mod audit {
+    pub const enabled: bool = false;
+    pub const max_file_size: u64 = 10 * 1024 * 1024;
+    pub const max_files: usize = 5;
+    pub const publish: bool = true;
 }
 
 impl Default for TransportUnicastConf {
@@ -104,6 +131,8 @@ impl Default for TransportUnicastConf {
             accept_pending: Some(100),
             max_sessions: Some(1000),
             max_links: Some(1),
+            max_fd_usage: None,
+            credits: None,
         }
     }
 }
@@ -119,7 +148,30 @@ impl Default for TransportMulticastConf {
 
 impl Default for QoSConf {
     fn default() -> Self {
-        Self { enabled: true }
+        Self {
+            enabled: true,
+            dscp: QoSDscpConf::default(),
+        }
+    }
+}
+
+impl QoSDscpConf {
+    pub const MAX: u8 = 0x3f;
+}
+
+impl Default for QoSDscpConf {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            control: 48,          // CS6
+            real_time: 46,        // EF
+            interactive_high: 34, // AF41
+            interactive_low: 26,  // AF31
+            data_high: 18,        // AF21
+            data: 0,              // CS0
+            data_low: 10,         // AF11
+            background: 8,        // CS1
+        }
     }
 }
 
@@ -133,6 +185,7 @@ impl Default for LinkTxConf {
             keep_alive: Some(4),
             batch_size: Some(u16::MAX),
             queue: QueueConf::default(),
+            adaptive_shedding: Some(false),
             threads: Some(num),
         }
     }
@@ -172,6 +225,7 @@ impl Default for LinkRxConf {
         Self {
             buffer_size: Some(u16::MAX as usize),
             max_message_size: Some(2_usize.pow(30)),
+            watchdog: None,
         }
     }
 }
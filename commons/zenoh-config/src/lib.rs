@@ -14,6 +14,7 @@
 
 //! Configuration to pass to `zenoh::open()` and `zenoh::scout()` functions and associated constants.
 pub mod defaults;
+pub mod schema;
 use serde::{
     de::{self, MapAccess, Visitor},
     Deserialize, Serialize,
@@ -34,7 +35,7 @@ pub use validated_struct::{GetError, ValidatedMap};
 pub use zenoh_cfg_properties::config::*;
 use zenoh_core::zlock;
 use zenoh_protocol::core::{
-    key_expr::OwnedKeyExpr,
+    key_expr::{keyexpr, OwnedKeyExpr},
     whatami::{WhatAmIMatcher, WhatAmIMatcherVisitor},
 };
 pub use zenoh_protocol::core::{whatami, EndPoint, Locator, Priority, WhatAmI, ZenohId};
@@ -110,12 +111,27 @@ validated_struct::validator! {
     Config {
         /// The Zenoh ID of the instance. This ID MUST be unique throughout your Zenoh infrastructure and cannot exceed 16 bytes of length. If left unset, a random UUIDv4 will be generated.
         id: ZenohId,
+        /// The path of a host key file from which the Zenoh ID is derived, so that it remains the same across restarts.
+        /// The file is generated on first run if it doesn't exist. When set, `id` is ignored.
+        id_key_file: Option<String>,
         /// The node's mode ("router" (default value in `zenohd`), "peer" or "client").
         mode: Option<whatami::WhatAmI>,
+        /// The domain of the instance (0 by default). The instances of different domains ignore each other's
+        /// scouting messages and refuse to establish sessions with each other, so that independent zenoh systems can share a network.
+        domain: Option<u64>,
+        /// A key expression prefixing all the key expressions declared or written by the sessions of this instance,
+        /// and stripped from the ones they receive. Messages outside of this namespace are not delivered to the sessions.
+        namespace: Option<OwnedKeyExpr>,
         /// Which zenoh nodes to connect to.
         pub connect: #[derive(Default)]
         ConnectConfig {
             pub endpoints: Vec<EndPoint>,
+            /// In client mode, the timeout of each attempt to connect to an endpoint. In milliseconds.
+            timeout_ms: Option<u64>,
+            /// In client mode, how many more times the endpoints are tried (or a router is scouted for) before failing to open.
+            retries: Option<u32>,
+            /// In client mode, whether to keep on trying until a router is reachable, regardless of `retries`.
+            wait_for_router: Option<bool>,
         },
         /// Which endpoints to listen on. `zenohd` will add `tcp/[::]:7447` to these locators if left empty.
         pub listen: #[derive(Default)]
@@ -133,15 +149,31 @@ validated_struct::validator! {
             ScoutingMulticastConf {
                 /// Whether multicast scouting is enabled or not. If left empty, `zenohd` will set it according to the presence of the `--no-multicast-scouting` argument.
                 enabled: Option<bool>,
-                /// The socket which should be used for multicast scouting. `zenohd` will use `224.0.0.224:7446` by default if none is provided.
+                /// The socket which should be used for multicast scouting, i.e. the multicast group and the port scout messages are listened on.
+                /// `zenohd` will use `224.0.0.224:7446` by default if none is provided.
                 address: Option<SocketAddr>,
                 /// The network interface which should be used for multicast scouting. `zenohd` will automatically select an interface if none is provided.
                 interface: Option<String>,
+                /// The time-to-live of the scout messages sent on UDP multicast (default: 1), i.e. the number of routed
+                /// network segments they may cross. The default confines the scouting to the local network segment.
+                ttl: Option<u32>,
                 /// Which type of Zenoh instances to automatically establish sessions with upon discovery through UDP multicast.
                 #[serde(deserialize_with = "treat_error_as_none")]
                 autoconnect: Option<ModeDependentValue<WhatAmIMatcher>>,
                 /// Whether or not to listen for scout messages on UDP multicast and reply to them.
                 listen: Option<ModeDependentValue<bool>>,
+                /// A key pre-shared by the instances to sign their scout and hello messages.
+                /// If set, the scouting messages not signed with this key are ignored.
+                key: Option<String>,
+            },
+            /// The unicast scouting configuration, for the networks where multicast is not available.
+            pub unicast: #[derive(Default)]
+            ScoutingUnicastConf {
+                /// The UDP address on which to listen for unicast scout messages and reply to them (e.g. `0.0.0.0:7446`). None by default.
+                address: Option<SocketAddr>,
+                /// In client mode, the UDP addresses probed with unicast scout messages when no endpoint to connect to is configured.
+                /// The client connects to the first router replying.
+                probes: Vec<SocketAddr>,
             },
             /// The gossip scouting configuration.
             pub gossip: #[derive(Default)]
@@ -169,6 +201,11 @@ validated_struct::validator! {
             /// If set to false (default), messages with timestamps in the future are retimestamped.
             /// Timestamps are ignored if timestamping is disabled.
             drop_future_timestamp: Option<bool>,
+            /// What to do with the data messages with timestamps in the future, overriding `drop_future_timestamp`.
+            future_timestamp_policy: Option<FutureTimestampPolicy>,
+            /// The maximum delta in milliseconds between a received timestamp and the local clock,
+            /// beyond which the timestamp is considered in the future (default: 500).
+            max_delta_ms: Option<u64>,
         },
 
         /// The default timeout to apply to queries in milliseconds.
@@ -185,6 +222,10 @@ validated_struct::validator! {
                 /// connected to each other.
                 /// The failover brokering only works if gossip discovery is enabled.
                 peers_failover_brokering: Option<bool>,
+                /// When set to true, a router waits for the interests of a client before sending it declarations,
+                /// and only sends it the declarations intersecting them (default: false).
+                /// A client that declares no interest gets all the declarations once it sends its first message.
+                client_interests: Option<bool>,
             },
             /// The routing strategy to use in peers and it's configuration.
             pub peer: #[derive(Default)]
@@ -192,6 +233,42 @@ validated_struct::validator! {
                 /// The routing strategy to use in peers. ("peer_to_peer" or "linkstate").
                 mode: Option<String>,
             },
+            /// The routing configuration of clients.
+            pub client: #[derive(Default)]
+            ClientRoutingConf {
+                /// The key expressions a client is interested in, declared to the routers it connects to
+                /// so that they only send it the matching declarations. All key expressions if empty.
+                interests: Vec<OwnedKeyExpr>,
+            },
+            /// Rewrite rules of the key expressions exchanged with the remote nodes, applied in order.
+            /// The first rule whose prefix matches a key expression rewrites it.
+            pub remapping: Vec<KeyRemappingRule>,
+            /// The maximum number of key expressions mapped to resource ids for each remote node (unbounded if unset).
+            /// Above it, the least recently declared mappings are forgotten.
+            max_resource_mappings: Option<usize>,
+            /// The number of samples routed to a remote node on a key expression above which this key expression
+            /// is mapped to a resource id for that node, the following samples carrying the id rather than the
+            /// key expression (disabled if unset). These mappings count in `max_resource_mappings`.
+            hot_key_threshold: Option<usize>,
+            /// The samples routed by this node on the key expressions matching `keys` are duplicated onto
+            /// `@debug/<original-key>`, so that a debugging client can tap into live flows.
+            pub mirror: #[derive(Default)]
+            MirrorConf {
+                /// The key expressions of the mirrored samples (mirroring is disabled if empty).
+                keys: Vec<OwnedKeyExpr>,
+                /// The maximum number of samples mirrored per second (default: 100).
+                max_rate: Option<usize>,
+            },
+            /// The samples routed by this node are accounted per key prefix, and the prefixes on which
+            /// the most bytes are routed are listed under `@/router/<zid>/talkers`.
+            pub talkers: #[derive(Default)]
+            TalkersConf {
+                /// The number of chunks of the key prefixes (accounting is disabled if 0, the default).
+                depth: Option<usize>,
+                /// The maximum number of key prefixes accounted, the samples routed on other prefixes
+                /// being accounted on `**` (default: 1000).
+                max_prefixes: Option<usize>,
+            },
         },
 
         /// The declarations aggregation strategy.
@@ -213,6 +290,14 @@ validated_struct::validator! {
                 max_sessions: Option<usize>,
                 /// Maximum number of unicast incoming links per transport session (default: 1)
                 max_links: Option<usize>,
+                /// Percentage of the file descriptors limit above which new sessions are refused and
+                /// droppable traffic is shed, until the usage drops again (default: disabled)
+                max_fd_usage: Option<usize>,
+                /// Number of zenoh messages per priority the remote nodes may send before waiting for this node
+                /// to grant them more, as it processes the received ones. Meant for constrained clients that
+                /// could otherwise be overwhelmed by their router. Only the reliable messages are limited, and only
+                /// once a reliable link is established (default: unlimited).
+                credits: Option<ZInt>,
             },
             pub multicast: TransportMulticastConf {
                 /// Link join interval duration in milliseconds (default: 2500)
@@ -223,7 +308,22 @@ validated_struct::validator! {
             pub qos: QoSConf {
                 /// Whether QoS is enabled or not.
                 /// If set to `false`, the QoS will be disabled. (default `true`).
-                enabled: bool
+                enabled: bool,
+                /// The DiffServ code point (DSCP) used to mark the IP packets carrying each priority,
+                /// so that the network honors the priorities as well. Only applies to the links carried
+                /// over IP (e.g. TCP, UDP, TLS). If qos is false, then only the DATA mark will be used.
+                pub dscp: QoSDscpConf {
+                    /// Whether the IP packets are marked or not (default `false`).
+                    enabled: bool,
+                    control: u8,
+                    real_time: u8,
+                    interactive_high: u8,
+                    interactive_low: u8,
+                    data_high: u8,
+                    data: u8,
+                    data_low: u8,
+                    background: u8,
+                } where (dscp_validator),
             },
             pub link: #[derive(Default)]
             TransportLinkConf {
@@ -232,6 +332,7 @@ validated_struct::validator! {
                 pub protocols: Option<Vec<String>>,
                 pub tx: LinkTxConf {
                     /// The largest value allowed for Zenoh message sequence numbers (wrappring to 0 when reached). When establishing a session with another Zenoh instance, the lowest value of the two instances will be used.
+                    /// Smaller values (down to 2) reduce the size of the sequence numbers on the wire, e.g. on constrained links.
                     /// Defaults to 2^28.
                     sequence_number_resolution: Option<ZInt>,
                     /// Link lease duration in milliseconds (default: 10000)
@@ -239,6 +340,9 @@ validated_struct::validator! {
                     /// Number fo keep-alive messages in a link lease duration (default: 4)
                     keep_alive: Option<usize>,
                     /// Zenoh's MTU equivalent (default: 2^16-1)
+                    /// The batch size of each link is negotiated with the remote side as the smallest of both limits
+                    /// and of the link MTU. It can be overridden per endpoint with the `batch_size` configuration,
+                    /// e.g. `serial//dev/ttyUSB0#batch_size=512`.
                     batch_size: Option<u16>,
                     pub queue: QueueConf {
                         /// The size of each priority queue indicates the number of batches a given queue can contain.
@@ -260,6 +364,9 @@ validated_struct::validator! {
                         /// Higher values lead to a more aggressive batching but it will introduce additional latency.
                         backoff: Option<ZInt>
                     },
+                    /// Whether droppable (best-effort) messages are shed when the estimated bandwidth of a link is saturated,
+                    /// proportionally to the saturation, before the queues fill up and the reliable messages are delayed (default: false).
+                    adaptive_shedding: Option<bool>,
                     // Number of threads used for TX
                     threads: Option<usize>,
                 },
@@ -273,6 +380,10 @@ validated_struct::validator! {
                     /// Maximum size of the defragmentation buffer at receiver end (default: 1GiB).
                     /// Fragmented messages that are larger than the configured size will be dropped.
                     max_message_size: Option<usize>,
+                    /// Period in milliseconds after which a link on which nothing was received,
+                    /// not even keep-alive messages, is considered stalled and closed (default: none).
+                    /// The link lease applies when not set or longer.
+                    watchdog: Option<ZInt>,
                 },
                 pub tls: #[derive(Default)]
                 TLSConf {
@@ -282,6 +393,18 @@ validated_struct::validator! {
                     client_auth: Option<bool>,
                     client_private_key: Option<String>,
                     client_certificate: Option<String>,
+                    /// The SPIFFE trust domain the peer certificates must belong to.
+                    spiffe_trust_domain: Option<String>,
+                    /// The period in milliseconds at which the listeners reload their certificate.
+                    svid_refresh_interval: Option<u64>,
+                    /// The path to the DER-encoded OCSP response stapled by the listeners.
+                    server_ocsp_response: Option<String>,
+                    /// The path to the DER-encoded CRLs the server certificates are checked against.
+                    crl: Option<String>,
+                    /// Whether the servers must staple a valid OCSP response.
+                    ocsp_required: Option<bool>,
+                    /// Whether to log the TLS secrets in the file pointed by SSLKEYLOGFILE.
+                    keylog: Option<bool>,
                 },
             },
             pub shared_memory: SharedMemoryConf {
@@ -312,13 +435,17 @@ validated_struct::validator! {
             },
         },
         /// Configuration of the admin space.
-        pub adminspace: #[derive(Default)]
+        pub adminspace:
         /// <div class="stab unstable">
         ///   <span class="emoji">🔬</span>
         ///   This API has been marked as unstable: it works as advertised, but we may change it in a future release.
         ///   To use it, you must enable zenoh's <code>unstable</code> feature flag.
         /// </div>
         AdminSpaceConf {
+            /// Whether the admin space is exposed at all (true by default).
+            /// Public-facing routers may disable it to hide `@/router/**` entirely.
+            #[serde(default = "set_true")]
+            pub enabled: bool,
             /// Permissions on the admin space
             pub permissions:
             PermissionsConf {
@@ -329,7 +456,24 @@ validated_struct::validator! {
                 #[serde(default = "set_false")]
                 pub write: bool,
             },
-
+            /// Per key expression access rules, evaluated in order before falling back to `permissions`.
+            /// The first rule applying to the accessed key and to the accessing node, and defining
+            /// the requested permission, decides the access.
+            pub acl: Vec<AdminSpaceAclRule>,
+        },
+        /// Configuration of the audit log, recording session, declaration and administrative events.
+        pub audit: #[derive(Default)]
+        AuditConf {
+            /// Whether audit events are recorded (false by default).
+            enabled: Option<bool>,
+            /// The file audit events are appended to, as JSON lines. No file is written if left unset.
+            path: Option<String>,
+            /// The size in bytes above which the audit file is rotated.
+            max_file_size: Option<u64>,
+            /// The number of rotated audit files to keep.
+            max_files: Option<usize>,
+            /// Whether audit events are published on `@/router/<zid>/audit` by the admin space.
+            publish: Option<bool>,
         },
         /// A list of directories where plugins may be searched for if no `__path__` was specified for them.
         /// The executable's current directory will be added to the search paths.
@@ -342,6 +486,113 @@ validated_struct::validator! {
     }
 }
 
+impl Default for AdminSpaceConf {
+    fn default() -> Self {
+        AdminSpaceConf {
+            enabled: true,
+            permissions: PermissionsConf::default(),
+            acl: Vec::new(),
+        }
+    }
+}
+
+/// An access rule of the admin space.
+///
+/// A rule applies to the keys of the admin space included in one of its `key_exprs`, when accessed
+/// by one of its `zids`. A permission left unset in a rule is not decided by this rule, and the
+/// next rules are evaluated.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdminSpaceAclRule {
+    /// The key expressions this rule applies to (e.g. `"@/router/*/config/**"`).
+    pub key_exprs: Vec<OwnedKeyExpr>,
+    /// The nodes this rule applies to, i.e. the nodes the accesses are received from.
+    /// The rule applies to all the nodes if empty.
+    #[serde(default)]
+    pub zids: Vec<ZenohId>,
+    /// Whether matching keys may be queried.
+    #[serde(default)]
+    pub read: Option<bool>,
+    /// Whether matching keys may be written to.
+    #[serde(default)]
+    pub write: Option<bool>,
+}
+
+/// What to do with the received data messages whose timestamp is too far in the future.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FutureTimestampPolicy {
+    /// Drop the message.
+    Drop,
+    /// Replace the timestamp by a timestamp of the local clock.
+    Clamp,
+    /// Keep the timestamp, logging a warning.
+    Accept,
+}
+
+/// A rewrite rule of the key expressions exchanged with some remote nodes.
+///
+/// The key expressions received from those nodes that start with the `from` prefix get this prefix replaced by `to`,
+/// and the key expressions sent to them that start with `to` get it replaced by `from`.
+/// This applies to data, declarations, queries and replies.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyRemappingRule {
+    /// The remote nodes this rule applies to. The rule applies to all the remote nodes if empty.
+    #[serde(default)]
+    pub zids: Vec<ZenohId>,
+    /// The prefix of the key expressions on the remote side (e.g. `"factoryA"`).
+    pub from: OwnedKeyExpr,
+    /// The prefix of the key expressions on the local side (e.g. `"site1/factoryA"`).
+    pub to: OwnedKeyExpr,
+}
+
+impl AdminSpaceConf {
+    // The rules applying to the accesses from `zid`, or from an unknown node if `None`
+    fn acl_for<'a>(
+        &'a self,
+        zid: Option<&'a ZenohId>,
+    ) -> impl Iterator<Item = &'a AdminSpaceAclRule> + 'a {
+        self.acl.iter().filter(move |rule| {
+            rule.zids.is_empty() || zid.map(|zid| rule.zids.contains(zid)).unwrap_or(false)
+        })
+    }
+
+    /// Returns whether the key `key_expr` may be read by the node `zid`, according to the
+    /// [`AdminSpaceAclRule`]s and the default permissions.
+    pub fn can_read(&self, key_expr: &keyexpr, zid: Option<&ZenohId>) -> bool {
+        self.acl_for(zid)
+            .filter(|rule| rule.key_exprs.iter().any(|k| k.includes(key_expr)))
+            .find_map(|rule| rule.read)
+            .unwrap_or(self.permissions.read)
+    }
+
+    /// Returns whether some of the keys matching the query `key_expr` may be read by the node `zid`,
+    /// according to the [`AdminSpaceAclRule`]s and the default permissions.
+    ///
+    /// The keys replied to such a query must still be checked one by one with [`can_read`](Self::can_read).
+    pub fn may_read(&self, key_expr: &keyexpr, zid: Option<&ZenohId>) -> bool {
+        for rule in self.acl_for(zid) {
+            match rule.read {
+                // this rule decides the access to all the keys matching the query
+                Some(read) if rule.key_exprs.iter().any(|k| k.includes(key_expr)) => return read,
+                Some(true) if rule.key_exprs.iter().any(|k| k.intersects(key_expr)) => return true,
+                _ => (),
+            }
+        }
+        self.permissions.read
+    }
+
+    /// Returns whether the key `key_expr` may be written to by the node `zid`, according to the
+    /// [`AdminSpaceAclRule`]s and the default permissions.
+    pub fn can_write(&self, key_expr: &keyexpr, zid: Option<&ZenohId>) -> bool {
+        self.acl_for(zid)
+            .filter(|rule| rule.key_exprs.iter().any(|k| k.includes(key_expr)))
+            .find_map(|rule| rule.write)
+            .unwrap_or(self.permissions.write)
+    }
+}
+
 impl Default for PermissionsConf {
     fn default() -> Self {
         PermissionsConf {
@@ -448,9 +699,81 @@ fn config_deser() {
         .unwrap(),
     )
     .unwrap_err());
+    let config = Config::from_deserializer(
+        &mut json5::Deserializer::from_str(
+            r#"{adminspace: { permissions: { read: true, write: true }, acl: [{ key_exprs: ["@/router/*/config/**"], write: false }]}}"#,
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(*config.adminspace().enabled());
+    assert!(config
+        .adminspace()
+        .can_write(keyexpr::new("@/router/abc/linkstate/peers").unwrap(), None));
+    assert!(!config
+        .adminspace()
+        .can_write(keyexpr::new("@/router/abc/config/mode").unwrap(), None));
+    assert!(config
+        .adminspace()
+        .can_read(keyexpr::new("@/router/abc/config/mode").unwrap(), None));
     dbg!(Config::from_file("../../DEFAULT_CONFIG.json5").unwrap());
 }
 
+#[test]
+fn adminspace_acl() {
+    let admin = "a1".parse::<ZenohId>().unwrap();
+    let other = "b2".parse::<ZenohId>().unwrap();
+    let config = Config::from_deserializer(
+        &mut json5::Deserializer::from_str(&format!(
+            r#"{{adminspace: {{ permissions: {{ read: true, write: false }}, acl: [
+                {{ key_exprs: ["@/router/*/config/**"], zids: ["{admin}"], read: true, write: true }},
+                {{ key_exprs: ["@/router/*/config/**"], read: false }},
+                {{ key_exprs: ["@/router/*/linkstate/*"], write: true }},
+            ]}}}}"#
+        ))
+        .unwrap(),
+    )
+    .unwrap();
+    let adminspace = config.adminspace();
+    let ke = |s: &'static str| keyexpr::new(s).unwrap();
+
+    // the rules apply to the keys they include, for both reads and writes
+    assert!(!adminspace.can_read(ke("@/router/abc/config/mode"), Some(&other)));
+    assert!(adminspace.can_read(ke("@/router/abc/linkstate/peers"), Some(&other)));
+    assert!(adminspace.can_write(ke("@/router/abc/linkstate/peers"), Some(&other)));
+    assert!(!adminspace.can_write(ke("@/router/abc/config/mode"), Some(&other)));
+    assert!(adminspace.can_read(ke("@/router/abc/configuration"), Some(&other)));
+
+    // the rules restricted to some nodes only apply to those nodes
+    assert!(adminspace.can_read(ke("@/router/abc/config/mode"), Some(&admin)));
+    assert!(adminspace.can_write(ke("@/router/abc/config/mode"), Some(&admin)));
+    assert!(!adminspace.can_read(ke("@/router/abc/config/mode"), None));
+    assert!(!adminspace.can_write(ke("@/router/abc/config/mode"), None));
+
+    // the queries are checked against the queried key expression
+    assert!(!adminspace.may_read(ke("@/router/abc/config/**"), Some(&other)));
+    assert!(adminspace.may_read(ke("@/router/abc/config/**"), Some(&admin)));
+    assert!(adminspace.may_read(ke("@/router/abc/**"), Some(&other)));
+    assert!(adminspace.may_read(ke("@/router/abc/linkstate/*"), Some(&other)));
+
+    let config = Config::from_deserializer(
+        &mut json5::Deserializer::from_str(
+            r#"{adminspace: { permissions: { read: false }, acl: [
+                { key_exprs: ["@/router/*/config/**"], read: false },
+                { key_exprs: ["@/router/*/status/**"], read: true },
+            ]}}"#,
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let adminspace = config.adminspace();
+    assert!(!adminspace.may_read(ke("@/router/abc/config/**"), None));
+    assert!(adminspace.may_read(ke("@/router/abc/**"), None));
+    assert!(adminspace.can_read(ke("@/router/abc/status/plugins/rest"), None));
+    assert!(!adminspace.can_read(ke("@/router/abc/linkstate/peers"), None));
+    assert!(!adminspace.may_read(ke("@/router/abc/linkstate/*"), None));
+}
+
 impl Config {
     pub fn add_plugin_validator(&mut self, name: impl Into<String>, validator: ValidationFunction) {
         self.plugins.validators.insert(name.into(), validator);
@@ -563,6 +886,44 @@ fn config_from_json() {
     println!("{}", serde_json::to_string_pretty(&config).unwrap());
 }
 
+#[test]
+fn plugin_restart_policy() {
+    use std::time::Duration;
+    let config = Config::from_deserializer(
+        &mut json5::Deserializer::from_str(
+            r#"{
+        plugins: {
+          a: {},
+          b: { __restart__: { max_restarts: 3, backoff: 100, max_backoff: 300 } }
+        }
+      }"#,
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let restart = |name: &str| {
+        config
+            .plugins()
+            .load_requests()
+            .find(|request| request.name == name)
+            .unwrap()
+            .restart
+    };
+
+    // plugins aren't restarted by default
+    let policy = restart("a");
+    assert_eq!(policy, PluginRestartPolicy::default());
+    assert_eq!(policy.restart_delay(0), None);
+
+    // the backoff doubles at each restart up to its maximum, until the maximum number of restarts
+    let policy = restart("b");
+    assert_eq!(policy.restart_delay(0), Some(Duration::from_millis(100)));
+    assert_eq!(policy.restart_delay(1), Some(Duration::from_millis(200)));
+    assert_eq!(policy.restart_delay(2), Some(Duration::from_millis(300)));
+    assert_eq!(policy.restart_delay(3), None);
+    assert_eq!(policy.delay(usize::MAX), Duration::from_millis(300));
+}
+
 pub type Notification = Arc<str>;
 
 struct NotifierInner<T> {
@@ -738,6 +1099,32 @@ impl<'a, T> AsRef<dyn Any> for GetGuard<'a, T> {
     }
 }
 
+fn dscp_validator(d: &QoSDscpConf) -> bool {
+    fn check(dscp: &u8) -> bool {
+        *dscp <= QoSDscpConf::MAX
+    }
+
+    let QoSDscpConf {
+        enabled: _,
+        control,
+        real_time,
+        interactive_high,
+        interactive_low,
+        data_high,
+        data,
+        data_low,
+        background,
+    } = d;
+    check(control)
+        && check(real_time)
+        && check(interactive_high)
+        && check(interactive_low)
+        && check(data_high)
+        && check(data)
+        && check(data_low)
+        && check(background)
+}
+
 fn queue_size_validator(q: &QueueSizeConf) -> bool {
     fn check(size: &usize) -> bool {
         (QueueSizeConf::MIN..=QueueSizeConf::MAX).contains(size)
@@ -809,6 +1196,40 @@ pub struct PluginLoad {
     pub name: String,
     pub paths: Option<Vec<String>>,
     pub required: bool,
+    pub restart: PluginRestartPolicy,
+}
+/// How a plugin is restarted after it failed (i.e. panicked), as configured by its `__restart__` property:
+/// `{ max_restarts: 3, backoff: 1000, max_backoff: 60000 }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PluginRestartPolicy {
+    /// The maximum number of restarts of the plugin, never restarted if 0 (default: 0).
+    pub max_restarts: usize,
+    /// The delay before the first restart, doubled at each restart (default: 1s).
+    pub backoff: std::time::Duration,
+    /// The maximum delay before a restart (default: 60s).
+    pub max_backoff: std::time::Duration,
+}
+impl Default for PluginRestartPolicy {
+    fn default() -> Self {
+        PluginRestartPolicy {
+            max_restarts: 0,
+            backoff: std::time::Duration::from_secs(1),
+            max_backoff: std::time::Duration::from_secs(60),
+        }
+    }
+}
+impl PluginRestartPolicy {
+    /// The delay before the restart of a plugin already restarted `restarts` times.
+    pub fn delay(&self, restarts: usize) -> std::time::Duration {
+        self.backoff
+            .saturating_mul(1 << restarts.min(16))
+            .min(self.max_backoff)
+    }
+    /// The delay before the restart of a plugin already restarted `restarts` times,
+    /// or `None` if it reached its maximum number of restarts.
+    pub fn restart_delay(&self, restarts: usize) -> Option<std::time::Duration> {
+        (restarts < self.max_restarts).then(|| self.delay(restarts))
+    }
 }
 impl PluginsConfig {
     pub fn sift_privates(&mut self) {
@@ -822,15 +1243,29 @@ impl PluginsConfig {
                 Some(Value::Bool(b)) => *b,
                 _ => panic!("Plugin '{}' has an invalid '__required__' configuration property (must be a boolean)", name)
             };
+            let mut restart = PluginRestartPolicy::default();
+            if let Some(policy) = value.get("__restart__") {
+                let policy = policy.as_object().unwrap_or_else(|| panic!("Plugin '{}' has an invalid '__restart__' configuration property (must be an object)", name));
+                let property = |key: &str| policy.get(key).map(|v| v.as_u64().unwrap_or_else(|| panic!("Plugin '{}' has an invalid '__restart__/{}' configuration property (must be a positive integer)", name, key)));
+                if let Some(max_restarts) = property("max_restarts") {
+                    restart.max_restarts = max_restarts as usize;
+                }
+                if let Some(backoff) = property("backoff") {
+                    restart.backoff = std::time::Duration::from_millis(backoff);
+                }
+                if let Some(max_backoff) = property("max_backoff") {
+                    restart.max_backoff = std::time::Duration::from_millis(max_backoff);
+                }
+            }
             if let Some(paths) = value.get("__path__"){
                 let paths = match paths {
                     Value::String(s) => vec![s.clone()],
                     Value::Array(a) => a.iter().map(|s| if let Value::String(s) = s {s.clone()} else {panic!("Plugin '{}' has an invalid '__path__' configuration property (must be either string or array of strings)", name)}).collect(),
                     _ => panic!("Plugin '{}' has an invalid '__path__' configuration property (must be either string or array of strings)", name)
                 };
-                PluginLoad {name: name.clone(), paths: Some(paths), required}
+                PluginLoad {name: name.clone(), paths: Some(paths), required, restart}
             } else {
-                PluginLoad {name: name.clone(), paths: None, required}
+                PluginLoad {name: name.clone(), paths: None, required, restart}
             }
         })
     }
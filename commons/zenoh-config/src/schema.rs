@@ -0,0 +1,239 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! JSON schemas of the configuration, for the plugins validating their own configuration
+//! and for the external tools linting configuration files.
+//!
+//! The schema of the [`Config`] is derived from its serde model: each section is an object
+//! whose properties are the ones of the model, typed after their default values, and unknown
+//! properties are rejected as they are by the deserialization. The configuration of the
+//! plugins is free-form, each plugin validating its own one (see [`validator()`]).
+use super::{Config, ValidationFunction};
+use serde_json::{json, Map, Value};
+use zenoh_result::{bail, ZResult};
+
+/// The JSON schema dialect of the schemas produced by this module.
+pub const DIALECT: &str = "http://json-schema.org/draft-07/schema#";
+
+/// The JSON schema of the configuration.
+pub fn config() -> Value {
+    let mut schema = match serde_json::to_value(Config::default()) {
+        Ok(value) => infer(&value, true),
+        Err(e) => panic!("The default configuration must be serializable: {e}"),
+    };
+    if let Some(properties) = schema.get_mut("properties").and_then(Value::as_object_mut) {
+        properties.insert(
+            "plugins".into(),
+            json!({ "type": "object", "additionalProperties": { "type": "object" } }),
+        );
+    }
+    if let Some(schema) = schema.as_object_mut() {
+        schema.insert("$schema".into(), DIALECT.into());
+        schema.insert("title".into(), "zenoh configuration".into());
+    }
+    schema
+}
+
+/// The JSON schema of the values shaped like `value`. The objects are `closed` to the
+/// properties they don't have, unless they are empty, and the nulls (i.e. the unset optional
+/// values) may be of any type.
+pub fn infer(value: &Value, closed: bool) -> Value {
+    match value {
+        Value::Null => json!({}),
+        Value::Bool(_) => json!({ "type": "boolean" }),
+        Value::Number(n) if n.is_f64() => json!({ "type": "number" }),
+        Value::Number(_) => json!({ "type": "integer" }),
+        Value::String(_) => json!({ "type": "string" }),
+        Value::Array(a) => match a.first() {
+            Some(item) => json!({ "type": "array", "items": infer(item, closed) }),
+            None => json!({ "type": "array" }),
+        },
+        Value::Object(o) if o.is_empty() => json!({ "type": "object" }),
+        Value::Object(o) => {
+            let properties: Map<String, Value> = o
+                .iter()
+                .map(|(k, v)| (k.clone(), infer(v, closed)))
+                .collect();
+            json!({
+                "type": "object",
+                "properties": properties,
+                "additionalProperties": !closed,
+            })
+        }
+    }
+}
+
+fn type_of(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn has_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        // An integer is a number too
+        "number" => value.is_number(),
+        ty => type_of(value) == ty,
+    }
+}
+
+/// Checks `value` against `schema`, supporting the `type`, `enum`, `minimum`, `maximum`,
+/// `properties`, `required`, `additionalProperties` and `items` keywords.
+pub fn validate(schema: &Value, value: &Value) -> ZResult<()> {
+    validate_at("", schema, value)
+}
+
+fn validate_at(path: &str, schema: &Value, value: &Value) -> ZResult<()> {
+    let schema = match schema {
+        Value::Bool(true) => return Ok(()),
+        Value::Bool(false) => bail!("Unexpected value at '{}'", path),
+        Value::Object(schema) => schema,
+        _ => bail!("Invalid schema at '{}': {}", path, schema),
+    };
+
+    match schema.get("type") {
+        Some(Value::String(ty)) if !has_type(value, ty) => {
+            bail!("'{}' must be of type {}, not {}", path, ty, type_of(value))
+        }
+        Some(Value::Array(types))
+            if !types
+                .iter()
+                .any(|ty| has_type(value, ty.as_str().unwrap_or_default())) =>
+        {
+            bail!(
+                "'{}' must be of one of the types {:?}, not {}",
+                path,
+                types,
+                type_of(value)
+            )
+        }
+        _ => {}
+    }
+    if let Some(Value::Array(values)) = schema.get("enum") {
+        if !values.contains(value) {
+            bail!("'{}' must be one of {:?}, not {}", path, values, value)
+        }
+    }
+    if let (Some(min), Some(n)) = (
+        schema.get("minimum").and_then(Value::as_f64),
+        value.as_f64(),
+    ) {
+        if n < min {
+            bail!("'{}' must be at least {}, not {}", path, min, n)
+        }
+    }
+    if let (Some(max), Some(n)) = (
+        schema.get("maximum").and_then(Value::as_f64),
+        value.as_f64(),
+    ) {
+        if n > max {
+            bail!("'{}' must be at most {}, not {}", path, max, n)
+        }
+    }
+
+    match value {
+        Value::Object(o) => {
+            if let Some(Value::Array(required)) = schema.get("required") {
+                for key in required.iter().filter_map(Value::as_str) {
+                    if !o.contains_key(key) {
+                        bail!("'{}' is missing the required property '{}'", path, key)
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, v) in o {
+                let sub_path = format!("{path}/{key}");
+                match properties.and_then(|p| p.get(key)) {
+                    Some(sub_schema) => validate_at(&sub_path, sub_schema, v)?,
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            bail!("'{}' has no property '{}'", path, key)
+                        }
+                        Some(sub_schema) => validate_at(&sub_path, sub_schema, v)?,
+                        None => {}
+                    },
+                }
+            }
+        }
+        Value::Array(a) => {
+            if let Some(items) = schema.get("items") {
+                for (i, v) in a.iter().enumerate() {
+                    validate_at(&format!("{path}/{i}"), items, v)?;
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// A [`ValidationFunction`] accepting the configurations of a plugin that comply with `schema`.
+pub fn validator(schema: Value) -> ValidationFunction {
+    std::sync::Arc::new(move |_path, _old, new| {
+        validate(&schema, &Value::Object(new.clone()))?;
+        Ok(None)
+    })
+}
+
+#[test]
+fn config_schema() {
+    let schema = config();
+    assert_eq!(schema["$schema"], DIALECT);
+
+    let default = serde_json::to_value(Config::default()).unwrap();
+    validate(&schema, &default).unwrap();
+
+    let config = Config::from_file("../../DEFAULT_CONFIG.json5").unwrap();
+    validate(&schema, &serde_json::to_value(config).unwrap()).unwrap();
+
+    let invalid = json!({ "scouting": { "multicast": { "enabled": "yes" } } });
+    assert!(validate(&schema, &invalid).is_err());
+    let unknown = json!({ "scouting": { "unknown": true } });
+    assert!(validate(&schema, &unknown).is_err());
+    let plugins = json!({ "plugins": { "rest": { "http_port": 8000 } } });
+    validate(&schema, &plugins).unwrap();
+    let plugins = json!({ "plugins": { "rest": 8000 } });
+    assert!(validate(&schema, &plugins).is_err());
+}
+
+#[test]
+fn plugin_validator() {
+    let validator = validator(json!({
+        "type": "object",
+        "properties": {
+            "port": { "type": "integer", "minimum": 1, "maximum": 65535 },
+            "mode": { "enum": ["read", "write"] },
+        },
+        "required": ["port"],
+        "additionalProperties": false,
+    }));
+    let config = |value: Value| value.as_object().unwrap().clone();
+    let old = config(json!({ "port": 8000 }));
+
+    assert!(
+        validator("", &old, &config(json!({ "port": 8080, "mode": "read" })))
+            .unwrap()
+            .is_none()
+    );
+    assert!(validator("", &old, &config(json!({ "mode": "read" }))).is_err());
+    assert!(validator("", &old, &config(json!({ "port": 0 }))).is_err());
+    assert!(validator("", &old, &config(json!({ "port": 8000, "mode": "all" }))).is_err());
+    assert!(validator("", &old, &config(json!({ "port": 8000, "other": 1 }))).is_err());
+}
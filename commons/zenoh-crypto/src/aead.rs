@@ -0,0 +1,125 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use rand::RngCore;
+use zenoh_result::{bail, zerror, ZResult};
+
+/// An authenticated cipher (AES-256-GCM).
+///
+/// Every encrypted message is prefixed with the random nonce used to encrypt it,
+/// so that [`AeadCipher::decrypt`] only needs the key.
+pub struct AeadCipher {
+    inner: Aes256Gcm,
+}
+
+impl AeadCipher {
+    pub const KEY_SIZE: usize = 32;
+    pub const NONCE_SIZE: usize = 12;
+
+    pub fn new(key: [u8; Self::KEY_SIZE]) -> AeadCipher {
+        AeadCipher {
+            inner: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+        }
+    }
+
+    pub fn encrypt(&self, bytes: &[u8]) -> ZResult<Vec<u8>> {
+        self.encrypt_with_aad(bytes, &[])
+    }
+
+    /// Encrypts `bytes`, authenticating `aad` along with them: the encrypted bytes may only be
+    /// decrypted with the same associated data.
+    pub fn encrypt_with_aad(&self, bytes: &[u8], aad: &[u8]) -> ZResult<Vec<u8>> {
+        let mut nonce = [0_u8; Self::NONCE_SIZE];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let encrypted = self
+            .inner
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: bytes, aad })
+            .map_err(|e| zerror!("Encryption failed: {:?}", e))?;
+
+        let mut result = Vec::with_capacity(Self::NONCE_SIZE + encrypted.len());
+        result.extend_from_slice(&nonce);
+        result.extend_from_slice(&encrypted);
+        Ok(result)
+    }
+
+    pub fn decrypt(&self, bytes: &[u8]) -> ZResult<Vec<u8>> {
+        self.decrypt_with_aad(bytes, &[])
+    }
+
+    /// Decrypts `bytes` encrypted with [`AeadCipher::encrypt_with_aad`] and the same `aad`.
+    pub fn decrypt_with_aad(&self, bytes: &[u8], aad: &[u8]) -> ZResult<Vec<u8>> {
+        if bytes.len() < Self::NONCE_SIZE {
+            bail!("Invalid bytes lenght to decrypt: {}", bytes.len());
+        }
+        let (nonce, encrypted) = bytes.split_at(Self::NONCE_SIZE);
+        let decrypted = self
+            .inner
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: encrypted,
+                    aad,
+                },
+            )
+            .map_err(|e| zerror!("Decryption failed: {:?}", e))?;
+        Ok(decrypted)
+    }
+}
+
+mod tests {
+    #[test]
+    fn aead() {
+        use super::AeadCipher;
+        use rand::RngCore;
+
+        let mut key = [0_u8; AeadCipher::KEY_SIZE];
+        rand::thread_rng().fill_bytes(&mut key);
+        let cipher = AeadCipher::new(key);
+
+        for clear in [
+            "".as_bytes(),
+            "A".as_bytes(),
+            "This is a medium string with some text".as_bytes(),
+        ] {
+            let encrypted = cipher.encrypt(clear).unwrap();
+            assert_ne!(&encrypted[AeadCipher::NONCE_SIZE..], clear);
+            let decrypted = cipher.decrypt(&encrypted).unwrap();
+            assert_eq!(&decrypted[..], clear);
+        }
+
+        // Tampered messages must be rejected
+        let mut encrypted = cipher.encrypt("Short string".as_bytes()).unwrap();
+        let last = encrypted.len() - 1;
+        encrypted[last] ^= 0xff;
+        assert!(cipher.decrypt(&encrypted).is_err());
+
+        // Messages encrypted with another key must be rejected
+        rand::thread_rng().fill_bytes(&mut key);
+        let other = AeadCipher::new(key);
+        let encrypted = other.encrypt("Short string".as_bytes()).unwrap();
+        assert!(cipher.decrypt(&encrypted).is_err());
+
+        // Messages must be decrypted with the associated data they were encrypted with
+        let encrypted = cipher
+            .encrypt_with_aad("Short string".as_bytes(), b"a/b")
+            .unwrap();
+        assert_eq!(
+            &cipher.decrypt_with_aad(&encrypted, b"a/b").unwrap()[..],
+            "Short string".as_bytes()
+        );
+        assert!(cipher.decrypt_with_aad(&encrypted, b"a/c").is_err());
+        assert!(cipher.decrypt(&encrypted).is_err());
+    }
+}
@@ -334,6 +334,7 @@ impl fmt::Display for keyexpr {
     }
 }
 
+// The errnos must stay above `zenoh_result::ErrorKind::ERRNO_BASE`
 #[repr(i8)]
 enum KeyExprConstructionError {
     LoneDollarStar = -1,
@@ -161,3 +161,105 @@ fn fuzz() {
         ke1 = ke2;
     }
 }
+
+mod properties {
+    use crate::key_expr::{keyexpr, OwnedKeyExpr};
+    use proptest::prelude::*;
+
+    // Chunks over a small alphabet, so that the generated key expressions often relate
+    fn chunk() -> impl Strategy<Value = &'static str> {
+        prop_oneof![
+            Just("a"),
+            Just("b"),
+            Just("ab"),
+            Just("*"),
+            Just("**"),
+            Just("a$*"),
+            Just("$*b"),
+            Just("a$*b"),
+        ]
+    }
+
+    fn concrete_chunk() -> impl Strategy<Value = &'static str> {
+        prop_oneof![Just("a"), Just("b"), Just("ab"), Just("ba")]
+    }
+
+    // The possibly non-canonical strings made of up to 5 chunks
+    fn raw_key_expr() -> impl Strategy<Value = String> {
+        prop::collection::vec(chunk(), 1..=5).prop_map(|chunks| chunks.join("/"))
+    }
+
+    fn key_expr() -> impl Strategy<Value = OwnedKeyExpr> {
+        raw_key_expr().prop_filter_map("not a key expression", |ke| {
+            OwnedKeyExpr::autocanonize(ke).ok()
+        })
+    }
+
+    fn concrete_key_expr() -> impl Strategy<Value = OwnedKeyExpr> {
+        prop::collection::vec(concrete_chunk(), 1..=5)
+            .prop_map(|chunks| OwnedKeyExpr::new(chunks.join("/")).unwrap())
+    }
+
+    proptest! {
+        #[test]
+        fn intersection_is_symmetric(a in key_expr(), b in key_expr()) {
+            prop_assert_eq!(a.intersects(&b), b.intersects(&a));
+        }
+
+        #[test]
+        fn intersection_and_inclusion_are_reflexive(a in key_expr()) {
+            prop_assert!(a.intersects(&a));
+            prop_assert!(a.includes(&a));
+        }
+
+        #[test]
+        fn inclusion_implies_intersection(a in key_expr(), b in key_expr()) {
+            if a.includes(&b) {
+                prop_assert!(a.intersects(&b));
+            }
+        }
+
+        #[test]
+        fn inclusion_is_transitive(a in key_expr(), b in key_expr(), c in key_expr()) {
+            if a.includes(&b) && b.includes(&c) {
+                prop_assert!(a.includes(&c));
+            }
+        }
+
+        #[test]
+        fn inclusion_is_transitive_to_concrete_key_exprs(
+            a in key_expr(),
+            b in key_expr(),
+            c in concrete_key_expr(),
+        ) {
+            if a.includes(&b) && b.intersects(&c) {
+                prop_assert!(a.intersects(&c));
+            }
+            if a.includes(&b) && b.includes(&c) {
+                prop_assert!(a.includes(&c));
+            }
+        }
+
+        #[test]
+        fn concrete_key_exprs_intersect_iff_equal(a in concrete_key_expr(), b in concrete_key_expr()) {
+            prop_assert_eq!(a.intersects(&b), a == b);
+            prop_assert_eq!(a.includes(&b), a == b);
+        }
+
+        #[test]
+        fn inclusion_of_concrete_key_exprs_is_intersection(a in key_expr(), c in concrete_key_expr()) {
+            prop_assert_eq!(a.includes(&c), a.intersects(&c));
+        }
+
+        #[test]
+        fn canonical_form_is_stable(ke in raw_key_expr()) {
+            if let Ok(canon) = OwnedKeyExpr::autocanonize(ke) {
+                // The canonical form is a valid key expression, which canonization leaves untouched
+                prop_assert!(keyexpr::new(canon.as_str()).is_ok());
+                let again = OwnedKeyExpr::autocanonize(canon.as_str().to_owned()).unwrap();
+                prop_assert_eq!(&again, &canon);
+                prop_assert!(again.includes(&canon) && canon.includes(&again));
+            }
+        }
+    }
+}
@@ -11,6 +11,9 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
+use super::{ZExtension, ZExtensionCodec};
+use crate::core::ZInt;
+use alloc::vec::Vec;
 use zenoh_buffers::ZBuf;
 
 /// # Attachment decorator
@@ -28,29 +31,68 @@ use zenoh_buffers::ZBuf;
 /// is the duty of that layer to serialize and de-serialize the attachment whenever deemed necessary.
 /// The attachement always contains serialized properties.
 ///
+/// If N==1 the attachment starts with a section of extensions (see [`ZExtension`]), followed by
+/// the rest of the attachment. Both are carried in the same length-prefixed buffer so that the
+/// receivers that do not support extensions receive them as part of an opaque attachment.
+///
 ///  7 6 5 4 3 2 1 0
 /// +-+-+-+-+-+-+-+-+
-/// |X|X|Z|  ATTCH  |
+/// |X|N|Z|  ATTCH  |
 /// +-+-+-+---------+
-/// ~   Attachment  ~
+/// ~   Attachment  ~  if N==1 then: [ ~ #extensions ~ | ~ ZExtension ~ ... | ~ buffer ~ ]
 /// +---------------+
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Attachment {
     pub buffer: ZBuf,
+    pub extensions: Vec<ZExtension>,
 }
 
 impl Attachment {
     pub fn new(buffer: ZBuf) -> Self {
-        Self { buffer }
+        Self {
+            buffer,
+            extensions: Vec::new(),
+        }
+    }
+
+    pub fn extension(&self, id: ZInt) -> Option<&ZExtension> {
+        self.extensions.iter().find(|e| e.id == id)
+    }
+
+    /// Add `extension`, replacing any extension with the same identifier.
+    pub fn insert_extension(&mut self, extension: ZExtension) {
+        match self.extensions.iter_mut().find(|e| e.id == extension.id) {
+            Some(e) => *e = extension,
+            None => self.extensions.push(extension),
+        }
+    }
+
+    pub fn remove_extension(&mut self, id: ZInt) -> Option<ZExtension> {
+        let idx = self.extensions.iter().position(|e| e.id == id)?;
+        Some(self.extensions.remove(idx))
+    }
+
+    /// Decode the typed extension `T`, if present and well-formed.
+    pub fn get<T: ZExtensionCodec>(&self) -> Option<T> {
+        self.extension(T::ID).and_then(|e| T::decode(&e.value))
+    }
+
+    /// Encode and add the typed extension `T`, replacing any previous one.
+    pub fn set<T: ZExtensionCodec>(&mut self, value: &T) {
+        self.insert_extension(ZExtension::new(T::ID, value.encode()));
     }
 
     #[cfg(feature = "test")]
     pub fn rand() -> Self {
         use rand::Rng;
 
+        let mut rng = rand::thread_rng();
         Self {
-            buffer: ZBuf::rand(rand::thread_rng().gen_range(1..128)),
+            buffer: ZBuf::rand(rng.gen_range(1..128)),
+            extensions: (0..rng.gen_range(0..3))
+                .map(|_| ZExtension::rand())
+                .collect(),
         }
     }
 }
@@ -0,0 +1,114 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use crate::core::ZInt;
+use alloc::collections::BTreeMap;
+use zenoh_buffers::ZBuf;
+use zenoh_result::{bail, ZResult};
+
+/// # Extension
+///
+/// An extension is a TLV (i.e., type-length-value) entry carried along with a message in the
+/// extensions section of its [`Attachment`](super::Attachment). Receivers that do not know the
+/// extension identifier skip it, so that new fields can be added to the messages without
+/// breaking the interoperability with the peers that do not support them.
+///
+/// ```text
+///  7 6 5 4 3 2 1 0
+/// +-+-+-+-+-+-+-+-+
+/// ~      id       ~
+/// +---------------+
+/// ~    length     ~
+/// +---------------+
+/// ~     value     ~
+/// +---------------+
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZExtension {
+    pub id: ZInt,
+    pub value: ZBuf,
+}
+
+impl ZExtension {
+    pub fn new(id: ZInt, value: ZBuf) -> Self {
+        Self { id, value }
+    }
+
+    #[cfg(feature = "test")]
+    pub fn rand() -> Self {
+        use rand::Rng;
+
+        let mut rng = rand::thread_rng();
+        Self {
+            id: rng.gen(),
+            value: ZBuf::rand(rng.gen_range(0..16)),
+        }
+    }
+}
+
+/// A typed extension, encoded into and decoded from the value of a [`ZExtension`].
+pub trait ZExtensionCodec: Sized {
+    /// The identifier of the extension on the wire.
+    const ID: ZInt;
+    /// A human readable name of the extension, used for diagnostic purposes.
+    const NAME: &'static str;
+
+    fn encode(&self) -> ZBuf;
+    fn decode(value: &ZBuf) -> Option<Self>;
+}
+
+/// The registry of the extension codecs known by a component, used to detect identifier
+/// collisions between extensions and to tell the known extensions of a message from the unknown ones.
+#[derive(Debug, Clone, Default)]
+pub struct ZExtensionRegistry {
+    codecs: BTreeMap<ZInt, &'static str>,
+}
+
+impl ZExtensionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the codec `T`, failing if another codec is already registered with the same identifier.
+    pub fn register<T: ZExtensionCodec>(&mut self) -> ZResult<()> {
+        match self.codecs.get(&T::ID) {
+            Some(name) if *name == T::NAME => Ok(()),
+            Some(name) => bail!(
+                "Extension id {} of {} already registered by {}",
+                T::ID,
+                T::NAME,
+                name
+            ),
+            None => {
+                self.codecs.insert(T::ID, T::NAME);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn is_known(&self, id: ZInt) -> bool {
+        self.codecs.contains_key(&id)
+    }
+
+    pub fn name(&self, id: ZInt) -> Option<&'static str> {
+        self.codecs.get(&id).copied()
+    }
+
+    /// The extensions in `extensions` whose codec is not registered.
+    pub fn unknown<'a>(
+        &'a self,
+        extensions: &'a [ZExtension],
+    ) -> impl Iterator<Item = &'a ZExtension> + 'a {
+        extensions.iter().filter(move |e| !self.is_known(e.id))
+    }
+}
@@ -12,7 +12,9 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 pub mod attachment;
+pub mod extension;
 pub use attachment::*;
+pub use extension::*;
 
 /*************************************/
 /*               IDS                 */
@@ -43,6 +45,9 @@ pub mod imsg {
         pub const UNIT: u8 = 0x0f;
         pub const LINK_STATE_LIST: u8 = 0x10;
 
+        // Transport Messages
+        pub const CREDIT: u8 = 0x11; // For unicast communications only
+
         // Message decorators
         pub const PRIORITY: u8 = 0x1c;
         pub const ROUTING_CONTEXT: u8 = 0x1d;
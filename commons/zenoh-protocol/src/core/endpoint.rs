@@ -15,7 +15,7 @@ use super::locator::*;
 use crate::core::split_once;
 use alloc::{borrow::ToOwned, format, string::String, vec::Vec};
 use core::{convert::TryFrom, fmt, str::FromStr};
-use zenoh_result::{zerror, Error as ZError, ZResult};
+use zenoh_result::{zerror, Error as ZError, ErrorKind, ZResult};
 
 // Parsing chars
 pub const PROTO_SEPARATOR: char = '/';
@@ -543,7 +543,7 @@ impl TryFrom<String> for EndPoint {
         let pidx = s
             .find(PROTO_SEPARATOR)
             .and_then(|i| (!s[..i].is_empty() && !s[i + 1..].is_empty()).then_some(i))
-            .ok_or_else(|| zerror!("{}: {}", ERR, s))?;
+            .ok_or_else(|| zerror!((ErrorKind::InvalidLocator) "{}: {}", ERR, s))?;
 
         match (s.find(METADATA_SEPARATOR), s.find(CONFIG_SEPARATOR)) {
             // No metadata or config at all
@@ -579,7 +579,7 @@ impl TryFrom<String> for EndPoint {
 
                 Ok(EndPoint { inner })
             }
-            _ => Err(zerror!("{}: {}", ERR, s).into()),
+            _ => Err(zerror!((ErrorKind::InvalidLocator) "{}: {}", ERR, s).into()),
         }
     }
 }
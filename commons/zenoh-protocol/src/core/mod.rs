@@ -445,10 +445,27 @@ pub enum SubMode {
     Pull,
 }
 
+/// The aggregation policy a subscriber asks the first router to apply
+/// on the samples it forwards to it.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SubAggregation {
+    /// The batching window in milliseconds: the samples are delivered at most once per window,
+    /// only the latest sample of each key expression being kept in the meantime.
+    pub window: ZInt,
+    /// The maximum number of samples per second delivered to the subscriber, 0 meaning unlimited.
+    pub max_rate: ZInt,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct SubInfo {
     pub reliability: Reliability,
     pub mode: SubMode,
+    /// An optional expression on the payload, evaluated by the first router
+    /// to only forward the matching samples to the subscriber.
+    pub filter: Option<String>,
+    /// An optional aggregation policy, applied by the first router
+    /// on the samples it forwards to the subscriber.
+    pub aggregation: Option<SubAggregation>,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
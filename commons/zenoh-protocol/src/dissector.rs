@@ -0,0 +1,222 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! Generation of a Wireshark dissector from the message definitions of this crate.
+//!
+//! The generated Lua dissector is checked in as `wireshark/zenoh.lua`, and a test makes sure
+//! it is regenerated whenever the message definitions change: run the tests of this crate with
+//! `ZENOH_BLESS_DISSECTOR=1` to update it.
+use crate::{common::imsg, transport::tmsg};
+use alloc::string::String;
+use core::fmt::Write;
+
+/// The port on which zenoh listens by default.
+pub const DISSECTOR_DEFAULT_PORT: u16 = 7447;
+/// The port of the default scouting multicast group.
+pub const DISSECTOR_SCOUTING_PORT: u16 = 7446;
+
+const MESSAGES: &[(&str, u8)] = &[
+    ("JOIN", imsg::id::JOIN),
+    ("SCOUT", imsg::id::SCOUT),
+    ("HELLO", imsg::id::HELLO),
+    ("INIT", imsg::id::INIT),
+    ("OPEN", imsg::id::OPEN),
+    ("CLOSE", imsg::id::CLOSE),
+    ("SYNC", imsg::id::SYNC),
+    ("ACK_NACK", imsg::id::ACK_NACK),
+    ("KEEP_ALIVE", imsg::id::KEEP_ALIVE),
+    ("PING_PONG", imsg::id::PING_PONG),
+    ("FRAME", imsg::id::FRAME),
+    ("DECLARE", imsg::id::DECLARE),
+    ("DATA", imsg::id::DATA),
+    ("QUERY", imsg::id::QUERY),
+    ("PULL", imsg::id::PULL),
+    ("UNIT", imsg::id::UNIT),
+    ("LINK_STATE_LIST", imsg::id::LINK_STATE_LIST),
+    ("CREDIT", imsg::id::CREDIT),
+    ("PRIORITY", imsg::id::PRIORITY),
+    ("ROUTING_CONTEXT", imsg::id::ROUTING_CONTEXT),
+    ("REPLY_CONTEXT", imsg::id::REPLY_CONTEXT),
+    ("ATTACHMENT", imsg::id::ATTACHMENT),
+];
+
+const LUA_BODY: &str = r#"
+local f_length = ProtoField.uint16("zenoh.length", "Batch length", base.DEC)
+local f_message = ProtoField.uint8("zenoh.message", "Message", base.HEX, message_ids, HEADER_MASK)
+local f_flags = ProtoField.uint8("zenoh.flags", "Flags", base.HEX, nil, FLAGS_MASK)
+local f_priority = ProtoField.uint8("zenoh.priority", "Priority", base.DEC, nil, FLAGS_MASK)
+local f_reliable = ProtoField.bool("zenoh.frame.reliable", "Reliable", 8, nil, FRAME_R)
+local f_fragment = ProtoField.bool("zenoh.frame.fragment", "Fragment", 8, nil, FRAME_F)
+local f_end = ProtoField.bool("zenoh.frame.end", "Last fragment", 8, nil, FRAME_E)
+local f_sn = ProtoField.uint64("zenoh.frame.sn", "Sequence number", base.DEC)
+local f_body = ProtoField.bytes("zenoh.body", "Body")
+
+zenoh.fields = { f_length, f_message, f_flags, f_priority, f_reliable, f_fragment, f_end, f_sn, f_body }
+
+-- Reads a variable length integer, returning its value and its length (or nil if truncated).
+local function read_zint(tvb, offset)
+    local value = UInt64(0)
+    local len = 0
+    while offset + len < tvb:len() and len < 10 do
+        local b = tvb(offset + len, 1):uint()
+        value = value + UInt64(bit.band(b, 0x7f)):lshift(7 * len)
+        len = len + 1
+        if b < 0x80 then
+            return value, len
+        end
+    end
+    return nil, len
+end
+
+-- Decodes the headers of the first message of a batch, including the first message of a frame.
+-- The bodies of the messages are shown as raw bytes.
+local function dissect_batch(tvb, pinfo, tree)
+    local offset = 0
+    local names = {}
+    while offset < tvb:len() do
+        local header = tvb(offset, 1):uint()
+        local id = bit.band(header, HEADER_MASK)
+        local name = message_ids[id] or string.format("UNKNOWN(0x%02x)", id)
+        local subtree = tree:add(zenoh, tvb(offset), name)
+        subtree:add(f_message, tvb(offset, 1))
+        table.insert(names, name)
+        if id == PRIORITY then
+            subtree:add(f_priority, tvb(offset, 1))
+            offset = offset + 1
+        elseif id == FRAME then
+            subtree:add(f_reliable, tvb(offset, 1))
+            subtree:add(f_fragment, tvb(offset, 1))
+            subtree:add(f_end, tvb(offset, 1))
+            offset = offset + 1
+            local sn, len = read_zint(tvb, offset)
+            if sn == nil then
+                break
+            end
+            subtree:add(f_sn, tvb(offset, len), sn)
+            offset = offset + len
+            if bit.band(header, FRAME_F) ~= 0 then
+                if offset < tvb:len() then
+                    subtree:add(f_body, tvb(offset))
+                end
+                break
+            end
+        else
+            subtree:add(f_flags, tvb(offset, 1))
+            if offset + 1 < tvb:len() then
+                subtree:add(f_body, tvb(offset + 1))
+            end
+            break
+        end
+    end
+    pinfo.cols.info = table.concat(names, " ")
+end
+
+local function get_batch_length(tvb, pinfo, offset)
+    return tvb(offset, 2):le_uint() + 2
+end
+
+local function dissect_streamed_batch(tvb, pinfo, tree)
+    pinfo.cols.protocol = "ZENOH"
+    tree:add_le(f_length, tvb(0, 2))
+    if tvb:len() > 2 then
+        dissect_batch(tvb(2):tvb(), pinfo, tree)
+    end
+    return tvb:len()
+end
+
+function zenoh.dissector(tvb, pinfo, tree)
+    pinfo.cols.protocol = "ZENOH"
+    if pinfo.port_type == 2 then
+        -- streamed links prefix each batch with its 16 bits little endian length
+        dissect_tcp_pdus(tvb, tree, 2, get_batch_length, dissect_streamed_batch)
+    else
+        dissect_batch(tvb, pinfo, tree)
+    end
+    return tvb:len()
+end
+"#;
+
+/// Generates a Lua dissector decoding the framing and the message headers of the zenoh protocol.
+///
+/// It is registered on the default TCP and UDP ports: use Wireshark's "Decode As..." for the others.
+pub fn lua_dissector() -> String {
+    let mut lua = String::new();
+    // writing to a String can't fail
+    let _ = write_lua(&mut lua);
+    lua
+}
+
+fn write_lua(lua: &mut String) -> core::fmt::Result {
+    writeln!(lua, "-- Wireshark dissector for the zenoh protocol.")?;
+    writeln!(
+        lua,
+        "-- Generated by zenoh_protocol::dissector::lua_dissector: do not edit."
+    )?;
+    writeln!(lua, "local zenoh = Proto(\"zenoh\", \"Zenoh Protocol\")")?;
+    writeln!(lua)?;
+    writeln!(lua, "local HEADER_MASK = {:#04x}", imsg::HEADER_MASK)?;
+    writeln!(lua, "local FLAGS_MASK = {:#04x}", !imsg::HEADER_MASK)?;
+    writeln!(lua, "local PRIORITY = {:#04x}", tmsg::id::PRIORITY)?;
+    writeln!(lua, "local FRAME = {:#04x}", tmsg::id::FRAME)?;
+    writeln!(lua, "local FRAME_R = {:#04x}", tmsg::flag::R)?;
+    writeln!(lua, "local FRAME_F = {:#04x}", tmsg::flag::F)?;
+    writeln!(lua, "local FRAME_E = {:#04x}", tmsg::flag::E)?;
+    writeln!(lua)?;
+    writeln!(lua, "local message_ids = {{")?;
+    for (name, id) in MESSAGES {
+        writeln!(lua, "    [{:#04x}] = \"{}\",", id, name)?;
+    }
+    writeln!(lua, "}}")?;
+    lua.push_str(LUA_BODY);
+    writeln!(lua)?;
+    writeln!(
+        lua,
+        "DissectorTable.get(\"tcp.port\"):add({}, zenoh)",
+        DISSECTOR_DEFAULT_PORT
+    )?;
+    writeln!(
+        lua,
+        "DissectorTable.get(\"udp.port\"):add({}, zenoh)",
+        DISSECTOR_DEFAULT_PORT
+    )?;
+    writeln!(
+        lua,
+        "DissectorTable.get(\"udp.port\"):add({}, zenoh)",
+        DISSECTOR_SCOUTING_PORT
+    )
+}
+
+#[cfg(all(test, feature = "std"))]
+#[test]
+fn lua_dissector_is_up_to_date() {
+    extern crate std;
+
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/wireshark/zenoh.lua");
+    let generated = lua_dissector();
+    if std::env::var_os("ZENOH_BLESS_DISSECTOR").is_some() {
+        std::fs::write(path, &generated).unwrap();
+    }
+    let checked_in = std::fs::read_to_string(path).unwrap_or_default();
+    assert!(
+        checked_in == generated,
+        "{} is out of date: rerun this test with ZENOH_BLESS_DISSECTOR=1",
+        path
+    );
+
+    // every message id is named once
+    let mut ids: alloc::vec::Vec<u8> = MESSAGES.iter().map(|(_, id)| *id).collect();
+    ids.sort_unstable();
+    ids.dedup();
+    assert_eq!(ids.len(), MESSAGES.len());
+}
@@ -23,6 +23,7 @@ extern crate alloc;
 pub mod common;
 pub mod core;
 pub mod defaults;
+pub mod dissector;
 pub mod scouting;
 pub mod transport;
 pub mod zenoh;
@@ -15,12 +15,13 @@ mod hello;
 mod scout;
 
 use crate::{
-    common::Attachment,
-    core::{whatami::WhatAmIMatcher, Locator, WhatAmI, ZenohId},
+    common::{Attachment, ZExtensionCodec},
+    core::{whatami::WhatAmIMatcher, Locator, WhatAmI, ZInt, ZenohId},
 };
 use alloc::vec::Vec;
 pub use hello::*;
 pub use scout::*;
+use zenoh_buffers::{SplitBuffer, ZBuf};
 
 // Zenoh messages at scouting level
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -37,7 +38,101 @@ pub struct ScoutingMessage {
     pub size: Option<core::num::NonZeroUsize>,
 }
 
+/// The domain of the sender of a scouting message, carried as an extension of its attachment.
+///
+/// The nodes of different domains ignore each other's scouting messages. The default domain
+/// (i.e. 0) is not carried, so that the nodes not supporting domains belong to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Domain(pub ZInt);
+
+impl ZExtensionCodec for Domain {
+    const ID: ZInt = 0x01;
+    const NAME: &'static str = "domain";
+
+    fn encode(&self) -> ZBuf {
+        ZBuf::from(self.0.to_le_bytes())
+    }
+
+    fn decode(value: &ZBuf) -> Option<Self> {
+        let bytes = <[u8; 8]>::try_from(value.contiguous().as_ref()).ok()?;
+        Some(Domain(ZInt::from_le_bytes(bytes)))
+    }
+}
+
+/// The signature of a scouting message, carried as an extension of its attachment.
+///
+/// It is the HMAC of the message serialized without its signature, computed with a key
+/// pre-shared by the nodes, so that they ignore the scouting messages of the nodes that don't
+/// know the key (e.g. a rogue device redirecting clients to a malicious router).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature(pub Vec<u8>);
+
+impl ZExtensionCodec for Signature {
+    const ID: ZInt = 0x02;
+    const NAME: &'static str = "signature";
+
+    fn encode(&self) -> ZBuf {
+        ZBuf::from(self.0.clone())
+    }
+
+    fn decode(value: &ZBuf) -> Option<Self> {
+        Some(Signature(value.contiguous().into_owned()))
+    }
+}
+
 impl ScoutingMessage {
+    /// The domain of the sender of this message.
+    pub fn domain(&self) -> ZInt {
+        self.attachment
+            .as_ref()
+            .and_then(|att| att.get::<Domain>())
+            .map_or(0, |domain| domain.0)
+    }
+
+    /// Set the domain of the sender of this message, see [`Domain`].
+    pub fn set_domain(&mut self, domain: ZInt) {
+        match self.attachment.as_mut() {
+            Some(att) => match domain {
+                0 => {
+                    att.remove_extension(Domain::ID);
+                }
+                domain => att.set(&Domain(domain)),
+            },
+            None if domain != 0 => {
+                let mut att = Attachment::new(ZBuf::default());
+                att.set(&Domain(domain));
+                self.attachment = Some(att);
+            }
+            None => {}
+        }
+    }
+
+    /// The signature of this message, if any.
+    pub fn signature(&self) -> Option<Signature> {
+        self.attachment
+            .as_ref()
+            .and_then(|att| att.get::<Signature>())
+    }
+
+    /// Set the signature of this message, see [`Signature`].
+    pub fn set_signature(&mut self, signature: Signature) {
+        self.attachment
+            .get_or_insert_with(|| Attachment::new(ZBuf::default()))
+            .set(&signature);
+    }
+
+    /// Remove the signature of this message, dropping the attachment if it was only carrying it.
+    pub fn take_signature(&mut self) -> Option<Signature> {
+        let att = self.attachment.as_mut()?;
+        let signature = att
+            .remove_extension(Signature::ID)
+            .and_then(|e| Signature::decode(&e.value));
+        if att.extensions.is_empty() && att.buffer.is_empty() {
+            self.attachment = None;
+        }
+        signature
+    }
+
     pub fn make_scout(
         what: Option<WhatAmIMatcher>,
         zid_request: bool,
@@ -0,0 +1,59 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use crate::core::{Priority, ZInt};
+
+/// # Credit message
+///
+/// ```text
+/// NOTE: 16 bits (2 bytes) may be prepended to the serialized message indicating the total length
+///       in bytes of the message, resulting in the maximum length of a message being 65_535 bytes.
+///       This is necessary in those stream-oriented transports (e.g., TCP) that do not preserve
+///       the boundary of the serialized messages. The length is encoded as little-endian.
+///       In any case, the length of a message must not exceed 65_535 bytes.
+///
+/// The CREDIT message grants the remote node the right to send `credit` more zenoh messages
+/// on the given priority. It is sent by the nodes with a limited receive capacity (e.g. constrained
+/// clients), the remote node pausing the sending on a priority once its credits are exhausted.
+/// A node never sent any CREDIT message for a priority is granted unlimited credits on it.
+///
+///  7 6 5 4 3 2 1 0
+/// +-+-+-+-+-+-+-+-+
+/// | prio | CREDIT |
+/// +-+-+-+-+-------+
+/// %    credit     %
+/// +---------------+
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credit {
+    pub priority: Priority,
+    pub credit: ZInt,
+}
+
+impl Credit {
+    #[cfg(feature = "test")]
+    pub fn rand() -> Self {
+        use core::convert::TryInto;
+        use rand::Rng;
+
+        let mut rng = rand::thread_rng();
+
+        let priority: Priority = rng
+            .gen_range(Priority::MAX as u8..=Priority::MIN as u8)
+            .try_into()
+            .unwrap();
+        let credit: ZInt = rng.gen();
+
+        Self { priority, credit }
+    }
+}
@@ -11,8 +11,11 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
-use crate::core::{WhatAmI, ZInt, ZenohId};
-use zenoh_buffers::ZSlice;
+use crate::{
+    common::ZExtensionCodec,
+    core::{WhatAmI, ZInt, ZenohId},
+};
+use zenoh_buffers::{SplitBuffer, ZBuf, ZSlice};
 
 /// # Init message
 ///
@@ -32,7 +35,7 @@ use zenoh_buffers::ZSlice;
 /// +-+-+-+-+-+-+-+-+
 /// |O|S|A|   INIT  |
 /// +-+-+-+-+-------+
-/// ~             |Q~ if O==1
+/// ~ features|X|F|X|Q~ if O==1
 /// +---------------+
 /// | v_maj | v_min | if A==0 -- Protocol Version VMaj.VMin
 /// +-------+-------+
@@ -49,6 +52,11 @@ use zenoh_buffers::ZSlice;
 ///     if A==1 and S==0 then the agreed resolution is the one communicated by the initiator.
 ///
 /// - if Q==1 then the initiator/responder support QoS.
+/// - if F==1 then the features supported by the initiator/responder are advertised in the options,
+///   starting at bit 8. Otherwise only the legacy features are assumed (i.e. fragmentation).
+///
+/// The batch size (see [`InitBatchSize`]) and the domain (see [`InitDomain`]) are carried as
+/// extensions of the attachment, so that the peers that do not support them skip them.
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct InitSyn {
@@ -57,6 +65,8 @@ pub struct InitSyn {
     pub zid: ZenohId,
     pub sn_resolution: ZInt,
     pub is_qos: bool,
+    /// The features supported by the initiator, see [`tmsg::features`](super::tmsg::features).
+    pub features: ZInt,
 }
 
 impl InitSyn {
@@ -76,6 +86,11 @@ impl InitSyn {
             SEQ_NUM_RES
         };
         let is_qos = rng.gen_bool(0.5);
+        let features = if rng.gen_bool(0.5) {
+            rng.gen::<u16>() as ZInt
+        } else {
+            super::tmsg::features::LEGACY
+        };
 
         Self {
             version,
@@ -83,6 +98,7 @@ impl InitSyn {
             zid,
             sn_resolution,
             is_qos,
+            features,
         }
     }
 }
@@ -93,6 +109,8 @@ pub struct InitAck {
     pub zid: ZenohId,
     pub sn_resolution: Option<ZInt>,
     pub is_qos: bool,
+    /// The features supported by the responder, see [`tmsg::features`](super::tmsg::features).
+    pub features: ZInt,
     pub cookie: ZSlice,
 }
 
@@ -114,6 +132,11 @@ impl InitAck {
             None
         };
         let is_qos = rng.gen_bool(0.5);
+        let features = if rng.gen_bool(0.5) {
+            rng.gen::<u16>() as ZInt
+        } else {
+            super::tmsg::features::LEGACY
+        };
         let cookie = ZSlice::rand(rng.gen_range(MIN..=MAX));
 
         Self {
@@ -121,7 +144,55 @@ impl InitAck {
             zid,
             sn_resolution,
             is_qos,
+            features,
             cookie,
         }
     }
 }
+
+/// The batch size of an INIT message, carried as an extension of its attachment.
+///
+/// In an InitSyn, the largest batch the initiator is willing to send and receive on the link.
+/// In an InitAck, the batch size agreed for the link (i.e. the smallest of both sides' limits):
+/// the responder only answers with it when the initiator advertised its own. The default batch
+/// size is not carried.
+///
+/// The identifier stays clear of the keys of the establishment properties (see
+/// `PeerAuthenticatorId`), which the peers not supporting extensions read from the same section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InitBatchSize(pub u16);
+
+impl ZExtensionCodec for InitBatchSize {
+    const ID: ZInt = 0x10;
+    const NAME: &'static str = "batch_size";
+
+    fn encode(&self) -> ZBuf {
+        ZBuf::from(self.0.to_le_bytes())
+    }
+
+    fn decode(value: &ZBuf) -> Option<Self> {
+        let bytes = <[u8; 2]>::try_from(value.contiguous().as_ref()).ok()?;
+        Some(InitBatchSize(u16::from_le_bytes(bytes)))
+    }
+}
+
+/// The domain of the sender of an INIT message, carried as an extension of its attachment.
+///
+/// The nodes of different domains do not establish transports. The default domain (i.e. 0) is
+/// not carried, so that the nodes not supporting domains belong to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InitDomain(pub ZInt);
+
+impl ZExtensionCodec for InitDomain {
+    const ID: ZInt = 0x11;
+    const NAME: &'static str = "domain";
+
+    fn encode(&self) -> ZBuf {
+        ZBuf::from(self.0.to_le_bytes())
+    }
+
+    fn decode(value: &ZBuf) -> Option<Self> {
+        let bytes = <[u8; 8]>::try_from(value.contiguous().as_ref()).ok()?;
+        Some(InitDomain(ZInt::from_le_bytes(bytes)))
+    }
+}
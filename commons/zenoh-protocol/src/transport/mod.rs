@@ -12,6 +12,7 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 mod close;
+mod credit;
 mod frame;
 mod init;
 mod join;
@@ -19,17 +20,19 @@ mod keepalive;
 mod open;
 
 use crate::{
-    common::Attachment,
-    core::{Channel, ConduitSnList, WhatAmI, ZInt, ZenohId},
+    common::{Attachment, ZExtensionCodec},
+    core::{Channel, ConduitSnList, Priority, WhatAmI, ZInt, ZenohId},
+    defaults::BATCH_SIZE,
 };
 pub use close::*;
 use core::time::Duration;
+pub use credit::*;
 pub use frame::*;
 pub use init::*;
 pub use join::*;
 pub use keepalive::*;
 pub use open::*;
-use zenoh_buffers::ZSlice;
+use zenoh_buffers::{ZBuf, ZSlice};
 
 pub mod tmsg {
     use crate::common::imsg;
@@ -51,6 +54,7 @@ pub mod tmsg {
         pub const PING_PONG: u8 = imsg::id::PING_PONG;
         pub const FRAME: u8 = imsg::id::FRAME;
         pub const JOIN: u8 = imsg::id::JOIN;
+        pub const CREDIT: u8 = imsg::id::CREDIT;
 
         // Message decorators
         pub const PRIORITY: u8 = imsg::id::PRIORITY;
@@ -67,6 +71,7 @@ pub mod tmsg {
         pub const K: u8 = 1 << 6; // 0x40 CloseLink     if K==1 then close the transport link only
         pub const L: u8 = 1 << 7; // 0x80 Locators      if L==1 then Locators are present
         pub const M: u8 = 1 << 5; // 0x20 Mask          if M==1 then a Mask is present
+        pub const N: u8 = 1 << 6; // 0x40 Extensions    if N==1 then extensions are present
         pub const O: u8 = 1 << 7; // 0x80 Options       if O==1 then Options are present
         pub const P: u8 = 1 << 5; // 0x20 PingOrPong    if P==1 then the message is Ping, otherwise is Pong
         pub const R: u8 = 1 << 5; // 0x20 Reliable      if R==1 then it concerns the reliable channel, best-effort otherwise
@@ -83,6 +88,24 @@ pub mod tmsg {
         use super::ZInt;
 
         pub const QOS: ZInt = 1 << 0; // 0x01 QoS       if PRIORITY==1 then the transport supports QoS
+        pub const FEATURES: ZInt = 1 << 2; // 0x04 Features   if FEATURES==1 then the supported features are in the upper options
+    }
+
+    // Features negotiated in the Init messages, advertised in the options starting at FEATURES_SHIFT
+    pub mod features {
+        use super::ZInt;
+
+        pub const FRAGMENTATION: ZInt = 1 << 0; // 0x01 Fragmentation of the messages larger than a batch
+        pub const ATTACHMENTS: ZInt = 1 << 1; // 0x02 Extensions section in the attachments
+        pub const COMPRESSION: ZInt = 1 << 2; // 0x04 Compression of the batches
+        pub const QOS: ZInt = 1 << 3; // 0x08 QoS
+        pub const SHARED_MEMORY: ZInt = 1 << 4; // 0x10 Shared memory
+        pub const CREDITS: ZInt = 1 << 5; // 0x20 Credit-based flow control, i.e. the Credit messages
+
+        // The features assumed for the peers that do not advertise them
+        pub const LEGACY: ZInt = FRAGMENTATION;
+        // The position of the features in the options of the Init messages
+        pub const FEATURES_SHIFT: u32 = 8;
     }
 
     pub mod join_options {
@@ -99,6 +122,10 @@ pub mod tmsg {
         pub const MAX_SESSIONS: u8 = 0x03;
         pub const MAX_LINKS: u8 = 0x04;
         pub const EXPIRED: u8 = 0x05;
+        /// The peer is too busy to accept the session: retry later.
+        pub const BUSY: u8 = 0x06;
+        /// The peer belongs to another domain.
+        pub const DOMAIN: u8 = 0x07;
     }
 
     pub fn close_reason_to_str(reason: u8) -> &'static str {
@@ -109,6 +136,8 @@ pub mod tmsg {
             close_reason::MAX_SESSIONS => "MAX_SESSIONS",
             close_reason::MAX_LINKS => "MAX_LINKS",
             close_reason::EXPIRED => "EXPIRED",
+            close_reason::BUSY => "BUSY",
+            close_reason::DOMAIN => "DOMAIN",
             _ => "UNKNOWN",
         }
     }
@@ -138,6 +167,7 @@ pub enum TransportBody {
     Close(Close),
     KeepAlive(KeepAlive),
     Frame(Frame),
+    Credit(Credit),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -155,20 +185,31 @@ impl TransportMessage {
         zid: ZenohId,
         sn_resolution: ZInt,
         is_qos: bool,
+        batch_size: u16,
+        features: ZInt,
+        domain: ZInt,
         attachment: Option<Attachment>,
     ) -> TransportMessage {
-        TransportMessage {
+        let mut msg = TransportMessage {
             body: TransportBody::InitSyn(InitSyn {
                 version,
                 whatami,
                 zid,
                 sn_resolution,
                 is_qos,
+                features,
             }),
             attachment,
             #[cfg(feature = "stats")]
             size: None,
+        };
+        if batch_size != BATCH_SIZE {
+            msg.set_extension(&InitBatchSize(batch_size));
         }
+        if domain != 0 {
+            msg.set_extension(&InitDomain(domain));
+        }
+        msg
     }
 
     pub fn make_init_ack(
@@ -176,21 +217,54 @@ impl TransportMessage {
         zid: ZenohId,
         sn_resolution: Option<ZInt>,
         is_qos: bool,
+        batch_size: Option<u16>,
+        features: ZInt,
+        domain: ZInt,
         cookie: ZSlice,
         attachment: Option<Attachment>,
     ) -> TransportMessage {
-        TransportMessage {
+        let mut msg = TransportMessage {
             body: TransportBody::InitAck(InitAck {
                 whatami,
                 zid,
                 sn_resolution,
                 is_qos,
+                features,
                 cookie,
             }),
             attachment,
             #[cfg(feature = "stats")]
             size: None,
+        };
+        if let Some(batch_size) = batch_size {
+            msg.set_extension(&InitBatchSize(batch_size));
         }
+        if domain != 0 {
+            msg.set_extension(&InitDomain(domain));
+        }
+        msg
+    }
+
+    /// The batch size carried by this InitSyn or InitAck, if any, see [`InitBatchSize`].
+    pub fn init_batch_size(&self) -> Option<u16> {
+        self.attachment
+            .as_ref()
+            .and_then(|att| att.get::<InitBatchSize>())
+            .map(|batch_size| batch_size.0)
+    }
+
+    /// The domain of the sender of this InitSyn or InitAck, see [`InitDomain`].
+    pub fn init_domain(&self) -> ZInt {
+        self.attachment
+            .as_ref()
+            .and_then(|att| att.get::<InitDomain>())
+            .map_or(0, |domain| domain.0)
+    }
+
+    fn set_extension<T: ZExtensionCodec>(&mut self, value: &T) {
+        self.attachment
+            .get_or_insert_with(|| Attachment::new(ZBuf::default()))
+            .set(value);
     }
 
     pub fn make_open_syn(
@@ -296,6 +370,19 @@ impl TransportMessage {
         }
     }
 
+    pub fn make_credit(
+        priority: Priority,
+        credit: ZInt,
+        attachment: Option<Attachment>,
+    ) -> TransportMessage {
+        TransportMessage {
+            body: TransportBody::Credit(Credit { priority, credit }),
+            attachment,
+            #[cfg(feature = "stats")]
+            size: None,
+        }
+    }
+
     #[cfg(feature = "test")]
     pub fn rand() -> Self {
         use rand::Rng;
@@ -308,7 +395,7 @@ impl TransportMessage {
             None
         };
 
-        let body = match rng.gen_range(0..8) {
+        let body = match rng.gen_range(0..9) {
             0 => TransportBody::InitSyn(InitSyn::rand()),
             1 => TransportBody::InitAck(InitAck::rand()),
             2 => TransportBody::OpenSyn(OpenSyn::rand()),
@@ -317,6 +404,7 @@ impl TransportMessage {
             5 => TransportBody::Close(Close::rand()),
             6 => TransportBody::KeepAlive(KeepAlive::rand()),
             7 => TransportBody::Frame(Frame::rand()),
+            8 => TransportBody::Credit(Credit::rand()),
             _ => unreachable!(),
         };
 
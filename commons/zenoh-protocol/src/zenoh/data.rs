@@ -11,7 +11,10 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
-use crate::core::{CongestionControl, Encoding, SampleKind, Timestamp, WireExpr, ZInt, ZenohId};
+use crate::{
+    common::ZExtensionCodec,
+    core::{CongestionControl, Encoding, SampleKind, Timestamp, WireExpr, ZInt, ZenohId},
+};
 use zenoh_buffers::ZBuf;
 
 /// # ReplyContext decorator
@@ -120,6 +123,9 @@ pub struct DataInfo {
     pub timestamp: Option<Timestamp>,
     pub source_id: Option<ZenohId>,
     pub source_sn: Option<ZInt>,
+    /// The attachment of the payload. It is not encoded with the other fields: it is carried as
+    /// the [`UserAttachment`] extension of the attachment of the message.
+    pub attachment: Option<ZBuf>,
 }
 
 impl DataInfo {
@@ -149,10 +155,29 @@ impl DataInfo {
             timestamp,
             source_id,
             source_sn,
+            attachment: None,
         }
     }
 }
 
+/// The attachment of a sample or of a query, carried as an extension of the attachment of the
+/// message, so that the peers that do not support it skip it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserAttachment(pub ZBuf);
+
+impl ZExtensionCodec for UserAttachment {
+    const ID: ZInt = 0x03;
+    const NAME: &'static str = "user_attachment";
+
+    fn encode(&self) -> ZBuf {
+        self.0.clone()
+    }
+
+    fn decode(value: &ZBuf) -> Option<Self> {
+        Some(UserAttachment(value.clone()))
+    }
+}
+
 /// # Data message
 ///
 /// ```text
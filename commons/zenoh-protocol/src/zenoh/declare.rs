@@ -11,8 +11,12 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
-use crate::core::{QueryableInfo, SubInfo, WireExpr, ZInt};
-use alloc::vec::Vec;
+use crate::{
+    common::ZExtensionCodec,
+    core::{QueryableInfo, SubAggregation, SubInfo, WireExpr, ZInt},
+};
+use alloc::{string::String, vec::Vec};
+use zenoh_buffers::ZBuf;
 
 /// ```text
 ///  7 6 5 4 3 2 1 0
@@ -55,6 +59,7 @@ pub enum Declaration {
     ForgetSubscriber(ForgetSubscriber),
     Queryable(Queryable),
     ForgetQueryable(ForgetQueryable),
+    Interest(Interest),
 }
 
 impl Declaration {
@@ -64,7 +69,7 @@ impl Declaration {
 
         let mut rng = rand::thread_rng();
 
-        match rng.gen_range(0..8) {
+        match rng.gen_range(0..9) {
             0 => Declaration::Resource(Resource::rand()),
             1 => Declaration::ForgetResource(ForgetResource::rand()),
             2 => Declaration::Publisher(Publisher::rand()),
@@ -73,6 +78,7 @@ impl Declaration {
             5 => Declaration::ForgetSubscriber(ForgetSubscriber::rand()),
             6 => Declaration::Queryable(Queryable::rand()),
             7 => Declaration::ForgetQueryable(ForgetQueryable::rand()),
+            8 => Declaration::Interest(Interest::rand()),
             _ => unreachable!(),
         }
     }
@@ -188,6 +194,10 @@ impl ForgetPublisher {
 /// |    SubMode    | if S==1. Otherwise: SubMode=Push
 /// +---------------+
 /// ```
+///
+/// The filter and the aggregation policy of the subscription are not encoded with it: they are
+/// carried as the [`SubscriberFilters`] and [`SubscriberAggregations`] extensions of the attachment
+/// of the declare message.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Subscriber {
     pub key: WireExpr<'static>,
@@ -213,12 +223,96 @@ impl Subscriber {
         } else {
             SubMode::Pull
         };
-        let info = SubInfo { reliability, mode };
+        let info = SubInfo {
+            reliability,
+            mode,
+            filter: None,
+            aggregation: None,
+        };
 
         Self { key, info }
     }
 }
 
+/// The filters of the subscriptions declared by a declare message, carried as an extension of
+/// the attachment of the message, so that the peers that do not support them skip them.
+///
+/// Each filter is associated to the index of its subscriber declaration in the message.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SubscriberFilters(pub Vec<(ZInt, String)>);
+
+impl ZExtensionCodec for SubscriberFilters {
+    const ID: ZInt = 0x04;
+    const NAME: &'static str = "subscriber_filters";
+
+    fn encode(&self) -> ZBuf {
+        let mut bytes = Vec::new();
+        for (index, filter) in self.0.iter() {
+            bytes.extend_from_slice(&index.to_le_bytes());
+            bytes.extend_from_slice(&(filter.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(filter.as_bytes());
+        }
+        ZBuf::from(bytes)
+    }
+
+    fn decode(value: &ZBuf) -> Option<Self> {
+        let bytes = value.contiguous();
+        let mut bytes = bytes.as_ref();
+        let mut filters = Vec::new();
+        while !bytes.is_empty() {
+            let index = ZInt::from_le_bytes(bytes.get(..8)?.try_into().ok()?);
+            let len = u32::from_le_bytes(bytes.get(8..12)?.try_into().ok()?) as usize;
+            let filter = core::str::from_utf8(bytes.get(12..12 + len)?).ok()?;
+            filters.push((index, String::from(filter)));
+            bytes = &bytes[12 + len..];
+        }
+        Some(SubscriberFilters(filters))
+    }
+}
+
+/// The aggregation policies of the subscriptions declared by a declare message, carried as
+/// an extension of the attachment of the message, so that the peers that do not support them skip them.
+///
+/// Each aggregation policy is associated to the index of its subscriber declaration in the message.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SubscriberAggregations(pub Vec<(ZInt, SubAggregation)>);
+
+impl ZExtensionCodec for SubscriberAggregations {
+    const ID: ZInt = 0x05;
+    const NAME: &'static str = "subscriber_aggregations";
+
+    fn encode(&self) -> ZBuf {
+        let mut bytes = Vec::new();
+        for (index, aggregation) in self.0.iter() {
+            bytes.extend_from_slice(&index.to_le_bytes());
+            bytes.extend_from_slice(&aggregation.window.to_le_bytes());
+            bytes.extend_from_slice(&aggregation.max_rate.to_le_bytes());
+        }
+        ZBuf::from(bytes)
+    }
+
+    fn decode(value: &ZBuf) -> Option<Self> {
+        let bytes = value.contiguous();
+        if bytes.len() % 24 != 0 {
+            return None;
+        }
+        let aggregations = bytes
+            .chunks_exact(24)
+            .map(|chunk| {
+                let int = |i: usize| ZInt::from_le_bytes(chunk[i..i + 8].try_into().unwrap());
+                (
+                    int(0),
+                    SubAggregation {
+                        window: int(8),
+                        max_rate: int(16),
+                    },
+                )
+            })
+            .collect();
+        Some(SubscriberAggregations(aggregations))
+    }
+}
+
 /// ```text
 ///  7 6 5 4 3 2 1 0
 /// +-+-+-+-+-+-+-+-+
@@ -294,3 +388,25 @@ impl ForgetQueryable {
         Self { key }
     }
 }
+
+/// ```text
+///  7 6 5 4 3 2 1 0
+/// +-+-+-+-+-+-+-+-+
+/// |K|X|X| INTEREST|
+/// +---------------+
+/// ~    KeyExpr     ~ if K==1 then key_expr has suffix
+/// +---------------+
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interest {
+    pub key: WireExpr<'static>,
+}
+
+impl Interest {
+    #[cfg(feature = "test")]
+    pub fn rand() -> Self {
+        let key = WireExpr::rand();
+
+        Self { key }
+    }
+}
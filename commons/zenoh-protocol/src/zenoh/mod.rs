@@ -105,6 +105,7 @@ pub mod zmsg {
             pub const PUBLISHER: u8 = 0x02;
             pub const SUBSCRIBER: u8 = 0x03;
             pub const QUERYABLE: u8 = 0x04;
+            pub const INTEREST: u8 = 0x05;
 
             pub const FORGET_RESOURCE: u8 = 0x11;
             pub const FORGET_PUBLISHER: u8 = 0x12;
@@ -144,11 +145,15 @@ pub mod zmsg {
     }
 
     // Default reliability for each Zenoh Message
+    //
+    // Declarations are sent on the Control priority, which can't be used by the applications:
+    // they are never starved behind a backlog of data when QoS is enabled. Queries and pulls
+    // are application traffic, they stay on the Data priority.
     pub mod default_channel {
         use super::{Channel, Priority, Reliability};
 
         pub const DECLARE: Channel = Channel {
-            priority: Priority::RealTime,
+            priority: Priority::Control,
             reliability: Reliability::Reliable,
         };
         pub const DATA: Channel = Channel {
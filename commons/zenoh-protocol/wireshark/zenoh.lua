@@ -0,0 +1,134 @@
+-- Wireshark dissector for the zenoh protocol.
+-- Generated by zenoh_protocol::dissector::lua_dissector: do not edit.
+local zenoh = Proto("zenoh", "Zenoh Protocol")
+
+local HEADER_MASK = 0x1f
+local FLAGS_MASK = 0xe0
+local PRIORITY = 0x1c
+local FRAME = 0x0a
+local FRAME_R = 0x20
+local FRAME_F = 0x40
+local FRAME_E = 0x80
+
+local message_ids = {
+    [0x00] = "JOIN",
+    [0x01] = "SCOUT",
+    [0x02] = "HELLO",
+    [0x03] = "INIT",
+    [0x04] = "OPEN",
+    [0x05] = "CLOSE",
+    [0x06] = "SYNC",
+    [0x07] = "ACK_NACK",
+    [0x08] = "KEEP_ALIVE",
+    [0x09] = "PING_PONG",
+    [0x0a] = "FRAME",
+    [0x0b] = "DECLARE",
+    [0x0c] = "DATA",
+    [0x0d] = "QUERY",
+    [0x0e] = "PULL",
+    [0x0f] = "UNIT",
+    [0x10] = "LINK_STATE_LIST",
+    [0x11] = "CREDIT",
+    [0x1c] = "PRIORITY",
+    [0x1d] = "ROUTING_CONTEXT",
+    [0x1e] = "REPLY_CONTEXT",
+    [0x1f] = "ATTACHMENT",
+}
+
+local f_length = ProtoField.uint16("zenoh.length", "Batch length", base.DEC)
+local f_message = ProtoField.uint8("zenoh.message", "Message", base.HEX, message_ids, HEADER_MASK)
+local f_flags = ProtoField.uint8("zenoh.flags", "Flags", base.HEX, nil, FLAGS_MASK)
+local f_priority = ProtoField.uint8("zenoh.priority", "Priority", base.DEC, nil, FLAGS_MASK)
+local f_reliable = ProtoField.bool("zenoh.frame.reliable", "Reliable", 8, nil, FRAME_R)
+local f_fragment = ProtoField.bool("zenoh.frame.fragment", "Fragment", 8, nil, FRAME_F)
+local f_end = ProtoField.bool("zenoh.frame.end", "Last fragment", 8, nil, FRAME_E)
+local f_sn = ProtoField.uint64("zenoh.frame.sn", "Sequence number", base.DEC)
+local f_body = ProtoField.bytes("zenoh.body", "Body")
+
+zenoh.fields = { f_length, f_message, f_flags, f_priority, f_reliable, f_fragment, f_end, f_sn, f_body }
+
+-- Reads a variable length integer, returning its value and its length (or nil if truncated).
+local function read_zint(tvb, offset)
+    local value = UInt64(0)
+    local len = 0
+    while offset + len < tvb:len() and len < 10 do
+        local b = tvb(offset + len, 1):uint()
+        value = value + UInt64(bit.band(b, 0x7f)):lshift(7 * len)
+        len = len + 1
+        if b < 0x80 then
+            return value, len
+        end
+    end
+    return nil, len
+end
+
+-- Decodes the headers of the first message of a batch, including the first message of a frame.
+-- The bodies of the messages are shown as raw bytes.
+local function dissect_batch(tvb, pinfo, tree)
+    local offset = 0
+    local names = {}
+    while offset < tvb:len() do
+        local header = tvb(offset, 1):uint()
+        local id = bit.band(header, HEADER_MASK)
+        local name = message_ids[id] or string.format("UNKNOWN(0x%02x)", id)
+        local subtree = tree:add(zenoh, tvb(offset), name)
+        subtree:add(f_message, tvb(offset, 1))
+        table.insert(names, name)
+        if id == PRIORITY then
+            subtree:add(f_priority, tvb(offset, 1))
+            offset = offset + 1
+        elseif id == FRAME then
+            subtree:add(f_reliable, tvb(offset, 1))
+            subtree:add(f_fragment, tvb(offset, 1))
+            subtree:add(f_end, tvb(offset, 1))
+            offset = offset + 1
+            local sn, len = read_zint(tvb, offset)
+            if sn == nil then
+                break
+            end
+            subtree:add(f_sn, tvb(offset, len), sn)
+            offset = offset + len
+            if bit.band(header, FRAME_F) ~= 0 then
+                if offset < tvb:len() then
+                    subtree:add(f_body, tvb(offset))
+                end
+                break
+            end
+        else
+            subtree:add(f_flags, tvb(offset, 1))
+            if offset + 1 < tvb:len() then
+                subtree:add(f_body, tvb(offset + 1))
+            end
+            break
+        end
+    end
+    pinfo.cols.info = table.concat(names, " ")
+end
+
+local function get_batch_length(tvb, pinfo, offset)
+    return tvb(offset, 2):le_uint() + 2
+end
+
+local function dissect_streamed_batch(tvb, pinfo, tree)
+    pinfo.cols.protocol = "ZENOH"
+    tree:add_le(f_length, tvb(0, 2))
+    if tvb:len() > 2 then
+        dissect_batch(tvb(2):tvb(), pinfo, tree)
+    end
+    return tvb:len()
+end
+
+function zenoh.dissector(tvb, pinfo, tree)
+    pinfo.cols.protocol = "ZENOH"
+    if pinfo.port_type == 2 then
+        -- streamed links prefix each batch with its 16 bits little endian length
+        dissect_tcp_pdus(tvb, tree, 2, get_batch_length, dissect_streamed_batch)
+    else
+        dissect_batch(tvb, pinfo, tree)
+    end
+    return tvb:len()
+end
+
+DissectorTable.get("tcp.port"):add(7447, zenoh)
+DissectorTable.get("udp.port"):add(7447, zenoh)
+DissectorTable.get("udp.port"):add(7446, zenoh)
@@ -213,17 +213,86 @@ impl From<ShmError> for Error {
     }
 }
 
+// +-----------+
+// | ERRORKIND |
+// +-----------+
+
+/// The kinds of failure callers may want to branch on.
+///
+/// The kind of a [`ZError`] is carried by its errno, e.g. `bail!((ErrorKind::Timeout) "...")`,
+/// and is retrieved with [`ErrNo::kind`]. Errors without a kind are inheriting the one of their source.
+///
+/// The kinds use the errnos from [`ErrorKind::ERRNO_BASE`] downwards, so that they never collide
+/// with the errnos the components use for their own errors, which must stay above it
+/// (e.g. the key expression construction errors use the errnos from -1 to -8).
+#[repr(i8)]
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorKind {
+    /// A locator or an endpoint is malformed, or relies on an unsupported protocol.
+    InvalidLocator = -64,
+    /// An operation didn't complete in time.
+    Timeout = -65,
+    /// A peer failed to authenticate, or isn't authorized.
+    AuthenticationFailed = -66,
+    /// A configured limit has been reached.
+    ResourceLimit = -67,
+    /// The session or the transport has been closed.
+    SessionClosed = -68,
+    /// The remote peer is too busy to accept a new session (e.g. it reached its maximum number
+    /// of sessions): the operation may be retried later.
+    Busy = -69,
+    /// Any other failure.
+    Other = i8::MIN,
+}
+
+impl ErrorKind {
+    /// The highest errno of the error kinds.
+    pub const ERRNO_BASE: i8 = -64;
+
+    pub const fn errno(self) -> NegativeI8 {
+        NegativeI8::new(self as i8)
+    }
+}
+
+impl From<NegativeI8> for ErrorKind {
+    fn from(errno: NegativeI8) -> Self {
+        match errno.get() {
+            -64 => ErrorKind::InvalidLocator,
+            -65 => ErrorKind::Timeout,
+            -66 => ErrorKind::AuthenticationFailed,
+            -67 => ErrorKind::ResourceLimit,
+            -68 => ErrorKind::SessionClosed,
+            -69 => ErrorKind::Busy,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
 // +-------+
 // | ERRNO |
 // +-------+
 
 pub trait ErrNo {
     fn errno(&self) -> NegativeI8;
+
+    fn kind(&self) -> ErrorKind {
+        self.errno().into()
+    }
 }
 
 impl ErrNo for ZError {
     fn errno(&self) -> NegativeI8 {
-        self.errno
+        match &self.source {
+            Some(source) if self.errno == NegativeI8::MIN => source.errno(),
+            _ => self.errno,
+        }
     }
 }
 
@@ -342,3 +411,42 @@ macro_rules! to_zerror {
         |e| Err(zerror!($kind, $descr, e))
     };
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_kind() {
+        let e: Error = zerror!((ErrorKind::Timeout) "No reply after {}s", 1).into();
+        assert_eq!(e.kind(), ErrorKind::Timeout);
+
+        // untyped errors inherit the kind of their source
+        let e: Error = zerror!(e => "Unable to open").into();
+        assert_eq!(e.kind(), ErrorKind::Timeout);
+        let e: Error = zerror!((ErrorKind::SessionClosed) e => "Closed").into();
+        assert_eq!(e.kind(), ErrorKind::SessionClosed);
+
+        let e: Error = zerror!("Something else").into();
+        assert_eq!(e.kind(), ErrorKind::Other);
+        let e: Error = std::io::Error::from(std::io::ErrorKind::Other).into();
+        assert_eq!(e.kind(), ErrorKind::Other);
+
+        // the errnos of the components' own errors are not kinds
+        for errno in ErrorKind::ERRNO_BASE + 1..0 {
+            let e: Error = zerror!((errno) "Invalid Key Expr").into();
+            assert_eq!(e.kind(), ErrorKind::Other);
+        }
+        for kind in [
+            ErrorKind::InvalidLocator,
+            ErrorKind::Timeout,
+            ErrorKind::AuthenticationFailed,
+            ErrorKind::ResourceLimit,
+            ErrorKind::SessionClosed,
+            ErrorKind::Busy,
+        ] {
+            assert!(kind.errno().get() <= ErrorKind::ERRNO_BASE);
+            assert_eq!(ErrorKind::from(kind.errno()), kind);
+        }
+    }
+}
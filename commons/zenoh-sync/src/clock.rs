@@ -0,0 +1,278 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! The clock of the timing-dependent logic (leases, scouting delays, retransmission timers...).
+//!
+//! Outside of a simulation, [`now()`], [`sleep()`] and [`timeout()`] behave like their
+//! `std` and `async_std` counterparts. Within [`simulate()`], they use a virtual clock that
+//! only advances when every task is blocked, jumping straight to the earliest pending timer:
+//! the tasks run on the calling thread in a deterministic order and without real sleeps,
+//! so that the timing-dependent logic can be tested reproducibly.
+use futures::executor::{LocalPool, LocalSpawner};
+use futures::future::{Either, FutureExt};
+use futures::task::LocalSpawnExt;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+struct Simulation {
+    epoch: Instant,
+    elapsed: Cell<Duration>,
+    // The wakers of the pending timers, ordered by deadline and then by registration
+    timers: RefCell<BTreeMap<(Duration, u64), Waker>>,
+    next_timer: Cell<u64>,
+    spawner: LocalSpawner,
+}
+
+impl Simulation {
+    // Advance the virtual clock to the earliest pending deadline and wake its timers.
+    // Returns false if there is no pending timer.
+    fn advance(&self) -> bool {
+        let mut timers = self.timers.borrow_mut();
+        let deadline = match timers.keys().next() {
+            Some((deadline, _)) => *deadline,
+            None => return false,
+        };
+        self.elapsed.set(self.elapsed.get().max(deadline));
+        let due: Vec<(Duration, u64)> = timers
+            .keys()
+            .take_while(|(d, _)| *d <= deadline)
+            .copied()
+            .collect();
+        for timer in due {
+            if let Some(waker) = timers.remove(&timer) {
+                waker.wake();
+            }
+        }
+        true
+    }
+}
+
+thread_local! {
+    static SIMULATION: RefCell<Option<Rc<Simulation>>> = RefCell::new(None);
+}
+
+fn simulation() -> Option<Rc<Simulation>> {
+    // The simulation may be gone if a timer is dropped while the thread exits
+    SIMULATION.try_with(|s| s.borrow().clone()).ok().flatten()
+}
+
+// Uninstalls the simulation of the thread, even if the simulated future panics
+struct SimulationGuard;
+
+impl Drop for SimulationGuard {
+    fn drop(&mut self) {
+        SIMULATION.with(|s| *s.borrow_mut() = None);
+    }
+}
+
+/// Run a future to completion on the current thread with a virtual clock.
+///
+/// The tasks spawned with [`spawn()`] within the simulation run on the current thread too.
+/// Whenever all of them are blocked, the virtual clock advances to the earliest deadline of
+/// the pending [`sleep()`] and [`timeout()`], whose tasks are then woken in the order they
+/// started waiting.
+///
+/// # Panics
+/// Panics if called within a simulation, or if the future can never complete because all
+/// the tasks are blocked without any pending timer. Real I/O is not simulated: a task
+/// waiting for it is considered blocked.
+pub fn simulate<F>(future: F) -> F::Output
+where
+    F: Future + 'static,
+{
+    assert!(
+        simulation().is_none(),
+        "Nested simulations are not supported"
+    );
+    let mut pool = LocalPool::new();
+    let sim = Rc::new(Simulation {
+        epoch: Instant::now(),
+        elapsed: Cell::new(Duration::ZERO),
+        timers: RefCell::new(BTreeMap::new()),
+        next_timer: Cell::new(0),
+        spawner: pool.spawner(),
+    });
+    SIMULATION.with(|s| *s.borrow_mut() = Some(sim.clone()));
+    let _guard = SimulationGuard;
+
+    let mut handle = pool
+        .spawner()
+        .spawn_local_with_handle(future)
+        .expect("The simulation executor is alive");
+    loop {
+        pool.run_until_stalled();
+        if let Some(output) = (&mut handle).now_or_never() {
+            return output;
+        }
+        assert!(sim.advance(), "Simulation deadlock: no task can progress");
+    }
+}
+
+/// Whether the current thread runs a simulation.
+pub fn is_simulated() -> bool {
+    simulation().is_some()
+}
+
+/// The current instant of the clock.
+pub fn now() -> Instant {
+    match simulation() {
+        Some(sim) => sim.epoch + sim.elapsed.get(),
+        None => Instant::now(),
+    }
+}
+
+/// Spawn a detached task, on the simulation executor if called within a simulation.
+pub fn spawn<F>(future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    match simulation() {
+        Some(sim) => sim
+            .spawner
+            .spawn_local(future)
+            .expect("The simulation executor is alive"),
+        None => {
+            async_std::task::spawn(future);
+        }
+    }
+}
+
+/// Wait until `duration` has elapsed on the clock.
+pub fn sleep(duration: Duration) -> Sleep {
+    Sleep {
+        deadline: now() + duration,
+        timer: None,
+        inner: None,
+    }
+}
+
+/// The future returned by [`sleep()`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Sleep {
+    deadline: Instant,
+    // The key of the timer registered in the simulation
+    timer: Option<(Duration, u64)>,
+    // The real timer outside of a simulation
+    inner: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let sim = match simulation() {
+            Some(sim) => sim,
+            None => {
+                let deadline = self.deadline;
+                let inner = self.inner.get_or_insert_with(|| {
+                    Box::pin(async_std::task::sleep(
+                        deadline.saturating_duration_since(Instant::now()),
+                    ))
+                });
+                return inner.poll_unpin(cx);
+            }
+        };
+
+        let deadline = self.deadline.saturating_duration_since(sim.epoch);
+        let mut timers = sim.timers.borrow_mut();
+        if sim.elapsed.get() >= deadline {
+            if let Some(timer) = self.timer.take() {
+                timers.remove(&timer);
+            }
+            return Poll::Ready(());
+        }
+        // A timer keeps its place among the timers of the same deadline when polled again
+        let timer = *self.timer.get_or_insert_with(|| {
+            let id = sim.next_timer.get();
+            sim.next_timer.set(id + 1);
+            (deadline, id)
+        });
+        timers.insert(timer, cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let (Some(timer), Some(sim)) = (self.timer, simulation()) {
+            sim.timers.borrow_mut().remove(&timer);
+        }
+    }
+}
+
+/// The error returned by [`timeout()`] when the future didn't complete in time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimedOut;
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "future has timed out")
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+/// Await a future for at most `duration` on the clock.
+pub async fn timeout<F>(duration: Duration, future: F) -> Result<F::Output, TimedOut>
+where
+    F: Future,
+{
+    futures::pin_mut!(future);
+    match futures::future::select(future, sleep(duration)).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right(_) => Err(TimedOut),
+    }
+}
+
+#[test]
+fn clock_simulation() {
+    use std::sync::{Arc, Mutex};
+
+    let start = Instant::now();
+    let events = simulate(async {
+        let events = Arc::new(Mutex::new(vec![]));
+        for (name, delay) in [("c", 30), ("a", 10), ("b", 10)] {
+            let events = events.clone();
+            spawn(async move {
+                let begin = now();
+                sleep(Duration::from_secs(delay)).await;
+                let elapsed = now() - begin;
+                events.lock().unwrap().push((name, elapsed.as_secs()));
+            });
+        }
+        // A lease expires if nothing happens in time
+        let pending = futures::future::pending::<()>();
+        assert_eq!(
+            timeout(Duration::from_secs(20), pending).await,
+            Err(TimedOut)
+        );
+        assert!(
+            timeout(Duration::from_secs(20), sleep(Duration::from_secs(5)))
+                .await
+                .is_ok()
+        );
+        sleep(Duration::from_secs(60)).await;
+        Arc::try_unwrap(events).unwrap().into_inner().unwrap()
+    });
+    assert_eq!(events, vec![("a", 10), ("b", 10), ("c", 30)]);
+    // No real sleep happened
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(!is_simulated());
+}
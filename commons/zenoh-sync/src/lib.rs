@@ -40,6 +40,8 @@ pub use condition::*;
 pub mod signal;
 pub use signal::*;
 
+pub mod clock;
+
 pub fn get_mut_unchecked<T>(arc: &mut std::sync::Arc<T>) -> &mut T {
     unsafe { &mut (*(std::sync::Arc::as_ptr(arc) as *mut T)) }
 }
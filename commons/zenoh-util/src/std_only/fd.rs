@@ -0,0 +1,69 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! Monitoring of the file descriptors used by the process.
+
+/// The number of file descriptors opened by the process, and the limit it may open.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FdUsage {
+    pub open: usize,
+    pub limit: usize,
+}
+
+impl FdUsage {
+    /// The usage of the file descriptors, as a percentage of the limit.
+    pub fn percent(&self) -> usize {
+        self.open.saturating_mul(100) / self.limit.max(1)
+    }
+}
+
+/// Returns the current file descriptors usage of the process,
+/// or `None` if it can't be determined on this platform.
+#[cfg(unix)]
+pub fn usage() -> Option<FdUsage> {
+    #[cfg(target_os = "linux")]
+    const FD_DIR: &str = "/proc/self/fd";
+    #[cfg(not(target_os = "linux"))]
+    const FD_DIR: &str = "/dev/fd";
+
+    let mut rlimit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut rlimit) } != 0 {
+        return None;
+    }
+    if rlimit.rlim_cur == libc::RLIM_INFINITY {
+        return None;
+    }
+    // the directory itself is opened while being read
+    let open = std::fs::read_dir(FD_DIR).ok()?.count().saturating_sub(1);
+    Some(FdUsage {
+        open,
+        limit: rlimit.rlim_cur as usize,
+    })
+}
+
+#[cfg(not(unix))]
+pub fn usage() -> Option<FdUsage> {
+    None
+}
+
+#[cfg(unix)]
+#[test]
+fn fd_usage() {
+    let usage = usage().unwrap();
+    assert!(usage.open > 0 && usage.open <= usage.limit);
+    assert!(usage.percent() <= 100);
+}
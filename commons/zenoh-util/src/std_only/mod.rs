@@ -1,3 +1,4 @@
+pub mod fd;
 pub mod ffi;
 mod lib_loader;
 pub mod net;
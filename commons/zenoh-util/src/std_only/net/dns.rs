@@ -0,0 +1,103 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use async_std::net::ToSocketAddrs;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use zenoh_core::{zconfigurable, zlock};
+use zenoh_result::{bail, ZResult};
+
+zconfigurable! {
+    // Amount of time in milliseconds a successful resolution is cached.
+    static ref DNS_CACHE_TTL: u64 = 30_000;
+    // Amount of time in milliseconds a failed resolution is cached, so that
+    // reconnection attempts do not hammer the resolver during outages.
+    static ref DNS_NEGATIVE_CACHE_TTL: u64 = 5_000;
+    // Maximum number of cached resolutions.
+    static ref DNS_CACHE_SIZE: usize = 1_024;
+}
+
+enum Resolution {
+    Resolved(Vec<SocketAddr>),
+    Failed(String),
+}
+
+struct CacheEntry {
+    resolution: Resolution,
+    expiry: Instant,
+}
+
+lazy_static! {
+    static ref DNS_CACHE: Mutex<HashMap<String, CacheEntry>> = Mutex::new(HashMap::new());
+}
+
+/// Asynchronously resolve `address`, in the `host:port` form, into socket addresses.
+/// Successful and failed resolutions are cached for a limited amount of time.
+pub async fn resolve(address: &str) -> ZResult<Vec<SocketAddr>> {
+    // Socket addresses do not need to be resolved
+    if let Ok(addr) = address.parse::<SocketAddr>() {
+        return Ok(vec![addr]);
+    }
+
+    if let Some(entry) = zlock!(DNS_CACHE).get(address) {
+        if entry.expiry > Instant::now() {
+            match &entry.resolution {
+                Resolution::Resolved(addrs) => return Ok(addrs.clone()),
+                Resolution::Failed(e) => bail!("{} (cached)", e),
+            }
+        }
+    }
+
+    let (resolution, ttl) = match address.to_socket_addrs().await {
+        Ok(addrs) => (
+            Resolution::Resolved(addrs.collect()),
+            Duration::from_millis(*DNS_CACHE_TTL),
+        ),
+        Err(e) => (
+            Resolution::Failed(format!("Unable to resolve {}: {}", address, e)),
+            Duration::from_millis(*DNS_NEGATIVE_CACHE_TTL),
+        ),
+    };
+    let res = match &resolution {
+        Resolution::Resolved(addrs) => Ok(addrs.clone()),
+        Resolution::Failed(e) => Err(e.clone()),
+    };
+
+    let mut cache = zlock!(DNS_CACHE);
+    let now = Instant::now();
+    if cache.len() >= *DNS_CACHE_SIZE {
+        cache.retain(|_, entry| entry.expiry > now);
+    }
+    if cache.len() < *DNS_CACHE_SIZE {
+        cache.insert(
+            address.to_string(),
+            CacheEntry {
+                resolution,
+                expiry: now + ttl,
+            },
+        );
+    }
+    drop(cache);
+
+    match res {
+        Ok(addrs) => Ok(addrs),
+        Err(e) => bail!("{}", e),
+    }
+}
+
+/// Forget all the cached resolutions.
+pub fn clear_dns_cache() {
+    zlock!(DNS_CACHE).clear();
+}
@@ -12,11 +12,14 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 use async_std::net::TcpStream;
-use std::net::{IpAddr, Ipv6Addr};
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
 use std::time::Duration;
 use zenoh_core::zconfigurable;
 use zenoh_result::{bail, ZResult};
 
+mod dns;
+pub use dns::*;
+
 zconfigurable! {
     static ref WINDOWS_GET_ADAPTERS_ADDRESSES_BUF_SIZE: u32 = 8192;
     static ref WINDOWS_GET_ADAPTERS_ADDRESSES_MAX_RETRIES: u32 = 3;
@@ -89,6 +92,346 @@ pub fn set_linger(socket: &TcpStream, dur: Option<Duration>) -> ZResult<()> {
     }
 }
 
+/// Set the DiffServ code point (DSCP) of the IP packets sent on `socket`, i.e. the 6 most
+/// significant bits of the IPv4 TOS byte or of the IPv6 traffic class.
+#[cfg(unix)]
+pub fn set_dscp<S: std::os::unix::io::AsRawFd>(socket: &S, is_ipv6: bool, dscp: u8) -> ZResult<()> {
+    if dscp > 0x3f {
+        bail!(
+            "Invalid DSCP value {}: it must be in the range 0..=63",
+            dscp
+        );
+    }
+    set_tos(socket, is_ipv6, dscp << 2)
+}
+
+/// Set the DiffServ code point (DSCP) of the IP packets sent on `socket`, i.e. the 6 most
+/// significant bits of the IPv4 TOS byte or of the IPv6 traffic class.
+#[cfg(windows)]
+pub fn set_dscp<S: std::os::windows::io::AsRawSocket>(
+    socket: &S,
+    is_ipv6: bool,
+    dscp: u8,
+) -> ZResult<()> {
+    if dscp > 0x3f {
+        bail!(
+            "Invalid DSCP value {}: it must be in the range 0..=63",
+            dscp
+        );
+    }
+    set_tos(socket, is_ipv6, dscp << 2)
+}
+
+/// Set the IPv4 TOS byte or the IPv6 traffic class of the IP packets sent on `socket`.
+#[cfg(unix)]
+pub fn set_tos<S: std::os::unix::io::AsRawFd>(socket: &S, is_ipv6: bool, tos: u8) -> ZResult<()> {
+    let tos = tos as libc::c_int;
+    let (level, name) = if is_ipv6 {
+        (libc::IPPROTO_IPV6, libc::IPV6_TCLASS)
+    } else {
+        (libc::IPPROTO_IP, libc::IP_TOS)
+    };
+
+    unsafe {
+        let ret = libc::setsockopt(
+            socket.as_raw_fd(),
+            level,
+            name,
+            &tos as *const libc::c_int as *const libc::c_void,
+            std::mem::size_of_val(&tos) as libc::socklen_t,
+        );
+        match ret {
+            0 => Ok(()),
+            err_code => bail!("setsockopt returned {}", err_code),
+        }
+    }
+}
+
+/// Set the IPv4 TOS byte or the IPv6 traffic class of the IP packets sent on `socket`.
+#[cfg(windows)]
+pub fn set_tos<S: std::os::windows::io::AsRawSocket>(
+    socket: &S,
+    is_ipv6: bool,
+    tos: u8,
+) -> ZResult<()> {
+    use winapi::shared::ws2def::{IPPROTO_IP, IPPROTO_IPV6};
+    use winapi::shared::ws2ipdef::{IPV6_TCLASS, IP_TOS};
+    use winapi::um::winsock2;
+    use winapi::um::ws2tcpip;
+
+    let tos = tos as i32;
+    let (level, name) = if is_ipv6 {
+        (IPPROTO_IPV6 as i32, IPV6_TCLASS as i32)
+    } else {
+        (IPPROTO_IP as i32, IP_TOS as i32)
+    };
+
+    unsafe {
+        let ret = winsock2::setsockopt(
+            socket.as_raw_socket().try_into().unwrap(),
+            level,
+            name,
+            &tos as *const i32 as *const i8,
+            std::mem::size_of_val(&tos) as ws2tcpip::socklen_t,
+        );
+        match ret {
+            0 => Ok(()),
+            err_code => bail!("setsockopt returned {}", err_code),
+        }
+    }
+}
+
+/// Set the Linux SO_PRIORITY of `socket`, i.e. the priority of its packets in the local
+/// queueing disciplines. It is not supported on the other platforms.
+#[cfg(unix)]
+pub fn set_so_priority<S: std::os::unix::io::AsRawFd>(socket: &S, priority: u32) -> ZResult<()> {
+    #[cfg(target_os = "linux")]
+    {
+        let priority = priority as libc::c_int;
+        unsafe {
+            let ret = libc::setsockopt(
+                socket.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_PRIORITY,
+                &priority as *const libc::c_int as *const libc::c_void,
+                std::mem::size_of_val(&priority) as libc::socklen_t,
+            );
+            match ret {
+                0 => Ok(()),
+                err_code => bail!("setsockopt returned {}", err_code),
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (socket, priority);
+        bail!("SO_PRIORITY is only supported on Linux")
+    }
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios"
+))]
+fn set_int_option<S: std::os::unix::io::AsRawFd>(
+    socket: &S,
+    level: libc::c_int,
+    name: libc::c_int,
+    value: libc::c_int,
+) -> ZResult<()> {
+    unsafe {
+        let ret = libc::setsockopt(
+            socket.as_raw_fd(),
+            level,
+            name,
+            &value as *const libc::c_int as *const libc::c_void,
+            std::mem::size_of_val(&value) as libc::socklen_t,
+        );
+        match ret {
+            0 => Ok(()),
+            err_code => bail!("setsockopt returned {}", err_code),
+        }
+    }
+}
+
+/// Enable the TCP keepalive probes on `socket`, optionally tuning the idle time before the first
+/// probe, the interval between probes and the number of unanswered probes before the connection
+/// is dropped. The unset parameters keep the system defaults.
+#[cfg(unix)]
+pub fn set_tcp_keepalive<S: std::os::unix::io::AsRawFd>(
+    socket: &S,
+    idle: Option<Duration>,
+    interval: Option<Duration>,
+    count: Option<u32>,
+) -> ZResult<()> {
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios"
+    ))]
+    {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        const TCP_KEEPIDLE: libc::c_int = libc::TCP_KEEPIDLE;
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        const TCP_KEEPIDLE: libc::c_int = libc::TCP_KEEPALIVE;
+
+        set_int_option(socket, libc::SOL_SOCKET, libc::SO_KEEPALIVE, 1)?;
+        if let Some(idle) = idle {
+            set_int_option(
+                socket,
+                libc::IPPROTO_TCP,
+                TCP_KEEPIDLE,
+                idle.as_secs().max(1) as libc::c_int,
+            )?;
+        }
+        if let Some(interval) = interval {
+            set_int_option(
+                socket,
+                libc::IPPROTO_TCP,
+                libc::TCP_KEEPINTVL,
+                interval.as_secs().max(1) as libc::c_int,
+            )?;
+        }
+        if let Some(count) = count {
+            set_int_option(
+                socket,
+                libc::IPPROTO_TCP,
+                libc::TCP_KEEPCNT,
+                count as libc::c_int,
+            )?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios"
+    )))]
+    {
+        let _ = (socket, idle, interval, count);
+        bail!("TCP keepalive tuning is not supported on this platform")
+    }
+}
+
+/// Enable the TCP keepalive probes on `socket`, optionally tuning the idle time before the first
+/// probe, the interval between probes and the number of unanswered probes before the connection
+/// is dropped. The unset parameters keep the system defaults.
+#[cfg(windows)]
+pub fn set_tcp_keepalive<S: std::os::windows::io::AsRawSocket>(
+    socket: &S,
+    idle: Option<Duration>,
+    interval: Option<Duration>,
+    count: Option<u32>,
+) -> ZResult<()> {
+    let _ = (socket, idle, interval, count);
+    bail!("TCP keepalive tuning is not supported on this platform")
+}
+
+/// Set the TCP_USER_TIMEOUT of `socket`, i.e. the maximum amount of time transmitted data may
+/// remain unacknowledged before the connection is dropped. It is only supported on Linux.
+#[cfg(unix)]
+pub fn set_tcp_user_timeout<S: std::os::unix::io::AsRawFd>(
+    socket: &S,
+    timeout: Duration,
+) -> ZResult<()> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        set_int_option(
+            socket,
+            libc::IPPROTO_TCP,
+            libc::TCP_USER_TIMEOUT,
+            timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int,
+        )
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    {
+        let _ = (socket, timeout);
+        bail!("TCP_USER_TIMEOUT is only supported on Linux")
+    }
+}
+
+/// Set the TCP_USER_TIMEOUT of `socket`, i.e. the maximum amount of time transmitted data may
+/// remain unacknowledged before the connection is dropped. It is only supported on Linux.
+#[cfg(windows)]
+pub fn set_tcp_user_timeout<S: std::os::windows::io::AsRawSocket>(
+    socket: &S,
+    timeout: Duration,
+) -> ZResult<()> {
+    let _ = (socket, timeout);
+    bail!("TCP_USER_TIMEOUT is only supported on Linux")
+}
+
+/// Bind `socket` to the network interface `iface` (SO_BINDTODEVICE), so that its packets are only
+/// sent and received through that interface. It is only supported on Linux.
+#[cfg(unix)]
+pub fn set_bind_to_device<S: std::os::unix::io::AsRawFd>(socket: &S, iface: &str) -> ZResult<()> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        unsafe {
+            let ret = libc::setsockopt(
+                socket.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_BINDTODEVICE,
+                iface.as_ptr() as *const libc::c_void,
+                iface.len() as libc::socklen_t,
+            );
+            match ret {
+                0 => Ok(()),
+                err_code => bail!("setsockopt returned {}", err_code),
+            }
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    {
+        let _ = (socket, iface);
+        bail!("Binding to a device is only supported on Linux")
+    }
+}
+
+/// Bind `socket` to the network interface `iface` (SO_BINDTODEVICE), so that its packets are only
+/// sent and received through that interface. It is only supported on Linux.
+#[cfg(windows)]
+pub fn set_bind_to_device<S: std::os::windows::io::AsRawSocket>(
+    socket: &S,
+    iface: &str,
+) -> ZResult<()> {
+    let _ = (socket, iface);
+    bail!("Binding to a device is only supported on Linux")
+}
+
+/// Query the operating system for the IP path MTU towards `dst`, i.e. the largest IP packet
+/// (headers included) that can be sent to `dst` without being fragmented.
+/// Returns `None` when the platform does not expose it.
+pub fn get_path_mtu(dst: &SocketAddr) -> Option<u16> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        // Connecting a UDP socket does not send any packet but makes the kernel
+        // resolve the route, and hence the path MTU, towards the destination.
+        let bind = match dst {
+            SocketAddr::V4(_) => SocketAddr::from(([0, 0, 0, 0], 0)),
+            SocketAddr::V6(_) => SocketAddr::from(([0_u16; 8], 0)),
+        };
+        let socket = std::net::UdpSocket::bind(bind).ok()?;
+        socket.connect(dst).ok()?;
+        let (level, name) = match dst {
+            SocketAddr::V4(_) => (libc::IPPROTO_IP, libc::IP_MTU),
+            SocketAddr::V6(_) => (libc::IPPROTO_IPV6, libc::IPV6_MTU),
+        };
+
+        let mut mtu: libc::c_int = 0;
+        let mut len = std::mem::size_of_val(&mtu) as libc::socklen_t;
+        let ret = unsafe {
+            libc::getsockopt(
+                socket.as_raw_fd(),
+                level,
+                name,
+                &mut mtu as *mut libc::c_int as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if ret != 0 || mtu <= 0 {
+            return None;
+        }
+        Some(mtu.min(u16::MAX as libc::c_int) as u16)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = dst;
+        None
+    }
+}
+
 pub fn get_interface(name: &str) -> ZResult<Option<IpAddr>> {
     #[cfg(unix)]
     {
@@ -421,3 +764,81 @@ pub fn get_ipv6_ipaddrs() -> Vec<IpAddr> {
         .chain(priv_ipv4_addrs)
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn path_mtu() {
+        use super::get_path_mtu;
+
+        let mtu = get_path_mtu(&"127.0.0.1:7447".parse().unwrap()).unwrap();
+        // The MTU of an IPv4 link is at least 576 bytes
+        assert!(mtu >= 576);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn dscp() {
+        use super::set_dscp;
+        use std::os::unix::io::AsRawFd;
+
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        set_dscp(&socket, false, 46).unwrap();
+
+        let mut tos: libc::c_int = 0;
+        let mut len = std::mem::size_of_val(&tos) as libc::socklen_t;
+        let ret = unsafe {
+            libc::getsockopt(
+                socket.as_raw_fd(),
+                libc::IPPROTO_IP,
+                libc::IP_TOS,
+                &mut tos as *mut libc::c_int as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        assert_eq!(ret, 0);
+        // The DSCP fills the 6 most significant bits of the TOS byte
+        assert_eq!(tos, 46 << 2);
+
+        assert!(set_dscp(&socket, false, 64).is_err());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn tcp_keepalive() {
+        use super::{set_tcp_keepalive, set_tcp_user_timeout};
+        use std::time::Duration;
+
+        let socket = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        set_tcp_keepalive(
+            &socket,
+            Some(Duration::from_secs(5)),
+            Some(Duration::from_secs(1)),
+            Some(3),
+        )
+        .unwrap();
+        set_tcp_keepalive(&socket, None, None, None).unwrap();
+        set_tcp_user_timeout(&socket, Duration::from_millis(2500)).unwrap();
+    }
+
+    #[test]
+    fn resolve() {
+        use super::resolve;
+
+        async_std::task::block_on(async {
+            let addrs = resolve("127.0.0.1:7447").await.unwrap();
+            assert_eq!(addrs, vec!["127.0.0.1:7447".parse().unwrap()]);
+
+            let addrs = resolve("localhost:7447").await.unwrap();
+            assert!(addrs
+                .iter()
+                .all(|a| a.ip().is_loopback() && a.port() == 7447));
+            // The cached resolution is returned
+            assert_eq!(resolve("localhost:7447").await.unwrap(), addrs);
+
+            assert!(resolve("localhost").await.is_err());
+            assert!(resolve("localhost").await.is_err());
+        });
+    }
+}
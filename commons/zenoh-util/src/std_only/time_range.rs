@@ -334,7 +334,7 @@ fn parse_time_bound(s: &str, inclusive: bool) -> Result<TimeBound<TimeExpr>, ZEr
 ///  - 'h' => hours
 ///  - 'd' => days
 ///  - 'w' => weeks
-fn parse_duration(s: &str) -> Result<f64, ZError> {
+pub fn parse_duration(s: &str) -> Result<f64, ZError> {
     if s.is_empty() {
         bail!(
             r#"Invalid duration: "" (expected format: <f64> (in seconds) or <f64><unit>. Accepted units: u, ms, s, m, h, d or w.)"#
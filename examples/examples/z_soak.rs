@@ -0,0 +1,189 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use async_std::task::sleep;
+use clap::{App, Arg};
+use std::time::{Duration, Instant};
+use zenoh::config::{Config, EndPoint};
+use zenoh::prelude::r#async::*;
+use zenoh::prelude::sync::SyncResolve;
+use zenoh::SessionCounters;
+
+// The time left to the asynchronous cleanups (e.g. closing faces) before reporting a leak
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(5);
+const SETTLE_PERIOD: Duration = Duration::from_millis(100);
+const REPORT_PERIOD: Duration = Duration::from_secs(10);
+
+#[async_std::main]
+async fn main() {
+    // initiate logging
+    env_logger::init();
+
+    let (config, endpoint, duration, samples) = parse_args();
+
+    println!("Opening session...");
+    let session = zenoh::open(config).res().await.unwrap();
+
+    // The first round warms up the session (e.g. declares the resources of the admin space)
+    println!("Warming up...");
+    soak_round(&session, &endpoint, 0, samples).await;
+    let baseline = settle(&session, None).await;
+    println!("Baseline: {baseline:?}");
+
+    println!(
+        "Soaking{}...",
+        match duration {
+            Some(duration) => format!(" for {duration:?}"),
+            None => String::new(),
+        }
+    );
+    let start = Instant::now();
+    let mut report = Instant::now();
+    let mut round = 1;
+    while duration.map_or(true, |duration| start.elapsed() < duration) {
+        soak_round(&session, &endpoint, round, samples).await;
+        let counters = settle(&session, Some(baseline)).await;
+        if counters != baseline {
+            println!("Leak detected after {round} rounds:");
+            println!("  baseline: {baseline:?}");
+            println!("  current:  {counters:?}");
+            std::process::exit(1);
+        }
+        if report.elapsed() >= REPORT_PERIOD {
+            println!(
+                "[{:>6}s] {round} rounds without leak",
+                start.elapsed().as_secs()
+            );
+            report = Instant::now();
+        }
+        round += 1;
+    }
+    println!("No leak detected after {round} rounds");
+}
+
+// Wait for the counters of the session to return to the baseline, or to stabilize
+async fn settle(session: &Session, baseline: Option<SessionCounters>) -> SessionCounters {
+    let deadline = Instant::now() + SETTLE_TIMEOUT;
+    let mut counters = session.counters();
+    loop {
+        sleep(SETTLE_PERIOD).await;
+        let current = session.counters();
+        if Some(current) == baseline
+            || (baseline.is_none() && current == counters)
+            || Instant::now() >= deadline
+        {
+            return current;
+        }
+        counters = current;
+    }
+}
+
+// Declare and undeclare every kind of entity, open and close a session, and stream data
+async fn soak_round(session: &Session, endpoint: &EndPoint, round: usize, samples: usize) {
+    let prefix = format!("soak/{round}");
+    let key_expr = format!("{prefix}/data");
+
+    // Local entities
+    let subscriber = session.declare_subscriber(&key_expr).res().await.unwrap();
+    let publisher = session.declare_publisher(&key_expr).res().await.unwrap();
+    let reply_key_expr = KeyExpr::try_from(key_expr.clone()).unwrap().into_owned();
+    let queryable = session
+        .declare_queryable(&key_expr)
+        .callback(move |query| {
+            query
+                .reply(Ok(Sample::new(reply_key_expr.clone(), "reply")))
+                .res_sync()
+                .unwrap_or_else(|e| println!("Error sending reply: {e}"));
+        })
+        .res()
+        .await
+        .unwrap();
+    let token = session
+        .liveliness()
+        .declare_token(format!("{prefix}/token"))
+        .res()
+        .await
+        .unwrap();
+    let declared = session.declare_keyexpr(&key_expr).res().await.unwrap();
+
+    for i in 0..samples {
+        publisher.put(i.to_string()).res().await.unwrap();
+    }
+    for _ in 0..samples {
+        subscriber.recv_async().await.unwrap();
+    }
+    let replies = session.get(&key_expr).res().await.unwrap();
+    while replies.recv_async().await.is_ok() {}
+
+    session.undeclare(declared).res().await.unwrap();
+    token.undeclare().res().await.unwrap();
+    queryable.undeclare().res().await.unwrap();
+    publisher.undeclare().res().await.unwrap();
+    subscriber.undeclare().res().await.unwrap();
+
+    // A remote session
+    let mut config = Config::default();
+    config.connect.endpoints = vec![endpoint.clone()];
+    config.scouting.multicast.set_enabled(Some(false)).unwrap();
+    let remote = zenoh::open(config).res().await.unwrap();
+    let subscriber = remote.declare_subscriber(&key_expr).res().await.unwrap();
+    // Wait for the remote subscriber to be known before streaming
+    sleep(SETTLE_PERIOD).await;
+    for i in 0..samples {
+        session.put(&key_expr, i.to_string()).res().await.unwrap();
+    }
+    for _ in 0..samples {
+        subscriber.recv_async().await.unwrap();
+    }
+    subscriber.undeclare().res().await.unwrap();
+    remote.close().res().await.unwrap();
+}
+
+fn parse_args() -> (Config, EndPoint, Option<Duration>, usize) {
+    let args = App::new("zenoh soak test")
+        .arg(
+            Arg::from_usage(
+                "-l, --listen=[ENDPOINT] 'The endpoint the remote sessions connect to.'",
+            )
+            .default_value("tcp/127.0.0.1:7449"),
+        )
+        .arg(
+            Arg::from_usage("-d, --duration=[SECONDS] 'How long to soak, forever if 0.'")
+                .default_value("60"),
+        )
+        .arg(
+            Arg::from_usage("-n, --samples=[NUMBER] 'The samples streamed on each round.'")
+                .default_value("100"),
+        )
+        .arg(Arg::from_usage(
+            "-c, --config=[FILE]      'A configuration file.'",
+        ))
+        .get_matches();
+
+    let mut config = if let Some(conf_file) = args.value_of("config") {
+        Config::from_file(conf_file).unwrap()
+    } else {
+        Config::default()
+    };
+    let endpoint: EndPoint = args.value_of("listen").unwrap().parse().unwrap();
+    config.listen.endpoints = vec![endpoint.clone()];
+    config.scouting.multicast.set_enabled(Some(false)).unwrap();
+
+    let duration = match args.value_of("duration").unwrap().parse().unwrap() {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    };
+    let samples: usize = args.value_of("samples").unwrap().parse().unwrap();
+
+    (config, endpoint, duration, samples)
+}
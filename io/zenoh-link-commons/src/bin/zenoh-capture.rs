@@ -0,0 +1,101 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! Pretty-prints the transport messages of a capture file written by a zenoh application
+//! run with the `ZENOH_CAPTURE_FILE` environment variable.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use zenoh_buffers::reader::{HasReader, Reader};
+use zenoh_codec::{RCodec, Zenoh060};
+use zenoh_link_commons::capture::{CaptureDirection, CaptureRecord, CAPTURE_MAGIC};
+use zenoh_protocol::transport::TransportMessage;
+
+fn print_frame(record: &CaptureRecord, frame: &[u8]) {
+    let arrow = match record.direction {
+        CaptureDirection::Tx => "->",
+        CaptureDirection::Rx => "<-",
+    };
+    println!(
+        "[{}.{:09}] {} {} {} ({} bytes)",
+        record.timestamp.as_secs(),
+        record.timestamp.subsec_nanos(),
+        record.src,
+        arrow,
+        record.dst,
+        frame.len()
+    );
+    let codec = Zenoh060::default();
+    let mut reader = frame.reader();
+    while reader.can_read() {
+        let res: Result<TransportMessage, _> = codec.read(&mut reader);
+        match res {
+            Ok(msg) => println!("    {:?}", msg),
+            Err(_) => {
+                println!("    <undecodable bytes: {:02x?}>", frame);
+                break;
+            }
+        }
+    }
+}
+
+fn main() {
+    let path = match std::env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: zenoh-capture <CAPTURE_FILE>");
+            std::process::exit(1);
+        }
+    };
+    let mut reader = match File::open(&path) {
+        Ok(file) => BufReader::new(file),
+        Err(e) => {
+            eprintln!("Unable to open {}: {}", path, e);
+            std::process::exit(1);
+        }
+    };
+    let mut magic = [0u8; 8];
+    if reader.read_exact(&mut magic).is_err() || &magic != CAPTURE_MAGIC {
+        eprintln!("{} is not a zenoh capture file", path);
+        std::process::exit(1);
+    }
+
+    // the bytes of streamed links are captured as they are read: reassemble their frames
+    let mut streams: HashMap<(String, String, CaptureDirection), Vec<u8>> = HashMap::new();
+    loop {
+        let record = match CaptureRecord::read(&mut reader) {
+            Ok(Some(record)) => record,
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("Truncated capture file {}: {}", path, e);
+                std::process::exit(1);
+            }
+        };
+        if !record.is_streamed {
+            print_frame(&record, &record.bytes);
+            continue;
+        }
+        let key = (record.src.clone(), record.dst.clone(), record.direction);
+        let stream = streams.entry(key).or_default();
+        stream.extend_from_slice(&record.bytes);
+        while stream.len() >= 2 {
+            let len = u16::from_le_bytes([stream[0], stream[1]]) as usize;
+            if stream.len() < 2 + len {
+                break;
+            }
+            let frame: Vec<u8> = stream.drain(..2 + len).skip(2).collect();
+            print_frame(&record, &frame);
+        }
+    }
+}
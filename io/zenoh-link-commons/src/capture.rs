@@ -0,0 +1,256 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! Capture of the raw bytes exchanged on the unicast links.
+//!
+//! When the `ZENOH_CAPTURE_FILE` environment variable is set, every byte written to or read from
+//! a unicast link is appended to that file, together with a timestamp and the link it went through.
+//! The `zenoh-capture` tool decodes such files into transport messages.
+//!
+//! A capture file starts with the [`CAPTURE_MAGIC`] bytes, followed by records made of
+//! (all integers being little endian):
+//! - the time since the UNIX epoch, in nanoseconds (`u64`);
+//! - the direction: 0 for received bytes, 1 for sent bytes (`u8`);
+//! - whether the link is streamed, i.e. frames are prefixed by their 16 bits length (`u8`);
+//! - the length (`u16`) and the UTF-8 bytes of the source locator;
+//! - the length (`u16`) and the UTF-8 bytes of the destination locator;
+//! - the length (`u32`) and the captured bytes.
+use super::{LinkUnicast, LinkUnicastTrait};
+use async_trait::async_trait;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use zenoh_protocol::core::Locator;
+use zenoh_result::ZResult;
+
+pub const CAPTURE_ENV: &str = "ZENOH_CAPTURE_FILE";
+pub const CAPTURE_MAGIC: &[u8; 8] = b"ZCAPTUR1";
+
+lazy_static::lazy_static! {
+    static ref CAPTURE: Option<Mutex<BufWriter<File>>> = open_capture();
+}
+
+fn open_capture() -> Option<Mutex<BufWriter<File>>> {
+    let path = std::env::var_os(CAPTURE_ENV)?;
+    let res = File::create(&path).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        writer.write_all(CAPTURE_MAGIC)?;
+        Ok(writer)
+    });
+    match res {
+        Ok(writer) => {
+            log::warn!("Capturing the traffic of unicast links to {:?}", path);
+            Some(Mutex::new(writer))
+        }
+        Err(e) => {
+            log::error!("Unable to open capture file {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CaptureDirection {
+    Rx,
+    Tx,
+}
+
+/// Some bytes sent or received on a link.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaptureRecord {
+    /// The time since the UNIX epoch.
+    pub timestamp: Duration,
+    pub direction: CaptureDirection,
+    pub is_streamed: bool,
+    pub src: String,
+    pub dst: String,
+    pub bytes: Vec<u8>,
+}
+
+impl CaptureRecord {
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&(self.timestamp.as_nanos() as u64).to_le_bytes())?;
+        writer.write_all(&[self.direction as u8, self.is_streamed as u8])?;
+        for locator in [&self.src, &self.dst] {
+            writer.write_all(&(locator.len() as u16).to_le_bytes())?;
+            writer.write_all(locator.as_bytes())?;
+        }
+        writer.write_all(&(self.bytes.len() as u32).to_le_bytes())?;
+        writer.write_all(&self.bytes)
+    }
+
+    /// Reads the next record, or `None` at the end of the capture.
+    pub fn read<R: Read>(reader: &mut R) -> io::Result<Option<CaptureRecord>> {
+        let mut timestamp = [0u8; 8];
+        match reader.read_exact(&mut timestamp) {
+            Ok(()) => (),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let mut flags = [0u8; 2];
+        reader.read_exact(&mut flags)?;
+        let direction = match flags[0] {
+            0 => CaptureDirection::Rx,
+            1 => CaptureDirection::Tx,
+            d => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid capture direction: {}", d),
+                ))
+            }
+        };
+        let mut read_string = || -> io::Result<String> {
+            let mut len = [0u8; 2];
+            reader.read_exact(&mut len)?;
+            let mut bytes = vec![0u8; u16::from_le_bytes(len) as usize];
+            reader.read_exact(&mut bytes)?;
+            String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        };
+        let src = read_string()?;
+        let dst = read_string()?;
+        let mut len = [0u8; 4];
+        reader.read_exact(&mut len)?;
+        let mut bytes = vec![0u8; u32::from_le_bytes(len) as usize];
+        reader.read_exact(&mut bytes)?;
+        Ok(Some(CaptureRecord {
+            timestamp: Duration::from_nanos(u64::from_le_bytes(timestamp)),
+            direction,
+            is_streamed: flags[1] != 0,
+            src,
+            dst,
+            bytes,
+        }))
+    }
+}
+
+/// Wraps `link` so that its traffic is captured, if a capture file is configured.
+pub fn capture_unicast(link: LinkUnicast) -> LinkUnicast {
+    if CAPTURE.is_some() {
+        LinkUnicast(Arc::new(CaptureLinkUnicast { inner: link.0 }))
+    } else {
+        link
+    }
+}
+
+struct CaptureLinkUnicast {
+    inner: Arc<dyn LinkUnicastTrait>,
+}
+
+impl CaptureLinkUnicast {
+    fn record(&self, direction: CaptureDirection, bytes: &[u8]) {
+        if let Some(capture) = CAPTURE.as_ref() {
+            let record = CaptureRecord {
+                timestamp: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default(),
+                direction,
+                is_streamed: self.inner.is_streamed(),
+                src: self.inner.get_src().to_string(),
+                dst: self.inner.get_dst().to_string(),
+                bytes: bytes.to_vec(),
+            };
+            let mut writer = match capture.lock() {
+                Ok(writer) => writer,
+                Err(e) => e.into_inner(),
+            };
+            if let Err(e) = record.write(&mut *writer).and_then(|_| writer.flush()) {
+                log::warn!("Unable to write capture record: {}", e);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl LinkUnicastTrait for CaptureLinkUnicast {
+    fn get_mtu(&self) -> u16 {
+        self.inner.get_mtu()
+    }
+
+    fn get_src(&self) -> &Locator {
+        self.inner.get_src()
+    }
+
+    fn get_dst(&self) -> &Locator {
+        self.inner.get_dst()
+    }
+
+    fn is_reliable(&self) -> bool {
+        self.inner.is_reliable()
+    }
+
+    fn is_streamed(&self) -> bool {
+        self.inner.is_streamed()
+    }
+
+    fn get_identity(&self) -> Option<String> {
+        self.inner.get_identity()
+    }
+
+    fn set_dscp(&self, dscp: u8) -> ZResult<()> {
+        self.inner.set_dscp(dscp)
+    }
+
+    async fn write(&self, buffer: &[u8]) -> ZResult<usize> {
+        let n = self.inner.write(buffer).await?;
+        self.record(CaptureDirection::Tx, &buffer[..n]);
+        Ok(n)
+    }
+
+    async fn write_all(&self, buffer: &[u8]) -> ZResult<()> {
+        self.inner.write_all(buffer).await?;
+        self.record(CaptureDirection::Tx, buffer);
+        Ok(())
+    }
+
+    async fn read(&self, buffer: &mut [u8]) -> ZResult<usize> {
+        let n = self.inner.read(buffer).await?;
+        self.record(CaptureDirection::Rx, &buffer[..n]);
+        Ok(n)
+    }
+
+    async fn read_exact(&self, buffer: &mut [u8]) -> ZResult<()> {
+        self.inner.read_exact(buffer).await?;
+        self.record(CaptureDirection::Rx, buffer);
+        Ok(())
+    }
+
+    async fn close(&self) -> ZResult<()> {
+        self.inner.close().await
+    }
+}
+
+#[test]
+fn capture_record() {
+    let record = CaptureRecord {
+        timestamp: Duration::from_nanos(1_675_000_000_123_456_789),
+        direction: CaptureDirection::Tx,
+        is_streamed: true,
+        src: "tcp/127.0.0.1:40000".into(),
+        dst: "tcp/127.0.0.1:7447".into(),
+        bytes: vec![3, 0, 1, 2, 3],
+    };
+    let mut buffer = vec![];
+    record.write(&mut buffer).unwrap();
+    record.write(&mut buffer).unwrap();
+
+    let mut reader = buffer.as_slice();
+    assert_eq!(
+        CaptureRecord::read(&mut reader).unwrap(),
+        Some(record.clone())
+    );
+    assert_eq!(CaptureRecord::read(&mut reader).unwrap(), Some(record));
+    assert_eq!(CaptureRecord::read(&mut reader).unwrap(), None);
+    assert!(CaptureRecord::read(&mut &buffer[..10]).is_err());
+}
@@ -17,6 +17,10 @@
 //! This crate is intended for Zenoh's internal use.
 //!
 //! [Click here for Zenoh's documentation](../zenoh/index.html)
+#[cfg(feature = "capture")]
+pub mod capture;
+pub mod socket;
+
 use async_trait::async_trait;
 use serde::Serialize;
 use std::{
@@ -51,6 +55,7 @@ pub struct Link {
     pub mtu: u16,
     pub is_reliable: bool,
     pub is_streamed: bool,
+    pub identity: Option<String>,
 }
 
 #[async_trait]
@@ -78,6 +83,7 @@ impl From<&LinkUnicast> for Link {
             mtu: link.get_mtu(),
             is_reliable: link.is_reliable(),
             is_streamed: link.is_streamed(),
+            identity: link.get_identity(),
         }
     }
 }
@@ -97,6 +103,7 @@ impl From<&LinkMulticast> for Link {
             mtu: link.get_mtu(),
             is_reliable: link.is_reliable(),
             is_streamed: false,
+            identity: None,
         }
     }
 }
@@ -141,6 +148,16 @@ pub trait LinkUnicastTrait: Send + Sync {
     fn get_dst(&self) -> &Locator;
     fn is_reliable(&self) -> bool;
     fn is_streamed(&self) -> bool;
+    /// The identity the remote end has been authenticated with at link level, if any
+    /// (e.g. the SPIFFE ID of its certificate).
+    fn get_identity(&self) -> Option<String> {
+        None
+    }
+    /// Mark the IP packets sent from now on with the given DiffServ code point (DSCP).
+    /// Links that are not carried over IP simply ignore it.
+    fn set_dscp(&self, _dscp: u8) -> ZResult<()> {
+        Ok(())
+    }
     async fn write(&self, buffer: &[u8]) -> ZResult<usize>;
     async fn write_all(&self, buffer: &[u8]) -> ZResult<()>;
     async fn read(&self, buffer: &mut [u8]) -> ZResult<usize>;
@@ -0,0 +1,305 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! Socket options of the IP based links, configured per endpoint,
+//! e.g. `tcp/192.168.1.1:7447#dscp=46;so_priority=6`.
+use async_std::net::TcpStream;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+use std::time::Duration;
+use zenoh_protocol::core::EndPoint;
+use zenoh_result::{bail, zerror, ZResult};
+
+/// The endpoint configuration key setting the DiffServ code point (0..=63) of the IP packets.
+/// It is overridden by the per-priority marks when `transport.qos.dscp` is enabled.
+pub const DSCP_KEY: &str = "dscp";
+/// The endpoint configuration key setting the raw IPv4 TOS byte, or IPv6 traffic class, of the
+/// IP packets. It is mutually exclusive with [`DSCP_KEY`].
+pub const TOS_KEY: &str = "tos";
+/// The endpoint configuration key setting the Linux SO_PRIORITY of the socket.
+pub const SO_PRIORITY_KEY: &str = "so_priority";
+/// The endpoint configuration key binding the outgoing links to a network interface, e.g.
+/// `tcp/10.0.0.1:7447#iface=eth1`. It is only supported on Linux.
+pub const IFACE_KEY: &str = "iface";
+/// The endpoint configuration key selecting the source address of the outgoing links, e.g.
+/// `tcp/10.0.0.1:7447#src=10.0.0.5`.
+pub const SRC_KEY: &str = "src";
+/// The endpoint configuration key setting the idle time in seconds before the first TCP keepalive
+/// probe is sent. Setting any of the keepalive keys enables the TCP keepalive probes.
+pub const KEEPALIVE_IDLE_KEY: &str = "keepalive_idle";
+/// The endpoint configuration key setting the interval in seconds between TCP keepalive probes.
+pub const KEEPALIVE_INTERVAL_KEY: &str = "keepalive_interval";
+/// The endpoint configuration key setting the number of unanswered TCP keepalive probes after
+/// which the connection is dropped.
+pub const KEEPALIVE_COUNT_KEY: &str = "keepalive_count";
+/// The endpoint configuration key setting the TCP_USER_TIMEOUT in milliseconds, i.e. the maximum
+/// amount of time transmitted data may remain unacknowledged before the connection is dropped.
+pub const USER_TIMEOUT_KEY: &str = "user_timeout";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SocketConfig {
+    pub tos: Option<u8>,
+    pub so_priority: Option<u32>,
+    pub iface: Option<String>,
+    pub src: Option<IpAddr>,
+}
+
+fn parse<T: FromStr>(endpoint: &EndPoint, key: &str) -> ZResult<Option<T>> {
+    endpoint
+        .config()
+        .get(key)
+        .map(|v| {
+            v.parse::<T>()
+                .map_err(|_| zerror!("Invalid {} value in {}: {}", key, endpoint, v).into())
+        })
+        .transpose()
+}
+
+impl SocketConfig {
+    pub fn from_endpoint(endpoint: &EndPoint) -> ZResult<Self> {
+        let dscp = parse::<u8>(endpoint, DSCP_KEY)?;
+        let tos = parse::<u8>(endpoint, TOS_KEY)?;
+        let tos = match (dscp, tos) {
+            (Some(_), Some(_)) => {
+                bail!(
+                    "Only one of {} and {} can be set in {}",
+                    DSCP_KEY,
+                    TOS_KEY,
+                    endpoint
+                )
+            }
+            (Some(dscp), None) if dscp > 0x3f => {
+                bail!("Invalid {} value in {}: {}", DSCP_KEY, endpoint, dscp)
+            }
+            (Some(dscp), None) => Some(dscp << 2),
+            (None, tos) => tos,
+        };
+        let so_priority = parse::<u32>(endpoint, SO_PRIORITY_KEY)?;
+        let iface = endpoint.config().get(IFACE_KEY).map(|s| s.to_string());
+        let src = parse::<IpAddr>(endpoint, SRC_KEY)?;
+
+        Ok(Self {
+            tos,
+            so_priority,
+            iface,
+            src,
+        })
+    }
+
+    /// Whether the outgoing links are bound to an interface or to a source address.
+    pub fn is_bound(&self) -> bool {
+        self.iface.is_some() || self.src.is_some()
+    }
+
+    /// The local address to bind the outgoing links towards `dst` to.
+    pub fn bind_addr(&self, dst: &SocketAddr) -> ZResult<SocketAddr> {
+        let ip = match (self.src, dst) {
+            (Some(src), dst) if src.is_ipv4() != dst.is_ipv4() => {
+                bail!(
+                    "The source address {} and the destination address {} are not of the same IP version",
+                    src,
+                    dst
+                )
+            }
+            (Some(src), _) => src,
+            (None, SocketAddr::V4(_)) => Ipv4Addr::UNSPECIFIED.into(),
+            (None, SocketAddr::V6(_)) => Ipv6Addr::UNSPECIFIED.into(),
+        };
+        Ok(SocketAddr::new(ip, 0))
+    }
+
+    #[cfg(unix)]
+    pub fn apply<S: std::os::unix::io::AsRawFd>(&self, socket: &S, is_ipv6: bool) -> ZResult<()> {
+        if let Some(tos) = self.tos {
+            zenoh_util::net::set_tos(socket, is_ipv6, tos)?;
+        }
+        if let Some(priority) = self.so_priority {
+            zenoh_util::net::set_so_priority(socket, priority)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    pub fn apply<S: std::os::windows::io::AsRawSocket>(
+        &self,
+        socket: &S,
+        is_ipv6: bool,
+    ) -> ZResult<()> {
+        if let Some(tos) = self.tos {
+            zenoh_util::net::set_tos(socket, is_ipv6, tos)?;
+        }
+        if self.so_priority.is_some() {
+            bail!("{} is only supported on Linux", SO_PRIORITY_KEY);
+        }
+        Ok(())
+    }
+}
+
+/// The socket options of the TCP based links, e.g.
+/// `tcp/192.168.1.1:7447#keepalive_idle=5;keepalive_interval=1;keepalive_count=3`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TcpSocketConfig {
+    pub socket: SocketConfig,
+    pub keepalive_idle: Option<Duration>,
+    pub keepalive_interval: Option<Duration>,
+    pub keepalive_count: Option<u32>,
+    pub user_timeout: Option<Duration>,
+}
+
+impl TcpSocketConfig {
+    pub fn from_endpoint(endpoint: &EndPoint) -> ZResult<Self> {
+        Ok(Self {
+            socket: SocketConfig::from_endpoint(endpoint)?,
+            keepalive_idle: parse::<u64>(endpoint, KEEPALIVE_IDLE_KEY)?.map(Duration::from_secs),
+            keepalive_interval: parse::<u64>(endpoint, KEEPALIVE_INTERVAL_KEY)?
+                .map(Duration::from_secs),
+            keepalive_count: parse::<u32>(endpoint, KEEPALIVE_COUNT_KEY)?,
+            user_timeout: parse::<u64>(endpoint, USER_TIMEOUT_KEY)?.map(Duration::from_millis),
+        })
+    }
+
+    /// Open a TCP connection towards `dst`, bound to the configured interface and source address.
+    /// The other socket options still have to be applied on the returned stream.
+    pub async fn connect(&self, dst: &SocketAddr) -> ZResult<TcpStream> {
+        if !self.socket.is_bound() {
+            return TcpStream::connect(dst).await.map_err(|e| zerror!(e).into());
+        }
+
+        let socket = Socket::new(Domain::for_address(*dst), Type::STREAM, Some(Protocol::TCP))
+            .map_err(|e| zerror!(e))?;
+        if let Some(iface) = self.socket.iface.as_ref() {
+            zenoh_util::net::set_bind_to_device(&socket, iface)?;
+        }
+        socket
+            .bind(&self.socket.bind_addr(dst)?.into())
+            .map_err(|e| zerror!(e))?;
+
+        // The socket is in blocking mode until it is handed over to async-std
+        let dst = *dst;
+        let stream = async_std::task::spawn_blocking(move || {
+            socket
+                .connect(&dst.into())
+                .map(|_| std::net::TcpStream::from(socket))
+        })
+        .await
+        .map_err(|e| zerror!(e))?;
+        Ok(stream.into())
+    }
+
+    fn is_keepalive(&self) -> bool {
+        self.keepalive_idle.is_some()
+            || self.keepalive_interval.is_some()
+            || self.keepalive_count.is_some()
+    }
+
+    #[cfg(unix)]
+    pub fn apply<S: std::os::unix::io::AsRawFd>(&self, socket: &S, is_ipv6: bool) -> ZResult<()> {
+        self.socket.apply(socket, is_ipv6)?;
+        if self.is_keepalive() {
+            zenoh_util::net::set_tcp_keepalive(
+                socket,
+                self.keepalive_idle,
+                self.keepalive_interval,
+                self.keepalive_count,
+            )?;
+        }
+        if let Some(timeout) = self.user_timeout {
+            zenoh_util::net::set_tcp_user_timeout(socket, timeout)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    pub fn apply<S: std::os::windows::io::AsRawSocket>(
+        &self,
+        socket: &S,
+        is_ipv6: bool,
+    ) -> ZResult<()> {
+        self.socket.apply(socket, is_ipv6)?;
+        if self.is_keepalive() {
+            zenoh_util::net::set_tcp_keepalive(
+                socket,
+                self.keepalive_idle,
+                self.keepalive_interval,
+                self.keepalive_count,
+            )?;
+        }
+        if let Some(timeout) = self.user_timeout {
+            zenoh_util::net::set_tcp_user_timeout(socket, timeout)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn socket_config() {
+        let c = |s: &str| SocketConfig::from_endpoint(&EndPoint::try_from(s.to_string()).unwrap());
+
+        assert_eq!(c("tcp/127.0.0.1:7447").unwrap(), SocketConfig::default());
+        assert_eq!(c("tcp/127.0.0.1:7447#dscp=46").unwrap().tos, Some(0xb8));
+        assert_eq!(c("tcp/127.0.0.1:7447#tos=16").unwrap().tos, Some(16));
+        assert_eq!(
+            c("udp/127.0.0.1:7447#so_priority=6").unwrap().so_priority,
+            Some(6)
+        );
+        assert!(c("tcp/127.0.0.1:7447#dscp=64").is_err());
+        assert!(c("tcp/127.0.0.1:7447#dscp=46;tos=16").is_err());
+        assert!(c("tcp/127.0.0.1:7447#so_priority=high").is_err());
+
+        let config = c("tcp/127.0.0.1:7447#iface=eth1;src=10.0.0.5").unwrap();
+        assert_eq!(config.iface.as_deref(), Some("eth1"));
+        assert_eq!(config.src, Some("10.0.0.5".parse().unwrap()));
+        assert!(config.is_bound());
+        assert_eq!(
+            config.bind_addr(&"10.0.0.1:7447".parse().unwrap()).unwrap(),
+            "10.0.0.5:0".parse().unwrap()
+        );
+        assert!(config.bind_addr(&"[::1]:7447".parse().unwrap()).is_err());
+        assert_eq!(
+            SocketConfig::default()
+                .bind_addr(&"[::1]:7447".parse().unwrap())
+                .unwrap(),
+            "[::]:0".parse().unwrap()
+        );
+        assert!(c("tcp/127.0.0.1:7447#src=eth1").is_err());
+    }
+
+    #[test]
+    fn tcp_socket_config() {
+        let c =
+            |s: &str| TcpSocketConfig::from_endpoint(&EndPoint::try_from(s.to_string()).unwrap());
+
+        let config = c("tcp/127.0.0.1:7447").unwrap();
+        assert_eq!(config, TcpSocketConfig::default());
+        assert!(!config.is_keepalive());
+
+        let config =
+            c("tcp/127.0.0.1:7447#dscp=46;keepalive_idle=5;keepalive_count=3;user_timeout=2500")
+                .unwrap();
+        assert_eq!(config.socket.tos, Some(0xb8));
+        assert_eq!(config.keepalive_idle, Some(Duration::from_secs(5)));
+        assert_eq!(config.keepalive_interval, None);
+        assert_eq!(config.keepalive_count, Some(3));
+        assert_eq!(config.user_timeout, Some(Duration::from_millis(2500)));
+        assert!(config.is_keepalive());
+
+        assert!(c("tcp/127.0.0.1:7447#keepalive_interval=-1").is_err());
+    }
+}
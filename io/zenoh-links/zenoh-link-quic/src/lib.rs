@@ -17,15 +17,15 @@
 //! This crate is intended for Zenoh's internal use.
 //!
 //! [Click here for Zenoh's documentation](../zenoh/index.html)
-use async_std::net::ToSocketAddrs;
 use async_trait::async_trait;
 use config::{
-    TLS_ROOT_CA_CERTIFICATE_FILE, TLS_SERVER_CERTIFICATE_FILE, TLS_SERVER_PRIVATE_KEY_FILE,
+    TLS_KEYLOG, TLS_ROOT_CA_CERTIFICATE_FILE, TLS_SERVER_CERTIFICATE_FILE,
+    TLS_SERVER_PRIVATE_KEY_FILE,
 };
 use std::net::SocketAddr;
 use webpki::DnsNameRef;
 use zenoh_cfg_properties::Properties;
-use zenoh_config::{Config, Locator};
+use zenoh_config::{Config, Locator, ZN_FALSE, ZN_TRUE};
 use zenoh_core::zconfigurable;
 use zenoh_link_commons::{ConfigurationInspector, LocatorInspector};
 use zenoh_protocol::core::endpoint::Address;
@@ -83,6 +83,12 @@ impl ConfigurationInspector<Config> for QuicConfigurator {
                 tls_server_certificate.into(),
             );
         }
+        if let Some(tls_keylog) = c.keylog() {
+            match tls_keylog {
+                true => properties.insert(TLS_KEYLOG.into(), ZN_TRUE.into()),
+                false => properties.insert(TLS_KEYLOG.into(), ZN_FALSE.into()),
+            };
+        }
 
         Ok(properties)
     }
@@ -112,10 +118,17 @@ pub mod config {
 
     pub const TLS_SERVER_CERTIFICATE_FILE: &str = ZN_TLS_SERVER_CERTIFICATE_STR;
     pub const TLS_SERVER_CERTIFICATE_RAW: &str = "tls_server_certificate_raw";
+
+    pub const TLS_KEYLOG: &str = "tls_keylog";
+    pub const TLS_KEYLOG_DEFAULT: &str = ZN_FALSE;
 }
 
 async fn get_quic_addr(address: &Address<'_>) -> ZResult<SocketAddr> {
-    match address.as_str().to_socket_addrs().await?.next() {
+    match zenoh_util::net::resolve(address.as_str())
+        .await?
+        .into_iter()
+        .next()
+    {
         Some(addr) => Ok(addr),
         None => bail!("Couldn't resolve QUIC locator address: {}", address),
     }
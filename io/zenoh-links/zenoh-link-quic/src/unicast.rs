@@ -33,7 +33,7 @@ use zenoh_core::{zasynclock, zread, zwrite};
 use zenoh_link_commons::{
     LinkManagerUnicastTrait, LinkUnicast, LinkUnicastTrait, NewLinkChannelSender,
 };
-use zenoh_protocol::core::{EndPoint, Locator};
+use zenoh_protocol::core::{endpoint::Config, EndPoint, Locator};
 use zenoh_result::{bail, zerror, ZResult};
 use zenoh_sync::Signal;
 
@@ -264,6 +264,9 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastQuic {
             .with_root_certificates(root_cert_store)
             .with_no_client_auth();
         client_crypto.alpn_protocols = ALPN_QUIC_HTTP.iter().map(|&x| x.into()).collect();
+        if is_keylog(&epconf)? {
+            client_crypto.key_log = Arc::new(rustls::KeyLogFile::new());
+        }
 
         let ip_addr: IpAddr = if addr.is_ipv4() {
             Ipv4Addr::UNSPECIFIED.into()
@@ -356,6 +359,9 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastQuic {
             .with_no_client_auth()
             .with_single_cert(certificates, private_key)?;
         server_crypto.alpn_protocols = ALPN_QUIC_HTTP.iter().map(|&x| x.into()).collect();
+        if is_keylog(&epconf)? {
+            server_crypto.key_log = Arc::new(rustls::KeyLogFile::new());
+        }
         let mut server_config = quinn::ServerConfig::with_crypto(Arc::new(server_crypto));
 
         // We do not accept unidireactional streams.
@@ -551,3 +557,17 @@ async fn accept_task(
 
     Ok(())
 }
+
+// Whether the TLS secrets are logged in the file pointed by the SSLKEYLOGFILE environment variable
+fn is_keylog(config: &Config<'_>) -> ZResult<bool> {
+    let keylog: bool = config
+        .get(TLS_KEYLOG)
+        .unwrap_or(TLS_KEYLOG_DEFAULT)
+        .parse()?;
+    if keylog {
+        log::warn!(
+            "TLS secrets are logged in the SSLKEYLOGFILE: the TLS traffic can be decrypted!"
+        );
+    }
+    Ok(keylog)
+}
@@ -17,13 +17,12 @@
 //! This crate is intended for Zenoh's internal use.
 //!
 //! [Click here for Zenoh's documentation](../zenoh/index.html)
-use async_std::net::ToSocketAddrs;
 use async_trait::async_trait;
 use std::net::SocketAddr;
 use zenoh_core::zconfigurable;
 use zenoh_link_commons::LocatorInspector;
 use zenoh_protocol::core::{endpoint::Address, Locator};
-use zenoh_result::{zerror, ZResult};
+use zenoh_result::ZResult;
 
 mod unicast;
 pub use unicast::*;
@@ -65,11 +64,9 @@ zconfigurable! {
 }
 
 pub async fn get_tcp_addrs(address: Address<'_>) -> ZResult<impl Iterator<Item = SocketAddr>> {
-    let iter = address
-        .as_str()
-        .to_socket_addrs()
-        .await
-        .map_err(|e| zerror!("{}", e))?
+    let iter = zenoh_util::net::resolve(address.as_str())
+        .await?
+        .into_iter()
         .filter(|x| !x.ip().is_multicast());
     Ok(iter)
 }
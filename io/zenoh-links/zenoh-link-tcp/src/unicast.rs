@@ -25,7 +25,8 @@ use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use zenoh_core::{zread, zwrite};
 use zenoh_link_commons::{
-    LinkManagerUnicastTrait, LinkUnicast, LinkUnicastTrait, NewLinkChannelSender,
+    socket::TcpSocketConfig, LinkManagerUnicastTrait, LinkUnicast, LinkUnicastTrait,
+    NewLinkChannelSender,
 };
 use zenoh_protocol::core::{EndPoint, Locator};
 use zenoh_result::{bail, zerror, Error as ZError, ZResult};
@@ -48,7 +49,7 @@ pub struct LinkUnicastTcp {
 }
 
 impl LinkUnicastTcp {
-    fn new(socket: TcpStream, src_addr: SocketAddr, dst_addr: SocketAddr) -> LinkUnicastTcp {
+    pub fn new(socket: TcpStream, src_addr: SocketAddr, dst_addr: SocketAddr) -> LinkUnicastTcp {
         // Set the TCP nodelay option
         if let Err(err) = socket.set_nodelay(true) {
             log::warn!(
@@ -153,6 +154,11 @@ impl LinkUnicastTrait for LinkUnicastTcp {
     fn is_streamed(&self) -> bool {
         true
     }
+
+    fn set_dscp(&self, dscp: u8) -> ZResult<()> {
+        zenoh_util::net::set_dscp(&self.socket, self.src_addr.is_ipv6(), dscp)
+            .map_err(|e| zerror!("Can not set DSCP {} on TCP link {}: {}", dscp, self, e).into())
+    }
 }
 
 impl Drop for LinkUnicastTcp {
@@ -222,8 +228,10 @@ impl LinkManagerUnicastTcp {
     async fn new_link_inner(
         &self,
         dst_addr: &SocketAddr,
+        config: &TcpSocketConfig,
     ) -> ZResult<(TcpStream, SocketAddr, SocketAddr)> {
-        let stream = TcpStream::connect(dst_addr)
+        let stream = config
+            .connect(dst_addr)
             .await
             .map_err(|e| zerror!("{}: {}", dst_addr, e))?;
 
@@ -231,6 +239,10 @@ impl LinkManagerUnicastTcp {
             .local_addr()
             .map_err(|e| zerror!("{}: {}", dst_addr, e))?;
 
+        config
+            .apply(&stream, src_addr.is_ipv6())
+            .map_err(|e| zerror!("{}: {}", dst_addr, e))?;
+
         let dst_addr = stream
             .peer_addr()
             .map_err(|e| zerror!("{}: {}", dst_addr, e))?;
@@ -256,10 +268,11 @@ impl LinkManagerUnicastTcp {
 impl LinkManagerUnicastTrait for LinkManagerUnicastTcp {
     async fn new_link(&self, endpoint: EndPoint) -> ZResult<LinkUnicast> {
         let dst_addrs = get_tcp_addrs(endpoint.address()).await?;
+        let config = TcpSocketConfig::from_endpoint(&endpoint)?;
 
         let mut errs: Vec<ZError> = vec![];
         for da in dst_addrs {
-            match self.new_link_inner(&da).await {
+            match self.new_link_inner(&da, &config).await {
                 Ok((stream, src_addr, dst_addr)) => {
                     let link = Arc::new(LinkUnicastTcp::new(stream, src_addr, dst_addr));
                     return Ok(LinkUnicast(link));
@@ -283,6 +296,7 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTcp {
 
     async fn new_listener(&self, mut endpoint: EndPoint) -> ZResult<Locator> {
         let addrs = get_tcp_addrs(endpoint.address()).await?;
+        let config = TcpSocketConfig::from_endpoint(&endpoint)?;
 
         let mut errs: Vec<ZError> = vec![];
         for da in addrs {
@@ -307,7 +321,7 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTcp {
                     let c_addr = local_addr;
                     let handle = task::spawn(async move {
                         // Wait for the accept loop to terminate
-                        let res = accept_task(socket, c_active, c_signal, c_manager).await;
+                        let res = accept_task(socket, config, c_active, c_signal, c_manager).await;
                         zwrite!(c_listeners).remove(&c_addr);
                         res
                     });
@@ -413,6 +427,7 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTcp {
 
 async fn accept_task(
     socket: TcpListener,
+    config: TcpSocketConfig,
     active: Arc<AtomicBool>,
     signal: Signal,
     manager: NewLinkChannelSender,
@@ -460,6 +475,9 @@ async fn accept_task(
         };
 
         log::debug!("Accepted TCP connection on {:?}: {:?}", src_addr, dst_addr);
+        if let Err(e) = config.apply(&stream, src_addr.is_ipv6()) {
+            log::warn!("Can not configure TCP connection {:?}: {}", dst_addr, e);
+        }
         // Create the new link object
         let link = Arc::new(LinkUnicastTcp::new(stream, src_addr, dst_addr));
 
@@ -19,11 +19,12 @@
 //! [Click here for Zenoh's documentation](../zenoh/index.html)
 use std::{convert::TryFrom, net::SocketAddr};
 
-use async_std::net::ToSocketAddrs;
 use async_trait::async_trait;
 use config::{
-    TLS_CLIENT_AUTH, TLS_CLIENT_CERTIFICATE_FILE, TLS_CLIENT_PRIVATE_KEY_FILE,
-    TLS_ROOT_CA_CERTIFICATE_FILE, TLS_SERVER_CERTIFICATE_FILE, TLS_SERVER_PRIVATE_KEY_FILE,
+    TLS_CLIENT_AUTH, TLS_CLIENT_CERTIFICATE_FILE, TLS_CLIENT_PRIVATE_KEY_FILE, TLS_CRL_FILE,
+    TLS_KEYLOG, TLS_OCSP_REQUIRED, TLS_ROOT_CA_CERTIFICATE_FILE, TLS_SERVER_CERTIFICATE_FILE,
+    TLS_SERVER_OCSP_RESPONSE_FILE, TLS_SERVER_PRIVATE_KEY_FILE, TLS_SPIFFE_TRUST_DOMAIN,
+    TLS_SVID_REFRESH_INTERVAL,
 };
 use zenoh_cfg_properties::Properties;
 use zenoh_config::{Config, ZN_FALSE, ZN_TRUE};
@@ -32,7 +33,10 @@ use zenoh_link_commons::{ConfigurationInspector, LocatorInspector};
 use zenoh_protocol::core::{endpoint::Address, Locator};
 use zenoh_result::{bail, zerror, ZResult};
 
+mod revocation;
+pub mod spiffe;
 mod unicast;
+mod verify;
 pub use unicast::*;
 
 // Default MTU (TLS PDU) in bytes.
@@ -99,6 +103,39 @@ impl ConfigurationInspector<Config> for TlsConfigurator {
                 tls_client_certificate.into(),
             );
         }
+        if let Some(tls_spiffe_trust_domain) = c.spiffe_trust_domain() {
+            properties.insert(
+                TLS_SPIFFE_TRUST_DOMAIN.into(),
+                tls_spiffe_trust_domain.into(),
+            );
+        }
+        if let Some(tls_svid_refresh_interval) = c.svid_refresh_interval() {
+            properties.insert(
+                TLS_SVID_REFRESH_INTERVAL.into(),
+                tls_svid_refresh_interval.to_string(),
+            );
+        }
+        if let Some(tls_server_ocsp_response) = c.server_ocsp_response() {
+            properties.insert(
+                TLS_SERVER_OCSP_RESPONSE_FILE.into(),
+                tls_server_ocsp_response.into(),
+            );
+        }
+        if let Some(tls_crl) = c.crl() {
+            properties.insert(TLS_CRL_FILE.into(), tls_crl.into());
+        }
+        if let Some(tls_ocsp_required) = c.ocsp_required() {
+            match tls_ocsp_required {
+                true => properties.insert(TLS_OCSP_REQUIRED.into(), ZN_TRUE.into()),
+                false => properties.insert(TLS_OCSP_REQUIRED.into(), ZN_FALSE.into()),
+            };
+        }
+        if let Some(tls_keylog) = c.keylog() {
+            match tls_keylog {
+                true => properties.insert(TLS_KEYLOG.into(), ZN_TRUE.into()),
+                false => properties.insert(TLS_KEYLOG.into(), ZN_FALSE.into()),
+            };
+        }
 
         Ok(properties)
     }
@@ -136,10 +173,44 @@ pub mod config {
 
     pub const TLS_CLIENT_AUTH: &str = ZN_TLS_CLIENT_AUTH_STR;
     pub const TLS_CLIENT_AUTH_DEFAULT: &str = ZN_TLS_CLIENT_AUTH_DEFAULT;
+
+    pub const TLS_SPIFFE_TRUST_DOMAIN: &str = "tls_spiffe_trust_domain";
+    pub const TLS_SVID_REFRESH_INTERVAL: &str = "tls_svid_refresh_interval";
+
+    /// Whether the connector verifies that the server certificate matches the endpoint host.
+    /// Disabling it is insecure and only meant for lab setups.
+    pub const TLS_VERIFY_NAME_ON_CONNECT: &str = "tls_verify_name_on_connect";
+    pub const TLS_VERIFY_NAME_ON_CONNECT_DEFAULT: &str = ZN_TRUE;
+
+    /// The SHA-256 fingerprint of the only server certificate the connector accepts, e.g. a
+    /// self-signed one. The certificate chain is not verified at all then.
+    pub const TLS_SERVER_CERTIFICATE_FINGERPRINT: &str = "tls_server_certificate_fingerprint";
+
+    /// The file containing the DER-encoded OCSP response stapled by the listener.
+    pub const TLS_SERVER_OCSP_RESPONSE_FILE: &str = "tls_server_ocsp_response";
+
+    /// The file containing the DER-encoded CRLs the connector checks the server certificates against.
+    pub const TLS_CRL_FILE: &str = "tls_crl";
+
+    /// Whether the connector requires the server to staple a valid OCSP response.
+    pub const TLS_OCSP_REQUIRED: &str = "tls_ocsp_required";
+    pub const TLS_OCSP_REQUIRED_DEFAULT: &str = ZN_FALSE;
+
+    pub const TLS_KEYLOG: &str = "tls_keylog";
+    pub const TLS_KEYLOG_DEFAULT: &str = ZN_FALSE;
+
+    /// Whether the listener also accepts plain TCP connections on the same port, to migrate a
+    /// fleet to TLS incrementally. Deprecated: it will be removed once migrations are over.
+    pub const TLS_ACCEPT_PLAIN_TCP: &str = "tls_accept_plain_tcp";
+    pub const TLS_ACCEPT_PLAIN_TCP_DEFAULT: &str = ZN_FALSE;
 }
 
 pub async fn get_tls_addr(address: &Address<'_>) -> ZResult<SocketAddr> {
-    match address.as_str().to_socket_addrs().await?.next() {
+    match zenoh_util::net::resolve(address.as_str())
+        .await?
+        .into_iter()
+        .next()
+    {
         Some(addr) => Ok(addr),
         None => bail!("Couldn't resolve TLS locator address: {}", address),
     }
@@ -0,0 +1,363 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+//! Revocation checking of the server certificates, either against a set of CRLs
+//! or against the OCSP response stapled by the server.
+use async_rustls::rustls::{
+    client::{ServerCertVerified, ServerCertVerifier},
+    Certificate, Error, ServerName,
+};
+use std::convert::TryFrom;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use webpki::{EndEntityCert, SignatureAlgorithm};
+use x509_parser::certificate::X509Certificate;
+use x509_parser::revocation_list::CertificateRevocationList;
+use zenoh_result::{bail, zerror, ZResult};
+
+// DER tags
+const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_ENUMERATED: u8 = 0x0a;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_CONTEXT_0: u8 = 0xa0;
+const TAG_CERT_STATUS_GOOD: u8 = 0x80;
+const TAG_CERT_STATUS_REVOKED: u8 = 0xa1;
+
+// id-pkix-ocsp-basic (1.3.6.1.5.5.7.48.1.1)
+const OID_OCSP_BASIC: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x01, 0x01];
+
+/*************************************/
+/*               CRL                 */
+/*************************************/
+/// A set of DER-encoded CRLs. The CRLs are trusted as the root certificates are,
+/// i.e. their signature is not verified.
+pub(crate) struct Crls(Vec<Vec<u8>>);
+
+impl Crls {
+    /// Parse the concatenation of one or more DER-encoded CRLs.
+    pub(crate) fn new(der: &[u8]) -> ZResult<Self> {
+        let mut crls = vec![];
+        let mut rest = der;
+        while !rest.is_empty() {
+            let (next, _) =
+                x509_parser::parse_x509_crl(rest).map_err(|e| zerror!("Invalid TLS CRL: {}", e))?;
+            crls.push(rest[..rest.len() - next.len()].to_vec());
+            rest = next;
+        }
+        Ok(Self(crls))
+    }
+
+    fn check(&self, cert: &X509Certificate) -> Result<(), Error> {
+        for der in self.0.iter() {
+            let crl: CertificateRevocationList = match x509_parser::parse_x509_crl(der) {
+                Ok((_, crl)) => crl,
+                Err(_) => continue,
+            };
+            if crl.issuer() != cert.issuer() {
+                continue;
+            }
+            if crl
+                .iter_revoked_certificates()
+                .any(|r| r.raw_serial() == cert.raw_serial())
+            {
+                return Err(Error::InvalidCertificateData(format!(
+                    "Certificate {} has been revoked",
+                    cert.subject()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/*************************************/
+/*               OCSP                */
+/*************************************/
+// A minimal DER reader, enough to walk through an OCSP response
+struct Der<'a>(&'a [u8]);
+
+impl<'a> Der<'a> {
+    // Read the next TLV, returning its tag, its content and its whole encoding
+    fn next(&mut self) -> Option<(u8, &'a [u8], &'a [u8])> {
+        let tag = *self.0.first()?;
+        let first = *self.0.get(1)? as usize;
+        let (len, header) = if first < 0x80 {
+            (first, 2)
+        } else {
+            let n = first & 0x7f;
+            if n == 0 || n > 4 {
+                return None;
+            }
+            let len = self
+                .0
+                .get(2..2 + n)?
+                .iter()
+                .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+            (len, 2 + n)
+        };
+        let raw = self.0.get(..header.checked_add(len)?)?;
+        self.0 = &self.0[raw.len()..];
+        Some((tag, &raw[header..], raw))
+    }
+
+    fn expect(&mut self, tag: u8) -> ZResult<&'a [u8]> {
+        match self.next() {
+            Some((t, content, _)) if t == tag => Ok(content),
+            _ => bail!("Invalid OCSP response: expected tag {:#04x}", tag),
+        }
+    }
+}
+
+// Parse a GeneralizedTime as YYYYMMDDHHMMSS[.fff]Z into seconds since UNIX epoch
+fn generalized_time(t: &[u8]) -> ZResult<u64> {
+    let s = std::str::from_utf8(t).map_err(|e| zerror!("Invalid OCSP time: {}", e))?;
+    let field = |r: std::ops::Range<usize>| -> ZResult<i64> {
+        s.get(r)
+            .and_then(|v| v.parse().ok())
+            .ok_or_else(|| zerror!("Invalid OCSP time: {}", s).into())
+    };
+    let (y, m, d) = (field(0..4)?, field(4..6)?, field(6..8)?);
+    let (hh, mm, ss) = (field(8..10)?, field(10..12)?, field(12..14)?);
+    // Days from civil, see http://howardhinnant.github.io/date_algorithms.html
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    u64::try_from(days * 86400 + hh * 3600 + mm * 60 + ss)
+        .map_err(|_| zerror!("Invalid OCSP time: {}", s).into())
+}
+
+static RSA_SHA256: &[&SignatureAlgorithm] = &[&webpki::RSA_PKCS1_2048_8192_SHA256];
+static RSA_SHA384: &[&SignatureAlgorithm] = &[&webpki::RSA_PKCS1_2048_8192_SHA384];
+static RSA_SHA512: &[&SignatureAlgorithm] = &[&webpki::RSA_PKCS1_2048_8192_SHA512];
+static ECDSA_SHA256: &[&SignatureAlgorithm] =
+    &[&webpki::ECDSA_P256_SHA256, &webpki::ECDSA_P384_SHA256];
+static ECDSA_SHA384: &[&SignatureAlgorithm] =
+    &[&webpki::ECDSA_P384_SHA384, &webpki::ECDSA_P256_SHA384];
+static ED25519: &[&SignatureAlgorithm] = &[&webpki::ED25519];
+
+fn signature_algorithms(oid: &[u8]) -> &'static [&'static SignatureAlgorithm] {
+    match oid {
+        // sha256WithRSAEncryption
+        [0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b] => RSA_SHA256,
+        // sha384WithRSAEncryption
+        [0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0c] => RSA_SHA384,
+        // sha512WithRSAEncryption
+        [0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0d] => RSA_SHA512,
+        // ecdsa-with-SHA256
+        [0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02] => ECDSA_SHA256,
+        // ecdsa-with-SHA384
+        [0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x03] => ECDSA_SHA384,
+        // Ed25519
+        [0x2b, 0x65, 0x70] => ED25519,
+        _ => &[],
+    }
+}
+
+/// Check that a DER-encoded OCSP response, signed by the issuer of the certificate,
+/// reports the certificate as good at time `now`.
+fn check_ocsp(response: &[u8], serial: &[u8], issuer: &[u8], now: u64) -> ZResult<()> {
+    // OCSPResponse
+    let mut der = Der(Der(response).expect(TAG_SEQUENCE)?);
+    if der.expect(TAG_ENUMERATED)? != [0] {
+        bail!("Unsuccessful OCSP response");
+    }
+    let mut der = Der(Der(der.expect(TAG_CONTEXT_0)?).expect(TAG_SEQUENCE)?);
+    if der.expect(TAG_OID)? != OID_OCSP_BASIC {
+        bail!("Unsupported OCSP response type");
+    }
+    // BasicOCSPResponse
+    let mut der = Der(Der(der.expect(TAG_OCTET_STRING)?).expect(TAG_SEQUENCE)?);
+    let (tag, tbs, tbs_raw) = der.next().ok_or_else(|| zerror!("Invalid OCSP response"))?;
+    if tag != TAG_SEQUENCE {
+        bail!("Invalid OCSP response");
+    }
+    let algorithm = Der(der.expect(TAG_SEQUENCE)?).expect(TAG_OID)?;
+    let signature = match der.expect(TAG_BIT_STRING)? {
+        [0, signature @ ..] => signature,
+        _ => bail!("Invalid OCSP response signature"),
+    };
+    // Only responses signed by the issuer itself are supported, not delegated responders
+    let issuer = EndEntityCert::try_from(issuer).map_err(|e| zerror!("{}", e))?;
+    if !signature_algorithms(algorithm)
+        .iter()
+        .any(|alg| issuer.verify_signature(alg, tbs_raw, signature).is_ok())
+    {
+        bail!("Invalid OCSP response signature");
+    }
+
+    // ResponseData
+    let mut der = Der(tbs);
+    let responses = loop {
+        match der.next() {
+            Some((TAG_SEQUENCE, responses, _)) => break responses,
+            Some(_) => continue,
+            None => bail!("Invalid OCSP response"),
+        }
+    };
+    let mut responses = Der(responses);
+    while let Some((_, response, _)) = responses.next() {
+        // SingleResponse
+        let mut der = Der(response);
+        let mut cert_id = Der(der.expect(TAG_SEQUENCE)?);
+        let _ = cert_id.next(); // hashAlgorithm
+        let _ = cert_id.next(); // issuerNameHash
+        let _ = cert_id.next(); // issuerKeyHash
+        if cert_id.expect(TAG_INTEGER)? != serial {
+            continue;
+        }
+        let (status, _, _) = der.next().ok_or_else(|| zerror!("Invalid OCSP response"))?;
+        let this_update = generalized_time(der.expect(TAG_GENERALIZED_TIME)?)?;
+        let next_update = match der.next() {
+            Some((TAG_CONTEXT_0, t, _)) => {
+                Some(generalized_time(Der(t).expect(TAG_GENERALIZED_TIME)?)?)
+            }
+            _ => None,
+        };
+        if this_update > now || next_update.map_or(false, |t| t < now) {
+            bail!("Stale OCSP response");
+        }
+        return match status {
+            TAG_CERT_STATUS_GOOD => Ok(()),
+            TAG_CERT_STATUS_REVOKED => bail!("Certificate has been revoked"),
+            _ => bail!("Unknown certificate status"),
+        };
+    }
+    bail!("No OCSP response for the certificate")
+}
+
+/*************************************/
+/*             VERIFIER              */
+/*************************************/
+/// Checks the revocation status of the server certificate after `inner` has verified it.
+pub(crate) struct RevocationVerifier {
+    inner: Arc<dyn ServerCertVerifier>,
+    crls: Option<Crls>,
+    ocsp_required: bool,
+    // The DER-encoded root certificates, used to find the issuer of the OCSP responses
+    roots: Vec<Vec<u8>>,
+}
+
+impl RevocationVerifier {
+    pub(crate) fn new(
+        inner: Arc<dyn ServerCertVerifier>,
+        crls: Option<Crls>,
+        ocsp_required: bool,
+        roots: Vec<Vec<u8>>,
+    ) -> Self {
+        Self {
+            inner,
+            crls,
+            ocsp_required,
+            roots,
+        }
+    }
+
+    fn find_issuer<'a>(
+        &'a self,
+        cert: &X509Certificate,
+        intermediates: &'a [Certificate],
+    ) -> Option<&'a [u8]> {
+        intermediates
+            .iter()
+            .map(|c| c.0.as_slice())
+            .chain(self.roots.iter().map(|c| c.as_slice()))
+            .find(|der| {
+                x509_parser::parse_x509_certificate(der)
+                    .map_or(false, |(_, c)| c.subject() == cert.issuer())
+            })
+    }
+}
+
+impl ServerCertVerifier for RevocationVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            scts,
+            ocsp_response,
+            now,
+        )?;
+
+        let invalid = |e: &dyn std::fmt::Display| Error::InvalidCertificateData(e.to_string());
+        let (_, cert) =
+            x509_parser::parse_x509_certificate(&end_entity.0).map_err(|e| invalid(&e))?;
+
+        if let Some(crls) = self.crls.as_ref() {
+            crls.check(&cert)?;
+            for c in intermediates.iter() {
+                let (_, c) = x509_parser::parse_x509_certificate(&c.0).map_err(|e| invalid(&e))?;
+                crls.check(&c)?;
+            }
+        }
+
+        if self.ocsp_required {
+            if ocsp_response.is_empty() {
+                return Err(invalid(&"Missing stapled OCSP response"));
+            }
+            let issuer = self
+                .find_issuer(&cert, intermediates)
+                .ok_or_else(|| invalid(&"Unable to find the issuer of the OCSP response"))?;
+            let now = now
+                .duration_since(UNIX_EPOCH)
+                .map_err(|_| Error::FailedToGetCurrentTime)?
+                .as_secs();
+            check_ocsp(ocsp_response, cert.raw_serial(), issuer, now).map_err(|e| invalid(&e))?;
+        }
+
+        Ok(verified)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn generalized_time() {
+        use super::generalized_time;
+
+        assert_eq!(generalized_time(b"19700101000000Z").unwrap(), 0);
+        assert_eq!(generalized_time(b"20000301000000Z").unwrap(), 951868800);
+        assert_eq!(generalized_time(b"20230615123045Z").unwrap(), 1686832245);
+        assert!(generalized_time(b"2023").is_err());
+    }
+
+    #[test]
+    fn der() {
+        use super::Der;
+
+        let mut long = vec![0x04, 0x81, 0x80];
+        long.extend_from_slice(&[0xaa; 0x80]);
+        let data = [&[0x02, 0x01, 0x05][..], &long].concat();
+        let mut der = Der(&data);
+        assert_eq!(der.next().unwrap().1, &[0x05]);
+        assert_eq!(der.next().unwrap().1.len(), 0x80);
+        assert!(der.next().is_none());
+        // Truncated
+        assert!(Der(&[0x30, 0x05, 0x00]).next().is_none());
+    }
+}
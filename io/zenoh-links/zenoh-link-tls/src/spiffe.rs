@@ -0,0 +1,208 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+//! Support for SPIFFE X.509-SVIDs, see <https://github.com/spiffe/spiffe/blob/main/standards/X509-SVID.md>.
+use crate::verify::verify_server_chain;
+use async_rustls::rustls::{
+    client::{ServerCertVerified, ServerCertVerifier},
+    server::{
+        AllowAnyAuthenticatedClient, ClientCertVerified, ClientCertVerifier, ClientHello,
+        ResolvesServerCert,
+    },
+    sign::{self, CertifiedKey},
+    Certificate, DistinguishedNames, Error, PrivateKey, RootCertStore, ServerName,
+};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+use x509_parser::extensions::GeneralName;
+use zenoh_core::{zread, zwrite};
+use zenoh_result::{bail, zerror, ZResult};
+
+const SPIFFE_SCHEME: &str = "spiffe://";
+
+/// Return the trust domain of a SPIFFE ID, e.g. `example.org` for `spiffe://example.org/service`.
+pub fn trust_domain(id: &str) -> ZResult<&str> {
+    let rest = id
+        .strip_prefix(SPIFFE_SCHEME)
+        .ok_or_else(|| zerror!("Invalid SPIFFE ID {}: missing {} scheme", id, SPIFFE_SCHEME))?;
+    let td = rest.split('/').next().unwrap_or_default();
+    if td.is_empty() {
+        bail!("Invalid SPIFFE ID {}: empty trust domain", id);
+    }
+    if !td
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '.' | '_'))
+    {
+        bail!("Invalid SPIFFE ID {}: invalid trust domain", id);
+    }
+    Ok(td)
+}
+
+/// Return the SPIFFE ID carried by the URI SAN of a DER-encoded X.509-SVID.
+pub fn spiffe_id(cert: &[u8]) -> ZResult<String> {
+    let (_, cert) = x509_parser::parse_x509_certificate(cert)
+        .map_err(|e| zerror!("Invalid X.509-SVID: {}", e))?;
+    let san = cert
+        .subject_alternative_name()
+        .map_err(|e| zerror!("Invalid X.509-SVID: {}", e))?
+        .ok_or_else(|| zerror!("Invalid X.509-SVID: no subject alternative name"))?;
+    let mut uris = san.value.general_names.iter().filter_map(|n| match n {
+        GeneralName::URI(uri) => Some(*uri),
+        _ => None,
+    });
+    let id = uris
+        .next()
+        .ok_or_else(|| zerror!("Invalid X.509-SVID: no URI subject alternative name"))?;
+    if uris.next().is_some() {
+        bail!("Invalid X.509-SVID: more than one URI subject alternative name");
+    }
+    trust_domain(id)?;
+    Ok(id.to_string())
+}
+
+fn check_trust_domain(cert: &Certificate, expected: &str) -> Result<String, Error> {
+    let id = spiffe_id(&cert.0).map_err(|e| Error::InvalidCertificateData(e.to_string()))?;
+    match trust_domain(&id) {
+        Ok(td) if td == expected => Ok(id),
+        _ => Err(Error::InvalidCertificateData(format!(
+            "SPIFFE ID {id} does not belong to the trust domain {expected}"
+        ))),
+    }
+}
+
+/// Verifies the server X.509-SVID against the root certificates and the trust domain.
+/// As SVIDs do not carry DNS names, the server name is not verified.
+pub(crate) struct SpiffeServerCertVerifier {
+    roots: Vec<Vec<u8>>,
+    trust_domain: String,
+}
+
+impl SpiffeServerCertVerifier {
+    pub(crate) fn new(roots: Vec<Vec<u8>>, trust_domain: String) -> Self {
+        Self {
+            roots,
+            trust_domain,
+        }
+    }
+}
+
+impl ServerCertVerifier for SpiffeServerCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, Error> {
+        verify_server_chain(&self.roots, end_entity, intermediates, now)?;
+        check_trust_domain(end_entity, &self.trust_domain)?;
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+/// Verifies the client X.509-SVID against the root certificates and the trust domain.
+pub(crate) struct SpiffeClientCertVerifier {
+    inner: AllowAnyAuthenticatedClient,
+    trust_domain: String,
+}
+
+impl SpiffeClientCertVerifier {
+    pub(crate) fn new(roots: RootCertStore, trust_domain: String) -> Self {
+        Self {
+            inner: AllowAnyAuthenticatedClient::new(roots),
+            trust_domain,
+        }
+    }
+}
+
+impl ClientCertVerifier for SpiffeClientCertVerifier {
+    fn client_auth_root_subjects(&self) -> Option<DistinguishedNames> {
+        self.inner.client_auth_root_subjects()
+    }
+
+    fn verify_client_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        now: SystemTime,
+    ) -> Result<ClientCertVerified, Error> {
+        self.inner
+            .verify_client_cert(end_entity, intermediates, now)?;
+        check_trust_domain(end_entity, &self.trust_domain)?;
+        Ok(ClientCertVerified::assertion())
+    }
+}
+
+/// Serves the current certificate of a listener, along with its stapled OCSP response,
+/// which can be replaced at runtime when the SVID is rotated.
+pub(crate) struct SvidResolver {
+    key: RwLock<Arc<CertifiedKey>>,
+}
+
+impl SvidResolver {
+    pub(crate) fn new(
+        certs: Vec<Certificate>,
+        key: PrivateKey,
+        ocsp: Option<Vec<u8>>,
+    ) -> ZResult<Self> {
+        Ok(Self {
+            key: RwLock::new(Self::certified_key(certs, key, ocsp)?),
+        })
+    }
+
+    pub(crate) fn update(
+        &self,
+        certs: Vec<Certificate>,
+        key: PrivateKey,
+        ocsp: Option<Vec<u8>>,
+    ) -> ZResult<()> {
+        *zwrite!(self.key) = Self::certified_key(certs, key, ocsp)?;
+        Ok(())
+    }
+
+    fn certified_key(
+        certs: Vec<Certificate>,
+        key: PrivateKey,
+        ocsp: Option<Vec<u8>>,
+    ) -> ZResult<Arc<CertifiedKey>> {
+        let key = sign::any_supported_type(&key).map_err(|e| zerror!(e))?;
+        let mut certified_key = CertifiedKey::new(certs, key);
+        certified_key.ocsp = ocsp;
+        Ok(Arc::new(certified_key))
+    }
+}
+
+impl ResolvesServerCert for SvidResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(zread!(self.key).clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn trust_domain() {
+        use super::trust_domain;
+
+        assert_eq!(
+            trust_domain("spiffe://example.org/ns/prod/sa/router").unwrap(),
+            "example.org"
+        );
+        assert_eq!(trust_domain("spiffe://example.org").unwrap(), "example.org");
+        assert!(trust_domain("https://example.org/router").is_err());
+        assert!(trust_domain("spiffe:///router").is_err());
+        assert!(trust_domain("spiffe://Example.org/router").is_err());
+    }
+}
@@ -12,10 +12,15 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 use crate::{
-    config::*, get_tls_addr, get_tls_host, get_tls_server_name, TLS_ACCEPT_THROTTLE_TIME,
-    TLS_DEFAULT_MTU, TLS_LINGER_TIMEOUT, TLS_LOCATOR_PREFIX,
+    config::*,
+    get_tls_addr, get_tls_host, get_tls_server_name,
+    revocation::{Crls, RevocationVerifier},
+    spiffe::{self, SpiffeClientCertVerifier, SpiffeServerCertVerifier, SvidResolver},
+    verify::{NoServerNameVerifier, PinnedCertVerifier},
+    TLS_ACCEPT_THROTTLE_TIME, TLS_DEFAULT_MTU, TLS_LINGER_TIMEOUT, TLS_LOCATOR_PREFIX,
 };
-use async_rustls::rustls::server::AllowAnyAuthenticatedClient;
+use async_rustls::rustls::client::{ServerCertVerifier, WebPkiVerifier};
+use async_rustls::rustls::server::{AllowAnyAuthenticatedClient, ClientCertVerifier};
 use async_rustls::rustls::version::TLS13;
 pub use async_rustls::rustls::*;
 use async_rustls::{TlsAcceptor, TlsConnector, TlsStream};
@@ -36,13 +41,15 @@ use std::fs::File;
 use std::io::{BufReader, Cursor};
 use std::net::{IpAddr, Shutdown};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, RwLock, Weak};
 use std::time::Duration;
 pub use webpki::*;
 use zenoh_core::{zasynclock, zread, zwrite};
 use zenoh_link_commons::{
-    LinkManagerUnicastTrait, LinkUnicast, LinkUnicastTrait, NewLinkChannelSender,
+    socket::TcpSocketConfig, LinkManagerUnicastTrait, LinkUnicast, LinkUnicastTrait,
+    NewLinkChannelSender,
 };
+use zenoh_link_tcp::LinkUnicastTcp;
 use zenoh_protocol::core::endpoint::Config;
 use zenoh_protocol::core::{EndPoint, Locator};
 use zenoh_result::{bail, zerror, ZResult};
@@ -58,12 +65,16 @@ pub struct LinkUnicastTls {
     //       the same stream: there is only one task at the time that writes on
     //       the stream and only one task at the time that reads from the stream.
     inner: UnsafeCell<TlsStream<TcpStream>>,
+    // A handle on the underlying TCP socket to set its options without touching the TLS stream
+    tcp: TcpStream,
     // The source socket address of this link (address used on the local host)
     src_addr: SocketAddr,
     src_locator: Locator,
     // The destination socket address of this link (address used on the local host)
     dst_addr: SocketAddr,
     dst_locator: Locator,
+    // The SPIFFE ID of the remote end, if SPIFFE is enabled
+    identity: Option<String>,
     // Make sure there are no concurrent read or writes
     write_mtx: AsyncMutex<()>,
     read_mtx: AsyncMutex<()>,
@@ -77,6 +88,7 @@ impl LinkUnicastTls {
         socket: TlsStream<TcpStream>,
         src_addr: SocketAddr,
         dst_addr: SocketAddr,
+        identity: Option<String>,
     ) -> LinkUnicastTls {
         let (tcp_stream, _) = socket.get_ref();
         // Set the TLS nodelay option
@@ -105,12 +117,15 @@ impl LinkUnicastTls {
         }
 
         // Build the Tls object
+        let tcp = tcp_stream.clone();
         LinkUnicastTls {
             inner: UnsafeCell::new(socket),
+            tcp,
             src_addr,
             src_locator: Locator::new(TLS_LOCATOR_PREFIX, src_addr.to_string(), "").unwrap(),
             dst_addr,
             dst_locator: Locator::new(TLS_LOCATOR_PREFIX, dst_addr.to_string(), "").unwrap(),
+            identity,
             write_mtx: AsyncMutex::new(()),
             read_mtx: AsyncMutex::new(()),
         }
@@ -197,6 +212,16 @@ impl LinkUnicastTrait for LinkUnicastTls {
     fn is_streamed(&self) -> bool {
         true
     }
+
+    #[inline(always)]
+    fn get_identity(&self) -> Option<String> {
+        self.identity.clone()
+    }
+
+    fn set_dscp(&self, dscp: u8) -> ZResult<()> {
+        zenoh_util::net::set_dscp(&self.tcp, self.src_addr.is_ipv6(), dscp)
+            .map_err(|e| zerror!("Can not set DSCP {} on TLS link {}: {}", dscp, self, e).into())
+    }
 }
 
 impl Drop for LinkUnicastTls {
@@ -271,16 +296,18 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTls {
 
         let server_name = get_tls_server_name(&epaddr)?;
         let addr = get_tls_addr(&epaddr).await?;
+        let socket_config = TcpSocketConfig::from_endpoint(&endpoint)?;
 
         // Initialize the TLS Config
         let client_config = TlsClientConfig::new(&epconf)
             .await
             .map_err(|e| zerror!("Cannot create a new TLS listener to {endpoint}: {e}"))?;
+        let is_spiffe = client_config.is_spiffe;
         let config = Arc::new(client_config.client_config);
         let connector = TlsConnector::from(config);
 
         // Initialize the TcpStream
-        let tcp_stream = TcpStream::connect(addr).await.map_err(|e| {
+        let tcp_stream = socket_config.connect(&addr).await.map_err(|e| {
             zerror!(
                 "Can not create a new TLS link bound to {:?}: {}",
                 server_name,
@@ -304,6 +331,16 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTls {
             )
         })?;
 
+        socket_config
+            .apply(&tcp_stream, src_addr.is_ipv6())
+            .map_err(|e| {
+                zerror!(
+                    "Can not create a new TLS link bound to {:?}: {}",
+                    server_name,
+                    e
+                )
+            })?;
+
         // Initialize the TlsStream
         let tls_stream = connector
             .connect(server_name.to_owned(), tcp_stream)
@@ -316,8 +353,11 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTls {
                 )
             })?;
         let tls_stream = TlsStream::Client(tls_stream);
+        let identity = is_spiffe.then(|| get_spiffe_id(&tls_stream)).flatten();
 
-        let link = Arc::new(LinkUnicastTls::new(tls_stream, src_addr, dst_addr));
+        let link = Arc::new(LinkUnicastTls::new(
+            tls_stream, src_addr, dst_addr, identity,
+        ));
 
         Ok(LinkUnicast(link))
     }
@@ -328,6 +368,7 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTls {
 
         let addr = get_tls_addr(&epaddr).await?;
         let host = get_tls_host(&epaddr)?;
+        let socket_config = TcpSocketConfig::from_endpoint(&endpoint)?;
 
         // Initialize TlsConfig
         let tls_server_config = TlsServerConfig::new(&epconf)
@@ -344,7 +385,32 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTls {
             .map_err(|e| zerror!("Can not create a new TLS listener on {}: {}", addr, e))?;
         let local_port = local_addr.port();
 
+        // Periodically reload the certificate, e.g. when the SVID is rotated
+        if let Some(resolver) = tls_server_config.resolver.as_ref() {
+            let interval = tls_server_config.refresh_interval;
+            let certificate = epconf.get(TLS_SERVER_CERTIFICATE_FILE).map(String::from);
+            let private_key = epconf.get(TLS_SERVER_PRIVATE_KEY_FILE).map(String::from);
+            let ocsp = epconf.get(TLS_SERVER_OCSP_RESPONSE_FILE).map(String::from);
+            match (interval, certificate, private_key) {
+                (Some(interval), Some(certificate), Some(private_key)) => {
+                    task::spawn(refresh_task(
+                        Arc::downgrade(resolver),
+                        interval,
+                        certificate,
+                        private_key,
+                        ocsp,
+                    ));
+                }
+                _ => log::warn!(
+                    "TLS certificate refresh on {} requires the certificate and private key files",
+                    addr
+                ),
+            }
+        }
+
         // Initialize the TlsAcceptor
+        let is_spiffe = tls_server_config.is_spiffe;
+        let accept_plain_tcp = is_accept_plain_tcp(&epconf)?;
         let acceptor = TlsAcceptor::from(Arc::new(tls_server_config.server_config));
         let active = Arc::new(AtomicBool::new(true));
         let signal = Signal::new();
@@ -357,7 +423,17 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTls {
         let c_addr = local_addr;
         let handle = task::spawn(async move {
             // Wait for the accept loop to terminate
-            let res = accept_task(socket, acceptor, c_active, c_signal, c_manager).await;
+            let res = accept_task(
+                socket,
+                socket_config,
+                acceptor,
+                is_spiffe,
+                accept_plain_tcp,
+                c_active,
+                c_signal,
+                c_manager,
+            )
+            .await;
             zwrite!(c_listeners).remove(&c_addr);
             res
         });
@@ -437,7 +513,10 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastTls {
 
 async fn accept_task(
     socket: TcpListener,
+    socket_config: TcpSocketConfig,
     acceptor: TlsAcceptor,
+    is_spiffe: bool,
+    accept_plain_tcp: bool,
     active: Arc<AtomicBool>,
     signal: Signal,
     manager: NewLinkChannelSender,
@@ -483,6 +562,23 @@ async fn accept_task(
                 continue;
             }
         };
+        if let Err(e) = socket_config.apply(&tcp_stream, src_addr.is_ipv6()) {
+            log::warn!("Can not configure TLS connection {:?}: {}", dst_addr, e);
+        }
+        // Accept a plain TCP connection if it does not start with a TLS handshake record
+        if accept_plain_tcp && !is_tls_handshake(&tcp_stream).await {
+            log::debug!(
+                "Accepted plain TCP connection on TLS listener {:?}: {:?}",
+                src_addr,
+                dst_addr
+            );
+            let link = Arc::new(LinkUnicastTcp::new(tcp_stream, src_addr, dst_addr));
+            if let Err(e) = manager.send_async(LinkUnicast(link)).await {
+                log::error!("{}-{}: {}", file!(), line!(), e)
+            }
+            continue;
+        }
+
         // Accept the TLS connection
         let tls_stream = match acceptor.accept(tcp_stream).await {
             Ok(stream) => TlsStream::Server(stream),
@@ -495,7 +591,10 @@ async fn accept_task(
 
         log::debug!("Accepted TLS connection on {:?}: {:?}", src_addr, dst_addr);
         // Create the new link object
-        let link = Arc::new(LinkUnicastTls::new(tls_stream, src_addr, dst_addr));
+        let identity = is_spiffe.then(|| get_spiffe_id(&tls_stream)).flatten();
+        let link = Arc::new(LinkUnicastTls::new(
+            tls_stream, src_addr, dst_addr, identity,
+        ));
 
         // Communicate the new link to the initial transport manager
         if let Err(e) = manager.send_async(LinkUnicast(link)).await {
@@ -506,8 +605,83 @@ async fn accept_task(
     Ok(())
 }
 
+async fn refresh_task(
+    resolver: Weak<SvidResolver>,
+    interval: Duration,
+    certificate: String,
+    private_key: String,
+    ocsp: Option<String>,
+) {
+    type Reloaded = (Vec<Certificate>, PrivateKey, Option<Vec<u8>>);
+
+    async fn reload(certificate: &str, private_key: &str, ocsp: Option<&str>) -> ZResult<Reloaded> {
+        let certificate = fs::read(certificate)
+            .await
+            .map_err(|e| zerror!("Invalid TLS certificate file: {}", e))?;
+        let private_key = fs::read(private_key)
+            .await
+            .map_err(|e| zerror!("Invalid TLS private key file: {}", e))?;
+        let ocsp = match ocsp {
+            Some(ocsp) => Some(
+                fs::read(ocsp)
+                    .await
+                    .map_err(|e| zerror!("Invalid TLS OCSP response file: {}", e))?,
+            ),
+            None => None,
+        };
+        Ok((
+            parse_certificates(&certificate)?,
+            parse_private_key(&private_key)?,
+            ocsp,
+        ))
+    }
+
+    let mut current = None;
+    loop {
+        task::sleep(interval).await;
+        // The listener has been closed
+        let resolver = match resolver.upgrade() {
+            Some(resolver) => resolver,
+            None => break,
+        };
+        match reload(&certificate, &private_key, ocsp.as_deref()).await {
+            Ok((certs, key, ocsp)) => {
+                // Only replace the certificate if it has changed
+                let fingerprint = Some((certs.clone(), key.clone(), ocsp.clone()));
+                if current == fingerprint {
+                    continue;
+                }
+                match resolver.update(certs, key, ocsp) {
+                    Ok(()) => {
+                        log::debug!("Reloaded TLS certificate {}", certificate);
+                        current = fingerprint;
+                    }
+                    Err(e) => log::warn!("Unable to reload TLS certificate {}: {}", certificate, e),
+                }
+            }
+            Err(e) => log::warn!("Unable to reload TLS certificate {}: {}", certificate, e),
+        }
+    }
+}
+
+// Return the SPIFFE ID of the certificate presented by the remote end of the stream
+fn get_spiffe_id(stream: &TlsStream<TcpStream>) -> Option<String> {
+    let (_, connection) = stream.get_ref();
+    let certificate = connection.peer_certificates()?.first()?;
+    match spiffe::spiffe_id(&certificate.0) {
+        Ok(id) => Some(id),
+        Err(e) => {
+            log::warn!("{}", e);
+            None
+        }
+    }
+}
+
 struct TlsServerConfig {
     server_config: ServerConfig,
+    is_spiffe: bool,
+    resolver: Option<Arc<SvidResolver>>,
+    refresh_interval: Option<Duration>,
 }
 
 impl TlsServerConfig {
@@ -516,37 +690,20 @@ impl TlsServerConfig {
         let tls_server_private_key = TlsServerConfig::load_tls_private_key(config).await?;
         let tls_server_certificate = TlsServerConfig::load_tls_certificate(config).await?;
 
-        let mut keys: Vec<PrivateKey> =
-            rustls_pemfile::rsa_private_keys(&mut Cursor::new(&tls_server_private_key))
-                .map_err(|e| zerror!(e))
-                .map(|mut keys| keys.drain(..).map(PrivateKey).collect())?;
-
-        if keys.is_empty() {
-            keys = rustls_pemfile::pkcs8_private_keys(&mut Cursor::new(&tls_server_private_key))
-                .map_err(|e| zerror!(e))
-                .map(|mut keys| keys.drain(..).map(PrivateKey).collect())?;
-        }
-
-        if keys.is_empty() {
-            keys = rustls_pemfile::ec_private_keys(&mut Cursor::new(&tls_server_private_key))
-                .map_err(|e| zerror!(e))
-                .map(|mut keys| keys.drain(..).map(PrivateKey).collect())?;
-        }
-
-        if keys.is_empty() {
-            bail!("No private key found");
-        }
-
-        let certs: Vec<Certificate> =
-            rustls_pemfile::certs(&mut Cursor::new(&tls_server_certificate))
-                .map_err(|e| zerror!(e))
-                .map(|mut certs| certs.drain(..).map(Certificate).collect())?;
+        let key = parse_private_key(&tls_server_private_key)?;
+        let certs = parse_certificates(&tls_server_certificate)?;
+        let ocsp = TlsServerConfig::load_tls_ocsp_response(config).await?;
 
         if let Some(value) = config.get(TLS_CLIENT_AUTH) {
             client_auth = value.parse()?
         }
+        let trust_domain = config.get(TLS_SPIFFE_TRUST_DOMAIN).map(String::from);
+        let refresh_interval = match config.get(TLS_SVID_REFRESH_INTERVAL) {
+            Some(value) => Some(Duration::from_millis(value.parse()?)),
+            None => None,
+        };
 
-        let sc = if client_auth {
+        let builder = if client_auth {
             let root_cert_store = load_trust_anchors(config)?.map_or_else(
                 || {
                     Err(zerror!(
@@ -555,22 +712,41 @@ impl TlsServerConfig {
                 },
                 Ok,
             )?;
+            let verifier: Arc<dyn ClientCertVerifier> = match trust_domain.as_ref() {
+                Some(td) => Arc::new(SpiffeClientCertVerifier::new(root_cert_store, td.clone())),
+                None => Arc::new(AllowAnyAuthenticatedClient::new(root_cert_store)),
+            };
             ServerConfig::builder()
                 .with_safe_default_cipher_suites()
                 .with_safe_default_kx_groups()
                 .with_protocol_versions(&[&TLS13]) // Force TLS 1.3
                 .map_err(|e| zerror!(e))?
-                .with_client_cert_verifier(Arc::new(AllowAnyAuthenticatedClient::new(root_cert_store)))
-                .with_single_cert(certs, keys.remove(0))
-                .map_err(|e| zerror!(e))?
+                .with_client_cert_verifier(verifier)
         } else {
             ServerConfig::builder()
                 .with_safe_defaults()
                 .with_no_client_auth()
-                .with_single_cert(certs, keys.remove(0))
-                .map_err(|e| zerror!(e))?
         };
-        Ok(TlsServerConfig { server_config: sc })
+
+        // Serve the certificate through a resolver when it needs to be reloaded
+        let (mut sc, resolver) = if refresh_interval.is_some() {
+            let resolver = Arc::new(SvidResolver::new(certs, key, ocsp)?);
+            (builder.with_cert_resolver(resolver.clone()), Some(resolver))
+        } else {
+            let sc = builder
+                .with_single_cert_with_ocsp_and_sct(certs, key, ocsp.unwrap_or_default(), vec![])
+                .map_err(|e| zerror!(e))?;
+            (sc, None)
+        };
+        if is_keylog(config)? {
+            sc.key_log = Arc::new(KeyLogFile::new());
+        }
+        Ok(TlsServerConfig {
+            server_config: sc,
+            is_spiffe: client_auth && trust_domain.is_some(),
+            resolver,
+            refresh_interval,
+        })
     }
 
     async fn load_tls_private_key(config: &Config<'_>) -> ZResult<Vec<u8>> {
@@ -590,10 +766,22 @@ impl TlsServerConfig {
         )
         .await
     }
+
+    async fn load_tls_ocsp_response(config: &Config<'_>) -> ZResult<Option<Vec<u8>>> {
+        match config.get(TLS_SERVER_OCSP_RESPONSE_FILE) {
+            Some(path) => {
+                Ok(Some(fs::read(path).await.map_err(|e| {
+                    zerror!("Invalid TLS OCSP response file: {}", e)
+                })?))
+            }
+            None => Ok(None),
+        }
+    }
 }
 
 struct TlsClientConfig {
     client_config: ClientConfig,
+    is_spiffe: bool,
 }
 
 impl TlsClientConfig {
@@ -602,53 +790,96 @@ impl TlsClientConfig {
         if let Some(value) = config.get(TLS_CLIENT_AUTH) {
             client_auth = value.parse()?
         }
+        let trust_domain = config.get(TLS_SPIFFE_TRUST_DOMAIN).map(String::from);
 
-        let root_cert_store =
-            load_trust_anchors(config)?.map_or_else(|| {
-                log::debug!("Field 'root_ca_certificate' not specified. Loading default Web PKI certificates instead.");
-                load_default_webpki_certs()
-            }, |certs| certs);
-        let cc = if client_auth {
-            log::debug!("Loading client authentication key and certificate...");
-            let tls_client_private_key = TlsClientConfig::load_tls_private_key(config).await?;
-            let tls_client_certificate = TlsClientConfig::load_tls_certificate(config).await?;
-
-            let certs: Vec<Certificate> =
-                rustls_pemfile::certs(&mut Cursor::new(&tls_client_certificate))
-                    .map_err(|e| zerror!(e))
-                    .map(|mut certs| certs.drain(..).map(Certificate).collect())?;
-
-            let mut keys: Vec<PrivateKey> =
-                rustls_pemfile::rsa_private_keys(&mut Cursor::new(&tls_client_private_key))
-                    .map_err(|e| zerror!(e))
-                    .map(|mut keys| keys.drain(..).map(PrivateKey).collect())?;
-
-            if keys.is_empty() {
-                keys =
-                    rustls_pemfile::pkcs8_private_keys(&mut Cursor::new(&tls_client_private_key))
-                        .map_err(|e| zerror!(e))
-                        .map(|mut keys| keys.drain(..).map(PrivateKey).collect())?;
-            }
-
-            if keys.is_empty() {
-                bail!("No private key found");
-            }
-
+        let builder = if client_auth {
             ClientConfig::builder()
                 .with_safe_default_cipher_suites()
                 .with_safe_default_kx_groups()
                 .with_protocol_versions(&[&TLS13])
                 .unwrap()
-                .with_root_certificates(root_cert_store)
-                .with_single_cert(certs, keys.remove(0))
+        } else {
+            ClientConfig::builder().with_safe_defaults()
+        };
+        let verify_name: bool = config
+            .get(TLS_VERIFY_NAME_ON_CONNECT)
+            .unwrap_or(TLS_VERIFY_NAME_ON_CONNECT_DEFAULT)
+            .parse()?;
+        let verifier: Arc<dyn ServerCertVerifier> = if let Some(fingerprint) =
+            config.get(TLS_SERVER_CERTIFICATE_FINGERPRINT)
+        {
+            log::warn!(
+                "TLS certificate verification is disabled: only the server certificate with \
+                fingerprint {} is accepted. Do not use this in production!",
+                fingerprint
+            );
+            Arc::new(PinnedCertVerifier::new(fingerprint)?)
+        } else if let Some(td) = trust_domain.as_ref() {
+            let roots = load_root_certificates(config)?.ok_or_else(|| {
+                zerror!("Missing root certificates while a SPIFFE trust domain is configured.")
+            })?;
+            Arc::new(SpiffeServerCertVerifier::new(roots, td.clone()))
+        } else if !verify_name {
+            log::warn!("TLS server name verification is disabled. Do not use this in production!");
+            let roots = load_root_certificates(config)?.ok_or_else(|| {
+                zerror!("Missing root certificates while server name verification is disabled.")
+            })?;
+            Arc::new(NoServerNameVerifier::new(roots))
+        } else {
+            let root_cert_store =
+                load_trust_anchors(config)?.map_or_else(|| {
+                    log::debug!("Field 'root_ca_certificate' not specified. Loading default Web PKI certificates instead.");
+                    load_default_webpki_certs()
+                }, |certs| certs);
+            Arc::new(WebPkiVerifier::new(root_cert_store, None))
+        };
+
+        // Check the revocation status of the server certificate
+        let crls = match config.get(TLS_CRL_FILE) {
+            Some(path) => Some(Crls::new(
+                &fs::read(path)
+                    .await
+                    .map_err(|e| zerror!("Invalid TLS CRL file: {}", e))?,
+            )?),
+            None => None,
+        };
+        let ocsp_required: bool = config
+            .get(TLS_OCSP_REQUIRED)
+            .unwrap_or(TLS_OCSP_REQUIRED_DEFAULT)
+            .parse()?;
+        let verifier: Arc<dyn ServerCertVerifier> = if crls.is_some() || ocsp_required {
+            let roots = load_root_certificates(config)?.unwrap_or_default();
+            Arc::new(RevocationVerifier::new(
+                verifier,
+                crls,
+                ocsp_required,
+                roots,
+            ))
+        } else {
+            verifier
+        };
+        let builder = builder.with_custom_certificate_verifier(verifier);
+        let mut cc = if client_auth {
+            log::debug!("Loading client authentication key and certificate...");
+            let tls_client_private_key = TlsClientConfig::load_tls_private_key(config).await?;
+            let tls_client_certificate = TlsClientConfig::load_tls_certificate(config).await?;
+
+            let certs = parse_certificates(&tls_client_certificate)?;
+            let key = parse_private_key(&tls_client_private_key)?;
+
+            builder
+                .with_single_cert(certs, key)
                 .expect("bad certificate/key")
         } else {
-            ClientConfig::builder()
-                .with_safe_defaults()
-                .with_root_certificates(root_cert_store)
-                .with_no_client_auth()
+            builder.with_no_client_auth()
         };
-        Ok(TlsClientConfig { client_config: cc })
+        if is_keylog(config)? {
+            cc.key_log = Arc::new(KeyLogFile::new());
+        }
+        Ok(TlsClientConfig {
+            client_config: cc,
+            is_spiffe: trust_domain.is_some(),
+        })
     }
 
     async fn load_tls_private_key(config: &Config<'_>) -> ZResult<Vec<u8>> {
@@ -670,6 +901,73 @@ impl TlsClientConfig {
     }
 }
 
+// Whether the TLS secrets are logged in the file pointed by the SSLKEYLOGFILE environment variable
+// The content type of the TLS record carrying the ClientHello.
+const TLS_HANDSHAKE_RECORD: u8 = 0x16;
+
+// Peek the first byte of the stream without consuming it, to detect a TLS ClientHello.
+async fn is_tls_handshake(stream: &TcpStream) -> bool {
+    let mut byte = [0_u8; 1];
+    matches!(stream.peek(&mut byte).await, Ok(1) if byte[0] == TLS_HANDSHAKE_RECORD)
+}
+
+fn is_accept_plain_tcp(config: &Config<'_>) -> ZResult<bool> {
+    let accept_plain_tcp: bool = config
+        .get(TLS_ACCEPT_PLAIN_TCP)
+        .unwrap_or(TLS_ACCEPT_PLAIN_TCP_DEFAULT)
+        .parse()?;
+    if accept_plain_tcp {
+        log::warn!(
+            "TLS listener accepting plain TCP connections: the traffic of non-TLS peers is not encrypted! \
+            The {} option is deprecated and will be removed once the migration to TLS is over.",
+            TLS_ACCEPT_PLAIN_TCP
+        );
+    }
+    Ok(accept_plain_tcp)
+}
+
+fn is_keylog(config: &Config<'_>) -> ZResult<bool> {
+    let keylog: bool = config
+        .get(TLS_KEYLOG)
+        .unwrap_or(TLS_KEYLOG_DEFAULT)
+        .parse()?;
+    if keylog {
+        log::warn!(
+            "TLS secrets are logged in the SSLKEYLOGFILE: the TLS traffic can be decrypted!"
+        );
+    }
+    Ok(keylog)
+}
+
+fn parse_private_key(pem: &[u8]) -> ZResult<PrivateKey> {
+    let mut keys: Vec<PrivateKey> = rustls_pemfile::rsa_private_keys(&mut Cursor::new(pem))
+        .map_err(|e| zerror!(e))
+        .map(|mut keys| keys.drain(..).map(PrivateKey).collect())?;
+
+    if keys.is_empty() {
+        keys = rustls_pemfile::pkcs8_private_keys(&mut Cursor::new(pem))
+            .map_err(|e| zerror!(e))
+            .map(|mut keys| keys.drain(..).map(PrivateKey).collect())?;
+    }
+
+    if keys.is_empty() {
+        keys = rustls_pemfile::ec_private_keys(&mut Cursor::new(pem))
+            .map_err(|e| zerror!(e))
+            .map(|mut keys| keys.drain(..).map(PrivateKey).collect())?;
+    }
+
+    if keys.is_empty() {
+        bail!("No private key found");
+    }
+    Ok(keys.remove(0))
+}
+
+fn parse_certificates(pem: &[u8]) -> ZResult<Vec<Certificate>> {
+    rustls_pemfile::certs(&mut Cursor::new(pem))
+        .map_err(|e| zerror!(e).into())
+        .map(|mut certs| certs.drain(..).map(Certificate).collect())
+}
+
 async fn load_tls_key(
     config: &Config<'_>,
     tls_private_key_raw_config_key: &str,
@@ -707,25 +1005,21 @@ async fn load_tls_certificate(
     Err(zerror!("Missing tls certificates.").into())
 }
 
-fn load_trust_anchors(config: &Config<'_>) -> ZResult<Option<RootCertStore>> {
-    let mut root_cert_store = RootCertStore::empty();
+fn load_root_certificates(config: &Config<'_>) -> ZResult<Option<Vec<Vec<u8>>>> {
     if let Some(value) = config.get(TLS_ROOT_CA_CERTIFICATE_RAW) {
         let mut pem = BufReader::new(value.as_bytes());
-        let certs = rustls_pemfile::certs(&mut pem)?;
-        let trust_anchors = certs.iter().map(|cert| {
-            let ta = TrustAnchor::try_from_cert_der(&cert[..]).unwrap();
-            OwnedTrustAnchor::from_subject_spki_name_constraints(
-                ta.subject,
-                ta.spki,
-                ta.name_constraints,
-            )
-        });
-        root_cert_store.add_server_trust_anchors(trust_anchors.into_iter());
-        return Ok(Some(root_cert_store));
+        return Ok(Some(rustls_pemfile::certs(&mut pem)?));
     }
     if let Some(filename) = config.get(TLS_ROOT_CA_CERTIFICATE_FILE) {
         let mut pem = BufReader::new(File::open(filename)?);
-        let certs = rustls_pemfile::certs(&mut pem)?;
+        return Ok(Some(rustls_pemfile::certs(&mut pem)?));
+    }
+    Ok(None)
+}
+
+fn load_trust_anchors(config: &Config<'_>) -> ZResult<Option<RootCertStore>> {
+    Ok(load_root_certificates(config)?.map(|certs| {
+        let mut root_cert_store = RootCertStore::empty();
         let trust_anchors = certs.iter().map(|cert| {
             let ta = TrustAnchor::try_from_cert_der(&cert[..]).unwrap();
             OwnedTrustAnchor::from_subject_spki_name_constraints(
@@ -735,9 +1029,8 @@ fn load_trust_anchors(config: &Config<'_>) -> ZResult<Option<RootCertStore>> {
             )
         });
         root_cert_store.add_server_trust_anchors(trust_anchors.into_iter());
-        return Ok(Some(root_cert_store));
-    }
-    Ok(None)
+        root_cert_store
+    }))
 }
 
 fn load_default_webpki_certs() -> RootCertStore {
@@ -0,0 +1,151 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+//! Relaxed server certificate verifiers, intended for lab setups without a full PKI.
+use async_rustls::rustls::{
+    client::{ServerCertVerified, ServerCertVerifier},
+    Certificate, Error, ServerName,
+};
+use sha2::{Digest, Sha256};
+use std::convert::TryFrom;
+use std::time::SystemTime;
+use webpki::{EndEntityCert, SignatureAlgorithm, TlsServerTrustAnchors, TrustAnchor};
+use zenoh_result::{zerror, ZResult};
+
+static SUPPORTED_SIG_ALGS: &[&SignatureAlgorithm] = &[
+    &webpki::ECDSA_P256_SHA256,
+    &webpki::ECDSA_P256_SHA384,
+    &webpki::ECDSA_P384_SHA256,
+    &webpki::ECDSA_P384_SHA384,
+    &webpki::ED25519,
+    &webpki::RSA_PSS_2048_8192_SHA256_LEGACY_KEY,
+    &webpki::RSA_PSS_2048_8192_SHA384_LEGACY_KEY,
+    &webpki::RSA_PSS_2048_8192_SHA512_LEGACY_KEY,
+    &webpki::RSA_PKCS1_2048_8192_SHA256,
+    &webpki::RSA_PKCS1_2048_8192_SHA384,
+    &webpki::RSA_PKCS1_2048_8192_SHA512,
+    &webpki::RSA_PKCS1_3072_8192_SHA384,
+];
+
+/// Verify that the server certificate chains up to one of the DER-encoded `roots`,
+/// without verifying the server name.
+pub(crate) fn verify_server_chain(
+    roots: &[Vec<u8>],
+    end_entity: &Certificate,
+    intermediates: &[Certificate],
+    now: SystemTime,
+) -> Result<(), Error> {
+    let cert = EndEntityCert::try_from(end_entity.0.as_slice())
+        .map_err(|e| Error::InvalidCertificateData(e.to_string()))?;
+    let anchors = roots
+        .iter()
+        .map(|der| TrustAnchor::try_from_cert_der(der))
+        .collect::<Result<Vec<TrustAnchor>, _>>()
+        .map_err(|e| Error::InvalidCertificateData(e.to_string()))?;
+    let chain: Vec<&[u8]> = intermediates.iter().map(|c| c.0.as_slice()).collect();
+    let now = webpki::Time::try_from(now).map_err(|_| Error::FailedToGetCurrentTime)?;
+    cert.verify_is_valid_tls_server_cert(
+        SUPPORTED_SIG_ALGS,
+        &TlsServerTrustAnchors(&anchors),
+        &chain,
+        now,
+    )
+    .map_err(|e| Error::InvalidCertificateData(e.to_string()))
+}
+
+/// Verifies the server certificate against the root certificates but not its name.
+pub(crate) struct NoServerNameVerifier {
+    roots: Vec<Vec<u8>>,
+}
+
+impl NoServerNameVerifier {
+    pub(crate) fn new(roots: Vec<Vec<u8>>) -> Self {
+        Self { roots }
+    }
+}
+
+impl ServerCertVerifier for NoServerNameVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, Error> {
+        verify_server_chain(&self.roots, end_entity, intermediates, now)?;
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+/// Accepts only the server certificate whose SHA-256 fingerprint has been pinned,
+/// e.g. a self-signed certificate. Neither the chain nor the name are verified.
+pub(crate) struct PinnedCertVerifier {
+    fingerprint: [u8; 32],
+}
+
+impl PinnedCertVerifier {
+    /// Parse a SHA-256 fingerprint in hexadecimal, optionally separated by colons
+    /// as printed by `openssl x509 -noout -fingerprint -sha256`.
+    pub(crate) fn new(fingerprint: &str) -> ZResult<Self> {
+        let hex: String = fingerprint.chars().filter(|c| *c != ':').collect();
+        let mut bytes = [0u8; 32];
+        hex::decode_to_slice(hex, &mut bytes).map_err(|e| {
+            zerror!(
+                "Invalid SHA-256 certificate fingerprint {}: {}",
+                fingerprint,
+                e
+            )
+        })?;
+        Ok(Self { fingerprint: bytes })
+    }
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, Error> {
+        let fingerprint: [u8; 32] = Sha256::digest(&end_entity.0).into();
+        if fingerprint != self.fingerprint {
+            return Err(Error::InvalidCertificateData(
+                "Server certificate does not match the pinned fingerprint".to_string(),
+            ));
+        }
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn fingerprint() {
+        use super::PinnedCertVerifier;
+
+        let hex = "AB".repeat(32);
+        assert!(PinnedCertVerifier::new(&hex).is_ok());
+        let colons = vec!["ab"; 32].join(":");
+        assert_eq!(
+            PinnedCertVerifier::new(&colons).unwrap().fingerprint,
+            [0xab; 32]
+        );
+        assert!(PinnedCertVerifier::new("abcd").is_err());
+        assert!(PinnedCertVerifier::new(&"zz".repeat(32)).is_err());
+    }
+}
@@ -20,7 +20,6 @@
 mod multicast;
 mod unicast;
 
-use async_std::net::ToSocketAddrs;
 use async_trait::async_trait;
 pub use multicast::*;
 use std::net::SocketAddr;
@@ -28,7 +27,7 @@ pub use unicast::*;
 use zenoh_core::zconfigurable;
 use zenoh_link_commons::LocatorInspector;
 use zenoh_protocol::core::{endpoint::Address, Locator};
-use zenoh_result::{zerror, ZResult};
+use zenoh_result::ZResult;
 
 // NOTE: In case of using UDP in high-throughput scenarios, it is recommended to set the
 //       UDP buffer size on the host to a reasonable size. Usually, default values for UDP buffers
@@ -47,6 +46,14 @@ use zenoh_result::{zerror, ZResult};
 //       understood.
 const UDP_MAX_MTU: u16 = 65_507;
 
+// Size of the IP and UDP headers to subtract from the path MTU to obtain the UDP PDU.
+const UDP_IPV4_HEADERS: u16 = 20 + 8;
+const UDP_IPV6_HEADERS: u16 = 40 + 8;
+
+// Minimum MTU (UDP PDU) in bytes derived from a path MTU: 1280 bytes is the minimum MTU
+// of an IPv6 link. A smaller path MTU is considered bogus and ignored.
+const UDP_MIN_MTU: u16 = 1_280 - UDP_IPV6_HEADERS;
+
 pub const UDP_LOCATOR_PREFIX: &str = "udp";
 
 #[cfg(any(target_os = "linux", target_os = "windows"))]
@@ -89,11 +96,9 @@ pub mod config {
 }
 
 pub async fn get_udp_addrs(address: Address<'_>) -> ZResult<impl Iterator<Item = SocketAddr>> {
-    let iter = address
-        .as_str()
-        .to_socket_addrs()
-        .await
-        .map_err(|e| zerror!("{}", e))?;
+    let iter = zenoh_util::net::resolve(address.as_str())
+        .await?
+        .into_iter();
     Ok(iter)
 }
 
@@ -13,9 +13,9 @@
 //
 use super::{
     get_udp_addrs, socket_addr_to_udp_locator, UDP_ACCEPT_THROTTLE_TIME, UDP_DEFAULT_MTU,
-    UDP_MAX_MTU,
+    UDP_IPV4_HEADERS, UDP_IPV6_HEADERS, UDP_MAX_MTU, UDP_MIN_MTU,
 };
-use async_std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use async_std::net::{SocketAddr, UdpSocket};
 use async_std::prelude::*;
 use async_std::sync::Mutex as AsyncMutex;
 use async_std::task;
@@ -29,8 +29,8 @@ use std::sync::{Arc, Mutex, RwLock, Weak};
 use std::time::Duration;
 use zenoh_core::{zasynclock, zlock, zread, zwrite};
 use zenoh_link_commons::{
-    ConstructibleLinkManagerUnicast, LinkManagerUnicastTrait, LinkUnicast, LinkUnicastTrait,
-    NewLinkChannelSender,
+    socket::SocketConfig, ConstructibleLinkManagerUnicast, LinkManagerUnicastTrait, LinkUnicast,
+    LinkUnicastTrait, NewLinkChannelSender,
 };
 use zenoh_protocol::core::{EndPoint, Locator};
 use zenoh_result::{bail, zerror, Error as ZError, ZResult};
@@ -132,6 +132,8 @@ pub struct LinkUnicastUdp {
     dst_locator: Locator,
     // The UDP socket is connected to the peer
     variant: LinkUnicastUdpVariant,
+    // The MTU of this link, bounded by the path MTU towards the peer
+    mtu: u16,
 }
 
 impl LinkUnicastUdp {
@@ -146,10 +148,32 @@ impl LinkUnicastUdp {
             src_addr,
             dst_addr,
             variant,
+            mtu: get_udp_mtu(&dst_addr),
         }
     }
 }
 
+// Size the datagrams after the path MTU towards the peer, if known, so that batches
+// are not fragmented at IP level: losing one fragment means losing the whole batch.
+fn get_udp_mtu(dst_addr: &SocketAddr) -> u16 {
+    let mtu = *UDP_DEFAULT_MTU;
+    match zenoh_util::net::get_path_mtu(dst_addr) {
+        Some(path_mtu) => {
+            let headers = match dst_addr {
+                SocketAddr::V4(_) => UDP_IPV4_HEADERS,
+                SocketAddr::V6(_) => UDP_IPV6_HEADERS,
+            };
+            let path_mtu = path_mtu.saturating_sub(headers);
+            if path_mtu < UDP_MIN_MTU {
+                mtu
+            } else {
+                mtu.min(path_mtu)
+            }
+        }
+        None => mtu,
+    }
+}
+
 #[async_trait]
 impl LinkUnicastTrait for LinkUnicastUdp {
     async fn close(&self) -> ZResult<()> {
@@ -205,7 +229,7 @@ impl LinkUnicastTrait for LinkUnicastUdp {
 
     #[inline(always)]
     fn get_mtu(&self) -> u16 {
-        *UDP_DEFAULT_MTU
+        self.mtu
     }
 
     #[inline(always)]
@@ -217,6 +241,20 @@ impl LinkUnicastTrait for LinkUnicastUdp {
     fn is_streamed(&self) -> bool {
         false
     }
+
+    fn set_dscp(&self, dscp: u8) -> ZResult<()> {
+        match &self.variant {
+            LinkUnicastUdpVariant::Connected(link) => {
+                zenoh_util::net::set_dscp(link.socket.as_ref(), self.src_addr.is_ipv6(), dscp)
+                    .map_err(|e| {
+                        zerror!("Can not set DSCP {} on UDP link {}: {}", dscp, self, e).into()
+                    })
+            }
+            // The listener socket is shared by all the unconnected links: marking it would
+            // affect the traffic towards every peer.
+            LinkUnicastUdpVariant::Unconnected(_) => Ok(()),
+        }
+    }
 }
 
 impl fmt::Display for LinkUnicastUdp {
@@ -284,18 +322,31 @@ impl LinkManagerUnicastUdp {
     async fn new_link_inner(
         &self,
         dst_addr: &SocketAddr,
+        config: &SocketConfig,
     ) -> ZResult<(UdpSocket, SocketAddr, SocketAddr)> {
-        // Establish a UDP socket
-        let socket = UdpSocket::bind(SocketAddr::new(
-            if dst_addr.is_ipv4() {
-                Ipv4Addr::UNSPECIFIED.into()
-            } else {
-                Ipv6Addr::UNSPECIFIED.into()
-            }, // UDP addr
-            0, // UDP port
-        ))
-        .await
-        .map_err(|e| {
+        // Establish a UDP socket, on the configured source address if any
+        let bind_addr = config.bind_addr(dst_addr).map_err(|e| {
+            let e = zerror!("Can not create a new UDP link bound to {}: {}", dst_addr, e);
+            log::warn!("{}", e);
+            e
+        })?;
+        let socket = UdpSocket::bind(bind_addr).await.map_err(|e| {
+            let e = zerror!("Can not create a new UDP link bound to {}: {}", dst_addr, e);
+            log::warn!("{}", e);
+            e
+        })?;
+
+        // Bind the socket to the configured interface if any
+        if let Some(iface) = config.iface.as_ref() {
+            zenoh_util::net::set_bind_to_device(&socket, iface).map_err(|e| {
+                let e = zerror!("Can not create a new UDP link bound to {}: {}", dst_addr, e);
+                log::warn!("{}", e);
+                e
+            })?;
+        }
+
+        // Apply the socket options
+        config.apply(&socket, dst_addr.is_ipv6()).map_err(|e| {
             let e = zerror!("Can not create a new UDP link bound to {}: {}", dst_addr, e);
             log::warn!("{}", e);
             e
@@ -324,7 +375,11 @@ impl LinkManagerUnicastUdp {
         Ok((socket, src_addr, dst_addr))
     }
 
-    async fn new_listener_inner(&self, addr: &SocketAddr) -> ZResult<(UdpSocket, SocketAddr)> {
+    async fn new_listener_inner(
+        &self,
+        addr: &SocketAddr,
+        config: &SocketConfig,
+    ) -> ZResult<(UdpSocket, SocketAddr)> {
         // Bind the UDP socket
         let socket = UdpSocket::bind(addr).await.map_err(|e| {
             let e = zerror!("Can not create a new UDP listener on {}: {}", addr, e);
@@ -332,6 +387,13 @@ impl LinkManagerUnicastUdp {
             e
         })?;
 
+        // Apply the socket options, shared by all the links accepted on the listener
+        config.apply(&socket, addr.is_ipv6()).map_err(|e| {
+            let e = zerror!("Can not create a new UDP listener on {}: {}", addr, e);
+            log::warn!("{}", e);
+            e
+        })?;
+
         let local_addr = socket.local_addr().map_err(|e| {
             let e = zerror!("Can not create a new UDP listener on {}: {}", addr, e);
             log::warn!("{}", e);
@@ -348,10 +410,11 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastUdp {
         let dst_addrs = get_udp_addrs(endpoint.address())
             .await?
             .filter(|a| !a.ip().is_multicast());
+        let config = SocketConfig::from_endpoint(&endpoint)?;
 
         let mut errs: Vec<ZError> = vec![];
         for da in dst_addrs {
-            match self.new_link_inner(&da).await {
+            match self.new_link_inner(&da, &config).await {
                 Ok((socket, src_addr, dst_addr)) => {
                     // Create UDP link
                     let link = Arc::new(LinkUnicastUdp::new(
@@ -385,10 +448,11 @@ impl LinkManagerUnicastTrait for LinkManagerUnicastUdp {
         let addrs = get_udp_addrs(endpoint.address())
             .await?
             .filter(|a| !a.ip().is_multicast());
+        let config = SocketConfig::from_endpoint(&endpoint)?;
 
         let mut errs: Vec<ZError> = vec![];
         for da in addrs {
-            match self.new_listener_inner(&da).await {
+            match self.new_listener_inner(&da, &config).await {
                 Ok((socket, local_addr)) => {
                     // Update the endpoint locator address
                     endpoint = EndPoint::new(
@@ -0,0 +1,164 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+// The period over which the bandwidth of a link is estimated
+const WINDOW: Duration = Duration::from_millis(100);
+// The saturation, in per-mille, above which the droppable messages start being shed
+const SHEDDING_THRESHOLD: u64 = 900;
+const SATURATED: u64 = 1_000;
+
+/// The bandwidth estimate of a link.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct BandwidthEstimate {
+    /// The bytes per second the link sustains while it is being written to,
+    /// i.e. its estimated available bandwidth.
+    pub bandwidth: u64,
+    /// The bytes per second actually written on the link.
+    pub throughput: u64,
+    /// The fraction of time spent writing on the link, 1.0 meaning that it is saturated.
+    pub saturation: f64,
+}
+
+/// Estimates the bandwidth of a link from the time its writes take: a link whose writes block
+/// because its send buffer is full (i.e. the queues grow) spends all of its time writing.
+///
+/// The estimate is updated every 100 ms by the single task writing on the link, and read by
+/// the tasks scheduling messages on it.
+pub(crate) struct BandwidthEstimator {
+    epoch: Instant,
+    // The current window, in nanoseconds since the epoch
+    window_start: AtomicU64,
+    window_bytes: AtomicU64,
+    window_busy: AtomicU64,
+    // The estimate of the last complete window
+    bandwidth: AtomicU64,
+    throughput: AtomicU64,
+    saturation: AtomicU64,
+}
+
+impl BandwidthEstimator {
+    pub(crate) fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            window_start: AtomicU64::new(0),
+            window_bytes: AtomicU64::new(0),
+            window_busy: AtomicU64::new(0),
+            bandwidth: AtomicU64::new(0),
+            throughput: AtomicU64::new(0),
+            saturation: AtomicU64::new(0),
+        }
+    }
+
+    fn now(&self) -> u64 {
+        u64::try_from(self.epoch.elapsed().as_nanos()).unwrap_or(u64::MAX)
+    }
+
+    /// Record that `bytes` have been written on the link in `busy` time.
+    pub(crate) fn record(&self, bytes: usize, busy: Duration) {
+        let busy = u64::try_from(busy.as_nanos()).unwrap_or(u64::MAX);
+        let bytes = self.window_bytes.fetch_add(bytes as u64, Ordering::Relaxed) + bytes as u64;
+        let busy = self.window_busy.fetch_add(busy, Ordering::Relaxed) + busy;
+
+        let now = self.now();
+        let start = self.window_start.load(Ordering::Relaxed);
+        let elapsed = now.saturating_sub(start);
+        if elapsed < WINDOW.as_nanos() as u64 {
+            return;
+        }
+
+        // Close the window
+        const NANOS: u128 = 1_000_000_000;
+        let throughput = (bytes as u128 * NANOS / elapsed as u128) as u64;
+        let bandwidth = match busy {
+            0 => self.bandwidth.load(Ordering::Relaxed),
+            busy => (bytes as u128 * NANOS / busy as u128) as u64,
+        };
+        let saturation = (busy.saturating_mul(SATURATED) / elapsed).min(SATURATED);
+        self.throughput.store(throughput, Ordering::Relaxed);
+        self.bandwidth.store(bandwidth, Ordering::Relaxed);
+        self.saturation.store(saturation, Ordering::Relaxed);
+
+        self.window_bytes.store(0, Ordering::Relaxed);
+        self.window_busy.store(0, Ordering::Relaxed);
+        self.window_start.store(now, Ordering::Relaxed);
+    }
+
+    // The saturation in per-mille, considering an idle link (i.e. no recent write) as not saturated
+    fn saturation(&self) -> u64 {
+        let start = self.window_start.load(Ordering::Relaxed);
+        if self.now().saturating_sub(start) > 2 * WINDOW.as_nanos() as u64 {
+            return 0;
+        }
+        self.saturation.load(Ordering::Relaxed)
+    }
+
+    /// Whether a droppable message should be shed: the probability grows linearly from 0 to 1
+    /// as the saturation goes from 90% to 100%.
+    pub(crate) fn should_shed(&self) -> bool {
+        let saturation = self.saturation();
+        saturation > SHEDDING_THRESHOLD
+            && rand::thread_rng().gen_ratio(
+                (saturation - SHEDDING_THRESHOLD) as u32,
+                (SATURATED - SHEDDING_THRESHOLD) as u32,
+            )
+    }
+
+    pub(crate) fn estimate(&self) -> BandwidthEstimate {
+        BandwidthEstimate {
+            bandwidth: self.bandwidth.load(Ordering::Relaxed),
+            throughput: self.throughput.load(Ordering::Relaxed),
+            saturation: self.saturation() as f64 / SATURATED as f64,
+        }
+    }
+}
+
+impl Default for BandwidthEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn bandwidth_estimator() {
+    let estimator = BandwidthEstimator::new();
+    assert!(!estimator.should_shed());
+
+    // A link always busy writing is saturated
+    std::thread::sleep(WINDOW);
+    estimator.record(1_000_000, WINDOW * 2);
+    let estimate = estimator.estimate();
+    assert_eq!(estimate.saturation, 1.0);
+    assert_eq!(estimate.bandwidth, 5_000_000);
+    assert!(estimate.throughput > 0);
+    assert!(estimator.should_shed());
+
+    // A link mostly idle is not
+    std::thread::sleep(WINDOW);
+    estimator.record(1_000, Duration::from_micros(10));
+    let estimate = estimator.estimate();
+    assert!(estimate.saturation < 0.01);
+    assert!(!estimator.should_shed());
+
+    // Nor is a link without any recent write
+    std::thread::sleep(WINDOW);
+    estimator.record(1_000_000, WINDOW * 2);
+    assert!(estimator.should_shed());
+    std::thread::sleep(WINDOW * 3);
+    assert_eq!(estimator.estimate().saturation, 0.0);
+    assert!(!estimator.should_shed());
+}
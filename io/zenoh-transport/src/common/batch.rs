@@ -11,7 +11,10 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
+use super::fanout;
 use std::num::NonZeroUsize;
+#[cfg(feature = "stats")]
+use std::time::Instant;
 use zenoh_buffers::{
     reader::{Reader, SiphonableReader},
     writer::{BacktrackableWriter, DidntWrite, HasWriter, Writer},
@@ -58,15 +61,28 @@ impl LatestSn {
 }
 
 #[cfg(feature = "stats")]
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug)]
 pub(crate) struct SerializationBatchStats {
     pub(crate) t_msgs: usize,
+    // when the batch started to be filled, i.e. when its first message got enqueued
+    pub(crate) enqueued: Instant,
 }
 
 #[cfg(feature = "stats")]
 impl SerializationBatchStats {
     fn clear(&mut self) {
         self.t_msgs = 0;
+        self.enqueued = Instant::now();
+    }
+}
+
+#[cfg(feature = "stats")]
+impl Default for SerializationBatchStats {
+    fn default() -> Self {
+        Self {
+            t_msgs: 0,
+            enqueued: Instant::now(),
+        }
     }
 }
 
@@ -239,8 +255,7 @@ impl Encode<&ZenohMessage> for &mut WBatch {
         let mut writer = self.buffer.writer();
         let mark = writer.mark();
 
-        let codec = Zenoh060::default();
-        codec.write(&mut writer, message).map_err(|_| {
+        fanout::write(&mut writer, message).map_err(|_| {
             // Revert the write operation
             writer.rewind(mark);
             WError::DidntWrite
@@ -276,7 +291,7 @@ impl Encode<(&ZenohMessage, Channel, ZInt)> for &mut WBatch {
             e
         })?;
         // Write the zenoh message
-        codec.write(&mut writer, message).map_err(|e| {
+        fanout::write(&mut writer, message).map_err(|e| {
             // Revert the write operation
             writer.rewind(mark);
             e
@@ -0,0 +1,153 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::cell::RefCell;
+use std::sync::Arc;
+use zenoh_buffers::{
+    writer::{DidntWrite, HasWriter, Writer},
+    ZBuf,
+};
+use zenoh_codec::{WCodec, Zenoh060};
+use zenoh_protocol::zenoh::{ZenohBody, ZenohMessage};
+
+// The latest data message serialized while fanning out a message to several transports,
+// along with its encoding.
+struct Encoded {
+    message: ZenohMessage,
+    bytes: Vec<u8>,
+}
+
+thread_local! {
+    // Some while a fan-out is in progress on this thread
+    static FANOUT: RefCell<Option<Option<Encoded>>> = RefCell::new(None);
+}
+
+/// A scope in which the same data message is sent to several transports, e.g. when routing a
+/// publication to all the matching sessions. Within the scope, a data message is serialized
+/// once and its encoding is shared by all the egress queues it is sent on unchanged, i.e. with
+/// the same key expression, routing context, and payload.
+///
+/// The scope lasts until the returned guard is dropped.
+pub struct FanOut(());
+
+impl FanOut {
+    pub fn enter() -> FanOut {
+        FANOUT.with(|f| *f.borrow_mut() = Some(None));
+        FanOut(())
+    }
+}
+
+impl Drop for FanOut {
+    fn drop(&mut self) {
+        FANOUT.with(|f| *f.borrow_mut() = None);
+    }
+}
+
+// Whether `a` and `b` share the same memory
+fn is_same_zbuf(a: &ZBuf, b: &ZBuf) -> bool {
+    a.zslices().count() == b.zslices().count()
+        && a.zslices().zip(b.zslices()).all(|(x, y)| {
+            Arc::as_ptr(&x.buf) as *const u8 == Arc::as_ptr(&y.buf) as *const u8
+                && x.range() == y.range()
+        })
+}
+
+// Whether `a` and `b` have the same encoding, without comparing the content of their payloads
+fn is_same_message(a: &ZenohMessage, b: &ZenohMessage) -> bool {
+    let is_same_body = match (&a.body, &b.body) {
+        (ZenohBody::Data(x), ZenohBody::Data(y)) => {
+            x.key == y.key
+                && x.data_info == y.data_info
+                && x.congestion_control == y.congestion_control
+                && x.reply_context == y.reply_context
+                && is_same_zbuf(&x.payload, &y.payload)
+        }
+        _ => false,
+    };
+    is_same_body
+        && a.channel == b.channel
+        && a.routing_context == b.routing_context
+        && a.attachment == b.attachment
+}
+
+// Serialize `message` on `writer`, reusing the encoding of the same message
+// if it has already been serialized within the current fan-out.
+pub(crate) fn write<W>(writer: &mut W, message: &ZenohMessage) -> Result<(), DidntWrite>
+where
+    W: Writer,
+{
+    let codec = Zenoh060::default();
+    if !matches!(message.body, ZenohBody::Data(_)) {
+        return codec.write(&mut *writer, message);
+    }
+
+    FANOUT.with(|f| match f.borrow_mut().as_mut() {
+        Some(encoded) => {
+            if let Some(e) = encoded.as_ref() {
+                if is_same_message(&e.message, message) {
+                    return writer.write_exact(&e.bytes);
+                }
+            }
+            let mut bytes = vec![];
+            codec.write(&mut bytes.writer(), message)?;
+            writer.write_exact(&bytes)?;
+            *encoded = Some(Encoded {
+                message: message.clone(),
+                bytes,
+            });
+            Ok(())
+        }
+        None => codec.write(&mut *writer, message),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zenoh_protocol::core::{Channel, CongestionControl, WireExpr};
+
+    fn encode(message: &ZenohMessage) -> Vec<u8> {
+        let mut bytes = vec![];
+        write(&mut bytes.writer(), message).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn fanout() {
+        let payload = ZBuf::from(vec![0u8; 1_024]);
+        let message = |key: &'static str, payload: ZBuf| {
+            ZenohMessage::make_data(
+                WireExpr::from(key),
+                payload,
+                Channel::default(),
+                CongestionControl::default(),
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+        let m1 = message("test/fanout", payload.clone());
+        let m2 = message("test/other", payload.clone());
+        let m3 = message("test/fanout", ZBuf::from(vec![1u8; 1_024]));
+        let (e1, e2, e3) = (encode(&m1), encode(&m2), encode(&m3));
+
+        let _fanout = FanOut::enter();
+        for _ in 0..3 {
+            assert_eq!(encode(&m1), e1);
+            assert_eq!(encode(&m1.clone()), e1);
+            assert_eq!(encode(&m2), e2);
+            assert_eq!(encode(&m3), e3);
+        }
+    }
+}
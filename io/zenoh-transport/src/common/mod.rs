@@ -11,9 +11,11 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
+pub(crate) mod bandwidth;
 pub(crate) mod batch;
 pub(crate) mod conduit;
 pub(crate) mod defragmentation;
+pub(crate) mod fanout;
 pub(crate) mod pipeline;
 pub(crate) mod seq_num;
 #[cfg(feature = "stats")]
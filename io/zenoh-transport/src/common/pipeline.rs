@@ -19,7 +19,8 @@ use super::conduit::{TransportChannelTx, TransportConduitTx};
 use async_std::prelude::FutureExt;
 use flume::{bounded, Receiver, Sender};
 use ringbuffer_spsc::{RingBuffer, RingBufferReader, RingBufferWriter};
-use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, MutexGuard};
 use std::thread;
 use std::time::Duration;
@@ -44,15 +45,81 @@ type NanoSeconds = u32;
 const RBLEN: usize = QueueSizeConf::MAX;
 const TSLOT: NanoSeconds = 100;
 
+/// The statistics of the transmission queue of a priority.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct PriorityQueueStats {
+    /// The number of serialization batches of the queue.
+    pub capacity: usize,
+    /// The number of serialization batches being filled, waiting to be sent or being sent.
+    pub depth: usize,
+    /// The number of messages pushed on the queue.
+    pub pushed: u64,
+    /// The number of messages dropped, e.g. because the queue was full and their congestion control is drop.
+    pub dropped: u64,
+}
+
+impl PriorityQueueStats {
+    /// Accumulate the statistics of another queue of the same priority.
+    pub fn merge(&mut self, other: &PriorityQueueStats) {
+        self.capacity += other.capacity;
+        self.depth += other.depth;
+        self.pushed += other.pushed;
+        self.dropped += other.dropped;
+    }
+}
+
+// The counters of a priority queue, shared by both stages of the pipeline
+struct QueueCounters {
+    capacity: usize,
+    in_use: AtomicUsize,
+    pushed: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl QueueCounters {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            in_use: AtomicUsize::new(0),
+            pushed: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    #[inline]
+    fn record(&self, pushed: bool) -> bool {
+        if pushed {
+            self.pushed.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        pushed
+    }
+
+    fn snapshot(&self) -> PriorityQueueStats {
+        PriorityQueueStats {
+            capacity: self.capacity,
+            depth: self.in_use.load(Ordering::Relaxed),
+            pushed: self.pushed.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
 // Inner structure to reuse serialization batches
 struct StageInRefill {
     n_ref_r: Receiver<()>,
     s_ref_r: RingBufferReader<WBatch, RBLEN>,
+    counters: Arc<QueueCounters>,
 }
 
 impl StageInRefill {
     fn pull(&mut self) -> Option<WBatch> {
-        self.s_ref_r.pull()
+        let batch = self.s_ref_r.pull();
+        if batch.is_some() {
+            self.counters.in_use.fetch_add(1, Ordering::Relaxed);
+        }
+        batch
     }
 
     fn wait(&self) -> bool {
@@ -113,6 +180,7 @@ struct StageIn {
     s_out: StageInOut,
     mutex: StageInMutex,
     fragbuf: ZBuf,
+    fragmentation: bool,
 }
 
 impl StageIn {
@@ -207,6 +275,14 @@ impl StageIn {
         // Reinsert the current batch for fragmentation.
         *c_guard = Some(batch);
 
+        if !self.fragmentation {
+            log::warn!(
+                "Zenoh message dropped because it is too large and fragmentation is not supported by the peer: {:?}",
+                msg
+            );
+            return false;
+        }
+
         // Take the expandable buffer and serialize the totality of the message
         self.fragbuf.clear();
 
@@ -249,8 +325,9 @@ impl StageIn {
         true
     }
 
+    // Push `msg`, waiting for a batch to be available only if `block` is true
     #[inline]
-    fn push_transport_message(&mut self, msg: TransportMessage) -> bool {
+    fn push_transport_message(&mut self, msg: TransportMessage, block: bool) -> bool {
         // Lock the current serialization batch.
         let mut c_guard = self.mutex.current();
 
@@ -265,6 +342,9 @@ impl StageIn {
                                 break batch;
                             }
                             None => {
+                                if !block {
+                                    return false;
+                                }
                                 drop(c_guard);
                                 if !self.s_ref.wait() {
                                     return false;
@@ -421,11 +501,13 @@ impl StageOutIn {
 struct StageOutRefill {
     n_ref_w: Sender<()>,
     s_ref_w: RingBufferWriter<WBatch, RBLEN>,
+    counters: Arc<QueueCounters>,
 }
 
 impl StageOutRefill {
     fn refill(&mut self, batch: WBatch) {
         assert!(self.s_ref_w.push(batch).is_none());
+        self.counters.in_use.fetch_sub(1, Ordering::Relaxed);
         let _ = self.n_ref_w.try_send(());
     }
 }
@@ -467,6 +549,7 @@ pub(crate) struct TransmissionPipelineConf {
     pub(crate) batch_size: u16,
     pub(crate) queue_size: [usize; Priority::NUM],
     pub(crate) backoff: Duration,
+    pub(crate) is_fragmentation: bool,
 }
 
 impl Default for TransmissionPipelineConf {
@@ -476,6 +559,7 @@ impl Default for TransmissionPipelineConf {
             batch_size: u16::MAX,
             queue_size: [1; Priority::NUM],
             backoff: Duration::from_micros(1),
+            is_fragmentation: true,
         }
     }
 }
@@ -490,6 +574,7 @@ impl TransmissionPipeline {
     ) -> (TransmissionPipelineProducer, TransmissionPipelineConsumer) {
         let mut stage_in = vec![];
         let mut stage_out = vec![];
+        let mut counters = vec![];
 
         let default_queue_size = [config.queue_size[Priority::default() as usize]];
         let size_iter = if conduit.len() == 1 {
@@ -517,6 +602,8 @@ impl TransmissionPipeline {
             // Create the channel for notifying that new batches are in the refill ring buffer
             // This is a SPSC channel
             let (n_ref_w, n_ref_r) = bounded(1);
+            let queue_counters = Arc::new(QueueCounters::new(*num));
+            counters.push(queue_counters.clone());
 
             // Create the refill ring buffer
             // This is a SPSC ring buffer
@@ -526,7 +613,11 @@ impl TransmissionPipeline {
             let backoff = Arc::new(AtomicBool::new(false));
 
             stage_in.push(Mutex::new(StageIn {
-                s_ref: StageInRefill { n_ref_r, s_ref_r },
+                s_ref: StageInRefill {
+                    n_ref_r,
+                    s_ref_r,
+                    counters: queue_counters.clone(),
+                },
                 s_out: StageInOut {
                     n_out_w: n_out_w.clone(),
                     s_out_w,
@@ -538,6 +629,7 @@ impl TransmissionPipeline {
                     conduit: conduit[prio].clone(),
                 },
                 fragbuf: ZBuf::default(),
+                fragmentation: config.is_fragmentation,
             }));
 
             // The stage out for this priority
@@ -547,13 +639,18 @@ impl TransmissionPipeline {
                     current,
                     backoff: Backoff::new(bytes, backoff),
                 },
-                s_ref: StageOutRefill { n_ref_w, s_ref_w },
+                s_ref: StageOutRefill {
+                    n_ref_w,
+                    s_ref_w,
+                    counters: queue_counters,
+                },
             });
         }
 
         let active = Arc::new(AtomicBool::new(true));
         let producer = TransmissionPipelineProducer {
             stage_in: stage_in.into_boxed_slice().into(),
+            counters: counters.into_boxed_slice().into(),
             active: active.clone(),
         };
         let consumer = TransmissionPipelineConsumer {
@@ -570,6 +667,7 @@ impl TransmissionPipeline {
 pub(crate) struct TransmissionPipelineProducer {
     // Each priority queue has its own Mutex
     stage_in: Arc<[Mutex<StageIn>]>,
+    counters: Arc<[Arc<QueueCounters>]>,
     active: Arc<AtomicBool>,
 }
 
@@ -584,7 +682,8 @@ impl TransmissionPipelineProducer {
         };
         // Lock the channel. We are the only one that will be writing on it.
         let mut queue = zlock!(self.stage_in[idx]);
-        queue.push_zenoh_message(&mut msg, priority)
+        let pushed = queue.push_zenoh_message(&mut msg, priority);
+        self.counters[idx].record(pushed)
     }
 
     #[inline]
@@ -597,7 +696,42 @@ impl TransmissionPipelineProducer {
         };
         // Lock the channel. We are the only one that will be writing on it.
         let mut queue = zlock!(self.stage_in[priority]);
-        queue.push_transport_message(msg)
+        queue.push_transport_message(msg, true)
+    }
+
+    /// Push `msg` only if this doesn't require to wait for the queue to be drained,
+    /// returning false otherwise.
+    #[inline]
+    pub(crate) fn try_push_transport_message(
+        &self,
+        msg: TransportMessage,
+        priority: Priority,
+    ) -> bool {
+        // If the queue is not QoS, it means that we only have one priority with index 0.
+        let priority = if self.stage_in.len() > 1 {
+            priority as usize
+        } else {
+            0
+        };
+        // The queue is held by the producers waiting for it to be drained
+        match self.stage_in[priority].try_lock() {
+            Ok(mut queue) => queue.push_transport_message(msg, false),
+            Err(_) => false,
+        }
+    }
+
+    /// The statistics of each priority queue, indexed by priority. Without QoS, the single
+    /// queue is accounted on the default priority.
+    pub(crate) fn stats(&self) -> [PriorityQueueStats; Priority::NUM] {
+        let mut stats = [PriorityQueueStats::default(); Priority::NUM];
+        if self.counters.len() > 1 {
+            for (stats, counters) in stats.iter_mut().zip(self.counters.iter()) {
+                *stats = counters.snapshot();
+            }
+        } else if let Some(counters) = self.counters.first() {
+            stats[Priority::default() as usize] = counters.snapshot();
+        }
+        stats
     }
 
     pub(crate) fn disable(&self) {
@@ -712,6 +846,7 @@ mod tests {
         batch_size: BATCH_SIZE,
         queue_size: [1; Priority::NUM],
         backoff: Duration::from_micros(1),
+        is_fragmentation: true,
     };
 
     #[test]
@@ -938,6 +1073,150 @@ mod tests {
         });
     }
 
+    #[test]
+    fn tx_pipeline_try_push() {
+        let tct = TransportConduitTx::make(SEQ_NUM_RES).unwrap();
+        let conduits = vec![tct];
+        let (producer, mut consumer) = TransmissionPipeline::make(CONFIG, conduits.as_slice());
+
+        // Block a producer on the full queue
+        let payload_size = (CONFIG.batch_size / 2) as usize;
+        let message = ZenohMessage::make_data(
+            "test".into(),
+            ZBuf::from(vec![0_u8; payload_size]),
+            Channel {
+                priority: Priority::Data,
+                reliability: Reliability::Reliable,
+            },
+            CongestionControl::Block,
+            None,
+            None,
+            None,
+            None,
+        );
+        let c_producer = producer.clone();
+        let blocked = task::spawn_blocking(move || {
+            for _ in 0..=CONFIG.queue_size[0] {
+                c_producer.push_zenoh_message(message.clone());
+            }
+        });
+
+        task::block_on(async {
+            task::sleep(SLEEP).await;
+            // The transport messages are refused rather than waiting for the queue to be drained
+            let credit = TransportMessage::make_credit(Priority::Data, 1, None);
+            let pushed = task::spawn_blocking(move || {
+                producer.try_push_transport_message(credit, Priority::Control)
+            });
+            assert!(!pushed.timeout(TIMEOUT).await.unwrap());
+
+            task::spawn_blocking(move || {
+                let _ = consumer.drain();
+            })
+            .timeout(TIMEOUT)
+            .await
+            .unwrap();
+            blocked.timeout(TIMEOUT).await.unwrap();
+        });
+    }
+
+    #[test]
+    fn tx_pipeline_control_priority() {
+        // One conduit per priority, i.e. QoS is enabled
+        let conduits = (0..Priority::NUM)
+            .map(|_| TransportConduitTx::make(SEQ_NUM_RES).unwrap())
+            .collect::<Vec<_>>();
+        let (producer, mut consumer) = TransmissionPipeline::make(CONFIG, conduits.as_slice());
+
+        // Congest the data priority
+        let payload_size = (CONFIG.batch_size / 2) as usize;
+        let message = ZenohMessage::make_data(
+            "test".into(),
+            ZBuf::from(vec![0_u8; payload_size]),
+            Channel {
+                priority: Priority::Data,
+                reliability: Reliability::BestEffort,
+            },
+            CongestionControl::Drop,
+            None,
+            None,
+            None,
+            None,
+        );
+        let mut congested = false;
+        for _ in 0..16 {
+            if !producer.push_zenoh_message(message.clone()) {
+                congested = true;
+                break;
+            }
+        }
+        assert!(congested);
+
+        // The declarations are not blocked behind the data backlog
+        let declare = ZenohMessage::make_declare(vec![], None, None);
+        assert_eq!(declare.channel.priority, Priority::Control);
+        let scheduled = task::spawn_blocking(move || producer.push_zenoh_message(declare));
+        task::block_on(async {
+            assert!(scheduled.timeout(TIMEOUT).await.unwrap());
+            let pull = async {
+                loop {
+                    let (batch, priority) = consumer.pull().await.unwrap();
+                    consumer.refill(batch, priority);
+                    if priority == Priority::Control as usize {
+                        break;
+                    }
+                }
+            };
+            pull.timeout(TIMEOUT).await.unwrap();
+        });
+    }
+
+    #[test]
+    fn tx_pipeline_stats() {
+        // One conduit per priority, i.e. QoS is enabled
+        let conduits = (0..Priority::NUM)
+            .map(|_| TransportConduitTx::make(SEQ_NUM_RES).unwrap())
+            .collect::<Vec<_>>();
+        let (producer, mut consumer) = TransmissionPipeline::make(CONFIG, conduits.as_slice());
+
+        // Congest the data priority
+        let payload_size = (CONFIG.batch_size / 2) as usize;
+        let message = ZenohMessage::make_data(
+            "test".into(),
+            ZBuf::from(vec![0_u8; payload_size]),
+            Channel {
+                priority: Priority::Data,
+                reliability: Reliability::BestEffort,
+            },
+            CongestionControl::Drop,
+            None,
+            None,
+            None,
+            None,
+        );
+        let mut pushed = 0;
+        while producer.push_zenoh_message(message.clone()) {
+            pushed += 1;
+        }
+
+        let stats = producer.stats();
+        let data = stats[Priority::Data as usize];
+        assert_eq!(data.capacity, CONFIG.queue_size[Priority::Data as usize]);
+        assert_eq!(data.depth, data.capacity);
+        assert_eq!(data.pushed, pushed);
+        assert_eq!(data.dropped, 1);
+        let control = stats[Priority::Control as usize];
+        assert_eq!((control.depth, control.pushed, control.dropped), (0, 0, 0));
+
+        // The batches are back once sent
+        task::block_on(async {
+            let (batch, priority) = consumer.pull().timeout(TIMEOUT).await.unwrap().unwrap();
+            assert_eq!(priority, Priority::Data as usize);
+            consumer.refill(batch, priority);
+        });
+        assert_eq!(producer.stats()[Priority::Data as usize].depth, 0);
+    }
+
     #[test]
     #[ignore]
     fn tx_pipeline_thr() {
@@ -14,6 +14,9 @@
 use zenoh_protocol::core::ZInt;
 use zenoh_result::{bail, ZResult};
 
+/// The smallest sequence number resolution allowing to tell a new message from a duplicate.
+pub(crate) const SN_RESOLUTION_MIN: ZInt = 2;
+
 /// Sequence Number
 ///
 /// Zenoh sequence numbers have a negotiable resolution. Each session can
@@ -44,10 +47,18 @@ impl SeqNum {
     ///                  - 16_386 (i.e., 2^14)
     ///                  - 2_097_152 (i.e., 2^21)
     ///
-    /// This funtion will panic if `value` is out of bound w.r.t. `resolution`. That is if
-    /// `value` is greater or equal than `resolution`.
+    /// This funtion will return an error if `value` is out of bound w.r.t. `resolution`. That is if
+    /// `value` is greater or equal than `resolution`, or if `resolution` is smaller than
+    /// [`SN_RESOLUTION_MIN`].
     ///
     pub(crate) fn make(value: ZInt, resolution: ZInt) -> ZResult<SeqNum> {
+        if resolution < SN_RESOLUTION_MIN {
+            bail!(
+                "The sequence number resolution must be at least {}: {}",
+                SN_RESOLUTION_MIN,
+                resolution
+            );
+        }
         let mut sn = SeqNum {
             value: 0,
             semi_int: resolution >> 1,
@@ -96,7 +107,9 @@ impl SeqNum {
     ///
     /// ## Case 2: sna > snb
     ///
-    /// In this case *sna* precedes *snb* iff (sna - snb) > semi_int.
+    /// In this case *sna* precedes *snb* iff (sna - snb) > semi_int. With a resolution
+    /// of 2 or 3 (i.e. semi_int is 1) this never holds, so *sna* then precedes *snb*
+    /// iff *snb* is the sequence number following *sna*.
     ///
     /// # Arguments
     ///
@@ -108,8 +121,10 @@ impl SeqNum {
 
         let res = if value > self.value {
             value - self.value <= self.semi_int
-        } else {
+        } else if self.semi_int > 1 {
             self.value - value > self.semi_int
+        } else {
+            value < self.value && self.resolution - (self.value - value) == 1
         };
 
         Ok(res)
@@ -281,6 +296,28 @@ mod tests {
         assert!(res.unwrap());
     }
 
+    #[test]
+    fn sn_precedence_small_resolution() {
+        assert!(SeqNum::make(0, 0).is_err());
+        assert!(SeqNum::make(0, 1).is_err());
+
+        // The sequence numbers alternate between 0 and 1
+        let mut sn0a = SeqNum::make(1, 2).unwrap();
+        assert!(sn0a.precedes(0).unwrap());
+        assert!(!sn0a.precedes(1).unwrap());
+        sn0a.increment();
+        assert_eq!(sn0a.get(), 0);
+        assert!(sn0a.precedes(1).unwrap());
+        assert!(!sn0a.precedes(0).unwrap());
+
+        // A wrapped around sequence number is ahead if less than half the resolution away
+        let sn0a = SeqNum::make(3, 4).unwrap();
+        assert!(sn0a.precedes(0).unwrap());
+        assert!(!sn0a.precedes(1).unwrap());
+        assert!(!sn0a.precedes(2).unwrap());
+        assert!(!sn0a.precedes(3).unwrap());
+    }
+
     #[test]
     fn sn_generation() {
         let mut sn0 = SeqNumGenerator::make(13, 14).unwrap();
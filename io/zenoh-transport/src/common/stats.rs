@@ -11,6 +11,10 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
 macro_rules! stats_struct {
     (
      $(#[$meta:meta])*
@@ -66,3 +70,118 @@ macro_rules! stats_struct {
     }
 }
 pub(crate) use stats_struct;
+
+const LATENCY_HISTOGRAM_BUCKETS: usize = 65;
+
+/// A latency histogram with exponential buckets, cheap enough to be recorded on hot paths.
+///
+/// The bucket `i > 0` counts the latencies in `[2^(i-1), 2^i)` nanoseconds,
+/// and the bucket 0 the null ones.
+pub struct LatencyHistogram {
+    buckets: [AtomicU64; LATENCY_HISTOGRAM_BUCKETS],
+    count: AtomicU64,
+    sum_ns: AtomicU64,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        LatencyHistogram {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            count: AtomicU64::new(0),
+            sum_ns: AtomicU64::new(0),
+        }
+    }
+
+    #[inline]
+    pub fn record(&self, latency: Duration) {
+        let ns = u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX);
+        let bucket = (u64::BITS - ns.leading_zeros()) as usize;
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_ns.fetch_add(ns, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> LatencyHistogramSnapshot {
+        let buckets = self
+            .buckets
+            .iter()
+            .enumerate()
+            .filter_map(|(i, bucket)| match bucket.load(Ordering::Relaxed) {
+                0 => None,
+                n => Some((bucket_upper_bound(i), n)),
+            })
+            .collect();
+        LatencyHistogramSnapshot {
+            count: self.count.load(Ordering::Relaxed),
+            sum_ns: self.sum_ns.load(Ordering::Relaxed),
+            buckets,
+        }
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn bucket_upper_bound(bucket: usize) -> u64 {
+    match bucket {
+        0 => 0,
+        i => u64::MAX >> (u64::BITS as usize - i),
+    }
+}
+
+/// A snapshot of a [`LatencyHistogram`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct LatencyHistogramSnapshot {
+    pub count: u64,
+    pub sum_ns: u64,
+    /// The non-empty buckets, as pairs of their (inclusive) upper bound in nanoseconds and count.
+    pub buckets: Vec<(u64, u64)>,
+}
+
+impl LatencyHistogramSnapshot {
+    pub fn mean(&self) -> Option<Duration> {
+        (self.count > 0).then(|| Duration::from_nanos(self.sum_ns / self.count))
+    }
+
+    /// An upper bound of the `q` quantile (e.g. 0.99), at most twice the actual value.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        let total: u64 = self.buckets.iter().map(|(_, n)| n).sum();
+        let rank = (q.clamp(0.0, 1.0) * total as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        self.buckets.iter().find_map(|(bound, n)| {
+            seen += n;
+            (seen >= rank).then_some(Duration::from_nanos(*bound))
+        })
+    }
+}
+
+#[test]
+fn latency_histogram() {
+    let histogram = LatencyHistogram::new();
+    assert_eq!(histogram.snapshot().quantile(0.5), None);
+    for ns in [0, 1, 2, 3, 100, 1_000, 1_000_000] {
+        histogram.record(Duration::from_nanos(ns));
+    }
+    histogram.record(Duration::MAX);
+
+    let snapshot = histogram.snapshot();
+    assert_eq!(snapshot.count, 8);
+    assert_eq!(
+        snapshot.buckets,
+        vec![
+            (0, 1),
+            (1, 1),
+            (3, 2),
+            (127, 1),
+            (1_023, 1),
+            ((1 << 20) - 1, 1),
+            (u64::MAX, 1)
+        ]
+    );
+    assert_eq!(snapshot.quantile(0.5), Some(Duration::from_nanos(3)));
+    assert_eq!(snapshot.quantile(0.75), Some(Duration::from_nanos(1_023)));
+    assert_eq!(snapshot.quantile(1.0), Some(Duration::from_nanos(u64::MAX)));
+}
@@ -25,6 +25,11 @@ mod primitives;
 mod shm;
 pub mod unicast;
 
+pub use common::bandwidth::BandwidthEstimate;
+pub use common::fanout::FanOut;
+pub use common::pipeline::PriorityQueueStats;
+#[cfg(feature = "stats")]
+pub use common::stats::{LatencyHistogram, LatencyHistogramSnapshot};
 pub use manager::*;
 pub use multicast::*;
 pub use primitives::*;
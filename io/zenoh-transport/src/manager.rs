@@ -11,6 +11,7 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
+use super::common::seq_num::SN_RESOLUTION_MIN;
 use super::multicast::manager::{
     TransportManagerBuilderMulticast, TransportManagerConfigMulticast,
     TransportManagerStateMulticast,
@@ -28,7 +29,7 @@ use std::sync::Arc;
 use std::sync::RwLock;
 use std::time::Duration;
 use zenoh_cfg_properties::{config::*, Properties};
-use zenoh_config::{Config, QueueConf, QueueSizeConf};
+use zenoh_config::{Config, QoSDscpConf, QueueConf, QueueSizeConf};
 use zenoh_core::zparse;
 use zenoh_crypto::{BlockCipher, PseudoRng};
 use zenoh_link::NewLinkChannelSender;
@@ -95,12 +96,16 @@ pub struct TransportManagerConfig {
     pub version: u8,
     pub zid: ZenohId,
     pub whatami: WhatAmI,
+    pub domain: ZInt,
     pub sn_resolution: ZInt,
     pub batch_size: u16,
     pub queue_size: [usize; Priority::NUM],
     pub queue_backoff: Duration,
+    pub dscp: Option<[u8; Priority::NUM]>,
+    pub adaptive_shedding: bool,
     pub defrag_buff_size: usize,
     pub link_rx_buffer_size: usize,
+    pub link_rx_watchdog: Option<Duration>,
     pub unicast: TransportManagerConfigUnicast,
     pub multicast: TransportManagerConfigMulticast,
     pub endpoint: HashMap<String, Properties>,
@@ -123,12 +128,16 @@ pub struct TransportManagerBuilder {
     version: u8,
     zid: ZenohId,
     whatami: WhatAmI,
+    domain: ZInt,
     sn_resolution: ZInt,
     batch_size: u16,
     queue_size: QueueSizeConf,
     queue_backoff: Duration,
+    dscp: QoSDscpConf,
+    adaptive_shedding: bool,
     defrag_buff_size: usize,
     link_rx_buffer_size: usize,
+    link_rx_watchdog: Option<Duration>,
     unicast: TransportManagerBuilderUnicast,
     multicast: TransportManagerBuilderMulticast,
     endpoint: HashMap<String, Properties>,
@@ -147,6 +156,11 @@ impl TransportManagerBuilder {
         self
     }
 
+    pub fn domain(mut self, domain: ZInt) -> Self {
+        self.domain = domain;
+        self
+    }
+
     pub fn sn_resolution(mut self, sn_resolution: ZInt) -> Self {
         self.sn_resolution = sn_resolution;
         self
@@ -167,6 +181,16 @@ impl TransportManagerBuilder {
         self
     }
 
+    pub fn dscp(mut self, dscp: QoSDscpConf) -> Self {
+        self.dscp = dscp;
+        self
+    }
+
+    pub fn adaptive_shedding(mut self, adaptive_shedding: bool) -> Self {
+        self.adaptive_shedding = adaptive_shedding;
+        self
+    }
+
     pub fn defrag_buff_size(mut self, defrag_buff_size: usize) -> Self {
         self.defrag_buff_size = defrag_buff_size;
         self
@@ -177,6 +201,11 @@ impl TransportManagerBuilder {
         self
     }
 
+    pub fn link_rx_watchdog(mut self, link_rx_watchdog: Option<Duration>) -> Self {
+        self.link_rx_watchdog = link_rx_watchdog;
+        self
+    }
+
     pub fn endpoint(mut self, endpoint: HashMap<String, Properties>) -> Self {
         self.endpoint = endpoint;
         self
@@ -207,6 +236,7 @@ impl TransportManagerBuilder {
         if let Some(v) = config.mode() {
             self = self.whatami(*v);
         }
+        self = self.domain(config.domain().unwrap_or(0));
 
         self = self.sn_resolution(
             config
@@ -219,7 +249,24 @@ impl TransportManagerBuilder {
         self = self.batch_size(config.transport().link().tx().batch_size().unwrap());
         self = self.defrag_buff_size(config.transport().link().rx().max_message_size().unwrap());
         self = self.link_rx_buffer_size(config.transport().link().rx().buffer_size().unwrap());
+        self = self.link_rx_watchdog(
+            config
+                .transport()
+                .link()
+                .rx()
+                .watchdog()
+                .map(Duration::from_millis),
+        );
         self = self.queue_size(config.transport().link().tx().queue().size().clone());
+        self = self.dscp(config.transport().qos().dscp().clone());
+        self = self.adaptive_shedding(
+            config
+                .transport()
+                .link()
+                .tx()
+                .adaptive_shedding()
+                .unwrap_or(false),
+        );
         self = self.tx_threads(config.transport().link().tx().threads().unwrap());
         self = self.protocols(config.transport().link().protocols().clone());
 
@@ -250,6 +297,13 @@ impl TransportManagerBuilder {
     }
 
     pub fn build(self, handler: Arc<dyn TransportEventHandler>) -> ZResult<TransportManager> {
+        if self.sn_resolution < SN_RESOLUTION_MIN {
+            bail!(
+                "Invalid sequence number resolution: {}. It must be at least {}.",
+                self.sn_resolution,
+                SN_RESOLUTION_MIN
+            );
+        }
         let unicast = self.unicast.build()?;
         let multicast = self.multicast.build()?;
 
@@ -263,16 +317,35 @@ impl TransportManagerBuilder {
         queue_size[Priority::DataLow as usize] = *self.queue_size.data_low();
         queue_size[Priority::Background as usize] = *self.queue_size.background();
 
+        let dscp = if *self.dscp.enabled() {
+            let mut dscp = [0; Priority::NUM];
+            dscp[Priority::Control as usize] = *self.dscp.control();
+            dscp[Priority::RealTime as usize] = *self.dscp.real_time();
+            dscp[Priority::InteractiveHigh as usize] = *self.dscp.interactive_high();
+            dscp[Priority::InteractiveLow as usize] = *self.dscp.interactive_low();
+            dscp[Priority::DataHigh as usize] = *self.dscp.data_high();
+            dscp[Priority::Data as usize] = *self.dscp.data();
+            dscp[Priority::DataLow as usize] = *self.dscp.data_low();
+            dscp[Priority::Background as usize] = *self.dscp.background();
+            Some(dscp)
+        } else {
+            None
+        };
+
         let config = TransportManagerConfig {
             version: self.version,
             zid: self.zid,
             whatami: self.whatami,
+            domain: self.domain,
             sn_resolution: self.sn_resolution,
             batch_size: self.batch_size,
             queue_size,
             queue_backoff: self.queue_backoff,
+            dscp,
+            adaptive_shedding: self.adaptive_shedding,
             defrag_buff_size: self.defrag_buff_size,
             link_rx_buffer_size: self.link_rx_buffer_size,
+            link_rx_watchdog: self.link_rx_watchdog,
             unicast: unicast.config,
             multicast: multicast.config,
             endpoint: self.endpoint,
@@ -305,12 +378,16 @@ impl Default for TransportManagerBuilder {
             version: VERSION,
             zid: ZenohId::rand(),
             whatami: ZN_MODE_DEFAULT.parse().unwrap(),
+            domain: 0,
             sn_resolution: SEQ_NUM_RES,
             batch_size: BATCH_SIZE,
             queue_size: queue.size,
             queue_backoff: Duration::from_nanos(backoff),
+            dscp: QoSDscpConf::default(),
+            adaptive_shedding: false,
             defrag_buff_size: zparse!(ZN_DEFRAG_BUFF_SIZE_DEFAULT).unwrap(),
             link_rx_buffer_size: zparse!(ZN_LINK_RX_BUFF_SIZE_DEFAULT).unwrap(),
+            link_rx_watchdog: None,
             endpoint: HashMap::new(),
             unicast: TransportManagerBuilderUnicast::default(),
             multicast: TransportManagerBuilderMulticast::default(),
@@ -396,6 +473,7 @@ impl TransportManager {
                 }
             }
         });
+        this.spawn_fd_monitor();
 
         this
     }
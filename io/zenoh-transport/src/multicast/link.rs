@@ -98,6 +98,7 @@ impl TransportLinkMulticast {
                 batch_size: config.batch_size.min(self.link.get_mtu()),
                 queue_size: self.transport.manager.config.queue_size,
                 backoff: self.transport.manager.config.queue_backoff,
+                is_fragmentation: true,
             };
             // The pipeline
             let (producer, consumer) = TransmissionPipeline::make(tpc, &conduit_tx);
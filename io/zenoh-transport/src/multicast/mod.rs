@@ -31,7 +31,7 @@ use transport::{TransportMulticastConfig, TransportMulticastInner};
 use zenoh_core::zread;
 use zenoh_link::Link;
 use zenoh_protocol::{core::ZInt, transport::tmsg, zenoh::ZenohMessage};
-use zenoh_result::{zerror, ZResult};
+use zenoh_result::{zerror, ErrorKind, ZResult};
 
 /*************************************/
 /*              STATS                */
@@ -85,7 +85,7 @@ impl TransportMulticast {
     fn get_transport(&self) -> ZResult<Arc<TransportMulticastInner>> {
         self.0
             .upgrade()
-            .ok_or_else(|| zerror!("Transport multicast closed").into())
+            .ok_or_else(|| zerror!((ErrorKind::SessionClosed) "Transport multicast closed").into())
     }
 
     #[inline(always)]
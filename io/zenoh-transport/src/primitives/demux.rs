@@ -14,12 +14,61 @@
 use super::Primitives;
 use crate::TransportPeerEventHandler;
 use std::any::Any;
+use zenoh_buffers::ZBuf;
 use zenoh_link::Link;
-use zenoh_protocol::zenoh::{
-    Data, Declaration, Declare, LinkStateList, Pull, Query, Unit, ZenohBody, ZenohMessage,
+use zenoh_protocol::{
+    common::{Attachment, ZExtensionCodec},
+    zenoh::{
+        Data, DataInfo, Declaration, Declare, LinkStateList, Pull, Query, QueryBody,
+        SubscriberFilters, Unit, UserAttachment, ZenohBody, ZenohMessage,
+    },
 };
 use zenoh_result::{bail, ZResult};
 
+// Take the user attachment carried in the extensions of `attachment`, if any
+fn take_user_attachment(attachment: &mut Option<Attachment>) -> Option<ZBuf> {
+    let att = attachment.as_mut()?;
+    let user = att.get::<UserAttachment>()?;
+    att.remove_extension(UserAttachment::ID);
+    if att.buffer.is_empty() && att.extensions.is_empty() {
+        *attachment = None;
+    }
+    Some(user.0)
+}
+
+// Restore the filters and the aggregation policies carried in the extensions of `attachment`
+// into the subscriber declarations
+fn restore_subscriber_infos(declarations: &mut [Declaration], attachment: &Option<Attachment>) {
+    let attachment = match attachment.as_ref() {
+        Some(attachment) => attachment,
+        None => return,
+    };
+    if let Some(filters) = attachment.get::<SubscriberFilters>() {
+        for (index, filter) in filters.0 {
+            match declarations.get_mut(index as usize) {
+                Some(Declaration::Subscriber(subscriber)) => subscriber.info.filter = Some(filter),
+                _ => log::warn!(
+                    "Ignoring the filter of unknown subscriber declaration {}",
+                    index
+                ),
+            }
+        }
+    }
+    if let Some(aggregations) = attachment.get::<SubscriberAggregations>() {
+        for (index, aggregation) in aggregations.0 {
+            match declarations.get_mut(index as usize) {
+                Some(Declaration::Subscriber(subscriber)) => {
+                    subscriber.info.aggregation = Some(aggregation)
+                }
+                _ => log::warn!(
+                    "Ignoring the aggregation policy of unknown subscriber declaration {}",
+                    index
+                ),
+            }
+        }
+    }
+}
+
 pub struct DeMux<P: Primitives> {
     primitives: P,
 }
@@ -31,9 +80,15 @@ impl<P: Primitives> DeMux<P> {
 }
 
 impl<P: 'static + Primitives> TransportPeerEventHandler for DeMux<P> {
-    fn handle_message(&self, msg: ZenohMessage) -> ZResult<()> {
+    fn handle_message(&self, mut msg: ZenohMessage) -> ZResult<()> {
         match msg.body {
-            ZenohBody::Declare(Declare { declarations, .. }) => {
+            ZenohBody::Declare(Declare { mut declarations }) => {
+                restore_subscriber_infos(&mut declarations, &msg.attachment);
+                // forward the declarations received together as a batch
+                let batch = declarations.len() > 1;
+                if batch {
+                    self.primitives.start_batch();
+                }
                 for declaration in declarations {
                     match declaration {
                         Declaration::Resource(r) => {
@@ -65,37 +120,54 @@ impl<P: 'static + Primitives> TransportPeerEventHandler for DeMux<P> {
                             self.primitives
                                 .forget_queryable(&q.key, msg.routing_context);
                         }
+                        Declaration::Interest(i) => {
+                            self.primitives.decl_interest(&i.key);
+                        }
                     }
                 }
+                if batch {
+                    self.primitives.end_batch();
+                }
             }
 
             ZenohBody::Data(Data {
                 key,
-                data_info,
+                mut data_info,
                 payload,
                 congestion_control,
                 reply_context,
-            }) => match reply_context {
-                None => {
-                    self.primitives.send_data(
-                        &key,
-                        payload,
-                        msg.channel,
-                        congestion_control,
-                        data_info,
-                        msg.routing_context,
-                    );
+            }) => {
+                if let Some(user) = take_user_attachment(&mut msg.attachment) {
+                    data_info.get_or_insert_with(DataInfo::default).attachment = Some(user);
                 }
-                Some(rep) => match rep.replier {
-                    Some(replier) => {
-                        self.primitives
-                            .send_reply_data(rep.qid, replier.id, key, data_info, payload);
-                    }
+                match reply_context {
                     None => {
-                        bail!("ReplyData with no replier_id")
+                        self.primitives.send_data(
+                            &key,
+                            payload,
+                            msg.channel,
+                            congestion_control,
+                            data_info,
+                            msg.routing_context,
+                        );
                     }
-                },
-            },
+                    Some(rep) => match rep.replier {
+                        Some(replier) => {
+                            self.primitives.send_reply_data(
+                                rep.qid,
+                                replier.id,
+                                key,
+                                data_info,
+                                payload,
+                                msg.attachment,
+                            );
+                        }
+                        None => {
+                            bail!("ReplyData with no replier_id")
+                        }
+                    },
+                }
+            }
 
             ZenohBody::Unit(Unit { reply_context, .. }) => {
                 if let Some(rep) = reply_context {
@@ -111,9 +183,17 @@ impl<P: 'static + Primitives> TransportPeerEventHandler for DeMux<P> {
                 qid,
                 target,
                 consolidation,
-                body,
+                mut body,
                 ..
             }) => {
+                if let Some(user) = take_user_attachment(&mut msg.attachment) {
+                    body.get_or_insert_with(|| QueryBody {
+                        data_info: DataInfo::default(),
+                        payload: ZBuf::default(),
+                    })
+                    .data_info
+                    .attachment = Some(user);
+                }
                 self.primitives.send_query(
                     &key,
                     &parameters,
@@ -18,6 +18,7 @@ pub use demux::*;
 pub use mux::*;
 use zenoh_buffers::ZBuf;
 use zenoh_protocol::{
+    common::Attachment,
     core::{
         Channel, CongestionControl, ConsolidationMode, QueryTarget, QueryableInfo, SubInfo,
         WireExpr, ZInt, ZenohId,
@@ -77,6 +78,7 @@ pub trait Primitives: Send + Sync {
         key_expr: WireExpr,
         info: Option<DataInfo>,
         payload: ZBuf,
+        attachment: Option<Attachment>,
     );
 
     fn send_reply_final(&self, qid: ZInt);
@@ -90,6 +92,17 @@ pub trait Primitives: Send + Sync {
     );
 
     fn send_close(&self);
+
+    /// Starts a batch of declarations: until the matching [`end_batch`](Primitives::end_batch),
+    /// the declarations may be packed together rather than sent one by one.
+    fn start_batch(&self) {}
+
+    /// Ends a batch of declarations started by [`start_batch`](Primitives::start_batch).
+    fn end_batch(&self) {}
+
+    /// Declares a key expression of interest: the remote end may restrict the declarations
+    /// it sends to the ones intersecting the declared interests.
+    fn decl_interest(&self, _key_expr: &WireExpr) {}
 }
 
 #[derive(Default)]
@@ -154,6 +167,7 @@ impl Primitives for DummyPrimitives {
         _key_expr: WireExpr,
         _info: Option<DataInfo>,
         _payload: ZBuf,
+        _attachment: Option<Attachment>,
     ) {
     }
     fn send_reply_final(&self, _qid: ZInt) {}
@@ -13,47 +13,160 @@
 //
 use super::super::TransportUnicast;
 use super::Primitives;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
 use zenoh_buffers::ZBuf;
+use zenoh_core::zlock;
 use zenoh_protocol::{
+    common::Attachment,
     core::{
         Channel, CongestionControl, ConsolidationMode, QueryTarget, QueryableInfo, SubInfo,
         WireExpr, ZInt, ZenohId,
     },
     zenoh::{
         zmsg, DataInfo, Declaration, ForgetPublisher, ForgetQueryable, ForgetResource,
-        ForgetSubscriber, Publisher, QueryBody, Queryable, ReplierInfo, ReplyContext, Resource,
-        RoutingContext, Subscriber, ZenohMessage,
+        ForgetSubscriber, Interest, Publisher, QueryBody, Queryable, ReplierInfo, ReplyContext,
+        Resource, RoutingContext, Subscriber, SubscriberAggregations, SubscriberFilters,
+        UserAttachment, ZenohMessage,
     },
 };
 
+// Move the user attachment of `data_info`, if any, in the extensions of `attachment`
+fn with_user_attachment(
+    attachment: Option<Attachment>,
+    data_info: Option<&mut DataInfo>,
+) -> Option<Attachment> {
+    match data_info.and_then(|di| di.attachment.take()) {
+        Some(user) => {
+            let mut attachment = attachment.unwrap_or_else(|| Attachment::new(ZBuf::default()));
+            attachment.set(&UserAttachment(user));
+            Some(attachment)
+        }
+        None => attachment,
+    }
+}
+
+// Make the declare message of `declarations`, moving the filters and the aggregation policies
+// of the subscriber declarations in the extensions of its attachment
+fn make_declare(
+    mut declarations: Vec<Declaration>,
+    routing_context: Option<RoutingContext>,
+) -> ZenohMessage {
+    let mut filters = SubscriberFilters::default();
+    let mut aggregations = SubscriberAggregations::default();
+    for (index, declaration) in declarations.iter_mut().enumerate() {
+        if let Declaration::Subscriber(subscriber) = declaration {
+            if let Some(filter) = subscriber.info.filter.take() {
+                filters.0.push((index as ZInt, filter));
+            }
+            if let Some(aggregation) = subscriber.info.aggregation.take() {
+                aggregations.0.push((index as ZInt, aggregation));
+            }
+        }
+    }
+    let attachment = (!filters.0.is_empty() || !aggregations.0.is_empty()).then(|| {
+        let mut attachment = Attachment::new(ZBuf::default());
+        if !filters.0.is_empty() {
+            attachment.set(&filters);
+        }
+        if !aggregations.0.is_empty() {
+            attachment.set(&aggregations);
+        }
+        attachment
+    });
+    ZenohMessage::make_declare(declarations, routing_context, attachment)
+}
+
+#[derive(Default)]
+struct DeclarationsBatch {
+    depth: usize,
+    declarations: Vec<Declaration>,
+    routing_context: Option<RoutingContext>,
+}
+
 pub struct Mux {
     handler: TransportUnicast,
+    // whether a batch is started or some declarations are pending
+    batching: AtomicBool,
+    batch: Mutex<DeclarationsBatch>,
 }
 
 impl Mux {
     pub fn new(handler: TransportUnicast) -> Mux {
-        Mux { handler }
+        Mux {
+            handler,
+            batching: AtomicBool::new(false),
+            batch: Mutex::new(DeclarationsBatch::default()),
+        }
+    }
+
+    fn declare(&self, declaration: Declaration, routing_context: Option<RoutingContext>) {
+        if self.batching.load(Ordering::Acquire) {
+            let mut batch = zlock!(self.batch);
+            if batch.depth > 0 {
+                if !batch.declarations.is_empty() && batch.routing_context != routing_context {
+                    self.send_batch(&mut batch);
+                }
+                batch.routing_context = routing_context;
+                batch.declarations.push(declaration);
+                return;
+            }
+            self.send_batch(&mut batch);
+            self.batching.store(false, Ordering::Release);
+        }
+        let _ = self
+            .handler
+            .handle_message(make_declare(vec![declaration], routing_context));
+    }
+
+    // send the pending declarations before any other message, to preserve their order
+    fn flush(&self) {
+        if self.batching.load(Ordering::Acquire) {
+            let mut batch = zlock!(self.batch);
+            self.send_batch(&mut batch);
+            if batch.depth == 0 {
+                self.batching.store(false, Ordering::Release);
+            }
+        }
+    }
+
+    fn send_batch(&self, batch: &mut DeclarationsBatch) {
+        if !batch.declarations.is_empty() {
+            let declarations = std::mem::take(&mut batch.declarations);
+            let _ = self
+                .handler
+                .handle_message(make_declare(declarations, batch.routing_context.take()));
+        }
     }
 }
 
 impl Primitives for Mux {
+    fn start_batch(&self) {
+        let mut batch = zlock!(self.batch);
+        batch.depth += 1;
+        self.batching.store(true, Ordering::Release);
+    }
+
+    fn end_batch(&self) {
+        let mut batch = zlock!(self.batch);
+        batch.depth = batch.depth.saturating_sub(1);
+        if batch.depth == 0 {
+            self.send_batch(&mut batch);
+            self.batching.store(false, Ordering::Release);
+        }
+    }
+
     fn decl_resource(&self, expr_id: ZInt, key_expr: &WireExpr) {
         let d = Declaration::Resource(Resource {
             expr_id,
             key: key_expr.to_owned(),
         });
-        let decls = vec![d];
-        let _ = self
-            .handler
-            .handle_message(ZenohMessage::make_declare(decls, None, None));
+        self.declare(d, None);
     }
 
     fn forget_resource(&self, expr_id: ZInt) {
         let d = Declaration::ForgetResource(ForgetResource { expr_id });
-        let decls = vec![d];
-        let _ = self
-            .handler
-            .handle_message(ZenohMessage::make_declare(decls, None, None));
+        self.declare(d, None);
     }
 
     fn decl_subscriber(
@@ -66,40 +179,28 @@ impl Primitives for Mux {
             key: key_expr.to_owned(),
             info: sub_info.clone(),
         });
-        let decls = vec![d];
-        let _ =
-            self.handler
-                .handle_message(ZenohMessage::make_declare(decls, routing_context, None));
+        self.declare(d, routing_context);
     }
 
     fn forget_subscriber(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
         let d = Declaration::ForgetSubscriber(ForgetSubscriber {
             key: key_expr.to_owned(),
         });
-        let decls = vec![d];
-        let _ =
-            self.handler
-                .handle_message(ZenohMessage::make_declare(decls, routing_context, None));
+        self.declare(d, routing_context);
     }
 
     fn decl_publisher(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
         let d = Declaration::Publisher(Publisher {
             key: key_expr.to_owned(),
         });
-        let decls = vec![d];
-        let _ =
-            self.handler
-                .handle_message(ZenohMessage::make_declare(decls, routing_context, None));
+        self.declare(d, routing_context);
     }
 
     fn forget_publisher(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
         let d = Declaration::ForgetPublisher(ForgetPublisher {
             key: key_expr.to_owned(),
         });
-        let decls = vec![d];
-        let _ =
-            self.handler
-                .handle_message(ZenohMessage::make_declare(decls, routing_context, None));
+        self.declare(d, routing_context);
     }
 
     fn decl_queryable(
@@ -112,20 +213,21 @@ impl Primitives for Mux {
             key: key_expr.to_owned(),
             info: qabl_info.clone(),
         });
-        let decls = vec![d];
-        let _ =
-            self.handler
-                .handle_message(ZenohMessage::make_declare(decls, routing_context, None));
+        self.declare(d, routing_context);
     }
 
     fn forget_queryable(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
         let d = Declaration::ForgetQueryable(ForgetQueryable {
             key: key_expr.to_owned(),
         });
-        let decls = vec![d];
-        let _ =
-            self.handler
-                .handle_message(ZenohMessage::make_declare(decls, routing_context, None));
+        self.declare(d, routing_context);
+    }
+
+    fn decl_interest(&self, key_expr: &WireExpr) {
+        let d = Declaration::Interest(Interest {
+            key: key_expr.to_owned(),
+        });
+        self.declare(d, None);
     }
 
     fn send_data(
@@ -134,9 +236,11 @@ impl Primitives for Mux {
         payload: ZBuf,
         channel: Channel,
         cogestion_control: CongestionControl,
-        data_info: Option<DataInfo>,
+        mut data_info: Option<DataInfo>,
         routing_context: Option<RoutingContext>,
     ) {
+        self.flush();
+        let attachment = with_user_attachment(None, data_info.as_mut());
         let _ = self.handler.handle_message(ZenohMessage::make_data(
             key_expr.to_owned(),
             payload,
@@ -145,7 +249,7 @@ impl Primitives for Mux {
             data_info,
             routing_context,
             None,
-            None,
+            attachment,
         ));
     }
 
@@ -156,9 +260,11 @@ impl Primitives for Mux {
         qid: ZInt,
         target: QueryTarget,
         consolidation: ConsolidationMode,
-        body: Option<QueryBody>,
+        mut body: Option<QueryBody>,
         routing_context: Option<RoutingContext>,
     ) {
+        self.flush();
+        let attachment = with_user_attachment(None, body.as_mut().map(|b| &mut b.data_info));
         let target_opt = if target == QueryTarget::default() {
             None
         } else {
@@ -172,7 +278,7 @@ impl Primitives for Mux {
             consolidation,
             body,
             routing_context,
-            None,
+            attachment,
         ));
     }
 
@@ -181,9 +287,12 @@ impl Primitives for Mux {
         qid: ZInt,
         replier_id: ZenohId,
         key_expr: WireExpr,
-        data_info: Option<DataInfo>,
+        mut data_info: Option<DataInfo>,
         payload: ZBuf,
+        attachment: Option<Attachment>,
     ) {
+        self.flush();
+        let attachment = with_user_attachment(attachment, data_info.as_mut());
         let _ = self.handler.handle_message(ZenohMessage::make_data(
             key_expr.to_owned(),
             payload,
@@ -192,11 +301,12 @@ impl Primitives for Mux {
             data_info,
             None,
             Some(ReplyContext::new(qid, Some(ReplierInfo { id: replier_id }))),
-            None,
+            attachment,
         ));
     }
 
     fn send_reply_final(&self, qid: ZInt) {
+        self.flush();
         let _ = self.handler.handle_message(ZenohMessage::make_unit(
             zmsg::default_channel::REPLY,
             zmsg::default_congestion_control::REPLY,
@@ -212,6 +322,7 @@ impl Primitives for Mux {
         pull_id: ZInt,
         max_samples: &Option<ZInt>,
     ) {
+        self.flush();
         let _ = self.handler.handle_message(ZenohMessage::make_pull(
             is_final,
             key_expr.to_owned(),
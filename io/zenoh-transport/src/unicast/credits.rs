@@ -0,0 +1,317 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+#[cfg(loom)]
+use loom::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+use std::collections::VecDeque;
+#[cfg(not(loom))]
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+use zenoh_core::zlock;
+use zenoh_protocol::{
+    core::{Priority, ZInt},
+    zenoh::ZenohMessage,
+};
+
+/// The number of messages per priority queued while waiting for credits, beyond which they are dropped.
+pub(crate) const CREDITS_QUEUE_SIZE: usize = 1_024;
+
+/// What to do with a message submitted to [`TransportCreditsTx::take`].
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Take<T> {
+    /// A credit was taken: send the message now.
+    Send(T),
+    /// The message waits for the remote node to grant more credits.
+    Queued,
+    /// The message is dropped for lack of credits.
+    Refused,
+}
+
+struct CreditsState<T> {
+    credits: [Option<ZInt>; Priority::NUM],
+    waiting: [VecDeque<T>; Priority::NUM],
+    closed: bool,
+}
+
+/// The credits granted by the remote node on each priority, i.e. the number of zenoh messages
+/// this node may still send before waiting for more. A priority on which the remote node never
+/// granted any credit is unlimited.
+///
+/// Taking a credit never blocks: when the credits are exhausted, the droppable messages are
+/// refused while the others are queued, in order, until the remote node grants more.
+pub(crate) struct TransportCreditsTx<T = ZenohMessage> {
+    state: Mutex<CreditsState<T>>,
+}
+
+impl<T> TransportCreditsTx<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Mutex::new(CreditsState {
+                credits: [None; Priority::NUM],
+                waiting: std::array::from_fn(|_| VecDeque::new()),
+                closed: false,
+            }),
+        }
+    }
+
+    /// Take a credit to send `msg` on `priority`.
+    pub(crate) fn take(&self, priority: Priority, msg: T, is_droppable: bool) -> Take<T> {
+        let mut guard = zlock!(self.state);
+        if guard.closed {
+            return Take::Refused;
+        }
+        let idx = priority as usize;
+        // the queued messages go first, for the messages to be sent in order
+        let has_waiting = !guard.waiting[idx].is_empty();
+        match guard.credits[idx].as_mut() {
+            None => Take::Send(msg),
+            Some(credit) if *credit > 0 && !has_waiting => {
+                *credit -= 1;
+                Take::Send(msg)
+            }
+            _ if is_droppable => Take::Refused,
+            _ if guard.waiting[idx].len() >= CREDITS_QUEUE_SIZE => Take::Refused,
+            _ => {
+                guard.waiting[idx].push_back(msg);
+                Take::Queued
+            }
+        }
+    }
+
+    /// Give back a credit taken for a message that couldn't be sent after all.
+    pub(crate) fn refund(&self, priority: Priority) {
+        if let Some(credit) = zlock!(self.state).credits[priority as usize].as_mut() {
+            *credit = credit.saturating_add(1);
+        }
+    }
+
+    /// Add the credits granted by the remote node on `priority`,
+    /// returning the queued messages they allow to send.
+    pub(crate) fn grant(&self, priority: Priority, credit: ZInt) -> Vec<T> {
+        let mut guard = zlock!(self.state);
+        let idx = priority as usize;
+        let mut current = guard.credits[idx].unwrap_or(0).saturating_add(credit);
+        let released = (current.min(guard.waiting[idx].len() as ZInt)) as usize;
+        current -= released as ZInt;
+        guard.credits[idx] = Some(current);
+        guard.waiting[idx].drain(..released).collect()
+    }
+
+    /// Drop the messages waiting for credits, and refuse the next ones.
+    pub(crate) fn close(&self) {
+        let mut guard = zlock!(self.state);
+        guard.closed = true;
+        guard.waiting.iter_mut().for_each(VecDeque::clear);
+    }
+
+    /// The number of messages waiting for credits on `priority`.
+    #[cfg(test)]
+    pub(crate) fn waiting(&self, priority: Priority) -> usize {
+        zlock!(self.state).waiting[priority as usize].len()
+    }
+}
+
+impl<T> Default for TransportCreditsTx<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The credits granted to the remote node: the messages it consumes are granted back, in halves
+/// of the credits configured on this node, once they have been processed. Only the reliable
+/// messages are accounted, a lost best-effort message would never be granted back.
+pub(crate) struct TransportCreditsRx {
+    credits: ZInt,
+    consumed: [AtomicU64; Priority::NUM],
+}
+
+impl TransportCreditsRx {
+    pub(crate) fn new(credits: ZInt) -> Self {
+        Self {
+            credits,
+            consumed: std::array::from_fn(|_| AtomicU64::new(0)),
+        }
+    }
+
+    /// The credits initially granted on each priority.
+    pub(crate) fn initial(&self) -> ZInt {
+        self.credits
+    }
+
+    /// Record that a message received on `priority` has been processed, returning the credits
+    /// to grant back to the remote node (if any).
+    pub(crate) fn consume(&self, priority: Priority) -> Option<ZInt> {
+        let threshold = (self.credits / 2).max(1);
+        let consumed = self.consumed[priority as usize].fetch_add(1, Ordering::AcqRel) + 1;
+        if consumed < threshold {
+            return None;
+        }
+        // Only the processing that reached the threshold grants the consumed credits back
+        self.consumed[priority as usize]
+            .compare_exchange(consumed, 0, Ordering::AcqRel, Ordering::Acquire)
+            .ok()
+    }
+
+    /// Keep the `credit` that couldn't be granted back on `priority` pending, to grant it with
+    /// the next ones.
+    pub(crate) fn restore(&self, priority: Priority, credit: ZInt) {
+        self.consumed[priority as usize].fetch_add(credit, Ordering::AcqRel);
+    }
+
+    /// Take all the credits consumed on `priority` and not granted back yet (if any),
+    /// whether they reached the threshold or not.
+    pub(crate) fn pending(&self, priority: Priority) -> Option<ZInt> {
+        let consumed = self.consumed[priority as usize].swap(0, Ordering::AcqRel);
+        (consumed > 0).then_some(consumed)
+    }
+}
+
+#[cfg(not(loom))]
+#[test]
+fn transport_credits() {
+    // Unlimited until the remote node grants credits
+    let tx = TransportCreditsTx::<u32>::new();
+    assert_eq!(tx.take(Priority::Data, 0, false), Take::Send(0));
+    assert!(tx.grant(Priority::Data, 2).is_empty());
+    assert_eq!(tx.take(Priority::Data, 1, false), Take::Send(1));
+    assert_eq!(tx.take(Priority::Data, 2, true), Take::Send(2));
+    assert_eq!(tx.take(Priority::Data, 3, true), Take::Refused);
+    assert_eq!(tx.take(Priority::Control, 4, true), Take::Send(4));
+
+    // The blocking messages are queued, without blocking the sender, until new credits
+    assert_eq!(tx.take(Priority::Data, 5, false), Take::Queued);
+    assert_eq!(tx.take(Priority::Data, 6, false), Take::Queued);
+    assert_eq!(tx.waiting(Priority::Data), 2);
+    assert_eq!(tx.grant(Priority::Data, 1), vec![5]);
+    // The queued messages go first
+    assert_eq!(tx.grant(Priority::Data, 1), vec![]);
+    assert_eq!(tx.take(Priority::Data, 7, true), Take::Refused);
+    assert_eq!(tx.grant(Priority::Data, 2), vec![6]);
+    assert_eq!(tx.take(Priority::Data, 8, true), Take::Send(8));
+
+    // A refund gives a credit back
+    assert_eq!(tx.take(Priority::Data, 9, true), Take::Refused);
+    tx.refund(Priority::Data);
+    assert_eq!(tx.take(Priority::Data, 10, true), Take::Send(10));
+
+    // The queue is bounded
+    for i in 0..CREDITS_QUEUE_SIZE as u32 {
+        assert_eq!(tx.take(Priority::Data, i, false), Take::Queued);
+    }
+    assert_eq!(tx.take(Priority::Data, 0, false), Take::Refused);
+
+    // Closing drops the queued messages
+    tx.close();
+    assert_eq!(tx.waiting(Priority::Data), 0);
+    assert!(tx.grant(Priority::Data, 1).is_empty());
+    assert_eq!(tx.take(Priority::RealTime, 0, false), Take::Refused);
+
+    // The processed messages are granted back by halves
+    let rx = TransportCreditsRx::new(4);
+    assert_eq!(rx.initial(), 4);
+    assert_eq!(rx.consume(Priority::Data), None);
+    assert_eq!(rx.consume(Priority::Data), Some(2));
+    assert_eq!(rx.consume(Priority::RealTime), None);
+    assert_eq!(rx.consume(Priority::Data), None);
+    assert_eq!(rx.consume(Priority::Data), Some(2));
+
+    // The credits that couldn't be granted back are granted with the next ones
+    rx.restore(Priority::Data, 2);
+    assert_eq!(rx.consume(Priority::Data), Some(3));
+    // The pending credits are all taken at once
+    assert_eq!(rx.pending(Priority::RealTime), Some(1));
+    assert_eq!(rx.pending(Priority::RealTime), None);
+}
+
+// Model-checked with `RUSTFLAGS="--cfg loom" cargo test -p zenoh-transport --release credits`
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use super::*;
+    use loom::sync::Arc;
+    use loom::thread;
+
+    #[test]
+    fn credits_grant_releases_the_queued_messages() {
+        loom::model(|| {
+            let tx = Arc::new(TransportCreditsTx::<u32>::new());
+            tx.grant(Priority::Data, 0);
+            let c_tx = tx.clone();
+            let taker = thread::spawn(move || c_tx.take(Priority::Data, 1, false));
+            let released = tx.grant(Priority::Data, 1);
+            // Either the message was queued and released by the grant, or it took the credit
+            match taker.join().unwrap() {
+                Take::Queued => assert_eq!(released, vec![1]),
+                Take::Send(1) => assert!(released.is_empty()),
+                other => panic!("Unexpected {:?}", other),
+            }
+            assert_eq!(tx.waiting(Priority::Data), 0);
+        });
+    }
+
+    #[test]
+    fn credits_are_never_overdrawn() {
+        loom::model(|| {
+            let tx = Arc::new(TransportCreditsTx::<u32>::new());
+            tx.grant(Priority::Data, 1);
+            let takers: Vec<_> = (0..2)
+                .map(|i| {
+                    let c_tx = tx.clone();
+                    thread::spawn(move || c_tx.take(Priority::Data, i, true))
+                })
+                .collect();
+            let sent = takers
+                .into_iter()
+                .map(|t| t.join().unwrap())
+                .filter(|take| matches!(take, Take::Send(_)))
+                .count();
+            assert_eq!(sent, 1);
+        });
+    }
+
+    #[test]
+    fn credits_close_drops_the_queued_messages() {
+        loom::model(|| {
+            let tx = Arc::new(TransportCreditsTx::<u32>::new());
+            tx.grant(Priority::Data, 0);
+            let c_tx = tx.clone();
+            let taker = thread::spawn(move || c_tx.take(Priority::Data, 1, false));
+            tx.close();
+            taker.join().unwrap();
+            assert_eq!(tx.waiting(Priority::Data), 0);
+            assert!(tx.grant(Priority::Data, 1).is_empty());
+        });
+    }
+
+    #[test]
+    fn credits_consumed_are_all_granted_back() {
+        loom::model(|| {
+            let rx = Arc::new(TransportCreditsRx::new(4));
+            let consumers: Vec<_> = (0..3)
+                .map(|_| {
+                    let c_rx = rx.clone();
+                    thread::spawn(move || c_rx.consume(Priority::Data).unwrap_or(0))
+                })
+                .collect();
+            let granted: ZInt = consumers.into_iter().map(|c| c.join().unwrap()).sum();
+            let pending = rx.consumed[Priority::Data as usize].load(Ordering::Acquire);
+            // No consumed credit is lost, and no more than half of them stays pending
+            assert_eq!(granted + pending, 3);
+            assert!(pending < 2);
+        });
+    }
+}
@@ -28,11 +28,16 @@ use zenoh_link::LinkUnicast;
 use zenoh_protocol::{
     common::Attachment,
     core::Property,
+    defaults::BATCH_SIZE,
     transport::{tmsg, TransportMessage},
 };
 use zenoh_result::zerror;
 
 // Send an InitAck
+pub(super) struct Input {
+    pub(super) batch_size: u16,
+}
+
 pub(super) struct Output {
     pub(super) cookie_hash: Vec<u8>,
 }
@@ -42,9 +47,26 @@ pub(super) async fn send(
     manager: &TransportManager,
     auth_link: &AuthenticatedPeerLink,
     mut input: init_syn::Output,
+    local: Input,
 ) -> AResult<Output> {
     // Compute the minimum SN Resolution
     let agreed_sn_resolution = manager.config.sn_resolution.min(input.sn_resolution);
+    // Compute the minimum batch size
+    if input.batch_size == 0 {
+        let e = zerror!("Rejecting InitSyn on {}. Invalid batch size: 0", link);
+        return Err((e.into(), Some(tmsg::close_reason::INVALID)));
+    }
+    let agreed_batch_size = local.batch_size.min(input.batch_size);
+    // Only answer with the agreed batch size if the initiator advertised its own:
+    // the initiators that do not negotiate it keep the default one
+    let batch_size = if input.batch_size == BATCH_SIZE {
+        None
+    } else {
+        Some(agreed_batch_size)
+    };
+    // Compute the features supported by both sides
+    let features = manager.get_features_unicast();
+    let agreed_features = features & input.features;
 
     // Build the fields for the InitAck message
     let whatami = manager.config.whatami;
@@ -61,6 +83,8 @@ pub(super) async fn send(
         zid: input.zid,
         sn_resolution: agreed_sn_resolution,
         is_qos: input.is_qos,
+        batch_size: agreed_batch_size,
+        features: agreed_features,
         nonce: zasynclock!(manager.prng).gen_range(0..agreed_sn_resolution),
         properties: EstablishmentProperties::new(),
     };
@@ -133,6 +157,9 @@ pub(super) async fn send(
         azid,
         sn_resolution,
         input.is_qos,
+        batch_size,
+        features,
+        manager.config.domain,
         cookie,
         attachment,
     );
@@ -15,10 +15,12 @@ use std::convert::TryFrom;
 //
 use super::super::{AuthenticatedPeerLink, EstablishmentProperties};
 use super::AResult;
+use crate::common::seq_num::SN_RESOLUTION_MIN;
 use crate::TransportManager;
 use zenoh_link::LinkUnicast;
 use zenoh_protocol::{
     core::{WhatAmI, ZInt, ZenohId},
+    defaults::BATCH_SIZE,
     transport::{tmsg, TransportBody},
 };
 use zenoh_result::zerror;
@@ -33,6 +35,8 @@ pub(super) struct Output {
     pub(super) zid: ZenohId,
     pub(super) sn_resolution: ZInt,
     pub(super) is_qos: bool,
+    pub(super) batch_size: u16,
+    pub(super) features: ZInt,
     pub(super) init_syn_properties: EstablishmentProperties,
 }
 pub(super) async fn recv(
@@ -55,6 +59,8 @@ pub(super) async fn recv(
     }
 
     let mut msg = messages.remove(0);
+    let domain = msg.init_domain();
+    let batch_size = msg.init_batch_size().unwrap_or(BATCH_SIZE);
     let init_syn = match msg.body {
         TransportBody::InitSyn(init_syn) => init_syn,
         _ => {
@@ -93,6 +99,28 @@ pub(super) async fn recv(
         return Err((e.into(), Some(tmsg::close_reason::INVALID)));
     }
 
+    // Check if the SN resolution allows to tell the new messages from the duplicates
+    if init_syn.sn_resolution < SN_RESOLUTION_MIN {
+        let e = zerror!(
+            "Rejecting InitSyn on {}. Invalid sn resolution: {}",
+            link,
+            init_syn.sn_resolution
+        );
+        return Err((e.into(), Some(tmsg::close_reason::INVALID)));
+    }
+
+    // Check if the peer belongs to the same domain
+    if domain != manager.config.domain {
+        let e = zerror!(
+            "Rejecting InitSyn on {} because peer {} belongs to domain {} instead of {}",
+            link,
+            init_syn.zid,
+            domain,
+            manager.config.domain
+        );
+        return Err((e.into(), Some(tmsg::close_reason::DOMAIN)));
+    }
+
     // Validate the InitSyn with the peer authenticators
     let init_syn_properties: EstablishmentProperties = match msg.attachment.take() {
         Some(att) => EstablishmentProperties::try_from(&att)
@@ -105,6 +133,8 @@ pub(super) async fn recv(
         zid: init_syn.zid,
         sn_resolution: init_syn.sn_resolution,
         is_qos: init_syn.is_qos,
+        batch_size,
+        features: init_syn.features,
         init_syn_properties,
     };
     Ok(output)
@@ -18,7 +18,7 @@ mod open_syn;
 
 use crate::unicast::establishment::authenticator::AuthenticatedPeerLink;
 use crate::unicast::establishment::{
-    close_link, transport_finalize, transport_init, InputFinalize,
+    close_link, init_error_to_close_reason, transport_finalize, transport_init, InputFinalize,
 };
 use crate::TransportManager;
 use zenoh_link::{LinkUnicast, LinkUnicastDirection};
@@ -48,7 +48,10 @@ pub(crate) async fn accept_link(
     }
 
     let output = step!(init_syn::recv(link, manager, auth_link).await);
-    let output = step!(init_ack::send(link, manager, auth_link, output).await);
+    let input = init_ack::Input {
+        batch_size: manager.get_listener_batch_size(link).min(link.get_mtu()),
+    };
+    let output = step!(init_ack::send(link, manager, auth_link, output, input).await);
     let output = step!(open_syn::recv(link, manager, auth_link, output).await);
 
     // Initialize the transport
@@ -59,10 +62,12 @@ pub(crate) async fn accept_link(
         sn_resolution: output.cookie.sn_resolution,
         is_shm: output.is_shm,
         is_qos: output.cookie.is_qos,
+        features: output.cookie.features,
     };
-    let transport = step!(transport_init(manager, input)
-        .await
-        .map_err(|e| (e, Some(tmsg::close_reason::INVALID))));
+    let transport = step!(transport_init(manager, input).await.map_err(|e| {
+        let reason = init_error_to_close_reason(&e);
+        (e, Some(reason))
+    }));
 
     // OPEN handshake
     macro_rules! step {
@@ -112,11 +117,13 @@ pub(crate) async fn accept_link(
         attachment: output.open_ack_attachment,
     };
     let lease = output.lease;
+    let batch_size = output.cookie.batch_size;
     step!(open_ack::send(link, manager, auth_link, input).await);
 
     let input = InputFinalize {
         transport: transport.clone(),
         lease,
+        batch_size,
     };
     step!(transport_finalize(link, manager, input)
         .await
@@ -120,18 +120,31 @@ impl LinkUnicastAuthenticatorTrait for DummyLinkUnicastAuthenticator {
 /*************************************/
 /*              PEER                 */
 /*************************************/
-#[derive(PartialEq, Eq, Hash)]
-#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum PeerAuthenticatorId {
-    Reserved = 0,
-    Shm = 1,
-    UserPassword = 2,
-    PublicKey = 3,
+    Reserved,
+    Shm,
+    UserPassword,
+    PublicKey,
+    /// An authenticator provided outside of this crate, e.g. by a plugin.
+    /// Both ends of a link need to agree on the same custom id.
+    Custom(u8),
+}
+
+impl PeerAuthenticatorId {
+    /// The first property key used by custom authenticators.
+    pub const CUSTOM_BASE: ZInt = 0x80;
 }
 
 impl From<PeerAuthenticatorId> for ZInt {
     fn from(pa: PeerAuthenticatorId) -> ZInt {
-        pa as ZInt
+        match pa {
+            PeerAuthenticatorId::Reserved => 0,
+            PeerAuthenticatorId::Shm => 1,
+            PeerAuthenticatorId::UserPassword => 2,
+            PeerAuthenticatorId::PublicKey => 3,
+            PeerAuthenticatorId::Custom(id) => PeerAuthenticatorId::CUSTOM_BASE + id as ZInt,
+        }
     }
 }
 
@@ -139,6 +152,16 @@ impl From<PeerAuthenticatorId> for ZInt {
 pub struct PeerAuthenticator(Arc<dyn PeerAuthenticatorTrait>);
 
 impl PeerAuthenticator {
+    /// Wrap a custom [`PeerAuthenticatorTrait`] implementation, e.g. a JWT or
+    /// LDAP-backed authenticator, so that it can be added to a
+    /// [`TransportManager`](crate::TransportManager).
+    pub fn new<T>(pa: T) -> Self
+    where
+        T: PeerAuthenticatorTrait + 'static,
+    {
+        Self(Arc::new(pa))
+    }
+
     pub async fn from_config(_config: &Config) -> ZResult<HashSet<PeerAuthenticator>> {
         #[allow(unused_mut)]
         let mut pas = HashSet::new();
@@ -311,6 +334,17 @@ pub trait PeerAuthenticatorTrait: Send + Sync {
     /// * `peerd_id` - The [`ZenohId`][ZenohId] of the transport being closed.
     ///
     async fn handle_close(&self, peer_id: &ZenohId);
+
+    /// Return the identity the peer has been authenticated with (e.g. a user name,
+    /// a JWT subject or a SPIFFE ID), if any. The identity is exposed by the
+    /// [`TransportManager`](crate::TransportManager) for the whole lifetime of the transport.
+    ///
+    /// # Arguments
+    /// * `peer_id` - The [`ZenohId`][ZenohId] of the authenticated peer.
+    ///
+    async fn identity(&self, _peer_id: &ZenohId) -> Option<String> {
+        None
+    }
 }
 
 /*************************************/
@@ -36,7 +36,7 @@ use zenoh_config::Config;
 use zenoh_core::{zasynclock, zparse};
 use zenoh_crypto::PseudoRng;
 use zenoh_protocol::core::{ZInt, ZenohId};
-use zenoh_result::{bail, zerror, ZResult};
+use zenoh_result::{bail, zerror, ErrorKind, ZResult};
 
 const MULTILINK_VERSION: ZInt = 1;
 
@@ -456,7 +456,7 @@ impl PeerAuthenticatorTrait for PubKeyAuthenticator {
                             if !kk.iter().any(|x| x == &init_syn_property.alice_pubkey) {
                                 // The peer is already present but no previous multilink intereset
                                 // was declared. Rejecting for inconsistent declaration.
-                                bail!("Unauthorized multilink PubKey on link: {}", link);
+                                bail!((ErrorKind::AuthenticationFailed) "Unauthorized multilink PubKey on link: {}", link);
                             }
                         }
 
@@ -30,7 +30,7 @@ use zenoh_codec::{RCodec, WCodec, Zenoh060};
 use zenoh_config::Config;
 use zenoh_core::{zasynclock, zasyncread, zasyncwrite};
 use zenoh_crypto::hmac;
-use zenoh_result::{bail, zerror, ZResult};
+use zenoh_result::{bail, zerror, ErrorKind, ZResult};
 
 const USRPWD_VERSION: ZInt = 1;
 
@@ -397,14 +397,16 @@ impl PeerAuthenticatorTrait for UserPasswordAuthenticator {
         })?;
         let password = match zasyncread!(self.lookup).get(&open_syn_property.user) {
             Some(password) => password.clone(),
-            None => bail!("Received OpenSyn with invalid user on link: {}", link),
+            None => {
+                bail!((ErrorKind::AuthenticationFailed) "Received OpenSyn with invalid user on link: {}", link)
+            }
         };
 
         // Create the HMAC of the password using the nonce received as challenge
         let key = cookie.nonce.to_le_bytes();
         let hmac = hmac::sign(&key, &password)?;
         if hmac != open_syn_property.hmac {
-            bail!("Received OpenSyn with invalid password on link: {}", link)
+            bail!((ErrorKind::AuthenticationFailed) "Received OpenSyn with invalid password on link: {}", link)
         }
 
         // Check PID validity
@@ -414,7 +416,7 @@ impl PeerAuthenticatorTrait for UserPasswordAuthenticator {
                 if open_syn_property.user != auth.credentials.user
                     || password != auth.credentials.password
                 {
-                    bail!("Received OpenSyn with invalid password on link: {}", link)
+                    bail!((ErrorKind::AuthenticationFailed) "Received OpenSyn with invalid password on link: {}", link)
                 }
                 auth.links.insert((link.src.clone(), link.dst.clone()));
             }
@@ -460,6 +462,12 @@ impl PeerAuthenticatorTrait for UserPasswordAuthenticator {
     async fn handle_close(&self, peer_id: &ZenohId) {
         zasynclock!(self.authenticated).remove(peer_id);
     }
+
+    async fn identity(&self, peer_id: &ZenohId) -> Option<String> {
+        zasynclock!(self.authenticated)
+            .get(peer_id)
+            .map(|auth| String::from_utf8_lossy(&auth.credentials.user).into_owned())
+    }
 }
 
 //noinspection ALL
@@ -26,6 +26,8 @@ pub struct Cookie {
     pub zid: ZenohId,
     pub sn_resolution: ZInt,
     pub is_qos: bool,
+    pub batch_size: u16,
+    pub features: ZInt,
     pub nonce: ZInt,
     pub properties: EstablishmentProperties,
 }
@@ -43,6 +45,8 @@ where
         self.write(&mut *writer, x.sn_resolution)?;
         let is_qos = u8::from(x.is_qos);
         self.write(&mut *writer, is_qos)?;
+        self.write(&mut *writer, x.batch_size as ZInt)?;
+        self.write(&mut *writer, x.features)?;
         self.write(&mut *writer, x.nonce)?;
         self.write(&mut *writer, x.properties.as_slice())?;
 
@@ -63,6 +67,9 @@ where
         let sn_resolution: ZInt = self.read(&mut *reader)?;
         let is_qos: u8 = self.read(&mut *reader)?;
         let is_qos = is_qos == 1;
+        let batch_size: ZInt = self.read(&mut *reader)?;
+        let batch_size = u16::try_from(batch_size).map_err(|_| DidntRead)?;
+        let features: ZInt = self.read(&mut *reader)?;
         let nonce: ZInt = self.read(&mut *reader)?;
         let mut ps: Vec<Property> = self.read(&mut *reader)?;
         let mut properties = EstablishmentProperties::new();
@@ -75,6 +82,8 @@ where
             zid,
             sn_resolution,
             is_qos,
+            batch_size,
+            features,
             nonce,
             properties,
         };
@@ -137,6 +146,8 @@ impl Cookie {
             zid: ZenohId::default(),
             sn_resolution: rng.gen(),
             is_qos: rng.gen_bool(0.5),
+            batch_size: rng.gen(),
+            features: rng.gen(),
             nonce: rng.gen(),
             properties: EstablishmentProperties::rand(),
         }
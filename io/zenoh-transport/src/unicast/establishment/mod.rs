@@ -20,6 +20,7 @@ pub(super) mod properties;
 use super::super::TransportManager;
 use super::{TransportConfigUnicast, TransportPeer, TransportUnicast};
 use authenticator::AuthenticatedPeerLink;
+pub use cookie::Cookie;
 use cookie::*;
 use properties::*;
 use rand::Rng;
@@ -28,9 +29,26 @@ use zenoh_core::{zasynclock, zasyncread};
 use zenoh_link::{Link, LinkUnicast};
 use zenoh_protocol::{
     core::{WhatAmI, ZInt, ZenohId},
-    transport::TransportMessage,
+    transport::{tmsg, TransportMessage},
 };
-use zenoh_result::ZResult;
+use zenoh_result::{ErrNo, ErrorKind, ZResult};
+
+// The kind of the error surfaced when the remote peer closes the link with `reason`.
+pub(super) fn close_reason_to_kind(reason: u8) -> ErrorKind {
+    match reason {
+        tmsg::close_reason::BUSY | tmsg::close_reason::MAX_SESSIONS => ErrorKind::Busy,
+        tmsg::close_reason::MAX_LINKS => ErrorKind::ResourceLimit,
+        _ => ErrorKind::Other,
+    }
+}
+
+// The reason sent to the remote peer when the transport can't be initialized because of `e`.
+pub(super) fn init_error_to_close_reason(e: &zenoh_result::Error) -> u8 {
+    match e.kind() {
+        ErrorKind::ResourceLimit | ErrorKind::Busy => tmsg::close_reason::BUSY,
+        _ => tmsg::close_reason::INVALID,
+    }
+}
 
 pub(super) async fn close_link(
     link: &LinkUnicast,
@@ -56,6 +74,19 @@ pub(super) async fn close_link(
     }
 }
 
+// The features in use on a transport: the ones supported by both sides, given `features`, with
+// QoS and shared memory as resulting from their own negotiation.
+pub(super) fn negotiated_features(features: ZInt, is_qos: bool, is_shm: bool) -> ZInt {
+    let mut features = features & !(tmsg::features::QOS | tmsg::features::SHARED_MEMORY);
+    if is_qos {
+        features |= tmsg::features::QOS;
+    }
+    if is_shm {
+        features |= tmsg::features::SHARED_MEMORY;
+    }
+    features
+}
+
 /*************************************/
 /*            TRANSPORT              */
 /*************************************/
@@ -65,6 +96,7 @@ pub(super) struct InputInit {
     pub(super) sn_resolution: ZInt,
     pub(super) is_shm: bool,
     pub(super) is_qos: bool,
+    pub(super) features: ZInt,
 }
 async fn transport_init(
     manager: &TransportManager,
@@ -79,6 +111,7 @@ async fn transport_init(
         sn_resolution: input.sn_resolution,
         is_shm: input.is_shm,
         is_qos: input.is_qos,
+        features: negotiated_features(input.features, input.is_qos, input.is_shm),
         initial_sn_tx,
     };
 
@@ -88,6 +121,7 @@ async fn transport_init(
 pub(super) struct InputFinalize {
     pub(super) transport: TransportUnicast,
     pub(super) lease: Duration,
+    pub(super) batch_size: u16,
 }
 // Finalize the transport, notify the callback and start the link tasks
 pub(super) async fn transport_finalize(
@@ -100,12 +134,7 @@ pub(super) async fn transport_finalize(
 
     // Start the TX loop
     let keep_alive = manager.config.unicast.lease / manager.config.unicast.keep_alive as u32;
-    transport.start_tx(
-        link,
-        &manager.tx_executor,
-        keep_alive,
-        manager.config.batch_size,
-    )?;
+    transport.start_tx(link, &manager.tx_executor, keep_alive, input.batch_size)?;
 
     // Assign a callback if the transport is new
     // Keep the lock to avoid concurrent new_transport and closing/closed notifications
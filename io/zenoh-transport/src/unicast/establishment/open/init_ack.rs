@@ -11,6 +11,7 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
+use crate::common::seq_num::SN_RESOLUTION_MIN;
 use crate::unicast::establishment::open::OResult;
 use crate::unicast::establishment::{
     authenticator::AuthenticatedPeerLink, EstablishmentProperties,
@@ -39,6 +40,8 @@ pub(super) struct Output {
     pub(super) sn_resolution: ZInt,
     pub(super) is_qos: bool,
     pub(super) is_shm: bool,
+    pub(super) batch_size: u16,
+    pub(super) features: ZInt,
     pub(super) cookie: ZSlice,
     pub(super) open_syn_attachment: Option<Attachment>,
 }
@@ -46,7 +49,7 @@ pub(super) async fn recv(
     link: &LinkUnicast,
     manager: &TransportManager,
     auth_link: &mut AuthenticatedPeerLink,
-    _input: super::init_syn::Output,
+    input: super::init_syn::Output,
 ) -> OResult<Output> {
     // Wait to read an InitAck
     let mut messages = link
@@ -66,16 +69,21 @@ pub(super) async fn recv(
     }
 
     let mut msg = messages.remove(0);
+    let domain = msg.init_domain();
+    let agreed_batch_size = msg.init_batch_size();
     let init_ack = match msg.body {
         TransportBody::InitAck(init_ack) => init_ack,
         TransportBody::Close(Close { reason, .. }) => {
             let e = zerror!(
+                (super::super::close_reason_to_kind(reason))
                 "Received a close message (reason {}) in response to an InitSyn on: {}",
                 tmsg::close_reason_to_str(reason),
                 link,
             );
             match reason {
-                tmsg::close_reason::MAX_LINKS => log::debug!("{}", e),
+                tmsg::close_reason::MAX_LINKS | tmsg::close_reason::BUSY => {
+                    log::debug!("{}", e)
+                }
                 _ => log::error!("{}", e),
             }
             return Err((e.into(), None));
@@ -93,7 +101,7 @@ pub(super) async fn recv(
 
     let sn_resolution = match init_ack.sn_resolution {
         Some(sn_resolution) => {
-            if sn_resolution > manager.config.sn_resolution {
+            if sn_resolution > manager.config.sn_resolution || sn_resolution < SN_RESOLUTION_MIN {
                 return Err((
                     zerror!(
                         "Rejecting InitAck on {}. Invalid sn resolution: {}",
@@ -109,6 +117,34 @@ pub(super) async fn recv(
         None => manager.config.sn_resolution,
     };
 
+    // Check if the peer belongs to the same domain
+    if domain != manager.config.domain {
+        return Err((
+            zerror!(
+                "Rejecting InitAck on {}. Peer {} belongs to domain {} instead of {}",
+                link,
+                init_ack.zid,
+                domain,
+                manager.config.domain
+            )
+            .into(),
+            Some(tmsg::close_reason::DOMAIN),
+        ));
+    }
+
+    // The agreed batch size can not be larger than the one proposed in the InitSyn.
+    // A responder that does not answer with a batch size keeps the proposed one.
+    let batch_size = match agreed_batch_size {
+        Some(bs) if bs == 0 || bs > input.batch_size => {
+            return Err((
+                zerror!("Rejecting InitAck on {}. Invalid batch size: {}", link, bs).into(),
+                Some(tmsg::close_reason::INVALID),
+            ));
+        }
+        Some(bs) => bs,
+        None => input.batch_size,
+    };
+
     // Store the peer id associate do this link
     auth_link.peer_id = Some(init_ack.zid);
 
@@ -176,6 +212,8 @@ pub(super) async fn recv(
         sn_resolution,
         is_qos: init_ack.is_qos,
         is_shm,
+        batch_size,
+        features: input.features & init_ack.features,
         cookie: init_ack.cookie,
         open_syn_attachment,
     };
@@ -21,19 +21,27 @@ use zenoh_core::zasyncread;
 use zenoh_link::LinkUnicast;
 use zenoh_protocol::common::Attachment;
 use zenoh_protocol::{
-    core::Property,
+    core::{Property, ZInt},
     transport::{tmsg, TransportMessage},
 };
 
 /*************************************/
 /*              OPEN                 */
 /*************************************/
-pub(super) struct Output;
+pub(super) struct Input {
+    pub(super) batch_size: u16,
+}
+
+pub(super) struct Output {
+    pub(super) batch_size: u16,
+    pub(super) features: ZInt,
+}
 
 pub(super) async fn send(
     link: &LinkUnicast,
     manager: &TransportManager,
     auth_link: &mut AuthenticatedPeerLink,
+    input: Input,
 ) -> OResult<Output> {
     let mut ps_attachment = EstablishmentProperties::new();
     for pa in zasyncread!(manager.state.unicast.peer_authenticator).iter() {
@@ -52,6 +60,7 @@ pub(super) async fn send(
     }
 
     // Build and send the InitSyn message
+    let features = manager.get_features_unicast();
     let init_syn_attachment = if ps_attachment.is_empty() {
         None
     } else {
@@ -66,6 +75,9 @@ pub(super) async fn send(
         manager.config.zid,
         manager.config.sn_resolution,
         manager.config.unicast.is_qos,
+        input.batch_size,
+        features,
+        manager.config.domain,
         init_syn_attachment,
     );
     let _ = link
@@ -73,6 +85,9 @@ pub(super) async fn send(
         .await
         .map_err(|e| (e, Some(tmsg::close_reason::GENERIC)))?;
 
-    let output = Output;
+    let output = Output {
+        batch_size: input.batch_size,
+        features,
+    };
     Ok(output)
 }
@@ -30,6 +30,7 @@ pub(crate) async fn open_link(
     link: &LinkUnicast,
     manager: &TransportManager,
     auth_link: &mut AuthenticatedPeerLink,
+    batch_size: u16,
 ) -> ZResult<TransportUnicast> {
     // INIT handshake
     macro_rules! step {
@@ -44,7 +45,10 @@ pub(crate) async fn open_link(
         };
     }
 
-    let output = step!(init_syn::send(link, manager, auth_link).await);
+    let input = init_syn::Input {
+        batch_size: batch_size.min(link.get_mtu()),
+    };
+    let output = step!(init_syn::send(link, manager, auth_link, input).await);
     let output = step!(init_ack::recv(link, manager, auth_link, output).await);
 
     // Initialize the transport
@@ -67,8 +71,10 @@ pub(crate) async fn open_link(
         sn_resolution: output.sn_resolution,
         is_shm: output.is_shm,
         is_qos: output.is_qos,
+        features: output.features,
     };
     let transport = step!(super::transport_init(manager, input).await);
+    let batch_size = output.batch_size;
 
     // OPEN handshake
     macro_rules! step {
@@ -121,6 +127,7 @@ pub(crate) async fn open_link(
     let output = InputFinalize {
         transport,
         lease: output.lease,
+        batch_size,
     };
     let transport = output.transport.clone();
     let res = transport_finalize(link, manager, output).await;
@@ -59,12 +59,15 @@ pub(super) async fn recv(
         TransportBody::OpenAck(open_ack) => open_ack,
         TransportBody::Close(Close { reason, .. }) => {
             let e = zerror!(
+                (super::super::close_reason_to_kind(reason))
                 "Received a close message (reason {}) in response to an OpenSyn on: {:?}",
                 tmsg::close_reason_to_str(reason),
                 link,
             );
             match reason {
-                tmsg::close_reason::MAX_LINKS => log::debug!("{}", e),
+                tmsg::close_reason::MAX_LINKS | tmsg::close_reason::BUSY => {
+                    log::debug!("{}", e)
+                }
                 _ => log::error!("{}", e),
             }
             return Err((e.into(), None));
@@ -15,11 +15,12 @@ use std::{
     convert::TryFrom,
     ops::{Deref, DerefMut},
 };
-use zenoh_buffers::{reader::HasReader, writer::HasWriter, ZBuf};
-use zenoh_codec::{RCodec, WCodec, Zenoh060};
+use zenoh_buffers::{reader::HasReader, SplitBuffer, ZBuf};
+use zenoh_codec::{RCodec, Zenoh060};
 use zenoh_protocol::{
-    common::Attachment,
+    common::{Attachment, ZExtension, ZExtensionCodec},
     core::{Property, ZInt},
+    transport::{InitBatchSize, InitDomain},
 };
 use zenoh_result::{bail, zerror, Error as ZError, ZResult};
 
@@ -61,6 +62,9 @@ impl EstablishmentProperties {
     }
 }
 
+// The properties are carried as the extensions of the attachment: the extensions section has the
+// layout of a list of properties, so that the peers not supporting extensions read it as such
+// along with the extensions of the message itself (e.g. the batch size of an InitSyn).
 impl TryFrom<&EstablishmentProperties> for Attachment {
     type Error = ZError;
 
@@ -69,15 +73,10 @@ impl TryFrom<&EstablishmentProperties> for Attachment {
             bail!("Can not create an attachment with zero properties")
         }
 
-        let mut zbuf = ZBuf::default();
-        let mut writer = zbuf.writer();
-        let codec = Zenoh060::default();
-
-        codec
-            .write(&mut writer, eps.0.as_slice())
-            .map_err(|_| zerror!(""))?;
-
-        let attachment = Attachment::new(zbuf);
+        let mut attachment = Attachment::new(ZBuf::default());
+        for p in eps.iter() {
+            attachment.insert_extension(ZExtension::new(p.key, ZBuf::from(p.value.clone())));
+        }
         Ok(attachment)
     }
 }
@@ -99,10 +98,25 @@ impl TryFrom<&Attachment> for EstablishmentProperties {
     type Error = ZError;
 
     fn try_from(att: &Attachment) -> Result<Self, Self::Error> {
-        let mut reader = att.buffer.reader();
-        let codec = Zenoh060::default();
-
-        let ps: Vec<Property> = codec.read(&mut reader).map_err(|_| zerror!(""))?;
+        let mut ps: Vec<Property> = att
+            .extensions
+            .iter()
+            .filter(|e| e.id != InitBatchSize::ID && e.id != InitDomain::ID)
+            .map(|e| Property {
+                key: e.id,
+                value: e.value.contiguous().into_owned(),
+            })
+            .collect();
+
+        // The peers not supporting extensions carry the properties in the buffer
+        if !att.buffer.is_empty() {
+            let mut reader = att.buffer.reader();
+            let codec = Zenoh060::default();
+            let legacy: Vec<Property> = codec
+                .read(&mut reader)
+                .map_err(|_| zerror!("Failed to decode establishment properties"))?;
+            ps.extend(legacy);
+        }
         EstablishmentProperties::try_from(ps)
     }
 }
@@ -15,23 +15,30 @@ use super::common::conduit::TransportConduitTx;
 use super::transport::TransportUnicastInner;
 #[cfg(feature = "stats")]
 use super::TransportUnicastStatsAtomic;
+use crate::common::bandwidth::BandwidthEstimator;
 use crate::common::pipeline::{
     TransmissionPipeline, TransmissionPipelineConf, TransmissionPipelineConsumer,
     TransmissionPipelineProducer,
 };
+#[cfg(feature = "stats")]
+use crate::LatencyHistogram;
 use crate::TransportExecutor;
 use async_std::prelude::FutureExt;
 use async_std::task;
 use async_std::task::JoinHandle;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use zenoh_buffers::reader::{HasReader, Reader};
 use zenoh_buffers::ZSlice;
 use zenoh_codec::{RCodec, Zenoh060};
 use zenoh_link::{LinkUnicast, LinkUnicastDirection};
-use zenoh_protocol::transport::TransportMessage;
-use zenoh_result::{bail, zerror, ZResult};
-use zenoh_sync::{RecyclingObjectPool, Signal};
+use zenoh_protocol::{
+    common::imsg,
+    core::Priority,
+    transport::{tmsg, TransportMessage},
+};
+use zenoh_result::{bail, zerror, ZError, ZResult};
+use zenoh_sync::{clock, RecyclingObjectPool, Signal};
 
 #[derive(Clone)]
 pub(super) struct TransportLinkUnicast {
@@ -41,6 +48,8 @@ pub(super) struct TransportLinkUnicast {
     pub(super) link: LinkUnicast,
     // The transmission pipeline
     pub(super) pipeline: Option<TransmissionPipelineProducer>,
+    // The bandwidth estimate of the link
+    pub(super) bandwidth: Arc<BandwidthEstimator>,
     // The transport this link is associated to
     transport: TransportUnicastInner,
     // The signals to stop TX/RX tasks
@@ -60,6 +69,7 @@ impl TransportLinkUnicast {
             transport,
             link,
             pipeline: None,
+            bandwidth: Arc::new(BandwidthEstimator::new()),
             handle_tx: None,
             signal_rx: Signal::new(),
             handle_rx: None,
@@ -76,26 +86,46 @@ impl TransportLinkUnicast {
         conduit_tx: &[TransportConduitTx],
     ) {
         if self.handle_tx.is_none() {
+            // The batch size is adapted to the MTU of each link
+            let batch_size = batch_size.min(self.link.get_mtu());
+            log::debug!("Batch size on link {}: {}", self.link, batch_size);
             let config = TransmissionPipelineConf {
                 is_streamed: self.link.is_streamed(),
-                batch_size: batch_size.min(self.link.get_mtu()),
+                batch_size,
                 queue_size: self.transport.config.manager.config.queue_size,
                 backoff: self.transport.config.manager.config.queue_backoff,
+                is_fragmentation: imsg::has_option(
+                    self.transport.config.features,
+                    tmsg::features::FRAGMENTATION,
+                ),
             };
+            // The IP marks of each priority queue, if any: a single queue carries the DATA priority
+            let dscp = self.transport.config.manager.config.dscp.map(|dscp| {
+                if conduit_tx.len() > 1 {
+                    dscp.to_vec()
+                } else {
+                    vec![dscp[Priority::default() as usize]]
+                }
+            });
             // The pipeline
             let (producer, consumer) = TransmissionPipeline::make(config, conduit_tx);
             self.pipeline = Some(producer);
 
             // Spawn the TX task
             let c_link = self.link.clone();
+            let c_bandwidth = self.bandwidth.clone();
             let c_transport = self.transport.clone();
             let handle = executor.spawn(async move {
                 let res = tx_task(
                     consumer,
                     c_link.clone(),
                     keep_alive,
+                    dscp,
+                    c_bandwidth,
                     #[cfg(feature = "stats")]
                     c_transport.stats.clone(),
+                    #[cfg(feature = "stats")]
+                    c_transport.queue_latency.clone(),
                 )
                 .await;
                 if let Err(e) = res {
@@ -122,6 +152,7 @@ impl TransportLinkUnicast {
             let c_transport = self.transport.clone();
             let c_signal = self.signal_rx.clone();
             let c_rx_buffer_size = self.transport.config.manager.config.link_rx_buffer_size;
+            let c_watchdog = self.transport.config.manager.config.link_rx_watchdog;
 
             let handle = task::spawn(async move {
                 // Start the consume task
@@ -129,6 +160,7 @@ impl TransportLinkUnicast {
                     c_link.clone(),
                     c_transport.clone(),
                     lease,
+                    c_watchdog,
                     c_signal.clone(),
                     c_rx_buffer_size,
                 )
@@ -176,15 +208,35 @@ async fn tx_task(
     mut pipeline: TransmissionPipelineConsumer,
     link: LinkUnicast,
     keep_alive: Duration,
+    dscp: Option<Vec<u8>>,
+    bandwidth: Arc<BandwidthEstimator>,
     #[cfg(feature = "stats")] stats: Arc<TransportUnicastStatsAtomic>,
+    #[cfg(feature = "stats")] queue_latency: Arc<LatencyHistogram>,
 ) -> ZResult<()> {
+    // The DSCP the link is currently marking its packets with
+    let mut mark: Option<u8> = None;
     loop {
-        match pipeline.pull().timeout(keep_alive).await {
+        match clock::timeout(keep_alive, pipeline.pull()).await {
             Ok(res) => match res {
                 Some((batch, priority)) => {
+                    #[cfg(feature = "stats")]
+                    queue_latency.record(batch.stats.enqueued.elapsed());
+
+                    // Mark the packets with the DSCP of the batch priority, if configured
+                    if let Some(d) = dscp.as_ref().map(|dscp| dscp[priority]) {
+                        if mark != Some(d) {
+                            if let Err(e) = link.set_dscp(d) {
+                                log::warn!("{}", e);
+                            }
+                            mark = Some(d);
+                        }
+                    }
+
                     // Send the buffer on the link
                     let bytes = batch.as_bytes();
+                    let start = Instant::now();
                     link.write_all(bytes).await?;
+                    bandwidth.record(bytes.len(), start.elapsed());
 
                     #[cfg(feature = "stats")]
                     {
@@ -216,8 +268,7 @@ async fn tx_task(
     // Drain the transmission pipeline and write remaining bytes on the wire
     let mut batches = pipeline.drain();
     for (b, _) in batches.drain(..) {
-        link.write_all(b.as_bytes())
-            .timeout(keep_alive)
+        clock::timeout(keep_alive, link.write_all(b.as_bytes()))
             .await
             .map_err(|_| zerror!("{}: flush failed after {} ms", link, keep_alive.as_millis()))??;
 
@@ -231,10 +282,41 @@ async fn tx_task(
     Ok(())
 }
 
+// The period after which a link on which nothing was received is closed
+fn rx_timeout(lease: Duration, watchdog: Option<Duration>) -> Duration {
+    watchdog.map_or(lease, |watchdog| watchdog.min(lease))
+}
+
+fn expired(
+    link: &LinkUnicast,
+    transport: &TransportUnicastInner,
+    lease: Duration,
+    watchdog: Option<Duration>,
+) -> ZError {
+    match watchdog {
+        Some(watchdog) if watchdog < lease => {
+            // Writes may still succeed on a half-open link: report it for the operators
+            log::warn!(
+                "Link stalled: link={} zid={} silent_ms={}",
+                link,
+                transport.config.zid,
+                watchdog.as_millis()
+            );
+            zerror!(
+                "{}: stalled for {} milliseconds",
+                link,
+                watchdog.as_millis()
+            )
+        }
+        _ => zerror!("{}: expired after {} milliseconds", link, lease.as_millis()),
+    }
+}
+
 async fn rx_task_stream(
     link: LinkUnicast,
     transport: TransportUnicastInner,
     lease: Duration,
+    watchdog: Option<Duration>,
     signal: Signal,
     rx_buffer_size: usize,
 ) -> ZResult<()> {
@@ -270,11 +352,12 @@ async fn rx_task_stream(
         // Retrieve one buffer
         let mut buffer = pool.try_take().unwrap_or_else(|| pool.alloc());
         // Async read from the underlying link
-        let action = read(&link, &mut buffer)
-            .race(stop(signal.clone()))
-            .timeout(lease)
-            .await
-            .map_err(|_| zerror!("{}: expired after {} milliseconds", link, lease.as_millis()))??;
+        let action = clock::timeout(
+            rx_timeout(lease, watchdog),
+            read(&link, &mut buffer).race(stop(signal.clone())),
+        )
+        .await
+        .map_err(|_| expired(&link, &transport, lease, watchdog))??;
         match action {
             Action::Read(n) => {
                 #[cfg(feature = "stats")]
@@ -308,6 +391,7 @@ async fn rx_task_dgram(
     link: LinkUnicast,
     transport: TransportUnicastInner,
     lease: Duration,
+    watchdog: Option<Duration>,
     signal: Signal,
     rx_buffer_size: usize,
 ) -> ZResult<()> {
@@ -329,7 +413,9 @@ async fn rx_task_dgram(
     let codec = Zenoh060::default();
 
     // The pool of buffers
-    let mtu = link.get_mtu() as usize;
+    // The MTU of the link only bounds the batches sent on it: the remote side may use
+    // larger datagrams if its own MTU towards this host is larger.
+    let mtu = u16::MAX as usize;
     let mut n = rx_buffer_size / mtu;
     if rx_buffer_size % mtu != 0 {
         n += 1;
@@ -339,11 +425,12 @@ async fn rx_task_dgram(
         // Retrieve one buffer
         let mut buffer = pool.try_take().unwrap_or_else(|| pool.alloc());
         // Async read from the underlying link
-        let action = read(&link, &mut buffer)
-            .race(stop(signal.clone()))
-            .timeout(lease)
-            .await
-            .map_err(|_| zerror!("{}: expired after {} milliseconds", link, lease.as_millis()))??;
+        let action = clock::timeout(
+            rx_timeout(lease, watchdog),
+            read(&link, &mut buffer).race(stop(signal.clone())),
+        )
+        .await
+        .map_err(|_| expired(&link, &transport, lease, watchdog))??;
         match action {
             Action::Read(n) => {
                 if n == 0 {
@@ -382,12 +469,13 @@ async fn rx_task(
     link: LinkUnicast,
     transport: TransportUnicastInner,
     lease: Duration,
+    watchdog: Option<Duration>,
     signal: Signal,
     rx_buffer_size: usize,
 ) -> ZResult<()> {
     if link.is_streamed() {
-        rx_task_stream(link, transport, lease, signal, rx_buffer_size).await
+        rx_task_stream(link, transport, lease, watchdog, signal, rx_buffer_size).await
     } else {
-        rx_task_dgram(link, transport, lease, signal, rx_buffer_size).await
+        rx_task_dgram(link, transport, lease, watchdog, signal, rx_buffer_size).await
     }
 }
@@ -0,0 +1,104 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use zenoh_link::{LinkUnicast, LinkUnicastTrait};
+use zenoh_protocol::core::Locator;
+use zenoh_result::ZResult;
+
+// A link accepted on a listener, accounted in the number of open connections of the
+// listener until the link is dropped, i.e. until the link is closed and not used any more.
+pub(super) struct CountedLinkUnicast {
+    inner: LinkUnicast,
+    connections: Arc<AtomicUsize>,
+}
+
+impl CountedLinkUnicast {
+    // Account `link` in `connections`, unless `max` connections are already open.
+    pub(super) fn make(
+        link: LinkUnicast,
+        connections: Arc<AtomicUsize>,
+        max: Option<usize>,
+    ) -> Result<LinkUnicast, LinkUnicast> {
+        let res = connections.fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| match max {
+            Some(max) if n >= max => None,
+            _ => Some(n + 1),
+        });
+        match res {
+            Ok(_) => Ok(LinkUnicast(Arc::new(CountedLinkUnicast {
+                inner: link,
+                connections,
+            }))),
+            Err(_) => Err(link),
+        }
+    }
+}
+
+impl Drop for CountedLinkUnicast {
+    fn drop(&mut self) {
+        self.connections.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[async_trait]
+impl LinkUnicastTrait for CountedLinkUnicast {
+    fn get_mtu(&self) -> u16 {
+        self.inner.get_mtu()
+    }
+
+    fn get_src(&self) -> &Locator {
+        self.inner.get_src()
+    }
+
+    fn get_dst(&self) -> &Locator {
+        self.inner.get_dst()
+    }
+
+    fn is_reliable(&self) -> bool {
+        self.inner.is_reliable()
+    }
+
+    fn is_streamed(&self) -> bool {
+        self.inner.is_streamed()
+    }
+
+    fn get_identity(&self) -> Option<String> {
+        self.inner.get_identity()
+    }
+
+    fn set_dscp(&self, dscp: u8) -> ZResult<()> {
+        self.inner.set_dscp(dscp)
+    }
+
+    async fn write(&self, buffer: &[u8]) -> ZResult<usize> {
+        self.inner.write(buffer).await
+    }
+
+    async fn write_all(&self, buffer: &[u8]) -> ZResult<()> {
+        self.inner.write_all(buffer).await
+    }
+
+    async fn read(&self, buffer: &mut [u8]) -> ZResult<usize> {
+        self.inner.read(buffer).await
+    }
+
+    async fn read_exact(&self, buffer: &mut [u8]) -> ZResult<()> {
+        self.inner.read_exact(buffer).await
+    }
+
+    async fn close(&self) -> ZResult<()> {
+        self.inner.close().await
+    }
+}
@@ -13,6 +13,7 @@
 //
 use crate::unicast::{
     establishment::authenticator::*,
+    listener::CountedLinkUnicast,
     transport::{TransportUnicastConfig, TransportUnicastInner},
     TransportConfigUnicast, TransportUnicast,
 };
@@ -21,17 +22,28 @@ use async_std::prelude::FutureExt;
 use async_std::sync::{Mutex as AsyncMutex, RwLock as AsyncRwLock};
 use async_std::task;
 use std::collections::{HashMap, HashSet};
-use std::sync::{Arc, Mutex};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 use std::time::Duration;
 use zenoh_cfg_properties::config::*;
 use zenoh_config::Config;
 use zenoh_core::{zasynclock, zasyncread, zasyncwrite, zlock, zparse};
 use zenoh_link::*;
 use zenoh_protocol::{
-    core::{endpoint::Protocol, ZenohId},
-    transport::tmsg,
+    core::{endpoint::Protocol, ZInt, ZenohId},
+    transport::{tmsg, TransportMessage},
 };
-use zenoh_result::{bail, zerror, ZResult};
+use zenoh_result::{bail, zerror, ErrorKind, ZResult};
+
+/// The endpoint configuration key overriding the batch size of the links opened towards,
+/// or accepted on, an endpoint, e.g. `serial//dev/ttyUSB0#batch_size=512`.
+/// The batch size of a link is negotiated with the remote side as the smallest of both limits.
+pub const BATCH_SIZE_KEY: &str = "batch_size";
+
+/// The endpoint configuration key limiting the number of connections open at the same time on
+/// a listener, e.g. `tcp/0.0.0.0:7447#max_connections=100`. Above it, new connections are rejected.
+pub const MAX_CONNECTIONS_KEY: &str = "max_connections";
 
 /*************************************/
 /*         TRANSPORT CONFIG          */
@@ -43,6 +55,8 @@ pub struct TransportManagerConfigUnicast {
     pub accept_pending: usize,
     pub max_sessions: usize,
     pub max_links: usize,
+    pub max_fd_usage: Option<usize>,
+    pub credits: Option<ZInt>,
     pub is_qos: bool,
     #[cfg(feature = "shared-memory")]
     pub is_shm: bool,
@@ -57,8 +71,12 @@ pub struct TransportManagerStateUnicast {
     pub(super) link_authenticator: Arc<AsyncRwLock<HashSet<LinkAuthenticator>>>,
     // Established listeners
     pub(super) protocols: Arc<Mutex<HashMap<String, LinkManagerUnicast>>>,
+    // Number of connections open on each listener, indexed by listener locator
+    pub(super) listener_connections: Arc<Mutex<HashMap<String, Arc<AtomicUsize>>>>,
     // Established transports
     pub(super) transports: Arc<Mutex<HashMap<ZenohId, Arc<TransportUnicastInner>>>>,
+    // Whether the runtime is degraded because of file descriptors exhaustion
+    pub(super) degraded: Arc<AtomicBool>,
 }
 
 pub struct TransportManagerParamsUnicast {
@@ -78,6 +96,8 @@ pub struct TransportManagerBuilderUnicast {
     pub(super) accept_pending: usize,
     pub(super) max_sessions: usize,
     pub(super) max_links: usize,
+    pub(super) max_fd_usage: Option<usize>,
+    pub(super) credits: Option<ZInt>,
     pub(super) is_qos: bool,
     #[cfg(feature = "shared-memory")]
     pub(super) is_shm: bool,
@@ -116,6 +136,18 @@ impl TransportManagerBuilderUnicast {
         self
     }
 
+    /// The percentage of the file descriptors limit above which the runtime degrades gracefully.
+    pub fn max_fd_usage(mut self, max_fd_usage: Option<usize>) -> Self {
+        self.max_fd_usage = max_fd_usage;
+        self
+    }
+
+    /// The number of messages per priority the remote nodes may send before waiting for more credits.
+    pub fn credits(mut self, credits: Option<ZInt>) -> Self {
+        self.credits = credits;
+        self
+    }
+
     pub fn peer_authenticator(mut self, peer_authenticator: HashSet<PeerAuthenticator>) -> Self {
         self.peer_authenticator = peer_authenticator;
         self
@@ -148,6 +180,8 @@ impl TransportManagerBuilderUnicast {
         self = self.accept_pending(config.transport().unicast().accept_pending().unwrap());
         self = self.max_sessions(config.transport().unicast().max_sessions().unwrap());
         self = self.max_links(config.transport().unicast().max_links().unwrap());
+        self = self.max_fd_usage(*config.transport().unicast().max_fd_usage());
+        self = self.credits(*config.transport().unicast().credits());
         self = self.qos(*config.transport().qos().enabled());
 
         #[cfg(feature = "shared-memory")]
@@ -171,6 +205,8 @@ impl TransportManagerBuilderUnicast {
             accept_pending: self.accept_pending,
             max_sessions: self.max_sessions,
             max_links: self.max_links,
+            max_fd_usage: self.max_fd_usage,
+            credits: self.credits,
             is_qos: self.is_qos,
             #[cfg(feature = "shared-memory")]
             is_shm: self.is_shm,
@@ -201,9 +237,11 @@ impl TransportManagerBuilderUnicast {
         let state = TransportManagerStateUnicast {
             incoming: Arc::new(AsyncMutex::new(0)),
             protocols: Arc::new(Mutex::new(HashMap::new())),
+            listener_connections: Arc::new(Mutex::new(HashMap::new())),
             transports: Arc::new(Mutex::new(HashMap::new())),
             link_authenticator: Arc::new(AsyncRwLock::new(self.link_authenticator)),
             peer_authenticator: Arc::new(AsyncRwLock::new(self.peer_authenticator)),
+            degraded: Arc::new(AtomicBool::new(false)),
         };
 
         let params = TransportManagerParamsUnicast { config, state };
@@ -221,6 +259,8 @@ impl Default for TransportManagerBuilderUnicast {
             accept_pending: zparse!(ZN_OPEN_INCOMING_PENDING_DEFAULT).unwrap(),
             max_sessions: zparse!(ZN_MAX_SESSIONS_UNICAST_DEFAULT).unwrap(),
             max_links: zparse!(ZN_MAX_LINKS_DEFAULT).unwrap(),
+            max_fd_usage: None,
+            credits: None,
             is_qos: zparse!(ZN_QOS_DEFAULT).unwrap(),
             #[cfg(feature = "shared-memory")]
             is_shm: zparse!(ZN_SHM_DEFAULT).unwrap(),
@@ -272,6 +312,35 @@ impl TransportManager {
         }
     }
 
+    /*************************************/
+    /*        PEER AUTHENTICATORS        */
+    /*************************************/
+    /// Add a peer authenticator to this transport manager, e.g. from a plugin
+    /// providing a custom identity provider. The authenticator only applies to
+    /// the transports established after it has been added.
+    pub async fn add_peer_authenticator(&self, pa: PeerAuthenticator) -> ZResult<()> {
+        let mut guard = zasyncwrite!(self.state.unicast.peer_authenticator);
+        if guard.contains(&pa) {
+            bail!(
+                "A peer authenticator with id {:?} is already registered",
+                pa.id()
+            );
+        }
+        guard.insert(pa);
+        Ok(())
+    }
+
+    /// Return the identities a peer has been authenticated with, per authenticator.
+    pub async fn get_peer_identities(&self, peer: &ZenohId) -> Vec<(PeerAuthenticatorId, String)> {
+        let mut identities = vec![];
+        for pa in zasyncread!(self.state.unicast.peer_authenticator).iter() {
+            if let Some(identity) = pa.identity(peer).await {
+                identities.push((pa.id(), identity));
+            }
+        }
+        identities
+    }
+
     /*************************************/
     /*            LINK MANAGER           */
     /*************************************/
@@ -293,6 +362,7 @@ impl TransportManager {
         match zlock!(self.state.unicast.protocols).get(protocol.as_str()) {
             Some(manager) => Ok(manager.clone()),
             None => bail!(
+                (ErrorKind::InvalidLocator)
                 "Can not get the link manager for protocol ({}) because it has not been found",
                 protocol
             ),
@@ -313,6 +383,9 @@ impl TransportManager {
     /*              LISTENER             */
     /*************************************/
     pub async fn add_listener_unicast(&self, mut endpoint: EndPoint) -> ZResult<Locator> {
+        // Validate the batch size and connections overrides, if any
+        self.get_endpoint_batch_size(&endpoint)?;
+        get_endpoint_max_connections(&endpoint)?;
         let manager = self.new_link_manager_unicast(&endpoint.protocol())?;
         // Fill and merge the endpoint configuration
         if let Some(config) = self.config.endpoint.get(endpoint.protocol().as_str()) {
@@ -324,6 +397,7 @@ impl TransportManager {
     pub async fn del_listener_unicast(&self, endpoint: &EndPoint) -> ZResult<()> {
         let lm = self.get_link_manager_unicast(&endpoint.protocol())?;
         lm.del_listener(endpoint).await?;
+        zlock!(self.state.unicast.listener_connections).remove(&endpoint.to_locator().to_string());
         if lm.get_listeners().is_empty() {
             self.del_link_manager_unicast(&endpoint.protocol())?;
         }
@@ -346,6 +420,91 @@ impl TransportManager {
         vec
     }
 
+    /*************************************/
+    /*             BATCH SIZE            */
+    /*************************************/
+    // The batch size limit of the links established with `endpoint`
+    fn get_endpoint_batch_size(&self, endpoint: &EndPoint) -> ZResult<u16> {
+        match endpoint.config().get(BATCH_SIZE_KEY) {
+            Some(s) => match s.parse::<u16>() {
+                Ok(batch_size) if batch_size > 0 => Ok(batch_size.min(self.config.batch_size)),
+                _ => bail!(
+                    (ErrorKind::InvalidLocator)
+                    "Invalid {} in endpoint {}: {}",
+                    BATCH_SIZE_KEY,
+                    endpoint,
+                    s
+                ),
+            },
+            None => Ok(self.config.batch_size),
+        }
+    }
+
+    // The features advertised to the remote peers during the handshake
+    pub(super) fn get_features_unicast(&self) -> ZInt {
+        let mut features =
+            tmsg::features::FRAGMENTATION | tmsg::features::ATTACHMENTS | tmsg::features::CREDITS;
+        if self.config.unicast.is_qos {
+            features |= tmsg::features::QOS;
+        }
+        #[cfg(feature = "shared-memory")]
+        if self.config.unicast.is_shm {
+            features |= tmsg::features::SHARED_MEMORY;
+        }
+        features
+    }
+
+    // The batch size limit of the links accepted on the listener `link` has been accepted on
+    pub(super) fn get_listener_batch_size(&self, link: &LinkUnicast) -> u16 {
+        self.get_listener_of(link)
+            .and_then(|listener| self.get_endpoint_batch_size(&listener).ok())
+            .unwrap_or(self.config.batch_size)
+    }
+
+    // The listener `link` has been accepted on
+    fn get_listener_of(&self, link: &LinkUnicast) -> Option<EndPoint> {
+        let src = link.get_src();
+        let listeners = zlock!(self.state.unicast.protocols)
+            .get(src.protocol().as_str())?
+            .get_listeners();
+        listeners
+            .into_iter()
+            .find(|listener| is_listener_of(listener, src))
+    }
+
+    /*************************************/
+    /*            CONNECTIONS            */
+    /*************************************/
+    /// The number of connections currently open on each listener, along with their limit if any.
+    pub fn get_listeners_connections(&self) -> Vec<(EndPoint, usize, Option<usize>)> {
+        let connections = zlock!(self.state.unicast.listener_connections);
+        self.get_listeners_unicast()
+            .into_iter()
+            .map(|listener| {
+                let n = connections
+                    .get(&listener.to_locator().to_string())
+                    .map_or(0, |n| n.load(Ordering::Acquire));
+                let max = get_endpoint_max_connections(&listener).ok().flatten();
+                (listener, n, max)
+            })
+            .collect()
+    }
+
+    // Account `link` in the connections of the listener it has been accepted on,
+    // or give it back if the listener has reached its maximum number of connections.
+    fn count_listener_connection(&self, link: LinkUnicast) -> Result<LinkUnicast, LinkUnicast> {
+        let listener = match self.get_listener_of(&link) {
+            Some(listener) => listener,
+            None => return Ok(link),
+        };
+        let max = get_endpoint_max_connections(&listener).ok().flatten();
+        let connections = zlock!(self.state.unicast.listener_connections)
+            .entry(listener.to_locator().to_string())
+            .or_default()
+            .clone();
+        CountedLinkUnicast::make(link, connections, max)
+    }
+
     /*************************************/
     /*             TRANSPORT             */
     /*************************************/
@@ -403,12 +562,24 @@ impl TransportManager {
                     return Err(e.into());
                 }
 
+                if transport.config.features != config.features {
+                    let e = zerror!(
+                        "Transport with peer {} already exist. Invalid features: {:#x}. Execpted: {:#x}.",
+                        config.peer,
+                        config.features,
+                        transport.config.features
+                    );
+                    log::trace!("{}", e);
+                    return Err(e.into());
+                }
+
                 Ok(transport.into())
             }
             None => {
                 // Then verify that we haven't reached the transport number limit
                 if guard.len() >= self.config.unicast.max_sessions {
                     let e = zerror!(
+                        (ErrorKind::ResourceLimit)
                         "Max transports reached ({}). Denying new transport with peer: {}",
                         self.config.unicast.max_sessions,
                         config.peer
@@ -426,6 +597,7 @@ impl TransportManager {
                     initial_sn_tx: config.initial_sn_tx,
                     is_shm: config.is_shm,
                     is_qos: config.is_qos,
+                    features: config.features,
                 };
                 let a_t = Arc::new(TransportUnicastInner::make(stc)?);
 
@@ -458,11 +630,13 @@ impl TransportManager {
             .await?
         {
             bail!(
+                (ErrorKind::InvalidLocator)
                 "Can not open a unicast transport with a multicast endpoint: {}.",
                 endpoint
             )
         }
 
+        let batch_size = self.get_endpoint_batch_size(&endpoint)?;
         // Automatically create a new link manager for the protocol if it does not exist
         let manager = self.new_link_manager_unicast(&endpoint.protocol())?;
         // Fill and merge the endpoint configuration
@@ -472,13 +646,15 @@ impl TransportManager {
 
         // Create a new link associated by calling the Link Manager
         let link = manager.new_link(endpoint).await?;
+        #[cfg(feature = "capture")]
+        let link = zenoh_link::capture::capture_unicast(link);
         // Open the link
         let mut auth_link = AuthenticatedPeerLink {
             src: link.get_src().to_owned(),
             dst: link.get_src().to_owned(),
             peer_id: None,
         };
-        super::establishment::open::open_link(&link, self, &mut auth_link).await
+        super::establishment::open::open_link(&link, self, &mut auth_link, batch_size).await
     }
 
     pub fn get_transport_unicast(&self, peer: &ZenohId) -> Option<TransportUnicast> {
@@ -494,6 +670,28 @@ impl TransportManager {
             .collect()
     }
 
+    /// Whether the runtime is degraded because of file descriptors exhaustion:
+    /// new sessions are refused and droppable messages are shed.
+    pub fn is_degraded(&self) -> bool {
+        self.state.unicast.degraded.load(Ordering::Relaxed)
+    }
+
+    pub fn set_degraded(&self, degraded: bool) {
+        self.state
+            .unicast
+            .degraded
+            .store(degraded, Ordering::Relaxed);
+    }
+
+    pub(crate) fn spawn_fd_monitor(&self) {
+        if let Some(threshold) = self.config.unicast.max_fd_usage {
+            task::spawn(monitor_fd_usage(
+                Arc::downgrade(&self.state.unicast.degraded),
+                threshold,
+            ));
+        }
+    }
+
     pub(super) async fn del_transport_unicast(&self, peer: &ZenohId) -> ZResult<()> {
         let _ = zlock!(self.state.unicast.transports)
             .remove(peer)
@@ -511,6 +709,43 @@ impl TransportManager {
     }
 
     pub(crate) async fn handle_new_link_unicast(&self, link: LinkUnicast) {
+        #[cfg(feature = "capture")]
+        let link = zenoh_link::capture::capture_unicast(link);
+        if self.is_degraded() {
+            log::debug!(
+                "Closing link because of file descriptors exhaustion: {}",
+                link
+            );
+            let message = TransportMessage::make_close(
+                Some(self.config.zid),
+                tmsg::close_reason::BUSY,
+                true,
+                None,
+            );
+            let _ = link.write_transport_message(&message).await;
+            let _ = link.close().await;
+            return;
+        }
+
+        let link = match self.count_listener_connection(link) {
+            Ok(link) => link,
+            Err(link) => {
+                log::debug!(
+                    "Closing link because the listener reached its maximum number of connections: {}",
+                    link
+                );
+                let message = TransportMessage::make_close(
+                    Some(self.config.zid),
+                    tmsg::close_reason::BUSY,
+                    true,
+                    None,
+                );
+                let _ = link.write_transport_message(&message).await;
+                let _ = link.close().await;
+                return;
+            }
+        };
+
         let mut guard = zasynclock!(self.state.unicast.incoming);
         if *guard >= self.config.unicast.accept_pending {
             // We reached the limit of concurrent incoming transport, this means two things:
@@ -580,3 +815,101 @@ impl TransportManager {
         });
     }
 }
+
+// The maximum number of connections open at the same time on the `listener` endpoint
+fn get_endpoint_max_connections(listener: &EndPoint) -> ZResult<Option<usize>> {
+    match listener.config().get(MAX_CONNECTIONS_KEY) {
+        Some(s) => match s.parse::<usize>() {
+            Ok(max) => Ok(Some(max)),
+            Err(_) => bail!(
+                (ErrorKind::InvalidLocator)
+                "Invalid {} in endpoint {}: {}",
+                MAX_CONNECTIONS_KEY,
+                listener,
+                s
+            ),
+        },
+        None => Ok(None),
+    }
+}
+
+// Whether `src`, the local locator of an accepted link, belongs to the `listener` endpoint.
+// Listeners on an unspecified IP address accept links on any local address with the same port.
+fn is_listener_of(listener: &EndPoint, src: &Locator) -> bool {
+    let (listener, src) = (listener.address(), src.address());
+    if listener.as_str() == src.as_str() {
+        return true;
+    }
+    match (
+        listener.as_str().parse::<SocketAddr>(),
+        src.as_str().parse::<SocketAddr>(),
+    ) {
+        (Ok(listener), Ok(src)) => {
+            listener.port() == src.port()
+                && (listener.ip().is_unspecified() || listener.ip() == src.ip())
+        }
+        _ => false,
+    }
+}
+
+const FD_MONITOR_PERIOD: Duration = Duration::from_secs(1);
+
+// Enters the degraded state when the file descriptors usage crosses `threshold` percents of the
+// limit, and leaves it when the usage drops below 90% of the threshold, to avoid flapping.
+async fn monitor_fd_usage(degraded: Weak<AtomicBool>, threshold: usize) {
+    let recovery = threshold * 9 / 10;
+    loop {
+        task::sleep(FD_MONITOR_PERIOD).await;
+        let degraded = match degraded.upgrade() {
+            Some(degraded) => degraded,
+            None => break,
+        };
+        let usage = match zenoh_util::fd::usage() {
+            Some(usage) => usage,
+            None => {
+                log::warn!("Unable to monitor the file descriptors usage on this platform");
+                break;
+            }
+        };
+        let percent = usage.percent();
+        if !degraded.load(Ordering::Relaxed) && percent >= threshold {
+            log::warn!(
+                "File descriptors usage is {}/{} ({}%): refusing new sessions and shedding droppable traffic",
+                usage.open,
+                usage.limit,
+                percent
+            );
+            degraded.store(true, Ordering::Relaxed);
+        } else if degraded.load(Ordering::Relaxed) && percent < recovery {
+            log::warn!(
+                "File descriptors usage is back to {}/{} ({}%): accepting new sessions",
+                usage.open,
+                usage.limit,
+                percent
+            );
+            degraded.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn listener_of() {
+        use super::is_listener_of;
+        use zenoh_protocol::core::{EndPoint, Locator};
+
+        let src: Locator = "tcp/127.0.0.1:7447".parse().unwrap();
+        let listener = |s: &str| -> EndPoint { s.parse().unwrap() };
+        assert!(is_listener_of(&listener("tcp/127.0.0.1:7447"), &src));
+        assert!(is_listener_of(&listener("tcp/0.0.0.0:7447"), &src));
+        assert!(!is_listener_of(&listener("tcp/0.0.0.0:7448"), &src));
+        assert!(!is_listener_of(&listener("tcp/192.168.1.1:7447"), &src));
+
+        let src: Locator = "serial//dev/ttyUSB0".parse().unwrap();
+        assert!(is_listener_of(
+            &listener("serial//dev/ttyUSB0#batch_size=512"),
+            &src
+        ));
+    }
+}
@@ -11,8 +11,10 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
+pub(crate) mod credits;
 pub mod establishment;
 pub(crate) mod link;
+mod listener;
 pub(crate) mod manager;
 pub(crate) mod rx;
 pub(crate) mod transport;
@@ -25,14 +27,15 @@ use super::{TransportPeer, TransportPeerEventHandler};
 pub use manager::*;
 use std::fmt;
 use std::sync::{Arc, Weak};
+use std::time::Duration;
 use transport::TransportUnicastInner;
 use zenoh_link::Link;
 use zenoh_protocol::{
-    core::{WhatAmI, ZInt, ZenohId},
+    core::{Priority, WhatAmI, ZInt, ZenohId},
     transport::tmsg,
     zenoh::ZenohMessage,
 };
-use zenoh_result::{zerror, ZResult};
+use zenoh_result::{zerror, ErrorKind, ZResult};
 
 /*************************************/
 /*              STATS                */
@@ -86,6 +89,7 @@ pub(crate) struct TransportConfigUnicast {
     pub(crate) initial_sn_tx: ZInt,
     pub(crate) is_shm: bool,
     pub(crate) is_qos: bool,
+    pub(crate) features: ZInt,
 }
 
 /// [`TransportUnicast`] is the transport handler returned
@@ -98,7 +102,7 @@ impl TransportUnicast {
     pub(super) fn get_inner(&self) -> ZResult<Arc<TransportUnicastInner>> {
         self.0
             .upgrade()
-            .ok_or_else(|| zerror!("Transport unicast closed").into())
+            .ok_or_else(|| zerror!((ErrorKind::SessionClosed) "Transport unicast closed").into())
     }
 
     #[inline(always)]
@@ -131,6 +135,19 @@ impl TransportUnicast {
         Ok(transport.is_qos())
     }
 
+    /// The features negotiated with the remote peer, see [`tmsg::features`].
+    #[inline(always)]
+    pub fn get_features(&self) -> ZResult<ZInt> {
+        let transport = self.get_inner()?;
+        Ok(transport.get_features())
+    }
+
+    #[inline(always)]
+    pub fn get_uptime(&self) -> ZResult<Duration> {
+        let transport = self.get_inner()?;
+        Ok(transport.get_uptime())
+    }
+
     #[inline(always)]
     pub fn get_callback(&self) -> ZResult<Option<Arc<dyn TransportPeerEventHandler>>> {
         let transport = self.get_inner()?;
@@ -163,6 +180,29 @@ impl TransportUnicast {
             .collect())
     }
 
+    /// The bandwidth estimate of each link of the transport.
+    pub fn get_bandwidth(&self) -> ZResult<Vec<(Link, crate::BandwidthEstimate)>> {
+        let transport = self.get_inner()?;
+        Ok(transport
+            .get_bandwidth()
+            .into_iter()
+            .map(|(l, b)| (l.into(), b))
+            .collect())
+    }
+
+    /// The statistics of the transmission queues of the transport, indexed by priority and
+    /// summed over its links.
+    pub fn get_queue_stats(&self) -> ZResult<[crate::PriorityQueueStats; Priority::NUM]> {
+        let transport = self.get_inner()?;
+        Ok(transport.get_queue_stats())
+    }
+
+    /// The identity of the peer as authenticated by its links, if any.
+    pub fn get_identity(&self) -> ZResult<Option<String>> {
+        let transport = self.get_inner()?;
+        Ok(transport.get_links().iter().find_map(|l| l.get_identity()))
+    }
+
     #[inline(always)]
     pub fn schedule(&self, message: ZenohMessage) -> ZResult<()> {
         let transport = self.get_inner()?;
@@ -202,6 +242,13 @@ impl TransportUnicast {
     pub fn get_stats(&self) -> ZResult<TransportUnicastStats> {
         Ok(self.get_inner()?.stats.snapshot())
     }
+
+    /// The time spent by the messages in the transmission queues, from their enqueuing
+    /// to the write of their batch on a link.
+    #[cfg(feature = "stats")]
+    pub fn get_queue_latency(&self) -> ZResult<crate::LatencyHistogramSnapshot> {
+        Ok(self.get_inner()?.queue_latency.snapshot())
+    }
 }
 
 impl From<&Arc<TransportUnicastInner>> for TransportUnicast {
@@ -14,6 +14,7 @@
 use super::common::conduit::TransportChannelRx;
 use super::transport::TransportUnicastInner;
 use async_std::task;
+use std::sync::atomic::Ordering;
 use std::sync::MutexGuard;
 #[cfg(feature = "stats")]
 use zenoh_buffers::SplitBuffer;
@@ -22,8 +23,10 @@ use zenoh_link::LinkUnicast;
 #[cfg(feature = "stats")]
 use zenoh_protocol::zenoh::ZenohBody;
 use zenoh_protocol::{
-    core::{Priority, Reliability, ZInt, ZenohId},
-    transport::{tmsg, Close, Frame, FramePayload, KeepAlive, TransportBody, TransportMessage},
+    core::{Channel, Priority, Reliability, ZInt, ZenohId},
+    transport::{
+        tmsg, Close, Credit, Frame, FramePayload, KeepAlive, TransportBody, TransportMessage,
+    },
     zenoh::ZenohMessage,
 };
 use zenoh_result::{bail, zerror, ZResult};
@@ -80,6 +83,22 @@ impl TransportUnicastInner {
         }
     }
 
+    // Grant the credits of the reliable messages processed on `channel` back to the remote node
+    fn consume_credit(&self, channel: Channel) {
+        if channel.reliability != Reliability::Reliable
+            || !self.credits_granted.load(Ordering::Acquire)
+        {
+            return;
+        }
+        if let Some(credit) = self
+            .credits_rx
+            .as_ref()
+            .and_then(|credits_rx| credits_rx.consume(channel.priority))
+        {
+            self.grant_credits(channel.priority, credit);
+        }
+    }
+
     fn handle_close(
         &self,
         link: &LinkUnicast,
@@ -122,6 +141,7 @@ impl TransportUnicastInner {
 
     fn handle_frame(
         &self,
+        channel: Channel,
         sn: ZInt,
         payload: FramePayload,
         mut guard: MutexGuard<'_, TransportChannelRx>,
@@ -156,7 +176,9 @@ impl TransportUnicastInner {
                     let msg = guard.defrag.defragment().ok_or_else(|| {
                         zerror!("Transport: {}. Defragmentation error.", self.config.zid)
                     })?;
-                    self.trigger_callback(msg)
+                    self.trigger_callback(msg)?;
+                    self.consume_credit(channel);
+                    Ok(())
                 } else {
                     Ok(())
                 }
@@ -164,6 +186,7 @@ impl TransportUnicastInner {
             FramePayload::Messages { mut messages } => {
                 for msg in messages.drain(..) {
                     self.trigger_callback(msg)?;
+                    self.consume_credit(channel);
                 }
                 Ok(())
             }
@@ -192,9 +215,11 @@ impl TransportUnicastInner {
                 };
 
                 match channel.reliability {
-                    Reliability::Reliable => self.handle_frame(sn, payload, zlock!(c.reliable)),
+                    Reliability::Reliable => {
+                        self.handle_frame(channel, sn, payload, zlock!(c.reliable))
+                    }
                     Reliability::BestEffort => {
-                        self.handle_frame(sn, payload, zlock!(c.best_effort))
+                        self.handle_frame(channel, sn, payload, zlock!(c.best_effort))
                     }
                 }
             }
@@ -203,7 +228,15 @@ impl TransportUnicastInner {
                 reason,
                 link_only,
             }) => self.handle_close(link, zid, reason, link_only),
-            TransportBody::KeepAlive(KeepAlive { .. }) => Ok(()),
+            TransportBody::KeepAlive(KeepAlive { .. }) => {
+                // The remote node may be idle for lack of credits
+                self.flush_credits();
+                Ok(())
+            }
+            TransportBody::Credit(Credit { priority, credit }) => {
+                self.grant_credits_tx(priority, credit);
+                Ok(())
+            }
             _ => {
                 log::debug!(
                     "Transport: {}. Message handling not implemented: {:?}",
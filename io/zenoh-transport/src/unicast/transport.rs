@@ -13,17 +13,23 @@
 //
 use super::super::{TransportExecutor, TransportManager, TransportPeerEventHandler};
 use super::common::conduit::{TransportConduitRx, TransportConduitTx};
+use super::credits::{TransportCreditsRx, TransportCreditsTx};
 use super::link::TransportLinkUnicast;
 #[cfg(feature = "stats")]
 use super::TransportUnicastStatsAtomic;
+#[cfg(feature = "stats")]
+use crate::LatencyHistogram;
+use crate::{BandwidthEstimate, PriorityQueueStats};
 use async_std::sync::{Mutex as AsyncMutex, MutexGuard as AsyncMutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use zenoh_core::{zasynclock, zread, zwrite};
 use zenoh_link::{Link, LinkUnicast, LinkUnicastDirection};
 use zenoh_protocol::{
+    common::imsg,
     core::{ConduitSn, Priority, WhatAmI, ZInt, ZenohId},
-    transport::TransportMessage,
+    transport::{tmsg, TransportMessage},
     zenoh::ZenohMessage,
 };
 use zenoh_result::{bail, zerror, ZResult};
@@ -58,6 +64,7 @@ pub(crate) struct TransportUnicastConfig {
     pub(crate) initial_sn_tx: ZInt,
     pub(crate) is_shm: bool,
     pub(crate) is_qos: bool,
+    pub(crate) features: ZInt,
 }
 
 #[derive(Clone)]
@@ -68,15 +75,26 @@ pub(crate) struct TransportUnicastInner {
     pub(super) conduit_tx: Arc<[TransportConduitTx]>,
     // Rx conduits
     pub(super) conduit_rx: Arc<[TransportConduitRx]>,
+    // The credits granted by the remote node
+    pub(super) credits_tx: Arc<TransportCreditsTx>,
+    // The credits granted to the remote node, if limited
+    pub(super) credits_rx: Option<Arc<TransportCreditsRx>>,
+    // Whether the initial credits have been granted to the remote node
+    pub(super) credits_granted: Arc<AtomicBool>,
     // The links associated to the channel
     pub(super) links: Arc<RwLock<Box<[TransportLinkUnicast]>>>,
     // The callback
     pub(super) callback: Arc<RwLock<Option<Arc<dyn TransportPeerEventHandler>>>>,
     // Mutex for notification
     pub(super) alive: Arc<AsyncMutex<bool>>,
+    // The time at which the transport was created
+    pub(super) created: Instant,
     // Transport statistics
     #[cfg(feature = "stats")]
     pub(super) stats: Arc<TransportUnicastStatsAtomic>,
+    // The time spent by the batches in the transmission queues
+    #[cfg(feature = "stats")]
+    pub(super) queue_latency: Arc<LatencyHistogram>,
 }
 
 impl TransportUnicastInner {
@@ -104,15 +122,30 @@ impl TransportUnicastInner {
             c.sync(initial_sn)?;
         }
 
+        // Only limit the credits of the remote nodes supporting them
+        let credits_rx = config
+            .manager
+            .config
+            .unicast
+            .credits
+            .filter(|_| imsg::has_option(config.features, tmsg::features::CREDITS))
+            .map(|credits| Arc::new(TransportCreditsRx::new(credits)));
+
         let t = TransportUnicastInner {
             config,
             conduit_tx: conduit_tx.into_boxed_slice().into(),
             conduit_rx: conduit_rx.into_boxed_slice().into(),
+            credits_tx: Arc::new(TransportCreditsTx::new()),
+            credits_rx,
+            credits_granted: Arc::new(AtomicBool::new(false)),
             links: Arc::new(RwLock::new(vec![].into_boxed_slice())),
             callback: Arc::new(RwLock::new(None)),
             alive: Arc::new(AsyncMutex::new(false)),
+            created: Instant::now(),
             #[cfg(feature = "stats")]
             stats: Arc::new(TransportUnicastStatsAtomic::default()),
+            #[cfg(feature = "stats")]
+            queue_latency: Arc::new(LatencyHistogram::new()),
         };
 
         Ok(t)
@@ -167,6 +200,9 @@ impl TransportUnicastInner {
         let mut a_guard = self.get_alive().await;
         *a_guard = false;
 
+        // Release the messages waiting for credits
+        self.credits_tx.close();
+
         // Notify the callback that we are going to close the transport
         let callback = zwrite!(self.callback).take();
         if let Some(cb) = callback.as_ref() {
@@ -250,6 +286,23 @@ impl TransportUnicastInner {
             Some(l) => {
                 assert!(!self.conduit_tx.is_empty());
                 l.start_tx(executor, keep_alive, batch_size, &self.conduit_tx);
+                let is_reliable = l.link.is_reliable();
+                drop(guard);
+                // Grant the initial credits once the first reliable link can transmit. The remote
+                // node stays unlimited on lossy links, where the messages carrying the credits
+                // may be lost.
+                if let Some(credits_rx) = self.credits_rx.as_ref().filter(|_| is_reliable) {
+                    if !self.credits_granted.swap(true, Ordering::AcqRel) {
+                        if self.is_qos() {
+                            for p in 0..Priority::NUM {
+                                let priority = Priority::try_from(p as u8).unwrap();
+                                self.grant_credits(priority, credits_rx.initial());
+                            }
+                        } else {
+                            self.grant_credits(Priority::default(), credits_rx.initial());
+                        }
+                    }
+                }
                 Ok(())
             }
             None => {
@@ -382,6 +435,14 @@ impl TransportUnicastInner {
         self.config.is_qos
     }
 
+    pub(crate) fn get_features(&self) -> ZInt {
+        self.config.features
+    }
+
+    pub(crate) fn get_uptime(&self) -> Duration {
+        self.created.elapsed()
+    }
+
     pub(crate) fn get_callback(&self) -> Option<Arc<dyn TransportPeerEventHandler>> {
         zread!(self.callback).clone()
     }
@@ -456,7 +517,60 @@ impl TransportUnicastInner {
         self.schedule_first_fit(message)
     }
 
+    /// Grant `credit` more messages on `priority` to the remote node.
+    ///
+    /// This is called from the RX task, so it never waits for the TX pipeline: should it be full,
+    /// the credit is kept pending until the next grant or keep alive.
+    pub(super) fn grant_credits(&self, priority: Priority, credit: ZInt) {
+        let pipeline = zread!(self.links)
+            .iter()
+            .filter(|tl| tl.link.is_reliable())
+            .find_map(|tl| tl.pipeline.clone());
+        let msg = TransportMessage::make_credit(priority, credit, None);
+        let granted = pipeline.map_or(false, |pl| {
+            pl.try_push_transport_message(msg, Priority::Control)
+        });
+        if !granted {
+            if let Some(credits_rx) = self.credits_rx.as_ref() {
+                credits_rx.restore(priority, credit);
+            }
+        }
+    }
+
+    /// Grant the pending credits to the remote node.
+    pub(super) fn flush_credits(&self) {
+        let credits_rx = match self.credits_rx.as_ref() {
+            Some(credits_rx) if self.credits_granted.load(Ordering::Acquire) => credits_rx,
+            _ => return,
+        };
+        for p in 0..Priority::NUM {
+            let priority = Priority::try_from(p as u8).unwrap();
+            if let Some(credit) = credits_rx.pending(priority) {
+                self.grant_credits(priority, credit);
+            }
+        }
+    }
+
     pub(crate) fn get_links(&self) -> Vec<LinkUnicast> {
         zread!(self.links).iter().map(|l| l.link.clone()).collect()
     }
+
+    pub(crate) fn get_bandwidth(&self) -> Vec<(LinkUnicast, BandwidthEstimate)> {
+        zread!(self.links)
+            .iter()
+            .map(|l| (l.link.clone(), l.bandwidth.estimate()))
+            .collect()
+    }
+
+    pub(crate) fn get_queue_stats(&self) -> [PriorityQueueStats; Priority::NUM] {
+        let mut stats = [PriorityQueueStats::default(); Priority::NUM];
+        for link in zread!(self.links).iter() {
+            if let Some(pipeline) = link.pipeline.as_ref() {
+                for (stats, link_stats) in stats.iter_mut().zip(pipeline.stats().iter()) {
+                    stats.merge(link_stats);
+                }
+            }
+        }
+        stats
+    }
 }
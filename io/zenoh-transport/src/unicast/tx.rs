@@ -11,13 +11,18 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
+use super::credits::Take;
 use super::transport::TransportUnicastInner;
-#[cfg(feature = "stats")]
 use zenoh_buffers::SplitBuffer;
 use zenoh_core::zread;
 #[cfg(feature = "stats")]
 use zenoh_protocol::zenoh::ZenohBody;
-use zenoh_protocol::zenoh::ZenohMessage;
+use zenoh_protocol::{
+    common::imsg,
+    core::{Priority, ZInt},
+    transport::tmsg,
+    zenoh::ZenohMessage,
+};
 
 impl TransportUnicastInner {
     fn schedule_on_link(&self, msg: ZenohMessage) -> bool {
@@ -33,24 +38,41 @@ impl TransportUnicastInner {
             };
         }
 
+        // Shed the droppable messages proportionally to the saturation of the link
+        let is_shedding = msg.is_droppable() && self.config.manager.config.adaptive_shedding;
+        macro_rules! zshed {
+            ($tl:expr, $msg:expr) => {
+                if is_shedding && $tl.bandwidth.should_shed() {
+                    log::trace!("Shed: {:?}", $msg);
+                    return false;
+                }
+            };
+        }
+
         let guard = zread!(self.links);
         // First try to find the best match between msg and link reliability
-        if let Some(pl) = guard
+        if let Some((tl, pl)) = guard
             .iter()
             .filter_map(|tl| {
                 if msg.is_reliable() == tl.link.is_reliable() {
-                    tl.pipeline.as_ref()
+                    tl.pipeline.as_ref().map(|pl| (tl, pl))
                 } else {
                     None
                 }
             })
             .next()
         {
+            zshed!(tl, msg);
             zpush!(guard, pl, msg);
         }
 
         // No best match found, take the first available link
-        if let Some(pl) = guard.iter().filter_map(|tl| tl.pipeline.as_ref()).next() {
+        if let Some((tl, pl)) = guard
+            .iter()
+            .filter_map(|tl| tl.pipeline.as_ref().map(|pl| (tl, pl)))
+            .next()
+        {
+            zshed!(tl, msg);
             zpush!(guard, pl, msg);
         }
 
@@ -63,9 +85,51 @@ impl TransportUnicastInner {
         false
     }
 
+    // Take a credit granted by the remote node before scheduling the message. When they are
+    // exhausted, the message is dropped or queued according to its congestion control,
+    // so that a slow remote node never blocks the sender. The best-effort messages, which
+    // may be lost, take no credit.
+    fn schedule_with_credits(&self, msg: ZenohMessage) -> bool {
+        if !msg.is_reliable() {
+            return self.schedule_on_link(msg);
+        }
+        let priority = if self.is_qos() {
+            msg.channel.priority
+        } else {
+            Priority::default()
+        };
+        let is_droppable = msg.is_droppable();
+        match self.credits_tx.take(priority, msg, is_droppable) {
+            Take::Send(msg) => {
+                let res = self.schedule_on_link(msg);
+                if !res {
+                    self.credits_tx.refund(priority);
+                }
+                res
+            }
+            Take::Queued => {
+                log::trace!("Message queued waiting for credits on {:?}", priority);
+                true
+            }
+            Take::Refused => {
+                log::trace!("Message dropped for lack of credits on {:?}", priority);
+                false
+            }
+        }
+    }
+
+    // Schedule the messages queued for lack of credits, once the remote node granted some
+    pub(super) fn grant_credits_tx(&self, priority: Priority, credit: ZInt) {
+        for msg in self.credits_tx.grant(priority, credit) {
+            if !self.schedule_on_link(msg) {
+                self.credits_tx.refund(priority);
+            }
+        }
+    }
+
     #[allow(clippy::let_and_return)] // When feature "stats" is not enabled
     #[inline(always)]
-    pub(super) fn schedule_first_fit(&self, msg: ZenohMessage) -> bool {
+    pub(super) fn schedule_first_fit(&self, mut msg: ZenohMessage) -> bool {
         #[cfg(feature = "stats")]
         match &msg.body {
             ZenohBody::Data(data) => match data.reply_context {
@@ -89,7 +153,22 @@ impl TransportUnicastInner {
             ZenohBody::LinkStateList(_) => self.stats.inc_tx_z_linkstate_msgs(1),
         }
 
-        let res = self.schedule_on_link(msg);
+        // Do not send the attachment extensions to the peers not supporting them
+        if !imsg::has_option(self.config.features, tmsg::features::ATTACHMENTS) {
+            if let Some(att) = msg.attachment.as_mut() {
+                att.extensions.clear();
+                if att.buffer.is_empty() {
+                    msg.attachment = None;
+                }
+            }
+        }
+
+        // Shed the droppable traffic when the runtime is degraded
+        let res = if msg.is_droppable() && self.config.manager.is_degraded() {
+            false
+        } else {
+            self.schedule_with_credits(msg)
+        };
 
         #[cfg(feature = "stats")]
         if res {
@@ -12,24 +12,32 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 use async_std::{prelude::FutureExt, task};
+use async_trait::async_trait;
 #[cfg(feature = "auth_pubkey")]
 use rsa::{BigUint, RsaPrivateKey, RsaPublicKey};
-#[cfg(feature = "auth_usrpwd")]
-use std::collections::HashMap;
-use std::{any::Any, collections::HashSet, iter::FromIterator, sync::Arc, time::Duration};
+use std::{
+    any::Any, collections::HashMap, collections::HashSet, iter::FromIterator, sync::Arc,
+    time::Duration,
+};
 use zenoh_core::zasync_executor_init;
 use zenoh_link::Link;
 use zenoh_protocol::{
-    core::{EndPoint, WhatAmI, ZenohId},
+    core::{EndPoint, WhatAmI, ZInt, ZenohId},
     zenoh::ZenohMessage,
 };
-use zenoh_result::ZResult;
+use zenoh_result::{bail, zerror, ZResult};
 #[cfg(feature = "auth_pubkey")]
 use zenoh_transport::unicast::establishment::authenticator::PubKeyAuthenticator;
 #[cfg(feature = "shared-memory")]
 use zenoh_transport::unicast::establishment::authenticator::SharedMemoryAuthenticator;
 #[cfg(feature = "auth_usrpwd")]
 use zenoh_transport::unicast::establishment::authenticator::UserPasswordAuthenticator;
+use zenoh_transport::unicast::establishment::{
+    authenticator::{
+        AuthenticatedPeerLink, PeerAuthenticator, PeerAuthenticatorId, PeerAuthenticatorTrait,
+    },
+    Cookie,
+};
 use zenoh_transport::{
     DummyTransportPeerEventHandler, TransportEventHandler, TransportMulticast,
     TransportMulticastEventHandler, TransportPeer, TransportPeerEventHandler, TransportUnicast,
@@ -775,7 +783,211 @@ async fn authenticator_shared_memory(endpoint: &EndPoint) {
     task::sleep(SLEEP).await;
 }
 
+// A token-based authenticator, standing in for a custom identity provider
+struct TokenAuthenticator {
+    token: Option<Vec<u8>>,
+    valid: Vec<Vec<u8>>,
+    authenticated: async_std::sync::Mutex<HashMap<ZenohId, String>>,
+}
+
+impl TokenAuthenticator {
+    fn new(token: Option<&str>, valid: &[&str]) -> Self {
+        Self {
+            token: token.map(|t| t.as_bytes().to_vec()),
+            valid: valid.iter().map(|v| v.as_bytes().to_vec()).collect(),
+            authenticated: async_std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl PeerAuthenticatorTrait for TokenAuthenticator {
+    fn id(&self) -> PeerAuthenticatorId {
+        PeerAuthenticatorId::Custom(1)
+    }
+
+    async fn close(&self) {}
+
+    async fn get_init_syn_properties(
+        &self,
+        _link: &AuthenticatedPeerLink,
+        _peer_id: &ZenohId,
+    ) -> ZResult<Option<Vec<u8>>> {
+        Ok(self.token.clone())
+    }
+
+    async fn handle_init_syn(
+        &self,
+        link: &AuthenticatedPeerLink,
+        _cookie: &Cookie,
+        property: Option<Vec<u8>>,
+    ) -> ZResult<(Option<Vec<u8>>, Option<Vec<u8>>)> {
+        match property {
+            Some(token) if self.valid.contains(&token) => Ok((None, Some(token))),
+            _ => bail!("Invalid token on link: {}", link),
+        }
+    }
+
+    async fn handle_init_ack(
+        &self,
+        _link: &AuthenticatedPeerLink,
+        _peer_id: &ZenohId,
+        _sn_resolution: ZInt,
+        _property: Option<Vec<u8>>,
+    ) -> ZResult<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    async fn handle_open_syn(
+        &self,
+        link: &AuthenticatedPeerLink,
+        cookie: &Cookie,
+        property: (Option<Vec<u8>>, Option<Vec<u8>>),
+    ) -> ZResult<Option<Vec<u8>>> {
+        let token = property
+            .1
+            .ok_or_else(|| zerror!("Missing token in cookie on link: {}", link))?;
+        self.authenticated
+            .lock()
+            .await
+            .insert(cookie.zid, String::from_utf8_lossy(&token).into_owned());
+        Ok(None)
+    }
+
+    async fn handle_open_ack(
+        &self,
+        _link: &AuthenticatedPeerLink,
+        _property: Option<Vec<u8>>,
+    ) -> ZResult<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    async fn handle_link_err(&self, _link: &AuthenticatedPeerLink) {}
+
+    async fn handle_close(&self, peer_id: &ZenohId) {
+        self.authenticated.lock().await.remove(peer_id);
+    }
+
+    async fn identity(&self, peer_id: &ZenohId) -> Option<String> {
+        self.authenticated.lock().await.get(peer_id).cloned()
+    }
+}
+
+async fn authenticator_custom(endpoint: &EndPoint) {
+    use zenoh_transport::TransportManager;
+
+    /* [CLIENT] */
+    let client01_id = ZenohId::try_from([2]).unwrap();
+    let client02_id = ZenohId::try_from([3]).unwrap();
+
+    /* [ROUTER] */
+    let router_id = ZenohId::try_from([1]).unwrap();
+    let router_handler = Arc::new(SHRouterAuthenticator::new());
+    // Create the router transport manager and plug the authenticator afterwards,
+    // as a plugin would do
+    let router_manager = TransportManager::builder()
+        .whatami(WhatAmI::Router)
+        .zid(router_id)
+        .build(router_handler.clone())
+        .unwrap();
+    let res = ztimeout!(
+        router_manager.add_peer_authenticator(PeerAuthenticator::new(TokenAuthenticator::new(
+            None,
+            &["alice"]
+        )))
+    );
+    assert!(res.is_ok());
+    // The same authenticator can not be registered twice
+    let res = ztimeout!(
+        router_manager.add_peer_authenticator(PeerAuthenticator::new(TokenAuthenticator::new(
+            None,
+            &["bob"]
+        )))
+    );
+    assert!(res.is_err());
+
+    // Create the transport transport manager for the clients
+    let unicast = TransportManager::config_unicast().peer_authenticator(HashSet::from_iter(vec![
+        PeerAuthenticator::new(TokenAuthenticator::new(Some("alice"), &[])),
+    ]));
+    let client01_manager = TransportManager::builder()
+        .whatami(WhatAmI::Client)
+        .zid(client01_id)
+        .unicast(unicast)
+        .build(Arc::new(SHClientAuthenticator::default()))
+        .unwrap();
+
+    let unicast = TransportManager::config_unicast().peer_authenticator(HashSet::from_iter(vec![
+        PeerAuthenticator::new(TokenAuthenticator::new(Some("mallory"), &[])),
+    ]));
+    let client02_manager = TransportManager::builder()
+        .whatami(WhatAmI::Client)
+        .zid(client02_id)
+        .unicast(unicast)
+        .build(Arc::new(SHClientAuthenticator::default()))
+        .unwrap();
+
+    /* [1] */
+    println!("\nTransport Authenticator Custom [1a1]");
+    // Add the locator on the router
+    let res = ztimeout!(router_manager.add_listener(endpoint.clone()));
+    println!("Transport Authenticator Custom [1a1]: {res:?}");
+    assert!(res.is_ok());
+
+    /* [2] */
+    // Open a transport with a valid token
+    // -> This should be accepted and the identity exposed
+    println!("Transport Authenticator Custom [2a1]");
+    let res = ztimeout!(client01_manager.open_transport(endpoint.clone()));
+    println!("Transport Authenticator Custom [2a1]: {res:?}");
+    assert!(res.is_ok());
+    let c_ses1 = res.unwrap();
+    let identities = ztimeout!(router_manager.get_peer_identities(&client01_id));
+    assert_eq!(
+        identities,
+        vec![(PeerAuthenticatorId::Custom(1), "alice".to_string())]
+    );
+
+    /* [3] */
+    // Open a transport with an invalid token
+    // -> This should be rejected
+    println!("Transport Authenticator Custom [3a1]");
+    let res = ztimeout!(client02_manager.open_transport(endpoint.clone()));
+    println!("Transport Authenticator Custom [3a1]: {res:?}");
+    assert!(res.is_err());
+
+    /* [4] */
+    println!("Transport Authenticator Custom [4a1]");
+    let res = ztimeout!(c_ses1.close());
+    println!("Transport Authenticator Custom [4a1]: {res:?}");
+    assert!(res.is_ok());
+
+    ztimeout!(async {
+        while !router_manager.get_transports().is_empty() {
+            task::sleep(SLEEP).await;
+        }
+    });
+    let identities = ztimeout!(router_manager.get_peer_identities(&client01_id));
+    assert!(identities.is_empty());
+
+    /* [5] */
+    // Perform clean up of the open locators
+    println!("Transport Authenticator Custom [5a1]");
+    let res = ztimeout!(router_manager.del_listener(endpoint));
+    println!("Transport Authenticator Custom [5a1]: {res:?}");
+    assert!(res.is_ok());
+
+    ztimeout!(async {
+        while !router_manager.get_listeners().is_empty() {
+            task::sleep(SLEEP).await;
+        }
+    });
+
+    task::sleep(SLEEP).await;
+}
+
 async fn run(endpoint: &EndPoint) {
+    authenticator_custom(endpoint).await;
     #[cfg(feature = "auth_pubkey")]
     authenticator_multilink(endpoint).await;
     #[cfg(feature = "auth_usrpwd")]
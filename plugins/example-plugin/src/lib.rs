@@ -21,7 +21,7 @@ use std::sync::{
     atomic::{AtomicBool, Ordering::Relaxed},
     Arc, Mutex,
 };
-use zenoh::plugins::{Plugin, RunningPluginTrait, ValidationFunction, ZenohPlugin};
+use zenoh::plugins::{Plugin, PluginTasks, RunningPluginTrait, ValidationFunction, ZenohPlugin};
 use zenoh::prelude::r#async::*;
 use zenoh::runtime::Runtime;
 use zenoh_core::zlock;
@@ -63,14 +63,16 @@ impl Plugin for ExamplePlugin {
 
         // a flag to end the plugin's loop when the plugin is removed from the config
         let flag = Arc::new(AtomicBool::new(true));
-        // spawn the task running the plugin's loop
-        async_std::task::spawn(run(runtime.clone(), selector, flag.clone()));
+        // spawn the task running the plugin's loop, so that zenohd isolates its panics
+        let tasks = PluginTasks::new(runtime, name);
+        tasks.spawn(run(runtime.clone(), selector, flag.clone()));
         // return a RunningPlugin to zenohd
         Ok(Box::new(RunningPlugin(Arc::new(Mutex::new(
             RunningPluginInner {
                 flag,
                 name: name.into(),
                 runtime: runtime.clone(),
+                tasks,
             },
         )))))
     }
@@ -81,6 +83,7 @@ struct RunningPluginInner {
     flag: Arc<AtomicBool>,
     name: String,
     runtime: Runtime,
+    tasks: PluginTasks,
 }
 // The RunningPlugin struct implementing the RunningPluginTrait trait
 #[derive(Clone)]
@@ -106,7 +109,7 @@ impl RunningPluginTrait for RunningPlugin {
                         match KeyExpr::try_from(selector.clone()) {
                             Err(e) => log::error!("{}", e),
                             Ok(selector) => {
-                                async_std::task::spawn(run(
+                                guard.tasks.spawn(run(
                                     guard.runtime.clone(),
                                     selector,
                                     guard.flag.clone(),
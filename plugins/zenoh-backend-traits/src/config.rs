@@ -50,6 +50,62 @@ pub struct StorageConfig {
     pub garbage_collection_config: GarbageCollectionConfig,
     // Note: ReplicaConfig is optional. Alignment will be performed only if it is a replica
     pub replica_config: Option<ReplicaConfig>,
+    // Note: EncryptionConfig is optional. Values are encrypted before being stored only if it is set
+    pub encryption: Option<EncryptionConfig>,
+}
+// The configuration for the encryption at rest of the values stored by a storage
+#[derive(Clone, PartialEq, Eq)]
+pub enum EncryptionConfig {
+    // A 256 bits key, given as a 64 characters hexadecimal string
+    Key(String),
+    // The name of an environment variable providing the hexadecimal key (e.g. set by a KMS agent)
+    KeyEnv(String),
+}
+
+// the key is never displayed, neither in the logs nor in the admin space
+impl std::fmt::Debug for EncryptionConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EncryptionConfig::Key(_) => f.debug_tuple("Key").field(&REDACTED).finish(),
+            EncryptionConfig::KeyEnv(var) => f.debug_tuple("KeyEnv").field(var).finish(),
+        }
+    }
+}
+
+const REDACTED: &str = "<redacted>";
+
+impl EncryptionConfig {
+    pub const KEY_SIZE: usize = 32;
+
+    /// The configuration as JSON, the key being redacted.
+    pub fn to_json_value(&self) -> Value {
+        match self {
+            EncryptionConfig::Key(_) => serde_json::json!({ "key": REDACTED }),
+            EncryptionConfig::KeyEnv(var) => serde_json::json!({ "key_env": var }),
+        }
+    }
+
+    /// Resolves the key to use for encryption.
+    pub fn key(&self) -> ZResult<[u8; Self::KEY_SIZE]> {
+        let hex = match self {
+            EncryptionConfig::Key(key) => key.clone(),
+            EncryptionConfig::KeyEnv(var) => std::env::var(var)
+                .map_err(|e| zerror!("Unable to read encryption key from `{}`: {}", var, e))?,
+        };
+        let hex = hex.trim();
+        if hex.len() != 2 * Self::KEY_SIZE || !hex.is_ascii() {
+            bail!(
+                "Encryption key must be a {} characters hexadecimal string",
+                2 * Self::KEY_SIZE
+            )
+        }
+        let mut key = [0_u8; Self::KEY_SIZE];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16)
+                .map_err(|e| zerror!("Invalid encryption key: {}", e))?;
+        }
+        Ok(key)
+    }
 }
 // Note: All parameters should be same for replicas, else will result on huge overhead
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -322,6 +378,9 @@ impl StorageConfig {
                 _ => unreachable!(),
             },
         );
+        if let Some(encryption) = &self.encryption {
+            result.insert("encryption".into(), encryption.to_json_value());
+        }
         Value::Object(result)
     }
     fn try_from<V: AsObject>(plugin_name: &str, storage_name: &str, config: &V) -> ZResult<Self> {
@@ -458,6 +517,18 @@ impl StorageConfig {
             }
             None => None,
         };
+        let encryption = match config.get("encryption") {
+            Some(Value::Object(e)) => match (e.get("key"), e.get("key_env")) {
+                (Some(Value::String(key)), None) => Some(EncryptionConfig::Key(key.clone())),
+                (None, Some(Value::String(var))) => Some(EncryptionConfig::KeyEnv(var.clone())),
+                _ => bail!("`encryption` field of storage `{}` must be an object with either a `key` or a `key_env` string-typed field", storage_name)
+            },
+            None => None,
+            _ => bail!("Invalid type for field `encryption` of storage `{}`. Only objects are accepted.", storage_name)
+        };
+        if let Some(encryption) = &encryption {
+            encryption.key()?;
+        }
         Ok(StorageConfig {
             name: storage_name.into(),
             key_expr,
@@ -467,6 +538,7 @@ impl StorageConfig {
             volume_cfg,
             garbage_collection_config,
             replica_config,
+            encryption,
         })
     }
 }
@@ -0,0 +1,348 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! ⚠️ WARNING ⚠️
+//!
+//! This crate is intended for Zenoh's internal use.
+//!
+//! [Click here for Zenoh's documentation](../zenoh/index.html)
+//!
+//! A plugin exposing the administration of its router over the gRPC service `zenoh.admin.v1.Admin`
+//! defined in `proto/zenoh_admin.proto`: its admin space, its sessions, the storages of its
+//! storage manager and its configuration. The typed client of this service is [`proto::admin_client::AdminClient`].
+use std::convert::TryFrom;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::oneshot;
+use tonic::{Request, Response, Status};
+use zenoh::config::{EndPoint, ValidatedMap, ZenohId};
+use zenoh::plugins::{Plugin, PluginTasks, RunningPluginTrait, ZenohPlugin};
+use zenoh::prelude::r#async::*;
+use zenoh::runtime::Runtime;
+use zenoh::Session;
+use zenoh_result::{bail, zerror, ZResult};
+
+mod config;
+pub use config::Config;
+
+pub mod proto {
+    tonic::include_proto!("zenoh.admin.v1");
+}
+use proto::admin_server::{Admin, AdminServer};
+
+const GIT_VERSION: &str = git_version::git_version!(prefix = "v", cargo_prefix = "v");
+
+// The path of the storages in the configuration of the router
+const STORAGES_PATH: &str = "plugins/storage_manager/storages";
+
+zenoh_plugin_trait::declare_plugin!(GrpcPlugin);
+pub struct GrpcPlugin {}
+
+impl ZenohPlugin for GrpcPlugin {}
+
+impl Plugin for GrpcPlugin {
+    type StartArgs = Runtime;
+    type RunningPlugin = zenoh::plugins::RunningPlugin;
+    const STATIC_NAME: &'static str = "grpc";
+
+    fn start(name: &str, runtime: &Self::StartArgs) -> ZResult<zenoh::plugins::RunningPlugin> {
+        // Try to initiate login.
+        // Required in case of dynamic lib, otherwise no logs.
+        // But cannot be done twice in case of static link.
+        let _ = env_logger::try_init();
+        log::debug!("gRPC plugin {}", GIT_VERSION);
+
+        let runtime_conf = runtime.config.lock();
+        let plugin_conf = runtime_conf
+            .plugin(name)
+            .ok_or_else(|| zerror!("Plugin `{}`: missing config", name))?;
+
+        let conf: Config = serde_json::from_value(plugin_conf.clone())
+            .map_err(|e| zerror!("Plugin `{}` configuration error: {}", name, e))?;
+        let addr = SocketAddr::from_str(&conf.grpc_port)
+            .map_err(|e| zerror!("Plugin `{}`: invalid grpc_port: {}", name, e))?;
+
+        // tonic requires a tokio runtime, the server gets its own one
+        let (stop, stopped) = oneshot::channel();
+        let tasks = PluginTasks::new(runtime, name);
+        let runtime = runtime.clone();
+        std::thread::Builder::new()
+            .name("zenoh-plugin-grpc".into())
+            .spawn(move || match tokio::runtime::Runtime::new() {
+                Ok(rt) => {
+                    rt.block_on(tasks.isolate(run(runtime, addr, stopped)));
+                }
+                Err(e) => log::error!("Unable to start tokio runtime for gRPC: {}", e),
+            })
+            .map_err(|e| zerror!("Plugin `{}`: {}", name, e))?;
+        Ok(Box::new(RunningPlugin(conf, stop)))
+    }
+}
+
+// Dropping the plugin stops its server
+struct RunningPlugin(Config, #[allow(dead_code)] oneshot::Sender<()>);
+impl RunningPluginTrait for RunningPlugin {
+    fn config_checker(&self) -> zenoh::plugins::ValidationFunction {
+        Arc::new(|_, _, _| {
+            bail!("zenoh-plugin-grpc doesn't accept any runtime configuration changes")
+        })
+    }
+
+    fn adminspace_getter<'a>(
+        &'a self,
+        selector: &'a Selector<'a>,
+        plugin_status_key: &str,
+    ) -> ZResult<Vec<zenoh::plugins::Response>> {
+        let mut responses = Vec::new();
+        for (suffix, value) in [
+            ("/version", GIT_VERSION.into()),
+            ("/port", (&self.0).into()),
+        ] {
+            let key = format!("{plugin_status_key}{suffix}");
+            if keyexpr::new(key.as_str())?.intersects(&selector.key_expr) {
+                responses.push(zenoh::plugins::Response::new(key, value));
+            }
+        }
+        Ok(responses)
+    }
+}
+
+// A selector of the admin space of the router from one relative to it
+fn admin_selector(selector: &str, zid: &str) -> ZResult<Selector<'static>> {
+    let selector = selector.trim_start_matches('/');
+    if selector.is_empty() || selector.starts_with('?') {
+        Selector::try_from(format!("@/router/{zid}{selector}"))
+    } else {
+        Selector::try_from(format!("@/router/{zid}/{selector}"))
+    }
+}
+
+fn storage_path(name: &str) -> Result<String, Status> {
+    if name.is_empty() || name.contains('/') {
+        return Err(Status::invalid_argument(format!(
+            "Invalid storage name: '{name}'"
+        )));
+    }
+    Ok(format!("{STORAGES_PATH}/{name}"))
+}
+
+struct AdminService {
+    runtime: Runtime,
+    session: Arc<Session>,
+    zid: String,
+}
+
+impl AdminService {
+    fn insert_config(&self, path: &str, value: &str) -> Result<(), Status> {
+        (&self.runtime.config)
+            .insert_json5(path, value)
+            .map_err(|e| Status::invalid_argument(format!("{path}: {e}")))
+    }
+}
+
+#[tonic::async_trait]
+impl Admin for AdminService {
+    async fn get(
+        &self,
+        request: Request<proto::GetRequest>,
+    ) -> Result<Response<proto::GetReply>, Status> {
+        let selector = admin_selector(&request.get_ref().selector, &self.zid)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let replies = self
+            .session
+            .get(selector)
+            .res()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let mut values = Vec::new();
+        while let Ok(reply) = replies.recv_async().await {
+            if let Ok(sample) = reply.sample {
+                values.push(proto::KeyValue {
+                    key: sample.key_expr.to_string(),
+                    encoding: sample.value.encoding.to_string(),
+                    value: sample.value.to_string(),
+                });
+            }
+        }
+        Ok(Response::new(proto::GetReply { values }))
+    }
+
+    async fn list_sessions(
+        &self,
+        _request: Request<proto::ListSessionsRequest>,
+    ) -> Result<Response<proto::ListSessionsReply>, Status> {
+        let sessions = self
+            .runtime
+            .get_sessions()
+            .into_iter()
+            .map(|peer| proto::Session {
+                zid: peer.zid.to_string(),
+                whatami: peer.whatami.to_string(),
+                links: peer
+                    .links
+                    .iter()
+                    .map(|link| proto::Link {
+                        src: link.src.to_string(),
+                        dst: link.dst.to_string(),
+                    })
+                    .collect(),
+                qos: peer.is_qos,
+                shm: peer.is_shm,
+            })
+            .collect();
+        Ok(Response::new(proto::ListSessionsReply { sessions }))
+    }
+
+    async fn add_peer(
+        &self,
+        request: Request<proto::AddPeerRequest>,
+    ) -> Result<Response<proto::AddPeerReply>, Status> {
+        let endpoint = EndPoint::from_str(&request.get_ref().endpoint)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let transport = self
+            .runtime
+            .manager()
+            .open_transport(endpoint)
+            .await
+            .map_err(|e| Status::unavailable(e.to_string()))?;
+        let zid = transport
+            .get_zid()
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(proto::AddPeerReply {
+            zid: zid.to_string(),
+        }))
+    }
+
+    async fn close_session(
+        &self,
+        request: Request<proto::CloseSessionRequest>,
+    ) -> Result<Response<proto::CloseSessionReply>, Status> {
+        let zid = ZenohId::from_str(&request.get_ref().zid)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        self.runtime
+            .close_session(&zid)
+            .await
+            .map_err(|e| Status::not_found(e.to_string()))?;
+        Ok(Response::new(proto::CloseSessionReply {}))
+    }
+
+    async fn list_storages(
+        &self,
+        _request: Request<proto::ListStoragesRequest>,
+    ) -> Result<Response<proto::ListStoragesReply>, Status> {
+        let storages = self
+            .runtime
+            .config
+            .lock()
+            .plugin("storage_manager")
+            .and_then(|conf| conf.get("storages"))
+            .and_then(serde_json::Value::as_object)
+            .map(|storages| {
+                storages
+                    .iter()
+                    .map(|(name, conf)| proto::Storage {
+                        name: name.clone(),
+                        key_expr: conf
+                            .get("key_expr")
+                            .and_then(serde_json::Value::as_str)
+                            .unwrap_or_default()
+                            .to_string(),
+                        config: conf.to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Response::new(proto::ListStoragesReply { storages }))
+    }
+
+    async fn put_storage(
+        &self,
+        request: Request<proto::PutStorageRequest>,
+    ) -> Result<Response<proto::PutStorageReply>, Status> {
+        let request = request.get_ref();
+        self.insert_config(&storage_path(&request.name)?, &request.config)?;
+        Ok(Response::new(proto::PutStorageReply {}))
+    }
+
+    async fn delete_storage(
+        &self,
+        request: Request<proto::DeleteStorageRequest>,
+    ) -> Result<Response<proto::DeleteStorageReply>, Status> {
+        self.runtime
+            .config
+            .remove(storage_path(&request.get_ref().name)?)
+            .map_err(|e| Status::not_found(e.to_string()))?;
+        Ok(Response::new(proto::DeleteStorageReply {}))
+    }
+
+    async fn put_config(
+        &self,
+        request: Request<proto::PutConfigRequest>,
+    ) -> Result<Response<proto::PutConfigReply>, Status> {
+        let request = request.get_ref();
+        self.insert_config(&request.path, &request.value)?;
+        Ok(Response::new(proto::PutConfigReply {}))
+    }
+}
+
+async fn run(runtime: Runtime, addr: SocketAddr, stopped: oneshot::Receiver<()>) {
+    let zid = runtime.zid.to_string();
+    let session = match zenoh::init(runtime.clone()).res().await {
+        Ok(session) => Arc::new(session),
+        Err(e) => {
+            log::error!("Unable to open session for gRPC: {}", e);
+            return;
+        }
+    };
+    let service = AdminService {
+        runtime,
+        session,
+        zid,
+    };
+    if let Err(e) = tonic::transport::Server::builder()
+        .add_service(AdminServer::new(service))
+        .serve_with_shutdown(addr, async {
+            let _ = stopped.await;
+        })
+        .await
+    {
+        log::error!("Unable to start gRPC server: {}", e);
+    }
+}
+
+#[test]
+fn admin_selectors() {
+    let zid = "a1b2";
+    assert_eq!(
+        admin_selector("", zid).unwrap().to_string(),
+        "@/router/a1b2"
+    );
+    assert_eq!(
+        admin_selector("?_stats=true", zid).unwrap().to_string(),
+        "@/router/a1b2?_stats=true"
+    );
+    assert_eq!(
+        admin_selector("/subscriber/**", zid).unwrap().to_string(),
+        "@/router/a1b2/subscriber/**"
+    );
+    assert!(admin_selector("config//mode", zid).is_err());
+
+    assert_eq!(
+        storage_path("demo").unwrap(),
+        "plugins/storage_manager/storages/demo"
+    );
+    assert!(storage_path("").is_err());
+    assert!(storage_path("a/b").is_err());
+}
@@ -27,7 +27,7 @@ use std::sync::Arc;
 use tide::http::Mime;
 use tide::sse::Sender;
 use tide::{Request, Response, Server, StatusCode};
-use zenoh::plugins::{Plugin, RunningPluginTrait, ZenohPlugin};
+use zenoh::plugins::{Plugin, PluginTasks, RunningPluginTrait, ZenohPlugin};
 use zenoh::prelude::r#async::*;
 use zenoh::query::{QueryConsolidation, Reply};
 use zenoh::runtime::Runtime;
@@ -198,7 +198,7 @@ impl Plugin for RestPlugin {
 
         let conf: Config = serde_json::from_value(plugin_conf.clone())
             .map_err(|e| zerror!("Plugin `{}` configuration error: {}", name, e))?;
-        async_std::task::spawn(run(runtime.clone(), conf.clone()));
+        PluginTasks::new(runtime, name).spawn(run(runtime.clone(), conf.clone()));
         Ok(Box::new(RunningPlugin(conf)))
     }
 }
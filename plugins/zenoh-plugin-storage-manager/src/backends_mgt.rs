@@ -11,9 +11,11 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
+use super::encryption::EncryptedStorage;
 use super::storages_mgt::*;
 use flume::Sender;
 use std::sync::Arc;
+use zenoh::plugins::PluginTasks;
 use zenoh::prelude::r#async::*;
 use zenoh::Session;
 use zenoh_backend_traits::config::StorageConfig;
@@ -34,10 +36,15 @@ pub(crate) async fn create_and_start_storage(
     in_interceptor: Option<Arc<dyn Fn(Sample) -> Sample + Send + Sync>>,
     out_interceptor: Option<Arc<dyn Fn(Sample) -> Sample + Send + Sync>>,
     zenoh: Arc<Session>,
+    tasks: &PluginTasks,
 ) -> ZResult<Sender<StorageMessage>> {
     log::trace!("Create storage {}", &admin_key);
     let capability = backend.get_capability();
     let storage = backend.create_storage(config.clone()).await?;
+    let storage: Box<dyn zenoh_backend_traits::Storage> = match &config.encryption {
+        Some(encryption) => Box::new(EncryptedStorage::new(storage, encryption)?),
+        None => storage,
+    };
     let store_intercept = StoreIntercept {
         storage,
         capability,
@@ -45,5 +52,5 @@ pub(crate) async fn create_and_start_storage(
         out_interceptor,
     };
 
-    start_storage(store_intercept, config, admin_key, zenoh).await
+    start_storage(store_intercept, config, admin_key, zenoh, tasks).await
 }
@@ -0,0 +1,209 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use async_trait::async_trait;
+use zenoh::buffers::{SplitBuffer, ZBuf};
+use zenoh::prelude::r#async::*;
+use zenoh::time::Timestamp;
+use zenoh_backend_traits::config::EncryptionConfig;
+use zenoh_backend_traits::{Storage, StorageInsertionResult, StoredData};
+use zenoh_crypto::AeadCipher;
+use zenoh_result::ZResult;
+
+/// A [`Storage`] wrapper encrypting the payloads before they reach the backend,
+/// so that persisted values aren't readable from disk dumps.
+/// Keys, encodings and timestamps are left in clear, as they are needed for indexing.
+pub struct EncryptedStorage {
+    inner: Box<dyn Storage>,
+    cipher: AeadCipher,
+}
+
+impl EncryptedStorage {
+    pub fn new(inner: Box<dyn Storage>, config: &EncryptionConfig) -> ZResult<EncryptedStorage> {
+        Ok(EncryptedStorage {
+            inner,
+            cipher: AeadCipher::new(config.key()?),
+        })
+    }
+
+    fn encrypt(&self, value: Value) -> ZResult<Value> {
+        let encrypted = self.cipher.encrypt(&value.payload.contiguous())?;
+        Ok(Value::new(ZBuf::from(encrypted)).encoding(value.encoding))
+    }
+
+    fn decrypt(&self, value: Value) -> ZResult<Value> {
+        let decrypted = self.cipher.decrypt(&value.payload.contiguous())?;
+        Ok(Value::new(ZBuf::from(decrypted)).encoding(value.encoding))
+    }
+}
+
+#[async_trait]
+impl Storage for EncryptedStorage {
+    fn get_admin_status(&self) -> serde_json::Value {
+        let mut status = self.inner.get_admin_status();
+        if let Some(status) = status.as_object_mut() {
+            status.insert("encrypted".into(), true.into());
+        }
+        status
+    }
+
+    async fn put(
+        &mut self,
+        key: Option<OwnedKeyExpr>,
+        value: Value,
+        timestamp: Timestamp,
+    ) -> ZResult<StorageInsertionResult> {
+        let value = self.encrypt(value)?;
+        self.inner.put(key, value, timestamp).await
+    }
+
+    async fn delete(
+        &mut self,
+        key: Option<OwnedKeyExpr>,
+        timestamp: Timestamp,
+    ) -> ZResult<StorageInsertionResult> {
+        self.inner.delete(key, timestamp).await
+    }
+
+    async fn get(
+        &mut self,
+        key: Option<OwnedKeyExpr>,
+        parameters: &str,
+    ) -> ZResult<Vec<StoredData>> {
+        let stored = self.inner.get(key, parameters).await?;
+        let mut result = Vec::with_capacity(stored.len());
+        for StoredData { value, timestamp } in stored {
+            result.push(StoredData {
+                value: self.decrypt(value)?,
+                timestamp,
+            });
+        }
+        Ok(result)
+    }
+
+    async fn get_all_entries(&self) -> ZResult<Vec<(Option<OwnedKeyExpr>, Timestamp)>> {
+        self.inner.get_all_entries().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_std::sync::{Arc, Mutex};
+    use async_std::task;
+    use std::collections::HashMap;
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    // A storage keeping the values it is given in a shared map
+    #[derive(Default)]
+    struct MapStorage(Arc<Mutex<HashMap<Option<OwnedKeyExpr>, StoredData>>>);
+
+    #[async_trait]
+    impl Storage for MapStorage {
+        fn get_admin_status(&self) -> serde_json::Value {
+            serde_json::json!({})
+        }
+
+        async fn put(
+            &mut self,
+            key: Option<OwnedKeyExpr>,
+            value: Value,
+            timestamp: Timestamp,
+        ) -> ZResult<StorageInsertionResult> {
+            self.0
+                .lock()
+                .await
+                .insert(key, StoredData { value, timestamp });
+            Ok(StorageInsertionResult::Inserted)
+        }
+
+        async fn delete(
+            &mut self,
+            key: Option<OwnedKeyExpr>,
+            _timestamp: Timestamp,
+        ) -> ZResult<StorageInsertionResult> {
+            self.0.lock().await.remove(&key);
+            Ok(StorageInsertionResult::Deleted)
+        }
+
+        async fn get(
+            &mut self,
+            key: Option<OwnedKeyExpr>,
+            _parameters: &str,
+        ) -> ZResult<Vec<StoredData>> {
+            Ok(self.0.lock().await.get(&key).cloned().into_iter().collect())
+        }
+
+        async fn get_all_entries(&self) -> ZResult<Vec<(Option<OwnedKeyExpr>, Timestamp)>> {
+            Ok(self
+                .0
+                .lock()
+                .await
+                .iter()
+                .map(|(key, stored)| (key.clone(), stored.timestamp))
+                .collect())
+        }
+    }
+
+    #[test]
+    fn encrypted_storage() {
+        task::block_on(async {
+            let inner = MapStorage::default();
+            let stored = inner.0.clone();
+            let config = EncryptionConfig::Key(KEY.into());
+            let mut storage = EncryptedStorage::new(Box::new(inner), &config).unwrap();
+            assert_eq!(storage.get_admin_status()["encrypted"], true);
+
+            let key = Some(OwnedKeyExpr::try_from("demo/encrypted/a").unwrap());
+            let value = Value::from("secret value").encoding(Encoding::TEXT_PLAIN);
+            storage
+                .put(key.clone(), value, zenoh::time::new_reception_timestamp())
+                .await
+                .unwrap();
+
+            // the values are encrypted at rest
+            let at_rest = stored.lock().await.get(&key).cloned().unwrap().value;
+            let encrypted = at_rest.payload.contiguous();
+            assert!(!encrypted
+                .windows("secret value".len())
+                .any(|w| w == "secret value".as_bytes()));
+            assert_eq!(at_rest.encoding, Encoding::TEXT_PLAIN);
+
+            // and decrypted when read
+            let read = storage.get(key.clone(), "").await.unwrap();
+            assert_eq!(read.len(), 1);
+            assert_eq!(
+                read[0].value.payload.contiguous().as_ref(),
+                "secret value".as_bytes()
+            );
+            assert_eq!(read[0].value.encoding, Encoding::TEXT_PLAIN);
+
+            // the values encrypted with another key can't be read
+            let other = EncryptionConfig::Key(KEY.replace('0', "f"));
+            let mut storage = EncryptedStorage::new(Box::new(MapStorage(stored)), &other).unwrap();
+            assert!(storage.get(key, "").await.is_err());
+        });
+    }
+
+    #[test]
+    fn encryption_key_redacted() {
+        let config = EncryptionConfig::Key(KEY.into());
+        assert!(!format!("{config:?}").contains(KEY));
+        assert!(!config.to_json_value().to_string().contains(KEY));
+        assert_eq!(
+            EncryptionConfig::KeyEnv("ZENOH_STORAGE_KEY".into()).to_json_value(),
+            serde_json::json!({ "key_env": "ZENOH_STORAGE_KEY" })
+        );
+    }
+}
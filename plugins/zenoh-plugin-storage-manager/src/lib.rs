@@ -30,7 +30,7 @@ use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::sync::Mutex;
 use storages_mgt::StorageMessage;
-use zenoh::plugins::{Plugin, RunningPluginTrait, ValidationFunction, ZenohPlugin};
+use zenoh::plugins::{Plugin, PluginTasks, RunningPluginTrait, ValidationFunction, ZenohPlugin};
 use zenoh::prelude::sync::*;
 use zenoh::runtime::Runtime;
 use zenoh::Session;
@@ -43,6 +43,7 @@ use zenoh_util::LibLoader;
 
 mod backends_mgt;
 use backends_mgt::*;
+mod encryption;
 mod memory_backend;
 mod replica;
 mod storages_mgt;
@@ -77,6 +78,7 @@ struct StorageRuntimeInner {
     name: String,
     runtime: Runtime,
     session: Arc<Session>,
+    tasks: PluginTasks,
     lib_loader: LibLoader,
     volumes: HashMap<String, VolumeHandle>,
     storages: HashMap<String, HashMap<String, Sender<StorageMessage>>>,
@@ -105,10 +107,12 @@ impl StorageRuntimeInner {
             .unwrap_or_default();
 
         let session = Arc::new(zenoh::init(runtime.clone()).res_sync().unwrap());
+        let tasks = PluginTasks::new(&runtime, &name);
         let mut new_self = StorageRuntimeInner {
             name,
             runtime,
             session,
+            tasks,
             lib_loader,
             volumes: Default::default(),
             storages: Default::default(),
@@ -265,6 +269,7 @@ impl StorageRuntimeInner {
                 in_interceptor,
                 out_interceptor,
                 self.session.clone(),
+                &self.tasks,
             ))?;
             self.storages
                 .entry(volume_id)
@@ -18,11 +18,12 @@ use async_std::sync::{Mutex, RwLock};
 use async_trait::async_trait;
 use flume::{Receiver, Sender};
 use futures::select;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::str::{self, FromStr};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use zenoh::buffers::ZBuf;
 use zenoh::prelude::r#async::*;
+use zenoh::selector::Aggregation;
 use zenoh::time::{Timestamp, NTP64};
 use zenoh::{Result as ZResult, Session};
 use zenoh_backend_traits::config::{GarbageCollectionConfig, StorageConfig};
@@ -271,7 +272,10 @@ impl StorageService {
             sample
         };
 
-        // if wildcard, update wildcard_updates
+        // A write on a wildcard key expression (e.g. a DELETE on `demo/**`) applies to the keys
+        // it matches that are already stored: it never creates new keys.
+        // It's also remembered in wildcard_updates, so that older updates on matching keys
+        // received afterwards (out of order) are overridden by it.
         if sample.key_expr.is_wild() {
             self.register_wildcard_update(sample.clone()).await;
         }
@@ -330,7 +334,7 @@ impl StorageService {
                     }
                 };
                 let mut storage = self.storage.lock().await;
-                let result = if sample.kind == SampleKind::Put {
+                let result = if sample_to_store.kind == SampleKind::Put {
                     storage
                         .put(
                             stripped_key,
@@ -338,7 +342,7 @@ impl StorageService {
                             sample_to_store.timestamp.unwrap(),
                         )
                         .await
-                } else if sample.kind == SampleKind::Delete {
+                } else if sample_to_store.kind == SampleKind::Delete {
                     // register a tombstone
                     self.mark_tombstone(&k, sample_to_store.timestamp.unwrap())
                         .await;
@@ -500,6 +504,25 @@ impl StorageService {
             }
         };
         log::trace!("[STORAGE] Processing query on key_expr: {}", q.key_expr());
+        let parameters = q.parameters();
+        let (projection, aggregation) = match parameters
+            .projection()
+            .and_then(|p| Ok((p, parameters.aggregation()?)))
+        {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                let err_message = format!("Storage {} received an invalid query: {}", self.name, e);
+                log::warn!("{}", err_message);
+                if let Err(e) = q.reply(Err(err_message.into())).res().await {
+                    log::warn!(
+                        "Storage {} raised an error replying a query: {}",
+                        self.name,
+                        e
+                    )
+                }
+                return;
+            }
+        };
         if q.key_expr().is_wild() {
             // resolve key expr into individual keys
             let matching_keys = self.get_matching_keys(q.key_expr()).await;
@@ -515,9 +538,19 @@ impl StorageService {
                 };
                 match storage.get(stripped_key, q.parameters()).await {
                     Ok(stored_data) => {
+                        let stored_data = match aggregation {
+                            Some((aggregation, window)) => {
+                                aggregate(stored_data, aggregation, window)
+                            }
+                            None => stored_data,
+                        };
                         for entry in stored_data {
-                            let sample = Sample::new(key.clone(), entry.value)
-                                .with_timestamp(entry.timestamp);
+                            let value = match &projection {
+                                Some(fields) => project(entry.value, fields),
+                                None => entry.value,
+                            };
+                            let sample =
+                                Sample::new(key.clone(), value).with_timestamp(entry.timestamp);
                             // apply outgoing interceptor on results
                             let sample = if let Some(ref interceptor) = self.out_interceptor {
                                 interceptor(sample)
@@ -561,8 +594,16 @@ impl StorageService {
                         }
                         return;
                     }
+                    let stored_data = match aggregation {
+                        Some((aggregation, window)) => aggregate(stored_data, aggregation, window),
+                        None => stored_data,
+                    };
                     for entry in stored_data {
-                        let sample = Sample::new(q.key_expr().clone(), entry.value)
+                        let value = match &projection {
+                            Some(fields) => project(entry.value, fields),
+                            None => entry.value,
+                        };
+                        let sample = Sample::new(q.key_expr().clone(), value)
                             .with_timestamp(entry.timestamp);
                         // apply outgoing interceptor on results
                         let sample = if let Some(ref interceptor) = self.out_interceptor {
@@ -690,6 +731,113 @@ impl StorageService {
     }
 }
 
+// Aggregates the numerical values of `entries`, either all together or per time window.
+// Each aggregate is timestamped with the start of its window (or the latest timestamp without window).
+// Non-numerical values are ignored by all aggregations but `count`.
+fn aggregate(
+    entries: Vec<StoredData>,
+    aggregation: Aggregation,
+    window: Option<Duration>,
+) -> Vec<StoredData> {
+    let mut windows: BTreeMap<u128, (Timestamp, Vec<f64>, i64)> = BTreeMap::new();
+    for entry in entries {
+        let bucket = match window {
+            Some(window) => entry.timestamp.get_time().to_duration().as_nanos() / window.as_nanos(),
+            None => 0,
+        };
+        let (timestamp, values, count) = windows
+            .entry(bucket)
+            .or_insert_with(|| (entry.timestamp, Vec::new(), 0));
+        if entry.timestamp > *timestamp {
+            *timestamp = entry.timestamp;
+        }
+        *count += 1;
+        if let Some(value) = as_number(&entry.value) {
+            values.push(value);
+        }
+    }
+    windows
+        .into_iter()
+        .filter_map(|(bucket, (latest, values, count))| {
+            let value: Value = match aggregation {
+                Aggregation::Count => count.into(),
+                _ if values.is_empty() => return None,
+                Aggregation::Sum => values.iter().sum::<f64>().into(),
+                Aggregation::Avg => (values.iter().sum::<f64>() / values.len() as f64).into(),
+                Aggregation::Min => values.iter().copied().fold(f64::INFINITY, f64::min).into(),
+                Aggregation::Max => values
+                    .iter()
+                    .copied()
+                    .fold(f64::NEG_INFINITY, f64::max)
+                    .into(),
+            };
+            let timestamp = match window {
+                Some(window) => {
+                    let start = Duration::from_nanos((bucket * window.as_nanos()) as u64);
+                    Timestamp::new(NTP64::from(start), *latest.get_id())
+                }
+                None => latest,
+            };
+            Some(StoredData { value, timestamp })
+        })
+        .collect()
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value.encoding.prefix() {
+        KnownEncoding::AppFloat
+        | KnownEncoding::AppInteger
+        | KnownEncoding::TextPlain
+        | KnownEncoding::AppJson
+        | KnownEncoding::TextJson => str::from_utf8(&value.payload.contiguous())
+            .ok()?
+            .trim()
+            .parse()
+            .ok(),
+        _ => None,
+    }
+}
+
+// Keeps only the requested fields of a JSON-encoded value.
+// Fields are `.`-separated paths into nested objects. Non-JSON values are returned untouched.
+fn project(value: Value, fields: &[String]) -> Value {
+    if !matches!(
+        value.encoding.prefix(),
+        KnownEncoding::AppJson | KnownEncoding::TextJson
+    ) {
+        return value;
+    }
+    let document: serde_json::Value = match serde_json::from_slice(&value.payload.contiguous()) {
+        Ok(document @ serde_json::Value::Object(_)) => document,
+        _ => return value,
+    };
+    let mut projected = serde_json::Map::new();
+    'fields: for field in fields {
+        let path: Vec<&str> = field.split('.').collect();
+        let field_value = match path
+            .iter()
+            .try_fold(&document, |source, name| source.get(name))
+        {
+            Some(field_value) => field_value.clone(),
+            None => continue,
+        };
+        let (name, parents) = path.split_last().unwrap();
+        let mut target = &mut projected;
+        for parent in parents {
+            target = match target
+                .entry(*parent)
+                .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()))
+            {
+                serde_json::Value::Object(target) => target,
+                // a parent field was already projected as a whole
+                _ => continue 'fields,
+            };
+        }
+        target.insert(name.to_string(), field_value);
+    }
+    Value::from(serde_json::Value::Object(projected).to_string()).encoding(value.encoding)
+}
+
 fn serialize_update(update: &Update) -> String {
     let result = (
         update.kind.to_string(),
@@ -763,3 +911,102 @@ impl Timed for GarbageCollectionEvent {
         log::trace!("End garbage collection of obsolete data-infos");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zenoh::time::TimestampId;
+
+    fn at(secs: u64) -> Timestamp {
+        Timestamp::new(
+            NTP64::from(Duration::from_secs(secs)),
+            TimestampId::try_from([1]).unwrap(),
+        )
+    }
+
+    fn entries() -> Vec<StoredData> {
+        [
+            (0, Value::from(1i64)),
+            (10, Value::from(2.5f64)),
+            (30, Value::from("3")),
+            (65, Value::from(4i64)),
+            (70, Value::from("n/a")),
+            (80, Value::from(vec![5u8])),
+            (130, Value::from("n/a")),
+        ]
+        .into_iter()
+        .map(|(secs, value)| StoredData {
+            value,
+            timestamp: at(secs),
+        })
+        .collect()
+    }
+
+    fn aggregated(
+        aggregation: Aggregation,
+        window: Option<Duration>,
+    ) -> Vec<(Option<f64>, Timestamp)> {
+        aggregate(entries(), aggregation, window)
+            .into_iter()
+            .map(|entry| (as_number(&entry.value), entry.timestamp))
+            .collect()
+    }
+
+    #[test]
+    fn aggregate_all() {
+        // the aggregate of all the values is timestamped with the latest of them
+        assert_eq!(
+            aggregated(Aggregation::Avg, None),
+            vec![(Some(10.5 / 4.0), at(130))]
+        );
+        assert_eq!(
+            aggregated(Aggregation::Sum, None),
+            vec![(Some(10.5), at(130))]
+        );
+        assert_eq!(
+            aggregated(Aggregation::Min, None),
+            vec![(Some(1.0), at(130))]
+        );
+        assert_eq!(
+            aggregated(Aggregation::Max, None),
+            vec![(Some(4.0), at(130))]
+        );
+        // the non-numerical values are only counted
+        assert_eq!(
+            aggregated(Aggregation::Count, None),
+            vec![(Some(7.0), at(130))]
+        );
+        assert!(aggregate(vec![], Aggregation::Count, None).is_empty());
+    }
+
+    #[test]
+    fn aggregate_windows() {
+        let minute = Some(Duration::from_secs(60));
+        // the aggregate of each window is timestamped with its start,
+        // and the windows without numerical values are dropped
+        assert_eq!(
+            aggregated(Aggregation::Avg, minute),
+            vec![(Some(6.5 / 3.0), at(0)), (Some(4.0), at(60))]
+        );
+        assert_eq!(
+            aggregated(Aggregation::Sum, minute),
+            vec![(Some(6.5), at(0)), (Some(4.0), at(60))]
+        );
+        assert_eq!(
+            aggregated(Aggregation::Min, minute),
+            vec![(Some(1.0), at(0)), (Some(4.0), at(60))]
+        );
+        assert_eq!(
+            aggregated(Aggregation::Max, minute),
+            vec![(Some(3.0), at(0)), (Some(4.0), at(60))]
+        );
+        assert_eq!(
+            aggregated(Aggregation::Count, minute),
+            vec![
+                (Some(3.0), at(0)),
+                (Some(3.0), at(60)),
+                (Some(1.0), at(120))
+            ]
+        );
+    }
+}
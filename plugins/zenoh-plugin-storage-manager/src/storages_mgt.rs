@@ -12,6 +12,7 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 use async_std::sync::Arc;
+use zenoh::plugins::PluginTasks;
 use zenoh::Session;
 use zenoh_backend_traits::config::StorageConfig;
 use zenoh_result::ZResult;
@@ -28,6 +29,7 @@ pub(crate) async fn start_storage(
     config: StorageConfig,
     admin_key: String,
     zenoh: Arc<Session>,
+    tasks: &PluginTasks,
 ) -> ZResult<flume::Sender<StorageMessage>> {
     // Ex: @/router/390CEC11A1E34977A1C609A35BC015E6/status/plugins/storage_manager/storages/demo1 -> 390CEC11A1E34977A1C609A35BC015E6/demo1 (/<type> needed????)
     let parts: Vec<&str> = admin_key.split('/').collect();
@@ -39,7 +41,7 @@ pub(crate) async fn start_storage(
 
     let (tx, rx) = flume::bounded(1);
 
-    async_std::task::spawn(async move {
+    tasks.spawn(async move {
         // If a configuration for replica is present, we initialize a replica, else only a storage service
         // A replica contains a storage service and all metadata required for anti-entropy
         if config.replica_config.is_some() {
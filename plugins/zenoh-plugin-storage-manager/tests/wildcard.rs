@@ -15,6 +15,7 @@
 // Test wild card updates -
 // 1. normal case, just some wild card puts and deletes on existing keys and ensure it works
 // 2. check for dealing with out of order updates
+// 3. wild card updates received by storages with overlapping key expressions
 
 use std::str::FromStr;
 use std::thread::sleep;
@@ -174,6 +175,82 @@ async fn test_wild_card_in_order() {
     drop(storage);
 }
 
+async fn test_wild_card_overlapping_storages() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+    let mut config = Config::default();
+    config
+        .insert_json5(
+            "plugins/storage-manager",
+            r#"{
+                    storages: {
+                        overlap_a: {
+                            key_expr: "overlap/a/**",
+                            volume: {
+                                id: "memory"
+                            }
+                        },
+                        overlap_all: {
+                            key_expr: "overlap/**",
+                            volume: {
+                                id: "memory"
+                            }
+                        }
+                    }
+                }"#,
+        )
+        .unwrap();
+
+    let runtime = zenoh::runtime::Runtime::new(config).await.unwrap();
+    let storage =
+        zenoh_plugin_storage_manager::StoragesPlugin::start("storage-manager", &runtime).unwrap();
+
+    let session = zenoh::init(runtime).res().await.unwrap();
+    sleep(std::time::Duration::from_secs(1));
+
+    let timestamp =
+        Timestamp::from_str("2022-01-17T10:42:10.418555997Z/BC779A06D7E049BD88C3FF3DB0C17FCC")
+            .unwrap();
+    put_data(&session, "overlap/a/1", "1", timestamp).await;
+    put_data(&session, "overlap/a/2", "2", timestamp).await;
+    put_data(&session, "overlap/b/1", "3", timestamp).await;
+
+    sleep(std::time::Duration::from_millis(10));
+
+    // expected three entries, the ones under overlap/a being stored by both storages
+    let data = get_data(&session, "overlap/**").await;
+    assert_eq!(data.len(), 3);
+    let data = get_data(&session, "overlap/a/**").await;
+    assert_eq!(data.len(), 2);
+
+    // the wild card delete only applies to the matching keys, in both storages
+    delete_data(&session, "overlap/a/*", timestamp).await;
+
+    sleep(std::time::Duration::from_millis(10));
+
+    // expected single entry
+    let data = get_data(&session, "overlap/**").await;
+    assert_eq!(data.len(), 1);
+    assert_eq!(data[0].key_expr.as_str(), "overlap/b/1");
+    assert_eq!(format!("{}", data[0].value), "3");
+    let data = get_data(&session, "overlap/a/**").await;
+    assert_eq!(data.len(), 0);
+
+    // the wild card put only updates existing keys: deleted keys are not re-created
+    put_data(&session, "overlap/*/1", "4", timestamp).await;
+
+    sleep(std::time::Duration::from_millis(10));
+
+    // expected single entry
+    let data = get_data(&session, "overlap/**").await;
+    assert_eq!(data.len(), 1);
+    assert_eq!(data[0].key_expr.as_str(), "overlap/b/1");
+    assert_eq!(format!("{}", data[0].value), "4");
+
+    drop(storage);
+}
+
 // fn test_wild_card_out_of_order() {
 //     assert_eq!(true, true);
 // }
@@ -181,5 +258,6 @@ async fn test_wild_card_in_order() {
 #[test]
 fn wildcard_test() {
     task::block_on(async { test_wild_card_in_order().await });
+    task::block_on(async { test_wild_card_overlapping_storages().await });
     // task::block_on(async { test_wild_card_out_of_order() });
 }
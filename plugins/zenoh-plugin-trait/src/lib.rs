@@ -23,6 +23,17 @@ pub mod vtable;
 
 use zenoh_result::ZResult;
 
+/// The start arguments of the plugins, whose layout depends on the version and on the feature
+/// flags of the crate defining them: a plugin built against another version or other features
+/// of this crate than its host can't be started safely.
+pub trait PluginStartArgs {
+    /// The version of the crate defining the start arguments.
+    fn version() -> &'static str;
+    /// The feature flags the crate defining the start arguments was built with, among the ones
+    /// altering the layout of its types.
+    fn features() -> &'static str;
+}
+
 #[repr(C)]
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Compatibility {
@@ -31,10 +42,12 @@ pub struct Compatibility {
     patch: u64,
     stable: bool,
     commit: &'static str,
+    version: &'static str,
+    features: &'static str,
 }
 const RELEASE_AND_COMMIT: (&str, &str) = zenoh_macros::rustc_version_release!();
 impl Compatibility {
-    pub fn new() -> ZResult<Self> {
+    pub fn new<StartArgs: PluginStartArgs>() -> ZResult<Self> {
         let (release, commit) = RELEASE_AND_COMMIT;
         let (release, stable) = if let Some(p) = release.chars().position(|c| c == '-') {
             (&release[..p], false)
@@ -48,23 +61,65 @@ impl Compatibility {
             patch: split.next().unwrap().parse().unwrap(),
             stable,
             commit,
+            version: StartArgs::version(),
+            features: StartArgs::features(),
         })
     }
+    /// The differences between the builds of the host and of a plugin that make them incompatible,
+    /// empty if they are compatible.
+    pub fn incompatibilities(host: &Self, plugin: &Self) -> Vec<String> {
+        let mut incompatibilities = vec![];
+        let rustc_compatible = if host.stable && plugin.stable {
+            host.major == plugin.major && host.minor == plugin.minor && host.patch == plugin.patch
+        } else {
+            host.major == plugin.major
+                && host.minor == plugin.minor
+                && host.patch == plugin.patch
+                && host.stable == plugin.stable
+                && host.commit == plugin.commit
+        };
+        if !rustc_compatible {
+            incompatibilities.push(format!(
+                "built with rustc {} while the host was built with rustc {}",
+                plugin.rustc(),
+                host.rustc()
+            ));
+        }
+        if host.version != plugin.version {
+            incompatibilities.push(format!(
+                "built against zenoh {} while the host runs zenoh {}",
+                plugin.version, host.version
+            ));
+        }
+        if host.features != plugin.features {
+            incompatibilities.push(format!(
+                "built with the zenoh features [{}] while the host was built with [{}]",
+                plugin.features, host.features
+            ));
+        }
+        incompatibilities
+    }
     pub fn are_compatible(a: &Self, b: &Self) -> bool {
-        if a.stable && b.stable {
-            a.major == b.major && a.minor == b.minor && a.patch == b.patch
+        Self::incompatibilities(a, b).is_empty()
+    }
+    fn rustc(&self) -> String {
+        if self.stable {
+            format!("{}.{}.{}", self.major, self.minor, self.patch)
         } else {
-            a == b
+            format!(
+                "{}.{}.{}-nightly ({})",
+                self.major, self.minor, self.patch, self.commit
+            )
         }
     }
 }
 
 pub mod prelude {
-    pub use crate::{loading::*, vtable::*, Plugin};
+    pub use crate::{loading::*, vtable::*, Plugin, PluginStartArgs};
 }
 
 pub trait Plugin: Sized + 'static {
-    type StartArgs;
+    type StartArgs: PluginStartArgs;
     type RunningPlugin;
     /// Your plugins' default name when statically linked.
     const STATIC_NAME: &'static str;
@@ -72,8 +127,40 @@ pub trait Plugin: Sized + 'static {
     ///
     /// Returns some build information on your plugin, allowing the host to detect potential ABI changes that would break it.
     fn compatibility() -> ZResult<Compatibility> {
-        Compatibility::new()
+        Compatibility::new::<Self::StartArgs>()
     }
     /// Starts your plugin. Use `Ok` to return your plugin's control structure
     fn start(name: &str, args: &Self::StartArgs) -> ZResult<Self::RunningPlugin>;
 }
+
+#[test]
+fn compatibility() {
+    struct Args;
+    impl PluginStartArgs for Args {
+        fn version() -> &'static str {
+            "0.7.0"
+        }
+        fn features() -> &'static str {
+            "shared-memory"
+        }
+    }
+
+    let host = Compatibility::new::<Args>().unwrap();
+    assert!(Compatibility::are_compatible(&host, &host.clone()));
+
+    let plugin = Compatibility {
+        version: "0.6.0",
+        features: "",
+        ..host.clone()
+    };
+    let incompatibilities = Compatibility::incompatibilities(&host, &plugin);
+    assert_eq!(incompatibilities.len(), 2);
+    assert!(incompatibilities[0].contains("zenoh 0.6.0"));
+    assert!(incompatibilities[1].contains("[shared-memory]"));
+
+    let plugin = Compatibility {
+        patch: host.patch + 1,
+        ..host.clone()
+    };
+    assert!(!Compatibility::are_compatible(&host, &plugin));
+}
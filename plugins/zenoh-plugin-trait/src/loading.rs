@@ -28,7 +28,9 @@ pub struct PluginsManager<StartArgs, RunningPlugin> {
     running_plugins: HashMap<String, (String, RunningPlugin)>,
 }
 
-impl<StartArgs: 'static, RunningPlugin: 'static> PluginsManager<StartArgs, RunningPlugin> {
+impl<StartArgs: PluginStartArgs + 'static, RunningPlugin: 'static>
+    PluginsManager<StartArgs, RunningPlugin>
+{
     /// Constructs a new plugin manager with dynamic library loading enabled.
     pub fn dynamic(loader: LibLoader) -> Self {
         PluginsManager {
@@ -73,7 +75,8 @@ impl<StartArgs: 'static, RunningPlugin: 'static> PluginsManager<StartArgs, Runni
                 match self.plugin_starters.iter().find(|p| p.name() == plugin) {
                     Some(s) => {
                         let path = s.path();
-                        let (_, plugin) = e.insert((path.into(), s.start(args).map_err(|e| zerror!(e => "Failed to load plugin {} (from {})", plugin, path))?));
+                        check_compatibility::<StartArgs, RunningPlugin>(&**s)?;
+                        let (_, plugin) = e.insert((path.into(), start_isolated(&**s, args).map_err(|e| zerror!(e => "Failed to load plugin {} (from {})", plugin, path))?));
                         Ok(Some((path, &*plugin)))
                     }
                     None => bail!("Plugin starter for `{}` not found", plugin),
@@ -96,7 +99,6 @@ impl<StartArgs: 'static, RunningPlugin: 'static> PluginsManager<StartArgs, Runni
             running_plugins,
             ..
         } = self;
-        let compat = crate::Compatibility::new().unwrap();
         plugin_starters.iter().map(move |p| {
             let name = p.name();
             let path = p.path();
@@ -106,21 +108,10 @@ impl<StartArgs: 'static, RunningPlugin: 'static> PluginsManager<StartArgs, Runni
                 match running_plugins.entry(name.into()) {
                     std::collections::hash_map::Entry::Occupied(_) => Ok(None),
                     std::collections::hash_map::Entry::Vacant(e) => {
-                        let compatible = match p.compatibility() {
-                            Some(Ok(c)) => {
-                                if Compatibility::are_compatible(&compat, &c) {
-                                    Ok(())
-                                } else {
-                                    Err(zerror!("Plugin compatibility mismatch: host: {:?} - plugin: {:?}. This could lead to segfaults, so wer'e not starting it.", &compat, &c))
-                                }
-                            }
-                            Some(Err(e)) => Err(zerror!(e => "Plugin {} (from {}) compatibility couldn't be recovered. This likely means it's very broken.", name, path)),
-                            None => Ok(()),
-                        };
-                        if let Err(e) = compatible {
-                            Err(e.into())
+                        if let Err(e) = check_compatibility::<StartArgs, RunningPlugin>(&**p) {
+                            Err(e)
                         } else {
-                            match p.start(args) {
+                            match start_isolated(&**p, args) {
                                 Ok(p) => Ok(Some(unsafe {
                                     std::mem::transmute(&e.insert((path.into(), p)).1)
                                 })),
@@ -133,6 +124,19 @@ impl<StartArgs: 'static, RunningPlugin: 'static> PluginsManager<StartArgs, Runni
         })
     }
 
+    /// Restarts `plugin`, e.g. after it failed, without reloading its library.
+    pub fn restart(
+        &mut self,
+        plugin: &str,
+        args: &StartArgs,
+    ) -> ZResult<Option<(&str, &RunningPlugin)>> {
+        if let Some(running) = self.running_plugins.remove(plugin) {
+            // The plugin may panic again while being dropped
+            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| drop(running)));
+        }
+        self.start(plugin, args)
+    }
+
     /// Stops `plugin`, returning `true` if it was indeed running.
     pub fn stop(&mut self, plugin: &str) -> bool {
         let result = self.running_plugins.remove(plugin).is_some();
@@ -214,6 +218,49 @@ impl<StartArgs: 'static, RunningPlugin: 'static> PluginsManager<StartArgs, Runni
     }
 }
 
+/// Checks that the plugin was built with the same rustc, against the same version and with the
+/// same features of zenoh as the host, before calling into it.
+fn check_compatibility<StartArgs: PluginStartArgs, RunningPlugin>(
+    plugin: &dyn PluginStarter<StartArgs, RunningPlugin>,
+) -> ZResult<()> {
+    let (name, path) = (plugin.name(), plugin.path());
+    match plugin.compatibility() {
+        Some(Ok(compat)) => {
+            let host = Compatibility::new::<StartArgs>()?;
+            let incompatibilities = Compatibility::incompatibilities(&host, &compat);
+            if !incompatibilities.is_empty() {
+                bail!(
+                    "Plugin {} (from {}) is not compatible with its host, so it's not started as it could lead to segfaults: it was {}.",
+                    name,
+                    path,
+                    incompatibilities.join(", and ")
+                );
+            }
+            Ok(())
+        }
+        Some(Err(e)) => Err(zerror!(e => "Plugin {} (from {}) compatibility couldn't be recovered. This likely means it's very broken.", name, path).into()),
+        None => Ok(()),
+    }
+}
+
+/// Starts the plugin, turning its panics into errors instead of unwinding into the host.
+fn start_isolated<StartArgs, RunningPlugin>(
+    plugin: &dyn PluginStarter<StartArgs, RunningPlugin>,
+    args: &StartArgs,
+) -> ZResult<RunningPlugin> {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| plugin.start(args))) {
+        Ok(result) => result,
+        Err(panic) => match panic
+            .downcast_ref::<String>()
+            .map(|s| s.as_str())
+            .or_else(|| panic.downcast_ref::<&str>().copied())
+        {
+            Some(e) => bail!("Plugin {} panicked while starting: {}", plugin.name(), e),
+            None => bail!("Plugin {} panicked while starting", plugin.name()),
+        },
+    }
+}
+
 trait PluginStarter<StartArgs, RunningPlugin> {
     fn name(&self) -> &str;
     fn path(&self) -> &str;
@@ -20,7 +20,7 @@ type LoadPluginResultInner = Result<PluginVTableInner<(), ()>, PluginVTableVersi
 pub type LoadPluginResult<A, B> = Result<PluginVTable<A, B>, PluginVTableVersion>;
 
 /// This number should change any time the internal structure of [`PluginVTable`] changes
-pub const PLUGIN_VTABLE_VERSION: PluginVTableVersion = 1;
+pub const PLUGIN_VTABLE_VERSION: PluginVTableVersion = 2;
 
 type StartFn<StartArgs, RunningPlugin> = fn(&str, &StartArgs) -> ZResult<RunningPlugin>;
 
@@ -0,0 +1,207 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+//! ⚠️ WARNING ⚠️
+//!
+//! This crate is intended for Zenoh's internal use.
+//!
+//! [Click here for Zenoh's documentation](../zenoh/index.html)
+//!
+//! A plugin serving a dashboard of its router, embedded in the plugin: its sessions, routes and
+//! metrics, and an editor of its configuration. The dashboard reads the admin space of the router
+//! with `GET /api/<key_expr>`, and writes its configuration with `PUT /api/config/<path>`.
+use serde_json::json;
+use std::convert::TryFrom;
+use std::sync::Arc;
+use tide::http::Mime;
+use tide::{Request, Response, Server, StatusCode};
+use zenoh::plugins::{Plugin, PluginTasks, RunningPluginTrait, ZenohPlugin};
+use zenoh::prelude::r#async::*;
+use zenoh::runtime::Runtime;
+use zenoh::Session;
+use zenoh_result::{bail, zerror, ZResult};
+
+mod config;
+pub use config::Config;
+
+const GIT_VERSION: &str = git_version::git_version!(prefix = "v", cargo_prefix = "v");
+
+const INDEX_HTML: &str = include_str!("../assets/index.html");
+
+zenoh_plugin_trait::declare_plugin!(WebUiPlugin);
+pub struct WebUiPlugin {}
+
+impl ZenohPlugin for WebUiPlugin {}
+
+impl Plugin for WebUiPlugin {
+    type StartArgs = Runtime;
+    type RunningPlugin = zenoh::plugins::RunningPlugin;
+    const STATIC_NAME: &'static str = "webui";
+
+    fn start(name: &str, runtime: &Self::StartArgs) -> ZResult<zenoh::plugins::RunningPlugin> {
+        // Try to initiate login.
+        // Required in case of dynamic lib, otherwise no logs.
+        // But cannot be done twice in case of static link.
+        let _ = env_logger::try_init();
+        log::debug!("WebUI plugin {}", GIT_VERSION);
+
+        let runtime_conf = runtime.config.lock();
+        let plugin_conf = runtime_conf
+            .plugin(name)
+            .ok_or_else(|| zerror!("Plugin `{}`: missing config", name))?;
+
+        let conf: Config = serde_json::from_value(plugin_conf.clone())
+            .map_err(|e| zerror!("Plugin `{}` configuration error: {}", name, e))?;
+        PluginTasks::new(runtime, name).spawn(run(runtime.clone(), conf.clone()));
+        Ok(Box::new(RunningPlugin(conf)))
+    }
+}
+
+struct RunningPlugin(Config);
+impl RunningPluginTrait for RunningPlugin {
+    fn config_checker(&self) -> zenoh::plugins::ValidationFunction {
+        Arc::new(|_, _, _| {
+            bail!("zenoh-plugin-webui doesn't accept any runtime configuration changes")
+        })
+    }
+
+    fn adminspace_getter<'a>(
+        &'a self,
+        selector: &'a Selector<'a>,
+        plugin_status_key: &str,
+    ) -> ZResult<Vec<zenoh::plugins::Response>> {
+        let mut responses = Vec::new();
+        for (suffix, value) in [
+            ("/version", GIT_VERSION.into()),
+            ("/port", (&self.0).into()),
+        ] {
+            let key = format!("{plugin_status_key}{suffix}");
+            if keyexpr::new(key.as_str())?.intersects(&selector.key_expr) {
+                responses.push(zenoh::plugins::Response::new(key, value));
+            }
+        }
+        Ok(responses)
+    }
+}
+
+fn response(status: StatusCode, content_type: Mime, body: &str) -> Response {
+    Response::builder(status)
+        .header("content-length", body.len().to_string())
+        .content_type(content_type)
+        .body(body)
+        .build()
+}
+
+fn text(status: StatusCode, body: &str) -> Response {
+    response(status, tide::http::mime::PLAIN, body)
+}
+
+// The admin space of the router, `local` standing for its zid
+fn admin_key_expr(path: &str, zid: &str) -> ZResult<KeyExpr<'static>> {
+    let path = path.trim_start_matches('/');
+    if path.is_empty() {
+        KeyExpr::try_from(format!("@/router/{zid}"))
+    } else {
+        KeyExpr::try_from(format!("@/router/{zid}/{path}"))
+    }
+}
+
+async fn index(_req: Request<(Arc<Session>, String)>) -> tide::Result<Response> {
+    Ok(response(StatusCode::Ok, tide::http::mime::HTML, INDEX_HTML))
+}
+
+// Replies a JSON object of the values of the admin space matching the path,
+// the query part of the URL being the parameters of the selector
+async fn get_admin(req: Request<(Arc<Session>, String)>) -> tide::Result<Response> {
+    let url = req.url();
+    let key_expr = match admin_key_expr(url.path().trim_start_matches("/api"), &req.state().1) {
+        Ok(key_expr) => key_expr,
+        Err(e) => return Ok(text(StatusCode::BadRequest, &e.to_string())),
+    };
+    let selector = match url.query() {
+        Some(q) => Selector::from(key_expr).with_parameters(q),
+        None => key_expr.into(),
+    };
+    let replies = match req.state().0.get(&selector).res().await {
+        Ok(replies) => replies,
+        Err(e) => return Ok(text(StatusCode::InternalServerError, &e.to_string())),
+    };
+    let mut values = serde_json::Map::new();
+    while let Ok(reply) = replies.recv_async().await {
+        if let Ok(sample) = reply.sample {
+            let payload = sample.value.to_string();
+            let value = serde_json::from_str(&payload).unwrap_or_else(|_| json!(payload));
+            values.insert(sample.key_expr.to_string(), value);
+        }
+    }
+    Ok(response(
+        StatusCode::Ok,
+        tide::http::mime::JSON,
+        &serde_json::Value::Object(values).to_string(),
+    ))
+}
+
+// Writes the configuration of the router through its admin space
+async fn put_config(mut req: Request<(Arc<Session>, String)>) -> tide::Result<Response> {
+    let body = match req.body_string().await {
+        Ok(body) => body,
+        Err(e) => return Ok(text(StatusCode::BadRequest, &e.to_string())),
+    };
+    let path = req.url().path().trim_start_matches("/api").to_string();
+    let key_expr = match admin_key_expr(&path, &req.state().1) {
+        Ok(key_expr) => key_expr,
+        Err(e) => return Ok(text(StatusCode::BadRequest, &e.to_string())),
+    };
+    match req.state().0.put(&key_expr, body).res().await {
+        Ok(_) => Ok(Response::new(StatusCode::Ok)),
+        Err(e) => Ok(text(StatusCode::InternalServerError, &e.to_string())),
+    }
+}
+
+pub async fn run(runtime: Runtime, conf: Config) {
+    // Try to initiate login.
+    // Required in case of dynamic lib, otherwise no logs.
+    // But cannot be done twice in case of static link.
+    let _ = env_logger::try_init();
+
+    let zid = runtime.zid.to_string();
+    let session = zenoh::init(runtime).res().await.unwrap();
+
+    let mut app = Server::with_state((Arc::new(session), zid));
+    app.at("/").get(index);
+    app.at("/api").get(get_admin);
+    app.at("/api/*").get(get_admin);
+    app.at("/api/config/*").put(put_config);
+
+    if let Err(e) = app.listen(conf.http_port).await {
+        log::error!("Unable to start http server for WebUI: {:?}", e);
+    }
+}
+
+#[test]
+fn admin_key_exprs() {
+    let zid = "a1b2";
+    assert_eq!(admin_key_expr("", zid).unwrap().as_str(), "@/router/a1b2");
+    assert_eq!(
+        admin_key_expr("/linkstate/routers", zid).unwrap().as_str(),
+        "@/router/a1b2/linkstate/routers"
+    );
+    assert_eq!(
+        admin_key_expr("/config/plugins/webui", zid)
+            .unwrap()
+            .as_str(),
+        "@/router/a1b2/config/plugins/webui"
+    );
+    assert!(admin_key_expr("/config//mode", zid).is_err());
+}
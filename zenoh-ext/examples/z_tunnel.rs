@@ -0,0 +1,145 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use clap::{App, Arg, ArgGroup};
+use std::net::SocketAddr;
+use zenoh::config::Config;
+use zenoh::prelude::r#async::*;
+use zenoh_ext::*;
+
+#[async_std::main]
+async fn main() {
+    // Initiate logging
+    env_logger::init();
+
+    let (config, key_expr, exit, allowed, entrance, target) = parse_args();
+
+    println!("Opening session...");
+    let session = zenoh::open(config).res().await.unwrap().into_arc();
+
+    let _exit = match (exit, allowed) {
+        (Some(target), _) => {
+            println!("Declaring TunnelExit on {key_expr} to {target}");
+            Some(TunnelExit::declare(session.clone(), &key_expr, target).unwrap())
+        }
+        (None, Some(allowed)) => {
+            println!("Declaring forwarding TunnelExit on {key_expr} to {allowed:?}");
+            Some(TunnelExit::declare_forwarding(session.clone(), &key_expr, allowed).unwrap())
+        }
+        (None, None) => None,
+    };
+    let _entrance = match entrance {
+        Some(local) => {
+            let entrance = match target {
+                Some(target) => {
+                    TunnelEntrance::bind_forwarding(session.clone(), &key_expr, local, target).await
+                }
+                None => TunnelEntrance::bind(session.clone(), &key_expr, local).await,
+            }
+            .unwrap();
+            println!(
+                "Declaring TunnelEntrance on {key_expr} listening on {}",
+                entrance.local_addr()
+            );
+            Some(entrance)
+        }
+        None => None,
+    };
+
+    futures::future::pending::<()>().await;
+}
+
+#[allow(clippy::type_complexity)]
+fn parse_args() -> (
+    Config,
+    String,
+    Option<SocketAddr>,
+    Option<Vec<String>>,
+    Option<SocketAddr>,
+    Option<String>,
+) {
+    let args = App::new("zenoh-ext tunnel example")
+        .arg(
+            Arg::from_usage("-m, --mode=[MODE] 'The zenoh session mode (peer by default).")
+                .possible_values(["peer", "client"]),
+        )
+        .arg(Arg::from_usage(
+            "-e, --connect=[ENDPOINT]...  'Endpoints to connect to.'",
+        ))
+        .arg(Arg::from_usage(
+            "-l, --listen=[ENDPOINT]...   'Endpoints to listen on.'",
+        ))
+        .arg(
+            Arg::from_usage("-k, --key=[KEYEXPR]        'The key expression of the tunnel.'")
+                .default_value("demo/example/tunnel"),
+        )
+        .arg(Arg::from_usage(
+            "--exit=[ADDRESS]     'The TCP address the tunnels are connected to (e.g. the one of a zenoh router).'",
+        ))
+        .arg(Arg::from_usage(
+            "--entrance=[ADDRESS] 'The local TCP address to listen on for connections to tunnel.'",
+        ))
+        .arg(Arg::from_usage(
+            "--allow=[TARGET]...  'A <host>:<port> the exit forwards the tunnels to when requested by their entrance.'",
+        ).conflicts_with("exit"))
+        .arg(Arg::from_usage(
+            "--target=[TARGET]    'The <host>:<port> the entrance requests its tunnels to be forwarded to.'",
+        ).requires("entrance"))
+        .group(
+            ArgGroup::new("side")
+                .args(&["exit", "allow", "entrance"])
+                .required(true)
+                .multiple(true),
+        )
+        .arg(Arg::from_usage(
+            "-c, --config=[FILE]      'A configuration file.'",
+        ))
+        .arg(Arg::from_usage(
+            "--no-multicast-scouting 'Disable the multicast-based scouting mechanism.'",
+        ))
+        .get_matches();
+
+    let mut config = if let Some(conf_file) = args.value_of("config") {
+        Config::from_file(conf_file).unwrap()
+    } else {
+        Config::default()
+    };
+    if let Some(Ok(mode)) = args.value_of("mode").map(|mode| mode.parse()) {
+        config.set_mode(Some(mode)).unwrap();
+    }
+    if let Some(values) = args.values_of("connect") {
+        config
+            .connect
+            .endpoints
+            .extend(values.map(|v| v.parse().unwrap()))
+    }
+    if let Some(values) = args.values_of("listen") {
+        config
+            .listen
+            .endpoints
+            .extend(values.map(|v| v.parse().unwrap()))
+    }
+    if args.is_present("no-multicast-scouting") {
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+    }
+
+    let key_expr = args.value_of("key").unwrap().to_string();
+    let exit = args.value_of("exit").map(|a| a.parse().unwrap());
+    let allowed = args
+        .values_of("allow")
+        .map(|values| values.map(String::from).collect());
+    let entrance = args.value_of("entrance").map(|a| a.parse().unwrap());
+    let target = args.value_of("target").map(String::from);
+
+    (config, key_expr, exit, allowed, entrance, target)
+}
@@ -0,0 +1,380 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use crate::IdempotencyKey;
+use flume::Receiver;
+use std::convert::TryInto;
+use std::future::Ready;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use zenoh::prelude::r#async::*;
+use zenoh::query::Reply;
+use zenoh::queryable::{Query, Queryable};
+use zenoh::Session;
+use zenoh_core::{AsyncResolve, Resolvable, SyncResolve};
+use zenoh_result::{bail, ZResult};
+
+/// The default time a [`CommandSender`] waits for the acknowledgement of a command.
+pub const COMMAND_DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The outcome of a command sent by a [`CommandSender`].
+#[derive(Debug, Clone)]
+pub enum CommandOutcome {
+    /// The command was accepted, with the value of its acknowledgement.
+    Accepted(Value),
+    /// The command was rejected, with the value of its acknowledgement.
+    Rejected(Value),
+    /// The command wasn't acknowledged in time.
+    TimedOut,
+}
+
+/// The builder of [`CommandSender`].
+pub struct CommandSenderBuilder {
+    session: Arc<Session>,
+    key_expr: ZResult<KeyExpr<'static>>,
+    timeout: Duration,
+}
+
+impl CommandSenderBuilder {
+    pub fn new<'b, TryIntoKeyExpr>(session: Arc<Session>, key_expr: TryIntoKeyExpr) -> Self
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+    {
+        CommandSenderBuilder {
+            session,
+            key_expr: key_expr
+                .try_into()
+                .map_err(Into::into)
+                .map(KeyExpr::into_owned),
+            timeout: COMMAND_DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Change the time to wait for the acknowledgement of a command.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl Resolvable for CommandSenderBuilder {
+    type To = ZResult<CommandSender>;
+}
+
+impl SyncResolve for CommandSenderBuilder {
+    fn res_sync(self) -> <Self as Resolvable>::To {
+        CommandSender::new(self)
+    }
+}
+
+impl AsyncResolve for CommandSenderBuilder {
+    type Future = Ready<Self::To>;
+
+    fn res_async(self) -> Self::Future {
+        std::future::ready(self.res_sync())
+    }
+}
+
+/// Sends commands, each as a query on its key expression with an [`IdempotencyKey`] attached,
+/// and waits for their acknowledgement by a [`CommandReceiver`].
+///
+/// The acknowledgement of a command is the reply to its query: it is routed back to the sender
+/// along the path of the command, without depending on any declaration to propagate.
+///
+/// # Examples
+/// ```no_run
+/// # async_std::task::block_on(async {
+/// use std::time::Duration;
+/// use zenoh::prelude::r#async::*;
+/// use zenoh_ext::{CommandOutcome, CommandSenderBuilder};
+///
+/// let session = zenoh::open(config::peer()).res().await.unwrap().into_arc();
+/// let sender = CommandSenderBuilder::new(session, "robot/1/arm")
+///     .timeout(Duration::from_millis(200))
+///     .res()
+///     .await
+///     .unwrap();
+/// match sender.send("home").await.unwrap() {
+///     CommandOutcome::Accepted(value) => println!("Homing: {}", value),
+///     CommandOutcome::Rejected(value) => println!("Not homing: {}", value),
+///     CommandOutcome::TimedOut => println!("The robot didn't answer"),
+/// }
+/// # })
+/// ```
+pub struct CommandSender {
+    session: Arc<Session>,
+    key_expr: KeyExpr<'static>,
+    timeout: Duration,
+    source: String,
+    sn: AtomicU64,
+}
+
+impl CommandSender {
+    fn new(builder: CommandSenderBuilder) -> ZResult<CommandSender> {
+        let CommandSenderBuilder {
+            session,
+            key_expr,
+            timeout,
+        } = builder;
+        let key_expr = key_expr?;
+        if timeout.is_zero() {
+            bail!("Invalid command sender: the timeout must be strictly positive");
+        }
+        Ok(CommandSender {
+            session,
+            key_expr,
+            timeout,
+            source: ZenohId::rand().to_string(),
+            sn: AtomicU64::new(0),
+        })
+    }
+
+    /// Send a command, returning its outcome once acknowledged or timed out.
+    ///
+    /// Only the first acknowledgement of a command is taken into account.
+    pub async fn send<IntoValue: Into<Value>>(&self, value: IntoValue) -> ZResult<CommandOutcome> {
+        let key = IdempotencyKey {
+            source: self.source.clone(),
+            sn: self.sn.fetch_add(1, Ordering::Relaxed),
+        };
+        let replies = self
+            .session
+            .get(&self.key_expr)
+            .with_value(value)
+            .with_attachment(key.encode()?)
+            .consolidation(ConsolidationMode::None)
+            .timeout(self.timeout)
+            .res_async()
+            .await?;
+        // the replies end when the command times out, or is dropped without being acknowledged
+        Ok(match replies.recv_async().await {
+            Ok(Reply {
+                sample: Ok(sample), ..
+            }) => CommandOutcome::Accepted(sample.value),
+            Ok(Reply {
+                sample: Err(value), ..
+            }) => CommandOutcome::Rejected(value),
+            Err(_) => CommandOutcome::TimedOut,
+        })
+    }
+}
+
+/// A command received by a [`CommandReceiver`], to acknowledge with
+/// [`accept`](Command::accept) or [`reject`](Command::reject).
+///
+/// A command dropped without being acknowledged is reported as timed out to its sender.
+pub struct Command {
+    query: Query,
+    sample: Sample,
+    key: IdempotencyKey,
+}
+
+impl Command {
+    // the command sent by a `CommandSender` as `query`, or the query if it wasn't
+    fn new(query: Query) -> Result<Command, Query> {
+        let value = query.value().cloned().unwrap_or_else(Value::empty);
+        let mut sample = Sample::new(query.key_expr().clone(), value);
+        sample.attachment = query.attachment().cloned();
+        match IdempotencyKey::of(&sample) {
+            Some(key) => Ok(Command { query, sample, key }),
+            None => Err(query),
+        }
+    }
+
+    /// The sample of this command.
+    pub fn sample(&self) -> &Sample {
+        &self.sample
+    }
+
+    /// The idempotency key of this command, e.g. to detect the commands sent again.
+    pub fn key(&self) -> &IdempotencyKey {
+        &self.key
+    }
+
+    /// Acknowledge this command as accepted, with `value`.
+    pub fn accept<IntoValue: Into<Value>>(self, value: IntoValue) -> ZResult<()> {
+        let key_expr = self.sample.key_expr.clone();
+        self.query
+            .reply(Ok(Sample::new(key_expr, value)))
+            .res_sync()
+    }
+
+    /// Acknowledge this command as rejected, with `value`.
+    pub fn reject<IntoValue: Into<Value>>(self, value: IntoValue) -> ZResult<()> {
+        self.query.reply(Err(value.into())).res_sync()
+    }
+}
+
+/// The builder of [`CommandReceiver`].
+pub struct CommandReceiverBuilder {
+    session: Arc<Session>,
+    key_expr: ZResult<KeyExpr<'static>>,
+}
+
+impl CommandReceiverBuilder {
+    pub fn new<'b, TryIntoKeyExpr>(session: Arc<Session>, key_expr: TryIntoKeyExpr) -> Self
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+    {
+        CommandReceiverBuilder {
+            session,
+            key_expr: key_expr
+                .try_into()
+                .map_err(Into::into)
+                .map(KeyExpr::into_owned),
+        }
+    }
+}
+
+impl Resolvable for CommandReceiverBuilder {
+    type To = ZResult<CommandReceiver>;
+}
+
+impl SyncResolve for CommandReceiverBuilder {
+    fn res_sync(self) -> <Self as Resolvable>::To {
+        CommandReceiver::new(self)
+    }
+}
+
+impl AsyncResolve for CommandReceiverBuilder {
+    type Future = Ready<Self::To>;
+
+    fn res_async(self) -> Self::Future {
+        std::future::ready(self.res_sync())
+    }
+}
+
+/// Receives the commands of [`CommandSender`]s, from the [`flume::Receiver`] it dereferences to.
+///
+/// The queries without [`IdempotencyKey`], which weren't sent by a [`CommandSender`], are ignored.
+///
+/// # Examples
+/// ```no_run
+/// # async_std::task::block_on(async {
+/// use zenoh::prelude::r#async::*;
+/// use zenoh_ext::CommandReceiverBuilder;
+///
+/// let session = zenoh::open(config::peer()).res().await.unwrap().into_arc();
+/// let commands = CommandReceiverBuilder::new(session, "robot/1/arm")
+///     .res()
+///     .await
+///     .unwrap();
+/// while let Ok(command) = commands.recv_async().await {
+///     if command.sample().value.to_string() == "home" {
+///         command.accept("homing").unwrap();
+///     } else {
+///         command.reject("unknown command").unwrap();
+///     }
+/// }
+/// # })
+/// ```
+pub struct CommandReceiver {
+    _queryable: Queryable<'static, ()>,
+    receiver: Receiver<Command>,
+}
+
+impl CommandReceiver {
+    fn new(builder: CommandReceiverBuilder) -> ZResult<CommandReceiver> {
+        let CommandReceiverBuilder { session, key_expr } = builder;
+        let key_expr = key_expr?;
+        let (sender, receiver) = flume::unbounded();
+        let queryable = session
+            .declare_queryable(&key_expr)
+            .callback(move |query| match Command::new(query) {
+                Ok(command) => {
+                    let _ = sender.send(command);
+                }
+                Err(query) => {
+                    log::debug!("CommandReceiver: ignoring {} without key", query.selector())
+                }
+            })
+            .res_sync()?;
+        Ok(CommandReceiver {
+            _queryable: queryable,
+            receiver,
+        })
+    }
+}
+
+impl Deref for CommandReceiver {
+    type Target = Receiver<Command>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.receiver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_std::task;
+
+    #[test]
+    fn command_outcomes() {
+        task::block_on(async {
+            let mut config = config::peer();
+            config.scouting.multicast.set_enabled(Some(false)).unwrap();
+            let session = zenoh::open(config).res().await.unwrap().into_arc();
+            let sender = CommandSenderBuilder::new(session.clone(), "test/command")
+                .timeout(Duration::from_millis(500))
+                .res()
+                .await
+                .unwrap();
+            let commands = CommandReceiverBuilder::new(session.clone(), "test/command")
+                .res()
+                .await
+                .unwrap();
+            let receiver = task::spawn(async move {
+                let mut keys = vec![];
+                while let Ok(command) = commands.recv_async().await {
+                    keys.push(command.key().clone());
+                    match command.sample().value.to_string().as_str() {
+                        "home" => command.accept("homing").unwrap(),
+                        "jump" => command.reject("unknown command").unwrap(),
+                        // the command dropped without being acknowledged
+                        _ => break,
+                    }
+                }
+                keys
+            });
+
+            match sender.send("home").await.unwrap() {
+                CommandOutcome::Accepted(value) => assert_eq!(value.to_string(), "homing"),
+                outcome => panic!("Unexpected outcome: {:?}", outcome),
+            }
+            match sender.send("jump").await.unwrap() {
+                CommandOutcome::Rejected(value) => {
+                    assert_eq!(value.to_string(), "unknown command")
+                }
+                outcome => panic!("Unexpected outcome: {:?}", outcome),
+            }
+            assert!(matches!(
+                sender.send("stop").await.unwrap(),
+                CommandOutcome::TimedOut
+            ));
+            // no receiver is left to acknowledge the commands
+            assert!(matches!(
+                sender.send("home").await.unwrap(),
+                CommandOutcome::TimedOut
+            ));
+
+            let keys = receiver.await;
+            assert_eq!(keys.iter().map(|k| k.sn).collect::<Vec<_>>(), vec![0, 1, 2]);
+            assert!(keys.iter().all(|k| k.source == keys[0].source));
+        });
+    }
+}
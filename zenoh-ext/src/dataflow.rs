@@ -0,0 +1,404 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use async_std::task::{self, JoinHandle};
+use flume::{Receiver, Sender, TrySendError};
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::future::Ready;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use zenoh::prelude::r#async::*;
+use zenoh::subscriber::Subscriber;
+use zenoh::Session;
+use zenoh_core::{AsyncResolve, Resolvable, SyncResolve};
+use zenoh_result::{bail, ZResult};
+
+/// The default capacity of the channels between the stages of a [`Dataflow`].
+pub const DATAFLOW_DEFAULT_CAPACITY: usize = 32;
+
+type OperatorFn = Arc<dyn Fn(Sample) -> Option<Sample> + Send + Sync>;
+
+/// What the sources of a [`Dataflow`] do with a sample when the channel to a downstream stage is full.
+///
+/// The sources are fed by the subscribers callbacks, which run on the threads receiving the
+/// samples from the network: they never wait for the downstream stages to catch up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DataflowOverflow {
+    /// Drop the sample received.
+    #[default]
+    DropNewest,
+    /// Drop the oldest sample waiting in the channel to make room for the sample received.
+    DropOldest,
+}
+
+enum Node {
+    Source(ZResult<OwnedKeyExpr>),
+    Operator(String, OperatorFn),
+    Sink(String, ZResult<OwnedKeyExpr>),
+}
+
+impl Node {
+    fn input(&self) -> Option<&str> {
+        match self {
+            Node::Source(_) => None,
+            Node::Operator(input, _) | Node::Sink(input, _) => Some(input),
+        }
+    }
+}
+
+/// The builder of [`Dataflow`], allowing to declare its stages.
+///
+/// Each stage is named, and operators and sinks take their input from a previously declared stage,
+/// so that the resulting graph is acyclic. A stage can feed several downstream stages.
+pub struct DataflowBuilder {
+    session: Arc<Session>,
+    capacity: usize,
+    overflow: DataflowOverflow,
+    nodes: Vec<(String, Node)>,
+}
+
+impl DataflowBuilder {
+    pub fn new(session: Arc<Session>) -> Self {
+        DataflowBuilder {
+            session,
+            capacity: DATAFLOW_DEFAULT_CAPACITY,
+            overflow: DataflowOverflow::default(),
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Change the capacity of the channels between the stages.
+    ///
+    /// When the channel of an operator or a sink is full, the upstream operators wait for it to
+    /// catch up, while the upstream sources apply the [`overflow`](Self::overflow) policy.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Change what the sources do with a sample when the channel to a downstream stage is full.
+    pub fn overflow(mut self, overflow: DataflowOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Add a source stage receiving the publications on `key_expr`.
+    pub fn source<'b, TryIntoKeyExpr>(mut self, name: &str, key_expr: TryIntoKeyExpr) -> Self
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+    {
+        let key_expr = key_expr.try_into().map_err(Into::into).map(Into::into);
+        self.nodes.push((name.into(), Node::Source(key_expr)));
+        self
+    }
+
+    /// Add an operator stage transforming the samples of the `input` stage.
+    ///
+    /// The samples for which `operator` returns `None` are not forwarded downstream.
+    pub fn operator<F>(mut self, name: &str, input: &str, operator: F) -> Self
+    where
+        F: Fn(Sample) -> Option<Sample> + Send + Sync + 'static,
+    {
+        self.nodes.push((
+            name.into(),
+            Node::Operator(input.into(), Arc::new(operator)),
+        ));
+        self
+    }
+
+    /// Add a sink stage publishing the values of the samples of the `input` stage on `key_expr`.
+    pub fn sink<'b, TryIntoKeyExpr>(
+        mut self,
+        name: &str,
+        input: &str,
+        key_expr: TryIntoKeyExpr,
+    ) -> Self
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+    {
+        let key_expr = key_expr.try_into().map_err(Into::into).map(Into::into);
+        self.nodes
+            .push((name.into(), Node::Sink(input.into(), key_expr)));
+        self
+    }
+
+    fn check(&self) -> ZResult<()> {
+        if self.capacity == 0 {
+            bail!("Invalid dataflow: the channels capacity must be strictly positive");
+        }
+        for (i, (name, node)) in self.nodes.iter().enumerate() {
+            let previous = &self.nodes[..i];
+            if previous.iter().any(|(n, _)| n == name) {
+                bail!("Invalid dataflow: duplicated stage '{}'", name);
+            }
+            if let Some(input) = node.input() {
+                match previous.iter().find(|(n, _)| n == input) {
+                    Some((_, Node::Sink(_, _))) => {
+                        bail!(
+                            "Invalid dataflow: stage '{}' takes its input from sink '{}'",
+                            name,
+                            input
+                        )
+                    }
+                    Some(_) => (),
+                    None => bail!(
+                        "Invalid dataflow: stage '{}' takes its input from unknown stage '{}'",
+                        name,
+                        input
+                    ),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Resolvable for DataflowBuilder {
+    type To = ZResult<Dataflow>;
+}
+
+impl SyncResolve for DataflowBuilder {
+    fn res_sync(self) -> <Self as Resolvable>::To {
+        Dataflow::new(self)
+    }
+}
+
+impl AsyncResolve for DataflowBuilder {
+    type Future = Ready<Self::To>;
+
+    fn res_async(self) -> Self::Future {
+        std::future::ready(self.res_sync())
+    }
+}
+
+/// A minimal in-process dataflow: sources subscribing to key expressions,
+/// operators transforming samples and sinks publishing them,
+/// each stage running as a task connected to the next ones by bounded channels.
+///
+/// The dataflow stops when dropped.
+///
+/// # Examples
+/// ```no_run
+/// # async_std::task::block_on(async {
+/// use zenoh::prelude::r#async::*;
+/// use zenoh_ext::DataflowBuilder;
+///
+/// let session = zenoh::open(config::peer()).res().await.unwrap().into_arc();
+/// let dataflow = DataflowBuilder::new(session)
+///     .source("readings", "sensors/*/temperature")
+///     .operator("hot", "readings", |sample| {
+///         let celsius: f64 = sample.value.to_string().parse().ok()?;
+///         (celsius > 30.0).then_some(sample)
+///     })
+///     .sink("alerts", "hot", "alerts/temperature")
+///     .res()
+///     .await
+///     .unwrap();
+/// # })
+/// ```
+pub struct Dataflow {
+    sources: Vec<Subscriber<'static, ()>>,
+    tasks: Vec<JoinHandle<()>>,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl Dataflow {
+    fn new(builder: DataflowBuilder) -> ZResult<Dataflow> {
+        builder.check()?;
+        let DataflowBuilder {
+            session,
+            capacity,
+            overflow,
+            nodes,
+        } = builder;
+
+        // create the input channel of every operator and sink,
+        // the sources keeping its receiver to drop the oldest samples
+        let mut outputs: HashMap<String, Vec<(Sender<Sample>, Receiver<Sample>)>> = HashMap::new();
+        let mut inputs: HashMap<String, Receiver<Sample>> = HashMap::new();
+        for (name, node) in &nodes {
+            if let Some(input) = node.input() {
+                let (tx, rx) = flume::bounded(capacity);
+                outputs
+                    .entry(input.into())
+                    .or_default()
+                    .push((tx, rx.clone()));
+                inputs.insert(name.clone(), rx);
+            }
+        }
+
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let mut sources = Vec::new();
+        let mut tasks = Vec::new();
+        for (name, node) in nodes {
+            let downstream = outputs.remove(&name).unwrap_or_default();
+            match node {
+                Node::Source(key_expr) => {
+                    let key_expr = key_expr?;
+                    if downstream.is_empty() {
+                        log::warn!("Dataflow source '{}' on {} feeds no stage", name, key_expr);
+                    }
+                    let dropped = dropped.clone();
+                    let subscriber = session
+                        .declare_subscriber(key_expr)
+                        .callback(move |sample| {
+                            for (tx, rx) in &downstream {
+                                let sample = match tx.try_send(sample.clone()) {
+                                    Err(TrySendError::Full(sample)) => sample,
+                                    _ => continue,
+                                };
+                                let mut count = 1;
+                                if overflow == DataflowOverflow::DropOldest {
+                                    // the oldest sample may have just been consumed
+                                    if rx.try_recv().is_err() {
+                                        count -= 1;
+                                    }
+                                    if tx.try_send(sample).is_err() {
+                                        count += 1;
+                                    }
+                                }
+                                dropped.fetch_add(count, Ordering::Relaxed);
+                            }
+                        })
+                        .res_sync()?;
+                    sources.push(subscriber);
+                }
+                Node::Operator(_, operator) => {
+                    let rx = inputs.remove(&name).unwrap();
+                    tasks.push(task::spawn(async move {
+                        while let Ok(sample) = rx.recv_async().await {
+                            if let Some(sample) = operator(sample) {
+                                for (tx, _) in &downstream {
+                                    let _ = tx.send_async(sample.clone()).await;
+                                }
+                            }
+                        }
+                        log::debug!("Dataflow operator '{}' stopped", name);
+                    }));
+                }
+                Node::Sink(_, key_expr) => {
+                    let rx = inputs.remove(&name).unwrap();
+                    let publisher = session.declare_publisher(key_expr?).res_sync()?;
+                    tasks.push(task::spawn(async move {
+                        while let Ok(sample) = rx.recv_async().await {
+                            if let Err(e) = publisher.put(sample.value).res_async().await {
+                                log::warn!("Dataflow sink '{}' failed to publish: {}", name, e);
+                            }
+                        }
+                        log::debug!("Dataflow sink '{}' stopped", name);
+                    }));
+                }
+            }
+        }
+
+        Ok(Dataflow {
+            sources,
+            tasks,
+            dropped,
+        })
+    }
+
+    /// The number of samples the sources dropped because of a full downstream channel.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Stops the sources and waits for the samples in flight to go through the other stages.
+    pub async fn stop(mut self) {
+        self.sources.clear();
+        for task in self.tasks.drain(..) {
+            task.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+    const COUNT: usize = 10;
+
+    // Publish COUNT samples while the operator of the dataflow is blocked,
+    // returning the samples the operator eventually received and the number of dropped ones
+    async fn run(overflow: DataflowOverflow) -> (Vec<String>, usize) {
+        let mut config = config::peer();
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        let session = zenoh::open(config).res().await.unwrap().into_arc();
+
+        let (gate_tx, gate_rx) = flume::bounded::<()>(0);
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let dataflow = DataflowBuilder::new(session.clone())
+            .capacity(1)
+            .overflow(overflow)
+            .source("source", "test/dataflow/backpressure")
+            .operator("operator", "source", {
+                let received = received.clone();
+                move |sample| {
+                    // blocked until the gate is closed
+                    let _ = gate_rx.recv();
+                    received.lock().unwrap().push(sample.value.to_string());
+                    None
+                }
+            })
+            .res()
+            .await
+            .unwrap();
+
+        // the publications must not wait for the blocked operator
+        async_std::future::timeout(TIMEOUT, async {
+            for i in 0..COUNT {
+                session
+                    .put("test/dataflow/backpressure", i.to_string())
+                    .res()
+                    .await
+                    .unwrap();
+            }
+        })
+        .await
+        .unwrap();
+
+        drop(gate_tx);
+        let dropped = dataflow.dropped();
+        async_std::future::timeout(TIMEOUT, dataflow.stop())
+            .await
+            .unwrap();
+        let received = received.lock().unwrap().clone();
+        (received, dropped)
+    }
+
+    #[test]
+    fn dataflow_drop_newest() {
+        task::block_on(async {
+            let (received, dropped) = run(DataflowOverflow::DropNewest).await;
+            assert!(dropped > 0);
+            assert_eq!(received.len() + dropped, COUNT);
+            assert_ne!(received.last().unwrap(), &(COUNT - 1).to_string());
+        });
+    }
+
+    #[test]
+    fn dataflow_drop_oldest() {
+        task::block_on(async {
+            let (received, dropped) = run(DataflowOverflow::DropOldest).await;
+            assert!(dropped > 0);
+            assert_eq!(received.len() + dropped, COUNT);
+            assert_eq!(received.last().unwrap(), &(COUNT - 1).to_string());
+        });
+    }
+}
@@ -0,0 +1,224 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::sync::Arc;
+use zenoh::buffers::ZBuf;
+use zenoh::handlers::{Callback, IntoCallbackReceiverPair};
+use zenoh::prelude::{keyexpr, OwnedKeyExpr, Sample, SplitBuffer, Value};
+use zenoh::publication::{Publication, Publisher};
+use zenoh::Result as ZResult;
+use zenoh_crypto::AeadCipher;
+use zenoh_result::zerror;
+
+/// The size in bytes of the symmetric keys used for end-to-end payload encryption.
+pub const PAYLOAD_KEY_SIZE: usize = AeadCipher::KEY_SIZE;
+
+/// Provides the symmetric key protecting the payloads published on a given key expression.
+///
+/// Keys are expected to be distributed out of band: zenoh routers only ever see ciphertext.
+pub trait KeyProvider: Send + Sync {
+    /// Returns the key for `key_expr`, or `None` if payloads on `key_expr` are not encrypted.
+    fn key(&self, key_expr: &keyexpr) -> Option<[u8; PAYLOAD_KEY_SIZE]>;
+}
+
+/// A [`KeyProvider`] associating keys to key expressions.
+///
+/// The first registered key expression including the published key expression is used.
+#[derive(Default)]
+pub struct StaticKeyProvider {
+    keys: Vec<(OwnedKeyExpr, [u8; PAYLOAD_KEY_SIZE])>,
+}
+
+impl StaticKeyProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `key` for all the key expressions included in `key_expr`.
+    pub fn with_key(mut self, key_expr: OwnedKeyExpr, key: [u8; PAYLOAD_KEY_SIZE]) -> Self {
+        self.keys.push((key_expr, key));
+        self
+    }
+}
+
+impl KeyProvider for StaticKeyProvider {
+    fn key(&self, key_expr: &keyexpr) -> Option<[u8; PAYLOAD_KEY_SIZE]> {
+        self.keys
+            .iter()
+            .find(|(ke, _)| ke.includes(key_expr))
+            .map(|(_, key)| *key)
+    }
+}
+
+/// Encrypts and decrypts payloads end-to-end with per key expression keys (AES-256-GCM).
+///
+/// The key expression of a payload is authenticated along with it, so that a payload
+/// can't be replayed on another key expression sharing its key.
+///
+/// # Examples
+/// ```no_run
+/// # async_std::task::block_on(async {
+/// use std::sync::Arc;
+/// use zenoh::prelude::r#async::*;
+/// use zenoh_ext::{PayloadCipher, StaticKeyProvider};
+///
+/// let provider = StaticKeyProvider::new().with_key("demo/**".try_into().unwrap(), [42; 32]);
+/// let cipher = PayloadCipher::new(Arc::new(provider));
+///
+/// let session = zenoh::open(config::peer()).res().await.unwrap();
+/// let subscriber = session
+///     .declare_subscriber("demo/**")
+///     .with(cipher.decrypting(flume::bounded(32)))
+///     .res()
+///     .await
+///     .unwrap();
+/// let publisher = session.declare_publisher("demo/example").res().await.unwrap();
+/// cipher.put(&publisher, "value").unwrap().res().await.unwrap();
+/// # })
+/// ```
+#[derive(Clone)]
+pub struct PayloadCipher {
+    provider: Arc<dyn KeyProvider>,
+}
+
+impl PayloadCipher {
+    pub fn new(provider: Arc<dyn KeyProvider>) -> Self {
+        PayloadCipher { provider }
+    }
+
+    /// Encrypts `value` for `key_expr`. The value is returned unchanged if no key is provided for `key_expr`.
+    pub fn encrypt(&self, key_expr: &keyexpr, value: Value) -> ZResult<Value> {
+        match self.provider.key(key_expr) {
+            Some(key) => {
+                let encrypted = AeadCipher::new(key)
+                    .encrypt_with_aad(&value.payload.contiguous(), key_expr.as_bytes())?;
+                Ok(Value::new(ZBuf::from(encrypted)).encoding(value.encoding))
+            }
+            None => Ok(value),
+        }
+    }
+
+    /// Decrypts the payload of `sample`. The sample is returned unchanged if no key is provided for its key expression.
+    pub fn decrypt(&self, mut sample: Sample) -> ZResult<Sample> {
+        if let Some(key) = self.provider.key(&sample.key_expr) {
+            let decrypted = AeadCipher::new(key)
+                .decrypt_with_aad(
+                    &sample.value.payload.contiguous(),
+                    sample.key_expr.as_bytes(),
+                )
+                .map_err(|e| zerror!("Unable to decrypt sample on {}: {}", sample.key_expr, e))?;
+            sample.value = Value::new(ZBuf::from(decrypted)).encoding(sample.value.encoding);
+        }
+        Ok(sample)
+    }
+
+    /// Encrypts `value` and publishes it with `publisher`.
+    pub fn put<'a, IntoValue>(
+        &self,
+        publisher: &'a Publisher<'_>,
+        value: IntoValue,
+    ) -> ZResult<Publication<'a>>
+    where
+        IntoValue: Into<Value>,
+    {
+        let value = self.encrypt(publisher.key_expr(), value.into())?;
+        Ok(publisher.put(value))
+    }
+
+    /// Wraps a handler so that it receives decrypted samples.
+    ///
+    /// Samples failing decryption (e.g. tampered or encrypted with another key) are dropped.
+    pub fn decrypting<Handler>(&self, handler: Handler) -> Decrypting<Handler> {
+        Decrypting {
+            cipher: self.clone(),
+            handler,
+        }
+    }
+}
+
+/// A handler decrypting the samples before passing them to the wrapped handler.
+///
+/// See [`PayloadCipher::decrypting`].
+pub struct Decrypting<Handler> {
+    cipher: PayloadCipher,
+    handler: Handler,
+}
+
+impl<'a, Handler> IntoCallbackReceiverPair<'a, Sample> for Decrypting<Handler>
+where
+    Handler: IntoCallbackReceiverPair<'a, Sample>,
+{
+    type Receiver = Handler::Receiver;
+
+    fn into_cb_receiver_pair(self) -> (Callback<'a, Sample>, Self::Receiver) {
+        let (callback, receiver) = self.handler.into_cb_receiver_pair();
+        let cipher = self.cipher;
+        (
+            Arc::new(move |sample: Sample| match cipher.decrypt(sample) {
+                Ok(sample) => callback(sample),
+                Err(e) => log::warn!("{}", e),
+            }),
+            receiver,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+
+    fn ke(s: &'static str) -> &'static keyexpr {
+        keyexpr::new(s).unwrap()
+    }
+
+    fn cipher() -> PayloadCipher {
+        let provider = StaticKeyProvider::new().with_key("demo/**".try_into().unwrap(), [42; 32]);
+        PayloadCipher::new(Arc::new(provider))
+    }
+
+    fn encrypted(cipher: &PayloadCipher, key_expr: &'static str, value: &str) -> Value {
+        cipher.encrypt(ke(key_expr), value.into()).unwrap()
+    }
+
+    #[test]
+    fn encryption_round_trip() {
+        let cipher = cipher();
+        let value = encrypted(&cipher, "demo/a", "value");
+        assert_ne!(&value.payload.contiguous()[..], b"value");
+        let sample = cipher.decrypt(Sample::new(ke("demo/a"), value)).unwrap();
+        assert_eq!(&sample.value.payload.contiguous()[..], b"value");
+
+        // the payloads without key are left unchanged
+        let value = encrypted(&cipher, "other/a", "value");
+        assert_eq!(&value.payload.contiguous()[..], b"value");
+        let sample = cipher.decrypt(Sample::new(ke("other/a"), value)).unwrap();
+        assert_eq!(&sample.value.payload.contiguous()[..], b"value");
+    }
+
+    #[test]
+    fn encryption_tampering() {
+        let cipher = cipher();
+        let value = encrypted(&cipher, "demo/a", "value");
+        let mut bytes = value.payload.contiguous().into_owned();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        assert!(cipher
+            .decrypt(Sample::new(ke("demo/a"), Value::new(ZBuf::from(bytes))))
+            .is_err());
+
+        // a payload replayed on another key expression using the same key is rejected
+        let value = encrypted(&cipher, "demo/a", "value");
+        assert!(cipher.decrypt(Sample::new(ke("demo/b"), value)).is_err());
+    }
+}
@@ -0,0 +1,524 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use async_std::task;
+use flume::{Receiver, Sender};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::convert::TryInto;
+use std::fs::File;
+use std::future::Ready;
+use std::io::Write;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use zenoh::prelude::r#async::*;
+use zenoh::publication::Publisher;
+use zenoh::subscriber::Subscriber;
+use zenoh::Session;
+use zenoh_core::{AsyncResolve, Resolvable, SyncResolve};
+use zenoh_result::{bail, zerror, ZResult};
+
+/// The default number of sequence numbers per source an [`ExactlyOnceSubscriber`] remembers.
+pub const EXACTLY_ONCE_DEFAULT_WINDOW: u64 = 1024;
+
+/// The key identifying a sample published by an [`ExactlyOncePublisher`],
+/// carried in the [`attachment`](zenoh::sample::Sample::attachment) of the sample.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IdempotencyKey {
+    /// The identifier of the publisher.
+    pub source: String,
+    /// The sequence number of the sample from its publisher.
+    pub sn: u64,
+}
+
+impl IdempotencyKey {
+    pub(crate) fn encode(&self) -> ZResult<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| zerror!("Invalid idempotency key: {}", e).into())
+    }
+
+    /// The idempotency key of `sample`, if it was published by an [`ExactlyOncePublisher`].
+    pub fn of(sample: &Sample) -> Option<IdempotencyKey> {
+        let attachment = sample.attachment.as_ref()?;
+        bincode::deserialize(&attachment.contiguous()).ok()
+    }
+}
+
+/// The builder of [`ExactlyOncePublisher`].
+pub struct ExactlyOncePublisherBuilder {
+    session: Arc<Session>,
+    key_expr: ZResult<KeyExpr<'static>>,
+    source: Option<String>,
+    first_sn: u64,
+}
+
+impl ExactlyOncePublisherBuilder {
+    pub fn new<'b, TryIntoKeyExpr>(session: Arc<Session>, key_expr: TryIntoKeyExpr) -> Self
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+    {
+        ExactlyOncePublisherBuilder {
+            session,
+            key_expr: key_expr
+                .try_into()
+                .map_err(Into::into)
+                .map(KeyExpr::into_owned),
+            source: None,
+            first_sn: 0,
+        }
+    }
+
+    /// Change the identifier of the publisher, random by default.
+    ///
+    /// A stable identifier lets a restarted publisher resume its sequence numbers with
+    /// [`first_sn`](ExactlyOncePublisherBuilder::first_sn): the subscribers would drop
+    /// the samples whose sequence numbers were already used.
+    pub fn source<IntoString: Into<String>>(mut self, source: IntoString) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Change the sequence number of the first published sample.
+    pub fn first_sn(mut self, first_sn: u64) -> Self {
+        self.first_sn = first_sn;
+        self
+    }
+}
+
+impl Resolvable for ExactlyOncePublisherBuilder {
+    type To = ZResult<ExactlyOncePublisher>;
+}
+
+impl SyncResolve for ExactlyOncePublisherBuilder {
+    fn res_sync(self) -> <Self as Resolvable>::To {
+        let ExactlyOncePublisherBuilder {
+            session,
+            key_expr,
+            source,
+            first_sn,
+        } = self;
+        let publisher = session
+            .declare_publisher(key_expr?)
+            .congestion_control(CongestionControl::Block)
+            .res_sync()?;
+        Ok(ExactlyOncePublisher {
+            publisher,
+            source: source.unwrap_or_else(|| ZenohId::rand().to_string()),
+            sn: AtomicU64::new(first_sn),
+        })
+    }
+}
+
+impl AsyncResolve for ExactlyOncePublisherBuilder {
+    type Future = Ready<Self::To>;
+
+    fn res_async(self) -> Self::Future {
+        std::future::ready(self.res_sync())
+    }
+}
+
+/// Publishes samples with an [`IdempotencyKey`] attached, so that an [`ExactlyOnceSubscriber`]
+/// delivers each of them once, even if it is published again.
+///
+/// A sample whose delivery is uncertain, e.g. when the connectivity was lost while publishing it,
+/// can be safely published again with [`republish`](ExactlyOncePublisher::republish).
+///
+/// # Examples
+/// ```no_run
+/// # async_std::task::block_on(async {
+/// use zenoh::prelude::r#async::*;
+/// use zenoh_ext::ExactlyOncePublisherBuilder;
+///
+/// let session = zenoh::open(config::peer()).res().await.unwrap().into_arc();
+/// let publisher = ExactlyOncePublisherBuilder::new(session, "robot/1/command")
+///     .res()
+///     .await
+///     .unwrap();
+/// let key = publisher.put("stop").unwrap();
+/// // the connectivity was lost: publish it again, it will be delivered once
+/// publisher.republish("stop", &key).unwrap();
+/// # })
+/// ```
+pub struct ExactlyOncePublisher {
+    publisher: Publisher<'static>,
+    source: String,
+    sn: AtomicU64,
+}
+
+impl ExactlyOncePublisher {
+    /// The identifier of this publisher.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Publish `value` with a new idempotency key, which is returned.
+    pub fn put<IntoValue: Into<Value>>(&self, value: IntoValue) -> ZResult<IdempotencyKey> {
+        let key = IdempotencyKey {
+            source: self.source.clone(),
+            sn: self.sn.fetch_add(1, Ordering::Relaxed),
+        };
+        self.republish(value, &key)?;
+        Ok(key)
+    }
+
+    /// Publish `value` again with the idempotency `key` it was first published with.
+    pub fn republish<IntoValue: Into<Value>>(
+        &self,
+        value: IntoValue,
+        key: &IdempotencyKey,
+    ) -> ZResult<()> {
+        self.publisher
+            .put(value)
+            .with_attachment(key.encode()?)
+            .res_sync()
+    }
+}
+
+// The sequence numbers received from a source, in a window ending at the highest one
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Window {
+    last: u64,
+    received: BTreeSet<u64>,
+}
+
+impl Window {
+    // Whether `sn` is received for the first time, recording it
+    fn insert(&mut self, sn: u64, size: u64) -> bool {
+        if sn.saturating_add(size) <= self.last || !self.received.insert(sn) {
+            return false;
+        }
+        if sn > self.last {
+            self.last = sn;
+            let first = self.last.saturating_sub(size - 1);
+            self.received = self.received.split_off(&first);
+        }
+        true
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Deduplicator {
+    sources: HashMap<String, Window>,
+}
+
+impl Deduplicator {
+    fn load(path: &Path) -> ZResult<Deduplicator> {
+        if !path.exists() {
+            return Ok(Deduplicator::default());
+        }
+        let bytes = std::fs::read(path).map_err(|e| zerror!("{}: {}", path.display(), e))?;
+        bincode::deserialize(&bytes)
+            .map_err(|e| zerror!("{}: invalid exactly-once state: {}", path.display(), e).into())
+    }
+
+    // Written and synced to a temporary file, then renamed so that a crash never leaves a partial state
+    fn store(bytes: &[u8], path: &Path) -> ZResult<()> {
+        let tmp = path.with_extension("tmp");
+        let mut file = File::create(&tmp).map_err(|e| zerror!("{}: {}", tmp.display(), e))?;
+        file.write_all(bytes)
+            .and_then(|()| file.sync_all())
+            .map_err(|e| zerror!("{}: {}", tmp.display(), e))?;
+        std::fs::rename(&tmp, path).map_err(|e| zerror!("{}: {}", path.display(), e))?;
+        // the rename itself is durable once the directory is synced
+        #[cfg(unix)]
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            File::open(dir)
+                .and_then(|dir| dir.sync_all())
+                .map_err(|e| zerror!("{}: {}", dir.display(), e))?;
+        }
+        Ok(())
+    }
+}
+
+// The state of a persistent subscriber
+struct Persisted {
+    deduplicator: Deduplicator,
+    // the samples whose idempotency keys are recorded but not yet on disk
+    pending: Vec<Sample>,
+}
+
+// Writes the recorded idempotency keys to disk each time it's woken up, then delivers the samples
+// they were recorded for. The samples recorded while writing are written in the next batch.
+async fn persist(
+    state: Arc<Mutex<Persisted>>,
+    path: PathBuf,
+    wakeups: Receiver<()>,
+    sender: Sender<Sample>,
+) {
+    while wakeups.recv_async().await.is_ok() {
+        let (bytes, samples) = {
+            let mut state = state.lock().unwrap();
+            (
+                bincode::serialize(&state.deduplicator),
+                std::mem::take(&mut state.pending),
+            )
+        };
+        let path = path.clone();
+        let stored = match bytes {
+            Ok(bytes) => task::spawn_blocking(move || Deduplicator::store(&bytes, &path)).await,
+            Err(e) => Err(zerror!("{}", e).into()),
+        };
+        if let Err(e) = stored {
+            log::warn!("ExactlyOnceSubscriber: {}", e);
+        }
+        for sample in samples {
+            let _ = sender.send(sample);
+        }
+    }
+}
+
+/// The builder of [`ExactlyOnceSubscriber`].
+pub struct ExactlyOnceSubscriberBuilder {
+    session: Arc<Session>,
+    key_expr: ZResult<KeyExpr<'static>>,
+    window: u64,
+    path: Option<PathBuf>,
+}
+
+impl ExactlyOnceSubscriberBuilder {
+    pub fn new<'b, TryIntoKeyExpr>(session: Arc<Session>, key_expr: TryIntoKeyExpr) -> Self
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+    {
+        ExactlyOnceSubscriberBuilder {
+            session,
+            key_expr: key_expr
+                .try_into()
+                .map_err(Into::into)
+                .map(KeyExpr::into_owned),
+            window: EXACTLY_ONCE_DEFAULT_WINDOW,
+            path: None,
+        }
+    }
+
+    /// Change the number of sequence numbers remembered per source.
+    ///
+    /// The samples older than the window are dropped, as they can't be told apart from duplicates.
+    pub fn window(mut self, window: u64) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Persist the received idempotency keys in the file at `path`, so that the samples received
+    /// before a restart of the subscriber aren't delivered again.
+    pub fn persistence<IntoPathBuf: Into<PathBuf>>(mut self, path: IntoPathBuf) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+}
+
+impl Resolvable for ExactlyOnceSubscriberBuilder {
+    type To = ZResult<ExactlyOnceSubscriber>;
+}
+
+impl SyncResolve for ExactlyOnceSubscriberBuilder {
+    fn res_sync(self) -> <Self as Resolvable>::To {
+        ExactlyOnceSubscriber::new(self)
+    }
+}
+
+impl AsyncResolve for ExactlyOnceSubscriberBuilder {
+    type Future = Ready<Self::To>;
+
+    fn res_async(self) -> Self::Future {
+        std::future::ready(self.res_sync())
+    }
+}
+
+/// Receives the samples of [`ExactlyOncePublisher`]s, dropping the ones already received.
+///
+/// The idempotency keys are recorded when the samples are delivered to the [`flume::Receiver`]
+/// it dereferences to. If [persistent](ExactlyOnceSubscriberBuilder::persistence), they are
+/// written to disk in batches by a background task, and the samples are only delivered once
+/// their keys are on disk. The samples without idempotency key are delivered as is.
+///
+/// # Examples
+/// ```no_run
+/// # async_std::task::block_on(async {
+/// use zenoh::prelude::r#async::*;
+/// use zenoh_ext::ExactlyOnceSubscriberBuilder;
+///
+/// let session = zenoh::open(config::peer()).res().await.unwrap().into_arc();
+/// let subscriber = ExactlyOnceSubscriberBuilder::new(session, "robot/1/command")
+///     .persistence("/var/lib/robot/commands.state")
+///     .res()
+///     .await
+///     .unwrap();
+/// while let Ok(command) = subscriber.recv_async().await {
+///     println!("Executing {}", command.value);
+/// }
+/// # })
+/// ```
+pub struct ExactlyOnceSubscriber {
+    _subscriber: Subscriber<'static, ()>,
+    receiver: Receiver<Sample>,
+    duplicates: Arc<AtomicU64>,
+}
+
+impl ExactlyOnceSubscriber {
+    fn new(builder: ExactlyOnceSubscriberBuilder) -> ZResult<ExactlyOnceSubscriber> {
+        let ExactlyOnceSubscriberBuilder {
+            session,
+            key_expr,
+            window,
+            path,
+        } = builder;
+        let key_expr = key_expr?;
+        if window == 0 {
+            bail!("Invalid exactly-once subscriber: the window must be strictly positive");
+        }
+        let (sender, receiver) = flume::unbounded();
+        let duplicates = Arc::new(AtomicU64::new(0));
+        let subscriber = session.declare_subscriber(&key_expr).reliable();
+        let subscriber = match path {
+            Some(path) => {
+                let state = Arc::new(Mutex::new(Persisted {
+                    deduplicator: Deduplicator::load(&path)?,
+                    pending: Vec::new(),
+                }));
+                // a pending wake-up makes the persisting task write all the recorded keys
+                let (wakeup, wakeups) = flume::bounded(1);
+                task::spawn(persist(state.clone(), path, wakeups, sender));
+                let duplicates = duplicates.clone();
+                subscriber
+                    .callback(move |sample| {
+                        let mut state = state.lock().unwrap();
+                        if let Some(key) = IdempotencyKey::of(&sample) {
+                            let received =
+                                state.deduplicator.sources.entry(key.source).or_default();
+                            if !received.insert(key.sn, window) {
+                                duplicates.fetch_add(1, Ordering::Relaxed);
+                                return;
+                            }
+                        }
+                        // delivered in recording order, once the keys recorded before are on disk
+                        state.pending.push(sample);
+                        let _ = wakeup.try_send(());
+                    })
+                    .res_sync()?
+            }
+            None => {
+                let deduplicator = Mutex::new(Deduplicator::default());
+                let duplicates = duplicates.clone();
+                subscriber
+                    .callback(move |sample| {
+                        // held while delivering, for the samples to be delivered in recording order
+                        let mut deduplicator = deduplicator.lock().unwrap();
+                        if let Some(key) = IdempotencyKey::of(&sample) {
+                            let received = deduplicator.sources.entry(key.source).or_default();
+                            if !received.insert(key.sn, window) {
+                                duplicates.fetch_add(1, Ordering::Relaxed);
+                                return;
+                            }
+                        }
+                        let _ = sender.send(sample);
+                    })
+                    .res_sync()?
+            }
+        };
+        Ok(ExactlyOnceSubscriber {
+            _subscriber: subscriber,
+            receiver,
+            duplicates,
+        })
+    }
+
+    /// The number of samples dropped as already received.
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates.load(Ordering::Relaxed)
+    }
+}
+
+impl Deref for ExactlyOnceSubscriber {
+    type Target = Receiver<Sample>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.receiver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn received(window: &Window) -> Vec<u64> {
+        window.received.iter().copied().collect()
+    }
+
+    #[test]
+    fn window_duplicates() {
+        let mut window = Window::default();
+        assert!(window.insert(0, 4));
+        assert!(window.insert(1, 4));
+        assert!(!window.insert(1, 4));
+        assert!(!window.insert(0, 4));
+        assert_eq!(received(&window), vec![0, 1]);
+    }
+
+    #[test]
+    fn window_out_of_order() {
+        let mut window = Window::default();
+        assert!(window.insert(3, 4));
+        assert!(window.insert(1, 4));
+        assert!(window.insert(2, 4));
+        assert!(!window.insert(3, 4));
+        assert!(!window.insert(1, 4));
+        assert_eq!(received(&window), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn window_sliding() {
+        let mut window = Window::default();
+        for sn in 0..10 {
+            assert!(window.insert(sn, 4));
+        }
+        // only the last sequence numbers are remembered
+        assert_eq!(received(&window), vec![6, 7, 8, 9]);
+        // the older ones are dropped, as they can't be told apart from duplicates
+        assert!(!window.insert(5, 4));
+        assert!(!window.insert(9, 4));
+
+        // after a gap, the missing sequence numbers still in the window are accepted
+        assert!(window.insert(20, 4));
+        assert_eq!(received(&window), vec![20]);
+        assert!(!window.insert(16, 4));
+        assert!(window.insert(17, 4));
+        assert!(!window.insert(17, 4));
+        assert_eq!(received(&window), vec![17, 20]);
+    }
+
+    #[test]
+    fn deduplicator_persistence() {
+        let path = std::env::temp_dir().join(format!(
+            "zenoh_ext_exactly_once_{}.state",
+            std::process::id()
+        ));
+        let mut deduplicator = Deduplicator::default();
+        assert!(deduplicator
+            .sources
+            .entry("source".to_string())
+            .or_default()
+            .insert(3, 4));
+        Deduplicator::store(&bincode::serialize(&deduplicator).unwrap(), &path).unwrap();
+        assert!(!path.with_extension("tmp").exists());
+
+        let mut loaded = Deduplicator::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let window = loaded.sources.get_mut("source").unwrap();
+        assert!(!window.insert(3, 4));
+        assert!(window.insert(2, 4));
+    }
+}
@@ -0,0 +1,393 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use zenoh::handlers::{Callback, IntoCallbackReceiverPair};
+use zenoh::prelude::{KnownEncoding, Sample, SplitBuffer};
+use zenoh_core::{zread, zwrite};
+
+/// A geographic position, in decimal degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoPoint {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// A rectangular region of interest, in decimal degrees.
+///
+/// Regions crossing the antimeridian can be expressed with `west > east`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoRegion {
+    pub south: f64,
+    pub west: f64,
+    pub north: f64,
+    pub east: f64,
+}
+
+impl GeoRegion {
+    pub fn new(south: f64, west: f64, north: f64, east: f64) -> Self {
+        GeoRegion {
+            south,
+            west,
+            north,
+            east,
+        }
+    }
+
+    /// Whether `point` lies within this region (borders included).
+    pub fn contains(&self, point: &GeoPoint) -> bool {
+        if point.latitude < self.south || point.latitude > self.north {
+            return false;
+        }
+        if self.west <= self.east {
+            self.west <= point.longitude && point.longitude <= self.east
+        } else {
+            self.west <= point.longitude || point.longitude <= self.east
+        }
+    }
+}
+
+impl GeoRegion {
+    /// The [subscriber filter](zenoh::subscriber::SubscriberBuilder::filter) accepting the samples
+    /// whose position, found by `locator`, lies within this region.
+    ///
+    /// Such filter is evaluated by the first router, which then only forwards the samples located in
+    /// the region: the subscribers don't have to geo-fence the samples by themselves.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # async_std::task::block_on(async {
+    /// use zenoh::prelude::r#async::*;
+    /// use zenoh_ext::{GeoLocator, GeoRegion};
+    ///
+    /// let locator = GeoLocator::KeyChunks { latitude: 1, longitude: 2 };
+    /// let toulouse = GeoRegion::new(43.5, 1.3, 43.7, 1.6);
+    ///
+    /// let session = zenoh::open(config::peer()).res().await.unwrap();
+    /// let subscriber = session
+    ///     .declare_subscriber("fleet/**")
+    ///     .filter(toulouse.filter(&locator))
+    ///     .res()
+    ///     .await
+    ///     .unwrap();
+    /// while let Ok(sample) = subscriber.recv_async().await {
+    ///     println!("{}", sample);
+    /// }
+    /// # })
+    /// ```
+    pub fn filter(&self, locator: &GeoLocator) -> String {
+        let (latitude, longitude) = match locator {
+            GeoLocator::KeyChunks {
+                latitude,
+                longitude,
+            } => (format!("${latitude}"), format!("${longitude}")),
+            GeoLocator::JsonFields {
+                latitude,
+                longitude,
+            } => (latitude.clone(), longitude.clone()),
+        };
+        format!(
+            "geo({}, {}) in [{}, {}, {}, {}]",
+            latitude, longitude, self.south, self.west, self.north, self.east
+        )
+    }
+}
+
+/// Where to find the position of a sample.
+#[derive(Debug, Clone)]
+pub enum GeoLocator {
+    /// The latitude and longitude are the chunks of the key expression at the given indexes,
+    /// e.g. `KeyChunks { latitude: 1, longitude: 2 }` for `fleet/43.6/1.44/truck-1`.
+    KeyChunks { latitude: usize, longitude: usize },
+    /// The latitude and longitude are the given fields of a JSON payload,
+    /// e.g. `JsonFields { latitude: "lat".into(), longitude: "lon".into() }` for `{"lat": 43.6, "lon": 1.44}`.
+    JsonFields { latitude: String, longitude: String },
+}
+
+impl GeoLocator {
+    /// Returns the position of `sample`, or `None` if it can't be found.
+    pub fn locate(&self, sample: &Sample) -> Option<GeoPoint> {
+        let (latitude, longitude) = match self {
+            GeoLocator::KeyChunks {
+                latitude,
+                longitude,
+            } => {
+                let chunks: Vec<&str> = sample.key_expr.as_str().split('/').collect();
+                (
+                    chunks.get(*latitude)?.parse().ok()?,
+                    chunks.get(*longitude)?.parse().ok()?,
+                )
+            }
+            GeoLocator::JsonFields {
+                latitude,
+                longitude,
+            } => match sample.value.encoding.prefix() {
+                KnownEncoding::AppJson | KnownEncoding::TextJson => {
+                    let json: serde_json::Value =
+                        serde_json::from_slice(&sample.value.payload.contiguous()).ok()?;
+                    (
+                        json.get(latitude)?.as_f64()?,
+                        json.get(longitude)?.as_f64()?,
+                    )
+                }
+                _ => return None,
+            },
+        };
+        (latitude.is_finite() && longitude.is_finite()).then_some(GeoPoint {
+            latitude,
+            longitude,
+        })
+    }
+}
+
+struct Registration {
+    id: usize,
+    region: GeoRegion,
+    callback: Callback<'static, Sample>,
+}
+
+/// Forwards samples only to the consumers whose region of interest contains their position.
+///
+/// A single [`GeoFilter`] is typically fed by one subscriber (e.g. declared by a plugin on a router)
+/// and shared between many consumers, which then don't have to geo-fence the samples by themselves.
+/// Samples whose position can't be found by the [`GeoLocator`] are dropped.
+///
+/// To have the routers geo-fence the samples forwarded to a remote subscriber instead,
+/// declare it with a [`GeoRegion::filter`].
+///
+/// # Examples
+/// ```no_run
+/// # async_std::task::block_on(async {
+/// use zenoh::prelude::r#async::*;
+/// use zenoh_ext::{GeoFilter, GeoLocator, GeoRegion};
+///
+/// let filter = GeoFilter::new(GeoLocator::KeyChunks { latitude: 1, longitude: 2 });
+/// let (_registration, toulouse) =
+///     filter.register(GeoRegion::new(43.5, 1.3, 43.7, 1.6), flume::bounded(32));
+///
+/// let session = zenoh::open(config::peer()).res().await.unwrap();
+/// let dispatcher = filter.clone();
+/// let subscriber = session
+///     .declare_subscriber("fleet/**")
+///     .callback(move |sample| dispatcher.dispatch(sample))
+///     .res()
+///     .await
+///     .unwrap();
+/// while let Ok(sample) = toulouse.recv_async().await {
+///     println!("{}", sample);
+/// }
+/// # })
+/// ```
+#[derive(Clone)]
+pub struct GeoFilter {
+    locator: GeoLocator,
+    registrations: Arc<RwLock<Vec<Registration>>>,
+    next_id: Arc<AtomicUsize>,
+}
+
+impl GeoFilter {
+    pub fn new(locator: GeoLocator) -> Self {
+        GeoFilter {
+            locator,
+            registrations: Arc::new(RwLock::new(Vec::new())),
+            next_id: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Registers a consumer interested in the samples located in `region`.
+    ///
+    /// The consumer remains registered until the returned [`GeoRegistration`] is dropped.
+    pub fn register<Handler>(
+        &self,
+        region: GeoRegion,
+        handler: Handler,
+    ) -> (GeoRegistration, Handler::Receiver)
+    where
+        Handler: IntoCallbackReceiverPair<'static, Sample>,
+    {
+        let (callback, receiver) = handler.into_cb_receiver_pair();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        zwrite!(self.registrations).push(Registration {
+            id,
+            region,
+            callback,
+        });
+        let registration = GeoRegistration {
+            id,
+            registrations: self.registrations.clone(),
+        };
+        (registration, receiver)
+    }
+
+    /// Forwards `sample` to all the consumers whose region of interest contains its position.
+    pub fn dispatch(&self, sample: Sample) {
+        let point = match self.locator.locate(&sample) {
+            Some(point) => point,
+            None => {
+                log::trace!("No position found for sample on {}", sample.key_expr);
+                return;
+            }
+        };
+        let callbacks: Vec<Callback<'static, Sample>> = zread!(self.registrations)
+            .iter()
+            .filter(|r| r.region.contains(&point))
+            .map(|r| r.callback.clone())
+            .collect();
+        if let Some((last, others)) = callbacks.split_last() {
+            for callback in others {
+                callback(sample.clone());
+            }
+            last(sample);
+        }
+    }
+}
+
+/// A consumer registration to a [`GeoFilter`], cancelled on drop.
+pub struct GeoRegistration {
+    id: usize,
+    registrations: Arc<RwLock<Vec<Registration>>>,
+}
+
+impl Drop for GeoRegistration {
+    fn drop(&mut self) {
+        zwrite!(self.registrations).retain(|r| r.id != self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zenoh::prelude::{Encoding, KeyExpr, Value};
+
+    fn sample(key_expr: &str, json: Option<&str>) -> Sample {
+        let key_expr = KeyExpr::try_from(key_expr.to_string()).unwrap();
+        match json {
+            Some(json) => Sample::new(key_expr, Value::from(json).encoding(Encoding::APP_JSON)),
+            None => Sample::new(key_expr, "value"),
+        }
+    }
+
+    #[test]
+    fn geo_region_contains() {
+        let toulouse = GeoRegion::new(43.5, 1.3, 43.7, 1.6);
+        assert!(toulouse.contains(&GeoPoint {
+            latitude: 43.6,
+            longitude: 1.44
+        }));
+        assert!(toulouse.contains(&GeoPoint {
+            latitude: 43.5,
+            longitude: 1.6
+        }));
+        assert!(!toulouse.contains(&GeoPoint {
+            latitude: 48.85,
+            longitude: 1.44
+        }));
+
+        // regions crossing the antimeridian
+        let pacific = GeoRegion::new(-50.0, 170.0, 10.0, -170.0);
+        for (longitude, contained) in [(178.1, true), (-175.0, true), (150.0, false)] {
+            let point = GeoPoint {
+                latitude: -17.7,
+                longitude,
+            };
+            assert_eq!(pacific.contains(&point), contained);
+        }
+    }
+
+    #[test]
+    fn geo_locator() {
+        let chunks = GeoLocator::KeyChunks {
+            latitude: 1,
+            longitude: 2,
+        };
+        assert_eq!(
+            chunks.locate(&sample("fleet/43.6/1.44/truck-1", None)),
+            Some(GeoPoint {
+                latitude: 43.6,
+                longitude: 1.44
+            })
+        );
+        assert_eq!(chunks.locate(&sample("fleet/truck-1", None)), None);
+
+        let fields = GeoLocator::JsonFields {
+            latitude: "lat".into(),
+            longitude: "lon".into(),
+        };
+        assert_eq!(
+            fields.locate(&sample(
+                "fleet/truck-1",
+                Some(r#"{"lat": 43.6, "lon": 1.44}"#)
+            )),
+            Some(GeoPoint {
+                latitude: 43.6,
+                longitude: 1.44
+            })
+        );
+        assert_eq!(
+            fields.locate(&sample("fleet/truck-1", Some(r#"{"lat": 43.6}"#))),
+            None
+        );
+        assert_eq!(fields.locate(&sample("fleet/truck-1", None)), None);
+    }
+
+    #[test]
+    fn geo_region_filter() {
+        let toulouse = GeoRegion::new(43.5, 1.3, 43.7, -1.6);
+        assert_eq!(
+            toulouse.filter(&GeoLocator::KeyChunks {
+                latitude: 1,
+                longitude: 2
+            }),
+            "geo($1, $2) in [43.5, 1.3, 43.7, -1.6]"
+        );
+        assert_eq!(
+            toulouse.filter(&GeoLocator::JsonFields {
+                latitude: "lat".into(),
+                longitude: "lon".into()
+            }),
+            "geo(lat, lon) in [43.5, 1.3, 43.7, -1.6]"
+        );
+    }
+
+    #[test]
+    fn geo_filter_dispatch() {
+        let filter = GeoFilter::new(GeoLocator::KeyChunks {
+            latitude: 1,
+            longitude: 2,
+        });
+        let (toulouse_registration, toulouse) =
+            filter.register(GeoRegion::new(43.5, 1.3, 43.7, 1.6), flume::unbounded());
+        let (_paris_registration, paris) =
+            filter.register(GeoRegion::new(48.8, 2.2, 48.9, 2.5), flume::unbounded());
+
+        filter.dispatch(sample("fleet/43.6/1.44/truck-1", None));
+        filter.dispatch(sample("fleet/48.85/2.35/truck-2", None));
+        filter.dispatch(sample("fleet/truck-3", None));
+        assert_eq!(
+            toulouse.try_recv().unwrap().key_expr.as_str(),
+            "fleet/43.6/1.44/truck-1"
+        );
+        assert!(toulouse.try_recv().is_err());
+        assert_eq!(
+            paris.try_recv().unwrap().key_expr.as_str(),
+            "fleet/48.85/2.35/truck-2"
+        );
+        assert!(paris.try_recv().is_err());
+
+        // the dropped registrations don't receive anything anymore
+        drop(toulouse_registration);
+        filter.dispatch(sample("fleet/43.6/1.44/truck-1", None));
+        assert!(toulouse.try_recv().is_err());
+    }
+}
@@ -11,18 +11,50 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
+mod command;
+mod dataflow;
+mod encryption;
+mod exactly_once;
+mod geo;
 pub mod group;
 mod publication_cache;
 mod querying_subscriber;
+mod schema;
 mod session_ext;
+mod stream;
 mod subscriber_ext;
+mod time_sync;
+mod tunnel;
+pub use command::{
+    Command, CommandOutcome, CommandReceiver, CommandReceiverBuilder, CommandSender,
+    CommandSenderBuilder, COMMAND_DEFAULT_TIMEOUT,
+};
+pub use dataflow::{Dataflow, DataflowBuilder, DataflowOverflow, DATAFLOW_DEFAULT_CAPACITY};
+pub use encryption::{Decrypting, KeyProvider, PayloadCipher, StaticKeyProvider, PAYLOAD_KEY_SIZE};
+pub use exactly_once::{
+    ExactlyOncePublisher, ExactlyOncePublisherBuilder, ExactlyOnceSubscriber,
+    ExactlyOnceSubscriberBuilder, IdempotencyKey, EXACTLY_ONCE_DEFAULT_WINDOW,
+};
+pub use geo::{GeoFilter, GeoLocator, GeoPoint, GeoRegion, GeoRegistration};
 pub use publication_cache::{PublicationCache, PublicationCacheBuilder};
 pub use querying_subscriber::{
     FetchingSubscriber, FetchingSubscriberBuilder, QueryingSubscriberBuilder,
 };
+pub use schema::{Schema, SchemaRegistry, SchemaRegistryBuilder, Validating, ValidationMode};
 pub use session_ext::SessionExt;
+pub use stream::{
+    StreamFrame, StreamPublisher, StreamPublisherBuilder, StreamSubscriber,
+    StreamSubscriberBuilder, STREAM_DEFAULT_CHUNK_SIZE, STREAM_DEFAULT_QUEUE_SIZE,
+};
 pub use subscriber_ext::SubscriberBuilderExt;
 pub use subscriber_ext::SubscriberForward;
+pub use time_sync::{
+    synchronized_clock, TimeServer, TimeSync, TimeSyncBuilder, TIME_SYNC_DEFAULT_PERIOD,
+    TIME_SYNC_DEFAULT_SAMPLES,
+};
+pub use tunnel::{
+    TunnelEntrance, TunnelExit, TUNNEL_BUFFER_SIZE, TUNNEL_TARGET_PARAMETER, TUNNEL_WINDOW,
+};
 
 /// The space of keys to use in a [`FetchingSubscriber`].
 pub enum KeySpace {
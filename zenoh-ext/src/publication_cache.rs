@@ -192,9 +192,17 @@ impl<'a> PublicationCache<'a> {
                     // on query, reply with cach content
                     query = quer_recv.recv_async() => {
                         if let Ok(query) = query {
+                            // reply only the most recent samples of each key if requested
+                            let depth = match query.selector().history() {
+                                Ok(depth) => depth.unwrap_or(usize::MAX),
+                                Err(e) => {
+                                    log::warn!("PublicationCache on {}: {}", pub_key_expr, e);
+                                    usize::MAX
+                                }
+                            };
                             if !query.selector().key_expr.as_str().contains('*') {
                                 if let Some(queue) = cache.get(query.selector().key_expr.as_keyexpr()) {
-                                    for sample in queue {
+                                    for sample in queue.iter().skip(queue.len().saturating_sub(depth)) {
                                         if let Err(e) = query.reply(Ok(sample.clone())).res_async().await {
                                             log::warn!("Error replying to query: {}", e);
                                         }
@@ -203,7 +211,7 @@ impl<'a> PublicationCache<'a> {
                             } else {
                                 for (key_expr, queue) in cache.iter() {
                                     if query.selector().key_expr.intersects(unsafe{ keyexpr::from_str_unchecked(key_expr) }) {
-                                        for sample in queue {
+                                        for sample in queue.iter().skip(queue.len().saturating_sub(depth)) {
                                             if let Err(e) = query.reply(Ok(sample.clone())).res_async().await {
                                                 log::warn!("Error replying to query: {}", e);
                                             }
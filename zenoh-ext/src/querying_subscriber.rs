@@ -20,11 +20,13 @@ use std::time::Duration;
 use zenoh::handlers::{locked, DefaultHandler};
 use zenoh::prelude::r#async::*;
 use zenoh::query::{QueryConsolidation, QueryTarget, ReplyKeyExpr};
+use zenoh::selector::HISTORY_KEY;
 use zenoh::subscriber::{Reliability, Subscriber};
 use zenoh::time::Timestamp;
 use zenoh::Result as ZResult;
 use zenoh::SessionRef;
 use zenoh_core::{zlock, AsyncResolve, Resolvable, SyncResolve};
+use zenoh_result::bail;
 
 /// The builder of [`FetchingSubscriber`], allowing to configure it.
 pub struct QueryingSubscriberBuilder<'a, 'b, KeySpace, Handler> {
@@ -38,6 +40,7 @@ pub struct QueryingSubscriberBuilder<'a, 'b, KeySpace, Handler> {
     pub(crate) query_consolidation: QueryConsolidation,
     pub(crate) query_accept_replies: ReplyKeyExpr,
     pub(crate) query_timeout: Duration,
+    pub(crate) history: Option<usize>,
     pub(crate) handler: Handler,
 }
 
@@ -62,6 +65,7 @@ impl<'a, 'b, KeySpace> QueryingSubscriberBuilder<'a, 'b, KeySpace, DefaultHandle
             query_consolidation,
             query_accept_replies,
             query_timeout,
+            history,
             handler: _,
         } = self;
         QueryingSubscriberBuilder {
@@ -75,6 +79,7 @@ impl<'a, 'b, KeySpace> QueryingSubscriberBuilder<'a, 'b, KeySpace, DefaultHandle
             query_consolidation,
             query_accept_replies,
             query_timeout,
+            history,
             handler: callback,
         }
     }
@@ -115,6 +120,7 @@ impl<'a, 'b, KeySpace> QueryingSubscriberBuilder<'a, 'b, KeySpace, DefaultHandle
             query_consolidation,
             query_accept_replies,
             query_timeout,
+            history,
             handler: _,
         } = self;
         QueryingSubscriberBuilder {
@@ -128,6 +134,7 @@ impl<'a, 'b, KeySpace> QueryingSubscriberBuilder<'a, 'b, KeySpace, DefaultHandle
             query_consolidation,
             query_accept_replies,
             query_timeout,
+            history,
             handler,
         }
     }
@@ -198,6 +205,16 @@ impl<'a, 'b, Handler> QueryingSubscriberBuilder<'a, 'b, crate::UserSpace, Handle
         self.query_accept_replies = accept_replies;
         self
     }
+
+    /// Request at most the `depth` most recent publications of each key from the matching
+    /// [`PublicationCache`](crate::PublicationCache)s, so that they are delivered upon subscription.
+    ///
+    /// The depth is passed to the queried caches with the `_history` selector parameter.
+    #[inline]
+    pub fn history(mut self, depth: usize) -> Self {
+        self.history = Some(depth);
+        self
+    }
 }
 
 impl<'a, 'b, KeySpace, Handler> QueryingSubscriberBuilder<'a, 'b, KeySpace, Handler> {
@@ -227,9 +244,12 @@ where
         let session = self.session.clone();
         let key_expr = self.key_expr?;
         let key_space = self.key_space.clone().into();
-        let query_selector = match self.query_selector {
-            Some(s) => Some(s?),
-            None => None,
+        let query_selector = match (self.query_selector, self.history) {
+            (_, Some(0)) => bail!("The history depth must be strictly positive"),
+            (Some(s), Some(depth)) => Some(with_history(s?, depth)),
+            (None, Some(depth)) => Some(with_history(key_expr.clone().into(), depth)),
+            (Some(s), None) => Some(s?),
+            (None, None) => None,
         };
         let query_target = self.query_target;
         let query_consolidation = self.query_consolidation;
@@ -279,6 +299,11 @@ where
     }
 }
 
+fn with_history(mut selector: Selector<'_>, depth: usize) -> Selector<'_> {
+    selector.extend([(HISTORY_KEY, depth.to_string())]);
+    selector
+}
+
 // Collects samples in their Timestamp order, if any,
 // and ignores repeating samples with duplicate timestamps.
 // Samples without Timestamps are kept in a separate Vector,
@@ -0,0 +1,550 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use zenoh::handlers::{Callback, IntoCallbackReceiverPair};
+use zenoh::prelude::r#async::*;
+use zenoh::publication::{Publication, Publisher};
+use zenoh::query::Reply;
+use zenoh::subscriber::Subscriber;
+use zenoh::Session;
+use zenoh_core::{zread, zwrite, AsyncResolve, Resolvable, SyncResolve};
+use zenoh_result::{bail, zerror, ZResult};
+
+/// The schema of a JSON payload.
+///
+/// Schemas are expressed in JSON:
+/// - a type name: `"null"`, `"boolean"`, `"number"`, `"integer"`, `"string"` or `"any"`,
+///   optionally followed by `?` if the value may be `null` or, for an object field, missing;
+/// - an array with a single schema, for arrays whose items all conform to this schema;
+/// - an object mapping field names to schemas, for objects having (at least) these fields.
+///
+/// E.g. `{"temperature": "number", "sensor": {"id": "integer", "label": "string?"}, "history": ["number"]}`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schema {
+    Any,
+    Null,
+    Boolean,
+    Number,
+    Integer,
+    String,
+    Optional(Box<Schema>),
+    Array(Box<Schema>),
+    Object(BTreeMap<String, Schema>),
+}
+
+impl Schema {
+    pub fn from_json(json: &JsonValue) -> ZResult<Schema> {
+        match json {
+            JsonValue::String(name) => match name.strip_suffix('?') {
+                Some(name) => Ok(Schema::Optional(Box::new(Schema::from_type_name(name)?))),
+                None => Schema::from_type_name(name),
+            },
+            JsonValue::Array(items) => match items.as_slice() {
+                [item] => Ok(Schema::Array(Box::new(Schema::from_json(item)?))),
+                _ => bail!("An array schema must contain exactly one item schema"),
+            },
+            JsonValue::Object(fields) => Ok(Schema::Object(
+                fields
+                    .iter()
+                    .map(|(name, field)| Ok((name.clone(), Schema::from_json(field)?)))
+                    .collect::<ZResult<_>>()?,
+            )),
+            _ => bail!("Invalid schema: {}", json),
+        }
+    }
+
+    fn from_type_name(name: &str) -> ZResult<Schema> {
+        match name {
+            "any" => Ok(Schema::Any),
+            "null" => Ok(Schema::Null),
+            "boolean" => Ok(Schema::Boolean),
+            "number" => Ok(Schema::Number),
+            "integer" => Ok(Schema::Integer),
+            "string" => Ok(Schema::String),
+            _ => bail!("Unknown type in schema: '{}'", name),
+        }
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        match self {
+            Schema::Any => "any".into(),
+            Schema::Null => "null".into(),
+            Schema::Boolean => "boolean".into(),
+            Schema::Number => "number".into(),
+            Schema::Integer => "integer".into(),
+            Schema::String => "string".into(),
+            Schema::Optional(schema) => match schema.to_json() {
+                JsonValue::String(name) => format!("{name}?").into(),
+                json => json,
+            },
+            Schema::Array(item) => JsonValue::Array(vec![item.to_json()]),
+            Schema::Object(fields) => JsonValue::Object(
+                fields
+                    .iter()
+                    .map(|(name, field)| (name.clone(), field.to_json()))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Checks that `json` conforms to this schema.
+    pub fn check(&self, json: &JsonValue) -> ZResult<()> {
+        self.check_at(json, "$")
+    }
+
+    fn check_at(&self, json: &JsonValue, path: &str) -> ZResult<()> {
+        let conforms = match (self, json) {
+            (Schema::Any, _)
+            | (Schema::Null, JsonValue::Null)
+            | (Schema::Boolean, JsonValue::Bool(_))
+            | (Schema::Number, JsonValue::Number(_))
+            | (Schema::String, JsonValue::String(_))
+            | (Schema::Optional(_), JsonValue::Null) => true,
+            (Schema::Integer, JsonValue::Number(n)) => n.is_i64() || n.is_u64(),
+            (Schema::Optional(schema), json) => return schema.check_at(json, path),
+            (Schema::Array(item), JsonValue::Array(items)) => {
+                for (i, json) in items.iter().enumerate() {
+                    item.check_at(json, &format!("{path}[{i}]"))?;
+                }
+                true
+            }
+            (Schema::Object(fields), JsonValue::Object(object)) => {
+                for (name, field) in fields {
+                    match object.get(name) {
+                        Some(json) => field.check_at(json, &format!("{path}.{name}"))?,
+                        None if matches!(field, Schema::Optional(_)) => (),
+                        None => bail!("Missing field {}.{}", path, name),
+                    }
+                }
+                true
+            }
+            _ => false,
+        };
+        if !conforms {
+            bail!("Expected {} at {}, found {}", self, path, json);
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_json())
+    }
+}
+
+impl FromStr for Schema {
+    type Err = zenoh_result::Error;
+
+    fn from_str(s: &str) -> ZResult<Self> {
+        let json: JsonValue =
+            serde_json::from_str(s).map_err(|e| zerror!("Invalid schema '{}': {}", s, e))?;
+        Schema::from_json(&json)
+    }
+}
+
+/// What to do with the samples that don't conform to their registered schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationMode {
+    /// Drop the non-conforming samples.
+    Reject,
+    /// Deliver the non-conforming samples, logging a warning.
+    Flag,
+}
+
+// The schemas known by a registry, indexed by the key under which they are stored.
+#[derive(Default)]
+struct Schemas {
+    entries: Vec<(OwnedKeyExpr, OwnedKeyExpr, Schema)>,
+}
+
+impl Schemas {
+    fn insert(&mut self, storage_key: OwnedKeyExpr, key_expr: OwnedKeyExpr, schema: Schema) {
+        self.entries.retain(|(k, _, _)| k != &storage_key);
+        self.entries.push((storage_key, key_expr, schema));
+    }
+
+    fn remove(&mut self, storage_key: &keyexpr) {
+        self.entries
+            .retain(|(k, _, _)| k.as_str() != storage_key.as_str());
+    }
+
+    // The schema registered on the most specific key expression including `key_expr`.
+    fn get(&self, key_expr: &keyexpr) -> Option<&Schema> {
+        self.entries
+            .iter()
+            .filter(|(_, ke, _)| ke.includes(key_expr))
+            .reduce(|a, b| if a.1.includes(&b.1) { b } else { a })
+            .map(|(_, _, schema)| schema)
+    }
+
+    fn validate(&self, key_expr: &keyexpr, value: &Value) -> ZResult<()> {
+        let schema = match self.get(key_expr) {
+            Some(schema) => schema,
+            None => return Ok(()),
+        };
+        match value.encoding.prefix() {
+            KnownEncoding::AppJson | KnownEncoding::TextJson => (),
+            _ => bail!(
+                "Payload on {} is not JSON-encoded, as required by its schema",
+                key_expr
+            ),
+        }
+        let json: JsonValue = serde_json::from_slice(&value.payload.contiguous())
+            .map_err(|e| zerror!("Invalid JSON payload on {}: {}", key_expr, e))?;
+        schema.check(&json).map_err(|e| {
+            zerror!(
+                "Payload on {} doesn't conform to its schema: {}",
+                key_expr,
+                e
+            )
+        })?;
+        Ok(())
+    }
+
+    // Handles a schema registration received from the registry's key space.
+    fn on_sample(&mut self, sample: Sample) {
+        match sample.kind {
+            SampleKind::Put => match parse_registration(&sample.value) {
+                Ok((key_expr, schema)) => {
+                    self.insert(sample.key_expr.into(), key_expr, schema);
+                }
+                Err(e) => log::warn!("Invalid schema registration on {}: {}", sample.key_expr, e),
+            },
+            SampleKind::Delete => self.remove(&sample.key_expr),
+        }
+    }
+}
+
+fn parse_registration(value: &Value) -> ZResult<(OwnedKeyExpr, Schema)> {
+    let json: JsonValue = serde_json::from_slice(&value.payload.contiguous())?;
+    let key_expr = json
+        .get("key_expr")
+        .and_then(JsonValue::as_str)
+        .ok_or_else(|| zerror!("Missing key_expr"))?;
+    let schema = json
+        .get("schema")
+        .ok_or_else(|| zerror!("Missing schema"))?;
+    Ok((
+        OwnedKeyExpr::from_str(key_expr)?,
+        Schema::from_json(schema)?,
+    ))
+}
+
+/// The builder of [`SchemaRegistry`], allowing to configure it.
+pub struct SchemaRegistryBuilder<'a, 'b> {
+    session: &'a Session,
+    prefix: ZResult<KeyExpr<'b>>,
+}
+
+impl<'a, 'b> SchemaRegistryBuilder<'a, 'b> {
+    pub(crate) fn new(session: &'a Session, prefix: ZResult<KeyExpr<'b>>) -> Self {
+        SchemaRegistryBuilder { session, prefix }
+    }
+}
+
+impl<'a> Resolvable for SchemaRegistryBuilder<'a, '_> {
+    type To = ZResult<SchemaRegistry<'a>>;
+}
+
+impl SyncResolve for SchemaRegistryBuilder<'_, '_> {
+    fn res_sync(self) -> <Self as Resolvable>::To {
+        let (registry, selector) = SchemaRegistry::declare(self)?;
+        let replies = registry.session.get(&selector).res_sync()?;
+        while let Ok(reply) = replies.recv() {
+            registry.on_reply(&selector, reply);
+        }
+        Ok(registry)
+    }
+}
+
+impl<'a> AsyncResolve for SchemaRegistryBuilder<'a, '_> {
+    type Future = Pin<Box<dyn Future<Output = Self::To> + Send + 'a>>;
+
+    fn res_async(self) -> Self::Future {
+        let declared = SchemaRegistry::declare(self);
+        Box::pin(async move {
+            let (registry, selector) = declared?;
+            let replies = registry.session.get(&selector).res_async().await?;
+            while let Ok(reply) = replies.recv_async().await {
+                registry.on_reply(&selector, reply);
+            }
+            ZResult::Ok(registry)
+        })
+    }
+}
+
+/// A registry of payload schemas per key expression.
+///
+/// The schemas are published under the registry's prefix, so that a storage configured on
+/// `<prefix>/**` makes them persistent and shares them with all the registries using this prefix.
+/// The registry validates publications against the schema registered on the most specific
+/// key expression including them, either on the publisher side with [`SchemaRegistry::put`]
+/// or on the subscriber side with [`SchemaRegistry::validating`].
+///
+/// # Examples
+/// ```no_run
+/// # async_std::task::block_on(async {
+/// use zenoh::prelude::r#async::*;
+/// use zenoh_ext::{SessionExt, ValidationMode};
+///
+/// let session = zenoh::open(config::peer()).res().await.unwrap();
+/// let registry = session.declare_schema_registry("@schemas").res().await.unwrap();
+/// registry
+///     .register("demo/sensors/**", r#"{"temperature": "number"}"#.parse().unwrap())
+///     .unwrap();
+///
+/// let subscriber = session
+///     .declare_subscriber("demo/sensors/**")
+///     .with(registry.validating(flume::bounded(32), ValidationMode::Reject))
+///     .res()
+///     .await
+///     .unwrap();
+/// let publisher = session.declare_publisher("demo/sensors/1").res().await.unwrap();
+/// let value = serde_json::json!({"temperature": 21.5});
+/// registry.put(&publisher, value).unwrap().res().await.unwrap();
+/// # })
+/// ```
+pub struct SchemaRegistry<'a> {
+    session: &'a Session,
+    prefix: OwnedKeyExpr,
+    schemas: Arc<RwLock<Schemas>>,
+    _subscriber: Subscriber<'a, ()>,
+}
+
+impl<'a> SchemaRegistry<'a> {
+    // Declares a registry keeping track of the registrations, returning it along with the selector
+    // the already registered schemas must be retrieved on
+    fn declare(conf: SchemaRegistryBuilder<'a, '_>) -> ZResult<(SchemaRegistry<'a>, OwnedKeyExpr)> {
+        let prefix: OwnedKeyExpr = conf.prefix?.into();
+        let selector = prefix.join("**")?;
+        let schemas = Arc::new(RwLock::new(Schemas::default()));
+
+        let subscriber = {
+            let schemas = schemas.clone();
+            conf.session
+                .declare_subscriber(&selector)
+                .callback(move |sample| zwrite!(schemas).on_sample(sample))
+                .res_sync()?
+        };
+
+        let registry = SchemaRegistry {
+            session: conf.session,
+            prefix,
+            schemas,
+            _subscriber: subscriber,
+        };
+        Ok((registry, selector))
+    }
+
+    // Handles a reply to the retrieval of the already registered schemas.
+    fn on_reply(&self, selector: &keyexpr, reply: Reply) {
+        match reply.sample {
+            Ok(sample) => zwrite!(self.schemas).on_sample(sample),
+            Err(e) => log::warn!("Error retrieving schemas on {}: {}", selector, e),
+        }
+    }
+
+    fn storage_key(&self, key_expr: &keyexpr) -> ZResult<OwnedKeyExpr> {
+        // the registrations are stored on non-wild keys
+        let escaped = key_expr.as_str().replace('$', "%24").replace('*', "%2A");
+        self.prefix.join(&escaped)
+    }
+
+    /// Registers `schema` for the publications on `key_expr`.
+    pub fn register<TryIntoKeyExpr>(&self, key_expr: TryIntoKeyExpr, schema: Schema) -> ZResult<()>
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'static>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'static>>>::Error: Into<zenoh_result::Error>,
+    {
+        let key_expr: OwnedKeyExpr = key_expr.try_into().map_err(Into::into)?.into();
+        let storage_key = self.storage_key(&key_expr)?;
+        let registration = serde_json::json!({
+            "key_expr": key_expr.as_str(),
+            "schema": schema.to_json(),
+        });
+        self.session.put(&storage_key, registration).res_sync()?;
+        zwrite!(self.schemas).insert(storage_key, key_expr, schema);
+        Ok(())
+    }
+
+    /// Removes the schema registered for `key_expr` (if any).
+    pub fn unregister<TryIntoKeyExpr>(&self, key_expr: TryIntoKeyExpr) -> ZResult<()>
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'static>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'static>>>::Error: Into<zenoh_result::Error>,
+    {
+        let key_expr: KeyExpr = key_expr.try_into().map_err(Into::into)?;
+        let storage_key = self.storage_key(&key_expr)?;
+        self.session.delete(&storage_key).res_sync()?;
+        zwrite!(self.schemas).remove(&storage_key);
+        Ok(())
+    }
+
+    /// Returns the schema applying to the publications on `key_expr` (if any).
+    pub fn schema(&self, key_expr: &keyexpr) -> Option<Schema> {
+        zread!(self.schemas).get(key_expr).cloned()
+    }
+
+    /// Checks that `value` conforms to the schema applying to `key_expr` (if any).
+    pub fn validate(&self, key_expr: &keyexpr, value: &Value) -> ZResult<()> {
+        zread!(self.schemas).validate(key_expr, value)
+    }
+
+    /// Validates `value` and publishes it with `publisher`, or returns an error if it doesn't conform.
+    pub fn put<'p, IntoValue>(
+        &self,
+        publisher: &'p Publisher<'_>,
+        value: IntoValue,
+    ) -> ZResult<Publication<'p>>
+    where
+        IntoValue: Into<Value>,
+    {
+        let value = value.into();
+        self.validate(publisher.key_expr(), &value)?;
+        Ok(publisher.put(value))
+    }
+
+    /// Wraps a handler so that it only receives (or is warned about) the samples
+    /// conforming to their schema, depending on `mode`.
+    pub fn validating<Handler>(
+        &self,
+        handler: Handler,
+        mode: ValidationMode,
+    ) -> Validating<Handler> {
+        Validating {
+            schemas: self.schemas.clone(),
+            mode,
+            handler,
+        }
+    }
+}
+
+/// A handler validating the samples before passing them to the wrapped handler.
+///
+/// See [`SchemaRegistry::validating`].
+pub struct Validating<Handler> {
+    schemas: Arc<RwLock<Schemas>>,
+    mode: ValidationMode,
+    handler: Handler,
+}
+
+impl<'a, Handler> IntoCallbackReceiverPair<'a, Sample> for Validating<Handler>
+where
+    Handler: IntoCallbackReceiverPair<'a, Sample>,
+{
+    type Receiver = Handler::Receiver;
+
+    fn into_cb_receiver_pair(self) -> (Callback<'a, Sample>, Self::Receiver) {
+        let (callback, receiver) = self.handler.into_cb_receiver_pair();
+        let (schemas, mode) = (self.schemas, self.mode);
+        (
+            Arc::new(move |sample: Sample| {
+                let result = zread!(schemas).validate(&sample.key_expr, &sample.value);
+                match (result, mode) {
+                    (Ok(()), _) => callback(sample),
+                    (Err(e), ValidationMode::Flag) => {
+                        log::warn!("{}", e);
+                        callback(sample)
+                    }
+                    (Err(e), ValidationMode::Reject) => log::warn!("Rejected sample: {}", e),
+                }
+            }),
+            receiver,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema(s: &str) -> Schema {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn schema_check_types() {
+        assert!(schema(r#""any""#).check(&json!({"a": [1]})).is_ok());
+        assert!(schema(r#""null""#).check(&json!(null)).is_ok());
+        assert!(schema(r#""boolean""#).check(&json!(true)).is_ok());
+        assert!(schema(r#""string""#).check(&json!("a")).is_ok());
+        assert!(schema(r#""string""#).check(&json!(1)).is_err());
+        assert!(schema(r#""number""#).check(&json!(1.5)).is_ok());
+        assert!(schema(r#""number""#).check(&json!(-1)).is_ok());
+        assert!(schema(r#""integer""#).check(&json!(-1)).is_ok());
+        assert!(schema(r#""integer""#).check(&json!(1.5)).is_err());
+        assert!(schema(r#""integer?""#).check(&json!(null)).is_ok());
+        assert!(schema(r#""integer?""#).check(&json!("1")).is_err());
+        assert!(schema(r#""integer""#).check(&json!(null)).is_err());
+    }
+
+    #[test]
+    fn schema_check_nested() {
+        let schema = schema(
+            r#"{"temperature": "number", "sensor": {"id": "integer", "label": "string?"}, "history": ["number"]}"#,
+        );
+        assert!(schema
+            .check(&json!({"temperature": 21.5, "sensor": {"id": 1}, "history": []}))
+            .is_ok());
+        // the optional fields may be null, and the unknown fields are allowed
+        assert!(schema
+            .check(&json!({
+                "temperature": 21.5,
+                "sensor": {"id": 1, "label": null, "room": "kitchen"},
+                "history": [20, 20.5]
+            }))
+            .is_ok());
+
+        let e = schema
+            .check(&json!({"temperature": 21.5, "sensor": {"label": "a"}, "history": []}))
+            .unwrap_err();
+        assert!(e.to_string().starts_with("Missing field $.sensor.id "));
+        let e = schema
+            .check(&json!({"temperature": 21.5, "sensor": {"id": 1}, "history": [20, "a"]}))
+            .unwrap_err();
+        assert!(e.to_string().contains("at $.history[1]"));
+        assert!(schema.check(&json!([21.5])).is_err());
+    }
+
+    #[test]
+    fn schema_json_round_trip() {
+        let json = json!({"a": "integer?", "b": ["string"], "c": {"d": "any"}});
+        let schema = Schema::from_json(&json).unwrap();
+        assert_eq!(schema.to_json(), json);
+        assert!(Schema::from_json(&json!("float")).is_err());
+        assert!(Schema::from_json(&json!(["number", "string"])).is_err());
+    }
+
+    #[test]
+    fn schemas_most_specific() {
+        let ke = |s: &str| OwnedKeyExpr::from_str(s).unwrap();
+        let mut schemas = Schemas::default();
+        schemas.insert(ke("s/1"), ke("demo/**"), schema(r#""any""#));
+        schemas.insert(ke("s/2"), ke("demo/sensors/*"), schema(r#""number""#));
+        assert_eq!(schemas.get(&ke("demo/sensors/1")), Some(&Schema::Number));
+        assert_eq!(schemas.get(&ke("demo/actuators/1")), Some(&Schema::Any));
+        assert_eq!(schemas.get(&ke("other")), None);
+        schemas.remove(&ke("s/2"));
+        assert_eq!(schemas.get(&ke("demo/sensors/1")), Some(&Schema::Any));
+    }
+}
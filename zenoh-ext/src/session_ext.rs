@@ -11,7 +11,7 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
-use super::PublicationCacheBuilder;
+use super::{PublicationCacheBuilder, SchemaRegistryBuilder};
 use std::convert::TryInto;
 use std::sync::Arc;
 use zenoh::prelude::KeyExpr;
@@ -26,6 +26,15 @@ pub trait SessionExt {
     where
         TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
         <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>;
+
+    /// Declares a [`SchemaRegistry`](super::SchemaRegistry) storing its schemas under `prefix`.
+    fn declare_schema_registry<'a, 'b, TryIntoKeyExpr>(
+        &'a self,
+        prefix: TryIntoKeyExpr,
+    ) -> SchemaRegistryBuilder<'a, 'b>
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>;
 }
 
 impl SessionExt for Session {
@@ -39,6 +48,17 @@ impl SessionExt for Session {
     {
         PublicationCacheBuilder::new(self, pub_key_expr.try_into().map_err(Into::into))
     }
+
+    fn declare_schema_registry<'a, 'b, TryIntoKeyExpr>(
+        &'a self,
+        prefix: TryIntoKeyExpr,
+    ) -> SchemaRegistryBuilder<'a, 'b>
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+    {
+        SchemaRegistryBuilder::new(self, prefix.try_into().map_err(Into::into))
+    }
 }
 
 impl SessionExt for Arc<Session> {
@@ -52,4 +72,15 @@ impl SessionExt for Arc<Session> {
     {
         PublicationCacheBuilder::new(self, pub_key_expr.try_into().map_err(Into::into))
     }
+
+    fn declare_schema_registry<'a, 'b, TryIntoKeyExpr>(
+        &'a self,
+        prefix: TryIntoKeyExpr,
+    ) -> SchemaRegistryBuilder<'a, 'b>
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+    {
+        SchemaRegistryBuilder::new(self, prefix.try_into().map_err(Into::into))
+    }
 }
@@ -0,0 +1,518 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use flume::{Receiver, Sender, TrySendError};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::future::Ready;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use zenoh::prelude::r#async::*;
+use zenoh::subscriber::Subscriber;
+use zenoh::Session;
+use zenoh_core::{AsyncResolve, Resolvable, SyncResolve};
+use zenoh_result::{bail, ZResult};
+
+/// The default maximum size of the chunks a frame is split into.
+pub const STREAM_DEFAULT_CHUNK_SIZE: usize = 16 * 1024;
+/// The default number of frames a [`StreamPublisher`] queues before dropping some.
+pub const STREAM_DEFAULT_QUEUE_SIZE: usize = 8;
+
+// stream (u64) | seq (u64) | frame (u64) | index (u32) | count (u32) | flags (u8)
+const HEADER_SIZE: usize = 33;
+const FLAG_KEYFRAME: u8 = 0x01;
+
+/// A frame of a stream: the unit of its drop policy, e.g. an encoded video frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamFrame {
+    /// The number of the frame in the stream.
+    pub number: u64,
+    /// Whether the frame can be decoded without the previous ones, starting a group of frames
+    /// (e.g. a GOP) the following ones depend on.
+    pub keyframe: bool,
+    pub data: Vec<u8>,
+}
+
+// A chunk of a frame, as published
+#[derive(Debug, PartialEq, Eq)]
+struct Chunk<'a> {
+    // the publisher of the chunk, telling it apart from the ones of other publishers
+    stream: u64,
+    seq: u64,
+    frame: u64,
+    index: u32,
+    count: u32,
+    keyframe: bool,
+    data: &'a [u8],
+}
+
+impl<'a> Chunk<'a> {
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(HEADER_SIZE + self.data.len());
+        buf.extend_from_slice(&self.stream.to_le_bytes());
+        buf.extend_from_slice(&self.seq.to_le_bytes());
+        buf.extend_from_slice(&self.frame.to_le_bytes());
+        buf.extend_from_slice(&self.index.to_le_bytes());
+        buf.extend_from_slice(&self.count.to_le_bytes());
+        buf.push(if self.keyframe { FLAG_KEYFRAME } else { 0 });
+        buf.extend_from_slice(self.data);
+        buf
+    }
+
+    fn decode(buf: &'a [u8]) -> ZResult<Chunk<'a>> {
+        if buf.len() < HEADER_SIZE {
+            bail!("Invalid stream chunk: {} bytes", buf.len());
+        }
+        let u64_at = |i: usize| u64::from_le_bytes(buf[i..i + 8].try_into().unwrap());
+        let u32_at = |i: usize| u32::from_le_bytes(buf[i..i + 4].try_into().unwrap());
+        let chunk = Chunk {
+            stream: u64_at(0),
+            seq: u64_at(8),
+            frame: u64_at(16),
+            index: u32_at(24),
+            count: u32_at(28),
+            keyframe: buf[32] & FLAG_KEYFRAME != 0,
+            data: &buf[HEADER_SIZE..],
+        };
+        if chunk.index >= chunk.count {
+            bail!(
+                "Invalid stream chunk: index {} of {}",
+                chunk.index,
+                chunk.count
+            );
+        }
+        Ok(chunk)
+    }
+}
+
+/// The builder of [`StreamPublisher`].
+pub struct StreamPublisherBuilder {
+    session: Arc<Session>,
+    key_expr: ZResult<KeyExpr<'static>>,
+    chunk_size: usize,
+    queue_size: usize,
+}
+
+impl StreamPublisherBuilder {
+    pub fn new<'b, TryIntoKeyExpr>(session: Arc<Session>, key_expr: TryIntoKeyExpr) -> Self
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+    {
+        StreamPublisherBuilder {
+            session,
+            key_expr: key_expr
+                .try_into()
+                .map_err(Into::into)
+                .map(KeyExpr::into_owned),
+            chunk_size: STREAM_DEFAULT_CHUNK_SIZE,
+            queue_size: STREAM_DEFAULT_QUEUE_SIZE,
+        }
+    }
+
+    /// Change the maximum size of the chunks the frames are split into.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Change the number of frames queued while the network can't keep up, before dropping some.
+    pub fn queue_size(mut self, queue_size: usize) -> Self {
+        self.queue_size = queue_size;
+        self
+    }
+}
+
+impl Resolvable for StreamPublisherBuilder {
+    type To = ZResult<StreamPublisher>;
+}
+
+impl SyncResolve for StreamPublisherBuilder {
+    fn res_sync(self) -> <Self as Resolvable>::To {
+        StreamPublisher::new(self)
+    }
+}
+
+impl AsyncResolve for StreamPublisherBuilder {
+    type Future = Ready<Self::To>;
+
+    fn res_async(self) -> Self::Future {
+        std::future::ready(self.res_sync())
+    }
+}
+
+/// Publishes a continuous stream of frames (e.g. a camera feed), split in numbered chunks.
+///
+/// The frames are queued, then published with a blocking congestion control from a dedicated
+/// thread, not to block the executor of the application. When congestion
+/// fills the queue, whole frames are dropped rather than arbitrary chunks: the frames following
+/// a dropped one are dropped until the next keyframe, and a keyframe replaces the frames still
+/// queued, which only delay it. A [`StreamSubscriber`] reassembles the frames.
+///
+/// # Examples
+/// ```no_run
+/// # async_std::task::block_on(async {
+/// use zenoh::prelude::r#async::*;
+/// use zenoh_ext::StreamPublisherBuilder;
+///
+/// let session = zenoh::open(config::peer()).res().await.unwrap().into_arc();
+/// let publisher = StreamPublisherBuilder::new(session, "robot/1/camera")
+///     .res()
+///     .await
+///     .unwrap();
+/// # let (encoded, is_keyframe) = (vec![0u8; 100_000], true);
+/// publisher.send(encoded, is_keyframe);
+/// # })
+/// ```
+pub struct StreamPublisher {
+    sender: Sender<StreamFrame>,
+    // to drain the queue when a keyframe replaces the frames it holds
+    receiver: Receiver<StreamFrame>,
+    skipping: AtomicBool,
+    frames: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl StreamPublisher {
+    fn new(builder: StreamPublisherBuilder) -> ZResult<StreamPublisher> {
+        let StreamPublisherBuilder {
+            session,
+            key_expr,
+            chunk_size,
+            queue_size,
+        } = builder;
+        let key_expr = key_expr?;
+        if chunk_size == 0 || queue_size == 0 {
+            bail!("Invalid stream publisher: the chunk and queue sizes must be strictly positive");
+        }
+
+        let mut hasher = RandomState::new().build_hasher();
+        session.zid().hash(&mut hasher);
+        let stream = hasher.finish();
+
+        let (sender, receiver) = flume::bounded::<StreamFrame>(queue_size);
+        let frames = receiver.clone();
+        std::thread::spawn(move || {
+            let mut seq = 0u64;
+            while let Ok(frame) = frames.recv() {
+                let chunks: Vec<&[u8]> = if frame.data.is_empty() {
+                    vec![&frame.data[..]]
+                } else {
+                    frame.data.chunks(chunk_size).collect()
+                };
+                let count = chunks.len() as u32;
+                for (index, data) in chunks.into_iter().enumerate() {
+                    let chunk = Chunk {
+                        stream,
+                        seq,
+                        frame: frame.number,
+                        index: index as u32,
+                        count,
+                        keyframe: frame.keyframe,
+                        data,
+                    };
+                    seq += 1;
+                    if let Err(e) = session
+                        .put(&key_expr, chunk.encode())
+                        .congestion_control(CongestionControl::Block)
+                        .res_sync()
+                    {
+                        log::warn!("StreamPublisher on {}: {}", key_expr, e);
+                    }
+                }
+            }
+        });
+
+        Ok(StreamPublisher {
+            sender,
+            receiver,
+            skipping: AtomicBool::new(false),
+            frames: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+        })
+    }
+
+    /// Queue a frame to publish, returning whether it was queued rather than dropped.
+    pub fn send<IntoData: Into<Vec<u8>>>(&self, data: IntoData, keyframe: bool) -> bool {
+        let frame = StreamFrame {
+            number: self.frames.fetch_add(1, Ordering::Relaxed),
+            keyframe,
+            data: data.into(),
+        };
+        if !keyframe && self.skipping.load(Ordering::Relaxed) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        match self.sender.try_send(frame) {
+            Ok(()) => {
+                self.skipping.store(false, Ordering::Relaxed);
+                true
+            }
+            Err(TrySendError::Full(frame)) if frame.keyframe => {
+                let obsolete = self.receiver.drain().count() as u64;
+                self.dropped.fetch_add(obsolete, Ordering::Relaxed);
+                self.skipping.store(false, Ordering::Relaxed);
+                self.send_keyframe(frame)
+            }
+            Err(TrySendError::Full(_)) => {
+                // the frames depending on this one are useless until the next keyframe
+                self.skipping.store(true, Ordering::Relaxed);
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+
+    fn send_keyframe(&self, frame: StreamFrame) -> bool {
+        match self.sender.try_send(frame) {
+            Ok(()) => true,
+            Err(_) => {
+                self.skipping.store(true, Ordering::Relaxed);
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                false
+            }
+        }
+    }
+
+    /// The number of frames dropped because of congestion.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+// The frame being reassembled
+struct Partial {
+    number: u64,
+    keyframe: bool,
+    data: Vec<u8>,
+}
+
+// Reassembles the frames from their chunks, dropping the incomplete ones and the ones depending on them
+#[derive(Default)]
+struct Reassembler {
+    next_seq: Option<u64>,
+    partial: Option<Partial>,
+    // whether a frame was lost since the last keyframe
+    broken: bool,
+}
+
+impl Reassembler {
+    fn push(&mut self, chunk: Chunk) -> Option<StreamFrame> {
+        if self.next_seq.map_or(false, |next| next != chunk.seq) {
+            self.partial = None;
+            self.broken = true;
+        }
+        self.next_seq = Some(chunk.seq.wrapping_add(1));
+
+        if chunk.index == 0 {
+            self.partial = None;
+            if self.broken && !chunk.keyframe {
+                return None;
+            }
+            self.broken = false;
+            self.partial = Some(Partial {
+                number: chunk.frame,
+                keyframe: chunk.keyframe,
+                data: Vec::new(),
+            });
+        }
+        match &mut self.partial {
+            Some(partial) if partial.number == chunk.frame => {
+                partial.data.extend_from_slice(chunk.data)
+            }
+            _ => return None,
+        }
+        if chunk.index + 1 == chunk.count {
+            return self.partial.take().map(|partial| StreamFrame {
+                number: partial.number,
+                keyframe: partial.keyframe,
+                data: partial.data,
+            });
+        }
+        None
+    }
+}
+
+/// The builder of [`StreamSubscriber`].
+pub struct StreamSubscriberBuilder {
+    session: Arc<Session>,
+    key_expr: ZResult<KeyExpr<'static>>,
+}
+
+impl StreamSubscriberBuilder {
+    pub fn new<'b, TryIntoKeyExpr>(session: Arc<Session>, key_expr: TryIntoKeyExpr) -> Self
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+    {
+        StreamSubscriberBuilder {
+            session,
+            key_expr: key_expr
+                .try_into()
+                .map_err(Into::into)
+                .map(KeyExpr::into_owned),
+        }
+    }
+}
+
+impl Resolvable for StreamSubscriberBuilder {
+    type To = ZResult<StreamSubscriber>;
+}
+
+impl SyncResolve for StreamSubscriberBuilder {
+    fn res_sync(self) -> <Self as Resolvable>::To {
+        StreamSubscriber::new(self)
+    }
+}
+
+impl AsyncResolve for StreamSubscriberBuilder {
+    type Future = Ready<Self::To>;
+
+    fn res_async(self) -> Self::Future {
+        std::future::ready(self.res_sync())
+    }
+}
+
+/// Receives the frames of a [`StreamPublisher`], reassembled from their chunks.
+///
+/// The frames of each publisher are reassembled apart, e.g. when subscribing to several streams
+/// through a wildcard key expression. When a chunk is lost, its frame and the following ones
+/// are dropped until the next keyframe, as they can't be decoded. The frames are received from the [`flume::Receiver`] it dereferences to.
+pub struct StreamSubscriber {
+    _subscriber: Subscriber<'static, ()>,
+    receiver: Receiver<StreamFrame>,
+}
+
+impl StreamSubscriber {
+    fn new(builder: StreamSubscriberBuilder) -> ZResult<StreamSubscriber> {
+        let StreamSubscriberBuilder { session, key_expr } = builder;
+        let key_expr = key_expr?;
+        let (sender, receiver) = flume::unbounded();
+        let reassemblers = std::sync::Mutex::new(HashMap::<u64, Reassembler>::new());
+        let subscriber = session
+            .declare_subscriber(&key_expr)
+            .reliable()
+            .callback(move |sample| {
+                let payload = sample.value.payload.contiguous();
+                let frame = match Chunk::decode(&payload) {
+                    Ok(chunk) => reassemblers
+                        .lock()
+                        .unwrap()
+                        .entry(chunk.stream)
+                        .or_default()
+                        .push(chunk),
+                    Err(e) => {
+                        log::warn!("StreamSubscriber on {}: {}", sample.key_expr, e);
+                        None
+                    }
+                };
+                if let Some(frame) = frame {
+                    let _ = sender.send(frame);
+                }
+            })
+            .res_sync()?;
+        Ok(StreamSubscriber {
+            _subscriber: subscriber,
+            receiver,
+        })
+    }
+}
+
+impl Deref for StreamSubscriber {
+    type Target = Receiver<StreamFrame>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.receiver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(seq: u64, frame: u64, index: u32, count: u32, keyframe: bool) -> Vec<u8> {
+        let data = [frame as u8, index as u8];
+        Chunk {
+            stream: 1,
+            seq,
+            frame,
+            index,
+            count,
+            keyframe,
+            data: &data,
+        }
+        .encode()
+    }
+
+    fn push(reassembler: &mut Reassembler, buf: &[u8]) -> Option<u64> {
+        reassembler
+            .push(Chunk::decode(buf).unwrap())
+            .map(|frame| frame.number)
+    }
+
+    #[test]
+    fn chunk_codec() {
+        let data = b"frame".to_vec();
+        let chunk = Chunk {
+            stream: 42,
+            seq: 7,
+            frame: 3,
+            index: 1,
+            count: 2,
+            keyframe: true,
+            data: &data,
+        };
+        let buf = chunk.encode();
+        assert_eq!(buf.len(), HEADER_SIZE + data.len());
+        assert_eq!(Chunk::decode(&buf).unwrap(), chunk);
+
+        assert!(Chunk::decode(&buf[..HEADER_SIZE - 1]).is_err());
+        let invalid = Chunk { index: 2, ..chunk };
+        assert!(Chunk::decode(&invalid.encode()).is_err());
+    }
+
+    #[test]
+    fn reassembler_frames() {
+        let mut reassembler = Reassembler::default();
+        assert_eq!(push(&mut reassembler, &chunk(0, 0, 0, 2, true)), None);
+        let frame = reassembler
+            .push(Chunk::decode(&chunk(1, 0, 1, 2, true)).unwrap())
+            .unwrap();
+        assert_eq!(
+            frame,
+            StreamFrame {
+                number: 0,
+                keyframe: true,
+                data: vec![0, 0, 0, 1],
+            }
+        );
+        assert_eq!(push(&mut reassembler, &chunk(2, 1, 0, 1, false)), Some(1));
+    }
+
+    #[test]
+    fn reassembler_losses() {
+        let mut reassembler = Reassembler::default();
+        assert_eq!(push(&mut reassembler, &chunk(0, 0, 0, 1, true)), Some(0));
+        // the chunk 1 of the frame 1 is lost: the frame 1 and 2 are dropped
+        assert_eq!(push(&mut reassembler, &chunk(1, 1, 0, 2, false)), None);
+        assert_eq!(push(&mut reassembler, &chunk(3, 2, 0, 1, false)), None);
+        // until the next keyframe
+        assert_eq!(push(&mut reassembler, &chunk(4, 3, 0, 2, true)), None);
+        assert_eq!(push(&mut reassembler, &chunk(5, 3, 1, 2, true)), Some(3));
+        assert_eq!(push(&mut reassembler, &chunk(6, 4, 0, 1, false)), Some(4));
+    }
+}
@@ -0,0 +1,305 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use async_std::task;
+use std::convert::{TryFrom, TryInto};
+use std::future::Ready;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use zenoh::prelude::r#async::*;
+use zenoh::queryable::Queryable;
+use zenoh::time::NTP64;
+use zenoh::Session;
+use zenoh_core::{AsyncResolve, Resolvable, SyncResolve};
+use zenoh_result::{bail, ZResult};
+
+/// The default period between two synchronization rounds of a [`TimeSync`].
+pub const TIME_SYNC_DEFAULT_PERIOD: Duration = Duration::from_secs(10);
+/// The default number of queries sent to the [`TimeServer`] at each synchronization round.
+pub const TIME_SYNC_DEFAULT_SAMPLES: usize = 8;
+
+// the offset used by `synchronized_clock`, in nanoseconds
+static GLOBAL_OFFSET: AtomicI64 = AtomicI64::new(0);
+const UNSYNCHRONIZED: i64 = -1;
+
+fn system_nanos() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as i64
+}
+
+fn to_ntp64(nanos: i64) -> NTP64 {
+    NTP64::from(Duration::from_nanos(nanos.max(0) as u64))
+}
+
+/// The system clock corrected with the offset of the [`TimeSync`] declared
+/// with [`TimeSyncBuilder::global`], or the system clock until it synchronizes.
+///
+/// Its signature allows to use it as the clock of an HLC, e.g. `HLCBuilder::new().with_clock(synchronized_clock)`.
+pub fn synchronized_clock() -> NTP64 {
+    to_ntp64(system_nanos() + GLOBAL_OFFSET.load(Ordering::Relaxed))
+}
+
+/// Answers the queries of the [`TimeSync`] clients with the time of its system clock.
+///
+/// It is typically declared on a router whose host is synchronized by other means (e.g. NTP or GPS).
+/// The time server stops when dropped.
+pub struct TimeServer {
+    _queryable: Queryable<'static, ()>,
+}
+
+impl TimeServer {
+    pub fn declare<'b, TryIntoKeyExpr>(
+        session: Arc<Session>,
+        key_expr: TryIntoKeyExpr,
+    ) -> ZResult<TimeServer>
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+    {
+        let key_expr: KeyExpr<'static> = key_expr.try_into().map_err(Into::into)?.into_owned();
+        let queryable = session
+            .declare_queryable(key_expr)
+            .callback(|query| {
+                let sample = Sample::new(query.key_expr().clone(), system_nanos());
+                if let Err(e) = query.reply(Ok(sample)).res_sync() {
+                    log::warn!("TimeServer failed to reply: {}", e);
+                }
+            })
+            .res_sync()?;
+        Ok(TimeServer {
+            _queryable: queryable,
+        })
+    }
+}
+
+/// The builder of [`TimeSync`].
+pub struct TimeSyncBuilder {
+    session: Arc<Session>,
+    key_expr: ZResult<OwnedKeyExpr>,
+    period: Duration,
+    samples: usize,
+    global: bool,
+}
+
+impl TimeSyncBuilder {
+    pub fn new<'b, TryIntoKeyExpr>(session: Arc<Session>, key_expr: TryIntoKeyExpr) -> Self
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+    {
+        TimeSyncBuilder {
+            session,
+            key_expr: key_expr.try_into().map_err(Into::into).map(Into::into),
+            period: TIME_SYNC_DEFAULT_PERIOD,
+            samples: TIME_SYNC_DEFAULT_SAMPLES,
+            global: false,
+        }
+    }
+
+    /// Change the period between two synchronization rounds.
+    pub fn period(mut self, period: Duration) -> Self {
+        self.period = period;
+        self
+    }
+
+    /// Change the number of queries sent at each synchronization round.
+    ///
+    /// Only the measurement with the shortest round trip is kept,
+    /// as it is the least affected by the asymmetry of the network delays.
+    pub fn samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// Make this [`TimeSync`] drive the [`synchronized_clock`].
+    pub fn global(mut self, global: bool) -> Self {
+        self.global = global;
+        self
+    }
+}
+
+impl Resolvable for TimeSyncBuilder {
+    type To = ZResult<TimeSync>;
+}
+
+impl SyncResolve for TimeSyncBuilder {
+    fn res_sync(self) -> <Self as Resolvable>::To {
+        TimeSync::new(self)
+    }
+}
+
+impl AsyncResolve for TimeSyncBuilder {
+    type Future = Ready<Self::To>;
+
+    fn res_async(self) -> Self::Future {
+        std::future::ready(self.res_sync())
+    }
+}
+
+struct SyncState {
+    offset: AtomicI64,
+    rtt: AtomicI64,
+    global: bool,
+}
+
+/// Estimates the offset between the local clock and the clock of a [`TimeServer`],
+/// for the devices that can't rely on NTP (e.g. air-gapped robot fleets).
+///
+/// At each round, several queries are sent to the time server, and the offset is estimated from
+/// the measurement with the shortest round trip, assuming symmetric network delays.
+/// The synchronization stops when dropped.
+///
+/// # Examples
+/// ```no_run
+/// # async_std::task::block_on(async {
+/// use zenoh::prelude::r#async::*;
+/// use zenoh_ext::TimeSyncBuilder;
+///
+/// let session = zenoh::open(config::client()).res().await.unwrap().into_arc();
+/// let sync = TimeSyncBuilder::new(session, "fleet/time")
+///     .global(true)
+///     .res()
+///     .await
+///     .unwrap();
+/// async_std::task::sleep(std::time::Duration::from_secs(1)).await;
+/// println!("offset: {:?} ns, now: {}", sync.offset(), sync.now());
+/// # })
+/// ```
+pub struct TimeSync {
+    state: Arc<SyncState>,
+}
+
+impl TimeSync {
+    fn new(builder: TimeSyncBuilder) -> ZResult<TimeSync> {
+        let TimeSyncBuilder {
+            session,
+            key_expr,
+            period,
+            samples,
+            global,
+        } = builder;
+        let key_expr = key_expr?;
+        if samples == 0 {
+            bail!("Invalid time synchronization: the number of samples must be strictly positive");
+        }
+
+        let state = Arc::new(SyncState {
+            offset: AtomicI64::new(0),
+            rtt: AtomicI64::new(UNSYNCHRONIZED),
+            global,
+        });
+        let weak = Arc::downgrade(&state);
+        task::spawn(async move {
+            while let Some(state) = Weak::upgrade(&weak) {
+                match measure(&session, &key_expr, samples, period).await {
+                    Some((offset, rtt)) => {
+                        log::debug!(
+                            "TimeSync on {}: offset={}ns rtt={}ns",
+                            key_expr,
+                            offset,
+                            rtt
+                        );
+                        state.offset.store(offset, Ordering::Relaxed);
+                        state.rtt.store(rtt, Ordering::Relaxed);
+                        if state.global {
+                            GLOBAL_OFFSET.store(offset, Ordering::Relaxed);
+                        }
+                    }
+                    None => log::warn!("TimeSync on {}: no time server replied", key_expr),
+                }
+                drop(state);
+                task::sleep(period).await;
+            }
+            log::debug!("TimeSync on {} stopped", key_expr);
+        });
+
+        Ok(TimeSync { state })
+    }
+
+    /// Whether at least one synchronization round succeeded.
+    pub fn is_synchronized(&self) -> bool {
+        self.state.rtt.load(Ordering::Relaxed) != UNSYNCHRONIZED
+    }
+
+    /// The offset to add to the local clock to get the clock of the time server, in nanoseconds.
+    pub fn offset(&self) -> Option<i64> {
+        self.is_synchronized()
+            .then(|| self.state.offset.load(Ordering::Relaxed))
+    }
+
+    /// The round trip of the measurement the current offset was estimated from.
+    pub fn round_trip(&self) -> Option<Duration> {
+        match self.state.rtt.load(Ordering::Relaxed) {
+            UNSYNCHRONIZED => None,
+            rtt => Some(Duration::from_nanos(rtt as u64)),
+        }
+    }
+
+    /// The local clock corrected with the current offset.
+    pub fn now(&self) -> NTP64 {
+        to_ntp64(system_nanos() + self.state.offset.load(Ordering::Relaxed))
+    }
+}
+
+impl Drop for TimeSync {
+    fn drop(&mut self) {
+        if self.state.global && self.is_synchronized() {
+            GLOBAL_OFFSET.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+// Returns the (offset, rtt) of the measurement with the shortest round trip.
+async fn measure(
+    session: &Session,
+    key_expr: &OwnedKeyExpr,
+    samples: usize,
+    timeout: Duration,
+) -> Option<(i64, i64)> {
+    let mut best: Option<(i64, i64)> = None;
+    for _ in 0..samples {
+        let sent = system_nanos();
+        let replies = match session
+            .get(key_expr)
+            .target(QueryTarget::BestMatching)
+            .timeout(timeout)
+            .res_async()
+            .await
+        {
+            Ok(replies) => replies,
+            Err(e) => {
+                log::warn!("TimeSync on {}: {}", key_expr, e);
+                return best;
+            }
+        };
+        let server = match replies.recv_async().await {
+            Ok(reply) => match reply.sample {
+                Ok(sample) => i64::try_from(&sample.value).ok(),
+                Err(_) => None,
+            },
+            Err(_) => None,
+        };
+        let received = system_nanos();
+        if let Some(server) = server {
+            let rtt = received - sent;
+            let offset = server - (sent + rtt / 2);
+            if best.map_or(true, |(_, best_rtt)| rtt < best_rtt) {
+                best = Some((offset, rtt));
+            }
+        }
+    }
+    best
+}
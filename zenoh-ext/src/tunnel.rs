@@ -0,0 +1,439 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use async_std::net::{Shutdown, TcpListener, TcpStream};
+use async_std::task;
+use futures::{select, AsyncReadExt, AsyncWriteExt, FutureExt};
+use std::convert::{TryFrom, TryInto};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use zenoh::prelude::r#async::*;
+use zenoh::queryable::{Query, Queryable};
+use zenoh::subscriber::Subscriber;
+use zenoh::Session;
+use zenoh_core::SyncResolve;
+use zenoh_result::{bail, zerror, ZResult};
+
+/// The size of the buffer the bytes of a tunneled stream are read into,
+/// i.e. the maximum payload of the samples carrying them.
+pub const TUNNEL_BUFFER_SIZE: usize = 64 * 1024;
+
+/// The number of bytes a side of a tunnel sends without them being acknowledged by the other side,
+/// the sending being suspended beyond.
+pub const TUNNEL_WINDOW: u64 = 1024 * 1024;
+
+/// The selector parameter carrying the target requested by a forwarding [`TunnelEntrance`].
+pub const TUNNEL_TARGET_PARAMETER: &str = "target";
+
+// The number of tunnels opened by this process, to name them uniquely
+static TUNNELS: AtomicU64 = AtomicU64::new(0);
+
+// Where an exit connects its tunnels to
+enum Target {
+    Fixed(SocketAddr),
+    Allowed(Vec<String>),
+}
+
+impl Target {
+    async fn connect(&self, requested: Option<&str>) -> ZResult<TcpStream> {
+        let connected = match (self, requested) {
+            (Target::Fixed(addr), None) => TcpStream::connect(*addr).await,
+            (Target::Fixed(_), Some(_)) => {
+                bail!("This tunnel exit doesn't forward to requested targets")
+            }
+            (Target::Allowed(allowed), Some(target)) if allowed.iter().any(|a| a == target) => {
+                TcpStream::connect(target).await
+            }
+            (Target::Allowed(_), Some(target)) => bail!("Forbidden target: {}", target),
+            (Target::Allowed(_), None) => bail!("Missing target"),
+        };
+        connected.map_err(|e| zerror!("Unable to connect to the target: {}", e).into())
+    }
+}
+
+/// The exit of the tunnels opened on a key expression: connects each of them to a TCP target.
+///
+/// A [`TunnelEntrance`] opens a tunnel with a query on the key expression, carrying the name
+/// of the tunnel. The bytes sent through the tunnel `<name>` are then published on
+/// `<key_expr>/<name>/up`, the ones received on `<key_expr>/<name>/down`, and its end is
+/// signaled by a deletion of those keys. Each side acknowledges the bytes it received on
+/// `<key_expr>/<name>/<up|down>/ack`, not to be sent more than [`TUNNEL_WINDOW`] bytes ahead.
+///
+/// Tunneling a TCP link of zenoh allows a device that can only reach one router to expose the
+/// zenoh network behind it: the exit is declared on the device with the endpoint of a router of
+/// the nested network as `target`, and the nodes of the outer network connect to the entrance.
+/// The exit stops accepting tunnels when dropped.
+pub struct TunnelExit {
+    _queryable: Queryable<'static, ()>,
+}
+
+impl TunnelExit {
+    /// Declares an exit connecting all its tunnels to `target`.
+    pub fn declare<'b, TryIntoKeyExpr>(
+        session: Arc<Session>,
+        key_expr: TryIntoKeyExpr,
+        target: SocketAddr,
+    ) -> ZResult<TunnelExit>
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+    {
+        TunnelExit::new(session, key_expr, Target::Fixed(target))
+    }
+
+    /// Declares an exit connecting each tunnel to the target its entrance requests
+    /// (see [`TunnelEntrance::bind_forwarding`]), among the `allowed` ones (as `<host>:<port>`).
+    ///
+    /// This allows the remote maintenance of the hosts reachable by this exit (e.g. with SSH or RDP),
+    /// across a zenoh network.
+    pub fn declare_forwarding<'b, TryIntoKeyExpr, I>(
+        session: Arc<Session>,
+        key_expr: TryIntoKeyExpr,
+        allowed: I,
+    ) -> ZResult<TunnelExit>
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let allowed = allowed.into_iter().map(Into::into).collect();
+        TunnelExit::new(session, key_expr, Target::Allowed(allowed))
+    }
+
+    fn new<'b, TryIntoKeyExpr>(
+        session: Arc<Session>,
+        key_expr: TryIntoKeyExpr,
+        target: Target,
+    ) -> ZResult<TunnelExit>
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+    {
+        let key_expr: KeyExpr<'static> = key_expr.try_into().map_err(Into::into)?.into_owned();
+        let target = Arc::new(target);
+        let queryable = session
+            .declare_queryable(key_expr.clone())
+            .callback({
+                let session = session.clone();
+                move |query| {
+                    task::spawn(exit(
+                        session.clone(),
+                        key_expr.clone(),
+                        target.clone(),
+                        query,
+                    ));
+                }
+            })
+            .res_sync()?;
+        Ok(TunnelExit {
+            _queryable: queryable,
+        })
+    }
+}
+
+// The keys and subscribers of a side of a tunnel
+struct TunnelSide {
+    // the key the bytes are sent on
+    tx: KeyExpr<'static>,
+    // the key the bytes received are acknowledged on
+    rx_ack: KeyExpr<'static>,
+    rx: Subscriber<'static, flume::Receiver<Sample>>,
+    tx_ack: Subscriber<'static, flume::Receiver<Sample>>,
+}
+
+impl TunnelSide {
+    async fn declare(
+        session: &Arc<Session>,
+        key_expr: &KeyExpr<'static>,
+        name: &str,
+        tx: &str,
+        rx: &str,
+    ) -> ZResult<TunnelSide> {
+        let subscriber = |key_expr: KeyExpr<'static>| {
+            session.declare_subscriber(key_expr).reliable().res_async()
+        };
+        Ok(TunnelSide {
+            tx: key_expr.join(&format!("{name}/{tx}"))?,
+            rx_ack: key_expr.join(&format!("{name}/{rx}/ack"))?,
+            rx: subscriber(key_expr.join(&format!("{name}/{rx}"))?).await?,
+            tx_ack: subscriber(key_expr.join(&format!("{name}/{tx}/ack"))?).await?,
+        })
+    }
+}
+
+// Opens the tunnel requested by `query`, returning its name, its side and the connection to its target.
+async fn open_exit(
+    session: &Arc<Session>,
+    key_expr: &KeyExpr<'static>,
+    target: &Target,
+    query: &Query,
+) -> ZResult<(String, TunnelSide, TcpStream)> {
+    let name = match query.value() {
+        Some(value) => String::from_utf8(value.payload.contiguous().to_vec())?,
+        None => bail!("Missing tunnel name"),
+    };
+    if name.is_empty() || name.contains(['/', '*', '$']) {
+        bail!("Invalid tunnel name: '{}'", name);
+    }
+    let [requested] = query
+        .parameters()
+        .get_parameters([TUNNEL_TARGET_PARAMETER])?;
+    let side = TunnelSide::declare(session, key_expr, &name, "down", "up").await?;
+    let stream = target.connect(requested.as_deref()).await?;
+    Ok((name, side, stream))
+}
+
+async fn exit(
+    session: Arc<Session>,
+    key_expr: KeyExpr<'static>,
+    target: Arc<Target>,
+    query: Query,
+) {
+    match open_exit(&session, &key_expr, &target, &query).await {
+        Ok((name, side, stream)) => {
+            if let Err(e) = query
+                .reply(Ok(Sample::new(key_expr.clone(), name.clone())))
+                .res_async()
+                .await
+            {
+                log::warn!("Tunnel {} on {}: {}", name, key_expr, e);
+                return;
+            }
+            log::debug!("Tunnel {} on {} opened", name, key_expr);
+            relay(&session, stream, side).await;
+            log::debug!("Tunnel {} on {} closed", name, key_expr);
+        }
+        Err(e) => {
+            log::warn!("Unable to open tunnel on {}: {}", key_expr, e);
+            let _ = query.reply(Err(e.to_string().into())).res_async().await;
+        }
+    }
+}
+
+/// The entrance of the tunnels to a [`TunnelExit`]: listens on a local TCP address and opens a
+/// tunnel for each connection accepted on it.
+///
+/// # Examples
+/// ```no_run
+/// # async_std::task::block_on(async {
+/// use zenoh::prelude::r#async::*;
+/// use zenoh_ext::TunnelEntrance;
+///
+/// let session = zenoh::open(config::peer()).res().await.unwrap().into_arc();
+/// // The nodes connecting to tcp/127.0.0.1:7448 join the network behind the exit
+/// let entrance = TunnelEntrance::bind(session.clone(), "site/1/tunnel", "127.0.0.1:7448".parse().unwrap())
+///     .await
+///     .unwrap();
+/// // `ssh -p 2222 127.0.0.1` reaches the SSH server of 10.0.0.5, if allowed by the exit
+/// let ssh = TunnelEntrance::bind_forwarding(session, "site/1/forward", "127.0.0.1:2222".parse().unwrap(), "10.0.0.5:22")
+///     .await
+///     .unwrap();
+/// # })
+/// ```
+///
+/// The entrance stops accepting connections when dropped, the tunnels already opened being
+/// closed with their connection.
+pub struct TunnelEntrance {
+    local_addr: SocketAddr,
+    _stop: flume::Sender<()>,
+}
+
+impl TunnelEntrance {
+    /// Binds an entrance to the exits connecting all their tunnels to the same target.
+    pub async fn bind<'b, TryIntoKeyExpr>(
+        session: Arc<Session>,
+        key_expr: TryIntoKeyExpr,
+        local: SocketAddr,
+    ) -> ZResult<TunnelEntrance>
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+    {
+        TunnelEntrance::new(session, key_expr, local, None).await
+    }
+
+    /// Binds an entrance forwarding its connections to `target` (as `<host>:<port>`), through an exit
+    /// declared with [`TunnelExit::declare_forwarding`] allowing it.
+    pub async fn bind_forwarding<'b, TryIntoKeyExpr, IntoTarget>(
+        session: Arc<Session>,
+        key_expr: TryIntoKeyExpr,
+        local: SocketAddr,
+        target: IntoTarget,
+    ) -> ZResult<TunnelEntrance>
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+        IntoTarget: Into<String>,
+    {
+        TunnelEntrance::new(session, key_expr, local, Some(target.into())).await
+    }
+
+    async fn new<'b, TryIntoKeyExpr>(
+        session: Arc<Session>,
+        key_expr: TryIntoKeyExpr,
+        local: SocketAddr,
+        target: Option<String>,
+    ) -> ZResult<TunnelEntrance>
+    where
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+    {
+        let key_expr: KeyExpr<'static> = key_expr.try_into().map_err(Into::into)?.into_owned();
+        let selector = match &target {
+            Some(target) => Selector::from(key_expr.clone())
+                .with_parameters(&format!("{TUNNEL_TARGET_PARAMETER}={target}"))
+                .to_string(),
+            None => key_expr.to_string(),
+        };
+        let selector: Selector<'static> = Selector::try_from(selector)?;
+        let listener = TcpListener::bind(local)
+            .await
+            .map_err(|e| zerror!("Unable to listen on {}: {}", local, e))?;
+        let local_addr = listener.local_addr()?;
+        let (stop, stopped) = flume::bounded::<()>(1);
+        task::spawn(async move {
+            loop {
+                select! {
+                    accepted = listener.accept().fuse() => match accepted {
+                        Ok((stream, peer)) => {
+                            log::debug!("Tunnel connection from {} on {}", peer, local_addr);
+                            task::spawn(entrance(session.clone(), selector.clone(), stream));
+                        }
+                        Err(e) => log::warn!("Tunnel entrance on {}: {}", local_addr, e),
+                    },
+                    _ = stopped.recv_async().fuse() => break,
+                }
+            }
+        });
+        Ok(TunnelEntrance {
+            local_addr,
+            _stop: stop,
+        })
+    }
+
+    /// The address the entrance listens on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+// Opens the tunnel `name` to an exit, returning its side.
+async fn open_entrance(
+    session: &Arc<Session>,
+    selector: &Selector<'static>,
+    name: &str,
+) -> ZResult<TunnelSide> {
+    // Subscribed before opening the tunnel, not to miss the first bytes sent by the exit
+    let side = TunnelSide::declare(session, &selector.key_expr, name, "up", "down").await?;
+    let replies = session
+        .get(selector)
+        .with_value(name)
+        .target(QueryTarget::BestMatching)
+        .res_async()
+        .await?;
+    match replies.recv_async().await {
+        Ok(reply) => match reply.sample {
+            Ok(_) => Ok(side),
+            Err(e) => bail!("{}", e),
+        },
+        Err(_) => bail!("No tunnel exit replied"),
+    }
+}
+
+async fn entrance(session: Arc<Session>, selector: Selector<'static>, stream: TcpStream) {
+    let name = format!(
+        "{}-{}",
+        session.zid(),
+        TUNNELS.fetch_add(1, Ordering::Relaxed)
+    );
+    match open_entrance(&session, &selector, &name).await {
+        Ok(side) => {
+            log::debug!("Tunnel {} on {} opened", name, selector);
+            relay(&session, stream, side).await;
+            log::debug!("Tunnel {} on {} closed", name, selector);
+        }
+        Err(e) => log::warn!("Unable to open tunnel {} on {}: {}", name, selector, e),
+    }
+}
+
+// Publishes the bytes read from `stream`, and writes the ones received to it
+async fn relay(session: &Session, stream: TcpStream, side: TunnelSide) {
+    let TunnelSide {
+        tx,
+        rx_ack,
+        rx,
+        tx_ack,
+    } = side;
+    let mut reader = stream.clone();
+    let mut writer = stream;
+    let send = async {
+        let mut buf = vec![0u8; TUNNEL_BUFFER_SIZE];
+        let (mut sent, mut acked) = (0u64, 0u64);
+        'send: loop {
+            while let Ok(ack) = tx_ack.try_recv() {
+                acked = acked.max(ack_of(&ack));
+            }
+            while sent.saturating_sub(acked) >= TUNNEL_WINDOW {
+                match tx_ack.recv_async().await {
+                    Ok(ack) => acked = acked.max(ack_of(&ack)),
+                    Err(_) => break 'send,
+                }
+            }
+            match reader.read(&mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    let put = session
+                        .put(&tx, buf[..n].to_vec())
+                        .congestion_control(CongestionControl::Block)
+                        .res_async()
+                        .await;
+                    if put.is_err() {
+                        break;
+                    }
+                    sent += n as u64;
+                }
+            }
+        }
+        let _ = session.delete(&tx).res_async().await;
+    };
+    let receive = async {
+        let (mut received, mut acked) = (0u64, 0u64);
+        while let Ok(sample) = rx.recv_async().await {
+            if sample.kind == SampleKind::Delete {
+                break;
+            }
+            let payload = sample.value.payload.contiguous();
+            if writer.write_all(&payload).await.is_err() {
+                break;
+            }
+            received += payload.len() as u64;
+            if received - acked >= TUNNEL_WINDOW / 2 {
+                let _ = session
+                    .put(&rx_ack, received as i64)
+                    .congestion_control(CongestionControl::Block)
+                    .res_async()
+                    .await;
+                acked = received;
+            }
+        }
+        let _ = writer.shutdown(Shutdown::Both);
+    };
+    futures::join!(send, receive);
+}
+
+// The number of bytes acknowledged by `ack`
+fn ack_of(ack: &Sample) -> u64 {
+    i64::try_from(&ack.value).map_or(0, |n| n.max(0) as u64)
+}
@@ -13,7 +13,12 @@
 //
 
 //! Callback handler trait.
+use crate::prelude::KeyExpr;
+use crate::sample::Sample;
 use crate::API_DATA_RECEPTION_CHANNEL_SIZE;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use zenoh_result::ZResult;
 
 /// An alias for `Arc<T>`.
 pub type Dyn<T> = std::sync::Arc<T>;
@@ -80,9 +85,297 @@ impl<T: Send + Sync + 'static> IntoCallbackReceiverPair<'static, T>
     }
 }
 
+/// The storage policy of a buffered channel.
+trait Buffer<T>: Send + 'static {
+    fn push(&mut self, t: T);
+    fn pop(&mut self) -> Option<T>;
+}
+
+/// The receiving side of a channel whose buffer never blocks the sender.
+struct BufferedReceiver<B> {
+    buffer: Arc<Mutex<B>>,
+    // holds a message whenever the buffer may be non empty, disconnected once the callback is dropped
+    signal: flume::Receiver<()>,
+}
+
+impl<B> BufferedReceiver<B> {
+    fn pair<T>(buffer: B) -> (Callback<'static, T>, Self)
+    where
+        B: Buffer<T>,
+        T: Send + 'static,
+    {
+        let buffer = Arc::new(Mutex::new(buffer));
+        let (signal_tx, signal) = flume::bounded(1);
+        let c_buffer = buffer.clone();
+        let callback = Dyn::new(move |t| {
+            zlock!(c_buffer).push(t);
+            let _ = signal_tx.try_send(());
+        });
+        (callback, BufferedReceiver { buffer, signal })
+    }
+
+    fn try_recv<T>(&self) -> ZResult<Option<T>>
+    where
+        B: Buffer<T>,
+    {
+        if let Some(t) = zlock!(self.buffer).pop() {
+            return Ok(Some(t));
+        }
+        if self.signal.is_disconnected() {
+            // the buffer may have been filled right before the disconnection
+            return zlock!(self.buffer)
+                .pop()
+                .map(Some)
+                .ok_or_else(|| zerror!("The channel is disconnected").into());
+        }
+        Ok(None)
+    }
+
+    fn recv<T>(&self) -> ZResult<T>
+    where
+        B: Buffer<T>,
+    {
+        loop {
+            if let Some(t) = zlock!(self.buffer).pop() {
+                return Ok(t);
+            }
+            if self.signal.recv().is_err() {
+                return zlock!(self.buffer)
+                    .pop()
+                    .ok_or_else(|| zerror!("The channel is disconnected").into());
+            }
+        }
+    }
+
+    async fn recv_async<T>(&self) -> ZResult<T>
+    where
+        B: Buffer<T>,
+    {
+        loop {
+            if let Some(t) = zlock!(self.buffer).pop() {
+                return Ok(t);
+            }
+            if self.signal.recv_async().await.is_err() {
+                return zlock!(self.buffer)
+                    .pop()
+                    .ok_or_else(|| zerror!("The channel is disconnected").into());
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Conflation {
+    order: VecDeque<KeyExpr<'static>>,
+    latest: HashMap<KeyExpr<'static>, Sample>,
+}
+
+impl Buffer<Sample> for Conflation {
+    fn push(&mut self, sample: Sample) {
+        match self.latest.get_mut(&sample.key_expr) {
+            Some(latest) => *latest = sample,
+            None => {
+                self.order.push_back(sample.key_expr.clone());
+                self.latest.insert(sample.key_expr.clone(), sample);
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Option<Sample> {
+        let key_expr = self.order.pop_front()?;
+        self.latest.remove(&key_expr)
+    }
+}
+
+/// A handler keeping only the latest [`Sample`] of each key expression until it is received (conflation).
+///
+/// A lagging consumer thus receives the freshest value of every key, in the order in which
+/// the keys were first updated, and the publisher is never blocked.
+///
+/// # Examples
+/// ```no_run
+/// # async_std::task::block_on(async {
+/// use zenoh::prelude::r#async::*;
+/// use zenoh::handlers::ConflatingChannel;
+///
+/// let session = zenoh::open(config::peer()).res().await.unwrap();
+/// let subscriber = session
+///     .declare_subscriber("telemetry/**")
+///     .with(ConflatingChannel)
+///     .res()
+///     .await
+///     .unwrap();
+/// while let Ok(sample) = subscriber.recv_async().await {
+///     println!("{}: {}", sample.key_expr, sample.value);
+/// }
+/// # })
+/// ```
+pub struct ConflatingChannel;
+
+impl IntoCallbackReceiverPair<'static, Sample> for ConflatingChannel {
+    type Receiver = ConflatingChannelHandler;
+
+    fn into_cb_receiver_pair(self) -> (Callback<'static, Sample>, Self::Receiver) {
+        let (callback, receiver) = BufferedReceiver::pair(Conflation::default());
+        (callback, ConflatingChannelHandler(receiver))
+    }
+}
+
+/// The receiver of a [`ConflatingChannel`].
+pub struct ConflatingChannelHandler(BufferedReceiver<Conflation>);
+
+impl ConflatingChannelHandler {
+    /// Returns a pending [`Sample`] if any, without blocking.
+    pub fn try_recv(&self) -> ZResult<Option<Sample>> {
+        self.0.try_recv()
+    }
+
+    /// Waits for a [`Sample`]. Fails once the subscriber is undeclared and all samples received.
+    pub fn recv(&self) -> ZResult<Sample> {
+        self.0.recv()
+    }
+
+    /// Asynchronously waits for a [`Sample`]. Fails once the subscriber is undeclared and all samples received.
+    pub async fn recv_async(&self) -> ZResult<Sample> {
+        self.0.recv_async().await
+    }
+}
+
+struct Ring<T> {
+    capacity: usize,
+    samples: VecDeque<T>,
+}
+
+impl<T: Send + 'static> Buffer<T> for Ring<T> {
+    fn push(&mut self, t: T) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(t);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.samples.pop_front()
+    }
+}
+
+/// A handler keeping the `capacity` most recent items until they are received, dropping the oldest ones.
+///
+/// Unlike a bounded flume channel, a full [`RingChannel`] never blocks the sender,
+/// so that a slow consumer (e.g. a control loop) always reads the freshest data.
+///
+/// # Examples
+/// ```no_run
+/// # async_std::task::block_on(async {
+/// use zenoh::prelude::r#async::*;
+/// use zenoh::handlers::RingChannel;
+///
+/// let session = zenoh::open(config::peer()).res().await.unwrap();
+/// let subscriber = session
+///     .declare_subscriber("key/expression")
+///     .with(RingChannel::new(10))
+///     .res()
+///     .await
+///     .unwrap();
+/// loop {
+///     while let Ok(Some(sample)) = subscriber.try_recv() {
+///         println!("{}", sample);
+///     }
+///     async_std::task::sleep(std::time::Duration::from_millis(100)).await;
+/// }
+/// # })
+/// ```
+pub struct RingChannel {
+    capacity: usize,
+}
+
+impl RingChannel {
+    /// Creates a [`RingChannel`] keeping the `capacity` most recent items (at least one).
+    pub fn new(capacity: usize) -> Self {
+        RingChannel {
+            capacity: capacity.max(1),
+        }
+    }
+}
+
+impl<T: Send + 'static> IntoCallbackReceiverPair<'static, T> for RingChannel {
+    type Receiver = RingChannelHandler<T>;
+
+    fn into_cb_receiver_pair(self) -> (Callback<'static, T>, Self::Receiver) {
+        let (callback, receiver) = BufferedReceiver::pair(Ring {
+            capacity: self.capacity,
+            samples: VecDeque::with_capacity(self.capacity),
+        });
+        (callback, RingChannelHandler(receiver))
+    }
+}
+
+/// The receiver of a [`RingChannel`].
+pub struct RingChannelHandler<T>(BufferedReceiver<Ring<T>>);
+
+impl<T: Send + 'static> RingChannelHandler<T> {
+    /// Returns the oldest pending item if any, without blocking.
+    pub fn try_recv(&self) -> ZResult<Option<T>> {
+        self.0.try_recv()
+    }
+
+    /// Waits for an item. Fails once the subscriber is undeclared and all items received.
+    pub fn recv(&self) -> ZResult<T> {
+        self.0.recv()
+    }
+
+    /// Asynchronously waits for an item. Fails once the subscriber is undeclared and all items received.
+    pub async fn recv_async(&self) -> ZResult<T> {
+        self.0.recv_async().await
+    }
+}
+
 /// A function that can transform a [`FnMut`]`(T)` to
 /// a [`Fn`]`(T)` with the help of a [`Mutex`](std::sync::Mutex).
 pub fn locked<T>(fnmut: impl FnMut(T)) -> impl Fn(T) {
     let lock = std::sync::Mutex::new(fnmut);
     move |x| zlock!(lock)(x)
 }
+
+#[test]
+fn conflating_channel() {
+    let (callback, receiver) = ConflatingChannel.into_cb_receiver_pair();
+    let put = |key: &'static str, value: i64| {
+        callback(Sample::new(KeyExpr::try_from(key).unwrap(), value));
+    };
+    let check = |sample: Sample, key: &str, value: &str| {
+        assert_eq!(sample.key_expr.as_str(), key);
+        assert_eq!(sample.value.to_string(), value);
+    };
+
+    put("a", 1);
+    put("b", 1);
+    put("a", 2);
+    check(receiver.recv().unwrap(), "a", "2");
+    check(receiver.try_recv().unwrap().unwrap(), "b", "1");
+    assert!(receiver.try_recv().unwrap().is_none());
+
+    put("a", 3);
+    drop(callback);
+    check(receiver.recv().unwrap(), "a", "3");
+    assert!(receiver.recv().is_err());
+    assert!(receiver.try_recv().is_err());
+}
+
+#[test]
+fn ring_channel() {
+    let (callback, receiver): (Callback<i32>, _) = RingChannel::new(2).into_cb_receiver_pair();
+
+    assert!(receiver.try_recv().unwrap().is_none());
+    for i in 0..5 {
+        callback(i);
+    }
+    assert_eq!(receiver.recv().unwrap(), 3);
+    assert_eq!(receiver.try_recv().unwrap(), Some(4));
+    assert!(receiver.try_recv().unwrap().is_none());
+
+    callback(5);
+    drop(callback);
+    assert_eq!(receiver.recv().unwrap(), 5);
+    assert!(receiver.recv().is_err());
+}
@@ -17,6 +17,8 @@ use crate::SessionRef;
 use std::future::Ready;
 use zenoh_config::{WhatAmI, ZenohId};
 use zenoh_core::{AsyncResolve, Resolvable, SyncResolve};
+#[zenoh_macros::unstable]
+use zenoh_protocol::{common::imsg, core::ZInt, transport::tmsg};
 
 /// A builder retuned by [`SessionInfo::zid()`](SessionInfo::zid) that allows
 /// to access the [`ZenohId`] of the current zenoh [`Session`](crate::Session).
@@ -148,6 +150,97 @@ impl<'a> AsyncResolve for PeersZidBuilder<'a> {
     }
 }
 
+/// The set of features negotiated with a remote zenoh node during the handshake,
+/// i.e. the features supported by both sides and thus used on the session with it.
+#[zenoh_macros::unstable]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Features(ZInt);
+
+#[zenoh_macros::unstable]
+impl Features {
+    /// Whether the messages larger than a batch are fragmented.
+    pub fn fragmentation(&self) -> bool {
+        imsg::has_option(self.0, tmsg::features::FRAGMENTATION)
+    }
+
+    /// Whether the attachments carry extensions.
+    pub fn attachments(&self) -> bool {
+        imsg::has_option(self.0, tmsg::features::ATTACHMENTS)
+    }
+
+    /// Whether the batches are compressed.
+    pub fn compression(&self) -> bool {
+        imsg::has_option(self.0, tmsg::features::COMPRESSION)
+    }
+
+    /// Whether the messages are sent on per-priority channels.
+    pub fn qos(&self) -> bool {
+        imsg::has_option(self.0, tmsg::features::QOS)
+    }
+
+    /// Whether the payloads are exchanged through shared memory.
+    pub fn shared_memory(&self) -> bool {
+        imsg::has_option(self.0, tmsg::features::SHARED_MEMORY)
+    }
+
+    /// Whether the flow of messages can be limited by the receive credits of each side.
+    pub fn credits(&self) -> bool {
+        imsg::has_option(self.0, tmsg::features::CREDITS)
+    }
+
+    /// The raw bitmap of the negotiated features.
+    pub fn bits(&self) -> ZInt {
+        self.0
+    }
+}
+
+/// A builder returned by [`SessionInfo::features()`](SessionInfo::features) that allows
+/// to access the [`Features`] negotiated with each of the zenoh nodes this process is
+/// currently connected to.
+///
+/// # Examples
+/// ```
+/// # async_std::task::block_on(async {
+/// use zenoh::prelude::r#async::*;
+///
+/// let session = zenoh::open(config::peer()).res().await.unwrap();
+/// let mut features = session.info().features().res().await;
+/// while let Some((zid, features)) = features.next() {}
+/// # })
+/// ```
+#[zenoh_macros::unstable]
+pub struct FeaturesBuilder<'a> {
+    pub(crate) session: SessionRef<'a>,
+}
+
+#[zenoh_macros::unstable]
+impl<'a> Resolvable for FeaturesBuilder<'a> {
+    type To = Box<dyn Iterator<Item = (ZenohId, Features)> + Send + Sync>;
+}
+
+#[zenoh_macros::unstable]
+impl<'a> SyncResolve for FeaturesBuilder<'a> {
+    fn res_sync(self) -> <Self as Resolvable>::To {
+        Box::new(
+            self.session
+                .runtime
+                .manager()
+                .get_transports()
+                .into_iter()
+                .filter_map(|s| Some((s.get_zid().ok()?, Features(s.get_features().ok()?)))),
+        )
+    }
+}
+
+#[zenoh_macros::unstable]
+impl<'a> AsyncResolve for FeaturesBuilder<'a> {
+    type Future = Ready<Self::To>;
+
+    fn res_async(self) -> Self::Future {
+        std::future::ready(self.res_sync())
+    }
+}
+
 /// Struct returned by [`Session::info()`](crate::Session::info) which allows
 /// to access informations about the current zenoh [`Session`](crate::Session).
 ///
@@ -219,4 +312,24 @@ impl SessionInfo<'_> {
             session: self.session.clone(),
         }
     }
+
+    /// Return the [`Features`] negotiated with each of the zenoh nodes this process is
+    /// currently connected to.
+    ///
+    /// # Examples
+    /// ```
+    /// # async_std::task::block_on(async {
+    /// use zenoh::prelude::r#async::*;
+    ///
+    /// let session = zenoh::open(config::peer()).res().await.unwrap();
+    /// let mut features = session.info().features().res().await;
+    /// while let Some((zid, features)) = features.next() {}
+    /// # })
+    /// ```
+    #[zenoh_macros::unstable]
+    pub fn features(&self) -> FeaturesBuilder<'_> {
+        FeaturesBuilder {
+            session: self.session.clone(),
+        }
+    }
 }
@@ -95,6 +95,8 @@ use zenoh_result::{zerror, ZResult};
 pub use zenoh_result::Error;
 /// A zenoh result.
 pub use zenoh_result::ZResult as Result;
+/// The kind of a zenoh [`Error`], retrieved with [`ErrNo::kind`].
+pub use zenoh_result::{ErrNo, ErrorKind};
 
 const GIT_VERSION: &str = git_version!(prefix = "v", cargo_prefix = "v");
 
@@ -746,6 +746,7 @@ where
                 Locality::default(),
                 self.timeout,
                 None,
+                None,
                 callback,
             )
             .map(|_| receiver)
@@ -0,0 +1,162 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use zenoh_buffers::ZBuf;
+use zenoh_core::zlock;
+use zenoh_protocol::{
+    core::{Channel, CongestionControl, SubAggregation, WireExpr, ZInt},
+    zenoh::{DataInfo, RoutingContext},
+};
+use zenoh_transport::Primitives;
+
+struct PendingData {
+    key_expr: WireExpr<'static>,
+    payload: ZBuf,
+    channel: Channel,
+    congestion_control: CongestionControl,
+    info: Option<DataInfo>,
+    routing_context: Option<RoutingContext>,
+}
+
+#[derive(Default)]
+struct AggregatorState {
+    // The latest pending data of each key expression, flushed in their order of arrival
+    pending: HashMap<(ZInt, String), PendingData>,
+    order: VecDeque<(ZInt, String)>,
+    flushing: bool,
+}
+
+/// Applies the aggregation policy of some subscriptions on the data sent to a face:
+/// the data is delivered at most once per period, only the latest data of each key
+/// expression being kept in the meantime, and at most `max_rate` data per second.
+pub(super) struct Aggregator {
+    period: Duration,
+    budget: usize,
+    primitives: Arc<dyn Primitives + Send + Sync>,
+    state: Mutex<AggregatorState>,
+}
+
+impl Aggregator {
+    /// Returns `None` if the policy doesn't aggregate anything (i.e. no window and no max rate).
+    pub(super) fn new(
+        policy: &SubAggregation,
+        primitives: Arc<dyn Primitives + Send + Sync>,
+    ) -> Option<Aggregator> {
+        let period = match (policy.window, policy.max_rate) {
+            (0, 0) => return None,
+            (0, max_rate) => Duration::from_secs(1) / u32::try_from(max_rate).unwrap_or(u32::MAX),
+            (window, _) => Duration::from_millis(window),
+        };
+        let budget = match policy.max_rate {
+            0 => usize::MAX,
+            max_rate => usize::try_from(max_rate as u128 * period.as_micros() / 1_000_000)
+                .unwrap_or(usize::MAX)
+                .max(1),
+        };
+        Some(Aggregator {
+            period,
+            budget,
+            primitives,
+            state: Mutex::new(AggregatorState::default()),
+        })
+    }
+
+    pub(super) fn send_data(
+        self: &Arc<Self>,
+        key_expr: &WireExpr,
+        payload: ZBuf,
+        channel: Channel,
+        congestion_control: CongestionControl,
+        info: Option<DataInfo>,
+        routing_context: Option<RoutingContext>,
+    ) {
+        let key = (key_expr.scope, key_expr.suffix.to_string());
+        let data = PendingData {
+            key_expr: key_expr.to_owned(),
+            payload,
+            channel,
+            congestion_control,
+            info,
+            routing_context,
+        };
+        let mut state = zlock!(self.state);
+        if state.pending.insert(key.clone(), data).is_none() {
+            state.order.push_back(key);
+        }
+        if !state.flushing {
+            state.flushing = true;
+            let aggregator = self.clone();
+            async_std::task::spawn(async move { aggregator.flush().await });
+        }
+    }
+
+    // Delivers up to the budget of pending data every period, until no data is pending.
+    async fn flush(self: Arc<Self>) {
+        loop {
+            async_std::task::sleep(self.period).await;
+            let (batch, done) = {
+                let mut state = zlock!(self.state);
+                let count = state.order.len().min(self.budget);
+                let keys: Vec<(ZInt, String)> = state.order.drain(..count).collect();
+                let batch: Vec<PendingData> = keys
+                    .iter()
+                    .filter_map(|key| state.pending.remove(key))
+                    .collect();
+                let done = state.order.is_empty();
+                if done {
+                    state.flushing = false;
+                }
+                (batch, done)
+            };
+            for data in batch {
+                self.primitives.send_data(
+                    &data.key_expr,
+                    data.payload,
+                    data.channel,
+                    data.congestion_control,
+                    data.info,
+                    data.routing_context,
+                );
+            }
+            if done {
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn aggregation_budget() {
+    let primitives = Arc::new(zenoh_transport::DummyPrimitives::new());
+    let policy = |window, max_rate| SubAggregation { window, max_rate };
+
+    assert!(Aggregator::new(&policy(0, 0), primitives.clone()).is_none());
+
+    let aggregator = Aggregator::new(&policy(100, 0), primitives.clone()).unwrap();
+    assert_eq!(aggregator.period, Duration::from_millis(100));
+    assert_eq!(aggregator.budget, usize::MAX);
+
+    let aggregator = Aggregator::new(&policy(100, 50), primitives.clone()).unwrap();
+    assert_eq!(aggregator.period, Duration::from_millis(100));
+    assert_eq!(aggregator.budget, 5);
+
+    let aggregator = Aggregator::new(&policy(0, 4), primitives.clone()).unwrap();
+    assert_eq!(aggregator.period, Duration::from_millis(250));
+    assert_eq!(aggregator.budget, 1);
+
+    let aggregator = Aggregator::new(&policy(100, 1), primitives).unwrap();
+    assert_eq!(aggregator.budget, 1);
+}
@@ -11,34 +11,125 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
+use super::aggregation::Aggregator;
+use super::filter::PayloadFilter;
 use super::router::*;
-use std::collections::{HashMap, HashSet};
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use zenoh_buffers::ZBuf;
 use zenoh_protocol::{
+    common::Attachment,
     core::{
-        Channel, CongestionControl, ConsolidationMode, QueryTarget, QueryableInfo, SubInfo,
-        WhatAmI, WireExpr, ZInt, ZenohId,
+        key_expr::{keyexpr, OwnedKeyExpr},
+        Channel, CongestionControl, ConsolidationMode, QueryTarget, QueryableInfo, SubAggregation,
+        SubInfo, WhatAmI, WireExpr, ZInt, ZenohId,
     },
     zenoh::{DataInfo, QueryBody, RoutingContext},
 };
+use zenoh_sync::get_mut_unchecked;
 use zenoh_transport::Primitives;
 
+/// The order in which the local mappings of a face that no declaration retains were last
+/// used, to forget the least recently used ones first.
+#[derive(Default)]
+pub(super) struct MappingsRecency {
+    clock: ZInt,
+    ticks: HashMap<ZInt, ZInt>,
+    by_tick: BTreeMap<ZInt, ZInt>,
+}
+
+impl MappingsRecency {
+    pub(super) fn touch(&mut self, expr_id: ZInt) {
+        self.clock += 1;
+        if let Some(tick) = self.ticks.insert(expr_id, self.clock) {
+            self.by_tick.remove(&tick);
+        }
+        self.by_tick.insert(self.clock, expr_id);
+    }
+
+    pub(super) fn remove(&mut self, expr_id: ZInt) {
+        if let Some(tick) = self.ticks.remove(&expr_id) {
+            self.by_tick.remove(&tick);
+        }
+    }
+
+    pub(super) fn least_recent(&self) -> Option<ZInt> {
+        self.by_tick.values().next().copied()
+    }
+}
+
+/// The maximum number of key expressions whose samples are counted for a face.
+const HOT_KEYS_CAPACITY: usize = 1_024;
+
+/// Counts the samples routed to a face per key expression, to find the hot ones worth mapping
+/// to a resource id. Once `HOT_KEYS_CAPACITY` key expressions are counted, the counts are halved
+/// and the key expressions whose count drops to zero are forgotten: only the key expressions in
+/// steady use become hot.
+#[derive(Default)]
+pub(super) struct HotKeys {
+    hits: HashMap<String, usize>,
+}
+
+impl HotKeys {
+    /// Counts a sample routed on `key`, returning true once `threshold` samples were counted.
+    pub(super) fn hit(&mut self, key: &str, threshold: usize) -> bool {
+        let hits = match self.hits.get_mut(key) {
+            Some(hits) => {
+                *hits += 1;
+                *hits
+            }
+            None => {
+                if self.hits.len() >= HOT_KEYS_CAPACITY {
+                    self.hits.retain(|_, hits| {
+                        *hits /= 2;
+                        *hits > 0
+                    });
+                }
+                self.hits.insert(key.to_string(), 1);
+                1
+            }
+        };
+        if hits >= threshold {
+            self.hits.remove(key);
+            true
+        } else {
+            false
+        }
+    }
+}
+
 pub struct FaceState {
     pub(super) id: usize,
     pub(super) zid: ZenohId,
     pub(super) whatami: WhatAmI,
     pub(super) primitives: Arc<dyn Primitives + Send + Sync>,
     pub(super) link_id: usize,
+    // whether the face is a session of this node rather than a remote node
+    pub(super) local: bool,
     pub(super) local_mappings: HashMap<ZInt, Arc<Resource>>,
+    pub(super) local_mappings_recency: MappingsRecency,
+    pub(super) local_mappings_refs: HashMap<ZInt, usize>,
+    pub(super) max_local_mappings: Option<usize>,
+    pub(super) hot_keys: Mutex<HotKeys>,
     pub(super) remote_mappings: HashMap<ZInt, Arc<Resource>>,
     pub(super) local_subs: HashSet<Arc<Resource>>,
+    // the subscriptions not declared to the face as covered by a subscription declared to it
+    pub(super) covered_subs: HashSet<Arc<Resource>>,
     pub(super) remote_subs: HashSet<Arc<Resource>>,
+    // the compiled payload filters of the filtered remote subscriptions
+    pub(super) sub_filters: HashMap<Arc<Resource>, PayloadFilter>,
+    pub(super) sub_aggregators: HashMap<SubAggregation, Arc<Aggregator>>,
     pub(super) local_qabls: HashMap<Arc<Resource>, QueryableInfo>,
     pub(super) remote_qabls: HashSet<Arc<Resource>>,
     pub(super) next_qid: ZInt,
     pub(super) pending_queries: HashMap<ZInt, Arc<Query>>,
+    // whether the face waits for its first message before being sent declarations
+    pub(super) pending_sync: AtomicBool,
+    // the key expressions the face declared its interest in (all of them if empty)
+    pub(super) interests: Vec<OwnedKeyExpr>,
 }
 
 impl FaceState {
@@ -48,6 +139,8 @@ impl FaceState {
         whatami: WhatAmI,
         primitives: Arc<dyn Primitives + Send + Sync>,
         link_id: usize,
+        max_local_mappings: Option<usize>,
+        local: bool,
     ) -> Arc<FaceState> {
         Arc::new(FaceState {
             id,
@@ -55,14 +148,24 @@ impl FaceState {
             whatami,
             primitives,
             link_id,
+            local,
             local_mappings: HashMap::new(),
+            local_mappings_recency: MappingsRecency::default(),
+            local_mappings_refs: HashMap::new(),
+            max_local_mappings,
+            hot_keys: Mutex::new(HotKeys::default()),
             remote_mappings: HashMap::new(),
             local_subs: HashSet::new(),
+            covered_subs: HashSet::new(),
             remote_subs: HashSet::new(),
+            sub_filters: HashMap::new(),
+            sub_aggregators: HashMap::new(),
             local_qabls: HashMap::new(),
             remote_qabls: HashSet::new(),
             next_qid: 0,
             pending_queries: HashMap::new(),
+            pending_sync: AtomicBool::new(false),
+            interests: vec![],
         })
     }
 
@@ -75,6 +178,15 @@ impl FaceState {
         }
     }
 
+    /// Whether the declarations of `res` should be sent to this face.
+    pub(super) fn is_interested_in(&self, res: &Resource) -> bool {
+        !self.pending_sync.load(Ordering::Acquire)
+            && (self.interests.is_empty()
+                || keyexpr::new(res.expr().as_str())
+                    .map(|ke| self.interests.iter().any(|i| i.intersects(ke)))
+                    .unwrap_or(false))
+    }
+
     pub(super) fn get_next_local_id(&self) -> ZInt {
         let mut id = 1;
         while self.local_mappings.get(&id).is_some() || self.remote_mappings.get(&id).is_some() {
@@ -158,20 +270,69 @@ impl fmt::Display for FaceState {
     }
 }
 
+thread_local! {
+    // The node the data or query being routed on this thread was received from
+    static ROUTED_FROM: Cell<Option<ZenohId>> = Cell::new(None);
+}
+
+/// A scope in which the data or query being routed was received from the node `zid`,
+/// so that the local faces it is routed to may tell where it comes from with [`routed_from`].
+///
+/// The scope lasts until the returned guard is dropped.
+pub(crate) struct RoutedFrom(Option<ZenohId>);
+
+impl RoutedFrom {
+    pub(crate) fn enter(zid: ZenohId) -> RoutedFrom {
+        RoutedFrom(ROUTED_FROM.with(|f| f.replace(Some(zid))))
+    }
+}
+
+impl Drop for RoutedFrom {
+    fn drop(&mut self) {
+        ROUTED_FROM.with(|f| f.set(self.0));
+    }
+}
+
+/// The node the data or query being routed on this thread was received from, if any.
+///
+/// This is the node that sent it to this one, which is not its original sender if it was
+/// forwarded by other routers.
+pub(crate) fn routed_from() -> Option<ZenohId> {
+    ROUTED_FROM.with(|f| f.get())
+}
+
 #[derive(Clone)]
 pub struct Face {
     pub(crate) tables: Arc<TablesLock>,
     pub(crate) state: Arc<FaceState>,
 }
 
+impl Face {
+    // Sends its initial declarations to a face that was waiting for its first message.
+    fn sync(&self) {
+        if self.state.pending_sync.load(Ordering::Acquire) {
+            let ctrl_lock = zlock!(self.tables.ctrl_lock);
+            let mut tables = zwrite!(self.tables.tables);
+            if self.state.pending_sync.swap(false, Ordering::AcqRel) {
+                pubsub_new_face(&mut tables, &mut self.state.clone());
+                queries_new_face(&mut tables, &mut self.state.clone());
+            }
+            drop(tables);
+            drop(ctrl_lock);
+        }
+    }
+}
+
 impl Primitives for Face {
     fn decl_resource(&self, expr_id: ZInt, key_expr: &WireExpr) {
+        self.sync();
         let ctrl_lock = zlock!(self.tables.ctrl_lock);
         register_expr(&self.tables, &mut self.state.clone(), expr_id, key_expr);
         drop(ctrl_lock);
     }
 
     fn forget_resource(&self, expr_id: ZInt) {
+        self.sync();
         let ctrl_lock = zlock!(self.tables.ctrl_lock);
         unregister_expr(&self.tables, &mut self.state.clone(), expr_id);
         drop(ctrl_lock);
@@ -183,6 +344,7 @@ impl Primitives for Face {
         sub_info: &SubInfo,
         routing_context: Option<RoutingContext>,
     ) {
+        self.sync();
         let ctrl_lock = zlock!(self.tables.ctrl_lock);
         let rtables = zread!(self.tables.tables);
         match (rtables.whatami, self.state.whatami) {
@@ -236,6 +398,7 @@ impl Primitives for Face {
     }
 
     fn forget_subscriber(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
+        self.sync();
         let ctrl_lock = zlock!(self.tables.ctrl_lock);
         let rtables = zread!(self.tables.tables);
         match (rtables.whatami, self.state.whatami) {
@@ -289,6 +452,7 @@ impl Primitives for Face {
         qabl_info: &QueryableInfo,
         routing_context: Option<RoutingContext>,
     ) {
+        self.sync();
         let ctrl_lock = zlock!(self.tables.ctrl_lock);
         let rtables = zread!(self.tables.tables);
         match (rtables.whatami, self.state.whatami) {
@@ -340,6 +504,7 @@ impl Primitives for Face {
     }
 
     fn forget_queryable(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
+        self.sync();
         let ctrl_lock = zlock!(self.tables.ctrl_lock);
         let rtables = zread!(self.tables.tables);
         match (rtables.whatami, self.state.whatami) {
@@ -390,6 +555,8 @@ impl Primitives for Face {
         data_info: Option<DataInfo>,
         routing_context: Option<RoutingContext>,
     ) {
+        self.sync();
+        let _routed_from = RoutedFrom::enter(self.state.zid);
         full_reentrant_route_data(
             &self.tables.tables,
             &self.state,
@@ -412,6 +579,8 @@ impl Primitives for Face {
         body: Option<QueryBody>,
         routing_context: Option<RoutingContext>,
     ) {
+        self.sync();
+        let _routed_from = RoutedFrom::enter(self.state.zid);
         route_query(
             &self.tables,
             &self.state,
@@ -432,7 +601,9 @@ impl Primitives for Face {
         key_expr: WireExpr,
         info: Option<DataInfo>,
         payload: ZBuf,
+        attachment: Option<Attachment>,
     ) {
+        self.sync();
         route_send_reply_data(
             &self.tables,
             &mut self.state.clone(),
@@ -441,10 +612,12 @@ impl Primitives for Face {
             key_expr,
             info,
             payload,
+            attachment,
         );
     }
 
     fn send_reply_final(&self, qid: ZInt) {
+        self.sync();
         route_send_reply_final(&self.tables, &mut self.state.clone(), qid);
     }
 
@@ -455,6 +628,7 @@ impl Primitives for Face {
         pull_id: ZInt,
         max_samples: &Option<ZInt>,
     ) {
+        self.sync();
         pull_data(
             &self.tables.tables,
             &self.state.clone(),
@@ -468,6 +642,59 @@ impl Primitives for Face {
     fn send_close(&self) {
         super::router::close_face(&self.tables, &Arc::downgrade(&self.state));
     }
+
+    fn start_batch(&self) {
+        // the declarations of a batch are propagated to all the other faces
+        let faces: Vec<Arc<FaceState>> = zread!(self.tables.tables)
+            .faces
+            .values()
+            .filter(|face| face.id != self.state.id)
+            .cloned()
+            .collect();
+        for face in faces {
+            face.primitives.start_batch();
+        }
+    }
+
+    fn end_batch(&self) {
+        let faces: Vec<Arc<FaceState>> = zread!(self.tables.tables)
+            .faces
+            .values()
+            .filter(|face| face.id != self.state.id)
+            .cloned()
+            .collect();
+        for face in faces {
+            face.primitives.end_batch();
+        }
+    }
+
+    fn decl_interest(&self, key_expr: &WireExpr) {
+        let ctrl_lock = zlock!(self.tables.ctrl_lock);
+        let mut tables = zwrite!(self.tables.tables);
+        // only routers restrict the declarations they send to their clients
+        if tables.whatami == WhatAmI::Router && self.state.whatami == WhatAmI::Client {
+            match tables
+                .get_mapping(&self.state, &key_expr.scope)
+                .map(|prefix| prefix.expr() + key_expr.suffix.as_ref())
+            {
+                Some(expr) => match OwnedKeyExpr::new(expr) {
+                    Ok(interest) => {
+                        log::debug!("Declare interest {} for {}", interest, self.state);
+                        let face = &mut self.state.clone();
+                        get_mut_unchecked(face).interests.push(interest);
+                        face.pending_sync.store(false, Ordering::Release);
+                        // send the matching declarations not sent yet
+                        pubsub_new_face(&mut tables, face);
+                        queries_new_face(&mut tables, face);
+                    }
+                    Err(e) => log::error!("Declare invalid interest: {}", e),
+                },
+                None => log::error!("Declare interest for unknown scope {}!", key_expr.scope),
+            }
+        }
+        drop(tables);
+        drop(ctrl_lock);
+    }
 }
 
 impl fmt::Display for Face {
@@ -475,3 +702,24 @@ impl fmt::Display for Face {
         self.state.fmt(f)
     }
 }
+
+#[test]
+fn hot_keys() {
+    let mut hot_keys = HotKeys::default();
+    assert!(!hot_keys.hit("a", 3));
+    assert!(!hot_keys.hit("a", 3));
+    assert!(hot_keys.hit("a", 3));
+    // counted again once hot
+    assert!(!hot_keys.hit("a", 3));
+
+    // the counts age once the capacity is reached
+    let mut hot_keys = HotKeys::default();
+    assert!(!hot_keys.hit("a", 3));
+    assert!(!hot_keys.hit("a", 3));
+    for i in 0..HOT_KEYS_CAPACITY {
+        hot_keys.hit(&i.to_string(), 3);
+    }
+    assert!(!hot_keys.hit("a", 3));
+    assert!(hot_keys.hit("a", 3));
+    assert!(hot_keys.hits.len() <= HOT_KEYS_CAPACITY);
+}
@@ -0,0 +1,400 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use serde_json::Value as JsonValue;
+use std::cmp::Ordering;
+use std::str::FromStr;
+use zenoh_buffers::{SplitBuffer, ZBuf};
+use zenoh_protocol::{core::KnownEncoding, zenoh::DataInfo};
+use zenoh_result::{bail, zerror, ZResult};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Clause {
+    path: Vec<String>,
+    operator: Operator,
+    literal: JsonValue,
+}
+
+// Where a coordinate of a geographic clause is read from
+#[derive(Debug, Clone, PartialEq)]
+enum Coordinate {
+    // a (dotted) field of the JSON payload
+    Field(Vec<String>),
+    // a chunk of the key expression
+    Chunk(usize),
+}
+
+// Whether the position read from the sample lies within a region, in decimal degrees
+#[derive(Debug, Clone, PartialEq)]
+struct GeoClause {
+    latitude: Coordinate,
+    longitude: Coordinate,
+    south: f64,
+    west: f64,
+    north: f64,
+    east: f64,
+}
+
+/// A payload filter attached to a subscription.
+///
+/// A filter is a conjunction of clauses, e.g. `temperature > 30 && sensor.kind == "outdoor"`:
+/// - comparisons between a (dotted) field of a JSON payload and a JSON literal,
+///   using the `==`, `!=`, `<`, `<=`, `>` and `>=` operators;
+/// - geographic clauses `geo(<latitude>, <longitude>) in [<south>, <west>, <north>, <east>]`,
+///   checking that a position lies within a region (borders included, `west > east` for the
+///   regions crossing the antimeridian). The coordinates are (dotted) fields of a JSON payload,
+///   or chunks of the key expression given by their index, e.g. `geo($1, $2) in [43.5, 1.3, 43.7, 1.6]`
+///   for `fleet/43.6/1.44/truck-1`.
+///
+/// Samples whose payload must be read but is not JSON-encoded, or that lack one of the read fields, don't match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PayloadFilter {
+    clauses: Vec<Clause>,
+    regions: Vec<GeoClause>,
+}
+
+impl PayloadFilter {
+    pub fn matches(&self, key_expr: &str, info: Option<&DataInfo>, payload: &ZBuf) -> bool {
+        // the payload is only parsed if it's read by a clause
+        let reads_payload = !self.clauses.is_empty()
+            || self.regions.iter().any(|region| {
+                matches!(region.latitude, Coordinate::Field(_))
+                    || matches!(region.longitude, Coordinate::Field(_))
+            });
+        let json = if reads_payload {
+            let is_json = info
+                .and_then(|info| info.encoding.as_ref())
+                .map(|encoding| {
+                    matches!(
+                        encoding.prefix(),
+                        KnownEncoding::AppJson | KnownEncoding::TextJson
+                    )
+                })
+                .unwrap_or(false);
+            if !is_json {
+                return false;
+            }
+            match serde_json::from_slice(&payload.contiguous()) {
+                Ok(json) => json,
+                Err(_) => return false,
+            }
+        } else {
+            JsonValue::Null
+        };
+        self.clauses.iter().all(|clause| clause.matches(&json))
+            && self
+                .regions
+                .iter()
+                .all(|region| region.matches(key_expr, &json))
+    }
+}
+
+impl Coordinate {
+    fn value(&self, key_expr: &str, json: &JsonValue) -> Option<f64> {
+        let value = match self {
+            Coordinate::Field(path) => path
+                .iter()
+                .try_fold(json, |value, field| value.get(field.as_str()))?
+                .as_f64()?,
+            Coordinate::Chunk(index) => key_expr.split('/').nth(*index)?.parse().ok()?,
+        };
+        value.is_finite().then_some(value)
+    }
+}
+
+impl GeoClause {
+    fn matches(&self, key_expr: &str, json: &JsonValue) -> bool {
+        let (latitude, longitude) = match (
+            self.latitude.value(key_expr, json),
+            self.longitude.value(key_expr, json),
+        ) {
+            (Some(latitude), Some(longitude)) => (latitude, longitude),
+            _ => return false,
+        };
+        if latitude < self.south || latitude > self.north {
+            return false;
+        }
+        if self.west <= self.east {
+            self.west <= longitude && longitude <= self.east
+        } else {
+            self.west <= longitude || longitude <= self.east
+        }
+    }
+}
+
+impl Clause {
+    fn matches(&self, json: &JsonValue) -> bool {
+        let value = match self
+            .path
+            .iter()
+            .try_fold(json, |value, field| value.get(field.as_str()))
+        {
+            Some(value) => value,
+            None => return false,
+        };
+        let ordering = match (value, &self.literal) {
+            (JsonValue::Number(a), JsonValue::Number(b)) => match (a.as_f64(), b.as_f64()) {
+                (Some(a), Some(b)) => a.partial_cmp(&b),
+                _ => None,
+            },
+            (JsonValue::String(a), JsonValue::String(b)) => Some(a.cmp(b)),
+            (a, b) if a == b => Some(Ordering::Equal),
+            _ => None,
+        };
+        match self.operator {
+            Operator::Eq => ordering == Some(Ordering::Equal),
+            Operator::Ne => ordering != Some(Ordering::Equal),
+            Operator::Lt => ordering == Some(Ordering::Less),
+            Operator::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            Operator::Gt => ordering == Some(Ordering::Greater),
+            Operator::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        }
+    }
+}
+
+impl FromStr for Clause {
+    type Err = zenoh_result::Error;
+
+    fn from_str(s: &str) -> ZResult<Self> {
+        let start = s
+            .find(|c| matches!(c, '=' | '!' | '<' | '>'))
+            .ok_or_else(|| zerror!("Missing comparison operator in filter clause '{}'", s))?;
+        let (path, rest) = s.split_at(start);
+        let (operator, literal) = [
+            ("==", Operator::Eq),
+            ("!=", Operator::Ne),
+            ("<=", Operator::Le),
+            (">=", Operator::Ge),
+            ("<", Operator::Lt),
+            (">", Operator::Gt),
+        ]
+        .iter()
+        .find_map(|(token, operator)| rest.strip_prefix(token).map(|l| (*operator, l)))
+        .ok_or_else(|| zerror!("Invalid comparison operator in filter clause '{}'", s))?;
+
+        let path = parse_path(path, s)?;
+        let literal = serde_json::from_str(literal.trim())
+            .map_err(|e| zerror!("Invalid literal in filter clause '{}': {}", s, e))?;
+        Ok(Clause {
+            path,
+            operator,
+            literal,
+        })
+    }
+}
+
+// Parses the (dotted) field `path` of the filter clause `clause`
+fn parse_path(path: &str, clause: &str) -> ZResult<Vec<String>> {
+    let path: Vec<String> = path.trim().split('.').map(String::from).collect();
+    if path.iter().any(|field| {
+        field.is_empty()
+            || !field
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    }) {
+        bail!("Invalid field path in filter clause '{}'", clause);
+    }
+    Ok(path)
+}
+
+impl FromStr for GeoClause {
+    type Err = zenoh_result::Error;
+
+    fn from_str(s: &str) -> ZResult<Self> {
+        let invalid = || zerror!("Invalid geographic filter clause '{}'", s);
+        let rest = s.trim().strip_prefix("geo(").ok_or_else(invalid)?;
+        let (coordinates, rest) = rest.split_once(')').ok_or_else(invalid)?;
+        let region = rest
+            .trim()
+            .strip_prefix("in")
+            .map(str::trim)
+            .and_then(|r| r.strip_prefix('['))
+            .and_then(|r| r.strip_suffix(']'))
+            .ok_or_else(invalid)?;
+
+        let coordinate = |c: &str| match c.trim().strip_prefix('$') {
+            Some(index) => index
+                .parse()
+                .map(Coordinate::Chunk)
+                .map_err(|_| zenoh_result::Error::from(invalid())),
+            None => parse_path(c, s).map(Coordinate::Field),
+        };
+        let (latitude, longitude) = coordinates.split_once(',').ok_or_else(invalid)?;
+        let bounds = region
+            .split(',')
+            .map(|b| b.trim().parse::<f64>().ok().filter(|b| b.is_finite()))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?;
+        match bounds[..] {
+            [south, west, north, east] => Ok(GeoClause {
+                latitude: coordinate(latitude)?,
+                longitude: coordinate(longitude)?,
+                south,
+                west,
+                north,
+                east,
+            }),
+            _ => Err(invalid().into()),
+        }
+    }
+}
+
+impl FromStr for PayloadFilter {
+    type Err = zenoh_result::Error;
+
+    fn from_str(s: &str) -> ZResult<Self> {
+        // split on `&&`, except within string literals
+        let mut clauses: Vec<&str> = Vec::new();
+        let (mut start, mut in_string, mut escaped) = (0, false, false);
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                _ if escaped => escaped = false,
+                b'\\' if in_string => escaped = true,
+                b'"' => in_string = !in_string,
+                b'&' if !in_string && bytes.get(i + 1) == Some(&b'&') => {
+                    clauses.push(&s[start..i]);
+                    i += 1;
+                    start = i + 1;
+                }
+                _ => (),
+            }
+            i += 1;
+        }
+        clauses.push(&s[start..]);
+
+        let mut filter = PayloadFilter {
+            clauses: Vec::new(),
+            regions: Vec::new(),
+        };
+        for clause in clauses {
+            if clause.trim_start().starts_with("geo(") {
+                filter.regions.push(clause.parse()?);
+            } else {
+                filter.clauses.push(clause.parse()?);
+            }
+        }
+        Ok(filter)
+    }
+}
+
+#[test]
+fn payload_filter() {
+    use zenoh_protocol::core::Encoding;
+
+    let info = DataInfo {
+        encoding: Some(Encoding::Exact(KnownEncoding::AppJson)),
+        ..Default::default()
+    };
+    let payload = ZBuf::from(
+        br#"{"temperature": 31.5, "sensor": {"kind": "outdoor", "id": 3}, "note": "a && b"}"#
+            .to_vec(),
+    );
+    let matches = |filter: &str| {
+        filter
+            .parse::<PayloadFilter>()
+            .unwrap()
+            .matches("test/sensor", Some(&info), &payload)
+    };
+
+    assert!(matches("temperature > 30"));
+    assert!(matches("temperature >= 31.5"));
+    assert!(!matches("temperature < 30"));
+    assert!(matches(r#"sensor.kind == "outdoor""#));
+    assert!(matches(r#"sensor.kind != "indoor""#));
+    assert!(matches("sensor.id <= 3 && temperature > 30"));
+    assert!(!matches("sensor.id <= 3 && temperature > 40"));
+    assert!(matches(r#"note == "a && b""#));
+    assert!(!matches("humidity > 0"));
+    assert!(!matches(r#"temperature > "30""#));
+
+    // non JSON payloads never match
+    assert!(!"temperature > 30"
+        .parse::<PayloadFilter>()
+        .unwrap()
+        .matches("test/sensor", None, &payload));
+
+    assert!("temperature".parse::<PayloadFilter>().is_err());
+    assert!("temperature =< 3".parse::<PayloadFilter>().is_err());
+    assert!("temperature > ".parse::<PayloadFilter>().is_err());
+    assert!(".temperature > 3".parse::<PayloadFilter>().is_err());
+    assert!("temperature > 3 &&".parse::<PayloadFilter>().is_err());
+}
+
+#[test]
+fn geo_filter() {
+    use zenoh_protocol::core::Encoding;
+
+    let info = DataInfo {
+        encoding: Some(Encoding::Exact(KnownEncoding::AppJson)),
+        ..Default::default()
+    };
+    let payload = ZBuf::from(br#"{"position": {"lat": 43.6, "lon": 1.44}, "speed": 12}"#.to_vec());
+    let matches = |filter: &str, key_expr: &str, info: Option<&DataInfo>| {
+        filter
+            .parse::<PayloadFilter>()
+            .unwrap()
+            .matches(key_expr, info, &payload)
+    };
+
+    // positions read from the key expression, whatever the payload
+    let toulouse = "geo($1, $2) in [43.5, 1.3, 43.7, 1.6]";
+    assert!(matches(toulouse, "fleet/43.6/1.44/truck-1", None));
+    assert!(!matches(toulouse, "fleet/48.85/2.35/truck-2", None));
+    assert!(!matches(toulouse, "fleet/unknown/truck-3", None));
+    assert!(!matches(toulouse, "fleet", None));
+
+    // positions read from the payload
+    let toulouse = "geo(position.lat, position.lon) in [43.5, 1.3, 43.7, 1.6]";
+    assert!(matches(toulouse, "fleet/truck-1", Some(&info)));
+    assert!(!matches(toulouse, "fleet/truck-1", None));
+    assert!(matches(
+        &format!("{toulouse} && speed > 10"),
+        "fleet/truck-1",
+        Some(&info)
+    ));
+    assert!(!matches(
+        &format!("speed > 20 && {toulouse}"),
+        "fleet/truck-1",
+        Some(&info)
+    ));
+    assert!(!matches(
+        "geo(position.lat, position.lon) in [48.8, 2.2, 48.9, 2.5]",
+        "fleet/truck-1",
+        Some(&info)
+    ));
+
+    // regions crossing the antimeridian
+    let pacific = "geo($1, $2) in [-50, 170, 10, -170]";
+    assert!(matches(pacific, "fleet/-17.7/178.1/ship", None));
+    assert!(matches(pacific, "fleet/-17.7/-175/ship", None));
+    assert!(!matches(pacific, "fleet/-17.7/150/ship", None));
+
+    assert!("geo($1) in [0, 0, 1, 1]".parse::<PayloadFilter>().is_err());
+    assert!("geo($1, $2) in [0, 0, 1]".parse::<PayloadFilter>().is_err());
+    assert!("geo($1, $x) in [0, 0, 1, 1]"
+        .parse::<PayloadFilter>()
+        .is_err());
+    assert!("geo($1, $2) [0, 0, 1, 1]".parse::<PayloadFilter>().is_err());
+}
@@ -0,0 +1,94 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use zenoh_core::zlock;
+use zenoh_protocol::core::key_expr::{keyexpr, OwnedKeyExpr};
+
+/// The prefix of the key expressions the mirrored samples are published on.
+pub const MIRROR_PREFIX: &str = "@debug";
+
+const MIRROR_WINDOW: Duration = Duration::from_secs(1);
+
+/// Duplicates the samples routed on some key expressions onto `@debug/<original-key>`,
+/// letting operators tap into live flows without modifying the producers or the consumers.
+pub struct DataMirror {
+    keys: Vec<OwnedKeyExpr>,
+    max_rate: usize,
+    // the start of the current rate limiting window, and the number of samples mirrored in it
+    window: Mutex<(Instant, usize)>,
+}
+
+impl DataMirror {
+    pub fn new(keys: Vec<OwnedKeyExpr>, max_rate: usize) -> Self {
+        DataMirror {
+            keys,
+            max_rate,
+            window: Mutex::new((Instant::now(), 0)),
+        }
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty() && self.max_rate > 0
+    }
+
+    /// The key expression a sample routed on `key` should be mirrored on,
+    /// or `None` if it doesn't match or if the rate limit is reached.
+    pub fn mirror(&self, key: &str) -> Option<String> {
+        if key == MIRROR_PREFIX || key.starts_with(&[MIRROR_PREFIX, "/"].concat()) {
+            return None;
+        }
+        let ke = keyexpr::new(key).ok()?;
+        if !self.keys.iter().any(|k| k.intersects(ke)) {
+            return None;
+        }
+        let mut window = zlock!(self.window);
+        let now = Instant::now();
+        if now.duration_since(window.0) >= MIRROR_WINDOW {
+            *window = (now, 0);
+        }
+        if window.1 >= self.max_rate {
+            return None;
+        }
+        window.1 += 1;
+        Some(format!("{}/{}", MIRROR_PREFIX, key))
+    }
+}
+
+impl Default for DataMirror {
+    fn default() -> Self {
+        DataMirror::new(vec![], 0)
+    }
+}
+
+#[test]
+fn data_mirror() {
+    use std::convert::TryFrom;
+
+    let mirror = DataMirror::new(vec![OwnedKeyExpr::try_from("robot/*/pose").unwrap()], 2);
+    assert!(mirror.is_enabled());
+    assert_eq!(mirror.mirror("robot/1/speed"), None);
+    assert_eq!(
+        mirror.mirror("robot/1/pose"),
+        Some("@debug/robot/1/pose".to_string())
+    );
+    assert!(mirror.mirror("robot/2/pose").is_some());
+    // rate limited
+    assert_eq!(mirror.mirror("robot/3/pose"), None);
+    // never mirrored twice
+    let mirror = DataMirror::new(vec![OwnedKeyExpr::try_from("**").unwrap()], 10);
+    assert_eq!(mirror.mirror("@debug/robot/1/pose"), None);
+    assert!(!DataMirror::default().is_enabled());
+}
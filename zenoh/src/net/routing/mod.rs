@@ -17,12 +17,18 @@
 //! This module is intended for Zenoh's internal use.
 //!
 //! [Click here for Zenoh's documentation](../zenoh/index.html)
+pub mod aggregation;
 pub mod face;
+pub mod filter;
+pub mod mirror;
+pub mod namespace;
 pub mod network;
 pub mod pubsub;
 pub mod queries;
+pub mod remapping;
 pub mod resource;
 pub mod router;
+pub mod talkers;
 
 use super::runtime;
 
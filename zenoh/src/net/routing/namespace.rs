@@ -0,0 +1,527 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use zenoh_buffers::ZBuf;
+use zenoh_core::{zlock, zread, zwrite};
+use zenoh_protocol::{
+    common::Attachment,
+    core::{
+        key_expr::{keyexpr, OwnedKeyExpr},
+        Channel, CongestionControl, ConsolidationMode, QueryTarget, QueryableInfo, SubInfo,
+        WireExpr, ZInt, ZenohId,
+    },
+    zenoh::{DataInfo, QueryBody, RoutingContext},
+};
+use zenoh_transport::Primitives;
+
+// The key expressions of the `@` system space (e.g. liveliness tokens) are not namespaced.
+fn is_system(key_expr: &str) -> bool {
+    key_expr == "@" || key_expr.starts_with("@/")
+}
+
+// The key expressions `k` such that `<namespace>/k` is included in `key_expr`, wildcards included:
+// e.g. `**` and `*/a` in namespace `ns` give `**` and `a`. The returned key expressions don't
+// include one another, and are empty if `key_expr` doesn't intersect the namespace.
+fn strip_namespace(namespace: &keyexpr, key_expr: &str) -> Vec<String> {
+    let chunk_matches = |chunk: &str, ns_chunk: &str| {
+        matches!(
+            (keyexpr::new(chunk), keyexpr::new(ns_chunk)),
+            (Ok(chunk), Ok(ns_chunk)) if chunk.intersects(ns_chunk)
+        )
+    };
+    let chunks: Vec<&str> = key_expr.split('/').collect();
+    // The indexes of the chunks of `key_expr` left to match after matching the namespace chunks
+    let mut states = vec![0];
+    for ns_chunk in namespace.as_str().split('/') {
+        let mut next = Vec::new();
+        for mut i in states {
+            while let Some(chunk) = chunks.get(i) {
+                if *chunk == "**" {
+                    // `**` either matches the namespace chunk (and maybe more) or nothing
+                    next.push(i);
+                    i += 1;
+                } else {
+                    if chunk_matches(chunk, ns_chunk) {
+                        next.push(i + 1);
+                    }
+                    break;
+                }
+            }
+        }
+        next.sort_unstable();
+        next.dedup();
+        states = next;
+    }
+    let stripped: Vec<String> = states
+        .into_iter()
+        .filter(|i| *i < chunks.len())
+        .map(|i| chunks[i..].join("/"))
+        .collect();
+    let includes = |a: &str, b: &str| {
+        matches!(
+            (keyexpr::new(a), keyexpr::new(b)),
+            (Ok(a), Ok(b)) if a.includes(b)
+        )
+    };
+    stripped
+        .iter()
+        .enumerate()
+        .filter(|(i, k)| {
+            !stripped
+                .iter()
+                .enumerate()
+                .any(|(j, other)| j != *i && includes(other, k) && (j < *i || !includes(k, other)))
+        })
+        .map(|(_, k)| k.clone())
+        .collect()
+}
+
+/// Prefixes the key expressions sent by a session with its namespace.
+///
+/// Only the key expressions with no scope need to be prefixed:
+/// the scoped ones are relative to resources that have been prefixed when declared.
+pub(crate) struct NamespacePrefixer {
+    namespace: OwnedKeyExpr,
+    inner: Arc<dyn Primitives>,
+}
+
+impl NamespacePrefixer {
+    pub(crate) fn new(namespace: OwnedKeyExpr, inner: Arc<dyn Primitives>) -> Self {
+        NamespacePrefixer { namespace, inner }
+    }
+
+    fn prefix<'a>(&self, key_expr: &'a WireExpr) -> WireExpr<'a> {
+        let suffix = if key_expr.scope == 0 && !is_system(&key_expr.suffix) {
+            Cow::Owned(format!("{}/{}", self.namespace, key_expr.suffix))
+        } else {
+            Cow::Borrowed(key_expr.suffix.as_ref())
+        };
+        WireExpr {
+            scope: key_expr.scope,
+            suffix,
+        }
+    }
+}
+
+impl Primitives for NamespacePrefixer {
+    fn decl_resource(&self, expr_id: ZInt, key_expr: &WireExpr) {
+        self.inner.decl_resource(expr_id, &self.prefix(key_expr))
+    }
+
+    fn forget_resource(&self, expr_id: ZInt) {
+        self.inner.forget_resource(expr_id)
+    }
+
+    fn decl_publisher(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
+        self.inner
+            .decl_publisher(&self.prefix(key_expr), routing_context)
+    }
+
+    fn forget_publisher(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
+        self.inner
+            .forget_publisher(&self.prefix(key_expr), routing_context)
+    }
+
+    fn decl_subscriber(
+        &self,
+        key_expr: &WireExpr,
+        sub_info: &SubInfo,
+        routing_context: Option<RoutingContext>,
+    ) {
+        self.inner
+            .decl_subscriber(&self.prefix(key_expr), sub_info, routing_context)
+    }
+
+    fn forget_subscriber(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
+        self.inner
+            .forget_subscriber(&self.prefix(key_expr), routing_context)
+    }
+
+    fn decl_queryable(
+        &self,
+        key_expr: &WireExpr,
+        qabl_info: &QueryableInfo,
+        routing_context: Option<RoutingContext>,
+    ) {
+        self.inner
+            .decl_queryable(&self.prefix(key_expr), qabl_info, routing_context)
+    }
+
+    fn forget_queryable(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
+        self.inner
+            .forget_queryable(&self.prefix(key_expr), routing_context)
+    }
+
+    fn send_data(
+        &self,
+        key_expr: &WireExpr,
+        payload: ZBuf,
+        channel: Channel,
+        congestion_control: CongestionControl,
+        data_info: Option<DataInfo>,
+        routing_context: Option<RoutingContext>,
+    ) {
+        self.inner.send_data(
+            &self.prefix(key_expr),
+            payload,
+            channel,
+            congestion_control,
+            data_info,
+            routing_context,
+        )
+    }
+
+    fn send_query(
+        &self,
+        key_expr: &WireExpr,
+        parameters: &str,
+        qid: ZInt,
+        target: QueryTarget,
+        consolidation: ConsolidationMode,
+        body: Option<QueryBody>,
+        routing_context: Option<RoutingContext>,
+    ) {
+        self.inner.send_query(
+            &self.prefix(key_expr),
+            parameters,
+            qid,
+            target,
+            consolidation,
+            body,
+            routing_context,
+        )
+    }
+
+    fn send_reply_data(
+        &self,
+        qid: ZInt,
+        replier_id: ZenohId,
+        key_expr: WireExpr,
+        info: Option<DataInfo>,
+        payload: ZBuf,
+        attachment: Option<Attachment>,
+    ) {
+        let key_expr = self.prefix(&key_expr).to_owned();
+        self.inner
+            .send_reply_data(qid, replier_id, key_expr, info, payload, attachment)
+    }
+
+    fn send_reply_final(&self, qid: ZInt) {
+        self.inner.send_reply_final(qid)
+    }
+
+    fn send_pull(
+        &self,
+        is_final: bool,
+        key_expr: &WireExpr,
+        pull_id: ZInt,
+        max_samples: &Option<ZInt>,
+    ) {
+        self.inner
+            .send_pull(is_final, &self.prefix(key_expr), pull_id, max_samples)
+    }
+
+    fn send_close(&self) {
+        self.inner.send_close()
+    }
+
+    fn start_batch(&self) {
+        self.inner.start_batch()
+    }
+
+    fn end_batch(&self) {
+        self.inner.end_batch()
+    }
+
+    fn decl_interest(&self, key_expr: &WireExpr) {
+        self.inner.decl_interest(&self.prefix(key_expr))
+    }
+}
+
+/// Strips the namespace of a session from the key expressions it receives,
+/// dropping the messages about key expressions outside of its namespace.
+///
+/// The resources declared to the session are resolved here, so that all the key expressions
+/// passed to the session are complete and unscoped.
+pub(crate) struct NamespaceStripper {
+    namespace: OwnedKeyExpr,
+    resources: RwLock<HashMap<ZInt, String>>,
+    // where to finalize the queries that are dropped
+    egress: Mutex<Option<Weak<dyn Primitives>>>,
+    inner: Arc<dyn Primitives>,
+}
+
+impl NamespaceStripper {
+    pub(crate) fn new(namespace: OwnedKeyExpr, inner: Arc<dyn Primitives>) -> Self {
+        NamespaceStripper {
+            namespace,
+            resources: RwLock::new(HashMap::new()),
+            egress: Mutex::new(None),
+            inner,
+        }
+    }
+
+    pub(crate) fn set_egress(&self, egress: Weak<dyn Primitives>) {
+        *zlock!(self.egress) = Some(egress);
+    }
+
+    fn resolve(&self, key_expr: &WireExpr) -> Option<String> {
+        if key_expr.scope == 0 {
+            Some(key_expr.suffix.to_string())
+        } else {
+            match zread!(self.resources).get(&key_expr.scope) {
+                Some(prefix) => Some(format!("{}{}", prefix, key_expr.suffix)),
+                None => {
+                    log::error!("Received message for unknown expr_id: {}", key_expr.scope);
+                    None
+                }
+            }
+        }
+    }
+
+    // Strips the namespace from `key_expr`. A key expression with wildcards intersecting
+    // the namespace may give several key expressions, covering the part of it in the namespace.
+    fn strip(&self, key_expr: &WireExpr) -> Vec<WireExpr<'static>> {
+        let key_expr = match self.resolve(key_expr) {
+            Some(key_expr) => key_expr,
+            None => return vec![],
+        };
+        let stripped = if is_system(&key_expr) {
+            vec![key_expr]
+        } else {
+            match key_expr
+                .strip_prefix(self.namespace.as_str())
+                .and_then(|s| s.strip_prefix('/'))
+            {
+                Some(suffix) => vec![suffix.to_owned()],
+                None => strip_namespace(&self.namespace, &key_expr),
+            }
+        };
+        if stripped.is_empty() {
+            log::trace!(
+                "Dropping message for {}, outside of namespace {}",
+                key_expr,
+                self.namespace
+            );
+        }
+        stripped
+            .into_iter()
+            .map(|suffix| WireExpr {
+                scope: 0,
+                suffix: Cow::Owned(suffix),
+            })
+            .collect()
+    }
+}
+
+impl Primitives for NamespaceStripper {
+    fn decl_resource(&self, expr_id: ZInt, key_expr: &WireExpr) {
+        if let Some(key_expr) = self.resolve(key_expr) {
+            zwrite!(self.resources).insert(expr_id, key_expr);
+        }
+    }
+
+    fn forget_resource(&self, expr_id: ZInt) {
+        zwrite!(self.resources).remove(&expr_id);
+    }
+
+    fn decl_publisher(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
+        for key_expr in self.strip(key_expr) {
+            self.inner.decl_publisher(&key_expr, routing_context)
+        }
+    }
+
+    fn forget_publisher(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
+        for key_expr in self.strip(key_expr) {
+            self.inner.forget_publisher(&key_expr, routing_context)
+        }
+    }
+
+    fn decl_subscriber(
+        &self,
+        key_expr: &WireExpr,
+        sub_info: &SubInfo,
+        routing_context: Option<RoutingContext>,
+    ) {
+        for key_expr in self.strip(key_expr) {
+            self.inner
+                .decl_subscriber(&key_expr, sub_info, routing_context)
+        }
+    }
+
+    fn forget_subscriber(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
+        for key_expr in self.strip(key_expr) {
+            self.inner.forget_subscriber(&key_expr, routing_context)
+        }
+    }
+
+    fn decl_queryable(
+        &self,
+        key_expr: &WireExpr,
+        qabl_info: &QueryableInfo,
+        routing_context: Option<RoutingContext>,
+    ) {
+        for key_expr in self.strip(key_expr) {
+            self.inner
+                .decl_queryable(&key_expr, qabl_info, routing_context)
+        }
+    }
+
+    fn forget_queryable(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
+        for key_expr in self.strip(key_expr) {
+            self.inner.forget_queryable(&key_expr, routing_context)
+        }
+    }
+
+    fn send_data(
+        &self,
+        key_expr: &WireExpr,
+        payload: ZBuf,
+        channel: Channel,
+        congestion_control: CongestionControl,
+        data_info: Option<DataInfo>,
+        routing_context: Option<RoutingContext>,
+    ) {
+        // The key expressions of the data have no wildcards: they are stripped at most once
+        if let Some(key_expr) = self.strip(key_expr).pop() {
+            self.inner.send_data(
+                &key_expr,
+                payload,
+                channel,
+                congestion_control,
+                data_info,
+                routing_context,
+            )
+        }
+    }
+
+    fn send_query(
+        &self,
+        key_expr: &WireExpr,
+        parameters: &str,
+        qid: ZInt,
+        target: QueryTarget,
+        consolidation: ConsolidationMode,
+        body: Option<QueryBody>,
+        routing_context: Option<RoutingContext>,
+    ) {
+        let mut stripped = self.strip(key_expr);
+        // A query may only be forwarded once: the query on several key expressions is
+        // widened to the whole namespace, the querier dropping the replies that do not match its query.
+        let key_expr = match stripped.len() {
+            0 | 1 => stripped.pop(),
+            _ => Some(WireExpr::from("**").to_owned()),
+        };
+        match key_expr {
+            Some(key_expr) => self.inner.send_query(
+                &key_expr,
+                parameters,
+                qid,
+                target,
+                consolidation,
+                body,
+                routing_context,
+            ),
+            None => {
+                // The querier still expects this session to finalize the query
+                if let Some(egress) = zlock!(self.egress).as_ref().and_then(Weak::upgrade) {
+                    egress.send_reply_final(qid);
+                }
+            }
+        }
+    }
+
+    fn send_reply_data(
+        &self,
+        qid: ZInt,
+        replier_id: ZenohId,
+        key_expr: WireExpr,
+        info: Option<DataInfo>,
+        payload: ZBuf,
+        attachment: Option<Attachment>,
+    ) {
+        if let Some(key_expr) = self.strip(&key_expr).pop() {
+            self.inner
+                .send_reply_data(qid, replier_id, key_expr, info, payload, attachment)
+        }
+    }
+
+    fn send_reply_final(&self, qid: ZInt) {
+        self.inner.send_reply_final(qid)
+    }
+
+    fn send_pull(
+        &self,
+        is_final: bool,
+        key_expr: &WireExpr,
+        pull_id: ZInt,
+        max_samples: &Option<ZInt>,
+    ) {
+        for key_expr in self.strip(key_expr) {
+            self.inner
+                .send_pull(is_final, &key_expr, pull_id, max_samples)
+        }
+    }
+
+    fn send_close(&self) {
+        self.inner.send_close()
+    }
+
+    fn start_batch(&self) {
+        self.inner.start_batch()
+    }
+
+    fn end_batch(&self) {
+        self.inner.end_batch()
+    }
+
+    fn decl_interest(&self, key_expr: &WireExpr) {
+        for key_expr in self.strip(key_expr) {
+            self.inner.decl_interest(&key_expr)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    fn strip(namespace: &str, key_expr: &str) -> Vec<String> {
+        strip_namespace(&OwnedKeyExpr::try_from(namespace).unwrap(), key_expr)
+    }
+
+    #[test]
+    fn strip_namespace_wildcards() {
+        assert_eq!(strip("ns", "ns/a/b"), vec!["a/b"]);
+        assert_eq!(strip("ns", "**"), vec!["**"]);
+        assert_eq!(strip("ns", "*/a"), vec!["a"]);
+        assert_eq!(strip("ns", "n$*/a/**"), vec!["a/**"]);
+        assert_eq!(strip("t/ns", "**/a"), vec!["**/a"]);
+        assert_eq!(strip("t/ns", "*/**/a"), vec!["**/a"]);
+        assert_eq!(strip("t/ns", "t/**"), vec!["**"]);
+        // the key expressions included in others are dropped
+        assert_eq!(strip("t/ns", "**/ns/**"), vec!["**"]);
+        // and several ones may be needed to cover the namespace
+        let mut stripped = strip("a/b", "**/b/c");
+        stripped.sort();
+        assert_eq!(stripped, vec!["**/b/c", "c"]);
+        // the key expressions not intersecting the namespace are dropped
+        assert!(strip("ns", "other/a").is_empty());
+        assert!(strip("ns", "*").is_empty());
+        assert!(strip("t/ns", "*/other/**").is_empty());
+    }
+}
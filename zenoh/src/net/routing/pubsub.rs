@@ -11,7 +11,9 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
+use super::aggregation::Aggregator;
 use super::face::FaceState;
+use super::filter::PayloadFilter;
 use super::network::Network;
 use super::resource::{DataRoutes, Direction, PullCaches, Resource, Route, SessionContext};
 use super::router::{RoutingExpr, Tables, TablesLock};
@@ -20,18 +22,20 @@ use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::sync::RwLock;
-use std::sync::{Arc, RwLockReadGuard};
-use zenoh_buffers::ZBuf;
+use std::sync::{Arc, RwLockReadGuard, Weak};
+use zenoh_buffers::{SplitBuffer, ZBuf};
+use zenoh_config::FutureTimestampPolicy;
 use zenoh_core::zread;
 use zenoh_protocol::core::key_expr::keyexpr;
 use zenoh_protocol::{
     core::{
-        key_expr::OwnedKeyExpr, Channel, CongestionControl, Priority, Reliability, SubInfo,
-        SubMode, WhatAmI, WireExpr, ZInt, ZenohId,
+        key_expr::OwnedKeyExpr, Channel, CongestionControl, Priority, Reliability, SubAggregation,
+        SubInfo, SubMode, WhatAmI, WireExpr, ZInt, ZenohId,
     },
     zenoh::{DataInfo, RoutingContext},
 };
 use zenoh_sync::get_mut_unchecked;
+use zenoh_transport::FanOut;
 
 #[inline]
 fn send_sourced_subscription_to_net_childs(
@@ -63,6 +67,127 @@ fn send_sourced_subscription_to_net_childs(
     }
 }
 
+// Whether the subscription `res` is declared to `face`, or covered by a subscription declared to it.
+#[inline]
+fn has_local_sub(face: &FaceState, res: &Arc<Resource>) -> bool {
+    face.local_subs.contains(res) || face.covered_subs.contains(res)
+}
+
+// Whether the declaration of the subscription `res` may be omitted when covered by another one.
+// Clients forward the filters and aggregation policies of their subscriptions, and liveliness
+// subscriptions stand for the tokens: their declarations are always propagated.
+#[inline]
+fn is_coverable(tables: &Tables, res: &Resource) -> bool {
+    tables.whatami != WhatAmI::Client && !res.expr().starts_with(super::PREFIX_LIVELINESS)
+}
+
+#[inline]
+fn sub_includes(sub: &Resource, res: &Resource) -> bool {
+    let (sub, res) = (sub.expr(), res.expr());
+    matches!(
+        (keyexpr::new(sub.as_str()), keyexpr::new(res.as_str())),
+        (Ok(sub), Ok(res)) if sub.includes(res)
+    )
+}
+
+// The resources matching `res`. The subscriptions covering `res` or covered by it are among
+// them, so that they are found without scanning all the subscriptions of a face.
+#[inline]
+fn matching_resources(res: &Arc<Resource>) -> impl Iterator<Item = Arc<Resource>> + '_ {
+    res.context
+        .iter()
+        .flat_map(|ctx| ctx.matches.iter())
+        .filter_map(Weak::upgrade)
+        .filter(move |match_| !Arc::ptr_eq(match_, res))
+}
+
+// Whether the subscription `res` is covered by a subscription declared to `face`.
+#[inline]
+fn is_covered(tables: &Tables, face: &FaceState, res: &Arc<Resource>) -> bool {
+    matching_resources(res).any(|sub| {
+        face.local_subs.contains(&sub) && is_coverable(tables, &sub) && sub_includes(&sub, res)
+    })
+}
+
+// Declares the subscription `res` to `face`, unless covered by a subscription already declared
+// to it: the face routes the matching data to this node anyway. Conversely, the subscriptions
+// covered by `res` are undeclared once it is declared.
+fn declare_simple_subscription_to(
+    tables: &Tables,
+    face: &mut Arc<FaceState>,
+    res: &Arc<Resource>,
+    sub_info: &SubInfo,
+) {
+    let coverable = is_coverable(tables, res);
+    if coverable && is_covered(tables, face, res) {
+        log::debug!("Subscription {} covered on {}", res.expr(), face);
+        get_mut_unchecked(face).covered_subs.insert(res.clone());
+        return;
+    }
+
+    get_mut_unchecked(face).local_subs.insert(res.clone());
+    let key_expr = Resource::decl_retained_key(res, face);
+    face.primitives.decl_subscriber(&key_expr, sub_info, None);
+
+    if coverable {
+        let covered = matching_resources(res)
+            .filter(|sub| {
+                face.local_subs.contains(sub) && is_coverable(tables, sub) && sub_includes(res, sub)
+            })
+            .collect::<Vec<Arc<Resource>>>();
+        for sub in covered {
+            log::debug!("Subscription {} covered on {}", sub.expr(), face);
+            let key_expr = Resource::get_best_key(&sub, "", face.id);
+            face.primitives.forget_subscriber(&key_expr, None);
+
+            get_mut_unchecked(face).local_subs.remove(&sub);
+            Resource::release_key(&sub, face);
+            get_mut_unchecked(face).covered_subs.insert(sub);
+        }
+    }
+}
+
+// Undeclares the subscription `res` to `face`, first declaring the subscriptions it covered
+// that are not covered by another subscription declared to it.
+fn forget_simple_subscription_to(tables: &Tables, face: &mut Arc<FaceState>, res: &Arc<Resource>) {
+    if get_mut_unchecked(face).covered_subs.remove(res) {
+        return;
+    }
+    if !get_mut_unchecked(face).local_subs.remove(res) {
+        return;
+    }
+
+    if is_coverable(tables, res) {
+        let uncovered = matching_resources(res)
+            .filter(|sub| face.covered_subs.contains(sub) && sub_includes(res, sub))
+            .collect::<Vec<Arc<Resource>>>();
+        let sub_info = SubInfo {
+            reliability: Reliability::Reliable, // @TODO
+            mode: SubMode::Push,
+            filter: None,
+            aggregation: None,
+        };
+        for sub in &uncovered {
+            // The subscriptions covered by another uncovered one remain covered
+            if !uncovered
+                .iter()
+                .any(|other| !Arc::ptr_eq(other, sub) && sub_includes(other, sub))
+                && !is_covered(tables, face, sub)
+            {
+                log::debug!("Subscription {} uncovered on {}", sub.expr(), face);
+                get_mut_unchecked(face).covered_subs.remove(sub);
+                get_mut_unchecked(face).local_subs.insert(sub.clone());
+                let key_expr = Resource::decl_retained_key(sub, face);
+                face.primitives.decl_subscriber(&key_expr, &sub_info, None);
+            }
+        }
+    }
+
+    let key_expr = Resource::get_best_key(res, "", face.id);
+    face.primitives.forget_subscriber(&key_expr, None);
+    Resource::release_key(res, face);
+}
+
 #[inline]
 fn propagate_simple_subscription_to(
     tables: &mut Tables,
@@ -73,7 +198,8 @@ fn propagate_simple_subscription_to(
     full_peer_net: bool,
 ) {
     if (src_face.id != dst_face.id || res.expr().starts_with(super::PREFIX_LIVELINESS))
-        && !dst_face.local_subs.contains(res)
+        && !has_local_sub(dst_face, res)
+        && dst_face.is_interested_in(res)
         && match tables.whatami {
             WhatAmI::Router => {
                 if full_peer_net {
@@ -92,14 +218,10 @@ fn propagate_simple_subscription_to(
                     src_face.whatami == WhatAmI::Client || dst_face.whatami == WhatAmI::Client
                 }
             }
-            _ => src_face.whatami == WhatAmI::Client || dst_face.whatami == WhatAmI::Client,
+            _ => tables.may_forward(src_face, dst_face),
         }
     {
-        get_mut_unchecked(dst_face).local_subs.insert(res.clone());
-        let key_expr = Resource::decl_key(res, dst_face);
-        dst_face
-            .primitives
-            .decl_subscriber(&key_expr, sub_info, None);
+        declare_simple_subscription_to(tables, dst_face, res, sub_info);
     }
 }
 
@@ -351,6 +473,16 @@ fn register_client_subscription(
                 Some(info) => {
                     if SubMode::Pull == info.mode {
                         get_mut_unchecked(ctx).subs = Some(sub_info.clone());
+                    } else if info.filter != sub_info.filter
+                        || info.aggregation != sub_info.aggregation
+                    {
+                        // The latest declaration defines the filter and the aggregation
+                        let info = SubInfo {
+                            filter: sub_info.filter.clone(),
+                            aggregation: sub_info.aggregation,
+                            ..info.clone()
+                        };
+                        get_mut_unchecked(ctx).subs = Some(info);
                     }
                 }
                 None => {
@@ -373,6 +505,152 @@ fn register_client_subscription(
         }
     }
     get_mut_unchecked(face).remote_subs.insert(res.clone());
+
+    // Index the compiled payload filter of the subscription (if any): as the latest declaration
+    // defines it, it replaces the filter of a previous declaration of the same subscription
+    let filter = res
+        .session_ctxs
+        .get(&face.id)
+        .and_then(|ctx| ctx.subs.as_ref())
+        .and_then(|info| info.filter.as_ref());
+    match filter.map(|filter| filter.parse::<PayloadFilter>()) {
+        Some(Ok(compiled)) => {
+            get_mut_unchecked(face)
+                .sub_filters
+                .insert(res.clone(), compiled);
+        }
+        Some(Err(e)) => {
+            log::warn!(
+                "Ignoring invalid filter of subscription {} for {}: {}",
+                res.expr(),
+                face,
+                e
+            );
+            get_mut_unchecked(face).sub_filters.remove(res);
+        }
+        None => {
+            get_mut_unchecked(face).sub_filters.remove(res);
+        }
+    }
+
+    // Create the aggregator of the aggregation policy (if any)
+    if let Some(aggregation) = &sub_info.aggregation {
+        if !face.sub_aggregators.contains_key(aggregation) {
+            if let Some(aggregator) = Aggregator::new(aggregation, face.primitives.clone()) {
+                get_mut_unchecked(face)
+                    .sub_aggregators
+                    .insert(*aggregation, Arc::new(aggregator));
+            }
+        }
+    }
+    prune_sub_aggregators(face);
+}
+
+// Forgets the aggregators that are no longer used by the subscriptions of the face.
+// The data pending in a forgotten aggregator is still delivered.
+fn prune_sub_aggregators(face: &mut Arc<FaceState>) {
+    if face.sub_aggregators.is_empty() {
+        return;
+    }
+    let used: HashSet<SubAggregation> = face
+        .remote_subs
+        .iter()
+        .filter_map(|res| {
+            res.session_ctxs
+                .get(&face.id)
+                .and_then(|ctx| ctx.subs.as_ref())
+                .and_then(|info| info.aggregation)
+        })
+        .collect();
+    if used.len() < face.sub_aggregators.len() {
+        get_mut_unchecked(face)
+            .sub_aggregators
+            .retain(|aggregation, _| used.contains(aggregation));
+    }
+}
+
+// Returns the aggregator the routed data should go through before being sent to `outface`:
+// the data is only aggregated if all the subscriptions of `outface` matching it are.
+#[inline]
+fn sub_aggregator(outface: &FaceState, expr: &mut RoutingExpr) -> Option<Arc<Aggregator>> {
+    if outface.sub_aggregators.is_empty() {
+        return None;
+    }
+    let key_expr = keyexpr::new(expr.full_expr()).ok()?;
+    let mut aggregator = None;
+    for res in outface.remote_subs.iter() {
+        if !keyexpr::new(res.expr().as_str())
+            .map(|ke| ke.intersects(key_expr))
+            .unwrap_or(false)
+        {
+            continue;
+        }
+        match res
+            .session_ctxs
+            .get(&outface.id)
+            .and_then(|ctx| ctx.subs.as_ref())
+            .and_then(|info| info.aggregation.as_ref())
+            .and_then(|aggregation| outface.sub_aggregators.get(aggregation))
+        {
+            Some(sub_aggregator) => {
+                aggregator.get_or_insert_with(|| sub_aggregator.clone());
+            }
+            None => return None,
+        }
+    }
+    aggregator
+}
+
+// Checks the payload filters of the subscriptions of `outface` matching the routed data:
+// the data is forwarded if at least one of them is unfiltered or accepts it.
+// The subscriptions are looked up among the resources matching the resource `res` of the data.
+// Without such resource, the subscriptions of `outface` are only scanned if a filtered one matches.
+#[inline]
+fn sub_filters_allow(
+    outface: &FaceState,
+    res: &Option<Arc<Resource>>,
+    expr: &mut RoutingExpr,
+    info: &Option<DataInfo>,
+    payload: &ZBuf,
+) -> bool {
+    if outface.sub_filters.is_empty() {
+        return true;
+    }
+    let key_expr = expr.full_expr();
+    let allows = |sub: &Arc<Resource>| match outface.sub_filters.get(sub) {
+        Some(filter) => filter.matches(key_expr, info.as_ref(), payload),
+        None => true,
+    };
+    match res.as_ref().filter(|res| res.context.is_some()) {
+        Some(res) => res
+            .context()
+            .matches
+            .iter()
+            .filter_map(Weak::upgrade)
+            .any(|sub| {
+                sub.session_ctxs
+                    .get(&outface.id)
+                    .map(|ctx| ctx.subs.is_some())
+                    .unwrap_or(false)
+                    && allows(&sub)
+            }),
+        None => {
+            let key_expr = match keyexpr::new(key_expr) {
+                Ok(key_expr) => key_expr,
+                Err(_) => return true,
+            };
+            let intersects = |sub: &Arc<Resource>| {
+                keyexpr::new(sub.expr().as_str())
+                    .map(|ke| ke.intersects(key_expr))
+                    .unwrap_or(false)
+            };
+            !outface.sub_filters.keys().any(intersects)
+                || outface
+                    .remote_subs
+                    .iter()
+                    .any(|sub| intersects(sub) && allows(sub))
+        }
+    }
 }
 
 pub fn declare_client_subscription(
@@ -409,6 +687,13 @@ pub fn declare_client_subscription(
             register_client_subscription(&mut wtables, face, &mut res, sub_info);
             let mut propa_sub_info = sub_info.clone();
             propa_sub_info.mode = SubMode::Push;
+            // Filters are evaluated by the first router: they are only forwarded upstream
+            // by clients, and only as long as a single local subscriber matches them.
+            // The same goes for aggregation policies.
+            if wtables.whatami != WhatAmI::Client || client_subs(&res).len() > 1 {
+                propa_sub_info.filter = None;
+                propa_sub_info.aggregation = None;
+            }
             match wtables.whatami {
                 WhatAmI::Router => {
                     let zid = wtables.zid;
@@ -521,12 +806,14 @@ fn send_forget_sourced_subscription_to_net_childs(
 }
 
 fn propagate_forget_simple_subscription(tables: &mut Tables, res: &Arc<Resource>) {
-    for face in tables.faces.values_mut() {
-        if face.local_subs.contains(res) {
-            let key_expr = Resource::get_best_key(res, "", face.id);
-            face.primitives.forget_subscriber(&key_expr, None);
-
-            get_mut_unchecked(face).local_subs.remove(res);
+    for mut face in tables
+        .faces
+        .values()
+        .cloned()
+        .collect::<Vec<Arc<FaceState>>>()
+    {
+        if has_local_sub(&face, res) {
+            forget_simple_subscription_to(tables, &mut face, res);
         }
     }
 }
@@ -543,7 +830,7 @@ fn propagate_forget_simple_subscription_to_peers(tables: &mut Tables, res: &Arc<
             .collect::<Vec<Arc<FaceState>>>()
         {
             if face.whatami == WhatAmI::Peer
-                && face.local_subs.contains(res)
+                && has_local_sub(&face, res)
                 && !res.session_ctxs.values().any(|s| {
                     face.zid != s.face.zid
                         && s.subs.is_some()
@@ -552,10 +839,7 @@ fn propagate_forget_simple_subscription_to_peers(tables: &mut Tables, res: &Arc<
                                 && tables.failover_brokering(s.face.zid, face.zid)))
                 })
             {
-                let key_expr = Resource::get_best_key(res, "", face.id);
-                face.primitives.forget_subscriber(&key_expr, None);
-
-                get_mut_unchecked(&mut face).local_subs.remove(res);
+                forget_simple_subscription_to(tables, &mut face, res);
             }
         }
     }
@@ -750,6 +1034,8 @@ pub(crate) fn undeclare_client_subscription(
         get_mut_unchecked(ctx).subs = None;
     }
     get_mut_unchecked(face).remote_subs.remove(res);
+    get_mut_unchecked(face).sub_filters.remove(res);
+    prune_sub_aggregators(face);
 
     let mut client_subs = client_subs(res);
     let router_subs = remote_router_subs(tables, res);
@@ -779,14 +1065,11 @@ pub(crate) fn undeclare_client_subscription(
     }
     if client_subs.len() == 1 && !router_subs && !peer_subs {
         let face = &mut client_subs[0];
-        if face.local_subs.contains(res)
+        if has_local_sub(face, res)
             && !(face.whatami == WhatAmI::Client
                 && res.expr().starts_with(super::PREFIX_LIVELINESS))
         {
-            let key_expr = Resource::get_best_key(res, "", face.id);
-            face.primitives.forget_subscriber(&key_expr, None);
-
-            get_mut_unchecked(face).local_subs.remove(res);
+            forget_simple_subscription_to(tables, face, res);
         }
     }
 }
@@ -829,14 +1112,16 @@ pub(crate) fn pubsub_new_face(tables: &mut Tables, face: &mut Arc<FaceState>) {
     let sub_info = SubInfo {
         reliability: Reliability::Reliable, // @TODO
         mode: SubMode::Push,
+        filter: None,
+        aggregation: None,
     };
     match tables.whatami {
         WhatAmI::Router => {
             if face.whatami == WhatAmI::Client {
                 for sub in &tables.router_subs {
-                    get_mut_unchecked(face).local_subs.insert(sub.clone());
-                    let key_expr = Resource::decl_key(sub, face);
-                    face.primitives.decl_subscriber(&key_expr, &sub_info, None);
+                    if !has_local_sub(face, sub) && face.is_interested_in(sub) {
+                        declare_simple_subscription_to(tables, face, sub, &sub_info);
+                    }
                 }
             } else if face.whatami == WhatAmI::Peer && !tables.full_net(WhatAmI::Peer) {
                 for sub in &tables.router_subs {
@@ -849,9 +1134,7 @@ pub(crate) fn pubsub_new_face(tables: &mut Tables, face: &mut Arc<FaceState>) {
                                             && tables.failover_brokering(s.face.zid, face.zid)))
                             }))
                     {
-                        get_mut_unchecked(face).local_subs.insert(sub.clone());
-                        let key_expr = Resource::decl_key(sub, face);
-                        face.primitives.decl_subscriber(&key_expr, &sub_info, None);
+                        declare_simple_subscription_to(tables, face, sub, &sub_info);
                     }
                 }
             }
@@ -860,9 +1143,7 @@ pub(crate) fn pubsub_new_face(tables: &mut Tables, face: &mut Arc<FaceState>) {
             if tables.full_net(WhatAmI::Peer) {
                 if face.whatami == WhatAmI::Client {
                     for sub in &tables.peer_subs {
-                        get_mut_unchecked(face).local_subs.insert(sub.clone());
-                        let key_expr = Resource::decl_key(sub, face);
-                        face.primitives.decl_subscriber(&key_expr, &sub_info, None);
+                        declare_simple_subscription_to(tables, face, sub, &sub_info);
                     }
                 }
             } else {
@@ -988,6 +1269,8 @@ pub(crate) fn pubsub_tree_change(
                             let sub_info = SubInfo {
                                 reliability: Reliability::Reliable, // @TODO
                                 mode: SubMode::Push,
+                                filter: None,
+                                aggregation: None,
                             };
                             send_sourced_subscription_to_net_childs(
                                 tables,
@@ -1027,7 +1310,7 @@ pub(crate) fn pubsub_linkstate_change(tables: &mut Tables, zid: &ZenohId, links:
                     {
                         let dst_face = &mut get_mut_unchecked(ctx).face;
                         if dst_face.whatami == WhatAmI::Peer && src_face.zid != dst_face.zid {
-                            if dst_face.local_subs.contains(res) {
+                            if has_local_sub(dst_face, res) {
                                 let forget = !Tables::failover_brokering_to(links, dst_face.zid)
                                     && {
                                         let ctx_links = tables
@@ -1045,22 +1328,17 @@ pub(crate) fn pubsub_linkstate_change(tables: &mut Tables, zid: &ZenohId, links:
                                         })
                                     };
                                 if forget {
-                                    let key_expr = Resource::get_best_key(res, "", dst_face.id);
-                                    dst_face.primitives.forget_subscriber(&key_expr, None);
-
-                                    get_mut_unchecked(dst_face).local_subs.remove(res);
+                                    forget_simple_subscription_to(tables, dst_face, res);
                                 }
                             } else if Tables::failover_brokering_to(links, ctx.face.zid) {
                                 let dst_face = &mut get_mut_unchecked(ctx).face;
-                                get_mut_unchecked(dst_face).local_subs.insert(res.clone());
-                                let key_expr = Resource::decl_key(res, dst_face);
                                 let sub_info = SubInfo {
                                     reliability: Reliability::Reliable, // TODO
                                     mode: SubMode::Push,
+                                    filter: None,
+                                    aggregation: None,
                                 };
-                                dst_face
-                                    .primitives
-                                    .decl_subscriber(&key_expr, &sub_info, None);
+                                declare_simple_subscription_to(tables, dst_face, res, &sub_info);
                             }
                         }
                     }
@@ -1410,7 +1688,7 @@ pub(super) fn disable_matches_data_routes(_tables: &mut Tables, res: &mut Arc<Re
 }
 
 macro_rules! treat_timestamp {
-    ($hlc:expr, $info:expr, $drop:expr) => {
+    ($hlc:expr, $info:expr, $policy:expr) => {
         // if an HLC was configured (via Config.add_timestamp),
         // check DataInfo and add a timestamp if there isn't
         match $hlc {
@@ -1420,14 +1698,15 @@ macro_rules! treat_timestamp {
                         // Timestamp is present; update HLC with it (possibly raising error if delta exceed)
                         match hlc.update_with_timestamp(ts) {
                             Ok(()) => Some(data_info),
-                            Err(e) => {
-                                if $drop {
+                            Err(e) => match $policy {
+                                FutureTimestampPolicy::Drop => {
                                     log::error!(
                                         "Error treating timestamp for received Data ({}). Drop it!",
                                         e
                                     );
                                     return;
-                                } else {
+                                }
+                                FutureTimestampPolicy::Clamp => {
                                     data_info.timestamp = Some(hlc.new_timestamp());
                                     log::error!(
                                         "Error treating timestamp for received Data ({}). Replace timestamp: {:?}",
@@ -1435,6 +1714,13 @@ macro_rules! treat_timestamp {
                                         data_info.timestamp);
                                     Some(data_info)
                                 }
+                                FutureTimestampPolicy::Accept => {
+                                    log::warn!(
+                                        "Error treating timestamp for received Data ({}). Keep it anyway",
+                                        e
+                                    );
+                                    Some(data_info)
+                                }
                             }
                         }
                     } else {
@@ -1567,7 +1853,7 @@ fn should_route(
     outface: &Arc<FaceState>,
     expr: &mut RoutingExpr,
 ) -> bool {
-    if src_face.id != outface.id {
+    if src_face.id != outface.id && tables.may_forward(src_face, outface) {
         let dst_master = tables.whatami != WhatAmI::Router
             || outface.whatami != WhatAmI::Peer
             || tables.peers_net.is_none()
@@ -1594,6 +1880,122 @@ pub fn full_reentrant_route_data(
     payload: ZBuf,
     routing_context: Option<RoutingContext>,
 ) {
+    record_talker(tables_ref, face, expr, &payload);
+    if let Some(mirror) = get_mirror_key(tables_ref, face, expr, routing_context) {
+        let _ = route_data(
+            tables_ref,
+            face,
+            &mirror.as_str().into(),
+            channel,
+            CongestionControl::Drop,
+            info.clone(),
+            payload.clone(),
+            None,
+        );
+    }
+    if let Some((key_expr, faces)) = route_data(
+        tables_ref,
+        face,
+        expr,
+        channel,
+        congestion_control,
+        info,
+        payload,
+        routing_context,
+    ) {
+        map_hot_key(tables_ref, &key_expr, faces);
+    }
+}
+
+// The faces of `route` on which the key expression of the routed data just became hot.
+fn hot_key_faces(tables: &Tables, route: &Route, expr: &mut RoutingExpr) -> Vec<Arc<FaceState>> {
+    let threshold = match tables.hot_key_threshold {
+        Some(threshold) => threshold,
+        None => return vec![],
+    };
+    // only the faces the key expression is not mapped on yet are counted
+    route
+        .values()
+        .filter(|(outface, key_expr, _)| {
+            !outface.local
+                && !key_expr.suffix.is_empty()
+                && zlock!(outface.hot_keys).hit(expr.full_expr(), threshold)
+        })
+        .map(|(outface, _, _)| outface.clone())
+        .collect()
+}
+
+// Maps the hot key expression `expr` to a resource id on `faces`, so that the following
+// data routed to them carries the id. The routes of the resource are recomputed accordingly.
+fn map_hot_key(tables_ref: &RwLock<Tables>, expr: &str, faces: Vec<Arc<FaceState>>) {
+    let mut tables = zwrite!(tables_ref);
+    let mut res = match Resource::get_resource(&tables.root_res, expr) {
+        Some(res) if res.context.is_some() => res,
+        _ => {
+            let mut matches = keyexpr::new(expr)
+                .map(|ke| Resource::get_matches(&tables, ke))
+                .unwrap_or_default();
+            let mut root = tables.root_res.clone();
+            let mut res = Resource::make_resource(&mut tables, &mut root, expr);
+            matches.push(Arc::downgrade(&res));
+            Resource::match_resource(&tables, &mut res, matches);
+            res
+        }
+    };
+    for mut face in faces {
+        // the face may have been closed meanwhile
+        if tables.faces.contains_key(&face.id) {
+            log::debug!("Map hot key {} on {}", expr, face);
+            Resource::decl_key(&res, &mut face);
+        }
+    }
+    tables.compute_matches_routes(&mut res);
+    Resource::clean(&mut res);
+}
+
+// The key expression the data should be mirrored on. The data is only mirrored where it enters
+// the routers network, not at each hop.
+fn get_mirror_key(
+    tables_ref: &RwLock<Tables>,
+    face: &FaceState,
+    expr: &WireExpr,
+    routing_context: Option<RoutingContext>,
+) -> Option<String> {
+    let tables = zread!(tables_ref);
+    if !tables.mirror.is_enabled() || routing_context.is_some() || face.whatami == WhatAmI::Router {
+        return None;
+    }
+    let prefix = tables.get_mapping(face, &expr.scope)?;
+    tables
+        .mirror
+        .mirror(&format!("{}{}", prefix.expr(), expr.suffix))
+}
+
+// Account the data in the top talkers of this node.
+fn record_talker(tables_ref: &RwLock<Tables>, face: &FaceState, expr: &WireExpr, payload: &ZBuf) {
+    let tables = zread!(tables_ref);
+    if !tables.talkers.is_enabled() {
+        return;
+    }
+    if let Some(prefix) = tables.get_mapping(face, &expr.scope) {
+        tables
+            .talkers
+            .record(&format!("{}{}", prefix.expr(), expr.suffix), payload.len());
+    }
+}
+
+// Returns the key expression of the data if it just became hot on some faces, and these faces.
+#[allow(clippy::too_many_arguments)]
+fn route_data(
+    tables_ref: &RwLock<Tables>,
+    face: &FaceState,
+    expr: &WireExpr,
+    channel: Channel,
+    congestion_control: CongestionControl,
+    info: Option<DataInfo>,
+    payload: ZBuf,
+    routing_context: Option<RoutingContext>,
+) -> Option<(String, Vec<Arc<FaceState>>)> {
     let tables = zread!(tables_ref);
     match tables.get_mapping(face, &expr.scope).cloned() {
         Some(prefix) => {
@@ -1603,6 +2005,7 @@ pub fn full_reentrant_route_data(
                 expr.suffix.as_ref()
             );
             let mut expr = RoutingExpr::new(&prefix, expr.suffix.as_ref());
+            let mut hot_faces = vec![];
 
             if tables.whatami != WhatAmI::Router
                 || face.whatami != WhatAmI::Peer
@@ -1610,26 +2013,44 @@ pub fn full_reentrant_route_data(
                 || tables.zid
                     == *tables.elect_router(expr.full_expr(), tables.get_router_links(face.zid))
             {
+                #[cfg(feature = "stats")]
+                let lookup = std::time::Instant::now();
                 let res = Resource::get_resource(&prefix, expr.suffix);
                 let route = get_data_route(&tables, face, &res, &mut expr, routing_context);
                 let matching_pulls = get_matching_pulls(&tables, &res, &mut expr);
+                #[cfg(feature = "stats")]
+                tables.data_route_latency.record(lookup.elapsed());
+                hot_faces = hot_key_faces(&tables, &route, &mut expr);
 
                 if !(route.is_empty() && matching_pulls.is_empty()) {
                     let data_info =
-                        treat_timestamp!(&tables.hlc, info, tables.drop_future_timestamp);
+                        treat_timestamp!(&tables.hlc, info, tables.future_timestamp_policy);
 
                     if route.len() == 1 && matching_pulls.len() == 0 {
                         let (outface, key_expr, context) = route.values().next().unwrap();
-                        if should_route(&tables, face, outface, &mut expr) {
+                        if should_route(&tables, face, outface, &mut expr)
+                            && sub_filters_allow(outface, &res, &mut expr, &data_info, &payload)
+                        {
+                            let aggregator = sub_aggregator(outface, &mut expr);
                             drop(tables);
-                            outface.primitives.send_data(
-                                key_expr,
-                                payload,
-                                channel, // @TODO: Need to check the active subscriptions to determine the right reliability value
-                                congestion_control,
-                                data_info,
-                                *context,
-                            )
+                            match aggregator {
+                                Some(aggregator) => aggregator.send_data(
+                                    key_expr,
+                                    payload,
+                                    channel,
+                                    congestion_control,
+                                    data_info,
+                                    *context,
+                                ),
+                                None => outface.primitives.send_data(
+                                    key_expr,
+                                    payload,
+                                    channel, // @TODO: Need to check the active subscriptions to determine the right reliability value
+                                    congestion_control,
+                                    data_info,
+                                    *context,
+                                ),
+                            }
                         }
                     } else {
                         if !matching_pulls.is_empty() {
@@ -1641,44 +2062,97 @@ pub fn full_reentrant_route_data(
                         if tables.whatami == WhatAmI::Router {
                             let route = route
                                 .values()
-                                .filter(|(outface, _key_expr, _context)| {
-                                    should_route(&tables, face, outface, &mut expr)
+                                .filter_map(|direction| {
+                                    let outface = &direction.0;
+                                    if should_route(&tables, face, outface, &mut expr)
+                                        && sub_filters_allow(
+                                            outface, &res, &mut expr, &data_info, &payload,
+                                        )
+                                    {
+                                        Some((
+                                            direction.clone(),
+                                            sub_aggregator(outface, &mut expr),
+                                        ))
+                                    } else {
+                                        None
+                                    }
                                 })
-                                .cloned()
-                                .collect::<Vec<Direction>>();
+                                .collect::<Vec<(Direction, Option<Arc<Aggregator>>)>>();
 
                             drop(tables);
-                            for (outface, key_expr, context) in route {
-                                outface.primitives.send_data(
-                                    &key_expr,
-                                    payload.clone(),
-                                    channel, // @TODO: Need to check the active subscriptions to determine the right reliability value
-                                    congestion_control,
-                                    data_info.clone(),
-                                    context,
-                                )
+                            let _fanout = FanOut::enter();
+                            for ((outface, key_expr, context), aggregator) in route {
+                                match aggregator {
+                                    Some(aggregator) => aggregator.send_data(
+                                        &key_expr,
+                                        payload.clone(),
+                                        channel,
+                                        congestion_control,
+                                        data_info.clone(),
+                                        context,
+                                    ),
+                                    None => outface.primitives.send_data(
+                                        &key_expr,
+                                        payload.clone(),
+                                        channel, // @TODO: Need to check the active subscriptions to determine the right reliability value
+                                        congestion_control,
+                                        data_info.clone(),
+                                        context,
+                                    ),
+                                }
                             }
                         } else {
+                            let route = route
+                                .values()
+                                .filter_map(|direction| {
+                                    let outface = &direction.0;
+                                    if face.id != outface.id
+                                        && tables.may_forward(face, outface)
+                                        && sub_filters_allow(
+                                            outface, &res, &mut expr, &data_info, &payload,
+                                        )
+                                    {
+                                        Some((
+                                            direction.clone(),
+                                            sub_aggregator(outface, &mut expr),
+                                        ))
+                                    } else {
+                                        None
+                                    }
+                                })
+                                .collect::<Vec<(Direction, Option<Arc<Aggregator>>)>>();
+
                             drop(tables);
-                            for (outface, key_expr, context) in route.values() {
-                                if face.id != outface.id {
-                                    outface.primitives.send_data(
-                                        key_expr,
+                            let _fanout = FanOut::enter();
+                            for ((outface, key_expr, context), aggregator) in route {
+                                match aggregator {
+                                    Some(aggregator) => aggregator.send_data(
+                                        &key_expr,
+                                        payload.clone(),
+                                        channel,
+                                        congestion_control,
+                                        data_info.clone(),
+                                        context,
+                                    ),
+                                    None => outface.primitives.send_data(
+                                        &key_expr,
                                         payload.clone(),
                                         channel, // @TODO: Need to check the active subscriptions to determine the right reliability value
                                         congestion_control,
                                         data_info.clone(),
-                                        *context,
-                                    )
+                                        context,
+                                    ),
                                 }
                             }
                         }
                     }
                 }
             }
+            (!hot_faces.is_empty()).then(|| (expr.full_expr().to_string(), hot_faces))
         }
         None => {
             log::error!("Route data with unknown scope {}!", expr.scope);
+            None
         }
     }
 }
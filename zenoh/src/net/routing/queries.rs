@@ -27,6 +27,7 @@ use std::sync::{Arc, RwLockReadGuard, Weak};
 use zenoh_buffers::ZBuf;
 use zenoh_protocol::core::key_expr::keyexpr;
 use zenoh_protocol::{
+    common::Attachment,
     core::{
         key_expr::{
             include::{Includer, DEFAULT_INCLUDER},
@@ -233,6 +234,7 @@ fn propagate_simple_queryable(
         let current_info = dst_face.local_qabls.get(res);
         if (src_face.is_none() || src_face.as_ref().unwrap().id != dst_face.id)
             && (current_info.is_none() || *current_info.unwrap() != info)
+            && dst_face.is_interested_in(res)
             && match tables.whatami {
                 WhatAmI::Router => {
                     if full_peers_net {
@@ -258,16 +260,19 @@ fn propagate_simple_queryable(
                     }
                 }
                 _ => {
-                    src_face.is_none()
-                        || src_face.as_ref().unwrap().whatami == WhatAmI::Client
-                        || dst_face.whatami == WhatAmI::Client
+                    src_face.is_none() || tables.may_forward(src_face.as_ref().unwrap(), &dst_face)
                 }
             }
         {
-            get_mut_unchecked(&mut dst_face)
+            let key_expr = if get_mut_unchecked(&mut dst_face)
                 .local_qabls
-                .insert(res.clone(), info.clone());
-            let key_expr = Resource::decl_key(res, &mut dst_face);
+                .insert(res.clone(), info.clone())
+                .is_none()
+            {
+                Resource::decl_retained_key(res, &mut dst_face)
+            } else {
+                Resource::decl_key(res, &mut dst_face)
+            };
             dst_face.primitives.decl_queryable(&key_expr, &info, None);
         }
     }
@@ -676,6 +681,7 @@ fn propagate_forget_simple_queryable(tables: &mut Tables, res: &mut Arc<Resource
             face.primitives.forget_queryable(&key_expr, None);
 
             get_mut_unchecked(face).local_qabls.remove(res);
+            Resource::release_key(res, face);
         }
     }
 }
@@ -705,6 +711,7 @@ fn propagate_forget_simple_queryable_to_peers(tables: &mut Tables, res: &mut Arc
                 face.primitives.forget_queryable(&key_expr, None);
 
                 get_mut_unchecked(&mut face).local_qabls.remove(res);
+                Resource::release_key(res, &mut face);
             }
         }
     }
@@ -944,6 +951,7 @@ pub(crate) fn undeclare_client_queryable(
             face.primitives.forget_queryable(&key_expr, None);
 
             get_mut_unchecked(face).local_qabls.remove(res);
+            Resource::release_key(res, face);
         }
     }
 }
@@ -987,12 +995,15 @@ pub(crate) fn queries_new_face(tables: &mut Tables, face: &mut Arc<FaceState>) {
         WhatAmI::Router => {
             if face.whatami == WhatAmI::Client {
                 for qabl in tables.router_qabls.iter() {
-                    if qabl.context.is_some() {
+                    if qabl.context.is_some()
+                        && !face.local_qabls.contains_key(qabl)
+                        && face.is_interested_in(qabl)
+                    {
                         let info = local_qabl_info(tables, qabl, face);
                         get_mut_unchecked(face)
                             .local_qabls
                             .insert(qabl.clone(), info.clone());
-                        let key_expr = Resource::decl_key(qabl, face);
+                        let key_expr = Resource::decl_retained_key(qabl, face);
                         face.primitives.decl_queryable(&key_expr, &info, None);
                     }
                 }
@@ -1011,7 +1022,7 @@ pub(crate) fn queries_new_face(tables: &mut Tables, face: &mut Arc<FaceState>) {
                         get_mut_unchecked(face)
                             .local_qabls
                             .insert(qabl.clone(), info.clone());
-                        let key_expr = Resource::decl_key(qabl, face);
+                        let key_expr = Resource::decl_retained_key(qabl, face);
                         face.primitives.decl_queryable(&key_expr, &info, None);
                     }
                 }
@@ -1026,7 +1037,7 @@ pub(crate) fn queries_new_face(tables: &mut Tables, face: &mut Arc<FaceState>) {
                             get_mut_unchecked(face)
                                 .local_qabls
                                 .insert(qabl.clone(), info.clone());
-                            let key_expr = Resource::decl_key(qabl, face);
+                            let key_expr = Resource::decl_retained_key(qabl, face);
                             face.primitives.decl_queryable(&key_expr, &info, None);
                         }
                     }
@@ -1158,6 +1169,7 @@ pub(crate) fn queries_linkstate_change(tables: &mut Tables, zid: &ZenohId, links
                                     dst_face.primitives.forget_queryable(&key_expr, None);
 
                                     get_mut_unchecked(dst_face).local_qabls.remove(res);
+                                    Resource::release_key(res, dst_face);
                                 }
                             } else if Tables::failover_brokering_to(links, ctx.face.zid) {
                                 let dst_face = &mut get_mut_unchecked(ctx).face;
@@ -1165,7 +1177,7 @@ pub(crate) fn queries_linkstate_change(tables: &mut Tables, zid: &ZenohId, links
                                 get_mut_unchecked(dst_face)
                                     .local_qabls
                                     .insert(res.clone(), info.clone());
-                                let key_expr = Resource::decl_key(res, dst_face);
+                                let key_expr = Resource::decl_retained_key(res, dst_face);
                                 dst_face.primitives.decl_queryable(&key_expr, &info, None);
                             }
                         }
@@ -1560,7 +1572,7 @@ fn should_route(
     outface: &Arc<FaceState>,
     expr: &mut RoutingExpr,
 ) -> bool {
-    if src_face.id != outface.id {
+    if src_face.id != outface.id && tables.may_forward(src_face, outface) {
         let dst_master = tables.whatami != WhatAmI::Router
             || outface.whatami != WhatAmI::Peer
             || tables.peers_net.is_none()
@@ -1668,7 +1680,10 @@ fn compute_final_route(
                 }
             } else {
                 for qabl in qabls.iter() {
-                    if qabl.direction.0.id != src_face.id && qabl.complete > 0 {
+                    if qabl.direction.0.id != src_face.id
+                        && qabl.complete > 0
+                        && tables.may_forward(src_face, &qabl.direction.0)
+                    {
                         let nb = std::cmp::min(qabl.complete, remaining);
                         route.entry(qabl.direction.0.id).or_insert_with(|| {
                             let mut direction = qabl.direction.clone();
@@ -1685,10 +1700,11 @@ fn compute_final_route(
             route
         }
         QueryTarget::BestMatching => {
-            if let Some(qabl) = qabls
-                .iter()
-                .find(|qabl| qabl.direction.0.id != src_face.id && qabl.complete > 0)
-            {
+            if let Some(qabl) = qabls.iter().find(|qabl| {
+                qabl.direction.0.id != src_face.id
+                    && qabl.complete > 0
+                    && tables.may_forward(src_face, &qabl.direction.0)
+            }) {
                 let mut route = HashMap::new();
                 #[cfg(feature = "complete_n")]
                 {
@@ -1882,6 +1898,9 @@ pub fn route_query(
     routing_context: Option<RoutingContext>,
 ) {
     let rtables = zread!(tables_ref.tables);
+    // a traced query records each node it goes through
+    let traced_parameters = crate::query::append_trace_hop(parameters, &rtables.zid);
+    let parameters = traced_parameters.as_deref().unwrap_or(parameters);
     match rtables.get_mapping(face, &expr.scope) {
         Some(prefix) => {
             log::debug!(
@@ -1900,8 +1919,12 @@ pub fn route_query(
                 || rtables.zid
                     == *rtables.elect_router(expr.full_expr(), rtables.get_router_links(face.zid))
             {
+                #[cfg(feature = "stats")]
+                let lookup = std::time::Instant::now();
                 let res = Resource::get_resource(&prefix, expr.suffix);
                 let route = get_query_route(&rtables, face, &res, &mut expr, routing_context);
+                #[cfg(feature = "stats")]
+                rtables.query_route_latency.record(lookup.elapsed());
 
                 let query = Arc::new(Query {
                     src_face: face.clone(),
@@ -1919,7 +1942,7 @@ pub fn route_query(
                 for (expr, payload) in local_replies {
                     face.primitives
                         .clone()
-                        .send_reply_data(qid, zid, expr, None, payload);
+                        .send_reply_data(qid, zid, expr, None, payload, None);
                 }
 
                 if route.is_empty() {
@@ -2006,6 +2029,7 @@ pub(crate) fn route_send_reply_data(
     key_expr: WireExpr,
     info: Option<DataInfo>,
     payload: ZBuf,
+    attachment: Option<Attachment>,
 ) {
     let queries_lock = zread!(tables_ref.queries_lock);
     match face.pending_queries.get(&qid) {
@@ -2017,6 +2041,7 @@ pub(crate) fn route_send_reply_data(
                 key_expr,
                 info,
                 payload,
+                attachment,
             );
         }
         None => log::warn!(
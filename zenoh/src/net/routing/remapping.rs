@@ -0,0 +1,339 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use zenoh_buffers::ZBuf;
+use zenoh_config::KeyRemappingRule;
+use zenoh_core::{zread, zwrite};
+use zenoh_protocol::{
+    common::Attachment,
+    core::{
+        key_expr::OwnedKeyExpr, Channel, CongestionControl, ConsolidationMode, QueryTarget,
+        QueryableInfo, SubInfo, WireExpr, ZInt, ZenohId,
+    },
+    zenoh::{DataInfo, QueryBody, RoutingContext},
+};
+use zenoh_result::{bail, ZResult};
+use zenoh_transport::Primitives;
+
+/// The key remapping rules configured on a router, from which the remappers of each face are built.
+#[derive(Clone, Default)]
+pub struct KeyRemappings {
+    rules: Vec<KeyRemappingRule>,
+}
+
+impl KeyRemappings {
+    pub fn new(rules: Vec<KeyRemappingRule>) -> ZResult<Self> {
+        for rule in &rules {
+            if rule.from.is_wild() || rule.to.is_wild() {
+                bail!(
+                    "Invalid remapping rule {} -> {}: prefixes can't contain wildcards",
+                    rule.from,
+                    rule.to
+                );
+            }
+        }
+        Ok(KeyRemappings { rules })
+    }
+
+    fn rules_for(&self, zid: &ZenohId) -> impl Iterator<Item = &KeyRemappingRule> {
+        self.rules
+            .iter()
+            .filter(move |rule| rule.zids.is_empty() || rule.zids.contains(zid))
+    }
+
+    /// The prefix substitutions to apply to the key expressions received from `zid`.
+    pub(crate) fn ingress(&self, zid: &ZenohId) -> Vec<(OwnedKeyExpr, OwnedKeyExpr)> {
+        self.rules_for(zid)
+            .map(|rule| (rule.from.clone(), rule.to.clone()))
+            .collect()
+    }
+
+    /// The prefix substitutions to apply to the key expressions sent to `zid`.
+    pub(crate) fn egress(&self, zid: &ZenohId) -> Vec<(OwnedKeyExpr, OwnedKeyExpr)> {
+        self.rules_for(zid)
+            .map(|rule| (rule.to.clone(), rule.from.clone()))
+            .collect()
+    }
+}
+
+/// Rewrites the prefixes of the key expressions passed to some [`Primitives`].
+///
+/// Scoped key expressions are kept as they are when their resource was rewritten consistently,
+/// and are sent complete and unscoped otherwise.
+pub(crate) struct KeyRemapper<P: Primitives> {
+    substitutions: Vec<(OwnedKeyExpr, OwnedKeyExpr)>,
+    resources: RwLock<HashMap<ZInt, String>>,
+    inner: P,
+}
+
+impl<P: Primitives> KeyRemapper<P> {
+    pub(crate) fn new(substitutions: Vec<(OwnedKeyExpr, OwnedKeyExpr)>, inner: P) -> Self {
+        KeyRemapper {
+            substitutions,
+            resources: RwLock::new(HashMap::new()),
+            inner,
+        }
+    }
+
+    fn substitute<'a>(&self, key_expr: &'a str) -> Cow<'a, str> {
+        for (from, to) in &self.substitutions {
+            if let Some(rest) = key_expr.strip_prefix(from.as_str()) {
+                if rest.is_empty() || rest.starts_with('/') {
+                    return Cow::Owned(format!("{}{}", to, rest));
+                }
+            }
+        }
+        Cow::Borrowed(key_expr)
+    }
+
+    fn remap<'a>(&self, key_expr: &'a WireExpr) -> WireExpr<'a> {
+        if self.substitutions.is_empty() {
+            return WireExpr {
+                scope: key_expr.scope,
+                suffix: Cow::Borrowed(key_expr.suffix.as_ref()),
+            };
+        }
+        if key_expr.scope == 0 {
+            return WireExpr {
+                scope: 0,
+                suffix: self.substitute(&key_expr.suffix),
+            };
+        }
+        let resources = zread!(self.resources);
+        let prefix = match resources.get(&key_expr.scope) {
+            Some(prefix) => prefix,
+            None => {
+                // unknown to this remapper: let the next hop deal with it
+                return WireExpr {
+                    scope: key_expr.scope,
+                    suffix: Cow::Borrowed(key_expr.suffix.as_ref()),
+                };
+            }
+        };
+        let full = format!("{}{}", prefix, key_expr.suffix);
+        let remapped = self.substitute(&full);
+        if *remapped == format!("{}{}", self.substitute(prefix), key_expr.suffix) {
+            WireExpr {
+                scope: key_expr.scope,
+                suffix: Cow::Borrowed(key_expr.suffix.as_ref()),
+            }
+        } else {
+            WireExpr {
+                scope: 0,
+                suffix: Cow::Owned(remapped.into_owned()),
+            }
+        }
+    }
+}
+
+impl<P: Primitives> Primitives for KeyRemapper<P> {
+    fn decl_resource(&self, expr_id: ZInt, key_expr: &WireExpr) {
+        if self.substitutions.is_empty() {
+            return self.inner.decl_resource(expr_id, key_expr);
+        }
+        let full = if key_expr.scope == 0 {
+            Some(key_expr.suffix.to_string())
+        } else {
+            zread!(self.resources)
+                .get(&key_expr.scope)
+                .map(|prefix| format!("{}{}", prefix, key_expr.suffix))
+        };
+        match full {
+            Some(full) => {
+                let remapped = WireExpr {
+                    scope: 0,
+                    suffix: Cow::Owned(self.substitute(&full).into_owned()),
+                };
+                zwrite!(self.resources).insert(expr_id, full);
+                self.inner.decl_resource(expr_id, &remapped)
+            }
+            None => self.inner.decl_resource(expr_id, key_expr),
+        }
+    }
+
+    fn forget_resource(&self, expr_id: ZInt) {
+        if !self.substitutions.is_empty() {
+            zwrite!(self.resources).remove(&expr_id);
+        }
+        self.inner.forget_resource(expr_id)
+    }
+
+    fn decl_publisher(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
+        self.inner
+            .decl_publisher(&self.remap(key_expr), routing_context)
+    }
+
+    fn forget_publisher(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
+        self.inner
+            .forget_publisher(&self.remap(key_expr), routing_context)
+    }
+
+    fn decl_subscriber(
+        &self,
+        key_expr: &WireExpr,
+        sub_info: &SubInfo,
+        routing_context: Option<RoutingContext>,
+    ) {
+        self.inner
+            .decl_subscriber(&self.remap(key_expr), sub_info, routing_context)
+    }
+
+    fn forget_subscriber(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
+        self.inner
+            .forget_subscriber(&self.remap(key_expr), routing_context)
+    }
+
+    fn decl_queryable(
+        &self,
+        key_expr: &WireExpr,
+        qabl_info: &QueryableInfo,
+        routing_context: Option<RoutingContext>,
+    ) {
+        self.inner
+            .decl_queryable(&self.remap(key_expr), qabl_info, routing_context)
+    }
+
+    fn forget_queryable(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
+        self.inner
+            .forget_queryable(&self.remap(key_expr), routing_context)
+    }
+
+    fn send_data(
+        &self,
+        key_expr: &WireExpr,
+        payload: ZBuf,
+        channel: Channel,
+        congestion_control: CongestionControl,
+        data_info: Option<DataInfo>,
+        routing_context: Option<RoutingContext>,
+    ) {
+        self.inner.send_data(
+            &self.remap(key_expr),
+            payload,
+            channel,
+            congestion_control,
+            data_info,
+            routing_context,
+        )
+    }
+
+    fn send_query(
+        &self,
+        key_expr: &WireExpr,
+        parameters: &str,
+        qid: ZInt,
+        target: QueryTarget,
+        consolidation: ConsolidationMode,
+        body: Option<QueryBody>,
+        routing_context: Option<RoutingContext>,
+    ) {
+        self.inner.send_query(
+            &self.remap(key_expr),
+            parameters,
+            qid,
+            target,
+            consolidation,
+            body,
+            routing_context,
+        )
+    }
+
+    fn send_reply_data(
+        &self,
+        qid: ZInt,
+        replier_id: ZenohId,
+        key_expr: WireExpr,
+        info: Option<DataInfo>,
+        payload: ZBuf,
+        attachment: Option<Attachment>,
+    ) {
+        let key_expr = self.remap(&key_expr).to_owned();
+        self.inner
+            .send_reply_data(qid, replier_id, key_expr, info, payload, attachment)
+    }
+
+    fn send_reply_final(&self, qid: ZInt) {
+        self.inner.send_reply_final(qid)
+    }
+
+    fn send_pull(
+        &self,
+        is_final: bool,
+        key_expr: &WireExpr,
+        pull_id: ZInt,
+        max_samples: &Option<ZInt>,
+    ) {
+        self.inner
+            .send_pull(is_final, &self.remap(key_expr), pull_id, max_samples)
+    }
+
+    fn send_close(&self) {
+        self.inner.send_close()
+    }
+
+    fn start_batch(&self) {
+        self.inner.start_batch()
+    }
+
+    fn end_batch(&self) {
+        self.inner.end_batch()
+    }
+
+    fn decl_interest(&self, key_expr: &WireExpr) {
+        self.inner.decl_interest(&self.remap(key_expr))
+    }
+}
+
+#[test]
+fn key_remapper() {
+    use zenoh_transport::DummyPrimitives;
+
+    let remapper = KeyRemapper::new(
+        vec![(
+            OwnedKeyExpr::new("factoryA").unwrap(),
+            OwnedKeyExpr::new("site1/factoryA").unwrap(),
+        )],
+        DummyPrimitives::new(),
+    );
+    assert_eq!(remapper.substitute("factoryA/**"), "site1/factoryA/**");
+    assert_eq!(remapper.substitute("factoryA"), "site1/factoryA");
+    assert_eq!(remapper.substitute("factoryAB/x"), "factoryAB/x");
+    assert_eq!(remapper.substitute("**"), "**");
+
+    // scoped key expressions are kept when their resource is rewritten consistently
+    remapper.decl_resource(1, &"factoryA".into());
+    let key_expr = remapper.remap(&WireExpr::from(1).with_suffix("/x"));
+    assert_eq!((key_expr.scope, key_expr.suffix.as_ref()), (1, "/x"));
+    // and sent unscoped otherwise
+    let key_expr = remapper.remap(&WireExpr::from(1).with_suffix("B/x"));
+    assert_eq!(
+        (key_expr.scope, key_expr.suffix.as_ref()),
+        (0, "factoryAB/x")
+    );
+    remapper.decl_resource(2, &"fact".into());
+    let key_expr = remapper.remap(&WireExpr::from(2).with_suffix("oryA/x"));
+    assert_eq!(
+        (key_expr.scope, key_expr.suffix.as_ref()),
+        (0, "site1/factoryA/x")
+    );
+
+    assert!(KeyRemappings::new(vec![KeyRemappingRule {
+        zids: vec![],
+        from: OwnedKeyExpr::new("factoryA/*").unwrap(),
+        to: OwnedKeyExpr::new("site1").unwrap(),
+    }])
+    .is_err());
+}
@@ -467,6 +467,54 @@ impl Resource {
         }
     }
 
+    /// Forgets the resource mapped to `expr_id` on `face`, letting it be cleaned.
+    pub(super) fn forget_local_mapping(face: &mut Arc<FaceState>, expr_id: ZInt) {
+        let face_id = face.id;
+        let mutface = get_mut_unchecked(face);
+        mutface.local_mappings_recency.remove(expr_id);
+        mutface.local_mappings_refs.remove(&expr_id);
+        if let Some(mut res) = mutface.local_mappings.remove(&expr_id) {
+            log::debug!(
+                "Forget mapping {} of {} on face {}",
+                expr_id,
+                res.expr(),
+                face_id
+            );
+            if let Some(ctx) = get_mut_unchecked(&mut res).session_ctxs.get_mut(&face_id) {
+                get_mut_unchecked(ctx).local_expr_id = None;
+            }
+            Resource::invalidate_data_routes(&mut res);
+            face.primitives.forget_resource(expr_id);
+            Resource::clean(&mut res);
+        }
+    }
+
+    // the cached data routes of `res` and its childs may use a forgotten mapping
+    fn invalidate_data_routes(res: &mut Arc<Resource>) {
+        let res = get_mut_unchecked(res);
+        if let Some(ctx) = res.context.as_mut() {
+            ctx.valid_data_routes = false;
+        }
+        for child in res.childs.values_mut() {
+            Resource::invalidate_data_routes(child);
+        }
+    }
+
+    // forget the least recently used mappings of `face` (except `keep`) above its maximum,
+    // the ones retained by declarations are never forgotten
+    fn evict_local_mappings(face: &mut Arc<FaceState>, keep: ZInt) {
+        if let Some(max) = face.max_local_mappings {
+            while face.local_mappings.len() > max.max(1) {
+                match face.local_mappings_recency.least_recent() {
+                    Some(expr_id) if expr_id != keep => {
+                        Resource::forget_local_mapping(face, expr_id)
+                    }
+                    _ => break,
+                }
+            }
+        }
+    }
+
     #[inline]
     pub fn decl_key(res: &Arc<Resource>, face: &mut Arc<FaceState>) -> WireExpr<'static> {
         let (nonwild_prefix, wildsuffix) = Resource::nonwild_prefix(res);
@@ -496,9 +544,17 @@ impl Resource {
                             .insert(expr_id, nonwild_prefix.clone());
                         face.primitives
                             .decl_resource(expr_id, &nonwild_prefix.expr().into());
+                        Resource::evict_local_mappings(face, expr_id);
                         expr_id
                     }
                 };
+                if face.local_mappings.contains_key(&expr_id)
+                    && !face.local_mappings_refs.contains_key(&expr_id)
+                {
+                    get_mut_unchecked(face)
+                        .local_mappings_recency
+                        .touch(expr_id);
+                }
                 WireExpr {
                     scope: expr_id,
                     suffix: wildsuffix.into(),
@@ -508,6 +564,47 @@ impl Resource {
         }
     }
 
+    // the local mapping of the non wild prefix of `res` on `face`
+    fn local_mapping(res: &Arc<Resource>, face: &FaceState) -> Option<ZInt> {
+        Resource::nonwild_prefix(res).0.and_then(|prefix| {
+            prefix
+                .session_ctxs
+                .get(&face.id)
+                .and_then(|ctx| ctx.local_expr_id)
+        })
+    }
+
+    /// Like [`Resource::decl_key`], for a subscriber or a queryable declared to `face`:
+    /// the mapping it uses is retained until the declaration is released.
+    pub(super) fn decl_retained_key(
+        res: &Arc<Resource>,
+        face: &mut Arc<FaceState>,
+    ) -> WireExpr<'static> {
+        let key_expr = Resource::decl_key(res, face);
+        if let Some(expr_id) = Resource::local_mapping(res, face) {
+            let mutface = get_mut_unchecked(face);
+            mutface.local_mappings_recency.remove(expr_id);
+            *mutface.local_mappings_refs.entry(expr_id).or_insert(0) += 1;
+        }
+        key_expr
+    }
+
+    /// Releases the mapping retained by the declaration of `res` to `face`,
+    /// forgetting it once no other declaration retains it.
+    pub(super) fn release_key(res: &Arc<Resource>, face: &mut Arc<FaceState>) {
+        if let Some(expr_id) = Resource::local_mapping(res, face) {
+            if let Some(refs) = get_mut_unchecked(face)
+                .local_mappings_refs
+                .get_mut(&expr_id)
+            {
+                *refs -= 1;
+                if *refs == 0 {
+                    Resource::forget_local_mapping(face, expr_id);
+                }
+            }
+        }
+    }
+
     #[inline]
     pub fn get_best_key<'a>(prefix: &Arc<Resource>, suffix: &'a str, sid: usize) -> WireExpr<'a> {
         fn get_best_key_<'a>(
@@ -714,6 +811,9 @@ pub fn register_expr(
                     get_mut_unchecked(face)
                         .local_mappings
                         .insert(local_expr_id, res.clone());
+                    get_mut_unchecked(face)
+                        .local_mappings_recency
+                        .touch(local_expr_id);
 
                     face.primitives
                         .decl_resource(local_expr_id, &res.expr().into());
@@ -12,26 +12,33 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 use super::face::{Face, FaceState};
+use super::mirror::DataMirror;
 use super::network::{shared_nodes, Network};
 pub use super::pubsub::*;
 pub use super::queries::*;
+use super::remapping::{KeyRemapper, KeyRemappings};
 pub use super::resource::*;
 use super::runtime::Runtime;
+use super::talkers::TopTalkers;
 use async_std::task::JoinHandle;
 use std::any::Any;
 use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet};
 use std::hash::Hasher;
+use std::sync::atomic::Ordering;
 use std::sync::{Arc, Weak};
 use std::sync::{Mutex, RwLock};
 use std::time::Duration;
 use uhlc::HLC;
 use zenoh_config::whatami::WhatAmIMatcher;
+use zenoh_config::FutureTimestampPolicy;
 use zenoh_link::Link;
 use zenoh_protocol::{
-    core::{WhatAmI, ZInt, ZenohId},
+    core::{key_expr::OwnedKeyExpr, WhatAmI, ZInt, ZenohId},
     zenoh::{ZenohBody, ZenohMessage},
 };
+#[cfg(feature = "stats")]
+use zenoh_transport::LatencyHistogram;
 use zenoh_transport::{DeMux, Mux, Primitives, TransportPeerEventHandler, TransportUnicast};
 // use zenoh_collections::Timer;
 use zenoh_core::zconfigurable;
@@ -73,8 +80,19 @@ pub struct Tables {
     face_counter: usize,
     #[allow(dead_code)]
     pub(crate) hlc: Option<Arc<HLC>>,
-    pub(crate) drop_future_timestamp: bool,
+    pub(crate) future_timestamp_policy: FutureTimestampPolicy,
     pub(crate) router_peers_failover_brokering: bool,
+    // the maximum number of resources mapped to each face
+    pub(crate) max_local_mappings: Option<usize>,
+    // the number of samples routed to a face on a key expression above which it is mapped to a resource id
+    pub(crate) hot_key_threshold: Option<usize>,
+    // whether the declarations sent to clients wait for and are restricted to their interests
+    pub(crate) client_interests: bool,
+    // the interests declared to the routers when this node is a client
+    pub(crate) interests: Vec<OwnedKeyExpr>,
+    // the samples duplicated onto the @debug key expressions
+    pub(crate) mirror: DataMirror,
+    pub(crate) talkers: TopTalkers,
     // pub(crate) timer: Timer,
     // pub(crate) queries_default_timeout: Duration,
     pub(crate) root_res: Arc<Resource>,
@@ -89,6 +107,11 @@ pub struct Tables {
     pub(crate) shared_nodes: Vec<ZenohId>,
     pub(crate) routers_trees_task: Option<JoinHandle<()>>,
     pub(crate) peers_trees_task: Option<JoinHandle<()>>,
+    // the time spent computing the routes of data and queries
+    #[cfg(feature = "stats")]
+    pub(crate) data_route_latency: LatencyHistogram,
+    #[cfg(feature = "stats")]
+    pub(crate) query_route_latency: LatencyHistogram,
 }
 
 impl Tables {
@@ -96,7 +119,7 @@ impl Tables {
         zid: ZenohId,
         whatami: WhatAmI,
         hlc: Option<Arc<HLC>>,
-        drop_future_timestamp: bool,
+        future_timestamp_policy: FutureTimestampPolicy,
         router_peers_failover_brokering: bool,
         _queries_default_timeout: Duration,
     ) -> Self {
@@ -105,8 +128,14 @@ impl Tables {
             whatami,
             face_counter: 0,
             hlc,
-            drop_future_timestamp,
+            future_timestamp_policy,
             router_peers_failover_brokering,
+            max_local_mappings: None,
+            hot_key_threshold: None,
+            client_interests: false,
+            interests: vec![],
+            mirror: DataMirror::default(),
+            talkers: TopTalkers::default(),
             // timer: Timer::new(true),
             // queries_default_timeout,
             root_res: Resource::root(),
@@ -121,6 +150,10 @@ impl Tables {
             shared_nodes: vec![],
             routers_trees_task: None,
             peers_trees_task: None,
+            #[cfg(feature = "stats")]
+            data_route_latency: LatencyHistogram::new(),
+            #[cfg(feature = "stats")]
+            query_route_latency: LatencyHistogram::new(),
         }
     }
 
@@ -233,6 +266,15 @@ impl Tables {
     }
 
     #[inline]
+    /// Whether this node may forward the traffic coming from `src` to `dst`.
+    ///
+    /// Routers and peers forward the traffic of remote nodes,
+    /// while clients only serve their local sessions and never forward third-party traffic.
+    #[inline]
+    pub(crate) fn may_forward(&self, src: &FaceState, dst: &FaceState) -> bool {
+        self.whatami != WhatAmI::Client || src.local || dst.local
+    }
+
     pub(crate) fn failover_brokering(&self, peer1: ZenohId, peer2: ZenohId) -> bool {
         self.router_peers_failover_brokering
             && self
@@ -242,22 +284,44 @@ impl Tables {
                 .unwrap_or(false)
     }
 
-    fn open_net_face(
+    pub(crate) fn open_net_face(
         &mut self,
         zid: ZenohId,
         whatami: WhatAmI,
         primitives: Arc<dyn Primitives + Send + Sync>,
         link_id: usize,
+        local: bool,
     ) -> Weak<FaceState> {
         let fid = self.face_counter;
         self.face_counter += 1;
         let mut newface = self
             .faces
             .entry(fid)
-            .or_insert_with(|| FaceState::new(fid, zid, whatami, primitives.clone(), link_id))
+            .or_insert_with(|| {
+                FaceState::new(
+                    fid,
+                    zid,
+                    whatami,
+                    primitives.clone(),
+                    link_id,
+                    self.max_local_mappings,
+                    local,
+                )
+            })
             .clone();
         log::debug!("New {}", newface);
 
+        if self.whatami == WhatAmI::Client && whatami != WhatAmI::Client {
+            for interest in &self.interests {
+                newface.primitives.decl_interest(&interest.into());
+            }
+        }
+        // remote clients declare their interests before being sent declarations
+        let pending_sync = !local
+            && self.whatami == WhatAmI::Router
+            && whatami == WhatAmI::Client
+            && self.client_interests;
+        newface.pending_sync.store(pending_sync, Ordering::Release);
         pubsub_new_face(self, &mut newface);
         queries_new_face(self, &mut newface);
 
@@ -270,7 +334,7 @@ impl Tables {
         whatami: WhatAmI,
         primitives: Arc<dyn Primitives + Send + Sync>,
     ) -> Weak<FaceState> {
-        self.open_net_face(zid, whatami, primitives, 0)
+        self.open_net_face(zid, whatami, primitives, 0, true)
     }
 
     fn compute_routes(&mut self, res: &mut Arc<Resource>) {
@@ -433,29 +497,46 @@ pub struct TablesLock {
 
 pub struct Router {
     whatami: WhatAmI,
+    remappings: KeyRemappings,
     pub tables: Arc<TablesLock>,
 }
 
 impl Router {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         zid: ZenohId,
         whatami: WhatAmI,
         hlc: Option<Arc<HLC>>,
-        drop_future_timestamp: bool,
+        future_timestamp_policy: FutureTimestampPolicy,
         router_peers_failover_brokering: bool,
         queries_default_timeout: Duration,
+        remappings: KeyRemappings,
+        max_local_mappings: Option<usize>,
+        hot_key_threshold: Option<usize>,
+        client_interests: bool,
+        interests: Vec<OwnedKeyExpr>,
+        mirror: DataMirror,
+        talkers: TopTalkers,
     ) -> Self {
+        let mut tables = Tables::new(
+            zid,
+            whatami,
+            hlc,
+            future_timestamp_policy,
+            router_peers_failover_brokering,
+            queries_default_timeout,
+        );
+        tables.max_local_mappings = max_local_mappings;
+        tables.hot_key_threshold = hot_key_threshold;
+        tables.client_interests = client_interests;
+        tables.interests = interests;
+        tables.mirror = mirror;
+        tables.talkers = talkers;
         Router {
             whatami,
+            remappings,
             tables: Arc::new(TablesLock {
-                tables: RwLock::new(Tables::new(
-                    zid,
-                    whatami,
-                    hlc,
-                    drop_future_timestamp,
-                    router_peers_failover_brokering,
-                    queries_default_timeout,
-                )),
+                tables: RwLock::new(tables),
                 ctrl_lock: Mutex::new(()),
                 queries_lock: RwLock::new(()),
             }),
@@ -557,21 +638,24 @@ impl Router {
             );
         }
 
+        let zid = transport.get_zid().unwrap();
+        let egress = self.remappings.egress(&zid);
+        let primitives: Arc<dyn Primitives + Send + Sync> = if egress.is_empty() {
+            Arc::new(Mux::new(transport.clone()))
+        } else {
+            Arc::new(KeyRemapper::new(egress, Mux::new(transport.clone())))
+        };
         let handler = Arc::new(LinkStateInterceptor::new(
-            transport.clone(),
+            transport,
             self.tables.clone(),
             Face {
                 tables: self.tables.clone(),
                 state: tables
-                    .open_net_face(
-                        transport.get_zid().unwrap(),
-                        whatami,
-                        Arc::new(Mux::new(transport)),
-                        link_id,
-                    )
+                    .open_net_face(zid, whatami, primitives, link_id, false)
                     .upgrade()
                     .unwrap(),
             },
+            self.remappings.ingress(&zid),
         ));
 
         match (self.whatami, whatami) {
@@ -597,16 +681,21 @@ pub struct LinkStateInterceptor {
     pub(crate) transport: TransportUnicast,
     pub(crate) tables: Arc<TablesLock>,
     pub(crate) face: Face,
-    pub(crate) demux: DeMux<Face>,
+    pub(crate) demux: DeMux<KeyRemapper<Face>>,
 }
 
 impl LinkStateInterceptor {
-    fn new(transport: TransportUnicast, tables: Arc<TablesLock>, face: Face) -> Self {
+    fn new(
+        transport: TransportUnicast,
+        tables: Arc<TablesLock>,
+        face: Face,
+        ingress: Vec<(OwnedKeyExpr, OwnedKeyExpr)>,
+    ) -> Self {
         LinkStateInterceptor {
             transport,
             tables,
             face: face.clone(),
-            demux: DeMux::new(face),
+            demux: DeMux::new(KeyRemapper::new(ingress, face)),
         }
     }
 }
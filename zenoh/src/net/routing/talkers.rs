@@ -0,0 +1,140 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use zenoh_core::zlock;
+
+/// The upper bounds (inclusive) of the buckets of the payload size histogram,
+/// a last bucket holding the larger payloads.
+pub const SIZE_BUCKETS: [usize; 8] = [64, 256, 1_024, 4_096, 16_384, 65_536, 262_144, 1_048_576];
+
+/// The prefix the samples are accounted on once the maximum number of prefixes is tracked.
+pub const OTHER_PREFIX: &str = "**";
+
+/// The samples routed on a key prefix.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TalkerStats {
+    pub samples: u64,
+    pub bytes: u64,
+}
+
+/// Accounts the samples routed by this node per key prefix (i.e. the first `depth` chunks of
+/// their key), to find the keys dominating the bandwidth, as well as the distribution of their
+/// payload sizes. The number of prefixes tracked is bounded by `max_prefixes`.
+pub struct TopTalkers {
+    depth: usize,
+    max_prefixes: usize,
+    prefixes: Mutex<HashMap<String, TalkerStats>>,
+    sizes: [AtomicU64; SIZE_BUCKETS.len() + 1],
+}
+
+impl TopTalkers {
+    pub fn new(depth: usize, max_prefixes: usize) -> Self {
+        TopTalkers {
+            depth,
+            max_prefixes,
+            prefixes: Mutex::new(HashMap::new()),
+            sizes: Default::default(),
+        }
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.depth > 0
+    }
+
+    fn prefix<'a>(&self, key: &'a str) -> &'a str {
+        match key.match_indices('/').nth(self.depth - 1) {
+            Some((i, _)) => &key[..i],
+            None => key,
+        }
+    }
+
+    /// Account a sample with a payload of `size` bytes routed on `key`.
+    pub fn record(&self, key: &str, size: usize) {
+        let bucket = SIZE_BUCKETS
+            .iter()
+            .position(|bound| size <= *bound)
+            .unwrap_or(SIZE_BUCKETS.len());
+        self.sizes[bucket].fetch_add(1, Ordering::Relaxed);
+
+        let prefix = self.prefix(key);
+        let mut prefixes = zlock!(self.prefixes);
+        let stats = match prefixes.get_mut(prefix) {
+            Some(stats) => stats,
+            None if prefixes.len() < self.max_prefixes => {
+                prefixes.entry(prefix.to_string()).or_default()
+            }
+            None => prefixes.entry(OTHER_PREFIX.to_string()).or_default(),
+        };
+        stats.samples += 1;
+        stats.bytes += size as u64;
+    }
+
+    /// The `limit` prefixes on which the most bytes were routed, in decreasing order.
+    pub fn top(&self, limit: usize) -> Vec<(String, TalkerStats)> {
+        let mut top: Vec<(String, TalkerStats)> = zlock!(self.prefixes)
+            .iter()
+            .map(|(prefix, stats)| (prefix.clone(), *stats))
+            .collect();
+        top.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes).then_with(|| a.0.cmp(&b.0)));
+        top.truncate(limit);
+        top
+    }
+
+    /// The number of samples in each bucket of [`SIZE_BUCKETS`], the last one holding the larger payloads.
+    pub fn sizes(&self) -> Vec<u64> {
+        self.sizes
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .collect()
+    }
+}
+
+impl Default for TopTalkers {
+    fn default() -> Self {
+        TopTalkers::new(0, 0)
+    }
+}
+
+#[test]
+fn top_talkers() {
+    let talkers = TopTalkers::new(2, 2);
+    assert!(talkers.is_enabled());
+    talkers.record("robot/1/pose", 100);
+    talkers.record("robot/1/pose", 100);
+    talkers.record("robot/2/camera", 100_000);
+    talkers.record("map", 10);
+    // above max_prefixes
+    talkers.record("lidar/1/scan", 2_000_000);
+
+    let top = talkers.top(10);
+    assert_eq!(top.len(), 3);
+    assert_eq!(top[0].0, OTHER_PREFIX);
+    assert_eq!(top[1].0, "robot/2");
+    assert_eq!(
+        top[1].1,
+        TalkerStats {
+            samples: 1,
+            bytes: 100_000
+        }
+    );
+    assert_eq!(top[2].0, "robot/1");
+    assert_eq!(top[2].1.samples, 2);
+    assert_eq!(talkers.top(1).len(), 1);
+
+    assert_eq!(talkers.sizes(), vec![1, 2, 0, 0, 0, 0, 1, 0, 1]);
+    assert!(!TopTalkers::default().is_enabled());
+}
@@ -10,7 +10,9 @@
 //
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
-use super::routing::face::Face;
+use super::audit::AuditEvent;
+use super::routing::face::{routed_from, Face};
+use super::routing::talkers::SIZE_BUCKETS;
 use super::Runtime;
 use crate::key_expr::KeyExpr;
 use crate::plugins::sealed as plugins;
@@ -24,30 +26,143 @@ use serde_json::json;
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::convert::TryInto;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::sync::Mutex;
 use zenoh_buffers::{SplitBuffer, ZBuf};
 use zenoh_config::ValidatedMap;
 use zenoh_core::SyncResolve;
 use zenoh_protocol::{
+    common::Attachment,
     core::{
-        key_expr::OwnedKeyExpr, Channel, CongestionControl, ConsolidationMode, KnownEncoding,
-        QueryTarget, QueryableInfo, SampleKind, SubInfo, WireExpr, ZInt, ZenohId, EMPTY_EXPR_ID,
+        key_expr::{keyexpr, OwnedKeyExpr},
+        Channel, CongestionControl, ConsolidationMode, KnownEncoding, QueryTarget, QueryableInfo,
+        SampleKind, SubInfo, WireExpr, ZInt, ZenohId, EMPTY_EXPR_ID,
     },
     zenoh::{DataInfo, QueryBody, RoutingContext},
 };
-use zenoh_result::ZResult;
+use zenoh_result::{bail, zerror, ZResult};
 use zenoh_transport::{Primitives, TransportUnicast};
 
 pub struct AdminContext {
     runtime: Runtime,
     plugins_mgr: Mutex<plugins::PluginsManager>,
+    // The plugins that panicked, and the ones that were restarted since
+    plugins_failures: Mutex<HashMap<String, PluginFailure>>,
+    plugins_failed: flume::Sender<String>,
     zid_str: String,
     version: String,
 }
 
+struct PluginFailure {
+    // Whether the plugin is currently failed, in which case it's not called anymore
+    failed: bool,
+    reason: String,
+    restarts: usize,
+}
+
 type Handler = Arc<dyn Fn(&AdminContext, Query) + Send + Sync>;
 
+// The number of prefixes listed by `@/router/<zid>/talkers` if no `limit` is given
+const TOP_TALKERS_LIMIT: usize = 10;
+
+// The parameter of the queries selecting the serialization of the replies
+const FORMAT_PARAMETER: &str = "_format";
+const CBOR_ENCODING: &str = "application/cbor";
+
+/// The serializations of the admin space replies, selected with the `_format` parameter
+/// of the queries: `json` (the default), `cbor` or `text` (one `path = value` line per field).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReplyFormat {
+    Json,
+    Cbor,
+    Text,
+}
+
+impl FromStr for ReplyFormat {
+    type Err = zenoh_result::Error;
+
+    fn from_str(s: &str) -> ZResult<Self> {
+        match s {
+            "json" => Ok(ReplyFormat::Json),
+            "cbor" => Ok(ReplyFormat::Cbor),
+            "text" => Ok(ReplyFormat::Text),
+            _ => bail!(
+                "Unsupported {} '{}': expected json, cbor or text",
+                FORMAT_PARAMETER,
+                s
+            ),
+        }
+    }
+}
+
+impl ReplyFormat {
+    fn of(query: &Query) -> ZResult<Self> {
+        match crate::prelude::Parameters::decode(&query.selector())
+            .find(|(k, _)| k.as_ref() == FORMAT_PARAMETER)
+        {
+            Some((_, v)) => v.parse(),
+            None => Ok(ReplyFormat::Json),
+        }
+    }
+
+    fn serialize(self, json: &serde_json::Value) -> ZResult<Value> {
+        Ok(match self {
+            ReplyFormat::Json => Value::from(json.to_string().as_bytes().to_vec())
+                .encoding(KnownEncoding::AppJson.into()),
+            ReplyFormat::Cbor => {
+                let mut bytes = vec![];
+                ciborium::ser::into_writer(json, &mut bytes).map_err(|e| zerror!("{}", e))?;
+                Value::from(bytes).encoding(CBOR_ENCODING.into())
+            }
+            ReplyFormat::Text => {
+                let mut text = String::new();
+                write_text(&mut text, "", json);
+                Value::from(text.as_bytes().to_vec()).encoding(KnownEncoding::TextPlain.into())
+            }
+        })
+    }
+}
+
+// Render a JSON value as one `path = value` line per field
+fn write_text(text: &mut String, path: &str, json: &serde_json::Value) {
+    use std::fmt::Write;
+
+    let child = |key: &dyn std::fmt::Display| match path {
+        "" => key.to_string(),
+        path => format!("{path}.{key}"),
+    };
+    match json {
+        serde_json::Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                write_text(text, &child(key), value);
+            }
+        }
+        serde_json::Value::Array(values) if !values.is_empty() => {
+            for (i, value) in values.iter().enumerate() {
+                write_text(text, &child(&i), value);
+            }
+        }
+        serde_json::Value::String(value) => {
+            let _ = writeln!(text, "{path} = {value}");
+        }
+        value => {
+            let _ = writeln!(text, "{path} = {value}");
+        }
+    }
+}
+
+// Reply to an admin space query with a JSON value, serialized as requested by the query
+fn reply_json(query: &Query, key_expr: KeyExpr<'static>, json: &serde_json::Value) {
+    let result = ReplyFormat::of(query)
+        .and_then(|format| format.serialize(json))
+        .map(|value| Sample::new(key_expr, value))
+        .map_err(|e| Value::from(e.to_string()));
+    if let Err(e) = query.reply(result).res() {
+        log::error!("Error sending AdminSpace reply: {:?}", e);
+    }
+}
+
 pub struct AdminSpace {
     zid: ZenohId,
     primitives: Mutex<Option<Arc<Face>>>,
@@ -62,6 +177,17 @@ enum PluginDiff {
     Start(crate::config::PluginLoad),
 }
 
+fn plugin_config(
+    config: &crate::config::Config,
+    name: &str,
+) -> serde_json::Map<String, serde_json::Value> {
+    config
+        .plugin(name)
+        .and_then(|value| value.as_object())
+        .cloned()
+        .unwrap_or_default()
+}
+
 impl AdminSpace {
     pub async fn start(runtime: &Runtime, plugins_mgr: plugins::PluginsManager, version: String) {
         let zid_str = runtime.zid.to_string();
@@ -81,6 +207,10 @@ impl AdminSpace {
                 .unwrap(),
             Arc::new(peers_linkstate_data),
         );
+        handlers.insert(
+            format!("@/router/{zid_str}/session/**").try_into().unwrap(),
+            Arc::new(sessions_data),
+        );
         handlers.insert(
             format!("@/router/{zid_str}/subscriber/**")
                 .try_into()
@@ -93,6 +223,10 @@ impl AdminSpace {
                 .unwrap(),
             Arc::new(queryables_data),
         );
+        handlers.insert(
+            format!("@/router/{zid_str}/talkers").try_into().unwrap(),
+            Arc::new(talkers_data),
+        );
         handlers.insert(
             format!("@/router/{zid_str}/status/plugins/**")
                 .try_into()
@@ -106,9 +240,12 @@ impl AdminSpace {
             .map(|(a, b)| (a.to_string(), b.to_string()))
             .collect::<HashMap<_, _>>();
 
+        let (plugins_failed, failed_rx) = flume::unbounded();
         let context = Arc::new(AdminContext {
             runtime: runtime.clone(),
             plugins_mgr: Mutex::new(plugins_mgr),
+            plugins_failures: Mutex::new(HashMap::new()),
+            plugins_failed,
             zid_str,
             version,
         });
@@ -120,6 +257,58 @@ impl AdminSpace {
             context,
         });
 
+        // The configurations of the running plugins, to notify them of the changes
+        let mut plugins_configs = {
+            let cfg_guard = runtime.config.lock();
+            active_plugins
+                .keys()
+                .map(|name| (name.clone(), plugin_config(&cfg_guard, name)))
+                .collect::<HashMap<_, _>>()
+        };
+
+        // Restart the failed plugins according to their restart policy
+        task::spawn({
+            let admin = admin.clone();
+            async move {
+                while let Ok(name) = failed_rx.recv_async().await {
+                    let policy = admin
+                        .context
+                        .runtime
+                        .config
+                        .lock()
+                        .plugins()
+                        .load_requests()
+                        .find(|request| request.name == name)
+                        .map(|request| request.restart);
+                    let restarts = zlock!(admin.context.plugins_failures)
+                        .get(&name)
+                        .map_or(0, |failure| failure.restarts);
+                    match policy.and_then(|policy| policy.restart_delay(restarts)) {
+                        Some(delay) => {
+                            log::info!("Restarting plugin `{}` in {:?}", name, delay);
+                            let admin = admin.clone();
+                            task::spawn(async move {
+                                task::sleep(delay).await;
+                                restart_plugin(&admin.context, &name);
+                            });
+                        }
+                        None => log::error!("Plugin `{}` failed and won't be restarted", name),
+                    }
+                }
+            }
+        });
+
+        // Isolate the panics of the tasks spawned by the plugins like the ones of their calls
+        task::spawn({
+            let admin = admin.clone();
+            let panics = runtime.plugins_panics.1.clone();
+            async move {
+                while let Ok((name, panic)) = panics.recv_async().await {
+                    plugin_panicked(&admin.context, &name, "running a task", panic);
+                }
+            }
+        });
+
         let cfg_rx = admin.context.runtime.config.subscribe();
         task::spawn({
             let admin = admin.clone();
@@ -159,6 +348,8 @@ impl AdminSpace {
                         match diff {
                             PluginDiff::Delete(plugin) => {
                                 active_plugins.remove(plugin.as_str());
+                                plugins_configs.remove(plugin.as_str());
+                                zlock!(admin.context.plugins_failures).remove(plugin.as_str());
                                 plugins_mgr.stop(&plugin);
                             }
                             PluginDiff::Start(plugin) => {
@@ -188,6 +379,10 @@ impl AdminSpace {
                                                 active_plugins.insert(name.into(), path.into());
                                                 let mut cfg_guard =
                                                     admin.context.runtime.config.lock();
+                                                plugins_configs.insert(
+                                                    name.clone(),
+                                                    plugin_config(&cfg_guard, name),
+                                                );
                                                 cfg_guard.add_plugin_validator(
                                                     name,
                                                     plugin.config_checker(),
@@ -208,13 +403,47 @@ impl AdminSpace {
                             }
                         }
                     }
-                    log::info!("Running plugins: {:?}", &active_plugins)
+                    log::info!("Running plugins: {:?}", &active_plugins);
+
+                    // Notify the running plugin whose configuration changed
+                    let relative = change["plugins".len()..].trim_start_matches('/');
+                    let (name, path) = relative.split_once('/').unwrap_or((relative, ""));
+                    if let (Some(plugin), Some(old)) =
+                        (plugins_mgr.plugin(name), plugins_configs.get_mut(name))
+                    {
+                        let new = plugin_config(&admin.context.runtime.config.lock(), name);
+                        if new != *old && !is_plugin_failed(&admin.context, name) {
+                            match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                                plugin.config_changed(path, old, &new)
+                            })) {
+                                Ok(Ok(())) => {}
+                                Ok(Err(e)) => log::error!(
+                                    "Plugin `{}` failed to apply its new configuration: {}",
+                                    name,
+                                    e
+                                ),
+                                Err(panic) => plugin_panicked(
+                                    &admin.context,
+                                    name,
+                                    "applying its new configuration",
+                                    panic,
+                                ),
+                            }
+                        }
+                        *old = new;
+                    }
                 }
             }
         });
 
         let primitives = runtime.router.new_primitives(admin.clone());
         zlock!(admin.primitives).replace(primitives.clone());
+        runtime.audit.set_publisher(primitives.clone());
+
+        if !*runtime.config.lock().adminspace.enabled() {
+            log::info!("Admin space disabled by configuration (adminspace.enabled=false)");
+            return;
+        }
 
         primitives.decl_queryable(
             &[&root_key, "/**"].concat().into(),
@@ -230,6 +459,12 @@ impl AdminSpace {
             &SubInfo::default(),
             None,
         );
+
+        primitives.decl_subscriber(
+            &[&root_key, "/session/*"].concat().into(),
+            &SubInfo::default(),
+            None,
+        );
     }
 
     pub fn key_expr_to_string<'a>(&self, key_expr: &'a WireExpr) -> ZResult<KeyExpr<'a>> {
@@ -316,16 +551,30 @@ impl Primitives for AdminSpace {
             data_info,
         );
 
-        {
+        let source = routed_from();
+        let allowed = {
             let conf = self.context.runtime.config.lock();
-            if !conf.adminspace.permissions().write {
-                log::error!(
-                    "Received PUT on '{}' but adminspace.permissions.write=false in configuration",
-                    key_expr
-                );
-                return;
+            match self.key_expr_to_string(key_expr) {
+                Ok(key) => conf.adminspace.can_write(&key, source.as_ref()),
+                Err(_) => conf.adminspace.permissions().write,
             }
+        };
+        if !allowed {
+            log::error!(
+                "Received PUT on '{}' but write access is denied by adminspace configuration",
+                key_expr
+            );
+            self.context.runtime.audit.record(AuditEvent::AccessDenied {
+                zid: source,
+                key_expr: key_expr.to_string(),
+                operation: "write",
+            });
+            return;
         }
+        self.context.runtime.audit.record(AuditEvent::AdminWrite {
+            zid: source,
+            key_expr: key_expr.to_string(),
+        });
 
         if let Some(key) = key_expr
             .as_str()
@@ -366,6 +615,27 @@ impl Primitives for AdminSpace {
                     ),
                 }
             }
+        } else if let Some(zid) = key_expr
+            .as_str()
+            .strip_prefix(&format!("@/router/{}/session/", &self.context.zid_str))
+        {
+            if let Some(DataInfo {
+                kind: SampleKind::Delete,
+                ..
+            }) = data_info
+            {
+                match ZenohId::from_str(zid) {
+                    Ok(zid) => {
+                        let runtime = self.context.runtime.clone();
+                        task::spawn(async move {
+                            if let Err(e) = runtime.close_session(&zid).await {
+                                log::error!("Error closing session with {} : {}", zid, e);
+                            }
+                        });
+                    }
+                    Err(e) => error!("Received DELETE on invalid session {} : {}", key_expr, e),
+                }
+            }
         }
     }
 
@@ -388,18 +658,6 @@ impl Primitives for AdminSpace {
         );
         let primitives = zlock!(self.primitives).as_ref().unwrap().clone();
 
-        {
-            let conf = self.context.runtime.config.lock();
-            if !conf.adminspace.permissions().read {
-                log::error!(
-                    "Received GET on '{}' but adminspace.permissions.read=false in configuration",
-                    key_expr
-                );
-                primitives.send_reply_final(qid);
-                return;
-            }
-        }
-
         let key_expr = match self.key_expr_to_string(key_expr) {
             Ok(key_expr) => key_expr.into_owned(),
             Err(e) => {
@@ -409,25 +667,54 @@ impl Primitives for AdminSpace {
             }
         };
 
+        let source = routed_from();
+        let adminspace = self.context.runtime.config.lock().adminspace.clone();
+        if !adminspace.may_read(&key_expr, source.as_ref()) {
+            log::error!(
+                "Received GET on '{}' but read access is denied by adminspace configuration",
+                key_expr
+            );
+            self.context.runtime.audit.record(AuditEvent::AccessDenied {
+                zid: source,
+                key_expr: key_expr.to_string(),
+                operation: "read",
+            });
+            primitives.send_reply_final(qid);
+            return;
+        }
+        let handlers = self
+            .handlers
+            .iter()
+            .filter(|(key, _)| key_expr.intersects(key))
+            .map(|(_, handler)| handler.clone())
+            .collect::<Vec<_>>();
+        // the replies on the keys that may not be read are dropped
+        let primitives = Arc::new(ReadFilter {
+            primitives,
+            adminspace,
+            source,
+            context: self.context.clone(),
+        });
+
         let zid = self.zid;
         let parameters = parameters.to_owned();
+        let (value, attachment) = QueryInner::split_body(body);
         let query = Query {
             inner: Arc::new(QueryInner {
                 key_expr: key_expr.clone(),
                 parameters,
-                value: body.map(|b| {
-                    Value::from(b.payload).encoding(b.data_info.encoding.unwrap_or_default())
-                }),
+                value,
+                attachment,
                 qid,
                 zid,
                 primitives,
             }),
+            permit: None,
+            in_flight: None,
         };
 
-        for (key, handler) in &self.handlers {
-            if key_expr.intersects(key) {
-                handler(&self.context, query.clone());
-            }
+        for handler in handlers {
+            handler(&self.context, query.clone());
         }
     }
 
@@ -438,6 +725,7 @@ impl Primitives for AdminSpace {
         key_expr: WireExpr,
         info: Option<DataInfo>,
         payload: ZBuf,
+        _attachment: Option<Attachment>,
     ) {
         trace!(
             "recv ReplyData {:?} {:?} {:?} {:?} {:?}",
@@ -474,11 +762,158 @@ impl Primitives for AdminSpace {
     }
 }
 
+// The primitives the replies to a query on the admin space are sent on, dropping the ones on the
+// keys that may not be read by the node the query was received from.
+struct ReadFilter {
+    primitives: Arc<Face>,
+    adminspace: zenoh_config::AdminSpaceConf,
+    source: Option<ZenohId>,
+    context: Arc<AdminContext>,
+}
+
+impl Primitives for ReadFilter {
+    fn decl_resource(&self, expr_id: ZInt, key_expr: &WireExpr) {
+        self.primitives.decl_resource(expr_id, key_expr)
+    }
+
+    fn forget_resource(&self, expr_id: ZInt) {
+        self.primitives.forget_resource(expr_id)
+    }
+
+    fn decl_publisher(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
+        self.primitives.decl_publisher(key_expr, routing_context)
+    }
+
+    fn forget_publisher(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
+        self.primitives.forget_publisher(key_expr, routing_context)
+    }
+
+    fn decl_subscriber(
+        &self,
+        key_expr: &WireExpr,
+        sub_info: &SubInfo,
+        routing_context: Option<RoutingContext>,
+    ) {
+        self.primitives
+            .decl_subscriber(key_expr, sub_info, routing_context)
+    }
+
+    fn forget_subscriber(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
+        self.primitives.forget_subscriber(key_expr, routing_context)
+    }
+
+    fn decl_queryable(
+        &self,
+        key_expr: &WireExpr,
+        qabl_info: &QueryableInfo,
+        routing_context: Option<RoutingContext>,
+    ) {
+        self.primitives
+            .decl_queryable(key_expr, qabl_info, routing_context)
+    }
+
+    fn forget_queryable(&self, key_expr: &WireExpr, routing_context: Option<RoutingContext>) {
+        self.primitives.forget_queryable(key_expr, routing_context)
+    }
+
+    fn send_data(
+        &self,
+        key_expr: &WireExpr,
+        payload: ZBuf,
+        channel: Channel,
+        congestion_control: CongestionControl,
+        data_info: Option<DataInfo>,
+        routing_context: Option<RoutingContext>,
+    ) {
+        self.primitives.send_data(
+            key_expr,
+            payload,
+            channel,
+            congestion_control,
+            data_info,
+            routing_context,
+        )
+    }
+
+    fn send_query(
+        &self,
+        key_expr: &WireExpr,
+        parameters: &str,
+        qid: ZInt,
+        target: QueryTarget,
+        consolidation: ConsolidationMode,
+        body: Option<QueryBody>,
+        routing_context: Option<RoutingContext>,
+    ) {
+        self.primitives.send_query(
+            key_expr,
+            parameters,
+            qid,
+            target,
+            consolidation,
+            body,
+            routing_context,
+        )
+    }
+
+    fn send_reply_data(
+        &self,
+        qid: ZInt,
+        replier_id: ZenohId,
+        key_expr: WireExpr,
+        info: Option<DataInfo>,
+        payload: ZBuf,
+        attachment: Option<Attachment>,
+    ) {
+        let allowed = key_expr.scope == EMPTY_EXPR_ID
+            && keyexpr::new(key_expr.suffix.as_ref())
+                .map(|key| self.adminspace.can_read(key, self.source.as_ref()))
+                .unwrap_or(false);
+        if !allowed {
+            log::debug!(
+                "Dropping reply on '{}': read access is denied by adminspace configuration",
+                key_expr
+            );
+            self.context.runtime.audit.record(AuditEvent::AccessDenied {
+                zid: self.source,
+                key_expr: key_expr.to_string(),
+                operation: "read",
+            });
+            return;
+        }
+        self.primitives
+            .send_reply_data(qid, replier_id, key_expr, info, payload, attachment)
+    }
+
+    fn send_reply_final(&self, qid: ZInt) {
+        self.primitives.send_reply_final(qid)
+    }
+
+    fn send_pull(
+        &self,
+        is_final: bool,
+        key_expr: &WireExpr,
+        pull_id: ZInt,
+        max_samples: &Option<ZInt>,
+    ) {
+        self.primitives
+            .send_pull(is_final, key_expr, pull_id, max_samples)
+    }
+
+    fn send_close(&self) {
+        self.primitives.send_close()
+    }
+}
+
 fn router_data(context: &AdminContext, query: Query) {
     let reply_key: OwnedKeyExpr = format!("@/router/{}", context.zid_str).try_into().unwrap();
 
     let transport_mgr = context.runtime.manager().clone();
 
+    #[cfg(feature = "stats")]
+    let stats = crate::prelude::Parameters::decode(&query.selector())
+        .any(|(k, v)| k.as_ref() == "_stats" && v != "false");
+
     // plugins info
     let plugins: serde_json::Value = {
         zlock!(context.plugins_mgr)
@@ -495,6 +930,19 @@ fn router_data(context: &AdminContext, query: Query) {
         .map(|locator| json!(locator.to_string()))
         .collect();
 
+    // listeners info
+    let listeners: Vec<serde_json::Value> = transport_mgr
+        .get_listeners_connections()
+        .iter()
+        .map(|(endpoint, connections, max_connections)| {
+            json!({
+                "endpoint": endpoint.to_locator().to_string(),
+                "connections": connections,
+                "max_connections": max_connections,
+            })
+        })
+        .collect();
+
     // transports info
     let transport_to_json = |transport: &TransportUnicast| {
         #[allow(unused_mut)]
@@ -505,11 +953,10 @@ fn router_data(context: &AdminContext, query: Query) {
                 |_| Vec::new(),
                 |links| links.iter().map(|link| link.dst.to_string()).collect()
             ),
+            "identity": transport.get_identity().ok().flatten(),
         });
         #[cfg(feature = "stats")]
         {
-            let stats = crate::prelude::Parameters::decode(selector)
-                .any(|(k, v)| k.as_ref() == "_stats" && v != "false");
             if stats {
                 json.as_object_mut().unwrap().insert(
                     "stats".to_string(),
@@ -517,6 +964,30 @@ fn router_data(context: &AdminContext, query: Query) {
                         .get_stats()
                         .map_or_else(|_| json!({}), |p| json!(p)),
                 );
+                json.as_object_mut().unwrap().insert(
+                    "queue_latency".to_string(),
+                    transport
+                        .get_queue_latency()
+                        .map_or_else(|_| json!({}), |h| json!(h)),
+                );
+                json.as_object_mut().unwrap().insert(
+                    "queues".to_string(),
+                    transport
+                        .get_queue_stats()
+                        .map_or_else(|_| json!([]), |q| json!(q)),
+                );
+                json.as_object_mut().unwrap().insert(
+                    "bandwidth".to_string(),
+                    transport.get_bandwidth().map_or_else(
+                        |_| json!({}),
+                        |links| {
+                            links
+                                .iter()
+                                .map(|(link, b)| (link.dst.to_string(), json!(b)))
+                                .collect()
+                        },
+                    ),
+                );
             }
         }
         json
@@ -527,24 +998,33 @@ fn router_data(context: &AdminContext, query: Query) {
         .map(transport_to_json)
         .collect();
 
-    let json = json!({
+    let mut json = json!({
         "zid": context.zid_str,
         "version": context.version,
         "locators": locators,
+        "listeners": listeners,
         "sessions": transports,
+        "connectivity": context.runtime.connectivity_state(),
         "plugins": plugins,
     });
-    log::trace!("AdminSpace router_data: {:?}", json);
-    if let Err(e) = query
-        .reply(Ok(Sample::new(
-            reply_key,
-            Value::from(json.to_string().as_bytes().to_vec())
-                .encoding(KnownEncoding::AppJson.into()),
-        )))
-        .res()
-    {
-        log::error!("Error sending AdminSpace reply: {:?}", e);
+    if transport_mgr.is_degraded() {
+        json.as_object_mut()
+            .unwrap()
+            .insert("alarms".to_string(), json!(["fd_exhaustion"]));
+    }
+    #[cfg(feature = "stats")]
+    if stats {
+        let tables = zread!(context.runtime.router.tables.tables);
+        json.as_object_mut().unwrap().insert(
+            "routing".to_string(),
+            json!({
+                "data_route_latency": tables.data_route_latency.snapshot(),
+                "query_route_latency": tables.query_route_latency.snapshot(),
+            }),
+        );
     }
+    log::trace!("AdminSpace router_data: {:?}", json);
+    reply_json(&query, reply_key.into(), &json);
 }
 
 fn routers_linkstate_data(context: &AdminContext, query: Query) {
@@ -601,6 +1081,46 @@ fn peers_linkstate_data(context: &AdminContext, query: Query) {
     }
 }
 
+fn sessions_data(context: &AdminContext, query: Query) {
+    // refreshed from the transport manager on each query
+    for transport in context.runtime.manager().get_transports() {
+        let zid = match transport.get_zid() {
+            Ok(zid) => zid,
+            Err(_) => continue, // closed meanwhile
+        };
+        let key =
+            KeyExpr::try_from(format!("@/router/{}/session/{}", context.zid_str, zid)).unwrap();
+        if !query.key_expr().intersects(&key) {
+            continue;
+        }
+        let links: Vec<serde_json::Value> = transport
+            .get_links()
+            .unwrap_or_default()
+            .iter()
+            .map(|link| {
+                json!({
+                    "src": link.src.to_string(),
+                    "dst": link.dst.to_string(),
+                    "mtu": link.mtu,
+                    "reliable": link.is_reliable,
+                    "streamed": link.is_streamed,
+                    "encrypted": matches!(link.dst.protocol().as_str(), "tls" | "quic"),
+                })
+            })
+            .collect();
+        let json = json!({
+            "zid": zid.to_string(),
+            "whatami": transport.get_whatami().map_or_else(|_| "unknown".to_string(), |w| w.to_string()),
+            "links": links,
+            "sn_resolution": transport.get_sn_resolution().ok(),
+            "qos": transport.is_qos().ok(),
+            "shm": transport.is_shm().ok(),
+            "uptime": transport.get_uptime().map(|d| d.as_secs()).ok(),
+        });
+        reply_json(&query, key, &json);
+    }
+}
+
 fn subscribers_data(context: &AdminContext, query: Query) {
     let tables = zread!(context.runtime.router.tables.tables);
     for sub in tables.router_subs.iter() {
@@ -635,6 +1155,42 @@ fn queryables_data(context: &AdminContext, query: Query) {
     }
 }
 
+fn talkers_data(context: &AdminContext, query: Query) {
+    let reply_key: OwnedKeyExpr = format!("@/router/{}/talkers", context.zid_str)
+        .try_into()
+        .unwrap();
+    let limit = crate::prelude::Parameters::decode(&query.selector())
+        .find(|(k, _)| k.as_ref() == "limit")
+        .and_then(|(_, v)| v.parse().ok())
+        .unwrap_or(TOP_TALKERS_LIMIT);
+
+    let tables = zread!(context.runtime.router.tables.tables);
+    let top: Vec<serde_json::Value> = tables
+        .talkers
+        .top(limit)
+        .into_iter()
+        .map(|(prefix, stats)| {
+            json!({
+                "prefix": prefix,
+                "samples": stats.samples,
+                "bytes": stats.bytes,
+            })
+        })
+        .collect();
+    let sizes: Vec<serde_json::Value> = tables
+        .talkers
+        .sizes()
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| json!({ "le": SIZE_BUCKETS.get(i), "count": count }))
+        .collect();
+    drop(tables);
+
+    let json = json!({ "top": top, "sizes": sizes });
+    log::trace!("AdminSpace talkers_data: {:?}", json);
+    reply_json(&query, reply_key.into(), &json);
+}
+
 fn plugins_status(context: &AdminContext, query: Query) {
     let selector = query.selector();
     let guard = zlock!(context.plugins_mgr);
@@ -645,15 +1201,7 @@ fn plugins_status(context: &AdminContext, query: Query) {
             with_extended_string(plugin_key, &["/__path__"], |plugin_path_key| {
                 if let Ok(key_expr) = KeyExpr::try_from(plugin_path_key.clone()) {
                     if query.key_expr().intersects(&key_expr) {
-                        if let Err(e) = query
-                            .reply(Ok(Sample::new(
-                                key_expr,
-                                Value::from(path).encoding(KnownEncoding::AppJson.into()),
-                            )))
-                            .res()
-                        {
-                            log::error!("Error sending AdminSpace reply: {:?}", e);
-                        }
+                        reply_json(&query, key_expr, &json!(path));
                     }
                 } else {
                     log::error!("Error: invalid plugin path key {}", plugin_path_key);
@@ -667,41 +1215,124 @@ fn plugins_status(context: &AdminContext, query: Query) {
             if !with_extended_string(plugin_key, &["/**"], matches_plugin) {
                 return;
             }
+            let status = match zlock!(context.plugins_failures).get(name) {
+                Some(failure) if failure.failed => json!({
+                    "state": "failed",
+                    "reason": failure.reason,
+                    "restarts": failure.restarts,
+                }),
+                Some(failure) => json!({ "state": "running", "restarts": failure.restarts }),
+                None => json!({ "state": "running", "restarts": 0 }),
+            };
+            let failed = status["state"] == "failed";
+            with_extended_string(plugin_key, &["/__status__"], |plugin_status_key| {
+                if let Ok(key_expr) = KeyExpr::try_from(plugin_status_key.clone()) {
+                    if query.key_expr().intersects(&key_expr) {
+                        reply_json(&query, key_expr, &status);
+                    }
+                }
+            });
+            if failed {
+                return;
+            }
             match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
                 plugin.adminspace_getter(&selector, plugin_key)
             })) {
                 Ok(Ok(responses)) => {
                     for response in responses {
                         if let Ok(key_expr) = KeyExpr::try_from(response.key) {
-                            if let Err(e) = query.reply(Ok(Sample::new(
-                                key_expr,
-                                Value::from(response.value).encoding(KnownEncoding::AppJson.into()),
-                            )))
-                            .res()
-                            {
-                                log::error!("Error sending AdminSpace reply: {:?}", e);
-                            }
+                            reply_json(&query, key_expr, &response.value);
                         } else {
                             log::error!("Error: plugin {} replied with an invalid key", plugin_key);
                         }
                     }
                 }
                 Ok(Err(e)) => {
-                    log::error!("Plugin {} bailed from responding to {}: {}", name, query.key_expr(), e)
+                    log::error!(
+                        "Plugin {} bailed from responding to {}: {}",
+                        name,
+                        query.key_expr(),
+                        e
+                    )
                 }
-                Err(e) => match e
-                    .downcast_ref::<String>()
-                    .map(|s| s.as_str())
-                    .or_else(|| e.downcast_ref::<&str>().copied())
-                {
-                    Some(e) => log::error!("Plugin {} panicked while responding to {}: {}", name, query.key_expr(), e),
-                    None => log::error!("Plugin {} panicked while responding to {}. The panic message couldn't be recovered.", name, query.key_expr()),
-                },
+                Err(panic) => plugin_panicked(
+                    context,
+                    name,
+                    &format!("responding to {}", query.key_expr()),
+                    panic,
+                ),
             }
         });
     }
 }
 
+fn is_plugin_failed(context: &AdminContext, name: &str) -> bool {
+    zlock!(context.plugins_failures)
+        .get(name)
+        .map_or(false, |failure| failure.failed)
+}
+
+// Marks the plugin failed after it panicked, so that it's not called anymore until it's restarted.
+fn plugin_panicked(
+    context: &AdminContext,
+    name: &str,
+    during: &str,
+    panic: Box<dyn std::any::Any + Send>,
+) {
+    let reason = match panic
+        .downcast_ref::<String>()
+        .map(|s| s.as_str())
+        .or_else(|| panic.downcast_ref::<&str>().copied())
+    {
+        Some(e) => format!("panicked while {during}: {e}"),
+        None => format!("panicked while {during}"),
+    };
+    log::error!("Plugin `{}` {}", name, reason);
+    let mut failures = zlock!(context.plugins_failures);
+    let failure = failures.entry(name.into()).or_insert(PluginFailure {
+        failed: false,
+        reason: String::new(),
+        restarts: 0,
+    });
+    failure.reason = reason;
+    if !failure.failed {
+        failure.failed = true;
+        let _ = context.plugins_failed.send(name.into());
+    }
+}
+
+fn restart_plugin(context: &AdminContext, name: &str) {
+    let mut plugins_mgr = zlock!(context.plugins_mgr);
+    if !is_plugin_failed(context, name) {
+        // The plugin was stopped in the meantime
+        return;
+    }
+    let result = plugins_mgr.restart(name, &context.runtime);
+    let mut failures = zlock!(context.plugins_failures);
+    let failure = match failures.get_mut(name) {
+        Some(failure) => failure,
+        None => return,
+    };
+    failure.restarts += 1;
+    match result {
+        Ok(Some((path, plugin))) => {
+            log::info!("Successfully restarted plugin `{}` from {}", name, path);
+            failure.failed = false;
+            context
+                .runtime
+                .config
+                .lock()
+                .add_plugin_validator(name, plugin.config_checker());
+        }
+        Ok(None) => failure.failed = false,
+        Err(e) => {
+            log::error!("Failed to restart plugin `{}`: {}", name, e);
+            failure.reason = e.to_string();
+            let _ = context.plugins_failed.send(name.into());
+        }
+    }
+}
+
 fn with_extended_string<R, F: FnMut(&mut String) -> R>(
     prefix: &mut String,
     suffixes: &[&str],
@@ -715,3 +1346,28 @@ fn with_extended_string<R, F: FnMut(&mut String) -> R>(
     prefix.truncate(prefix_len);
     result
 }
+
+#[test]
+fn reply_formats() {
+    assert_eq!("cbor".parse::<ReplyFormat>().unwrap(), ReplyFormat::Cbor);
+    assert!("yaml".parse::<ReplyFormat>().is_err());
+
+    let json = json!({
+        "zid": "1",
+        "locators": ["tcp/127.0.0.1:7447"],
+        "plugins": {},
+        "sessions": [{ "qos": true }],
+    });
+    let text = ReplyFormat::Text.serialize(&json).unwrap();
+    assert_eq!(text.encoding, KnownEncoding::TextPlain.into());
+    assert_eq!(
+        String::try_from(&text).unwrap(),
+        "locators.0 = tcp/127.0.0.1:7447\nplugins = {}\nsessions.0.qos = true\nzid = 1\n"
+    );
+
+    let cbor = ReplyFormat::Cbor.serialize(&json).unwrap();
+    assert_eq!(cbor.encoding, CBOR_ENCODING.into());
+    let decoded: serde_json::Value =
+        ciborium::de::from_reader(&cbor.payload.contiguous()[..]).unwrap();
+    assert_eq!(decoded, json);
+}
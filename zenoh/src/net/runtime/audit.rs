@@ -0,0 +1,260 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use super::routing::face::Face;
+use crate::config::{unwrap_or_default, Config};
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zenoh_buffers::ZBuf;
+use zenoh_protocol::{
+    core::{Channel, CongestionControl, Encoding, KnownEncoding, WhatAmI, WireExpr, ZenohId},
+    zenoh::DataInfo,
+};
+use zenoh_transport::Primitives;
+
+/// An event recorded by the audit log.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEvent {
+    SessionOpened {
+        zid: ZenohId,
+        whatami: WhatAmI,
+    },
+    SessionClosed {
+        zid: ZenohId,
+    },
+    Declaration {
+        zid: ZenohId,
+        kind: &'static str,
+        key_expr: String,
+    },
+    /// An allowed write in the admin space, by the node `zid` (or by the local session if `None`).
+    AdminWrite {
+        zid: Option<ZenohId>,
+        key_expr: String,
+    },
+    /// A read or a write in the admin space denied by its permissions, by the node `zid`
+    /// (or by the local session if `None`).
+    AccessDenied {
+        zid: Option<ZenohId>,
+        key_expr: String,
+        operation: &'static str,
+    },
+}
+
+#[derive(Serialize)]
+struct AuditRecord<'a> {
+    /// Milliseconds since the UNIX epoch.
+    time: u128,
+    /// The zenoh instance that recorded the event.
+    source: &'a ZenohId,
+    #[serde(flatten)]
+    event: &'a AuditEvent,
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    max_files: usize,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_size: u64, max_files: usize) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            file,
+            size,
+            max_size,
+            max_files,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{index}"));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        if self.max_files == 0 {
+            self.file.set_len(0)?;
+        } else {
+            for index in (1..self.max_files).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    std::fs::rename(from, self.rotated_path(index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+            self.file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+
+    fn append(&mut self, line: &[u8]) -> std::io::Result<()> {
+        if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        self.file.write_all(line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+/// An append-only log of session, declaration and administrative events.
+///
+/// Events are appended as JSON lines to a rotating file and/or published on `@/router/<zid>/audit`
+/// once the admin space has been started.
+pub struct AuditLog {
+    zid: ZenohId,
+    enabled: bool,
+    publish: bool,
+    file: Option<Mutex<RotatingFile>>,
+    publisher: Mutex<Option<(Arc<Face>, String)>>,
+}
+
+impl AuditLog {
+    pub(crate) fn new(zid: ZenohId, config: &Config) -> Self {
+        let enabled = unwrap_or_default!(config.audit().enabled());
+        let file = match config.audit().path() {
+            Some(path) if enabled => {
+                let max_file_size = unwrap_or_default!(config.audit().max_file_size());
+                let max_files = unwrap_or_default!(config.audit().max_files());
+                match RotatingFile::open(path.into(), max_file_size, max_files) {
+                    Ok(file) => Some(Mutex::new(file)),
+                    Err(e) => {
+                        log::error!("Unable to open audit file {}: {}", path, e);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        AuditLog {
+            zid,
+            enabled,
+            publish: unwrap_or_default!(config.audit().publish()),
+            file,
+            publisher: Mutex::new(None),
+        }
+    }
+
+    /// Whether events are being recorded.
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub(crate) fn set_publisher(&self, primitives: Arc<Face>) {
+        if self.enabled && self.publish {
+            let key_expr = format!("@/router/{}/audit", self.zid);
+            zlock!(self.publisher).replace((primitives, key_expr));
+        }
+    }
+
+    /// Records the given event.
+    pub fn record(&self, event: AuditEvent) {
+        if !self.enabled {
+            return;
+        }
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        let record = AuditRecord {
+            time,
+            source: &self.zid,
+            event: &event,
+        };
+        let mut line = match serde_json::to_vec(&record) {
+            Ok(line) => line,
+            Err(e) => {
+                log::error!("Unable to serialize audit event {:?}: {}", event, e);
+                return;
+            }
+        };
+        log::trace!("Audit: {}", String::from_utf8_lossy(&line));
+
+        if let Some((primitives, key_expr)) = zlock!(self.publisher).as_ref() {
+            let data_info = DataInfo {
+                encoding: Some(Encoding::Exact(KnownEncoding::AppJson)),
+                ..Default::default()
+            };
+            primitives.send_data(
+                &WireExpr::from(key_expr.as_str()),
+                ZBuf::from(line.clone()),
+                Channel::default(),
+                CongestionControl::Drop,
+                Some(data_info),
+                None,
+            );
+        }
+
+        if let Some(file) = &self.file {
+            line.push(b'\n');
+            if let Err(e) = zlock!(file).append(&line) {
+                log::error!("Unable to write audit event: {}", e);
+            }
+        }
+    }
+}
+
+#[test]
+fn rotating_file() {
+    let dir = std::env::temp_dir().join(format!("zenoh-audit-{}", ZenohId::rand()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("audit.log");
+    let read = |path: PathBuf| std::fs::read_to_string(path).unwrap_or_default();
+
+    let mut file = RotatingFile::open(path.clone(), 10, 2).unwrap();
+    file.append(b"aaaaaa\n").unwrap();
+    file.append(b"bbbbbb\n").unwrap();
+    file.append(b"cccccc\n").unwrap();
+    assert_eq!(read(path.clone()), "cccccc\n");
+    assert_eq!(read(file.rotated_path(1)), "bbbbbb\n");
+    assert_eq!(read(file.rotated_path(2)), "aaaaaa\n");
+
+    // the oldest file is dropped above the maximum number of files
+    file.append(b"dddddd\n").unwrap();
+    assert_eq!(read(path.clone()), "dddddd\n");
+    assert_eq!(read(file.rotated_path(1)), "cccccc\n");
+    assert_eq!(read(file.rotated_path(2)), "bbbbbb\n");
+    assert!(!file.rotated_path(3).exists());
+
+    // a line larger than the maximum size is written in a file of its own
+    file.append(b"eeeeeeeeeeeeeeee\n").unwrap();
+    file.append(b"ff\n").unwrap();
+    assert_eq!(read(file.rotated_path(1)), "eeeeeeeeeeeeeeee\n");
+    assert_eq!(read(path.clone()), "ff\n");
+
+    // the size of an existing file is taken into account when reopening it
+    drop(file);
+    let mut file = RotatingFile::open(path.clone(), 10, 0).unwrap();
+    file.append(b"gggggggg\n").unwrap();
+    assert_eq!(read(path), "gggggggg\n");
+    assert_eq!(read(file.rotated_path(1)), "eeeeeeeeeeeeeeee\n");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
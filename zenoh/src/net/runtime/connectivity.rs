@@ -0,0 +1,159 @@
+//
+// Copyright (c) 2023 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use zenoh_core::zlock;
+
+/// The connectivity state of a zenoh instance, as driven by its orchestrator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", content = "sessions", rename_all = "snake_case")]
+pub enum ConnectivityState {
+    /// Looking for a first session with the scouting.
+    Scouting,
+    /// Waiting for a first session: connecting to the configured endpoints or accepting incoming sessions.
+    Connecting,
+    /// Connected to the given number of remote nodes.
+    Connected(usize),
+    /// All the sessions were lost: trying to establish new ones.
+    Degraded,
+    /// The instance is closed.
+    Closed,
+}
+
+struct ConnectivityInner {
+    state: ConnectivityState,
+    sessions: usize,
+    watchers: Vec<flume::Sender<ConnectivityState>>,
+}
+
+/// Tracks the [`ConnectivityState`] of a zenoh instance, notifying its watchers of every change.
+pub(crate) struct Connectivity {
+    inner: Mutex<ConnectivityInner>,
+    // whether some session is established, read without locking on the publication path
+    connected: AtomicBool,
+}
+
+impl Connectivity {
+    pub(crate) fn new() -> Self {
+        Connectivity {
+            inner: Mutex::new(ConnectivityInner {
+                state: ConnectivityState::Connecting,
+                sessions: 0,
+                watchers: vec![],
+            }),
+            connected: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn state(&self) -> ConnectivityState {
+        zlock!(self.inner).state
+    }
+
+    /// Whether some session with a remote node is established.
+    #[inline]
+    pub(crate) fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// A channel receiving the current state, then each of its changes.
+    pub(crate) fn watch(&self) -> flume::Receiver<ConnectivityState> {
+        let (sender, receiver) = flume::unbounded();
+        let mut inner = zlock!(self.inner);
+        let _ = sender.send(inner.state);
+        inner.watchers.push(sender);
+        receiver
+    }
+
+    fn set(inner: &mut ConnectivityInner, state: ConnectivityState) {
+        if inner.state != state && inner.state != ConnectivityState::Closed {
+            log::debug!("Connectivity state: {:?}", state);
+            inner.state = state;
+            inner.watchers.retain(|w| w.send(state).is_ok());
+        }
+    }
+
+    /// The orchestrator starts looking for a first session, with the scouting or not.
+    pub(crate) fn starting(&self, scouting: bool) {
+        let mut inner = zlock!(self.inner);
+        if inner.state == ConnectivityState::Connecting {
+            let state = if scouting {
+                ConnectivityState::Scouting
+            } else {
+                ConnectivityState::Connecting
+            };
+            Self::set(&mut inner, state);
+        }
+    }
+
+    pub(crate) fn session_opened(&self) {
+        let mut inner = zlock!(self.inner);
+        inner.sessions += 1;
+        self.connected.store(true, Ordering::Relaxed);
+        let state = ConnectivityState::Connected(inner.sessions);
+        Self::set(&mut inner, state);
+    }
+
+    pub(crate) fn session_closed(&self) {
+        let mut inner = zlock!(self.inner);
+        inner.sessions = inner.sessions.saturating_sub(1);
+        self.connected.store(inner.sessions > 0, Ordering::Relaxed);
+        let state = match inner.sessions {
+            0 => ConnectivityState::Degraded,
+            n => ConnectivityState::Connected(n),
+        };
+        Self::set(&mut inner, state);
+    }
+
+    pub(crate) fn closed(&self) {
+        let mut inner = zlock!(self.inner);
+        Self::set(&mut inner, ConnectivityState::Closed);
+        self.connected.store(false, Ordering::Relaxed);
+        inner.watchers.clear();
+    }
+}
+
+#[test]
+fn connectivity_transitions() {
+    let connectivity = Connectivity::new();
+    connectivity.starting(true);
+    let watcher = connectivity.watch();
+    assert!(!connectivity.is_connected());
+    connectivity.session_opened();
+    connectivity.session_opened();
+    connectivity.session_closed();
+    assert!(connectivity.is_connected());
+    connectivity.session_closed();
+    assert!(!connectivity.is_connected());
+    // The initial state is not restored once a session was lost
+    connectivity.starting(true);
+    connectivity.session_opened();
+    connectivity.closed();
+    connectivity.session_closed();
+
+    assert_eq!(
+        watcher.drain().collect::<Vec<_>>(),
+        vec![
+            ConnectivityState::Scouting,
+            ConnectivityState::Connected(1),
+            ConnectivityState::Connected(2),
+            ConnectivityState::Connected(1),
+            ConnectivityState::Degraded,
+            ConnectivityState::Connected(1),
+            ConnectivityState::Closed,
+        ]
+    );
+    assert_eq!(connectivity.state(), ConnectivityState::Closed);
+    assert!(!connectivity.is_connected());
+}
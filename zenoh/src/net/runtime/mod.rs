@@ -18,18 +18,28 @@
 //!
 //! [Click here for Zenoh's documentation](../zenoh/index.html)
 mod adminspace;
+pub(crate) mod audit;
+mod connectivity;
 pub mod orchestrator;
 
 use super::routing;
+use super::routing::mirror::DataMirror;
 use super::routing::pubsub::full_reentrant_route_data;
+use super::routing::remapping::KeyRemappings;
 use super::routing::router::{LinkStateInterceptor, Router};
-use crate::config::{unwrap_or_default, Config, ModeDependent, Notifier};
+use super::routing::talkers::TopTalkers;
+use crate::config::{unwrap_or_default, Config, FutureTimestampPolicy, ModeDependent, Notifier};
 use crate::GIT_VERSION;
 pub use adminspace::AdminSpace;
 use async_std::task::JoinHandle;
+use audit::{AuditEvent, AuditLog};
+use connectivity::Connectivity;
+pub use connectivity::ConnectivityState;
 use futures::stream::StreamExt;
 use futures::Future;
 use std::any::Any;
+use std::io::Write;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 use stop_token::future::FutureExt;
@@ -38,9 +48,9 @@ use uhlc::{HLCBuilder, HLC};
 use zenoh_link::{EndPoint, Link};
 use zenoh_protocol::{
     core::{whatami::WhatAmIMatcher, Locator, WhatAmI, ZenohId},
-    zenoh::{ZenohBody, ZenohMessage},
+    zenoh::{Declaration, Declare, ZenohBody, ZenohMessage},
 };
-use zenoh_result::{bail, ZResult};
+use zenoh_result::{bail, zerror, ZResult};
 use zenoh_sync::get_mut_unchecked;
 use zenoh_transport::{
     TransportEventHandler, TransportManager, TransportMulticast, TransportMulticastEventHandler,
@@ -56,9 +66,16 @@ pub struct RuntimeState {
     pub transport_handlers: std::sync::RwLock<Vec<Arc<dyn TransportEventHandler>>>,
     pub(crate) locators: std::sync::RwLock<Vec<Locator>>,
     pub hlc: Option<Arc<HLC>>,
+    pub(crate) audit: AuditLog,
+    pub(crate) connectivity: Connectivity,
+    // The panics of the tasks spawned by the plugins, handled by the admin space
+    pub(crate) plugins_panics: (flume::Sender<PluginPanic>, flume::Receiver<PluginPanic>),
     pub(crate) stop_source: std::sync::RwLock<Option<StopSource>>,
 }
 
+/// The name of a plugin and the payload of a panic of one of its tasks.
+pub(crate) type PluginPanic = (String, Box<dyn std::any::Any + Send>);
+
 #[derive(Clone)]
 pub struct Runtime {
     state: Arc<RuntimeState>,
@@ -81,20 +98,39 @@ impl Runtime {
         }
     }
 
-    pub(crate) async fn init(config: Config) -> ZResult<Runtime> {
+    pub(crate) async fn init(mut config: Config) -> ZResult<Runtime> {
         log::debug!("Zenoh Rust API {}", GIT_VERSION);
         // Make sure to have have enough threads spawned in the async futures executor
         zasync_executor_init!();
 
+        if let Some(path) = config.id_key_file().clone() {
+            let zid = zid_from_key_file(Path::new(&path))?;
+            config
+                .set_id(zid)
+                .map_err(|_| zerror!("Invalid id derived from {}", path))?;
+        }
         let zid = *config.id();
 
         log::info!("Using PID: {}", zid);
 
         let whatami = unwrap_or_default!(config.mode());
-        let hlc = (*unwrap_or_default!(config.timestamping().enabled().get(whatami)))
-            .then(|| Arc::new(HLCBuilder::new().with_id(uhlc::ID::from(&zid)).build()));
-        let drop_future_timestamp =
-            unwrap_or_default!(config.timestamping().drop_future_timestamp());
+        let max_delta =
+            Duration::from_millis(unwrap_or_default!(config.timestamping().max_delta_ms()));
+        let hlc = (*unwrap_or_default!(config.timestamping().enabled().get(whatami))).then(|| {
+            Arc::new(
+                HLCBuilder::new()
+                    .with_id(uhlc::ID::from(&zid))
+                    .with_max_delta(max_delta)
+                    .build(),
+            )
+        });
+        let future_timestamp_policy = match config.timestamping().future_timestamp_policy() {
+            Some(policy) => *policy,
+            None if unwrap_or_default!(config.timestamping().drop_future_timestamp()) => {
+                FutureTimestampPolicy::Drop
+            }
+            None => FutureTimestampPolicy::Clamp,
+        };
 
         let gossip = unwrap_or_default!(config.scouting().gossip().enabled());
         let gossip_multihop = unwrap_or_default!(config.scouting().gossip().multihop());
@@ -111,14 +147,34 @@ impl Runtime {
             unwrap_or_default!(config.routing().router().peers_failover_brokering());
         let queries_default_timeout =
             Duration::from_millis(unwrap_or_default!(config.queries_default_timeout()));
+        let remappings = KeyRemappings::new(config.routing().remapping().clone())?;
+        let max_resource_mappings = *config.routing().max_resource_mappings();
+        let hot_key_threshold = *config.routing().hot_key_threshold();
+        let client_interests = unwrap_or_default!(config.routing().router().client_interests());
+        let interests = config.routing().client().interests().clone();
+        let mirror = DataMirror::new(
+            config.routing().mirror().keys().clone(),
+            unwrap_or_default!(config.routing().mirror().max_rate()),
+        );
+        let talkers = TopTalkers::new(
+            unwrap_or_default!(config.routing().talkers().depth()),
+            unwrap_or_default!(config.routing().talkers().max_prefixes()),
+        );
 
         let router = Arc::new(Router::new(
             zid,
             whatami,
             hlc.clone(),
-            drop_future_timestamp,
+            future_timestamp_policy,
             router_peers_failover_brokering,
             queries_default_timeout,
+            remappings,
+            max_resource_mappings,
+            hot_key_threshold,
+            client_interests,
+            interests,
+            mirror,
+            talkers,
         ));
 
         let handler = Arc::new(RuntimeTransportEventHandler {
@@ -132,6 +188,8 @@ impl Runtime {
             .zid(zid)
             .build(handler.clone())?;
 
+        let audit = AuditLog::new(zid, &config);
+
         let config = Notifier::new(config);
 
         let runtime = Runtime {
@@ -144,6 +202,9 @@ impl Runtime {
                 transport_handlers: std::sync::RwLock::new(vec![]),
                 locators: std::sync::RwLock::new(vec![]),
                 hlc,
+                audit,
+                connectivity: Connectivity::new(),
+                plugins_panics: flume::unbounded(),
                 stop_source: std::sync::RwLock::new(Some(StopSource::new())),
             }),
         };
@@ -189,9 +250,20 @@ impl Runtime {
         log::trace!("Runtime::close())");
         drop(self.stop_source.write().unwrap().take());
         self.manager().close().await;
+        self.connectivity.closed();
         Ok(())
     }
 
+    /// The current connectivity state of this instance.
+    pub fn connectivity_state(&self) -> ConnectivityState {
+        self.connectivity.state()
+    }
+
+    /// A channel receiving the current connectivity state of this instance, then each of its changes.
+    pub fn watch_connectivity(&self) -> flume::Receiver<ConnectivityState> {
+        self.connectivity.watch()
+    }
+
     pub fn new_timestamp(&self) -> Option<uhlc::Timestamp> {
         self.hlc.as_ref().map(|hlc| hlc.new_timestamp())
     }
@@ -200,6 +272,27 @@ impl Runtime {
         self.locators.read().unwrap().clone()
     }
 
+    /// The sessions currently established with remote nodes.
+    pub fn get_sessions(&self) -> Vec<TransportPeer> {
+        self.manager()
+            .get_transports()
+            .iter()
+            .filter_map(|transport| transport.get_peer().ok())
+            .collect()
+    }
+
+    /// Closes the session established with the remote node `zid`,
+    /// with the same cleanup as when it disconnects.
+    pub async fn close_session(&self, zid: &ZenohId) -> ZResult<()> {
+        match self.manager().get_transport(zid) {
+            Some(transport) => {
+                log::info!("Closing session with {}", zid);
+                transport.close().await
+            }
+            None => bail!("No session with {}", zid),
+        }
+    }
+
     pub(crate) fn spawn<F, T>(&self, future: F) -> Option<JoinHandle<Result<T, TimedOutError>>>
     where
         F: Future<Output = T> + Send + 'static,
@@ -213,6 +306,33 @@ impl Runtime {
     }
 }
 
+/// Derives a Zenoh ID from the host key stored at `path`, generating the key if the file doesn't exist.
+fn zid_from_key_file(path: &Path) -> ZResult<ZenohId> {
+    let key = match std::fs::read_to_string(path) {
+        Ok(key) => hex::decode(key.trim())
+            .map_err(|e| zerror!("Invalid host key in {}: {}", path.display(), e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let key: [u8; 32] = rand::random();
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+            options
+                .open(path)
+                .and_then(|mut file| writeln!(file, "{}", hex::encode(key)))
+                .map_err(|e| zerror!("Unable to create host key {}: {}", path.display(), e))?;
+            log::info!("Generated host key {}", path.display());
+            key.to_vec()
+        }
+        Err(e) => bail!("Unable to read host key {}: {}", path.display(), e),
+    };
+    if key.len() < 16 {
+        bail!("Invalid host key in {}: too short", path.display());
+    }
+    // the key itself isn't disclosed by the id
+    ZenohId::try_from(&zenoh_crypto::hmac::digest(&key)[..16])
+}
+
 struct RuntimeTransportEventHandler {
     runtime: std::sync::RwLock<Option<Runtime>>,
 }
@@ -232,8 +352,14 @@ impl TransportEventHandler for RuntimeTransportEventHandler {
                             handler.new_unicast(peer.clone(), transport.clone()).ok()
                         })
                         .collect();
+                runtime.audit.record(AuditEvent::SessionOpened {
+                    zid: peer.zid,
+                    whatami: peer.whatami,
+                });
+                runtime.connectivity.session_opened();
                 Ok(Arc::new(RuntimeSession {
                     runtime: runtime.clone(),
+                    zid: peer.zid,
                     endpoint: std::sync::RwLock::new(None),
                     main_handler: runtime.router.new_transport_unicast(transport).unwrap(),
                     slave_handlers,
@@ -254,11 +380,35 @@ impl TransportEventHandler for RuntimeTransportEventHandler {
 
 pub(super) struct RuntimeSession {
     pub(super) runtime: Runtime,
+    pub(super) zid: ZenohId,
     pub(super) endpoint: std::sync::RwLock<Option<EndPoint>>,
     pub(super) main_handler: Arc<LinkStateInterceptor>,
     pub(super) slave_handlers: Vec<Arc<dyn TransportPeerEventHandler>>,
 }
 
+impl RuntimeSession {
+    fn audit_declarations(&self, declare: &Declare) {
+        for declaration in &declare.declarations {
+            let (kind, key_expr) = match declaration {
+                Declaration::Resource(d) => ("resource", &d.key),
+                Declaration::ForgetResource(_) => continue,
+                Declaration::Publisher(d) => ("publisher", &d.key),
+                Declaration::ForgetPublisher(d) => ("forget_publisher", &d.key),
+                Declaration::Subscriber(d) => ("subscriber", &d.key),
+                Declaration::ForgetSubscriber(d) => ("forget_subscriber", &d.key),
+                Declaration::Queryable(d) => ("queryable", &d.key),
+                Declaration::ForgetQueryable(d) => ("forget_queryable", &d.key),
+                Declaration::Interest(d) => ("interest", &d.key),
+            };
+            self.runtime.audit.record(AuditEvent::Declaration {
+                zid: self.zid,
+                kind,
+                key_expr: key_expr.to_string(),
+            });
+        }
+    }
+}
+
 impl TransportPeerEventHandler for RuntimeSession {
     fn handle_message(&self, mut msg: ZenohMessage) -> ZResult<()> {
         // critical path shortcut
@@ -281,6 +431,12 @@ impl TransportPeerEventHandler for RuntimeSession {
             }
         }
 
+        if self.runtime.audit.is_enabled() {
+            if let ZenohBody::Declare(declare) = &msg.body {
+                self.audit_declarations(declare);
+            }
+        }
+
         self.main_handler.handle_message(msg)
     }
 
@@ -307,6 +463,10 @@ impl TransportPeerEventHandler for RuntimeSession {
     }
 
     fn closed(&self) {
+        self.runtime
+            .audit
+            .record(AuditEvent::SessionClosed { zid: self.zid });
+        self.runtime.connectivity.session_closed();
         self.main_handler.closed();
         for handler in &self.slave_handlers {
             handler.closed();
@@ -317,3 +477,13 @@ impl TransportPeerEventHandler for RuntimeSession {
         self
     }
 }
+
+#[test]
+fn zid_from_key_file_test() {
+    let path = std::env::temp_dir().join(format!("zenoh-host-{}.key", ZenohId::rand()));
+    let zid = zid_from_key_file(&path).unwrap();
+    assert_eq!(zid_from_key_file(&path).unwrap(), zid);
+    std::fs::write(&path, "not hex").unwrap();
+    assert!(zid_from_key_file(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
@@ -16,18 +16,20 @@ use async_std::net::UdpSocket;
 use async_std::prelude::FutureExt;
 use futures::prelude::*;
 use socket2::{Domain, Socket, Type};
-use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::time::Duration;
 use zenoh_buffers::reader::DidntRead;
 use zenoh_buffers::{reader::HasReader, writer::HasWriter};
 use zenoh_codec::{RCodec, WCodec, Zenoh060};
 use zenoh_config::{unwrap_or_default, EndPoint, ModeDependent};
+use zenoh_crypto::hmac;
 use zenoh_link::Locator;
 use zenoh_protocol::{
-    core::{whatami::WhatAmIMatcher, WhatAmI, ZenohId},
-    scouting::{Hello, Scout, ScoutingBody, ScoutingMessage},
+    core::{whatami::WhatAmIMatcher, WhatAmI, ZInt, ZenohId},
+    scouting::{Hello, Scout, ScoutingBody, ScoutingMessage, Signature},
 };
-use zenoh_result::{bail, zerror, ZResult};
+use zenoh_result::{bail, ErrorKind, ZResult};
+use zenoh_sync::clock;
 use zenoh_transport::TransportUnicast;
 
 const RCV_BUF_SIZE: usize = u16::MAX as usize;
@@ -48,6 +50,14 @@ pub enum Loop {
 
 impl Runtime {
     pub(crate) async fn start(&mut self) -> ZResult<()> {
+        let scouting = {
+            let guard = self.config.lock();
+            guard.connect().endpoints().is_empty()
+                && (unwrap_or_default!(guard.scouting().multicast().enabled())
+                    || (self.whatami == WhatAmI::Client
+                        && !guard.scouting().unicast().probes().is_empty()))
+        };
+        self.connectivity.starting(scouting);
         match self.whatami {
             WhatAmI::Client => self.start_client().await,
             WhatAmI::Peer => self.start_peer().await,
@@ -56,59 +66,105 @@ impl Runtime {
     }
 
     async fn start_client(&self) -> ZResult<()> {
-        let (peers, scouting, addr, ifaces, timeout) = {
+        let (
+            peers,
+            scouting,
+            addr,
+            ifaces,
+            ttl,
+            timeout,
+            connect_timeout,
+            retries,
+            wait_for_router,
+        ) = {
             let guard = self.config.lock();
             (
                 guard.connect().endpoints().clone(),
                 unwrap_or_default!(guard.scouting().multicast().enabled()),
                 unwrap_or_default!(guard.scouting().multicast().address()),
                 unwrap_or_default!(guard.scouting().multicast().interface()),
+                unwrap_or_default!(guard.scouting().multicast().ttl()),
                 std::time::Duration::from_millis(unwrap_or_default!(guard.scouting().timeout())),
+                std::time::Duration::from_millis(unwrap_or_default!(guard.connect().timeout_ms())),
+                unwrap_or_default!(guard.connect().retries()),
+                unwrap_or_default!(guard.connect().wait_for_router()),
             )
         };
-        match peers.len() {
+        let probes = self.config.lock().scouting().unicast().probes().clone();
+        let sockets: Vec<UdpSocket> = match peers.len() {
             0 => {
                 if scouting {
-                    log::info!("Scouting for router ...");
                     let ifaces = Runtime::get_interfaces(&ifaces);
                     if ifaces.is_empty() {
                         bail!("Unable to find multicast interface!")
-                    } else {
-                        let sockets: Vec<UdpSocket> = ifaces
-                            .into_iter()
-                            .filter_map(|iface| Runtime::bind_ucast_port(iface).ok())
-                            .collect();
-                        if sockets.is_empty() {
-                            bail!("Unable to bind UDP port to any multicast interface!")
-                        } else {
-                            self.connect_first(&sockets, WhatAmI::Router.into(), &addr, timeout)
-                                .await
-                        }
                     }
+                    let sockets: Vec<UdpSocket> = ifaces
+                        .into_iter()
+                        .filter_map(|iface| Runtime::bind_ucast_port(iface, ttl).ok())
+                        .collect();
+                    if sockets.is_empty() {
+                        bail!("Unable to bind UDP port to any multicast interface!")
+                    }
+                    sockets
+                } else if !probes.is_empty() {
+                    vec![Runtime::bind_ucast_port(Ipv4Addr::UNSPECIFIED.into(), ttl)?]
                 } else {
                     bail!("No peer specified and multicast scouting desactivated!")
                 }
             }
-            _ => {
-                for locator in &peers {
-                    match self
-                        .manager()
-                        .open_transport(locator.clone())
-                        .timeout(CONNECTION_TIMEOUT)
-                        .await
-                    {
-                        Ok(Ok(_)) => return Ok(()),
-                        Ok(Err(e)) => log::warn!("Unable to connect to {}! {}", locator, e),
-                        Err(e) => log::warn!("Unable to connect to {}! {}", locator, e),
-                    }
+            _ => vec![],
+        };
+        // The routers are scouted on UDP multicast and by probing the candidate addresses
+        let mut scouted = probes;
+        if scouting {
+            scouted.push(addr);
+        }
+
+        let mut attempt = 0;
+        let mut period = CONNECTION_RETRY_INITIAL_PERIOD;
+        loop {
+            let res = if peers.is_empty() {
+                log::info!("Scouting for router ...");
+                self.connect_first(&sockets, WhatAmI::Router.into(), &scouted, timeout)
+                    .await
+            } else {
+                self.connect_any(&peers, connect_timeout).await
+            };
+            match res {
+                Ok(()) => return Ok(()),
+                Err(e) if wait_for_router || attempt < retries => {
+                    attempt += 1;
+                    log::warn!("{} Retrying in {:?} (attempt {}).", e, period, attempt);
+                    clock::sleep(period).await;
+                    period = std::cmp::min(
+                        period * CONNECTION_RETRY_PERIOD_INCREASE_FACTOR,
+                        CONNECTION_RETRY_MAX_PERIOD,
+                    );
+                }
+                Err(e) => {
+                    log::error!("{}", &e);
+                    return Err(e);
                 }
-                let e = zerror!("Unable to connect to any of {:?}! ", peers);
-                log::error!("{}", &e);
-                Err(e.into())
             }
         }
     }
 
+    async fn connect_any(&self, peers: &[EndPoint], timeout: Duration) -> ZResult<()> {
+        for locator in peers {
+            match self
+                .manager()
+                .open_transport(locator.clone())
+                .timeout(timeout)
+                .await
+            {
+                Ok(Ok(_)) => return Ok(()),
+                Ok(Err(e)) => log::warn!("Unable to connect to {}! {}", locator, e),
+                Err(e) => log::warn!("Unable to connect to {}! {}", locator, e),
+            }
+        }
+        bail!("Unable to connect to any of {:?}!", peers)
+    }
+
     async fn start_peer(&self) -> ZResult<()> {
         let (listeners, peers, scouting, listen, autoconnect, addr, ifaces, delay) = {
             let guard = &self.config.lock();
@@ -151,7 +207,8 @@ impl Runtime {
         if scouting {
             self.start_scout(listen, autoconnect, addr, ifaces).await?;
         }
-        async_std::task::sleep(delay).await;
+        self.start_unicast_scout()?;
+        clock::sleep(delay).await;
         Ok(())
     }
 
@@ -196,6 +253,7 @@ impl Runtime {
         if scouting {
             self.start_scout(listen, autoconnect, addr, ifaces).await?;
         }
+        self.start_unicast_scout()?;
 
         Ok(())
     }
@@ -207,12 +265,13 @@ impl Runtime {
         addr: SocketAddr,
         ifaces: String,
     ) -> ZResult<()> {
+        let ttl = unwrap_or_default!(self.config.lock().scouting().multicast().ttl());
         let ifaces = Runtime::get_interfaces(&ifaces);
         let mcast_socket = Runtime::bind_mcast_port(&addr, &ifaces).await?;
         if !ifaces.is_empty() {
             let sockets: Vec<UdpSocket> = ifaces
                 .into_iter()
-                .filter_map(|iface| Runtime::bind_ucast_port(iface).ok())
+                .filter_map(|iface| Runtime::bind_ucast_port(iface, ttl).ok())
                 .collect();
             if !sockets.is_empty() {
                 let this = self.clone();
@@ -243,6 +302,20 @@ impl Runtime {
         Ok(())
     }
 
+    // Reply to the scout messages received on the configured unicast address, if any
+    fn start_unicast_scout(&self) -> ZResult<()> {
+        let addr = match *self.config.lock().scouting().unicast().address() {
+            Some(addr) => addr,
+            None => return Ok(()),
+        };
+        let socket = Runtime::bind_unicast_scout_port(&addr)?;
+        let this = self.clone();
+        self.spawn(async move {
+            this.responder(&socket, std::slice::from_ref(&socket)).await;
+        });
+        Ok(())
+    }
+
     pub(crate) async fn update_peers(&self) -> ZResult<()> {
         let peers = { self.config.lock().connect().endpoints().clone() };
         let tranports = self.manager().get_transports();
@@ -422,7 +495,31 @@ impl Runtime {
         Ok(std::net::UdpSocket::from(socket).into())
     }
 
-    pub fn bind_ucast_port(addr: IpAddr) -> ZResult<UdpSocket> {
+    pub fn bind_unicast_scout_port(addr: &SocketAddr) -> ZResult<UdpSocket> {
+        let socket = match Socket::new(Domain::for_address(*addr), Type::DGRAM, None) {
+            Ok(socket) => socket,
+            Err(err) => {
+                log::error!("Unable to create datagram socket: {}", err);
+                bail!(err => "Unable to create datagram socket");
+            }
+        };
+        // The port may be shared with the multicast scouting
+        if let Err(err) = socket.set_reuse_address(true) {
+            log::error!("Unable to set SO_REUSEADDR option: {}", err);
+            bail!(err => "Unable to set SO_REUSEADDR option");
+        }
+        match socket.bind(&(*addr).into()) {
+            Ok(()) => log::debug!("UDP port bound to {}", addr),
+            Err(err) => {
+                log::error!("Unable to bind UDP port {}: {}", addr, err);
+                bail!(err => "Unable to bind UDP port {}", addr);
+            }
+        }
+        log::info!("zenohd listening scout messages on {}", addr);
+        Ok(std::net::UdpSocket::from(socket).into())
+    }
+
+    pub fn bind_ucast_port(addr: IpAddr, ttl: u32) -> ZResult<UdpSocket> {
         let socket = match Socket::new(Domain::IPV4, Type::DGRAM, None) {
             Ok(socket) => socket,
             Err(err) => {
@@ -430,6 +527,10 @@ impl Runtime {
                 bail!(err=> "Unable to create datagram socket");
             }
         };
+        if let Err(err) = socket.set_multicast_ttl_v4(ttl) {
+            log::warn!("Unable to set IP_MULTICAST_TTL option to {}: {}", ttl, err);
+            bail!(err => "Unable to set IP_MULTICAST_TTL option to {}", ttl);
+        }
         match socket.bind(&SocketAddr::new(addr, 0).into()) {
             Ok(()) => {
                 #[allow(clippy::or_fun_call)]
@@ -489,7 +590,7 @@ impl Runtime {
                     );
                 }
             }
-            async_std::task::sleep(delay).await;
+            clock::sleep(delay).await;
             delay *= CONNECTION_RETRY_PERIOD_INCREASE_FACTOR;
             if delay > CONNECTION_RETRY_MAX_PERIOD {
                 delay = CONNECTION_RETRY_MAX_PERIOD;
@@ -500,48 +601,53 @@ impl Runtime {
     pub async fn scout<Fut, F>(
         sockets: &[UdpSocket],
         matcher: WhatAmIMatcher,
-        mcast_addr: &SocketAddr,
+        domain: ZInt,
+        key: Option<&[u8]>,
+        addrs: &[SocketAddr],
         f: F,
     ) where
         F: Fn(Hello) -> Fut + std::marker::Send + std::marker::Sync + Clone,
         Fut: Future<Output = Loop> + std::marker::Send,
         Self: Sized,
     {
+        let mut scout = ScoutingMessage::make_scout(Some(matcher), true, None);
+        scout.set_domain(domain);
+        if let Some(key) = key {
+            if let Err(e) = sign(&mut scout, key) {
+                log::error!("Unable to sign {:?}: {}", scout.body, e);
+                return;
+            }
+        }
+        let wbuf = serialize(&scout);
+
         let send = async {
             let mut delay = SCOUT_INITIAL_PERIOD;
 
-            let scout = ScoutingMessage::make_scout(Some(matcher), true, None);
-            let mut wbuf = vec![];
-            let mut writer = wbuf.writer();
-            let codec = Zenoh060::default();
-            codec.write(&mut writer, &scout).unwrap();
-
             loop {
                 for socket in sockets {
-                    log::trace!(
-                        "Send {:?} to {} on interface {}",
-                        scout.body,
-                        mcast_addr,
-                        socket
-                            .local_addr()
-                            .map_or("unknown".to_string(), |addr| addr.ip().to_string())
-                    );
-                    if let Err(err) = socket
-                        .send_to(wbuf.as_slice(), mcast_addr.to_string())
-                        .await
-                    {
-                        log::debug!(
-                            "Unable to send {:?} to {} on interface {}: {}",
+                    for dest in addrs {
+                        log::trace!(
+                            "Send {:?} to {} on interface {}",
                             scout.body,
-                            mcast_addr,
+                            dest,
                             socket
                                 .local_addr()
-                                .map_or("unknown".to_string(), |addr| addr.ip().to_string()),
-                            err
+                                .map_or("unknown".to_string(), |addr| addr.ip().to_string())
                         );
+                        if let Err(err) = socket.send_to(wbuf.as_slice(), *dest).await {
+                            log::debug!(
+                                "Unable to send {:?} to {} on interface {}: {}",
+                                scout.body,
+                                dest,
+                                socket
+                                    .local_addr()
+                                    .map_or("unknown".to_string(), |addr| addr.ip().to_string()),
+                                err
+                            );
+                        }
                     }
                 }
-                async_std::task::sleep(delay).await;
+                clock::sleep(delay).await;
                 if delay * SCOUT_PERIOD_INCREASE_FACTOR <= SCOUT_MAX_PERIOD {
                     delay *= SCOUT_PERIOD_INCREASE_FACTOR;
                 }
@@ -560,7 +666,18 @@ impl Runtime {
                             if let Ok(msg) = res {
                                 log::trace!("Received {:?} from {}", msg.body, peer);
                                 if let ScoutingBody::Hello(hello) = &msg.body {
-                                    if matcher.matches(hello.whatami) {
+                                    if msg.domain() != domain {
+                                        log::trace!(
+                                            "Ignore Hello of domain {} from {}",
+                                            msg.domain(),
+                                            peer
+                                        );
+                                    } else if !verify(&msg, key) {
+                                        log::warn!(
+                                            "Ignore Hello with an invalid signature from {}",
+                                            peer
+                                        );
+                                    } else if matcher.matches(hello.whatami) {
                                         if let Loop::Break = f(hello.clone()).await {
                                             break;
                                         }
@@ -629,11 +746,14 @@ impl Runtime {
         &self,
         sockets: &[UdpSocket],
         what: WhatAmIMatcher,
-        addr: &SocketAddr,
+        addrs: &[SocketAddr],
         timeout: std::time::Duration,
     ) -> ZResult<()> {
         let scout = async {
-            Runtime::scout(sockets, what, addr, move |hello| async move {
+            let domain = self.manager().config.domain;
+            let key = self.config.lock().scouting().multicast().key().clone();
+            let key = key.as_deref().map(str::as_bytes);
+            Runtime::scout(sockets, what, domain, key, addrs, move |hello| async move {
                 log::info!("Found {:?}", hello);
                 if !hello.locators.is_empty() {
                     if let Some(transport) = self.connect(&hello.locators).await {
@@ -654,8 +774,8 @@ impl Runtime {
             Ok(())
         };
         let timeout = async {
-            async_std::task::sleep(timeout).await;
-            bail!("timeout")
+            clock::sleep(timeout).await;
+            bail!((ErrorKind::Timeout) "No {} found after {:?}", what, timeout)
         };
         async_std::prelude::FutureExt::race(scout, timeout).await
     }
@@ -666,25 +786,35 @@ impl Runtime {
         what: WhatAmIMatcher,
         addr: &SocketAddr,
     ) {
-        Runtime::scout(ucast_sockets, what, addr, move |hello| async move {
-            match &hello.zid {
-                Some(zid) => {
-                    if !hello.locators.is_empty() {
-                        self.connect_peer(zid, &hello.locators).await
-                    } else {
-                        log::warn!("Received Hello with no locators: {:?}", hello);
+        let domain = self.manager().config.domain;
+        let key = self.config.lock().scouting().multicast().key().clone();
+        let key = key.as_deref().map(str::as_bytes);
+        Runtime::scout(
+            ucast_sockets,
+            what,
+            domain,
+            key,
+            std::slice::from_ref(addr),
+            move |hello| async move {
+                match &hello.zid {
+                    Some(zid) => {
+                        if !hello.locators.is_empty() {
+                            self.connect_peer(zid, &hello.locators).await
+                        } else {
+                            log::warn!("Received Hello with no locators: {:?}", hello);
+                        }
+                    }
+                    None => {
+                        log::warn!("Received Hello with no zid: {:?}", hello);
                     }
                 }
-                None => {
-                    log::warn!("Received Hello with no zid: {:?}", hello);
-                }
-            }
-            Loop::Continue
-        })
+                Loop::Continue
+            },
+        )
         .await
     }
 
-    async fn responder(&self, mcast_socket: &UdpSocket, ucast_sockets: &[UdpSocket]) {
+    async fn responder(&self, scout_socket: &UdpSocket, ucast_sockets: &[UdpSocket]) {
         fn get_best_match<'a>(addr: &IpAddr, sockets: &'a [UdpSocket]) -> Option<&'a UdpSocket> {
             fn octets(addr: &IpAddr) -> Vec<u8> {
                 match addr {
@@ -713,9 +843,12 @@ impl Runtime {
             .iter()
             .filter_map(|sock| sock.local_addr().ok())
             .collect();
+        let domain = self.manager().config.domain;
+        let key = self.config.lock().scouting().multicast().key().clone();
+        let key = key.as_deref().map(str::as_bytes);
         log::debug!("Waiting for UDP datagram...");
         loop {
-            let (n, peer) = mcast_socket.recv_from(&mut buf).await.unwrap();
+            let (n, peer) = scout_socket.recv_from(&mut buf).await.unwrap();
             if local_addrs.iter().any(|addr| *addr == peer) {
                 log::trace!("Ignore UDP datagram from own socket");
                 continue;
@@ -730,23 +863,34 @@ impl Runtime {
                     what, zid_request, ..
                 }) = &msg.body
                 {
+                    if msg.domain() != domain {
+                        log::trace!("Ignore Scout of domain {} from {}", msg.domain(), peer);
+                        continue;
+                    }
+                    if !verify(&msg, key) {
+                        log::warn!("Ignore Scout with an invalid signature from {}", peer);
+                        continue;
+                    }
                     let what = what.or(Some(WhatAmI::Router.into())).unwrap();
                     if what.matches(self.whatami) {
-                        let mut wbuf = vec![];
-                        let mut writer = wbuf.writer();
-                        let codec = Zenoh060::default();
-
                         let zid = if *zid_request {
                             Some(self.manager().zid())
                         } else {
                             None
                         };
-                        let hello = ScoutingMessage::make_hello(
+                        let mut hello = ScoutingMessage::make_hello(
                             zid,
                             Some(self.whatami),
                             Some(self.get_locators()),
                             None,
                         );
+                        hello.set_domain(domain);
+                        if let Some(key) = key {
+                            if let Err(e) = sign(&mut hello, key) {
+                                log::error!("Unable to sign {:?}: {}", hello.body, e);
+                                continue;
+                            }
+                        }
                         let socket = get_best_match(&peer.ip(), ucast_sockets).unwrap();
                         log::trace!(
                             "Send {:?} to {} on interface {}",
@@ -756,8 +900,7 @@ impl Runtime {
                                 .local_addr()
                                 .map_or("unknown".to_string(), |addr| addr.ip().to_string())
                         );
-                        codec.write(&mut writer, &hello).unwrap();
-
+                        let wbuf = serialize(&hello);
                         if let Err(err) = socket.send_to(wbuf.as_slice(), peer).await {
                             log::error!("Unable to send {:?} to {}: {}", hello.body, peer, err);
                         }
@@ -780,7 +923,7 @@ impl Runtime {
                 session.runtime.spawn(async move {
                     let mut delay = CONNECTION_RETRY_INITIAL_PERIOD;
                     while runtime.start_client().await.is_err() {
-                        async_std::task::sleep(delay).await;
+                        clock::sleep(delay).await;
                         delay *= CONNECTION_RETRY_PERIOD_INCREASE_FACTOR;
                         if delay > CONNECTION_RETRY_MAX_PERIOD {
                             delay = CONNECTION_RETRY_MAX_PERIOD;
@@ -803,3 +946,54 @@ impl Runtime {
         }
     }
 }
+
+fn serialize(msg: &ScoutingMessage) -> Vec<u8> {
+    let mut wbuf = vec![];
+    let mut writer = wbuf.writer();
+    let codec = Zenoh060::default();
+    codec.write(&mut writer, msg).unwrap();
+    wbuf
+}
+
+// Sign a scouting message with the HMAC of its serialization
+fn sign(msg: &mut ScoutingMessage, key: &[u8]) -> ZResult<()> {
+    let signature = hmac::sign(key, &serialize(msg))?;
+    msg.set_signature(Signature(signature));
+    Ok(())
+}
+
+// Whether a scouting message is signed with `key`, or no signature is required
+fn verify(msg: &ScoutingMessage, key: Option<&[u8]>) -> bool {
+    let key = match key {
+        Some(key) => key,
+        None => return true,
+    };
+    let mut msg = msg.clone();
+    match msg.take_signature() {
+        Some(signature) => hmac::verify(key, &serialize(&msg), &signature.0).unwrap_or(false),
+        None => false,
+    }
+}
+
+#[test]
+fn scouting_signature() {
+    let key = b"key".as_slice();
+    let mut hello = ScoutingMessage::make_hello(None, Some(WhatAmI::Router), None, None);
+    hello.set_domain(1);
+    assert!(verify(&hello, None));
+    assert!(!verify(&hello, Some(key)));
+
+    sign(&mut hello, key).unwrap();
+    assert!(verify(&hello, Some(key)));
+    assert!(!verify(&hello, Some(b"other".as_slice())));
+    // The signature survives the serialization
+    let bytes = serialize(&hello);
+    let mut reader = bytes.as_slice().reader();
+    let msg: ScoutingMessage = Zenoh060::default().read(&mut reader).unwrap();
+    assert!(verify(&msg, Some(key)));
+
+    // A tampered message is rejected
+    let mut tampered = hello;
+    tampered.set_domain(2);
+    assert!(!verify(&tampered, Some(key)));
+}
@@ -11,19 +11,21 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
+use crate::net::routing::face::{routed_from, Face};
 use crate::net::routing::router::{self, *};
 use std::convert::{TryFrom, TryInto};
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::Duration;
 use uhlc::HLC;
 use zenoh_buffers::ZBuf;
-use zenoh_config::ZN_QUERIES_DEFAULT_TIMEOUT_DEFAULT;
+use zenoh_config::{FutureTimestampPolicy, ZN_QUERIES_DEFAULT_TIMEOUT_DEFAULT};
 use zenoh_core::zlock;
 use zenoh_protocol::{
+    common::Attachment,
     core::{
-        key_expr::keyexpr, Channel, CongestionControl, ConsolidationMode, QueryTarget,
-        QueryableInfo, Reliability, SubInfo, SubMode, WhatAmI, WireExpr, ZInt, ZenohId,
-        EMPTY_EXPR_ID,
+        key_expr::keyexpr, Channel, CongestionControl, ConsolidationMode, Encoding, KnownEncoding,
+        QueryTarget, QueryableInfo, Reliability, SubAggregation, SubInfo, SubMode, WhatAmI,
+        WireExpr, ZInt, ZenohId, EMPTY_EXPR_ID,
     },
     zenoh::{DataInfo, QueryBody, RoutingContext},
 };
@@ -36,7 +38,7 @@ fn base_test() {
             ZenohId::try_from([1]).unwrap(),
             WhatAmI::Client,
             Some(Arc::new(HLC::default())),
-            false,
+            FutureTimestampPolicy::Clamp,
             true,
             Duration::from_millis(ZN_QUERIES_DEFAULT_TIMEOUT_DEFAULT.parse().unwrap()),
         )),
@@ -66,6 +68,8 @@ fn base_test() {
     let sub_info = SubInfo {
         reliability: Reliability::Reliable,
         mode: SubMode::Push,
+        filter: None,
+        aggregation: None,
     };
     declare_client_subscription(
         &tables,
@@ -137,7 +141,7 @@ fn match_test() {
             ZenohId::try_from([1]).unwrap(),
             WhatAmI::Client,
             Some(Arc::new(HLC::default())),
-            false,
+            FutureTimestampPolicy::Clamp,
             true,
             Duration::from_millis(ZN_QUERIES_DEFAULT_TIMEOUT_DEFAULT.parse().unwrap()),
         )),
@@ -183,7 +187,7 @@ fn clean_test() {
             ZenohId::try_from([1]).unwrap(),
             WhatAmI::Client,
             Some(Arc::new(HLC::default())),
-            false,
+            FutureTimestampPolicy::Clamp,
             true,
             Duration::from_millis(ZN_QUERIES_DEFAULT_TIMEOUT_DEFAULT.parse().unwrap()),
         )),
@@ -252,6 +256,8 @@ fn clean_test() {
     let sub_info = SubInfo {
         reliability: Reliability::Reliable,
         mode: SubMode::Push,
+        filter: None,
+        aggregation: None,
     };
 
     declare_client_subscription(
@@ -376,6 +382,8 @@ fn clean_test() {
 pub struct ClientPrimitives {
     data: std::sync::Mutex<Option<WireExpr<'static>>>,
     mapping: std::sync::Mutex<std::collections::HashMap<ZInt, String>>,
+    subs: std::sync::Mutex<std::collections::BTreeSet<String>>,
+    routed_from: std::sync::Mutex<Option<ZenohId>>,
 }
 
 impl ClientPrimitives {
@@ -383,6 +391,8 @@ impl ClientPrimitives {
         ClientPrimitives {
             data: std::sync::Mutex::new(None),
             mapping: std::sync::Mutex::new(std::collections::HashMap::new()),
+            subs: std::sync::Mutex::new(std::collections::BTreeSet::new()),
+            routed_from: std::sync::Mutex::new(None),
         }
     }
 
@@ -422,6 +432,10 @@ impl ClientPrimitives {
     fn get_last_key(&self) -> Option<WireExpr> {
         self.data.lock().unwrap().as_ref().cloned()
     }
+
+    fn get_subs(&self) -> Vec<String> {
+        self.subs.lock().unwrap().iter().cloned().collect()
+    }
 }
 
 impl Primitives for ClientPrimitives {
@@ -439,12 +453,17 @@ impl Primitives for ClientPrimitives {
 
     fn decl_subscriber(
         &self,
-        _key_expr: &WireExpr,
+        key_expr: &WireExpr,
         _sub_info: &SubInfo,
         _routing_context: Option<RoutingContext>,
     ) {
+        let name = self.get_name(key_expr);
+        zlock!(self.subs).insert(name);
+    }
+    fn forget_subscriber(&self, key_expr: &WireExpr, _routing_context: Option<RoutingContext>) {
+        let name = self.get_name(key_expr);
+        zlock!(self.subs).remove(&name);
     }
-    fn forget_subscriber(&self, _key_expr: &WireExpr, _routing_context: Option<RoutingContext>) {}
 
     fn decl_queryable(
         &self,
@@ -465,6 +484,7 @@ impl Primitives for ClientPrimitives {
         _routing_context: Option<RoutingContext>,
     ) {
         *zlock!(self.data) = Some(key_expr.to_owned());
+        *zlock!(self.routed_from) = routed_from();
     }
 
     fn send_query(
@@ -486,6 +506,7 @@ impl Primitives for ClientPrimitives {
         _key_expr: WireExpr,
         _info: Option<DataInfo>,
         _payload: ZBuf,
+        _attachment: Option<Attachment>,
     ) {
     }
     fn send_reply_final(&self, _qid: ZInt) {}
@@ -509,7 +530,7 @@ fn client_test() {
             ZenohId::try_from([1]).unwrap(),
             WhatAmI::Client,
             Some(Arc::new(HLC::default())),
-            false,
+            FutureTimestampPolicy::Clamp,
             true,
             Duration::from_millis(ZN_QUERIES_DEFAULT_TIMEOUT_DEFAULT.parse().unwrap()),
         )),
@@ -520,6 +541,8 @@ fn client_test() {
     let sub_info = SubInfo {
         reliability: Reliability::Reliable,
         mode: SubMode::Push,
+        filter: None,
+        aggregation: None,
     };
 
     let primitives0 = Arc::new(ClientPrimitives::new());
@@ -730,3 +753,796 @@ fn client_test() {
     // mapping strategy check
     // assert_eq!(primitives2.get_last_key().unwrap(), KeyExpr::IdWithSuffix(31, "/z2_pub1".to_string()));
 }
+
+#[test]
+fn filtered_subscription_test() {
+    let tables = TablesLock {
+        tables: RwLock::new(Tables::new(
+            ZenohId::try_from([1]).unwrap(),
+            WhatAmI::Client,
+            Some(Arc::new(HLC::default())),
+            FutureTimestampPolicy::Clamp,
+            true,
+            Duration::from_millis(ZN_QUERIES_DEFAULT_TIMEOUT_DEFAULT.parse().unwrap()),
+        )),
+        ctrl_lock: Mutex::new(()),
+        queries_lock: RwLock::new(()),
+    };
+
+    let primitives0 = Arc::new(ClientPrimitives::new());
+    let face0 = zwrite!(tables.tables).open_face(
+        ZenohId::try_from([1]).unwrap(),
+        WhatAmI::Client,
+        primitives0,
+    );
+
+    let primitives1 = Arc::new(ClientPrimitives::new());
+    let face1 = zwrite!(tables.tables).open_face(
+        ZenohId::try_from([1]).unwrap(),
+        WhatAmI::Client,
+        primitives1.clone(),
+    );
+    declare_client_subscription(
+        &tables,
+        zread!(tables.tables),
+        &mut face1.upgrade().unwrap(),
+        &"test/filter/**".into(),
+        &SubInfo {
+            reliability: Reliability::Reliable,
+            mode: SubMode::Push,
+            filter: Some("temperature > 30".to_string()),
+            aggregation: None,
+        },
+    );
+
+    let primitives2 = Arc::new(ClientPrimitives::new());
+    let face2 = zwrite!(tables.tables).open_face(
+        ZenohId::try_from([1]).unwrap(),
+        WhatAmI::Client,
+        primitives2.clone(),
+    );
+    declare_client_subscription(
+        &tables,
+        zread!(tables.tables),
+        &mut face2.upgrade().unwrap(),
+        &"test/filter/**".into(),
+        &SubInfo::default(),
+    );
+
+    let route_json = |payload: &str| {
+        primitives1.clear_data();
+        primitives2.clear_data();
+        full_reentrant_route_data(
+            &tables.tables,
+            &face0.upgrade().unwrap(),
+            &"test/filter/sensor".into(),
+            Channel::default(),
+            CongestionControl::default(),
+            Some(DataInfo {
+                encoding: Some(Encoding::Exact(KnownEncoding::AppJson)),
+                ..Default::default()
+            }),
+            ZBuf::from(payload.as_bytes().to_vec()),
+            None,
+        );
+    };
+
+    // the filtered subscriber only receives the matching data
+    route_json(r#"{"temperature": 20}"#);
+    assert!(primitives1.get_last_name().is_none());
+    assert_eq!(primitives2.get_last_name().unwrap(), "test/filter/sensor");
+
+    route_json(r#"{"temperature": 40}"#);
+    assert_eq!(primitives1.get_last_name().unwrap(), "test/filter/sensor");
+    assert_eq!(primitives2.get_last_name().unwrap(), "test/filter/sensor");
+
+    // a new declaration of the same subscription replaces the filter
+    declare_client_subscription(
+        &tables,
+        zread!(tables.tables),
+        &mut face1.upgrade().unwrap(),
+        &"test/filter/**".into(),
+        &SubInfo {
+            reliability: Reliability::Reliable,
+            mode: SubMode::Push,
+            filter: Some("temperature > 50".to_string()),
+            aggregation: None,
+        },
+    );
+    route_json(r#"{"temperature": 40}"#);
+    assert!(primitives1.get_last_name().is_none());
+    route_json(r#"{"temperature": 60}"#);
+    assert_eq!(primitives1.get_last_name().unwrap(), "test/filter/sensor");
+
+    // the filters are looked up among the matching resources once the data has its resource
+    register_expr(
+        &tables,
+        &mut face0.upgrade().unwrap(),
+        11,
+        &"test/filter/sensor".into(),
+    );
+    route_json(r#"{"temperature": 40}"#);
+    assert!(primitives1.get_last_name().is_none());
+    assert_eq!(primitives2.get_last_name().unwrap(), "test/filter/sensor");
+    route_json(r#"{"temperature": 60}"#);
+    assert_eq!(primitives1.get_last_name().unwrap(), "test/filter/sensor");
+
+    // a new unfiltered declaration of the same subscription removes the filter
+    declare_client_subscription(
+        &tables,
+        zread!(tables.tables),
+        &mut face1.upgrade().unwrap(),
+        &"test/filter/**".into(),
+        &SubInfo::default(),
+    );
+    route_json(r#"{"temperature": 20}"#);
+    assert_eq!(primitives1.get_last_name().unwrap(), "test/filter/sensor");
+}
+
+#[test]
+fn geo_subscription_test() {
+    let tables = TablesLock {
+        tables: RwLock::new(Tables::new(
+            ZenohId::try_from([1]).unwrap(),
+            WhatAmI::Client,
+            Some(Arc::new(HLC::default())),
+            FutureTimestampPolicy::Clamp,
+            true,
+            Duration::from_millis(ZN_QUERIES_DEFAULT_TIMEOUT_DEFAULT.parse().unwrap()),
+        )),
+        ctrl_lock: Mutex::new(()),
+        queries_lock: RwLock::new(()),
+    };
+
+    let primitives0 = Arc::new(ClientPrimitives::new());
+    let face0 = zwrite!(tables.tables).open_face(
+        ZenohId::try_from([1]).unwrap(),
+        WhatAmI::Client,
+        primitives0,
+    );
+
+    let primitives1 = Arc::new(ClientPrimitives::new());
+    let face1 = zwrite!(tables.tables).open_face(
+        ZenohId::try_from([1]).unwrap(),
+        WhatAmI::Client,
+        primitives1.clone(),
+    );
+    declare_client_subscription(
+        &tables,
+        zread!(tables.tables),
+        &mut face1.upgrade().unwrap(),
+        &"fleet/**".into(),
+        &SubInfo {
+            reliability: Reliability::Reliable,
+            mode: SubMode::Push,
+            filter: Some("geo($1, $2) in [43.5, 1.3, 43.7, 1.6]".to_string()),
+            aggregation: None,
+        },
+    );
+
+    let route = |key_expr: &str| {
+        primitives1.clear_data();
+        full_reentrant_route_data(
+            &tables.tables,
+            &face0.upgrade().unwrap(),
+            &key_expr.into(),
+            Channel::default(),
+            CongestionControl::default(),
+            None,
+            ZBuf::default(),
+            None,
+        );
+    };
+
+    // the router only forwards the data located in the region of interest of the subscriber
+    route("fleet/43.6/1.44/truck-1");
+    assert_eq!(
+        primitives1.get_last_name().unwrap(),
+        "fleet/43.6/1.44/truck-1"
+    );
+    route("fleet/48.85/2.35/truck-2");
+    assert!(primitives1.get_last_name().is_none());
+    route("fleet/truck-3");
+    assert!(primitives1.get_last_name().is_none());
+}
+
+#[test]
+fn aggregated_subscription_test() {
+    let tables = TablesLock {
+        tables: RwLock::new(Tables::new(
+            ZenohId::try_from([1]).unwrap(),
+            WhatAmI::Router,
+            Some(Arc::new(HLC::default())),
+            FutureTimestampPolicy::Clamp,
+            true,
+            Duration::from_millis(ZN_QUERIES_DEFAULT_TIMEOUT_DEFAULT.parse().unwrap()),
+        )),
+        ctrl_lock: Mutex::new(()),
+        queries_lock: RwLock::new(()),
+    };
+
+    let primitives0 = Arc::new(ClientPrimitives::new());
+    let face0 = zwrite!(tables.tables).open_face(
+        ZenohId::try_from([1]).unwrap(),
+        WhatAmI::Client,
+        primitives0,
+    );
+
+    let primitives1 = Arc::new(ClientPrimitives::new());
+    let face1 = zwrite!(tables.tables).open_face(
+        ZenohId::try_from([1]).unwrap(),
+        WhatAmI::Client,
+        primitives1.clone(),
+    );
+    declare_client_subscription(
+        &tables,
+        zread!(tables.tables),
+        &mut face1.upgrade().unwrap(),
+        &"test/aggregation/**".into(),
+        &SubInfo {
+            reliability: Reliability::Reliable,
+            mode: SubMode::Push,
+            filter: None,
+            aggregation: Some(SubAggregation {
+                window: 100,
+                max_rate: 0,
+            }),
+        },
+    );
+
+    let primitives2 = Arc::new(ClientPrimitives::new());
+    let face2 = zwrite!(tables.tables).open_face(
+        ZenohId::try_from([1]).unwrap(),
+        WhatAmI::Client,
+        primitives2.clone(),
+    );
+    declare_client_subscription(
+        &tables,
+        zread!(tables.tables),
+        &mut face2.upgrade().unwrap(),
+        &"test/aggregation/**".into(),
+        &SubInfo::default(),
+    );
+
+    let route = |key_expr: &str| {
+        full_reentrant_route_data(
+            &tables.tables,
+            &face0.upgrade().unwrap(),
+            &key_expr.into(),
+            Channel::default(),
+            CongestionControl::default(),
+            None,
+            ZBuf::default(),
+            None,
+        );
+    };
+
+    // the aggregated subscriber only receives the data at the end of the window
+    route("test/aggregation/sensor1");
+    route("test/aggregation/sensor2");
+    assert!(primitives1.get_last_name().is_none());
+    assert_eq!(
+        primitives2.get_last_name().unwrap(),
+        "test/aggregation/sensor2"
+    );
+    std::thread::sleep(Duration::from_millis(500));
+    assert_eq!(
+        primitives1.get_last_name().unwrap(),
+        "test/aggregation/sensor2"
+    );
+
+    // a new unaggregated declaration of the same subscription removes the aggregation
+    declare_client_subscription(
+        &tables,
+        zread!(tables.tables),
+        &mut face1.upgrade().unwrap(),
+        &"test/aggregation/**".into(),
+        &SubInfo::default(),
+    );
+    primitives1.clear_data();
+    route("test/aggregation/sensor1");
+    assert_eq!(
+        primitives1.get_last_name().unwrap(),
+        "test/aggregation/sensor1"
+    );
+}
+
+#[test]
+fn mappings_test() {
+    let tables = TablesLock {
+        tables: RwLock::new(Tables::new(
+            ZenohId::try_from([1]).unwrap(),
+            WhatAmI::Client,
+            Some(Arc::new(HLC::default())),
+            FutureTimestampPolicy::Clamp,
+            true,
+            Duration::from_millis(ZN_QUERIES_DEFAULT_TIMEOUT_DEFAULT.parse().unwrap()),
+        )),
+        ctrl_lock: Mutex::new(()),
+        queries_lock: RwLock::new(()),
+    };
+    zwrite!(tables.tables).max_local_mappings = Some(2);
+
+    let sub_info = SubInfo {
+        reliability: Reliability::Reliable,
+        mode: SubMode::Push,
+        filter: None,
+        aggregation: None,
+    };
+
+    let primitives0 = Arc::new(ClientPrimitives::new());
+    let _face0 = zwrite!(tables.tables).open_face(
+        ZenohId::try_from([1]).unwrap(),
+        WhatAmI::Client,
+        primitives0.clone(),
+    );
+    let primitives1 = Arc::new(ClientPrimitives::new());
+    let face1 = zwrite!(tables.tables).open_face(
+        ZenohId::try_from([1]).unwrap(),
+        WhatAmI::Client,
+        primitives1,
+    );
+
+    for key_expr in ["test/mappings/a", "test/mappings/b", "test/mappings/c"] {
+        declare_client_subscription(
+            &tables,
+            zread!(tables.tables),
+            &mut face1.upgrade().unwrap(),
+            &key_expr.into(),
+            &sub_info,
+        );
+    }
+    // the mappings used by declarations are never forgotten, even above the maximum
+    let mapped = || {
+        let mut mapped: Vec<String> = zlock!(primitives0.mapping).values().cloned().collect();
+        mapped.sort();
+        mapped
+    };
+    assert_eq!(
+        mapped(),
+        vec!["test/mappings/a", "test/mappings/b", "test/mappings/c"]
+    );
+
+    // the mapping of an undeclared subscription is forgotten
+    forget_client_subscription(
+        &tables,
+        zread!(tables.tables),
+        &mut face1.upgrade().unwrap(),
+        &"test/mappings/a".into(),
+    );
+    assert_eq!(mapped(), vec!["test/mappings/b", "test/mappings/c"]);
+
+    // the mappings used by the subscriptions of a closed face are forgotten
+    router::close_face(&tables, &face1);
+    assert!(zlock!(primitives0.mapping).is_empty());
+}
+
+#[test]
+fn hot_keys_test() {
+    let tables = TablesLock {
+        tables: RwLock::new(Tables::new(
+            ZenohId::try_from([1]).unwrap(),
+            WhatAmI::Peer,
+            Some(Arc::new(HLC::default())),
+            FutureTimestampPolicy::Clamp,
+            true,
+            Duration::from_millis(ZN_QUERIES_DEFAULT_TIMEOUT_DEFAULT.parse().unwrap()),
+        )),
+        ctrl_lock: Mutex::new(()),
+        queries_lock: RwLock::new(()),
+    };
+    zwrite!(tables.tables).hot_key_threshold = Some(3);
+    zwrite!(tables.tables).max_local_mappings = Some(1);
+
+    let sub_info = SubInfo {
+        reliability: Reliability::Reliable,
+        mode: SubMode::Push,
+        filter: None,
+        aggregation: None,
+    };
+
+    let primitives0 = Arc::new(ClientPrimitives::new());
+    let face0 = zwrite!(tables.tables).open_net_face(
+        ZenohId::try_from([2]).unwrap(),
+        WhatAmI::Client,
+        primitives0,
+        0,
+        false,
+    );
+    let primitives1 = Arc::new(ClientPrimitives::new());
+    let face1 = zwrite!(tables.tables).open_net_face(
+        ZenohId::try_from([3]).unwrap(),
+        WhatAmI::Client,
+        primitives1.clone(),
+        0,
+        false,
+    );
+    declare_client_subscription(
+        &tables,
+        zread!(tables.tables),
+        &mut face1.upgrade().unwrap(),
+        &"test/hot/**".into(),
+        &sub_info,
+    );
+
+    let route = |key_expr: &str| {
+        full_reentrant_route_data(
+            &tables.tables,
+            &face0.upgrade().unwrap(),
+            &key_expr.into(),
+            Channel::default(),
+            CongestionControl::default(),
+            None,
+            ZBuf::default(),
+            None,
+        );
+        (
+            primitives1.get_last_name().unwrap(),
+            primitives1.get_last_key().unwrap().scope,
+        )
+    };
+
+    // the key expression is sent until it becomes hot, then its resource id
+    assert_eq!(
+        route("test/hot/a"),
+        ("test/hot/a".to_string(), EMPTY_EXPR_ID)
+    );
+    assert_eq!(
+        route("test/hot/a"),
+        ("test/hot/a".to_string(), EMPTY_EXPR_ID)
+    );
+    assert_eq!(
+        route("test/hot/a"),
+        ("test/hot/a".to_string(), EMPTY_EXPR_ID)
+    );
+    let (name, scope) = route("test/hot/a");
+    assert_eq!(name, "test/hot/a");
+    assert_ne!(scope, EMPTY_EXPR_ID);
+
+    // above the maximum number of mappings, the least recently used one is forgotten
+    for _ in 0..3 {
+        route("test/hot/b");
+    }
+    assert_ne!(route("test/hot/b").1, EMPTY_EXPR_ID);
+    let mapped: Vec<String> = zlock!(primitives1.mapping).values().cloned().collect();
+    assert_eq!(mapped, vec!["test/hot/b"]);
+    assert_eq!(
+        route("test/hot/a"),
+        ("test/hot/a".to_string(), EMPTY_EXPR_ID)
+    );
+}
+
+#[test]
+fn client_forwarding_test() {
+    let tables = TablesLock {
+        tables: RwLock::new(Tables::new(
+            ZenohId::try_from([1]).unwrap(),
+            WhatAmI::Client,
+            Some(Arc::new(HLC::default())),
+            FutureTimestampPolicy::Clamp,
+            true,
+            Duration::from_millis(ZN_QUERIES_DEFAULT_TIMEOUT_DEFAULT.parse().unwrap()),
+        )),
+        ctrl_lock: Mutex::new(()),
+        queries_lock: RwLock::new(()),
+    };
+
+    let sub_info = SubInfo {
+        reliability: Reliability::Reliable,
+        mode: SubMode::Push,
+        filter: None,
+        aggregation: None,
+    };
+
+    // a local session and two remote nodes
+    let primitives0 = Arc::new(ClientPrimitives::new());
+    let face0 = zwrite!(tables.tables).open_face(
+        ZenohId::try_from([1]).unwrap(),
+        WhatAmI::Client,
+        primitives0.clone(),
+    );
+    let primitives1 = Arc::new(ClientPrimitives::new());
+    let face1 = zwrite!(tables.tables).open_net_face(
+        ZenohId::try_from([2]).unwrap(),
+        WhatAmI::Client,
+        primitives1.clone(),
+        0,
+        false,
+    );
+    let primitives2 = Arc::new(ClientPrimitives::new());
+    let face2 = zwrite!(tables.tables).open_net_face(
+        ZenohId::try_from([3]).unwrap(),
+        WhatAmI::Router,
+        primitives2.clone(),
+        0,
+        false,
+    );
+    declare_client_subscription(
+        &tables,
+        zread!(tables.tables),
+        &mut face2.upgrade().unwrap(),
+        &"test/forwarding".into(),
+        &sub_info,
+    );
+
+    // the local session is served
+    full_reentrant_route_data(
+        &tables.tables,
+        &face0.upgrade().unwrap(),
+        &"test/forwarding".into(),
+        Channel::default(),
+        CongestionControl::default(),
+        None,
+        ZBuf::default(),
+        None,
+    );
+    assert_eq!(
+        primitives2.get_last_name(),
+        Some("test/forwarding".to_string())
+    );
+
+    // but the traffic of a remote node is never forwarded to another remote node
+    primitives2.clear_data();
+    full_reentrant_route_data(
+        &tables.tables,
+        &face1.upgrade().unwrap(),
+        &"test/forwarding".into(),
+        Channel::default(),
+        CongestionControl::default(),
+        None,
+        ZBuf::default(),
+        None,
+    );
+    assert!(primitives2.get_last_name().is_none());
+    assert!(primitives1.get_last_name().is_none());
+}
+
+#[test]
+fn subscription_coverage_test() {
+    let tables = TablesLock {
+        tables: RwLock::new(Tables::new(
+            ZenohId::try_from([1]).unwrap(),
+            WhatAmI::Peer,
+            Some(Arc::new(HLC::default())),
+            FutureTimestampPolicy::Clamp,
+            true,
+            Duration::from_millis(ZN_QUERIES_DEFAULT_TIMEOUT_DEFAULT.parse().unwrap()),
+        )),
+        ctrl_lock: Mutex::new(()),
+        queries_lock: RwLock::new(()),
+    };
+
+    let sub_info = SubInfo {
+        reliability: Reliability::Reliable,
+        mode: SubMode::Push,
+        filter: None,
+        aggregation: None,
+    };
+
+    let primitives0 = Arc::new(ClientPrimitives::new());
+    let face0 = zwrite!(tables.tables).open_face(
+        ZenohId::try_from([2]).unwrap(),
+        WhatAmI::Client,
+        primitives0.clone(),
+    );
+    let primitives1 = Arc::new(ClientPrimitives::new());
+    let face1 = zwrite!(tables.tables).open_face(
+        ZenohId::try_from([3]).unwrap(),
+        WhatAmI::Client,
+        primitives1.clone(),
+    );
+    let declare = |expr: &str| {
+        declare_client_subscription(
+            &tables,
+            zread!(tables.tables),
+            &mut face0.upgrade().unwrap(),
+            &expr.into(),
+            &sub_info,
+        )
+    };
+    let forget = |expr: &str| {
+        forget_client_subscription(
+            &tables,
+            zread!(tables.tables),
+            &mut face0.upgrade().unwrap(),
+            &expr.into(),
+        )
+    };
+
+    declare("test/cover/a/b");
+    assert_eq!(primitives1.get_subs(), vec!["test/cover/a/b"]);
+
+    // a wildcard subscription supersedes the subscriptions it covers
+    declare("test/cover/**");
+    assert_eq!(primitives1.get_subs(), vec!["test/cover/**"]);
+    declare("test/cover/a/*");
+    declare("test/cover/c");
+    assert_eq!(primitives1.get_subs(), vec!["test/cover/**"]);
+
+    // the liveliness subscriptions are always declared
+    declare("@/liveliness/test/cover/**");
+    declare("@/liveliness/test/cover/a");
+    assert_eq!(
+        primitives1.get_subs(),
+        vec![
+            "@/liveliness/test/cover/**",
+            "@/liveliness/test/cover/a",
+            "test/cover/**"
+        ]
+    );
+    forget("@/liveliness/test/cover/**");
+    forget("@/liveliness/test/cover/a");
+
+    // undeclaring a covered subscription sends nothing
+    forget("test/cover/c");
+    assert_eq!(primitives1.get_subs(), vec!["test/cover/**"]);
+
+    // undeclaring the wildcard declares the subscriptions it covered, but not those still covered
+    forget("test/cover/**");
+    assert_eq!(primitives1.get_subs(), vec!["test/cover/a/*"]);
+    forget("test/cover/a/*");
+    assert_eq!(primitives1.get_subs(), vec!["test/cover/a/b"]);
+    forget("test/cover/a/b");
+    assert!(primitives1.get_subs().is_empty());
+
+    // the data is still routed through the covering subscription
+    declare("test/cover/**");
+    declare("test/cover/a/b");
+    full_reentrant_route_data(
+        &tables.tables,
+        &face1.upgrade().unwrap(),
+        &"test/cover/a/b".into(),
+        Channel::default(),
+        CongestionControl::default(),
+        None,
+        ZBuf::default(),
+        None,
+    );
+    assert_eq!(
+        primitives0.get_last_name(),
+        Some("test/cover/a/b".to_string())
+    );
+    assert_eq!(primitives1.get_subs(), vec!["test/cover/**"]);
+}
+
+#[test]
+fn future_timestamp_test() {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use zenoh_protocol::core::{Timestamp, NTP64};
+
+    let tables = TablesLock {
+        tables: RwLock::new(Tables::new(
+            ZenohId::try_from([1]).unwrap(),
+            WhatAmI::Client,
+            Some(Arc::new(HLC::default())),
+            FutureTimestampPolicy::Drop,
+            true,
+            Duration::from_millis(ZN_QUERIES_DEFAULT_TIMEOUT_DEFAULT.parse().unwrap()),
+        )),
+        ctrl_lock: Mutex::new(()),
+        queries_lock: RwLock::new(()),
+    };
+
+    let sub_info = SubInfo {
+        reliability: Reliability::Reliable,
+        mode: SubMode::Push,
+        filter: None,
+        aggregation: None,
+    };
+
+    let primitives0 = Arc::new(ClientPrimitives::new());
+    let face0 = zwrite!(tables.tables).open_face(
+        ZenohId::try_from([2]).unwrap(),
+        WhatAmI::Client,
+        primitives0,
+    );
+    let primitives1 = Arc::new(ClientPrimitives::new());
+    let face1 = zwrite!(tables.tables).open_face(
+        ZenohId::try_from([3]).unwrap(),
+        WhatAmI::Client,
+        primitives1.clone(),
+    );
+    declare_client_subscription(
+        &tables,
+        zread!(tables.tables),
+        &mut face1.upgrade().unwrap(),
+        &"test/timestamp".into(),
+        &sub_info,
+    );
+
+    let id = *HLC::default().new_timestamp().get_id();
+    let route = |time: Duration| {
+        full_reentrant_route_data(
+            &tables.tables,
+            &face0.upgrade().unwrap(),
+            &"test/timestamp".into(),
+            Channel::default(),
+            CongestionControl::default(),
+            Some(DataInfo {
+                timestamp: Some(Timestamp::new(NTP64::from(time), id)),
+                ..Default::default()
+            }),
+            ZBuf::default(),
+            None,
+        )
+    };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+
+    route(now);
+    assert_eq!(
+        primitives1.get_last_name(),
+        Some("test/timestamp".to_string())
+    );
+
+    // the data stamped an hour in the future is dropped
+    primitives1.clear_data();
+    route(now + Duration::from_secs(3600));
+    assert!(primitives1.get_last_name().is_none());
+}
+
+#[test]
+fn routed_from_test() {
+    let tables = Arc::new(TablesLock {
+        tables: RwLock::new(Tables::new(
+            ZenohId::try_from([1]).unwrap(),
+            WhatAmI::Client,
+            Some(Arc::new(HLC::default())),
+            FutureTimestampPolicy::Clamp,
+            true,
+            Duration::from_millis(ZN_QUERIES_DEFAULT_TIMEOUT_DEFAULT.parse().unwrap()),
+        )),
+        ctrl_lock: Mutex::new(()),
+        queries_lock: RwLock::new(()),
+    });
+
+    let sub_info = SubInfo {
+        reliability: Reliability::Reliable,
+        mode: SubMode::Push,
+        filter: None,
+        aggregation: None,
+    };
+
+    let primitives0 = Arc::new(ClientPrimitives::new());
+    let face0 = zwrite!(tables.tables).open_face(
+        ZenohId::try_from([2]).unwrap(),
+        WhatAmI::Client,
+        primitives0,
+    );
+    let primitives1 = Arc::new(ClientPrimitives::new());
+    let face1 = zwrite!(tables.tables).open_face(
+        ZenohId::try_from([3]).unwrap(),
+        WhatAmI::Client,
+        primitives1.clone(),
+    );
+    declare_client_subscription(
+        &tables,
+        zread!(tables.tables),
+        &mut face1.upgrade().unwrap(),
+        &"test/routed_from".into(),
+        &sub_info,
+    );
+
+    // the faces the data is routed to are told which node it was received from
+    let face0 = Face {
+        tables: tables.clone(),
+        state: face0.upgrade().unwrap(),
+    };
+    face0.send_data(
+        &"test/routed_from".into(),
+        ZBuf::default(),
+        Channel::default(),
+        CongestionControl::default(),
+        None,
+        None,
+    );
+    assert_eq!(
+        primitives1.get_last_name(),
+        Some("test/routed_from".to_string())
+    );
+    assert_eq!(
+        *zlock!(primitives1.routed_from),
+        Some(ZenohId::try_from([2]).unwrap())
+    );
+    // out of the routing, the node is unknown
+    assert!(routed_from().is_none());
+}
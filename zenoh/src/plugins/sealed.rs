@@ -17,6 +17,9 @@
 use crate::prelude::Selector;
 pub use crate::runtime::Runtime;
 pub use crate::Result as ZResult;
+use futures::FutureExt;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
 use zenoh_core::zconfigurable;
 
 zconfigurable! {
@@ -28,9 +31,75 @@ pub trait ZenohPlugin: Plugin<StartArgs = StartArgs, RunningPlugin = RunningPlug
 
 /// A zenoh plugin receives a reference to a value of this type when started.
 pub type StartArgs = Runtime;
+
+lazy_static::lazy_static! {
+    // The features altering the layout of the types shared with the plugins
+    static ref FEATURES: String = [
+        ("complete_n", cfg!(feature = "complete_n")),
+        ("shared-memory", cfg!(feature = "shared-memory")),
+        ("stats", cfg!(feature = "stats")),
+        ("unstable", cfg!(feature = "unstable")),
+    ]
+    .iter()
+    .filter_map(|(feature, enabled)| enabled.then_some(*feature))
+    .collect::<Vec<_>>()
+    .join(" ");
+}
+
+impl zenoh_plugin_trait::PluginStartArgs for StartArgs {
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+    fn features() -> &'static str {
+        FEATURES.as_str()
+    }
+}
 /// A zenoh plugin, when started, must return this type.
 pub type RunningPlugin = Box<dyn RunningPluginTrait + 'static>;
 
+/// Spawns the tasks of a plugin, isolating their panics like the ones of the plugin's calls:
+/// should a task panic, the plugin is marked failed, then restarted according to its `__restart__` policy.
+#[derive(Clone)]
+pub struct PluginTasks {
+    runtime: Runtime,
+    name: String,
+}
+
+impl PluginTasks {
+    /// The spawner of the tasks of the plugin started as `name` on `runtime`.
+    pub fn new(runtime: &Runtime, name: &str) -> Self {
+        PluginTasks {
+            runtime: runtime.clone(),
+            name: name.to_string(),
+        }
+    }
+
+    /// Spawns `future` as a task of the plugin. The returned handle resolves to `None` if the task panicked.
+    pub fn spawn<F>(&self, future: F) -> async_std::task::JoinHandle<Option<F::Output>>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        async_std::task::spawn(self.isolate(future))
+    }
+
+    /// Isolates the panics of `future`, to run it on another executor than the one of [`spawn`](Self::spawn).
+    /// The returned future resolves to `None` if `future` panicked.
+    pub fn isolate<F: Future>(&self, future: F) -> impl Future<Output = Option<F::Output>> {
+        let panics = self.runtime.plugins_panics.0.clone();
+        let name = self.name.clone();
+        async move {
+            match AssertUnwindSafe(future).catch_unwind().await {
+                Ok(output) => Some(output),
+                Err(panic) => {
+                    let _ = panics.send((name, panic));
+                    None
+                }
+            }
+        }
+    }
+}
+
 #[non_exhaustive]
 #[derive(serde::Serialize, Debug, Clone)]
 /// A Response for the administration space.
@@ -59,6 +128,23 @@ pub trait RunningPluginTrait: Send + Sync + std::any::Any {
     /// * `Ok(None)` indicates that the plugin has accepted the configuration change.
     /// * `Ok(Some(value))` indicates that the plugin would rather the new configuration be `value`.
     fn config_checker(&self) -> ValidationFunction;
+    /// Called once a configuration change relevant to the plugin was applied, so that it may
+    /// apply the change incrementally.
+    ///
+    /// This function is called with 3 arguments:
+    /// * `path`, the relative path from the plugin's configuration root to the changed value.
+    /// * `old`, the previous configuration of the plugin (from its root).
+    /// * `new`, the configuration of the plugin now in effect (from its root).
+    ///
+    /// An `Err` is only logged: the change was already accepted by the [`config_checker`](Self::config_checker).
+    fn config_changed(
+        &self,
+        _path: &str,
+        _old: &serde_json::Map<String, serde_json::Value>,
+        _new: &serde_json::Map<String, serde_json::Value>,
+    ) -> ZResult<()> {
+        Ok(())
+    }
     /// Used to request your plugin's status for the administration space.
     fn adminspace_getter<'a>(
         &'a self,
@@ -80,3 +166,24 @@ pub type ValidationFunction = std::sync::Arc<
         + Send
         + Sync,
 >;
+
+#[test]
+fn plugin_tasks() {
+    async_std::task::block_on(async {
+        let mut config = crate::config::peer();
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        let runtime = Runtime::new(config).await.unwrap();
+        let tasks = PluginTasks::new(&runtime, "test");
+
+        assert_eq!(tasks.spawn(async { 42 }).await, Some(42));
+        assert!(runtime.plugins_panics.1.is_empty());
+
+        // the panic of a task is reported to the admin space with the name of its plugin
+        assert_eq!(tasks.spawn(async { panic!("failure") }).await, None::<()>);
+        let (name, panic) = runtime.plugins_panics.1.recv_async().await.unwrap();
+        assert_eq!(name, "test");
+        assert_eq!(panic.downcast_ref::<&str>(), Some(&"failure"));
+
+        runtime.close().await.unwrap();
+    });
+}
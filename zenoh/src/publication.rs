@@ -20,10 +20,23 @@ use crate::subscriber::Reliability;
 use crate::Encoding;
 use crate::SessionRef;
 use crate::Undeclarable;
+#[cfg(loom)]
+use loom::sync::Mutex;
+use std::collections::VecDeque;
+use std::fmt;
 use std::future::Ready;
-use zenoh_core::{zread, AsyncResolve, Resolvable, Resolve, SyncResolve};
-use zenoh_protocol::{core::Channel, zenoh::DataInfo};
-use zenoh_result::ZResult;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(not(loom))]
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use zenoh_buffers::ZBuf;
+use zenoh_core::{zlock, zread, AsyncResolve, Resolvable, Resolve, SyncResolve};
+use zenoh_protocol::{
+    core::{Channel, Timestamp, WireExpr},
+    zenoh::DataInfo,
+};
+use zenoh_result::{zerror, ZResult};
 
 /// The kind of congestion control.
 pub use zenoh_protocol::core::CongestionControl;
@@ -69,6 +82,7 @@ pub struct PutBuilder<'a, 'b> {
     pub(crate) publisher: PublisherBuilder<'a, 'b>,
     pub(crate) value: Value,
     pub(crate) kind: SampleKind,
+    pub(crate) attachment: Option<ZBuf>,
 }
 
 impl PutBuilder<'_, '_> {
@@ -104,10 +118,30 @@ impl PutBuilder<'_, '_> {
         self
     }
 
+    /// Do not send the data to the network if no remote subscriber currently matches it.
+    #[zenoh_macros::unstable]
+    #[inline]
+    pub fn skip_unmatched(mut self, skip_unmatched: bool) -> Self {
+        self.publisher = self.publisher.skip_unmatched(skip_unmatched);
+        self
+    }
+
     pub fn kind(mut self, kind: SampleKind) -> Self {
         self.kind = kind;
         self
     }
+
+    /// Attach some bytes to the written data, received by the subscribers in
+    /// [`Sample::attachment`](crate::sample::Sample::attachment).
+    #[zenoh_macros::unstable]
+    #[inline]
+    pub fn with_attachment<IntoZBuf>(mut self, attachment: IntoZBuf) -> Self
+    where
+        IntoZBuf: Into<ZBuf>,
+    {
+        self.attachment = Some(attachment.into());
+        self
+    }
 }
 
 impl Resolvable for PutBuilder<'_, '_> {
@@ -121,14 +155,14 @@ impl SyncResolve for PutBuilder<'_, '_> {
             publisher,
             value,
             kind,
+            attachment,
         } = self;
         let key_expr = publisher.key_expr?;
         log::trace!("write({:?}, [...])", &key_expr);
-        let primitives = zread!(publisher.session.state)
-            .primitives
-            .as_ref()
-            .unwrap()
-            .clone();
+        let state = zread!(publisher.session.state);
+        let primitives = state.primitives.as_ref().unwrap().clone();
+        let matching = !publisher.skip_unmatched || state.has_remote_subscribers(&key_expr);
+        drop(state);
 
         let info = DataInfo {
             kind,
@@ -138,6 +172,7 @@ impl SyncResolve for PutBuilder<'_, '_> {
                 None
             },
             timestamp: publisher.session.runtime.new_timestamp(),
+            attachment,
             ..Default::default()
         };
         let data_info = if info != DataInfo::default() {
@@ -146,25 +181,26 @@ impl SyncResolve for PutBuilder<'_, '_> {
             None
         };
 
-        if publisher.destination != Locality::SessionLocal {
-            primitives.send_data(
+        if publisher.destination != Locality::Remote {
+            // serve the subscribers of this session without waiting for the network
+            publisher.session.handle_data(
+                true,
                 &key_expr.to_wire(&publisher.session),
+                data_info.clone(),
                 value.payload.clone(),
+            );
+        }
+        if publisher.destination != Locality::SessionLocal && matching {
+            primitives.send_data(
+                &key_expr.to_wire(&publisher.session),
+                value.payload,
                 Channel {
                     priority: publisher.priority.into(),
                     reliability: Reliability::Reliable, // @TODO: need to check subscriptions to determine the right reliability value
                 },
                 publisher.congestion_control,
-                data_info.clone(),
-                None,
-            );
-        }
-        if publisher.destination != Locality::Remote {
-            publisher.session.handle_data(
-                true,
-                &key_expr.to_wire(&publisher.session),
                 data_info,
-                value.payload,
+                None,
             );
         }
         Ok(())
@@ -215,13 +251,34 @@ use zenoh_result::Error;
 /// subscriber.stream().map(Ok).forward(publisher).await.unwrap();
 /// # })
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Publisher<'a> {
     pub(crate) session: SessionRef<'a>,
     pub(crate) key_expr: KeyExpr<'a>,
     pub(crate) congestion_control: CongestionControl,
     pub(crate) priority: Priority,
     pub(crate) destination: Locality,
+    // resolved once at declaration so that publications perform no key lookup nor allocation
+    pub(crate) wire_expr: WireExpr<'static>,
+    pub(crate) primitives: Arc<dyn Primitives>,
+    // whether some remote subscriber matches, maintained by the session
+    pub(crate) matching: Arc<AtomicBool>,
+    pub(crate) skip_unmatched: bool,
+    pub(crate) buffer: Option<Arc<PublicationBuffer>>,
+}
+
+impl fmt::Debug for Publisher<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Publisher")
+            .field("key_expr", &self.key_expr)
+            .field("wire_expr", &self.wire_expr)
+            .field("congestion_control", &self.congestion_control)
+            .field("priority", &self.priority)
+            .field("destination", &self.destination)
+            .field("skip_unmatched", &self.skip_unmatched)
+            .field("buffered", &self.buffer.as_ref().map(|b| b.len()))
+            .finish()
+    }
 }
 
 impl<'a> Publisher<'a> {
@@ -252,11 +309,44 @@ impl<'a> Publisher<'a> {
         self
     }
 
+    /// Do not send the data to the network if no remote subscriber currently matches it.
+    ///
+    /// The data is then neither serialized nor sent while the network declares no matching subscriber.
+    /// The subscribers are declared asynchronously: the data published before the declaration
+    /// of a new subscriber reaches the publisher's session is not received by this subscriber.
+    #[zenoh_macros::unstable]
+    #[inline]
+    pub fn skip_unmatched(mut self, skip_unmatched: bool) -> Self {
+        self.skip_unmatched = skip_unmatched;
+        self
+    }
+
+    /// Whether some remote subscriber currently matches the key expression of this [`Publisher`].
+    #[zenoh_macros::unstable]
+    pub fn has_remote_subscribers(&self) -> bool {
+        self.matching.load(Ordering::Relaxed)
+    }
+
+    /// The number of samples published while the session had no connectivity
+    /// that are waiting to be sent.
+    #[zenoh_macros::unstable]
+    pub fn buffered(&self) -> usize {
+        self.buffer.as_ref().map(|b| b.len()).unwrap_or(0)
+    }
+
+    // the connectivity is tracked from the transport events, not to allocate on every publication
+    #[inline]
+    fn is_connected(&self) -> bool {
+        self.session.runtime.connectivity.is_connected()
+    }
+
     fn _write(&self, kind: SampleKind, value: Value) -> Publication {
         Publication {
             publisher: self,
             value,
             kind,
+            timestamp: None,
+            attachment: None,
         }
     }
 
@@ -361,8 +451,16 @@ impl Resolvable for PublisherUndeclaration<'_> {
 impl SyncResolve for PublisherUndeclaration<'_> {
     fn res_sync(mut self) -> <Self as Resolvable>::To {
         let Publisher {
-            session, key_expr, ..
+            session,
+            key_expr,
+            matching,
+            buffer,
+            ..
         } = &self.publisher;
+        session.unregister_matching_publisher(matching);
+        if let Some(buffer) = buffer {
+            session.unregister_publication_buffer(buffer);
+        }
         session
             .undeclare_publication_intent(key_expr.clone())
             .res_sync()?;
@@ -382,6 +480,10 @@ impl AsyncResolve for PublisherUndeclaration<'_> {
 impl Drop for Publisher<'_> {
     fn drop(&mut self) {
         if !self.key_expr.is_empty() {
+            self.session.unregister_matching_publisher(&self.matching);
+            if let Some(buffer) = &self.buffer {
+                self.session.unregister_publication_buffer(buffer);
+            }
             let _ = self
                 .session
                 .undeclare_publication_intent(self.key_expr.clone())
@@ -396,6 +498,55 @@ pub struct Publication<'a> {
     publisher: &'a Publisher<'a>,
     value: Value,
     kind: SampleKind,
+    timestamp: Option<Timestamp>,
+    attachment: Option<ZBuf>,
+}
+
+impl Publication<'_> {
+    /// Publish the data with the given [`Timestamp`](crate::time::Timestamp) instead of one
+    /// generated by the session, e.g. to preserve the original timing of replayed data.
+    ///
+    /// If the session has an HLC, the resolution fails when the timestamp is further in the
+    /// future than the `timestamping/max_delta_ms` allowed drift.
+    ///
+    /// # Examples
+    /// ```
+    /// # async_std::task::block_on(async {
+    /// use zenoh::prelude::r#async::*;
+    ///
+    /// let session = zenoh::open(config::peer()).res().await.unwrap().into_arc();
+    /// let publisher = session.declare_publisher("key/expression").res().await.unwrap();
+    /// let timestamp = zenoh::time::new_reception_timestamp();
+    /// publisher.put("value").with_timestamp(timestamp).res().await.unwrap();
+    /// # })
+    /// ```
+    #[zenoh_macros::unstable]
+    pub fn with_timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Attach some bytes to the published data, received by the subscribers in
+    /// [`Sample::attachment`](crate::sample::Sample::attachment).
+    ///
+    /// # Examples
+    /// ```
+    /// # async_std::task::block_on(async {
+    /// use zenoh::prelude::r#async::*;
+    ///
+    /// let session = zenoh::open(config::peer()).res().await.unwrap().into_arc();
+    /// let publisher = session.declare_publisher("key/expression").res().await.unwrap();
+    /// publisher.put("value").with_attachment(vec![1, 2, 3]).res().await.unwrap();
+    /// # })
+    /// ```
+    #[zenoh_macros::unstable]
+    pub fn with_attachment<IntoZBuf>(mut self, attachment: IntoZBuf) -> Self
+    where
+        IntoZBuf: Into<ZBuf>,
+    {
+        self.attachment = Some(attachment.into());
+        self
+    }
 }
 
 impl Resolvable for Publication<'_> {
@@ -408,14 +559,20 @@ impl SyncResolve for Publication<'_> {
             publisher,
             value,
             kind,
+            timestamp,
+            attachment,
         } = self;
         log::trace!("write({:?}, [...])", publisher.key_expr);
-        let primitives = zread!(publisher.session.state)
-            .primitives
-            .as_ref()
-            .unwrap()
-            .clone();
-
+        let timestamp = match timestamp {
+            Some(timestamp) => {
+                if let Some(hlc) = publisher.session.hlc() {
+                    hlc.update_with_timestamp(&timestamp)
+                        .map_err(|e| zerror!("Invalid timestamp {}: {}", timestamp, e))?;
+                }
+                Some(timestamp)
+            }
+            None => publisher.session.runtime.new_timestamp(),
+        };
         let info = DataInfo {
             kind,
             encoding: if value.encoding != Encoding::default() {
@@ -423,7 +580,8 @@ impl SyncResolve for Publication<'_> {
             } else {
                 None
             },
-            timestamp: publisher.session.runtime.new_timestamp(),
+            timestamp,
+            attachment,
             ..Default::default()
         };
         let data_info = if info != DataInfo::default() {
@@ -432,27 +590,44 @@ impl SyncResolve for Publication<'_> {
             None
         };
 
-        if publisher.destination != Locality::SessionLocal {
-            primitives.send_data(
-                &publisher.key_expr.to_wire(&publisher.session),
-                value.payload.clone(),
-                Channel {
-                    priority: publisher.priority.into(),
-                    reliability: Reliability::Reliable, // @TODO: need to check subscriptions to determine the right reliability value
-                },
-                publisher.congestion_control,
-                data_info.clone(),
-                None,
-            );
-        }
         if publisher.destination != Locality::Remote {
+            // serve the subscribers of this session without waiting for the network
             publisher.session.handle_data(
                 true,
-                &publisher.key_expr.to_wire(&publisher.session),
-                data_info,
-                value.payload,
+                &publisher.wire_expr,
+                data_info.clone(),
+                value.payload.clone(),
             );
         }
+        if publisher.destination != Locality::SessionLocal {
+            let channel = Channel {
+                priority: publisher.priority.into(),
+                reliability: Reliability::Reliable, // @TODO: need to check subscriptions to determine the right reliability value
+            };
+            match &publisher.buffer {
+                Some(buffer) if !publisher.is_connected() => buffer.push(BufferedSample {
+                    time: Instant::now(),
+                    payload: value.payload.clone(),
+                    channel,
+                    congestion_control: publisher.congestion_control,
+                    data_info: data_info.clone(),
+                }),
+                _ if !publisher.skip_unmatched || publisher.matching.load(Ordering::Relaxed) => {
+                    if let Some(buffer) = &publisher.buffer {
+                        buffer.flush();
+                    }
+                    publisher.primitives.send_data(
+                        &publisher.wire_expr,
+                        value.payload.clone(),
+                        channel,
+                        publisher.congestion_control,
+                        data_info.clone(),
+                        None,
+                    );
+                }
+                _ => (),
+            }
+        }
         Ok(())
     }
 }
@@ -516,6 +691,8 @@ pub struct PublisherBuilder<'a, 'b: 'a> {
     pub(crate) congestion_control: CongestionControl,
     pub(crate) priority: Priority,
     pub(crate) destination: Locality,
+    pub(crate) skip_unmatched: bool,
+    pub(crate) buffer: Option<(usize, Option<Duration>)>,
 }
 
 impl<'a, 'b> Clone for PublisherBuilder<'a, 'b> {
@@ -529,6 +706,8 @@ impl<'a, 'b> Clone for PublisherBuilder<'a, 'b> {
             congestion_control: self.congestion_control,
             priority: self.priority,
             destination: self.destination,
+            skip_unmatched: self.skip_unmatched,
+            buffer: self.buffer,
         }
     }
 }
@@ -556,6 +735,33 @@ impl<'a, 'b> PublisherBuilder<'a, 'b> {
         self.destination = destination;
         self
     }
+
+    /// Do not send the data to the network if no remote subscriber currently matches it.
+    ///
+    /// See [`Publisher::skip_unmatched`].
+    #[zenoh_macros::unstable]
+    #[inline]
+    pub fn skip_unmatched(mut self, skip_unmatched: bool) -> Self {
+        self.skip_unmatched = skip_unmatched;
+        self
+    }
+
+    /// Buffer the data published while the session has no connectivity, instead of dropping it.
+    ///
+    /// At most `max_samples` samples are kept, the oldest ones being dropped first,
+    /// as well as the ones older than `max_age` if any.
+    /// The buffered samples are sent in order once a matching subscriber is reachable again,
+    /// before any newer publication.
+    #[zenoh_macros::unstable]
+    #[inline]
+    pub fn buffer_while_disconnected(
+        mut self,
+        max_samples: usize,
+        max_age: Option<Duration>,
+    ) -> Self {
+        self.buffer = Some((max_samples, max_age));
+        self
+    }
 }
 
 impl<'a, 'b> Resolvable for PublisherBuilder<'a, 'b> {
@@ -596,12 +802,39 @@ impl<'a, 'b> SyncResolve for PublisherBuilder<'a, 'b> {
         self.session
             .declare_publication_intent(key_expr.clone())
             .res_sync()?;
+        let wire_expr = key_expr.to_wire(&self.session).to_owned();
+        let primitives = zread!(self.session.state)
+            .primitives
+            .as_ref()
+            .unwrap()
+            .clone();
+        let matching = self.session.register_matching_publisher(&key_expr);
+        let buffer = match self.buffer {
+            Some((max_samples, max_age)) if max_samples > 0 => {
+                let buffer = Arc::new(PublicationBuffer {
+                    max_samples,
+                    max_age,
+                    wire_expr: wire_expr.clone(),
+                    primitives: primitives.clone(),
+                    samples: Mutex::new(VecDeque::new()),
+                });
+                self.session
+                    .register_publication_buffer(&key_expr, buffer.clone());
+                Some(buffer)
+            }
+            _ => None,
+        };
         let publisher = Publisher {
             session: self.session,
             key_expr,
             congestion_control: self.congestion_control,
             priority: self.priority,
             destination: self.destination,
+            wire_expr,
+            primitives,
+            matching,
+            skip_unmatched: self.skip_unmatched,
+            buffer,
         };
         log::trace!("publish({:?})", publisher.key_expr);
         Ok(publisher)
@@ -616,6 +849,128 @@ impl<'a, 'b> AsyncResolve for PublisherBuilder<'a, 'b> {
     }
 }
 
+struct BufferedSample {
+    time: Instant,
+    payload: ZBuf,
+    channel: Channel,
+    congestion_control: CongestionControl,
+    data_info: Option<DataInfo>,
+}
+
+/// The samples published by a [`Publisher`] while its session had no connectivity.
+pub(crate) struct PublicationBuffer {
+    max_samples: usize,
+    max_age: Option<Duration>,
+    wire_expr: WireExpr<'static>,
+    primitives: Arc<dyn Primitives>,
+    samples: Mutex<VecDeque<BufferedSample>>,
+}
+
+impl PublicationBuffer {
+    fn expire(&self, samples: &mut VecDeque<BufferedSample>) {
+        if let Some(max_age) = self.max_age {
+            while samples
+                .front()
+                .map(|s| s.time.elapsed() > max_age)
+                .unwrap_or(false)
+            {
+                samples.pop_front();
+            }
+        }
+    }
+
+    fn push(&self, sample: BufferedSample) {
+        let mut samples = zlock!(self.samples);
+        self.expire(&mut samples);
+        if samples.len() >= self.max_samples {
+            log::debug!(
+                "Publication buffer on {} full: dropping the oldest sample",
+                self.wire_expr
+            );
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        zlock!(self.samples).len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        zlock!(self.samples).is_empty()
+    }
+
+    /// Send the buffered samples that didn't expire, in order.
+    pub(crate) fn flush(&self) {
+        let mut samples = zlock!(self.samples);
+        self.expire(&mut samples);
+        if !samples.is_empty() {
+            log::debug!(
+                "Sending {} samples buffered on {} while disconnected",
+                samples.len(),
+                self.wire_expr
+            );
+        }
+        // the lock is held so that no newer publication overtakes the buffered ones
+        for sample in samples.drain(..) {
+            self.primitives.send_data(
+                &self.wire_expr,
+                sample.payload,
+                sample.channel,
+                sample.congestion_control,
+                sample.data_info,
+                None,
+            );
+        }
+    }
+}
+
+// Model-checked with `RUSTFLAGS="--cfg loom" cargo test -p zenoh --lib --release publication_buffer`
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use super::*;
+    use loom::thread;
+    use zenoh_transport::DummyPrimitives;
+
+    fn sample() -> BufferedSample {
+        BufferedSample {
+            time: Instant::now(),
+            payload: ZBuf::default(),
+            channel: Channel::default(),
+            congestion_control: CongestionControl::default(),
+            data_info: None,
+        }
+    }
+
+    #[test]
+    fn publication_buffer_push_and_flush() {
+        loom::model(|| {
+            let buffer = loom::sync::Arc::new(PublicationBuffer {
+                max_samples: 2,
+                max_age: None,
+                wire_expr: WireExpr {
+                    scope: 0,
+                    suffix: "test/buffer".into(),
+                },
+                primitives: Arc::new(DummyPrimitives::new()),
+                samples: Mutex::new(VecDeque::new()),
+            });
+            let c_buffer = buffer.clone();
+            let pusher = thread::spawn(move || {
+                c_buffer.push(sample());
+                c_buffer.push(sample());
+                c_buffer.push(sample());
+            });
+            buffer.flush();
+            pusher.join().unwrap();
+            // The buffer never holds more than its capacity, and a flush empties it
+            assert!(buffer.len() <= 2);
+            buffer.flush();
+            assert!(buffer.is_empty());
+        });
+    }
+}
+
 /// The Priority of zenoh messages.
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
 #[repr(u8)]
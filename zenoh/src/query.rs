@@ -20,6 +20,7 @@ use crate::Session;
 use std::collections::HashMap;
 use std::future::Ready;
 use std::time::Duration;
+use zenoh_buffers::ZBuf;
 use zenoh_core::{AsyncResolve, Resolvable, SyncResolve};
 use zenoh_result::ZResult;
 
@@ -82,6 +83,16 @@ pub struct Reply {
     pub sample: Result<Sample, Value>,
     /// The id of the zenoh instance that answered this Reply.
     pub replier_id: ZenohId,
+    // the hops of a traced query, as encoded in its `_trace` selector parameter
+    pub(crate) trace: Option<String>,
+}
+
+impl Reply {
+    /// The nodes the query went through to reach the replier, if it was traced with [`GetBuilder::trace`].
+    #[zenoh_macros::unstable]
+    pub fn trace(&self) -> Option<QueryTrace> {
+        self.trace.as_deref().and_then(QueryTrace::decode)
+    }
 }
 
 pub(crate) struct QueryState {
@@ -125,6 +136,7 @@ pub struct GetBuilder<'a, 'b, Handler> {
     pub(crate) timeout: Duration,
     pub(crate) handler: Handler,
     pub(crate) value: Option<Value>,
+    pub(crate) attachment: Option<ZBuf>,
 }
 
 impl<'a, 'b> GetBuilder<'a, 'b, DefaultHandler> {
@@ -158,6 +170,7 @@ impl<'a, 'b> GetBuilder<'a, 'b, DefaultHandler> {
             destination,
             timeout,
             value,
+            attachment,
             handler: _,
         } = self;
         GetBuilder {
@@ -169,6 +182,7 @@ impl<'a, 'b> GetBuilder<'a, 'b, DefaultHandler> {
             destination,
             timeout,
             value,
+            attachment,
             handler: callback,
         }
     }
@@ -237,6 +251,7 @@ impl<'a, 'b> GetBuilder<'a, 'b, DefaultHandler> {
             destination,
             timeout,
             value,
+            attachment,
             handler: _,
         } = self;
         GetBuilder {
@@ -248,6 +263,7 @@ impl<'a, 'b> GetBuilder<'a, 'b, DefaultHandler> {
             destination,
             timeout,
             value,
+            attachment,
             handler,
         }
     }
@@ -276,7 +292,10 @@ impl<'a, 'b, Handler> GetBuilder<'a, 'b, Handler> {
         self
     }
 
-    /// Set query timeout.
+    /// Set query timeout (defaults to the `queries_default_timeout` of the configuration).
+    ///
+    /// Once the timeout expires, the replies still pending are dropped: the query receives a last
+    /// `Timeout` error reply and its receiver is closed, even if a queryable never sends its final reply.
     #[inline]
     pub fn timeout(mut self, timeout: Duration) -> Self {
         self.timeout = timeout;
@@ -293,6 +312,36 @@ impl<'a, 'b, Handler> GetBuilder<'a, 'b, Handler> {
         self
     }
 
+    /// Attach some bytes to the query, received by the queryables in
+    /// [`Query::attachment`](crate::queryable::Query::attachment).
+    #[zenoh_macros::unstable]
+    #[inline]
+    pub fn with_attachment<IntoZBuf>(mut self, attachment: IntoZBuf) -> Self
+    where
+        IntoZBuf: Into<ZBuf>,
+    {
+        self.attachment = Some(attachment.into());
+        self
+    }
+
+    /// Trace this query: each node it goes through records its id and the time,
+    /// which the replies report with [`Reply::trace`].
+    #[zenoh_macros::unstable]
+    pub fn trace(mut self) -> Self {
+        self.selector = self.selector.and_then(|s| {
+            let mut s = s.into_owned();
+            if s.parameter_index(_TRACE_SEL_PARAM)?.is_none() {
+                let parameters = s.parameters_mut();
+                if !parameters.is_empty() {
+                    parameters.push('&')
+                }
+                parameters.push_str(_TRACE_SEL_PARAM);
+            }
+            Ok(s)
+        });
+        self
+    }
+
     /// By default, `get` guarantees that it will only receive replies whose key expressions intersect
     /// with the queried key expression.
     ///
@@ -309,6 +358,7 @@ impl<'a, 'b, Handler> GetBuilder<'a, 'b, Handler> {
             destination,
             timeout,
             value,
+            attachment,
             handler,
         } = self;
         Self {
@@ -320,11 +370,97 @@ impl<'a, 'b, Handler> GetBuilder<'a, 'b, Handler> {
             destination,
             timeout,
             value,
+            attachment,
             handler,
         }
     }
 }
 
+pub(crate) const _TRACE_SEL_PARAM: &str = "_trace";
+
+/// The value of the `_trace` parameter of `parameters`, or `None` if the query isn't traced.
+pub(crate) fn get_trace(parameters: &str) -> Option<&str> {
+    parameters.split('&').find_map(|p| match p.split_once('=') {
+        Some((k, v)) if k == _TRACE_SEL_PARAM => Some(v),
+        None if p == _TRACE_SEL_PARAM => Some(""),
+        _ => None,
+    })
+}
+
+/// Appends the hop of the node `zid` to the `_trace` parameter of `parameters`,
+/// or returns `None` if the query isn't traced.
+pub(crate) fn append_trace_hop(parameters: &str, zid: &ZenohId) -> Option<String> {
+    let trace = get_trace(parameters)?;
+    let time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let trace = if trace.is_empty() {
+        format!("{}={}@{}", _TRACE_SEL_PARAM, zid, time)
+    } else {
+        format!("{}={},{}@{}", _TRACE_SEL_PARAM, trace, zid, time)
+    };
+    Some(
+        parameters
+            .split('&')
+            .filter(|p| !p.is_empty())
+            .map(|p| {
+                if p == _TRACE_SEL_PARAM || p.starts_with(&[_TRACE_SEL_PARAM, "="].concat()) {
+                    trace.as_str()
+                } else {
+                    p
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("&"),
+    )
+}
+
+/// A node a traced query went through: a router that routed it, or the queryable that replied to it.
+#[zenoh_macros::unstable]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceHop {
+    pub zid: ZenohId,
+    /// The time at which the query went through this node, according to its clock, since the UNIX epoch.
+    pub time: Duration,
+}
+
+/// The route of a traced query, from the querier to the replier.
+///
+/// Comparing the times of consecutive hops tells where the query spent its time,
+/// as long as the clocks of the nodes are synchronized.
+#[zenoh_macros::unstable]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryTrace {
+    pub hops: Vec<TraceHop>,
+}
+
+#[zenoh_macros::unstable]
+impl QueryTrace {
+    fn decode(trace: &str) -> Option<QueryTrace> {
+        use std::str::FromStr;
+        let hops = trace
+            .split(',')
+            .filter(|h| !h.is_empty())
+            .map(|hop| {
+                let (zid, time) = hop.split_once('@')?;
+                Some(TraceHop {
+                    zid: ZenohId::from_str(zid).ok()?,
+                    time: Duration::from_nanos(time.parse().ok()?),
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(QueryTrace { hops })
+    }
+
+    /// The time elapsed between the first and the last hops.
+    pub fn elapsed(&self) -> Option<Duration> {
+        let first = self.hops.first()?;
+        let last = self.hops.last()?;
+        last.time.checked_sub(first.time)
+    }
+}
+
 pub(crate) const _REPLY_KEY_EXPR_ANY_SEL_PARAM: &str = "_anyke";
 #[zenoh_macros::unstable]
 pub const REPLY_KEY_EXPR_ANY_SEL_PARAM: &str = _REPLY_KEY_EXPR_ANY_SEL_PARAM;
@@ -368,6 +504,7 @@ where
                 self.destination,
                 self.timeout,
                 self.value,
+                self.attachment,
                 callback,
             )
             .map(|_| receiver)
@@ -385,3 +522,26 @@ where
         std::future::ready(self.res_sync())
     }
 }
+
+#[test]
+fn trace_parameters() {
+    let zid = ZenohId::rand();
+    assert_eq!(get_trace("a=1&b"), None);
+    assert_eq!(append_trace_hop("a=1&b", &zid), None);
+
+    let parameters = append_trace_hop("a=1&_trace&b", &zid).unwrap();
+    let trace = get_trace(&parameters).unwrap();
+    assert!(parameters.starts_with("a=1&_trace="));
+    assert!(parameters.ends_with("&b"));
+    assert!(trace.starts_with(&format!("{}@", zid)));
+
+    let parameters = append_trace_hop(&parameters, &zid).unwrap();
+    assert_eq!(get_trace(&parameters).unwrap().split(',').count(), 2);
+    #[cfg(feature = "unstable")]
+    {
+        let trace = QueryTrace::decode(get_trace(&parameters).unwrap()).unwrap();
+        assert_eq!(trace.hops.len(), 2);
+        assert!(trace.hops.iter().all(|hop| hop.zid == zid));
+        assert!(trace.elapsed().is_some());
+    }
+}
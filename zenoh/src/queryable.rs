@@ -18,16 +18,23 @@ use crate::handlers::{locked, DefaultHandler};
 use crate::prelude::*;
 #[zenoh_macros::unstable]
 use crate::query::ReplyKeyExpr;
+use crate::query::{append_trace_hop, get_trace};
 use crate::SessionRef;
 use crate::Undeclarable;
 
+use std::collections::VecDeque;
 use std::fmt;
-use std::future::Ready;
+use std::future::{Future, Ready};
 use std::ops::Deref;
-use std::sync::Arc;
-use zenoh_core::{AsyncResolve, Resolvable, SyncResolve};
-use zenoh_protocol::core::WireExpr;
+use std::pin::Pin;
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use zenoh_buffers::ZBuf;
+use zenoh_core::{zlock, AsyncResolve, Resolvable, SyncResolve};
+use zenoh_protocol::{common::Attachment, core::WireExpr, zenoh::QueryBody};
 use zenoh_result::ZResult;
+use zenoh_sync::Condition;
 use zenoh_transport::Primitives;
 
 pub(crate) struct QueryInner {
@@ -37,12 +44,29 @@ pub(crate) struct QueryInner {
     pub(crate) parameters: String,
     /// This Query's body.
     pub(crate) value: Option<Value>,
+    /// The attachment of this Query.
+    pub(crate) attachment: Option<ZBuf>,
 
     pub(crate) qid: ZInt,
     pub(crate) zid: ZenohId,
     pub(crate) primitives: Arc<dyn Primitives>,
 }
 
+impl QueryInner {
+    /// The value and the attachment of the body of a query,
+    /// a body without encoding carrying only an attachment.
+    pub(crate) fn split_body(body: Option<QueryBody>) -> (Option<Value>, Option<ZBuf>) {
+        match body {
+            Some(QueryBody { data_info, payload }) => {
+                let value = (data_info.encoding.is_some() || data_info.attachment.is_none())
+                    .then(|| Value::from(payload).encoding(data_info.encoding.unwrap_or_default()));
+                (value, data_info.attachment)
+            }
+            None => (None, None),
+        }
+    }
+}
+
 impl Drop for QueryInner {
     fn drop(&mut self) {
         self.primitives.send_reply_final(self.qid);
@@ -53,6 +77,10 @@ impl Drop for QueryInner {
 #[derive(Clone)]
 pub struct Query {
     pub(crate) inner: Arc<QueryInner>,
+    // the slot this query occupies in a queryable with a limited concurrency, released on drop
+    pub(crate) permit: Option<Arc<QueryPermit>>,
+    // keeps this query accounted as in flight in the queryable it was dispatched to
+    pub(crate) in_flight: Option<Arc<InFlightQuery>>,
 }
 
 impl Query {
@@ -83,6 +111,13 @@ impl Query {
         self.inner.value.as_ref()
     }
 
+    /// The attachment of this Query, if the querier sent one.
+    #[zenoh_macros::unstable]
+    #[inline(always)]
+    pub fn attachment(&self) -> Option<&ZBuf> {
+        self.inner.attachment.as_ref()
+    }
+
     /// Sends a reply to this Query.
     ///
     /// By default, queries only accept replies whose key expression intersects with the query's.
@@ -156,6 +191,14 @@ impl SyncResolve for ReplyBuilder<'_> {
                     bail!("Attempted to reply on `{}`, which does not intersect with query `{}`, despite query only allowing replies on matching key expressions", sample.key_expr, self.query.key_expr())
                 }
                 let (key_expr, payload, data_info) = sample.split();
+                // a traced query gets the hops it went through back in the reply attachment
+                let attachment =
+                    append_trace_hop(&self.query.inner.parameters, &self.query.inner.zid).and_then(
+                        |parameters| {
+                            get_trace(&parameters)
+                                .map(|trace| Attachment::new(ZBuf::from(trace.as_bytes().to_vec())))
+                        },
+                    );
                 self.query.inner.primitives.send_reply_data(
                     self.query.inner.qid,
                     self.query.inner.zid,
@@ -165,6 +208,7 @@ impl SyncResolve for ReplyBuilder<'_> {
                     },
                     Some(data_info),
                     payload,
+                    attachment,
                 );
                 Ok(())
             }
@@ -181,12 +225,145 @@ impl<'a> AsyncResolve for ReplyBuilder<'a> {
     }
 }
 
+type QueryCallback = Arc<dyn Fn(Query) + Send + Sync>;
+
+/// Bounds the number of queries concurrently handled by a queryable, queueing the excess ones.
+pub(crate) struct QueryAdmission {
+    max_concurrent: usize,
+    max_queued: usize,
+    callback: QueryCallback,
+    state: Mutex<AdmissionState>,
+}
+
+struct AdmissionState {
+    outstanding: usize,
+    queue: VecDeque<Query>,
+}
+
+impl QueryAdmission {
+    fn new(max_concurrent: usize, max_queued: usize, callback: QueryCallback) -> Arc<Self> {
+        Arc::new(QueryAdmission {
+            max_concurrent,
+            max_queued,
+            callback,
+            state: Mutex::new(AdmissionState {
+                outstanding: 0,
+                queue: VecDeque::new(),
+            }),
+        })
+    }
+
+    fn admit(self: &Arc<Self>, query: Query) {
+        let mut state = zlock!(self.state);
+        if state.outstanding < self.max_concurrent {
+            state.outstanding += 1;
+            drop(state);
+            self.dispatch(query);
+        } else if state.queue.len() < self.max_queued {
+            state.queue.push_back(query);
+        } else {
+            drop(state);
+            log::warn!("Queryable overloaded: rejecting {}", query);
+            if let Err(e) = query.reply(Err("overloaded".into())).res_sync() {
+                log::warn!("Error rejecting {}: {}", query, e);
+            }
+        }
+    }
+
+    fn dispatch(self: &Arc<Self>, mut query: Query) {
+        query.permit = Some(Arc::new(QueryPermit {
+            admission: self.clone(),
+        }));
+        (self.callback)(query);
+    }
+}
+
+pub(crate) struct QueryPermit {
+    admission: Arc<QueryAdmission>,
+}
+
+impl Drop for QueryPermit {
+    fn drop(&mut self) {
+        let next = {
+            let mut state = zlock!(self.admission.state);
+            let next = state.queue.pop_front();
+            if next.is_none() {
+                state.outstanding -= 1;
+            }
+            next
+        };
+        // The slot is handed over to the oldest queued query. It is dispatched from another task,
+        // as the permit may be dropped from the callback of the queryable or while holding user locks.
+        if let Some(query) = next {
+            let admission = self.admission.clone();
+            async_std::task::spawn_blocking(move || admission.dispatch(query));
+        }
+    }
+}
+
+/// Counts the queries dispatched to a queryable that are not finalized yet.
+#[derive(Default)]
+pub(crate) struct InFlightQueries {
+    count: Mutex<usize>,
+    drained: Condition,
+}
+
+impl InFlightQueries {
+    pub(crate) fn count(&self) -> usize {
+        *zlock!(self.count)
+    }
+
+    /// Waits for all the in flight queries to be finalized, returning how many remain after `timeout`.
+    async fn wait_drained(&self, timeout: Duration) -> usize {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let waiter = {
+                let count = zlock!(self.count);
+                if *count == 0 {
+                    return 0;
+                }
+                self.drained.waiter(count)
+            };
+            let now = Instant::now();
+            if now >= deadline
+                || async_std::future::timeout(deadline - now, waiter)
+                    .await
+                    .is_err()
+            {
+                return self.count();
+            }
+        }
+    }
+}
+
+pub(crate) struct InFlightQuery(Arc<InFlightQueries>);
+
+impl InFlightQuery {
+    pub(crate) fn new(queries: &Arc<InFlightQueries>) -> Self {
+        *zlock!(queries.count) += 1;
+        InFlightQuery(queries.clone())
+    }
+}
+
+impl Drop for InFlightQuery {
+    fn drop(&mut self) {
+        let mut count = zlock!(self.0.count);
+        *count -= 1;
+        if *count == 0 {
+            self.0.drained.notify_all();
+        }
+    }
+}
+
 pub(crate) struct QueryableState {
     pub(crate) id: Id,
     pub(crate) key_expr: WireExpr<'static>,
     pub(crate) complete: bool,
     pub(crate) origin: Locality,
     pub(crate) callback: Arc<dyn Fn(Query) + Send + Sync>,
+    pub(crate) in_flight: Arc<InFlightQueries>,
+    // the number of queries delivered to the callback
+    pub(crate) received: AtomicU64,
 }
 
 impl fmt::Debug for QueryableState {
@@ -234,7 +411,10 @@ pub(crate) struct CallbackQueryable<'a> {
 
 impl<'a> Undeclarable<(), QueryableUndeclaration<'a>> for CallbackQueryable<'a> {
     fn undeclare_inner(self, _: ()) -> QueryableUndeclaration<'a> {
-        QueryableUndeclaration { queryable: self }
+        QueryableUndeclaration {
+            queryable: self,
+            drain_timeout: None,
+        }
     }
 }
 
@@ -252,26 +432,65 @@ impl<'a> Undeclarable<(), QueryableUndeclaration<'a>> for CallbackQueryable<'a>
 /// ```
 pub struct QueryableUndeclaration<'a> {
     queryable: CallbackQueryable<'a>,
+    drain_timeout: Option<Duration>,
 }
 
 impl Resolvable for QueryableUndeclaration<'_> {
     type To = ZResult<()>;
 }
 
+impl QueryableUndeclaration<'_> {
+    // Undeclare the queryable, then drop it: its callback, and thus the queries left
+    // in its channel, are dropped. Returns the queries to wait for, if any.
+    fn undeclare(self) -> ZResult<Option<(Arc<InFlightQueries>, Duration, String)>> {
+        let QueryableUndeclaration {
+            mut queryable,
+            drain_timeout,
+        } = self;
+        queryable.alive = false;
+        queryable.session.close_queryable(queryable.state.id)?;
+        Ok(drain_timeout.map(|timeout| {
+            (
+                queryable.state.in_flight.clone(),
+                timeout,
+                format!("{:?}", queryable.state),
+            )
+        }))
+    }
+}
+
+async fn wait_drained(in_flight: Arc<InFlightQueries>, timeout: Duration, queryable: String) {
+    let remaining = in_flight.wait_drained(timeout).await;
+    if remaining > 0 {
+        log::warn!(
+            "Undeclared {} with {} queries still in flight after {:?}",
+            queryable,
+            remaining,
+            timeout
+        );
+    }
+}
+
 impl SyncResolve for QueryableUndeclaration<'_> {
-    fn res_sync(mut self) -> <Self as Resolvable>::To {
-        self.queryable.alive = false;
-        self.queryable
-            .session
-            .close_queryable(self.queryable.state.id)
+    fn res_sync(self) -> <Self as Resolvable>::To {
+        if let Some((in_flight, timeout, queryable)) = self.undeclare()? {
+            async_std::task::block_on(wait_drained(in_flight, timeout, queryable));
+        }
+        Ok(())
     }
 }
 
 impl<'a> AsyncResolve for QueryableUndeclaration<'a> {
-    type Future = Ready<Self::To>;
+    type Future = Pin<Box<dyn Future<Output = <Self as Resolvable>::To> + Send + 'a>>;
 
     fn res_async(self) -> Self::Future {
-        std::future::ready(self.res_sync())
+        match self.undeclare() {
+            Ok(Some((in_flight, timeout, queryable))) => Box::pin(async move {
+                wait_drained(in_flight, timeout, queryable).await;
+                ZResult::Ok(())
+            }),
+            undeclared => Box::pin(std::future::ready(undeclared.map(|_| ()))),
+        }
     }
 }
 
@@ -302,6 +521,8 @@ pub struct QueryableBuilder<'a, 'b, Handler> {
     pub(crate) key_expr: ZResult<KeyExpr<'b>>,
     pub(crate) complete: bool,
     pub(crate) origin: Locality,
+    pub(crate) max_concurrent_queries: Option<usize>,
+    pub(crate) max_queued_queries: usize,
     pub(crate) handler: Handler,
 }
 
@@ -332,6 +553,8 @@ impl<'a, 'b> QueryableBuilder<'a, 'b, DefaultHandler> {
             key_expr,
             complete,
             origin,
+            max_concurrent_queries,
+            max_queued_queries,
             handler: _,
         } = self;
         QueryableBuilder {
@@ -339,6 +562,8 @@ impl<'a, 'b> QueryableBuilder<'a, 'b, DefaultHandler> {
             key_expr,
             complete,
             origin,
+            max_concurrent_queries,
+            max_queued_queries,
             handler: callback,
         }
     }
@@ -403,6 +628,8 @@ impl<'a, 'b> QueryableBuilder<'a, 'b, DefaultHandler> {
             key_expr,
             complete,
             origin,
+            max_concurrent_queries,
+            max_queued_queries,
             handler: _,
         } = self;
         QueryableBuilder {
@@ -410,6 +637,8 @@ impl<'a, 'b> QueryableBuilder<'a, 'b, DefaultHandler> {
             key_expr,
             complete,
             origin,
+            max_concurrent_queries,
+            max_queued_queries,
             handler,
         }
     }
@@ -430,6 +659,28 @@ impl<'a, 'b, Handler> QueryableBuilder<'a, 'b, Handler> {
         self.complete = complete;
         self
     }
+
+    /// Limit the number of queries this [`Queryable`] handles concurrently.
+    ///
+    /// A query is being handled until all its copies passed to the handler are dropped.
+    /// The queries received while `max` of them are being handled are queued,
+    /// up to [`max_queued_queries`](QueryableBuilder::max_queued_queries),
+    /// and rejected beyond: they are then finalized without any reply from this [`Queryable`].
+    #[zenoh_macros::unstable]
+    #[inline]
+    pub fn max_concurrent_queries(mut self, max: usize) -> Self {
+        self.max_concurrent_queries = Some(max);
+        self
+    }
+
+    /// Change the number of queries waiting to be handled when the
+    /// [`max_concurrent_queries`](QueryableBuilder::max_concurrent_queries) are reached (0 by default).
+    #[zenoh_macros::unstable]
+    #[inline]
+    pub fn max_queued_queries(mut self, depth: usize) -> Self {
+        self.max_queued_queries = depth;
+        self
+    }
 }
 
 /// A queryable that provides data through a [`Handler`](crate::prelude::IntoCallbackReceiverPair).
@@ -474,6 +725,18 @@ impl<'a, Receiver> Queryable<'a, Receiver> {
     pub fn undeclare(self) -> impl Resolve<ZResult<()>> + 'a {
         Undeclarable::undeclare_inner(self, ())
     }
+
+    /// Undeclare this [`Queryable`], then wait for the queries it's handling to be finalized.
+    ///
+    /// No new query is received once undeclared, while the queries already received
+    /// can still be replied to, until they're dropped or the `timeout` expires.
+    /// The queries left in the receiver are dropped, and thus finalized, right away.
+    #[zenoh_macros::unstable]
+    pub fn undeclare_drained(self, timeout: Duration) -> impl Resolve<ZResult<()>> + 'a {
+        let mut undeclaration = Undeclarable::undeclare_inner(self.queryable, ());
+        undeclaration.drain_timeout = Some(timeout);
+        undeclaration
+    }
 }
 
 impl<'a, T> Undeclarable<(), QueryableUndeclaration<'a>> for Queryable<'a, T> {
@@ -505,7 +768,14 @@ where
 {
     fn res_sync(self) -> <Self as Resolvable>::To {
         let session = self.session;
-        let (callback, receiver) = self.handler.into_cb_receiver_pair();
+        let (mut callback, receiver) = self.handler.into_cb_receiver_pair();
+        if let Some(max_concurrent) = self.max_concurrent_queries {
+            if max_concurrent == 0 {
+                bail!("The maximum number of concurrent queries must be strictly positive");
+            }
+            let admission = QueryAdmission::new(max_concurrent, self.max_queued_queries, callback);
+            callback = Arc::new(move |query| admission.admit(query));
+        }
         session
             .declare_queryable_inner(
                 &self.key_expr?.to_wire(&session),
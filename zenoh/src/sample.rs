@@ -112,6 +112,16 @@ pub struct Sample {
     ///
     /// Infos on the source of this Sample.
     pub source_info: SourceInfo,
+
+    #[cfg(feature = "unstable")]
+    /// <div class="stab unstable">
+    ///   <span class="emoji">🔬</span>
+    ///   This API has been marked as unstable: it works as advertised, but we may change it in a future release.
+    ///   To use it, you must enable zenoh's <code>unstable</code> feature flag.
+    /// </div>
+    ///
+    /// The attachment sent along with this Sample by its publisher or replier.
+    pub attachment: Option<ZBuf>,
 }
 
 impl Sample {
@@ -129,6 +139,8 @@ impl Sample {
             timestamp: None,
             #[cfg(feature = "unstable")]
             source_info: SourceInfo::empty(),
+            #[cfg(feature = "unstable")]
+            attachment: None,
         }
     }
     /// Creates a new Sample.
@@ -149,6 +161,8 @@ impl Sample {
             timestamp: None,
             #[cfg(feature = "unstable")]
             source_info: SourceInfo::empty(),
+            #[cfg(feature = "unstable")]
+            attachment: None,
         })
     }
 
@@ -164,6 +178,8 @@ impl Sample {
             if let Some(encoding) = &data_info.encoding {
                 value.encoding = encoding.clone();
             }
+            #[cfg(feature = "unstable")]
+            let attachment = data_info.attachment.clone();
             Sample {
                 key_expr,
                 value,
@@ -171,6 +187,8 @@ impl Sample {
                 timestamp: data_info.timestamp,
                 #[cfg(feature = "unstable")]
                 source_info: data_info.into(),
+                #[cfg(feature = "unstable")]
+                attachment,
             }
         } else {
             Sample {
@@ -180,6 +198,8 @@ impl Sample {
                 timestamp: None,
                 #[cfg(feature = "unstable")]
                 source_info: SourceInfo::empty(),
+                #[cfg(feature = "unstable")]
+                attachment: None,
             }
         }
     }
@@ -200,6 +220,10 @@ impl Sample {
             source_sn: self.source_info.source_sn,
             #[cfg(not(feature = "unstable"))]
             source_sn: None,
+            #[cfg(feature = "unstable")]
+            attachment: self.attachment,
+            #[cfg(not(feature = "unstable"))]
+            attachment: None,
         };
         (self.key_expr, self.value.payload, info)
     }
@@ -225,6 +249,17 @@ impl Sample {
         self
     }
 
+    /// Sets the attachment of this Sample.
+    #[zenoh_macros::unstable]
+    #[inline]
+    pub fn with_attachment<IntoZBuf>(mut self, attachment: IntoZBuf) -> Self
+    where
+        IntoZBuf: Into<ZBuf>,
+    {
+        self.attachment = Some(attachment.into());
+        self
+    }
+
     #[inline]
     /// Ensure that an associated Timestamp is present in this Sample.
     /// If not, a new one is created with the current system time and 0x00 as id.
@@ -306,6 +306,13 @@ fn scout(
         }
     };
     let addr = config.scouting.multicast.address().unwrap_or(default_addr);
+    let domain = config.domain().unwrap_or(0);
+    let key = config.scouting.multicast.key().clone();
+    let ttl = config
+        .scouting
+        .multicast
+        .ttl()
+        .unwrap_or(zenoh_config::defaults::scouting::multicast::ttl);
     let ifaces = config
         .scouting
         .multicast
@@ -317,12 +324,13 @@ fn scout(
     if !ifaces.is_empty() {
         let sockets: Vec<UdpSocket> = ifaces
             .into_iter()
-            .filter_map(|iface| Runtime::bind_ucast_port(iface).ok())
+            .filter_map(|iface| Runtime::bind_ucast_port(iface, ttl).ok())
             .collect();
         if !sockets.is_empty() {
             async_std::task::spawn(async move {
                 let mut stop_receiver = stop_receiver.stream();
-                let scout = Runtime::scout(&sockets, what, &addr, move |hello| {
+                let key = key.as_deref().map(str::as_bytes);
+                let scout = Runtime::scout(&sockets, what, domain, key, &[addr], move |hello| {
                     let callback = callback.clone();
                     async move {
                         callback(hello);
@@ -25,6 +25,7 @@ use std::{
     collections::HashMap,
     convert::TryFrom,
     hash::Hash,
+    time::Duration,
 };
 
 /// A selector is the combination of a [Key Expression](crate::prelude::KeyExpr), which defines the
@@ -58,6 +59,13 @@ use std::{
 /// Here are the currently standardized parameters for Zenoh (check the specification page for the exhaustive list):
 /// - `_time`: used to express interest in only values dated within a certain time range, values for
 ///   this parameter must be readable by the [Zenoh Time DSL](zenoh_util::time_range::TimeRange) for the value to be considered valid.
+/// - `_project`: used to express interest in only some fields of JSON-encoded values, given as a comma-separated
+///   list of field names (nested fields are designated with `.`-separated paths, e.g. `_project=temp,pos.lat`).
+/// - `_agg`: used to request values aggregated by the queried storage rather than the raw history,
+///   with one of the [`Aggregation`] functions (e.g. `_agg=avg`). It may be combined with `_window`,
+///   a duration (e.g. `_window=1m`) splitting the values in time windows that are aggregated separately.
+/// - `_history`: used to request at most the given number of most recent values for each key
+///   from the queried publication caches (e.g. `_history=10`).
 /// - **`[unstable]`** `_anyke`: used in queries to express interest in replies coming from any key expression. By default, only replies
 ///   whose key expression match query's key expression are accepted. `_anyke` disables the query-reply key expression matching check.
 #[non_exhaustive]
@@ -70,6 +78,50 @@ pub struct Selector<'a> {
 }
 
 pub const TIME_RANGE_KEY: &str = "_time";
+pub const PROJECTION_KEY: &str = "_project";
+pub const AGGREGATION_KEY: &str = "_agg";
+pub const AGGREGATION_WINDOW_KEY: &str = "_window";
+pub const HISTORY_KEY: &str = "_history";
+
+/// The aggregation functions that may be requested with the `_agg` selector parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    Avg,
+    Min,
+    Max,
+    Sum,
+    Count,
+}
+
+impl std::str::FromStr for Aggregation {
+    type Err = zenoh_result::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "avg" => Ok(Aggregation::Avg),
+            "min" => Ok(Aggregation::Min),
+            "max" => Ok(Aggregation::Max),
+            "sum" => Ok(Aggregation::Sum),
+            "count" => Ok(Aggregation::Count),
+            s => bail!(
+                "Unknown aggregation `{}`. Accepted values: avg, min, max, sum or count",
+                s
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for Aggregation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Aggregation::Avg => write!(f, "avg"),
+            Aggregation::Min => write!(f, "min"),
+            Aggregation::Max => write!(f, "max"),
+            Aggregation::Sum => write!(f, "sum"),
+            Aggregation::Count => write!(f, "count"),
+        }
+    }
+}
 impl<'a> Selector<'a> {
     /// Gets the parameters as a raw string.
     pub fn parameters(&self) -> &str {
@@ -280,6 +332,55 @@ fn selector_accessors() {
         assert_eq!(selector.to_string(), without_any + "&other");
     }
 }
+#[test]
+fn selector_projection() {
+    let selector = Selector::try_from("hello/there?_time=[..]&_project=temp,%20pos.lat").unwrap();
+    assert_eq!(
+        selector.projection().unwrap(),
+        Some(vec!["temp".to_string(), "pos.lat".to_string()])
+    );
+    let selector = Selector::try_from("hello/there?_time=[..]").unwrap();
+    assert_eq!(selector.projection().unwrap(), None);
+    let selector = Selector::try_from("hello/there?_project=").unwrap();
+    assert!(selector.projection().is_err());
+    let selector = Selector::try_from("hello/there?_project=a&_project=b").unwrap();
+    assert!(selector.projection().is_err());
+}
+#[test]
+fn selector_aggregation() {
+    let selector = Selector::try_from("hello/there?_agg=avg&_window=1m").unwrap();
+    assert_eq!(
+        selector.aggregation().unwrap(),
+        Some((Aggregation::Avg, Some(Duration::from_secs(60))))
+    );
+    let selector = Selector::try_from("hello/there?_time=[..]&_agg=count").unwrap();
+    assert_eq!(
+        selector.aggregation().unwrap(),
+        Some((Aggregation::Count, None))
+    );
+    let selector = Selector::try_from("hello/there?_time=[..]").unwrap();
+    assert_eq!(selector.aggregation().unwrap(), None);
+    for invalid in [
+        "hello/there?_agg=median",
+        "hello/there?_window=1m",
+        "hello/there?_agg=avg&_window=0",
+        "hello/there?_agg=avg&_window=x",
+    ] {
+        let selector = Selector::try_from(invalid).unwrap();
+        assert!(selector.aggregation().is_err());
+    }
+}
+#[test]
+fn selector_history() {
+    let selector = Selector::try_from("hello/there?_history=10").unwrap();
+    assert_eq!(selector.history().unwrap(), Some(10));
+    let selector = Selector::try_from("hello/there?_time=[..]").unwrap();
+    assert_eq!(selector.history().unwrap(), None);
+    for invalid in ["hello/there?_history=0", "hello/there?_history=x"] {
+        let selector = Selector::try_from(invalid).unwrap();
+        assert!(selector.history().is_err());
+    }
+}
 pub trait Parameter: Sized {
     type Name: AsRef<str> + Sized;
     type Value: AsRef<str> + Sized;
@@ -404,6 +505,75 @@ pub trait Parameters<'a> {
             None => None,
         })
     }
+
+    /// Extracts the standardized `_project` argument from the selector parameters, as a list of field paths.
+    ///
+    /// The default implementation still causes a complete pass through the selector parameters to ensure that there are no duplicates of the `_project` key.
+    fn projection(&'a self) -> ZResult<Option<Vec<String>>>
+    where
+        <Self::Decoder as Iterator>::Item: Parameter,
+    {
+        Ok(match &self.get_parameters([PROJECTION_KEY])?[0] {
+            Some(s) => {
+                let fields: Vec<String> = s
+                    .as_ref()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|f| !f.is_empty())
+                    .map(ToOwned::to_owned)
+                    .collect();
+                if fields.is_empty() {
+                    bail!("`{}` parameter requires at least one field", PROJECTION_KEY)
+                }
+                Some(fields)
+            }
+            None => None,
+        })
+    }
+
+    /// Extracts the standardized `_agg` and `_window` arguments from the selector parameters.
+    ///
+    /// Returns an error if `_window` is present without `_agg`.
+    fn aggregation(&'a self) -> ZResult<Option<(Aggregation, Option<Duration>)>>
+    where
+        <Self::Decoder as Iterator>::Item: Parameter,
+    {
+        let [aggregation, window] =
+            self.get_parameters([AGGREGATION_KEY, AGGREGATION_WINDOW_KEY])?;
+        let window = match &window {
+            Some(w) => {
+                let secs = zenoh_util::time_range::parse_duration(w.as_ref())?;
+                if !secs.is_finite() || secs <= 0.0 {
+                    bail!("`{}` must be a positive duration", AGGREGATION_WINDOW_KEY)
+                }
+                Some(Duration::from_secs_f64(secs))
+            }
+            None => None,
+        };
+        match &aggregation {
+            Some(a) => Ok(Some((a.as_ref().parse()?, window))),
+            None if window.is_some() => bail!(
+                "`{}` parameter requires an `{}` parameter",
+                AGGREGATION_WINDOW_KEY,
+                AGGREGATION_KEY
+            ),
+            None => Ok(None),
+        }
+    }
+
+    /// Extracts the standardized `_history` argument from the selector parameters, as a strictly positive depth.
+    fn history(&'a self) -> ZResult<Option<usize>>
+    where
+        <Self::Decoder as Iterator>::Item: Parameter,
+    {
+        Ok(match &self.get_parameters([HISTORY_KEY])?[0] {
+            Some(s) => match s.as_ref().parse::<usize>() {
+                Ok(depth) if depth > 0 => Some(depth),
+                _ => bail!("`{}` must be a strictly positive integer", HISTORY_KEY),
+            },
+            None => None,
+        })
+    }
 }
 impl<'a> Parameters<'a> for Selector<'a> {
     type Decoder = <str as Parameters<'a>>::Decoder;
@@ -20,7 +20,10 @@ use crate::info::*;
 use crate::key_expr::KeyExprInner;
 #[zenoh_macros::unstable]
 use crate::liveliness::{Liveliness, LivelinessTokenState};
-use crate::net::routing::face::Face;
+use crate::net::routing::filter::PayloadFilter;
+use crate::net::routing::namespace::{NamespacePrefixer, NamespaceStripper};
+#[zenoh_macros::unstable]
+pub use crate::net::runtime::ConnectivityState;
 use crate::net::runtime::Runtime;
 use crate::net::transport::Primitives;
 use crate::prelude::Locality;
@@ -38,21 +41,23 @@ use crate::Selector;
 use crate::Value;
 use async_std::task;
 use log::{error, trace, warn};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::convert::TryInto;
 use std::fmt;
 use std::ops::Deref;
-use std::sync::atomic::{AtomicU16, AtomicU64, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::sync::RwLock;
+use std::sync::Weak;
 use std::time::Duration;
 use uhlc::HLC;
-use zenoh_buffers::ZBuf;
+use zenoh_buffers::{SplitBuffer, ZBuf};
 use zenoh_collections::SingleOrVec;
 use zenoh_config::unwrap_or_default;
 use zenoh_core::{zconfigurable, zread, Resolve, ResolveClosure, ResolveFuture, SyncResolve};
 use zenoh_protocol::{
+    common::Attachment,
     core::{
         key_expr::{keyexpr, OwnedKeyExpr},
         Channel, CongestionControl, ExprId, QueryTarget, QueryableInfo, SubInfo, WireExpr, ZInt,
@@ -61,6 +66,8 @@ use zenoh_protocol::{
     zenoh::{DataInfo, QueryBody, RoutingContext},
 };
 use zenoh_result::ZResult;
+#[zenoh_macros::unstable]
+pub use zenoh_transport::PriorityQueueStats;
 use zenoh_util::core::AsyncResolve;
 
 pub type AtomicZInt = AtomicU64;
@@ -74,8 +81,8 @@ zconfigurable! {
 }
 
 pub(crate) struct SessionState {
-    pub(crate) primitives: Option<Arc<Face>>, // @TODO replace with MaybeUninit ??
-    pub(crate) expr_id_counter: AtomicUsize,  // @TODO: manage rollover and uniqueness
+    pub(crate) primitives: Option<Arc<dyn Primitives>>, // @TODO replace with MaybeUninit ??
+    pub(crate) expr_id_counter: AtomicUsize,            // @TODO: manage rollover and uniqueness
     pub(crate) qid_counter: AtomicZInt,
     pub(crate) decl_id_counter: AtomicUsize,
     pub(crate) local_resources: HashMap<ExprId, Resource>,
@@ -88,6 +95,9 @@ pub(crate) struct SessionState {
     pub(crate) queries: HashMap<ZInt, QueryState>,
     pub(crate) aggregated_subscribers: Vec<OwnedKeyExpr>,
     pub(crate) aggregated_publishers: Vec<OwnedKeyExpr>,
+    pub(crate) remote_subscribers: HashSet<OwnedKeyExpr>,
+    pub(crate) matching_publishers: Vec<(OwnedKeyExpr, Arc<AtomicBool>)>,
+    pub(crate) publication_buffers: Vec<(OwnedKeyExpr, Arc<PublicationBuffer>)>,
 }
 
 impl SessionState {
@@ -110,6 +120,9 @@ impl SessionState {
             queries: HashMap::new(),
             aggregated_subscribers,
             aggregated_publishers,
+            remote_subscribers: HashSet::new(),
+            matching_publishers: Vec::new(),
+            publication_buffers: Vec::new(),
         }
     }
 }
@@ -207,6 +220,21 @@ impl SessionState {
             self.remote_key_to_expr(key_expr)
         }
     }
+
+    /// Whether some subscriber declared by the network matches `key_expr`.
+    pub(crate) fn has_remote_subscribers(&self, key_expr: &keyexpr) -> bool {
+        self.remote_subscribers
+            .iter()
+            .any(|sub| sub.intersects(key_expr))
+    }
+
+    fn update_matching_publishers(&self, key_expr: &keyexpr) {
+        for (pub_expr, matching) in &self.matching_publishers {
+            if pub_expr.intersects(key_expr) {
+                matching.store(self.has_remote_subscribers(pub_expr), Ordering::Relaxed);
+            }
+        }
+    }
 }
 
 impl fmt::Debug for SessionState {
@@ -300,6 +328,71 @@ where
     }
 }
 
+/// The kind of an entity declared by a [`Session`].
+#[zenoh_macros::unstable]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeclarationKind {
+    Publisher,
+    Subscriber,
+    Queryable,
+    LivelinessToken,
+}
+
+/// An entity currently declared by a [`Session`], as listed by [`Session::declarations`].
+#[zenoh_macros::unstable]
+#[derive(Debug, Clone)]
+pub struct Declaration {
+    /// The id of the entity (shared by the key expressions of a multi-key subscriber).
+    /// Publishers have none.
+    pub id: Option<usize>,
+    pub kind: DeclarationKind,
+    pub key_expr: KeyExpr<'static>,
+    /// The origin of the samples or queries accepted by a subscriber or a queryable.
+    pub origin: Option<Locality>,
+    /// The number of samples delivered to a subscriber, or of queries delivered to a queryable.
+    pub received: Option<u64>,
+    /// The number of queries a queryable is still processing.
+    pub in_flight: Option<usize>,
+}
+
+/// The number of entries of the internal maps of a [`Session`], as returned by [`Session::counters`].
+///
+/// Once the entities declared by a session are undeclared, its counters return to their
+/// previous values: counters that keep growing indicate a leak.
+#[zenoh_macros::unstable]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SessionCounters {
+    pub local_resources: usize,
+    pub remote_resources: usize,
+    pub publications: usize,
+    pub subscribers: usize,
+    pub queryables: usize,
+    pub tokens: usize,
+    /// The queries waiting for replies.
+    pub queries: usize,
+    /// The publishers listening for matching subscribers.
+    pub matching_publishers: usize,
+    pub publication_buffers: usize,
+    /// The faces of the routing tables of the session runtime.
+    pub faces: usize,
+}
+
+/// The statistics of the transmission queues of a [`Session`], as returned by [`Session::stats`].
+#[zenoh_macros::unstable]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SessionStats {
+    queues: [PriorityQueueStats; zenoh_protocol::core::Priority::NUM],
+}
+
+#[zenoh_macros::unstable]
+impl SessionStats {
+    /// The statistics of the transmission queues of the given priority, summed over the
+    /// sessions with the remote nodes.
+    pub fn priority(&self, priority: Priority) -> PriorityQueueStats {
+        self.queues[zenoh_protocol::core::Priority::from(priority) as usize]
+    }
+}
+
 /// A zenoh session.
 ///
 pub struct Session {
@@ -307,6 +400,8 @@ pub struct Session {
     pub(crate) state: Arc<RwLock<SessionState>>,
     pub(crate) id: u16,
     pub(crate) alive: bool,
+    // whether closing this session closes its runtime
+    pub(crate) owns_runtime: bool,
 }
 
 static SESSION_ID_COUNTER: AtomicU16 = AtomicU16::new(0);
@@ -317,29 +412,51 @@ impl Session {
         aggregated_publishers: Vec<OwnedKeyExpr>,
     ) -> impl Resolve<Session> {
         ResolveClosure::new(move || {
-            let router = runtime.router.clone();
-            let state = Arc::new(RwLock::new(SessionState::new(
-                aggregated_subscribers,
-                aggregated_publishers,
-            )));
-            let session = Session {
-                runtime: runtime.clone(),
-                state: state.clone(),
-                id: SESSION_ID_COUNTER.fetch_add(1, Ordering::SeqCst),
-                alive: true,
-            };
-
-            runtime.new_handler(Arc::new(admin::Handler::new(session.clone())));
-
-            let primitives = Some(router.new_primitives(Arc::new(session.clone())));
-            zwrite!(state).primitives = primitives;
-
+            let session = Self::new_face(runtime, aggregated_subscribers, aggregated_publishers);
+            session
+                .runtime
+                .new_handler(Arc::new(admin::Handler::new(session.clone())));
             admin::init(&session);
-
             session
         })
     }
 
+    // Create a session connected to a new face of the router of `runtime`, without admin space
+    fn new_face(
+        runtime: Runtime,
+        aggregated_subscribers: Vec<OwnedKeyExpr>,
+        aggregated_publishers: Vec<OwnedKeyExpr>,
+    ) -> Session {
+        let router = runtime.router.clone();
+        let namespace = runtime.config.lock().namespace().clone();
+        let state = Arc::new(RwLock::new(SessionState::new(
+            aggregated_subscribers,
+            aggregated_publishers,
+        )));
+        let session = Session {
+            runtime,
+            state: state.clone(),
+            id: SESSION_ID_COUNTER.fetch_add(1, Ordering::SeqCst),
+            alive: true,
+            owns_runtime: true,
+        };
+
+        let primitives: Arc<dyn Primitives> = match namespace {
+            Some(namespace) => {
+                let ingress = Arc::new(NamespaceStripper::new(
+                    namespace.clone(),
+                    Arc::new(session.clone()),
+                ));
+                let face = router.new_primitives(ingress.clone());
+                ingress.set_egress(Arc::downgrade(&face) as Weak<dyn Primitives>);
+                Arc::new(NamespacePrefixer::new(namespace, face))
+            }
+            None => router.new_primitives(Arc::new(session.clone())),
+        };
+        zwrite!(state).primitives = Some(primitives);
+        session
+    }
+
     /// Consumes the given `Session`, returning a thread-safe reference-counting
     /// pointer to it (`Arc<Session>`). This is equivalent to `Arc::new(session)`.
     ///
@@ -403,6 +520,82 @@ impl Session {
         Box::leak(Box::new(s))
     }
 
+    /// Create a new [`Session`] over the same runtime as this one.
+    ///
+    /// Both sessions share the network identity, the transports and the routing of the runtime,
+    /// while their declarations are isolated: each of them has its own subscribers, publishers,
+    /// queryables and liveliness tokens, all undeclared when it's closed.
+    /// The data exchanged between sibling sessions is routed as if they were remote to each other.
+    ///
+    /// Closing a sibling session doesn't close the runtime, which is closed with this session.
+    /// The admin space of the runtime (`@/session/<zid>/**`) is served by this session only:
+    /// the sibling sessions share it rather than each registering its own.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # async_std::task::block_on(async {
+    /// use zenoh::prelude::r#async::*;
+    ///
+    /// let session = zenoh::open(config::peer()).res().await.unwrap();
+    /// let tenant = session.new_sibling().res().await;
+    /// let subscriber = tenant.declare_subscriber("key/expression").res().await.unwrap();
+    /// tenant.close().res().await.unwrap();
+    /// # })
+    /// ```
+    #[zenoh_macros::unstable]
+    pub fn new_sibling(&self) -> impl Resolve<Session> + '_ {
+        ResolveClosure::new(move || {
+            let (aggregated_subscribers, aggregated_publishers) = {
+                let config = self.runtime.config.lock();
+                (
+                    config.aggregation().subscribers().clone(),
+                    config.aggregation().publishers().clone(),
+                )
+            };
+            let mut session = Self::new_face(
+                self.runtime.clone(),
+                aggregated_subscribers,
+                aggregated_publishers,
+            );
+            session.owns_runtime = false;
+            session
+        })
+    }
+
+    /// Run `declare` with this session, packing the declarations it makes into as few protocol messages as possible.
+    ///
+    /// This reduces the number of messages sent when declaring many entities at once (e.g. at startup).
+    /// The declarations are only guaranteed to be sent when `declare` returns.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use zenoh::prelude::sync::*;
+    ///
+    /// let session = zenoh::open(config::peer()).res().unwrap();
+    /// let subscribers = session.declare_batch(|session| {
+    ///     (0..100)
+    ///         .map(|i| session.declare_subscriber(format!("key/expression/{i}")).res())
+    ///         .collect::<zenoh::Result<Vec<_>>>()
+    /// });
+    /// ```
+    #[zenoh_macros::unstable]
+    pub fn declare_batch<'a, F, T>(&'a self, declare: F) -> T
+    where
+        F: FnOnce(&'a Session) -> T,
+    {
+        struct BatchGuard(Arc<dyn Primitives>);
+        impl Drop for BatchGuard {
+            fn drop(&mut self) {
+                self.0.end_batch();
+            }
+        }
+
+        let primitives = zread!(self.state).primitives.as_ref().unwrap().clone();
+        primitives.start_batch();
+        let _guard = BatchGuard(primitives);
+        declare(self)
+    }
+
     /// Returns the identifier of the current session. `zid()` is a convenient shortcut.
     /// See [`Session::info()`](`Session::info()`) and [`SessionInfo::zid()`](`SessionInfo::zid()`) for more details.
     pub fn zid(&self) -> ZenohId {
@@ -430,7 +623,9 @@ impl Session {
     pub fn close(self) -> impl Resolve<ZResult<()>> {
         ResolveFuture::new(async move {
             trace!("close()");
-            self.runtime.close().await?;
+            if self.owns_runtime {
+                self.runtime.close().await?;
+            }
 
             let primitives = zwrite!(self.state).primitives.as_ref().unwrap().clone();
             primitives.send_close();
@@ -495,6 +690,166 @@ impl Session {
         }
     }
 
+    /// List the entities currently declared by this [`Session`](Session).
+    ///
+    /// Useful to track the entities that are never undeclared, or to display the zenoh footprint of a process.
+    ///
+    /// # Examples
+    /// ```
+    /// # async_std::task::block_on(async {
+    /// use zenoh::prelude::r#async::*;
+    ///
+    /// let session = zenoh::open(config::peer()).res().await.unwrap();
+    /// let subscriber = session.declare_subscriber("key/expression").res().await.unwrap();
+    /// for declaration in session.declarations() {
+    ///     println!("{:?} {} received={:?}", declaration.kind, declaration.key_expr, declaration.received);
+    /// }
+    /// # })
+    /// ```
+    #[zenoh_macros::unstable]
+    pub fn declarations(&self) -> Vec<Declaration> {
+        let state = zread!(self.state);
+        let publishers = state.publications.iter().map(|key_expr| Declaration {
+            id: None,
+            kind: DeclarationKind::Publisher,
+            key_expr: key_expr.clone().into(),
+            origin: None,
+            received: None,
+            in_flight: None,
+        });
+        let subscribers = state.subscribers.values().map(|sub| Declaration {
+            id: Some(sub.id),
+            kind: DeclarationKind::Subscriber,
+            key_expr: sub.key_expr.clone(),
+            origin: Some(sub.origin),
+            received: Some(sub.received.load(Ordering::Relaxed)),
+            in_flight: None,
+        });
+        let queryables = state.queryables.values().filter_map(|qable| {
+            let key_expr = match state.local_wireexpr_to_expr(&qable.key_expr) {
+                Ok(key_expr) => key_expr.into_owned(),
+                Err(e) => {
+                    log::error!(
+                        "{}. Internal error (queryable key_expr to key_expr failed).",
+                        e
+                    );
+                    return None;
+                }
+            };
+            Some(Declaration {
+                id: Some(qable.id),
+                kind: DeclarationKind::Queryable,
+                key_expr,
+                origin: Some(qable.origin),
+                received: Some(qable.received.load(Ordering::Relaxed)),
+                in_flight: Some(qable.in_flight.count()),
+            })
+        });
+        let tokens = state.tokens.values().map(|token| Declaration {
+            id: Some(token.id),
+            kind: DeclarationKind::LivelinessToken,
+            key_expr: token.key_expr.clone(),
+            origin: None,
+            received: None,
+            in_flight: None,
+        });
+        let mut declarations: Vec<Declaration> = publishers
+            .chain(subscribers)
+            .chain(queryables)
+            .chain(tokens)
+            .collect();
+        declarations.sort_by_key(|d| d.id);
+        declarations
+    }
+
+    /// Count the entries of the internal maps of this [`Session`](Session).
+    ///
+    /// Useful to detect leaks: see [`SessionCounters`].
+    #[zenoh_macros::unstable]
+    pub fn counters(&self) -> SessionCounters {
+        let faces = zread!(self.runtime.router.tables.tables).faces.len();
+        let state = zread!(self.state);
+        SessionCounters {
+            local_resources: state.local_resources.len(),
+            remote_resources: state.remote_resources.len(),
+            publications: state.publications.len(),
+            subscribers: state.subscribers.len(),
+            queryables: state.queryables.len(),
+            tokens: state.tokens.len(),
+            queries: state.queries.len(),
+            matching_publishers: state.matching_publishers.len(),
+            publication_buffers: state.publication_buffers.len(),
+            faces,
+        }
+    }
+
+    /// The statistics of the transmission queues of this [`Session`](Session), per priority.
+    ///
+    /// Useful to verify that the priorities of the publications take effect, e.g. that the data
+    /// of a high priority is not dropped while the queues of the lower priorities are congested.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # async_std::task::block_on(async {
+    /// use zenoh::prelude::r#async::*;
+    /// use zenoh::publication::Priority;
+    ///
+    /// let session = zenoh::open(config::peer()).res().await.unwrap();
+    /// let publisher = session
+    ///     .declare_publisher("key/expression")
+    ///     .priority(Priority::InteractiveHigh)
+    ///     .res()
+    ///     .await
+    ///     .unwrap();
+    /// publisher.put("value").res().await.unwrap();
+    /// let stats = session.stats().priority(Priority::InteractiveHigh);
+    /// println!("pushed: {}, dropped: {}", stats.pushed, stats.dropped);
+    /// # })
+    /// ```
+    #[zenoh_macros::unstable]
+    pub fn stats(&self) -> SessionStats {
+        let mut stats = SessionStats::default();
+        for transport in self.runtime.manager().get_transports() {
+            if let Ok(queues) = transport.get_queue_stats() {
+                for (stats, queue) in stats.queues.iter_mut().zip(queues.iter()) {
+                    stats.merge(queue);
+                }
+            }
+        }
+        stats
+    }
+
+    /// The current connectivity state of this [`Session`](Session).
+    #[zenoh_macros::unstable]
+    pub fn connectivity(&self) -> ConnectivityState {
+        self.runtime.connectivity_state()
+    }
+
+    /// A channel receiving the current connectivity state of this [`Session`](Session),
+    /// then each of its changes.
+    ///
+    /// Useful to await a given state (e.g. a first session established) instead of sleeping.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # async_std::task::block_on(async {
+    /// use zenoh::prelude::r#async::*;
+    /// use zenoh::ConnectivityState;
+    ///
+    /// let session = zenoh::open(config::peer()).res().await.unwrap();
+    /// let watcher = session.connectivity_watcher();
+    /// while let Ok(state) = watcher.recv_async().await {
+    ///     if let ConnectivityState::Connected(_) = state {
+    ///         break;
+    ///     }
+    /// }
+    /// # })
+    /// ```
+    #[zenoh_macros::unstable]
+    pub fn connectivity_watcher(&self) -> flume::Receiver<ConnectivityState> {
+        self.runtime.watch_connectivity()
+    }
+
     /// Create a [`Subscriber`](Subscriber) for the given key expression.
     ///
     /// # Arguments
@@ -527,6 +882,9 @@ impl Session {
             reliability: Reliability::default(),
             mode: PushMode,
             origin: Locality::default(),
+            filter: None,
+            aggregation: None,
+            extra_key_exprs: Vec::new(),
             handler: DefaultHandler,
         }
     }
@@ -566,6 +924,8 @@ impl Session {
             key_expr: key_expr.try_into().map_err(Into::into),
             complete: false,
             origin: Locality::default(),
+            max_concurrent_queries: None,
+            max_queued_queries: 0,
             handler: DefaultHandler,
         }
     }
@@ -603,6 +963,8 @@ impl Session {
             congestion_control: CongestionControl::default(),
             priority: Priority::default(),
             destination: Locality::default(),
+            skip_unmatched: false,
+            buffer: None,
         }
     }
 
@@ -699,6 +1061,7 @@ impl Session {
             publisher: self.declare_publisher(key_expr),
             value: value.into(),
             kind: SampleKind::Put,
+            attachment: None,
         }
     }
 
@@ -730,6 +1093,7 @@ impl Session {
             publisher: self.declare_publisher(key_expr),
             value: Value::empty(),
             kind: SampleKind::Delete,
+            attachment: None,
         }
     }
     /// Query data from the matching queryables in the system.
@@ -772,6 +1136,7 @@ impl Session {
             destination: Locality::default(),
             timeout: Duration::from_millis(unwrap_or_default!(conf.queries_default_timeout())),
             value: None,
+            attachment: None,
             handler: DefaultHandler,
         }
     }
@@ -807,6 +1172,7 @@ impl Session {
             state: self.state.clone(),
             id: self.id,
             alive: false,
+            owns_runtime: self.owns_runtime,
         }
     }
 
@@ -960,6 +1326,39 @@ impl Session {
         })
     }
 
+    /// Returns a flag tracking whether some subscriber declared by the network matches `key_expr`.
+    pub(crate) fn register_matching_publisher(&self, key_expr: &keyexpr) -> Arc<AtomicBool> {
+        let mut state = zwrite!(self.state);
+        let matching = Arc::new(AtomicBool::new(state.has_remote_subscribers(key_expr)));
+        state
+            .matching_publishers
+            .push((key_expr.into(), matching.clone()));
+        matching
+    }
+
+    pub(crate) fn unregister_matching_publisher(&self, matching: &Arc<AtomicBool>) {
+        zwrite!(self.state)
+            .matching_publishers
+            .retain(|(_, m)| !Arc::ptr_eq(m, matching));
+    }
+
+    /// Registers the buffer of a publisher to flush when a matching subscriber gets declared.
+    pub(crate) fn register_publication_buffer(
+        &self,
+        key_expr: &keyexpr,
+        buffer: Arc<PublicationBuffer>,
+    ) {
+        zwrite!(self.state)
+            .publication_buffers
+            .push((key_expr.into(), buffer));
+    }
+
+    pub(crate) fn unregister_publication_buffer(&self, buffer: &Arc<PublicationBuffer>) {
+        zwrite!(self.state)
+            .publication_buffers
+            .retain(|(_, b)| !Arc::ptr_eq(b, buffer));
+    }
+
     pub(crate) fn declare_subscriber_inner(
         &self,
         key_expr: &KeyExpr,
@@ -968,19 +1367,84 @@ impl Session {
         callback: Callback<'static, Sample>,
         info: &SubInfo,
     ) -> ZResult<Arc<SubscriberState>> {
+        self.declare_subscriber_slot(key_expr, scope, origin, callback, info, None)
+    }
+
+    /// Declares a subscriber on several key expressions, sharing the same callback and id.
+    ///
+    /// Only the key expressions that are not included in another one of the set are declared,
+    /// each of them in its own slot of `state.subscribers`. The first one is returned.
+    pub(crate) fn declare_multi_subscriber_inner(
+        &self,
+        key_exprs: &[KeyExpr],
+        origin: Locality,
+        callback: Callback<'static, Sample>,
+        info: &SubInfo,
+    ) -> ZResult<Arc<SubscriberState>> {
+        let covering = key_exprs.iter().enumerate().filter(|(i, key_expr)| {
+            !key_exprs.iter().enumerate().any(|(j, other)| {
+                j != *i && other.includes(key_expr) && (j < *i || !key_expr.includes(other))
+            })
+        });
+        let id = zread!(self.state)
+            .decl_id_counter
+            .fetch_add(1, Ordering::SeqCst);
+        let mut primary = None;
+        for (_, key_expr) in covering {
+            match self.declare_subscriber_slot(
+                key_expr,
+                &None,
+                origin,
+                callback.clone(),
+                info,
+                Some(id),
+            ) {
+                Ok(sub_state) => {
+                    primary.get_or_insert(sub_state);
+                }
+                Err(e) => {
+                    if primary.is_some() {
+                        let _ = self.unsubscribe(id);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        primary.ok_or_else(|| zerror!("No key expression to subscribe to").into())
+    }
+
+    fn declare_subscriber_slot(
+        &self,
+        key_expr: &KeyExpr,
+        scope: &Option<KeyExpr>,
+        origin: Locality,
+        callback: Callback<'static, Sample>,
+        info: &SubInfo,
+        multi_key_id: Option<Id>,
+    ) -> ZResult<Arc<SubscriberState>> {
+        let filter = info
+            .filter
+            .as_deref()
+            .map(str::parse::<PayloadFilter>)
+            .transpose()?;
+        let mut info = info.clone();
         let mut state = zwrite!(self.state);
         log::trace!("subscribe({:?})", key_expr);
-        let id = state.decl_id_counter.fetch_add(1, Ordering::SeqCst);
+        let slot = state.decl_id_counter.fetch_add(1, Ordering::SeqCst);
         let key_expr = match scope {
             Some(scope) => scope / key_expr,
             None => key_expr.clone(),
         };
 
         let sub_state = Arc::new(SubscriberState {
-            id,
+            id: multi_key_id.unwrap_or(slot),
             key_expr: key_expr.clone().into_owned(),
             scope: scope.clone().map(|e| e.into_owned()),
             origin,
+            filter,
+            aggregation: info.aggregation,
+            multi_key: multi_key_id.is_some(),
+            received: AtomicU64::new(0),
             callback,
         });
 
@@ -1000,23 +1464,44 @@ impl Session {
                 .find(|s| s.includes( &key_expr))
                 {
                     Some(join_sub) => {
+                        // Filters and aggregation policies are applied per declaration
+                        // by the router: aggregated subscribers are declared without them.
+                        info.filter = None;
+                        info.aggregation = None;
                         let joined_sub = state.subscribers.values().any(|s| {
                             s.origin != Locality::SessionLocal && join_sub.includes(&s.key_expr)
                         });
                         (!joined_sub).then(|| join_sub.clone().into())
                     }
                     None => {
-                        let twin_sub = state
+                        let mut twin_subs = state
                             .subscribers
                             .values()
-                            .any(|s| s.origin != Locality::SessionLocal && s.key_expr == key_expr);
-                        (!twin_sub).then(|| key_expr.clone())
+                            .filter(|s| {
+                                s.origin != Locality::SessionLocal && s.key_expr == key_expr
+                            })
+                            .peekable();
+                        if twin_subs.peek().is_none() {
+                            Some(key_expr.clone())
+                        } else if info.filter.is_some()
+                            || info.aggregation.is_some()
+                            || twin_subs.any(|s| s.filter.is_some() || s.aggregation.is_some())
+                        {
+                            // Twin subscribers with different filters share the same declaration:
+                            // re-declare it unfiltered and let each of them filter locally.
+                            // It is also re-declared unaggregated, as samples can't be aggregated locally.
+                            info.filter = None;
+                            info.aggregation = None;
+                            Some(key_expr.clone())
+                        } else {
+                            None
+                        }
                     }
                 }
             })
             .flatten();
 
-        state.subscribers.insert(sub_state.id, sub_state.clone());
+        state.subscribers.insert(slot, sub_state.clone());
         for res in state
             .local_resources
             .values_mut()
@@ -1062,7 +1547,7 @@ impl Session {
             //     key_expr.to_wire(self)
             // };
 
-            primitives.decl_subscriber(&key_expr.to_wire(self), info, None);
+            primitives.decl_subscriber(&key_expr.to_wire(self), &info, None);
         }
 
         Ok(sub_state)
@@ -1070,23 +1555,43 @@ impl Session {
 
     pub(crate) fn unsubscribe(&self, sid: usize) -> ZResult<()> {
         let mut state = zwrite!(self.state);
-        if let Some(sub_state) = state.subscribers.remove(&sid) {
-            trace!("unsubscribe({:?})", sub_state);
-            for res in state
-                .local_resources
-                .values_mut()
-                .filter_map(Resource::as_node_mut)
-            {
-                res.subscribers.retain(|sub| sub.id != sub_state.id);
-            }
-            for res in state
-                .remote_resources
-                .values_mut()
-                .filter_map(Resource::as_node_mut)
-            {
-                res.subscribers.retain(|sub| sub.id != sub_state.id);
+        let sub_states = match state.subscribers.remove(&sid) {
+            Some(sub_state) => vec![sub_state],
+            None => {
+                // The key expressions of a multi-key subscriber are stored in their own slots
+                let slots: Vec<Id> = state
+                    .subscribers
+                    .iter()
+                    .filter(|(_, s)| s.multi_key && s.id == sid)
+                    .map(|(slot, _)| *slot)
+                    .collect();
+                slots
+                    .iter()
+                    .filter_map(|slot| state.subscribers.remove(slot))
+                    .collect()
             }
+        };
+        if sub_states.is_empty() {
+            return Err(zerror!("Unable to find subscriber").into());
+        }
+        trace!("unsubscribe({:?})", sub_states);
+        for res in state
+            .local_resources
+            .values_mut()
+            .filter_map(Resource::as_node_mut)
+        {
+            res.subscribers.retain(|sub| sub.id != sid);
+        }
+        for res in state
+            .remote_resources
+            .values_mut()
+            .filter_map(Resource::as_node_mut)
+        {
+            res.subscribers.retain(|sub| sub.id != sid);
+        }
 
+        let mut forgotten: Vec<KeyExpr<'static>> = Vec::new();
+        for sub_state in &sub_states {
             #[cfg(not(feature = "unstable"))]
             let send_forget = sub_state.origin != Locality::SessionLocal;
             #[cfg(feature = "unstable")]
@@ -1099,7 +1604,7 @@ impl Session {
                 // Note: there might be several Subscribers on the same KeyExpr.
                 // Before calling forget_subscriber(key_expr), check if this was the last one.
                 let key_expr = &sub_state.key_expr;
-                match state
+                let forget = match state
                     .aggregated_subscribers
                     .iter()
                     .find(|s| s.includes(key_expr))
@@ -1108,30 +1613,32 @@ impl Session {
                         let joined_sub = state.subscribers.values().any(|s| {
                             s.origin != Locality::SessionLocal && join_sub.includes(&s.key_expr)
                         });
-                        if !joined_sub {
-                            let primitives = state.primitives.as_ref().unwrap().clone();
-                            let key_expr = WireExpr::from(join_sub).to_owned();
-                            drop(state);
-                            primitives.forget_subscriber(&key_expr, None);
-                        }
+                        (!joined_sub).then(|| join_sub.clone().into())
                     }
                     None => {
                         let twin_sub = state
                             .subscribers
                             .values()
                             .any(|s| s.origin != Locality::SessionLocal && s.key_expr == *key_expr);
-                        if !twin_sub {
-                            let primitives = state.primitives.as_ref().unwrap().clone();
-                            drop(state);
-                            primitives.forget_subscriber(&key_expr.to_wire(self), None);
-                        }
+                        (!twin_sub).then(|| key_expr.clone())
                     }
                 };
+                // Several key expressions of a multi-key subscriber may share an aggregated declaration
+                if let Some(key_expr) = forget {
+                    if !forgotten.contains(&key_expr) {
+                        forgotten.push(key_expr);
+                    }
+                }
+            }
+        }
+        if !forgotten.is_empty() {
+            let primitives = state.primitives.as_ref().unwrap().clone();
+            drop(state);
+            for key_expr in &forgotten {
+                primitives.forget_subscriber(&key_expr.to_wire(self), None);
             }
-            Ok(())
-        } else {
-            Err(zerror!("Unable to find subscriber").into())
         }
+        Ok(())
     }
 
     pub(crate) fn declare_queryable_inner(
@@ -1150,6 +1657,8 @@ impl Session {
             complete,
             origin,
             callback,
+            in_flight: Arc::new(InFlightQueries::default()),
+            received: AtomicU64::new(0),
         });
         #[cfg(feature = "complete_n")]
         {
@@ -1313,14 +1822,24 @@ impl Session {
         payload: ZBuf,
     ) {
         let mut callbacks = SingleOrVec::default();
+        // ids of the multi-key subscribers already served, which must receive each sample once
+        let mut served: Vec<Id> = Vec::new();
         let state = zread!(self.state);
         if key_expr.suffix.is_empty() {
             match state.get_res(&key_expr.scope, local) {
                 Some(Resource::Node(res)) => {
                     for sub in &res.subscribers {
-                        if sub.origin == Locality::Any
-                            || (local == (sub.origin == Locality::SessionLocal))
+                        if (sub.origin == Locality::Any
+                            || (local == (sub.origin == Locality::SessionLocal)))
+                            && sub.accepts(&res.key_expr, &info, &payload)
                         {
+                            if sub.multi_key {
+                                if served.contains(&sub.id) {
+                                    continue;
+                                }
+                                served.push(sub.id);
+                            }
+                            sub.received.fetch_add(1, Ordering::Relaxed);
                             match &sub.scope {
                                 Some(scope) => {
                                     if !res.key_expr.starts_with(&***scope) {
@@ -1371,7 +1890,15 @@ impl Session {
                         if (sub.origin == Locality::Any
                             || (local == (sub.origin == Locality::SessionLocal)))
                             && key_expr.intersects(&sub.key_expr)
+                            && sub.accepts(&key_expr, &info, &payload)
                         {
+                            if sub.multi_key {
+                                if served.contains(&sub.id) {
+                                    continue;
+                                }
+                                served.push(sub.id);
+                            }
+                            sub.received.fetch_add(1, Ordering::Relaxed);
                             match &sub.scope {
                                 Some(scope) => {
                                     if !key_expr.starts_with(&***scope) {
@@ -1439,6 +1966,7 @@ impl Session {
         destination: Locality,
         timeout: Duration,
         value: Option<Value>,
+        attachment: Option<ZBuf>,
         callback: Callback<'static, Reply>,
     ) -> ZResult<()> {
         log::trace!("get({}, {:?}, {:?})", selector, target, consolidation);
@@ -1475,6 +2003,7 @@ impl Session {
                     (query.callback)(Reply {
                         sample: Err("Timeout".into()),
                         replier_id: zid,
+                        trace: None,
                     });
                 }
             }
@@ -1505,6 +2034,21 @@ impl Session {
         let primitives = state.primitives.as_ref().unwrap().clone();
 
         drop(state);
+        // a query attaching some bytes without a value has a body without encoding
+        let body = (value.is_some() || attachment.is_some()).then(|| {
+            let (encoding, payload) = match value {
+                Some(v) => (Some(v.encoding), v.payload),
+                None => (None, ZBuf::default()),
+            };
+            QueryBody {
+                data_info: DataInfo {
+                    encoding,
+                    attachment,
+                    ..Default::default()
+                },
+                payload,
+            }
+        });
         if destination != Locality::SessionLocal {
             primitives.send_query(
                 &wexpr,
@@ -1512,16 +2056,7 @@ impl Session {
                 qid,
                 target,
                 consolidation,
-                value.as_ref().map(|v| {
-                    let data_info = DataInfo {
-                        encoding: Some(v.encoding.clone()),
-                        ..Default::default()
-                    };
-                    QueryBody {
-                        data_info,
-                        payload: v.payload.clone(),
-                    }
-                }),
+                body.clone(),
                 None,
             );
         }
@@ -1533,16 +2068,7 @@ impl Session {
                 qid,
                 target,
                 consolidation,
-                value.map(|v| {
-                    let data_info = DataInfo {
-                        encoding: Some(v.encoding),
-                        ..Default::default()
-                    };
-                    QueryBody {
-                        data_info,
-                        payload: v.payload,
-                    }
-                }),
+                body,
             );
         }
         Ok(())
@@ -1584,8 +2110,11 @@ impl Session {
                                     }
                                 }
                         )
-                        .map(|qable| qable.callback.clone())
-                        .collect::<Vec<Arc<dyn Fn(Query) + Send + Sync>>>();
+                        .map(|qable| {
+                            qable.received.fetch_add(1, Ordering::Relaxed);
+                            (qable.callback.clone(), qable.in_flight.clone())
+                        })
+                        .collect::<Vec<(Arc<dyn Fn(Query) + Send + Sync>, Arc<InFlightQueries>)>>();
                     (
                         state.primitives.as_ref().unwrap().clone(),
                         key_expr.into_owned(),
@@ -1603,25 +2132,26 @@ impl Session {
 
         let zid = self.runtime.zid; // @TODO build/use prebuilt specific zid
 
-        let query = Query {
-            inner: Arc::new(QueryInner {
-                key_expr,
-                parameters,
-                value: body.map(|b| Value {
-                    payload: b.payload,
-                    encoding: b.data_info.encoding.unwrap_or_default(),
-                }),
-                qid,
-                zid,
-                primitives: if local {
-                    Arc::new(self.clone())
-                } else {
-                    primitives
-                },
-            }),
-        };
-        for callback in callbacks.iter() {
-            callback(query.clone());
+        let (value, attachment) = QueryInner::split_body(body);
+        let inner = Arc::new(QueryInner {
+            key_expr,
+            parameters,
+            value,
+            attachment,
+            qid,
+            zid,
+            primitives: if local {
+                Arc::new(self.clone())
+            } else {
+                primitives
+            },
+        });
+        for (callback, in_flight) in callbacks {
+            callback(Query {
+                inner: inner.clone(),
+                permit: None,
+                in_flight: Some(Arc::new(InFlightQuery::new(&in_flight))),
+            });
         }
     }
 }
@@ -1664,6 +2194,9 @@ impl SessionDeclarations for Arc<Session> {
             reliability: Reliability::default(),
             mode: PushMode,
             origin: Locality::default(),
+            filter: None,
+            aggregation: None,
+            extra_key_exprs: Vec::new(),
             handler: DefaultHandler,
         }
     }
@@ -1708,6 +2241,8 @@ impl SessionDeclarations for Arc<Session> {
             key_expr: key_expr.try_into().map_err(Into::into),
             complete: false,
             origin: Locality::default(),
+            max_concurrent_queries: None,
+            max_queued_queries: 0,
             handler: DefaultHandler,
         }
     }
@@ -1745,6 +2280,8 @@ impl SessionDeclarations for Arc<Session> {
             congestion_control: CongestionControl::default(),
             priority: Priority::default(),
             destination: Locality::default(),
+            skip_unmatched: false,
+            buffer: None,
         }
     }
 
@@ -1798,8 +2335,9 @@ impl Primitives for Session {
         }
     }
 
-    fn forget_resource(&self, _expr_id: ZInt) {
-        trace!("recv Forget Resource {}", _expr_id);
+    fn forget_resource(&self, expr_id: ZInt) {
+        trace!("recv Forget Resource {}", expr_id);
+        zwrite!(self.state).remote_resources.remove(&expr_id);
     }
 
     fn decl_publisher(&self, _key_expr: &WireExpr, _routing_context: Option<RoutingContext>) {
@@ -1817,45 +2355,65 @@ impl Primitives for Session {
         _routing_context: Option<RoutingContext>,
     ) {
         trace!("recv Decl Subscriber {:?} , {:?}", key_expr, _sub_info);
-        #[cfg(feature = "unstable")]
+        let mut state = zwrite!(self.state);
+        match state
+            .wireexpr_to_keyexpr(key_expr, false)
+            .map(OwnedKeyExpr::from)
         {
-            let state = zread!(self.state);
-            match state.wireexpr_to_keyexpr(key_expr, false) {
-                Ok(expr) => {
-                    if expr
-                        .as_str()
-                        .starts_with(crate::liveliness::PREFIX_LIVELINESS)
-                    {
-                        drop(state);
-                        self.handle_data(false, key_expr, None, ZBuf::default());
-                    }
+            Ok(expr) => {
+                state.remote_subscribers.insert(expr.clone());
+                state.update_matching_publishers(&expr);
+                let buffers: Vec<Arc<PublicationBuffer>> = state
+                    .publication_buffers
+                    .iter()
+                    .filter(|(pub_expr, buffer)| pub_expr.intersects(&expr) && !buffer.is_empty())
+                    .map(|(_, buffer)| buffer.clone())
+                    .collect();
+                drop(state);
+                if !buffers.is_empty() {
+                    // the routing tables may be locked by the caller: flush from another task
+                    task::spawn(async move {
+                        for buffer in buffers {
+                            buffer.flush();
+                        }
+                    });
+                }
+                #[cfg(feature = "unstable")]
+                if expr
+                    .as_str()
+                    .starts_with(crate::liveliness::PREFIX_LIVELINESS)
+                {
+                    self.handle_data(false, key_expr, None, ZBuf::default());
                 }
-                Err(err) => log::error!("Received Forget Subscriber for unkown key_expr: {}", err),
             }
+            Err(err) => log::error!("Received Decl Subscriber for unkown key_expr: {}", err),
         }
     }
 
     fn forget_subscriber(&self, key_expr: &WireExpr, _routing_context: Option<RoutingContext>) {
         trace!("recv Forget Subscriber {:?}", key_expr);
-        #[cfg(feature = "unstable")]
+        let mut state = zwrite!(self.state);
+        match state
+            .wireexpr_to_keyexpr(key_expr, false)
+            .map(OwnedKeyExpr::from)
         {
-            let state = zread!(self.state);
-            match state.wireexpr_to_keyexpr(key_expr, false) {
-                Ok(expr) => {
-                    if expr
-                        .as_str()
-                        .starts_with(crate::liveliness::PREFIX_LIVELINESS)
-                    {
-                        drop(state);
-                        let data_info = DataInfo {
-                            kind: SampleKind::Delete,
-                            ..Default::default()
-                        };
-                        self.handle_data(false, key_expr, Some(data_info), ZBuf::default());
-                    }
+            Ok(expr) => {
+                state.remote_subscribers.remove(&expr);
+                state.update_matching_publishers(&expr);
+                drop(state);
+                #[cfg(feature = "unstable")]
+                if expr
+                    .as_str()
+                    .starts_with(crate::liveliness::PREFIX_LIVELINESS)
+                {
+                    let data_info = DataInfo {
+                        kind: SampleKind::Delete,
+                        ..Default::default()
+                    };
+                    self.handle_data(false, key_expr, Some(data_info), ZBuf::default());
                 }
-                Err(err) => log::error!("Received Forget Subscriber for unkown key_expr: {}", err),
             }
+            Err(err) => log::error!("Received Forget Subscriber for unkown key_expr: {}", err),
         }
     }
 
@@ -1927,6 +2485,7 @@ impl Primitives for Session {
         key_expr: WireExpr,
         data_info: Option<DataInfo>,
         payload: ZBuf,
+        attachment: Option<Attachment>,
     ) {
         trace!(
             "recv ReplyData {:?} {:?} {:?} {:?} {:?}",
@@ -1988,9 +2547,18 @@ impl Primitives for Session {
                     }
                     None => key_expr,
                 };
+                let trace = match attachment {
+                    Some(attachment)
+                        if crate::query::get_trace(query.selector.parameters()).is_some() =>
+                    {
+                        String::from_utf8(attachment.buffer.contiguous().into_owned()).ok()
+                    }
+                    _ => None,
+                };
                 let new_reply = Reply {
                     sample: Ok(Sample::with_info(key_expr.into_owned(), payload, data_info)),
                     replier_id,
+                    trace,
                 };
                 let callback = match query.reception_mode {
                     ConsolidationMode::None => Some((query.callback.clone(), new_reply)),
@@ -14,6 +14,7 @@
 
 //! Subscribing primitives.
 use crate::handlers::{locked, Callback, DefaultHandler};
+use crate::net::routing::filter::PayloadFilter;
 use crate::prelude::Locality;
 use crate::prelude::{Id, IntoCallbackReceiverPair, KeyExpr, Sample};
 use crate::Undeclarable;
@@ -21,9 +22,15 @@ use crate::{Result as ZResult, SessionRef};
 use std::fmt;
 use std::future::Ready;
 use std::ops::{Deref, DerefMut};
+use std::sync::atomic::AtomicU64;
 use std::sync::Arc;
+use std::time::Duration;
+use zenoh_buffers::ZBuf;
 use zenoh_core::{AsyncResolve, Resolvable, Resolve, SyncResolve};
-use zenoh_protocol::core::SubInfo;
+use zenoh_protocol::{
+    core::{SubAggregation, SubInfo, ZInt},
+    zenoh::DataInfo,
+};
 
 /// The subscription mode.
 pub use zenoh_protocol::core::SubMode;
@@ -36,9 +43,26 @@ pub(crate) struct SubscriberState {
     pub(crate) key_expr: KeyExpr<'static>,
     pub(crate) scope: Option<KeyExpr<'static>>,
     pub(crate) origin: Locality,
+    pub(crate) filter: Option<PayloadFilter>,
+    pub(crate) aggregation: Option<SubAggregation>,
+    // whether this state is one of the key expressions of a multi-key subscriber, sharing its `id`
+    pub(crate) multi_key: bool,
+    // the number of samples delivered to the callback
+    pub(crate) received: AtomicU64,
     pub(crate) callback: Callback<'static, Sample>,
 }
 
+impl SubscriberState {
+    /// Whether the payload filter of this subscriber (if any) accepts the given data.
+    #[inline]
+    pub(crate) fn accepts(&self, key_expr: &str, info: &Option<DataInfo>, payload: &ZBuf) -> bool {
+        self.filter
+            .as_ref()
+            .map(|filter| filter.matches(key_expr, info.as_ref(), payload))
+            .unwrap_or(true)
+    }
+}
+
 impl fmt::Debug for SubscriberState {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("Subscriber")
@@ -313,6 +337,21 @@ pub struct SubscriberBuilder<'a, 'b, Mode, Handler> {
     #[cfg(not(feature = "unstable"))]
     pub(crate) origin: Locality,
 
+    #[cfg(feature = "unstable")]
+    pub filter: Option<String>,
+    #[cfg(not(feature = "unstable"))]
+    pub(crate) filter: Option<String>,
+
+    #[cfg(feature = "unstable")]
+    pub aggregation: Option<SubAggregation>,
+    #[cfg(not(feature = "unstable"))]
+    pub(crate) aggregation: Option<SubAggregation>,
+
+    #[cfg(feature = "unstable")]
+    pub extra_key_exprs: Vec<ZResult<KeyExpr<'b>>>,
+    #[cfg(not(feature = "unstable"))]
+    pub(crate) extra_key_exprs: Vec<ZResult<KeyExpr<'b>>>,
+
     #[cfg(feature = "unstable")]
     pub handler: Handler,
     #[cfg(not(feature = "unstable"))]
@@ -347,6 +386,9 @@ impl<'a, 'b, Mode> SubscriberBuilder<'a, 'b, Mode, DefaultHandler> {
             reliability,
             mode,
             origin,
+            filter,
+            aggregation,
+            extra_key_exprs,
             handler: _,
         } = self;
         SubscriberBuilder {
@@ -355,6 +397,9 @@ impl<'a, 'b, Mode> SubscriberBuilder<'a, 'b, Mode, DefaultHandler> {
             reliability,
             mode,
             origin,
+            filter,
+            aggregation,
+            extra_key_exprs,
             handler: callback,
         }
     }
@@ -420,6 +465,9 @@ impl<'a, 'b, Mode> SubscriberBuilder<'a, 'b, Mode, DefaultHandler> {
             reliability,
             mode,
             origin,
+            filter,
+            aggregation,
+            extra_key_exprs,
             handler: _,
         } = self;
         SubscriberBuilder {
@@ -428,6 +476,9 @@ impl<'a, 'b, Mode> SubscriberBuilder<'a, 'b, Mode, DefaultHandler> {
             reliability,
             mode,
             origin,
+            filter,
+            aggregation,
+            extra_key_exprs,
             handler,
         }
     }
@@ -463,6 +514,113 @@ impl<'a, 'b, Mode, Handler> SubscriberBuilder<'a, 'b, Mode, Handler> {
         self
     }
 
+    /// Only receive the samples whose payload matches the given filter expression.
+    ///
+    /// The filter is evaluated by the first router, so that the non-matching samples
+    /// of high-volume key expressions are dropped close to their source.
+    /// It's a conjunction of comparisons between a (dotted) field of a JSON payload and a JSON literal,
+    /// using the `==`, `!=`, `<`, `<=`, `>` and `>=` operators, and of geographic clauses
+    /// `geo(<latitude>, <longitude>) in [<south>, <west>, <north>, <east>]` whose coordinates are
+    /// fields of a JSON payload or chunks of the key expression (e.g. `$1` for the second chunk).
+    /// Samples whose payload must be read but is not JSON-encoded, or that lack one of the read fields,
+    /// are not received.
+    ///
+    /// # Examples
+    /// ```
+    /// # async_std::task::block_on(async {
+    /// use zenoh::prelude::r#async::*;
+    ///
+    /// let session = zenoh::open(config::peer()).res().await.unwrap();
+    /// let subscriber = session
+    ///     .declare_subscriber("sensors/**")
+    ///     .filter(r#"temperature > 30 && location.kind == "outdoor""#)
+    ///     .res()
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    #[zenoh_macros::unstable]
+    #[inline]
+    pub fn filter<IntoString>(mut self, filter: IntoString) -> Self
+    where
+        IntoString: Into<String>,
+    {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    /// Ask the first router to aggregate the samples it forwards to this subscriber,
+    /// e.g. when it is behind a constrained link.
+    ///
+    /// The samples are then delivered at most once per `window`, only the latest sample
+    /// of each key expression being kept in the meantime, and at most `max_rate` samples
+    /// per second (0 meaning unlimited).
+    ///
+    /// # Examples
+    /// ```
+    /// # async_std::task::block_on(async {
+    /// use std::time::Duration;
+    /// use zenoh::prelude::r#async::*;
+    ///
+    /// let session = zenoh::open(config::peer()).res().await.unwrap();
+    /// let subscriber = session
+    ///     .declare_subscriber("sensors/**")
+    ///     .aggregation(Duration::from_millis(500), 100)
+    ///     .res()
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    #[zenoh_macros::unstable]
+    #[inline]
+    pub fn aggregation(mut self, window: Duration, max_rate: u32) -> Self {
+        let aggregation = SubAggregation {
+            window: window.as_millis().try_into().unwrap_or(ZInt::MAX),
+            max_rate: max_rate.into(),
+        };
+        self.aggregation = (aggregation != SubAggregation::default()).then_some(aggregation);
+        self
+    }
+
+    /// Also subscribe to the given key expressions, sharing the same handler and declaration.
+    ///
+    /// Only the minimal set of key expressions covering all the given ones is declared,
+    /// and samples matching several of them are delivered only once.
+    /// Pull subscribers can't be declared on several key expressions.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # async_std::task::block_on(async {
+    /// use zenoh::prelude::r#async::*;
+    ///
+    /// let session = zenoh::open(config::peer()).res().await.unwrap();
+    /// let subscriber = session
+    ///     .declare_subscriber("robot/arm/*")
+    ///     .add_key_exprs(["robot/battery", "robot/arm/joint1", "alarms/**"])
+    ///     .res()
+    ///     .await
+    ///     .unwrap();
+    /// while let Ok(sample) = subscriber.recv_async().await {
+    ///     println!("Received: {} {}", sample.key_expr, sample.value);
+    /// }
+    /// # })
+    /// ```
+    #[zenoh_macros::unstable]
+    #[inline]
+    pub fn add_key_exprs<I, TryIntoKeyExpr>(mut self, key_exprs: I) -> Self
+    where
+        I: IntoIterator<Item = TryIntoKeyExpr>,
+        TryIntoKeyExpr: TryInto<KeyExpr<'b>>,
+        <TryIntoKeyExpr as TryInto<KeyExpr<'b>>>::Error: Into<zenoh_result::Error>,
+    {
+        self.extra_key_exprs.extend(
+            key_exprs
+                .into_iter()
+                .map(|key_expr| key_expr.try_into().map_err(Into::into)),
+        );
+        self
+    }
+
     /// Change the subscription mode to Pull.
     #[inline]
     pub fn pull_mode(self) -> SubscriberBuilder<'a, 'b, PullMode, Handler> {
@@ -472,6 +630,9 @@ impl<'a, 'b, Mode, Handler> SubscriberBuilder<'a, 'b, Mode, Handler> {
             reliability,
             mode: _,
             origin,
+            filter,
+            aggregation,
+            extra_key_exprs,
             handler,
         } = self;
         SubscriberBuilder {
@@ -480,6 +641,9 @@ impl<'a, 'b, Mode, Handler> SubscriberBuilder<'a, 'b, Mode, Handler> {
             reliability,
             mode: PullMode,
             origin,
+            filter,
+            aggregation,
+            extra_key_exprs,
             handler,
         }
     }
@@ -493,6 +657,9 @@ impl<'a, 'b, Mode, Handler> SubscriberBuilder<'a, 'b, Mode, Handler> {
             reliability,
             mode: _,
             origin,
+            filter,
+            aggregation,
+            extra_key_exprs,
             handler,
         } = self;
         SubscriberBuilder {
@@ -501,6 +668,9 @@ impl<'a, 'b, Mode, Handler> SubscriberBuilder<'a, 'b, Mode, Handler> {
             reliability,
             mode: PushMode,
             origin,
+            filter,
+            aggregation,
+            extra_key_exprs,
             handler,
         }
     }
@@ -523,18 +693,32 @@ where
     fn res_sync(self) -> <Self as Resolvable>::To {
         let key_expr = self.key_expr?;
         let session = self.session;
+        let info = SubInfo {
+            reliability: self.reliability,
+            mode: self.mode.into(),
+            filter: self.filter,
+            aggregation: self.aggregation,
+        };
+        if !self.extra_key_exprs.is_empty() {
+            let mut key_exprs = vec![key_expr];
+            for key_expr in self.extra_key_exprs {
+                key_exprs.push(key_expr?);
+            }
+            let (callback, receiver) = self.handler.into_cb_receiver_pair();
+            return session
+                .declare_multi_subscriber_inner(&key_exprs, self.origin, callback, &info)
+                .map(|sub_state| Subscriber {
+                    subscriber: SubscriberInner {
+                        session,
+                        state: sub_state,
+                        alive: true,
+                    },
+                    receiver,
+                });
+        }
         let (callback, receiver) = self.handler.into_cb_receiver_pair();
         session
-            .declare_subscriber_inner(
-                &key_expr,
-                &None,
-                self.origin,
-                callback,
-                &SubInfo {
-                    reliability: self.reliability,
-                    mode: self.mode.into(),
-                },
-            )
+            .declare_subscriber_inner(&key_expr, &None, self.origin, callback, &info)
             .map(|sub_state| Subscriber {
                 subscriber: SubscriberInner {
                     session,
@@ -574,6 +758,9 @@ where
 {
     fn res_sync(self) -> <Self as Resolvable>::To {
         let key_expr = self.key_expr?;
+        if !self.extra_key_exprs.is_empty() {
+            bail!("Pull subscribers can't be declared on several key expressions")
+        }
         let session = self.session;
         let (callback, receiver) = self.handler.into_cb_receiver_pair();
         session
@@ -585,6 +772,8 @@ where
                 &SubInfo {
                     reliability: self.reliability,
                     mode: self.mode.into(),
+                    filter: self.filter,
+                    aggregation: self.aggregation,
                 },
             )
             .map(|sub_state| PullSubscriber {
@@ -33,6 +33,9 @@ use zenoh_shm::SharedMemoryBuf;
 #[derive(Clone)]
 pub struct Value {
     /// The payload of this Value.
+    ///
+    /// Cloning a Value (e.g. when delivering a sample to several subscribers) shares the memory of
+    /// its payload: use [`ZBuf::to_mut`] to modify it, which copies it only if it is shared.
     pub payload: ZBuf,
     /// An encoding description indicating how the associated payload is encoded.
     pub encoding: Encoding,
@@ -383,3 +386,25 @@ impl TryFrom<Value> for Properties {
         Self::try_from(&v)
     }
 }
+
+#[test]
+#[cfg(feature = "shared-memory")]
+fn value_shm_to_mut() {
+    use zenoh_shm::SharedMemoryManager;
+
+    let id = format!("value_shm_to_mut_{}", std::process::id());
+    let mut manager = SharedMemoryManager::make(id, 1024).unwrap();
+    let mut smb = manager.alloc(4).unwrap();
+    unsafe { smb.as_mut_slice() }.copy_from_slice(&[0u8, 1, 2, 3]);
+    let segment = smb.as_slice().as_ptr();
+    let mut value = Value::from(smb);
+
+    // The payload is copied rather than written into the segment read by other processes
+    value.payload.to_mut()[0] = 10;
+    assert_ne!(value.payload.as_contiguous().unwrap().as_ptr(), segment);
+    assert_eq!(&value.payload.contiguous()[..], &[10u8, 1, 2, 3]);
+    assert_eq!(
+        unsafe { std::slice::from_raw_parts(segment, 4) },
+        &[0u8, 1, 2, 3]
+    );
+}
@@ -160,6 +160,310 @@ async fn test_session_qryrep(peer01: &Session, peer02: &Session) {
     }
 }
 
+#[cfg(feature = "unstable")]
+async fn test_session_matching(peer01: &Session, peer02: &Session) {
+    let key_expr = "test/session/matching";
+
+    println!("[MT][01b] Declaring publisher on peer02 session");
+    let publisher = ztimeout!(peer02
+        .declare_publisher(key_expr)
+        .skip_unmatched(true)
+        .res_async())
+    .unwrap();
+    assert!(!publisher.has_remote_subscribers());
+
+    println!("[MT][02b] Subscribing on peer01 session");
+    let sub = ztimeout!(peer01.declare_subscriber("test/session/*").res_async()).unwrap();
+    task::sleep(SLEEP).await;
+    assert!(publisher.has_remote_subscribers());
+
+    ztimeout!(publisher.put("value").res_async()).unwrap();
+    let sample = ztimeout!(sub.recv_async()).unwrap();
+    assert_eq!(sample.key_expr.as_str(), key_expr);
+
+    println!("[MT][03b] Unsubscribing on peer01 session");
+    ztimeout!(sub.undeclare().res_async()).unwrap();
+    task::sleep(SLEEP).await;
+    assert!(!publisher.has_remote_subscribers());
+}
+
+#[cfg(feature = "unstable")]
+async fn test_session_locality(peer01: &Session, peer02: &Session) {
+    let key_expr = "test/session/locality";
+
+    println!("[LO][01b] Subscribing with each locality on peer01 session");
+    let remote = ztimeout!(peer01
+        .declare_subscriber(key_expr)
+        .allowed_origin(Locality::Remote)
+        .res_async())
+    .unwrap();
+    let local = ztimeout!(peer01
+        .declare_subscriber(key_expr)
+        .allowed_origin(Locality::SessionLocal)
+        .res_async())
+    .unwrap();
+    let any = ztimeout!(peer01.declare_subscriber(key_expr).res_async()).unwrap();
+    task::sleep(SLEEP).await;
+
+    println!("[LO][02b] Putting on peer01 and peer02 sessions");
+    ztimeout!(peer01.put(key_expr, "local").res_async()).unwrap();
+    ztimeout!(peer02.put(key_expr, "remote").res_async()).unwrap();
+    task::sleep(SLEEP).await;
+
+    let values = |sub: &flume::Receiver<Sample>| {
+        sub.try_iter()
+            .map(|s| s.value.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(values(&remote), ["remote"]);
+    assert_eq!(values(&local), ["local"]);
+    assert_eq!(values(&any), ["local", "remote"]);
+
+    println!("[LO][03b] Putting on peer02 session towards local subscribers only");
+    ztimeout!(peer02
+        .put(key_expr, "unsent")
+        .allowed_destination(Locality::SessionLocal)
+        .res_async())
+    .unwrap();
+    task::sleep(SLEEP).await;
+    assert!(values(&any).is_empty());
+
+    println!("[LO][04b] Unsubscribing on peer01 session");
+    ztimeout!(remote.undeclare().res_async()).unwrap();
+    ztimeout!(local.undeclare().res_async()).unwrap();
+    ztimeout!(any.undeclare().res_async()).unwrap();
+}
+
+#[cfg(feature = "unstable")]
+async fn test_session_query_locality(peer01: &Session, peer02: &Session) {
+    let key_expr = "test/session/query_locality";
+
+    println!("[QL][01c] Queryables on peer01 and peer02 sessions");
+    let qbl01 = ztimeout!(peer01
+        .declare_queryable(key_expr)
+        .callback(move |query| {
+            task::block_on(async {
+                let rep = Sample::try_from(key_expr, "peer01").unwrap();
+                ztimeout!(query.reply(Ok(rep)).res_async()).unwrap()
+            });
+        })
+        .res_async())
+    .unwrap();
+    let qbl02 = ztimeout!(peer02
+        .declare_queryable(key_expr)
+        .callback(move |query| {
+            task::block_on(async {
+                let rep = Sample::try_from(key_expr, "peer02").unwrap();
+                ztimeout!(query.reply(Ok(rep)).res_async()).unwrap()
+            });
+        })
+        .res_async())
+    .unwrap();
+    task::sleep(SLEEP).await;
+
+    println!("[QL][02c] Getting on peer01 session with each destination");
+    for (destination, expected) in [
+        (Locality::SessionLocal, vec!["peer01"]),
+        (Locality::Remote, vec!["peer02"]),
+        (Locality::Any, vec!["peer01", "peer02"]),
+    ] {
+        let replies = ztimeout!(peer01
+            .get(key_expr)
+            .consolidation(ConsolidationMode::None)
+            .allowed_destination(destination)
+            .res_async())
+        .unwrap();
+        let mut values = Vec::new();
+        while let Ok(reply) = ztimeout!(replies.recv_async()) {
+            values.push(reply.sample.unwrap().value.to_string());
+        }
+        values.sort();
+        assert_eq!(values, expected);
+    }
+
+    println!("[QL][03c] Unqueryable on peer01 and peer02 sessions");
+    ztimeout!(qbl01.undeclare().res_async()).unwrap();
+    ztimeout!(qbl02.undeclare().res_async()).unwrap();
+    task::sleep(SLEEP).await;
+}
+
+#[cfg(feature = "unstable")]
+async fn test_session_queryable_limits(peer01: &Session, peer02: &Session) {
+    let key_expr = "test/session/queryable_limits";
+
+    println!("[QB][01c] Queryable handling 1 query at a time on peer01 session");
+    let held = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let c_held = held.clone();
+    let qbl = ztimeout!(peer01
+        .declare_queryable(key_expr)
+        .callback(move |query| c_held.lock().unwrap().push(query))
+        .max_concurrent_queries(1)
+        .max_queued_queries(1)
+        .res_async())
+    .unwrap();
+    task::sleep(SLEEP).await;
+
+    println!("[QB][02c] Getting 3 times on peer02 session");
+    let mut replies = Vec::new();
+    for _ in 0..3 {
+        replies.push(ztimeout!(peer02.get(key_expr).res_async()).unwrap());
+    }
+    task::sleep(SLEEP).await;
+    assert_eq!(held.lock().unwrap().len(), 1);
+
+    // the third query is rejected
+    let reply = ztimeout!(replies[2].recv_async()).unwrap();
+    assert_eq!(reply.sample.unwrap_err().to_string(), "overloaded");
+    assert!(ztimeout!(replies[2].recv_async()).is_err());
+
+    println!("[QB][03c] Replying to the handled queries");
+    for rs in &replies[..2] {
+        let query = held.lock().unwrap().pop().unwrap();
+        let rep = Sample::try_from(key_expr, "value").unwrap();
+        ztimeout!(query.reply(Ok(rep)).res_async()).unwrap();
+        // releasing the query dispatches the queued one
+        drop(query);
+        assert!(ztimeout!(rs.recv_async()).unwrap().sample.is_ok());
+        assert!(ztimeout!(rs.recv_async()).is_err());
+        task::sleep(SLEEP).await;
+    }
+    assert!(held.lock().unwrap().is_empty());
+
+    println!("[QB][04c] Unqueryable on peer01 session");
+    ztimeout!(qbl.undeclare().res_async()).unwrap();
+    task::sleep(SLEEP).await;
+}
+
+#[cfg(feature = "unstable")]
+async fn test_session_queryable_drain(peer01: &Session, peer02: &Session) {
+    let key_expr = "test/session/queryable_drain";
+
+    println!("[QD][01c] Queryable replying late on peer01 session");
+    let qbl = ztimeout!(peer01
+        .declare_queryable(key_expr)
+        .callback(move |query| {
+            task::spawn(async move {
+                task::sleep(SLEEP).await;
+                let rep = Sample::try_from(key_expr, "late").unwrap();
+                ztimeout!(query.reply(Ok(rep)).res_async()).unwrap();
+            });
+        })
+        .res_async())
+    .unwrap();
+    task::sleep(SLEEP).await;
+
+    println!("[QD][02c] Getting on peer02 session");
+    let rs = ztimeout!(peer02.get(key_expr).res_async()).unwrap();
+    task::sleep(SLEEP / 10).await;
+
+    println!("[QD][03c] Undeclaring the queryable while the query is in flight");
+    ztimeout!(qbl.undeclare_drained(TIMEOUT).res_async()).unwrap();
+    let reply = ztimeout!(rs.recv_async()).unwrap();
+    assert_eq!(reply.sample.unwrap().value.to_string(), "late");
+    assert!(ztimeout!(rs.recv_async()).is_err());
+    task::sleep(SLEEP).await;
+
+    println!("[QD][04c] Queryable never receiving its queries on peer01 session");
+    let qbl = ztimeout!(peer01.declare_queryable(key_expr).res_async()).unwrap();
+    task::sleep(SLEEP).await;
+
+    println!("[QD][05c] Getting on peer02 session");
+    let rs = ztimeout!(peer02.get(key_expr).res_async()).unwrap();
+    task::sleep(SLEEP).await;
+
+    println!("[QD][06c] Undeclaring the queryable drops the queries left in its channel");
+    let start = std::time::Instant::now();
+    ztimeout!(qbl.undeclare_drained(TIMEOUT).res_async()).unwrap();
+    assert!(start.elapsed() < TIMEOUT / 2);
+    assert!(ztimeout!(rs.recv_async()).is_err());
+    task::sleep(SLEEP).await;
+}
+
+#[cfg(feature = "unstable")]
+async fn test_session_multi_key(peer01: &Session, peer02: &Session) {
+    println!("[MK][01b] Subscribing on several key expressions on peer01 session");
+    let sub = ztimeout!(peer01
+        .declare_subscriber("test/multi/a")
+        .add_key_exprs(["test/multi/*", "test/other/**", "test/x/*/c", "test/x/b/*"])
+        .res_async())
+    .unwrap();
+    task::sleep(SLEEP).await;
+
+    println!("[MK][02b] Putting on peer02 session");
+    let keys = [
+        "test/multi/a",
+        "test/multi/b",
+        "test/other/a/b",
+        "test/x/b/c",
+    ];
+    for key in keys {
+        ztimeout!(peer02.put(key, "value").res_async()).unwrap();
+    }
+    ztimeout!(peer02.put("test/unrelated", "value").res_async()).unwrap();
+    task::sleep(SLEEP).await;
+
+    // each sample is received once, even when matching several key expressions
+    for key in keys {
+        let sample = ztimeout!(sub.recv_async()).unwrap();
+        assert_eq!(sample.key_expr.as_str(), key);
+    }
+    assert!(sub.try_recv().is_err());
+
+    println!("[MK][03b] Unsubscribing on peer01 session");
+    ztimeout!(sub.undeclare().res_async()).unwrap();
+}
+
+#[cfg(feature = "unstable")]
+async fn test_session_declare_batch(peer01: &Session, peer02: &Session) {
+    use zenoh_core::SyncResolve;
+
+    println!("[DB][01b] Declaring a batch of subscribers on peer01 session");
+    let subs = peer01
+        .declare_batch(|session| {
+            (0..10)
+                .map(|i| {
+                    session
+                        .declare_subscriber(format!("test/batch/{i}"))
+                        .res_sync()
+                })
+                .collect::<zenoh::Result<Vec<_>>>()
+        })
+        .unwrap();
+    task::sleep(SLEEP).await;
+
+    println!("[DB][02b] Putting on peer02 session");
+    for i in 0..10 {
+        ztimeout!(peer02.put(format!("test/batch/{i}"), "value").res_async()).unwrap();
+    }
+    for (i, sub) in subs.iter().enumerate() {
+        let sample = ztimeout!(sub.recv_async()).unwrap();
+        assert_eq!(sample.key_expr.as_str(), format!("test/batch/{i}"));
+    }
+
+    println!("[DB][03b] Unsubscribing on peer01 session");
+    for sub in subs {
+        ztimeout!(sub.undeclare().res_async()).unwrap();
+    }
+}
+
+#[cfg(feature = "unstable")]
+async fn test_session_features(peer01: &Session, peer02: &Session) {
+    println!("[FT][01b] Checking the features negotiated by peer01 session");
+    let mut features = ztimeout!(peer01.info().features().res_async());
+    let (zid, f01) = features.next().unwrap();
+    assert_eq!(zid, peer02.zid());
+    assert!(f01.fragmentation());
+    assert!(f01.attachments());
+    assert!(!f01.compression());
+    assert!(f01.credits());
+
+    println!("[FT][02b] Checking the features negotiated by peer02 session");
+    let mut features = ztimeout!(peer02.info().features().res_async());
+    let (zid, f02) = features.next().unwrap();
+    assert_eq!(zid, peer01.zid());
+    assert_eq!(f01, f02);
+}
+
 #[test]
 fn zenoh_session() {
     task::block_on(async {
@@ -169,6 +473,850 @@ fn zenoh_session() {
         let (peer01, peer02) = open_session(&["tcp/127.0.0.1:17447"]).await;
         test_session_pubsub(&peer01, &peer02).await;
         test_session_qryrep(&peer01, &peer02).await;
+        #[cfg(feature = "unstable")]
+        test_session_matching(&peer01, &peer02).await;
+        #[cfg(feature = "unstable")]
+        test_session_locality(&peer01, &peer02).await;
+        #[cfg(feature = "unstable")]
+        test_session_query_locality(&peer01, &peer02).await;
+        #[cfg(feature = "unstable")]
+        test_session_queryable_limits(&peer01, &peer02).await;
+        #[cfg(feature = "unstable")]
+        test_session_queryable_drain(&peer01, &peer02).await;
+        #[cfg(feature = "unstable")]
+        test_session_multi_key(&peer01, &peer02).await;
+        #[cfg(feature = "unstable")]
+        test_session_declare_batch(&peer01, &peer02).await;
+        #[cfg(feature = "unstable")]
+        test_session_features(&peer01, &peer02).await;
+        close_session(peer01, peer02).await;
+    });
+}
+
+#[test]
+fn zenoh_session_client_retry() {
+    task::block_on(async {
+        zasync_executor_init!();
+        let _ = env_logger::try_init();
+        let endpoint = "tcp/127.0.0.1:17448";
+
+        println!("[  ][01a] Opening client session before its router");
+        let mut config = config::client([endpoint.parse::<config::EndPoint>().unwrap()]);
+        config.connect.set_timeout_ms(Some(1_000)).unwrap();
+        config.connect.set_retries(Some(5)).unwrap();
+        let client = task::spawn(async move { zenoh::open(config).res_async().await });
+        task::sleep(SLEEP).await;
+
+        println!("[  ][02a] Opening peer session");
+        let mut config = config::peer();
+        config.listen.endpoints = vec![endpoint.parse().unwrap()];
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        let peer = ztimeout!(zenoh::open(config).res_async()).unwrap();
+
+        let client = ztimeout!(client).unwrap();
+        close_session(peer, client).await;
+    });
+}
+
+#[cfg(feature = "unstable")]
+#[test]
+fn zenoh_session_sibling() {
+    task::block_on(async {
+        zasync_executor_init!();
+        let _ = env_logger::try_init();
+        let key_expr = "test/session/sibling";
+
+        let mut config = config::peer();
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        println!("[SB][01a] Opening session and its sibling");
+        let session = ztimeout!(zenoh::open(config).res_async()).unwrap();
+        let sibling = ztimeout!(session.new_sibling().res_async());
+        assert_eq!(session.zid(), sibling.zid());
+
+        println!("[SB][02b] Subscribing on sibling session");
+        let sub = ztimeout!(sibling
+            .declare_subscriber(key_expr)
+            .allowed_origin(Locality::Remote)
+            .res_async())
+        .unwrap();
+        task::sleep(SLEEP).await;
+
+        println!("[SB][03b] Putting on session");
+        ztimeout!(session.put(key_expr, "value").res_async()).unwrap();
+        let sample = ztimeout!(sub.recv_async()).unwrap();
+        assert_eq!(sample.value.to_string(), "value");
+
+        ztimeout!(sub.undeclare().res_async()).unwrap();
+        println!("[SB][04d] Closing sibling session");
+        ztimeout!(sibling.close().res_async()).unwrap();
+        // the runtime is still open
+        ztimeout!(session.put(key_expr, "value").res_async()).unwrap();
+        ztimeout!(session.close().res_async()).unwrap();
+    });
+}
+
+#[test]
+fn zenoh_session_namespace() {
+    task::block_on(async {
+        zasync_executor_init!();
+        let _ = env_logger::try_init();
+        let endpoint = "tcp/127.0.0.1:17449";
+
+        let mut config = config::peer();
+        config.listen.endpoints = vec![endpoint.parse().unwrap()];
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        println!("[NS][01a] Opening peer01 session");
+        let peer01 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+
+        let mut config = config::peer();
+        config.connect.endpoints = vec![endpoint.parse().unwrap()];
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        config
+            .set_namespace(Some("tenantA".parse().unwrap()))
+            .unwrap();
+        println!("[NS][02a] Opening peer02 session in namespace tenantA");
+        let peer02 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+
+        println!("[NS][01b] Subscribing on peer01 session");
+        let sub01 = ztimeout!(peer01
+            .declare_subscriber("tenantA/test/session/namespace")
+            .res_async())
+        .unwrap();
+        println!("[NS][02b] Subscribing on peer02 session");
+        let sub02 = ztimeout!(peer02
+            .declare_subscriber("test/session/namespace")
+            .res_async())
+        .unwrap();
+        task::sleep(SLEEP).await;
+
+        // the publications of peer02 are prefixed
+        println!("[NS][02c] Putting on peer02 session");
+        ztimeout!(peer02.put("test/session/namespace", "in").res_async()).unwrap();
+        let sample = ztimeout!(sub01.recv_async()).unwrap();
+        assert_eq!(sample.key_expr.as_str(), "tenantA/test/session/namespace");
+        // while the local ones are not
+        let sample = ztimeout!(sub02.recv_async()).unwrap();
+        assert_eq!(sample.key_expr.as_str(), "test/session/namespace");
+
+        // and the namespace is stripped from the publications received by peer02
+        println!("[NS][01c] Putting on peer01 session");
+        ztimeout!(peer01
+            .put("tenantA/test/session/namespace", "in")
+            .res_async())
+        .unwrap();
+        let sample = ztimeout!(sub02.recv_async()).unwrap();
+        assert_eq!(sample.key_expr.as_str(), "test/session/namespace");
+
+        // publications outside of the namespace don't reach peer02
+        ztimeout!(peer01.put("test/session/namespace", "out").res_async()).unwrap();
+        task::sleep(SLEEP).await;
+        assert!(sub02.try_recv().is_err());
+
+        // the namespace is stripped from the wildcard queries intersecting it
+        println!("[NS][02d] Queryable on peer02 session");
+        let qbl = ztimeout!(peer02
+            .declare_queryable("test/session/namespace/qbl")
+            .res_async())
+        .unwrap();
+        task::sleep(SLEEP).await;
+        println!("[NS][01e] Getting on peer01 session");
+        let rs = ztimeout!(peer01.get("*/test/session/namespace/*").res_async()).unwrap();
+        let query = ztimeout!(qbl.recv_async()).unwrap();
+        assert_eq!(query.key_expr().as_str(), "test/session/namespace/*");
+        let rep = Sample::try_from("test/session/namespace/qbl", "qbl").unwrap();
+        ztimeout!(query.reply(Ok(rep)).res_async()).unwrap();
+        drop(query);
+        let reply = ztimeout!(rs.recv_async()).unwrap();
+        assert_eq!(
+            reply.sample.unwrap().key_expr.as_str(),
+            "tenantA/test/session/namespace/qbl"
+        );
+        ztimeout!(qbl.undeclare().res_async()).unwrap();
+
+        ztimeout!(sub01.undeclare().res_async()).unwrap();
+        ztimeout!(sub02.undeclare().res_async()).unwrap();
+        close_session(peer01, peer02).await;
+    });
+}
+
+#[test]
+fn zenoh_session_remapping() {
+    task::block_on(async {
+        zasync_executor_init!();
+        let _ = env_logger::try_init();
+        let endpoint = "tcp/127.0.0.1:17450";
+
+        let mut config = config::peer();
+        config.listen.endpoints = vec![endpoint.parse().unwrap()];
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        config.routing.remapping = vec![config::KeyRemappingRule {
+            zids: vec![],
+            from: "factoryA".parse().unwrap(),
+            to: "site1/factoryA".parse().unwrap(),
+        }];
+        println!("[RM][01a] Opening peer01 session remapping factoryA to site1/factoryA");
+        let peer01 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+
+        let mut config = config::peer();
+        config.connect.endpoints = vec![endpoint.parse().unwrap()];
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        println!("[RM][02a] Opening peer02 session");
+        let peer02 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+
+        println!("[RM][01b] Subscribing on peer01 session");
+        let sub01 = ztimeout!(peer01.declare_subscriber("site1/factoryA/**").res_async()).unwrap();
+        println!("[RM][02b] Subscribing on peer02 session");
+        let sub02 = ztimeout!(peer02.declare_subscriber("factoryA/**").res_async()).unwrap();
+        task::sleep(SLEEP).await;
+
+        // the key expressions received from peer02 are remapped
+        println!("[RM][02c] Putting on peer02 session");
+        ztimeout!(peer02.put("factoryA/temperature", "21").res_async()).unwrap();
+        let sample = ztimeout!(sub01.recv_async()).unwrap();
+        assert_eq!(sample.key_expr.as_str(), "site1/factoryA/temperature");
+        let _ = ztimeout!(sub02.recv_async()).unwrap();
+
+        // and so are the ones sent to peer02, the other way around
+        println!("[RM][01c] Putting on peer01 session");
+        ztimeout!(peer01.put("site1/factoryA/pressure", "1013").res_async()).unwrap();
+        let sample = ztimeout!(sub02.recv_async()).unwrap();
+        assert_eq!(sample.key_expr.as_str(), "factoryA/pressure");
+        let _ = ztimeout!(sub01.recv_async()).unwrap();
+
+        ztimeout!(sub01.undeclare().res_async()).unwrap();
+        ztimeout!(sub02.undeclare().res_async()).unwrap();
+        close_session(peer01, peer02).await;
+    });
+}
+
+#[test]
+fn zenoh_session_interests() {
+    task::block_on(async {
+        zasync_executor_init!();
+        let _ = env_logger::try_init();
+        let endpoint = "tcp/127.0.0.1:17451";
+
+        let mut config = config::default();
+        config.set_mode(Some(config::WhatAmI::Router)).unwrap();
+        config.listen.endpoints = vec![endpoint.parse().unwrap()];
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        config
+            .routing
+            .router
+            .set_client_interests(Some(true))
+            .unwrap();
+        println!("[IN][01a] Opening router session restricting declarations to client interests");
+        let router = ztimeout!(zenoh::open(config).res_async()).unwrap();
+
+        let mut config = config::client([endpoint.parse::<config::EndPoint>().unwrap()]);
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        println!("[IN][02a] Opening client02 session");
+        let client02 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+
+        println!("[IN][02b] Subscribing on client02 session");
+        let sub_a =
+            ztimeout!(client02.declare_subscriber("test/interest/a/x").res_async()).unwrap();
+        let sub_b =
+            ztimeout!(client02.declare_subscriber("test/interest/b/x").res_async()).unwrap();
+        task::sleep(SLEEP).await;
+
+        let mut config = config::client([endpoint.parse::<config::EndPoint>().unwrap()]);
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        config
+            .routing
+            .client
+            .set_interests(vec!["test/interest/a/**".parse().unwrap()])
+            .unwrap();
+        println!("[IN][03a] Opening client03 session interested in test/interest/a/**");
+        let client03 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+        task::sleep(SLEEP).await;
+
+        // client03 only knows the subscriptions matching its interests
+        println!("[IN][03b] Putting on client03 session");
+        ztimeout!(client03.put("test/interest/a/x", "a").res_async()).unwrap();
+        ztimeout!(client03.put("test/interest/b/x", "b").res_async()).unwrap();
+        let sample = ztimeout!(sub_a.recv_async()).unwrap();
+        assert_eq!(sample.key_expr.as_str(), "test/interest/a/x");
+        task::sleep(SLEEP).await;
+        assert!(sub_b.try_recv().is_err());
+
+        ztimeout!(sub_a.undeclare().res_async()).unwrap();
+        ztimeout!(sub_b.undeclare().res_async()).unwrap();
+        ztimeout!(client03.close().res_async()).unwrap();
+        close_session(router, client02).await;
+    });
+}
+
+#[test]
+fn zenoh_session_admin_close() {
+    task::block_on(async {
+        zasync_executor_init!();
+        let _ = env_logger::try_init();
+        let endpoint = "tcp/127.0.0.1:17452";
+
+        let mut config = config::peer();
+        config.listen.endpoints = vec![endpoint.parse().unwrap()];
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        config.adminspace.permissions.set_write(true).unwrap();
+        println!("[AC][01a] Opening peer01 session with a writable admin space");
+        let peer01 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+
+        let mut config = config::peer();
+        config.connect.endpoints = vec![endpoint.parse().unwrap()];
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        println!("[AC][02a] Opening peer02 session");
+        let peer02 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+        task::sleep(SLEEP).await;
+
+        let peers: Vec<ZenohId> = ztimeout!(peer01.info().peers_zid().res_async()).collect();
+        assert_eq!(peers, vec![peer02.zid()]);
+
+        println!("[AC][01b] Closing the session with peer02 through the admin space");
+        let key_expr = format!("@/router/{}/session/{}", peer01.zid(), peer02.zid());
+        ztimeout!(peer01.delete(key_expr).res_async()).unwrap();
+        task::sleep(SLEEP).await;
+
+        assert_eq!(ztimeout!(peer01.info().peers_zid().res_async()).count(), 0);
+        close_session(peer01, peer02).await;
+    });
+}
+
+#[test]
+fn zenoh_session_admin_audit() {
+    task::block_on(async {
+        zasync_executor_init!();
+        let _ = env_logger::try_init();
+        let endpoint = "tcp/127.0.0.1:17463";
+        let path = std::env::temp_dir().join(format!("zenoh-audit-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut config = config::peer();
+        config.listen.endpoints = vec![endpoint.parse().unwrap()];
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        config.adminspace.permissions.set_read(false).unwrap();
+        config.adminspace.permissions.set_write(true).unwrap();
+        config.audit.set_enabled(Some(true)).unwrap();
+        config
+            .audit
+            .set_path(Some(path.to_str().unwrap().to_string()))
+            .unwrap();
+        println!("[AA][01a] Opening peer01 session with an audited admin space");
+        let peer01 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+
+        let mut config = config::peer();
+        config.connect.endpoints = vec![endpoint.parse().unwrap()];
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        println!("[AA][02a] Opening peer02 session");
+        let peer02 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+        task::sleep(SLEEP).await;
+
+        println!("[AA][02b] Writing and reading the admin space of peer01 from peer02");
+        let key_expr = format!("@/router/{}/status", peer01.zid());
+        ztimeout!(peer02.put(&key_expr, "value").res_async()).unwrap();
+        let replies = ztimeout!(peer02.get(&key_expr).res_async()).unwrap();
+        assert!(ztimeout!(replies.recv_async()).is_err());
+        task::sleep(SLEEP).await;
+
+        // the admin events record the node that made the access
+        let events: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .filter(|event: &serde_json::Value| event["key_expr"] == key_expr.as_str())
+            .collect();
+        let zid = peer02.zid().to_string();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["event"], "admin_write");
+        assert_eq!(events[0]["zid"], zid.as_str());
+        assert_eq!(events[1]["event"], "access_denied");
+        assert_eq!(events[1]["operation"], "read");
+        assert_eq!(events[1]["zid"], zid.as_str());
+
+        close_session(peer01, peer02).await;
+        let _ = std::fs::remove_file(&path);
+    });
+}
+
+#[test]
+fn zenoh_session_unicast_scouting() {
+    task::block_on(async {
+        zasync_executor_init!();
+        let _ = env_logger::try_init();
+        let endpoint = "tcp/127.0.0.1:17457";
+        let scouting = "127.0.0.1:17458";
+
+        let mut config = config::default();
+        config.set_mode(Some(config::WhatAmI::Router)).unwrap();
+        config.listen.endpoints = vec![endpoint.parse().unwrap()];
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        config
+            .scouting
+            .unicast
+            .set_address(Some(scouting.parse().unwrap()))
+            .unwrap();
+        println!("[US][01a] Opening router session replying to unicast scouts");
+        let router = ztimeout!(zenoh::open(config).res_async()).unwrap();
+
+        let mut config = config::client(Vec::<config::EndPoint>::new());
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        config
+            .scouting
+            .unicast
+            .set_probes(vec![scouting.parse().unwrap()])
+            .unwrap();
+        println!("[US][02a] Opening client session probing the router");
+        let client = ztimeout!(zenoh::open(config).res_async()).unwrap();
+
+        let routers: Vec<ZenohId> = ztimeout!(client.info().routers_zid().res_async()).collect();
+        assert_eq!(routers, vec![router.zid()]);
+        close_session(router, client).await;
+    });
+}
+
+#[cfg(feature = "unstable")]
+#[test]
+fn zenoh_session_connectivity() {
+    use zenoh::ConnectivityState;
+
+    async fn await_state(session: &Session, expected: ConnectivityState) {
+        let watcher = session.connectivity_watcher();
+        ztimeout!(async { while watcher.recv_async().await.unwrap() != expected {} });
+    }
+
+    task::block_on(async {
+        zasync_executor_init!();
+        let _ = env_logger::try_init();
+        let endpoint = "tcp/127.0.0.1:17459";
+
+        let mut config = config::peer();
+        config.listen.endpoints = vec![endpoint.parse().unwrap()];
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        println!("[CS][01a] Opening peer01 session");
+        let peer01 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+        assert_eq!(peer01.connectivity(), ConnectivityState::Connecting);
+
+        let mut config = config::peer();
+        config.connect.endpoints = vec![endpoint.parse().unwrap()];
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        println!("[CS][02a] Opening peer02 session");
+        let peer02 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+        await_state(&peer01, ConnectivityState::Connected(1)).await;
+        await_state(&peer02, ConnectivityState::Connected(1)).await;
+
+        println!("[CS][02b] Closing peer02 session");
+        ztimeout!(peer02.close().res_async()).unwrap();
+        await_state(&peer01, ConnectivityState::Degraded).await;
+
+        println!("[CS][01b] Closing peer01 session");
+        let watcher = peer01.connectivity_watcher();
+        ztimeout!(peer01.close().res_async()).unwrap();
+        let states: Vec<ConnectivityState> = watcher.drain().collect();
+        assert_eq!(states.last(), Some(&ConnectivityState::Closed));
+    });
+}
+
+#[cfg(feature = "unstable")]
+#[test]
+fn zenoh_session_priority_stats() {
+    use zenoh::publication::Priority;
+
+    task::block_on(async {
+        zasync_executor_init!();
+        let _ = env_logger::try_init();
+
+        let (peer01, peer02) = open_session(&["tcp/127.0.0.1:17460"]).await;
+        let key_expr = "test/session/priority";
+        let sub = ztimeout!(peer02.declare_subscriber(key_expr).res_async()).unwrap();
+        task::sleep(SLEEP).await;
+
+        println!("[PS][01a] Publishing with the real time priority");
+        let publisher = ztimeout!(peer01
+            .declare_publisher(key_expr)
+            .priority(Priority::RealTime)
+            .res_async())
+        .unwrap();
+        for _ in 0..MSG_COUNT {
+            ztimeout!(publisher.put(vec![0u8; 8]).res_async()).unwrap();
+        }
+        for _ in 0..MSG_COUNT {
+            ztimeout!(sub.recv_async()).unwrap();
+        }
+
+        let stats = peer01.stats();
+        let real_time = stats.priority(Priority::RealTime);
+        assert!(real_time.capacity > 0);
+        assert!(real_time.pushed >= MSG_COUNT as u64);
+        assert_eq!(real_time.dropped, 0);
+        assert_eq!(stats.priority(Priority::Background).pushed, 0);
+
+        ztimeout!(publisher.undeclare().res_async()).unwrap();
+        ztimeout!(sub.undeclare().res_async()).unwrap();
+        close_session(peer01, peer02).await;
+    });
+}
+
+#[test]
+fn zenoh_session_domain() {
+    task::block_on(async {
+        zasync_executor_init!();
+        let _ = env_logger::try_init();
+        let endpoint = "tcp/127.0.0.1:17456";
+
+        let mut config = config::peer();
+        config.listen.endpoints = vec![endpoint.parse().unwrap()];
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        config.set_domain(Some(1)).unwrap();
+        println!("[DO][01a] Opening peer01 session in domain 1");
+        let peer01 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+
+        let mut config = config::peer();
+        config.connect.endpoints = vec![endpoint.parse().unwrap()];
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        println!("[DO][02a] Opening peer02 session in the default domain");
+        let peer02 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+        task::sleep(SLEEP).await;
+
+        // peer01 rejects the peers of other domains
+        assert_eq!(ztimeout!(peer01.info().peers_zid().res_async()).count(), 0);
+        assert_eq!(ztimeout!(peer02.info().peers_zid().res_async()).count(), 0);
+
+        let mut config = config::peer();
+        config.connect.endpoints = vec![endpoint.parse().unwrap()];
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        config.set_domain(Some(1)).unwrap();
+        println!("[DO][03a] Opening peer03 session in domain 1");
+        let peer03 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+        task::sleep(SLEEP).await;
+
+        let peers: Vec<ZenohId> = ztimeout!(peer01.info().peers_zid().res_async()).collect();
+        assert_eq!(peers, vec![peer03.zid()]);
+
+        ztimeout!(peer03.close().res_async()).unwrap();
+        close_session(peer01, peer02).await;
+    });
+}
+
+#[cfg(feature = "unstable")]
+#[test]
+fn zenoh_session_buffer_while_disconnected() {
+    task::block_on(async {
+        zasync_executor_init!();
+        let _ = env_logger::try_init();
+        let endpoint = "tcp/127.0.0.1:17453";
+        let key_expr = "test/session/buffered";
+
+        let mut config = config::peer();
+        config.listen.endpoints = vec![endpoint.parse().unwrap()];
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        println!("[BF][01a] Opening peer01 session with no connectivity");
+        let peer01 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+
+        println!("[BF][01b] Putting on a buffering publisher of peer01");
+        let publisher = ztimeout!(peer01
+            .declare_publisher(key_expr)
+            .buffer_while_disconnected(2, None)
+            .res_async())
+        .unwrap();
+        for value in ["0", "1", "2"] {
+            ztimeout!(publisher.put(value).res_async()).unwrap();
+        }
+        // the oldest sample is dropped when the buffer is full
+        assert_eq!(publisher.buffered(), 2);
+
+        let mut config = config::peer();
+        config.connect.endpoints = vec![endpoint.parse().unwrap()];
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        println!("[BF][02a] Opening peer02 session and subscribing");
+        let peer02 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+        let sub = ztimeout!(peer02.declare_subscriber(key_expr).res_async()).unwrap();
+
+        // the buffered samples are sent in order once peer02 is reachable
+        for value in ["1", "2"] {
+            let sample = ztimeout!(sub.recv_async()).unwrap();
+            assert_eq!(sample.value.to_string(), value);
+        }
+        assert_eq!(publisher.buffered(), 0);
+
+        ztimeout!(sub.undeclare().res_async()).unwrap();
+        ztimeout!(publisher.undeclare().res_async()).unwrap();
+        close_session(peer01, peer02).await;
+    });
+}
+
+#[cfg(feature = "unstable")]
+#[test]
+fn zenoh_session_publisher_timestamp() {
+    task::block_on(async {
+        zasync_executor_init!();
+        let _ = env_logger::try_init();
+        let endpoint = "tcp/127.0.0.1:17455";
+        let key_expr = "test/session/timestamp";
+
+        let timestamped = || {
+            let mut config = config::peer();
+            config.scouting.multicast.set_enabled(Some(false)).unwrap();
+            config
+                .timestamping
+                .set_enabled(Some(zenoh::config::ModeDependentValue::Unique(true)))
+                .unwrap();
+            config
+        };
+        let mut config = timestamped();
+        config.listen.endpoints = vec![endpoint.parse().unwrap()];
+        println!("[TS][01a] Opening timestamping peer01 session");
+        let peer01 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+        let mut config = timestamped();
+        config.connect.endpoints = vec![endpoint.parse().unwrap()];
+        println!("[TS][01b] Opening timestamping peer02 session");
+        let peer02 = ztimeout!(zenoh::open(config).res_async()).unwrap();
+
+        let sub = ztimeout!(peer02.declare_subscriber(key_expr).res_async()).unwrap();
+        let publisher = ztimeout!(peer01.declare_publisher(key_expr).res_async()).unwrap();
+        task::sleep(SLEEP).await;
+
+        println!("[TS][02a] Putting with a past timestamp");
+        let id = peer01.hlc().unwrap().get_id().to_owned();
+        let now = zenoh::time::new_reception_timestamp();
+        let past =
+            zenoh::time::NTP64::from(now.get_time().to_duration() - Duration::from_secs(3_600));
+        let timestamp = zenoh::time::Timestamp::new(past, id);
+        ztimeout!(publisher
+            .put("replayed")
+            .with_timestamp(timestamp)
+            .res_async())
+        .unwrap();
+        let sample = ztimeout!(sub.recv_async()).unwrap();
+        assert_eq!(sample.timestamp, Some(timestamp));
+
+        println!("[TS][02b] Putting with a timestamp beyond the allowed drift");
+        let future =
+            zenoh::time::NTP64::from(now.get_time().to_duration() + Duration::from_secs(3_600));
+        let timestamp = zenoh::time::Timestamp::new(future, id);
+        assert!(ztimeout!(publisher
+            .put("future")
+            .with_timestamp(timestamp)
+            .res_async())
+        .is_err());
+
+        ztimeout!(publisher.undeclare().res_async()).unwrap();
+        ztimeout!(sub.undeclare().res_async()).unwrap();
+        close_session(peer01, peer02).await;
+    });
+}
+
+#[cfg(feature = "unstable")]
+#[test]
+fn zenoh_session_declarations() {
+    use zenoh::DeclarationKind;
+
+    task::block_on(async {
+        zasync_executor_init!();
+        let _ = env_logger::try_init();
+
+        let mut config = config::peer();
+        config.scouting.multicast.set_enabled(Some(false)).unwrap();
+        println!("[DC][01a] Opening session");
+        let session = ztimeout!(zenoh::open(config).res_async()).unwrap();
+
+        println!("[DC][02a] Declaring a subscriber, a queryable and a publisher");
+        let sub = ztimeout!(session
+            .declare_subscriber("test/declarations/*")
+            .res_async())
+        .unwrap();
+        let queryable =
+            ztimeout!(session.declare_queryable("test/declarations/q").res_async()).unwrap();
+        let publisher =
+            ztimeout!(session.declare_publisher("test/declarations/p").res_async()).unwrap();
+        ztimeout!(publisher.put("value").res_async()).unwrap();
+        ztimeout!(sub.recv_async()).unwrap();
+
+        let declarations = session.declarations();
+        let kinds: Vec<DeclarationKind> = declarations.iter().map(|d| d.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                DeclarationKind::Publisher,
+                DeclarationKind::Subscriber,
+                DeclarationKind::Queryable
+            ]
+        );
+        let subscriber = &declarations[1];
+        assert_eq!(subscriber.key_expr.as_str(), "test/declarations/*");
+        assert_eq!(subscriber.received, Some(1));
+        let queryable_declaration = &declarations[2];
+        assert_eq!(
+            queryable_declaration.key_expr.as_str(),
+            "test/declarations/q"
+        );
+        assert_eq!(queryable_declaration.in_flight, Some(0));
+
+        println!("[DC][03a] Undeclaring the subscriber");
+        ztimeout!(sub.undeclare().res_async()).unwrap();
+        assert!(session
+            .declarations()
+            .iter()
+            .all(|d| d.kind != DeclarationKind::Subscriber));
+
+        ztimeout!(queryable.undeclare().res_async()).unwrap();
+        ztimeout!(publisher.undeclare().res_async()).unwrap();
+        ztimeout!(session.close().res_async()).unwrap();
+    });
+}
+
+#[cfg(feature = "unstable")]
+#[test]
+fn zenoh_session_query_trace() {
+    use zenoh_core::SyncResolve;
+
+    task::block_on(async {
+        zasync_executor_init!();
+        let _ = env_logger::try_init();
+
+        let (peer01, peer02) = open_session(&["tcp/127.0.0.1:17454"]).await;
+
+        println!("[QT][01b] Declaring a queryable on peer01 session");
+        let queryable = ztimeout!(peer01
+            .declare_queryable("test/trace")
+            .callback(|query| {
+                let sample = Sample::new(query.key_expr().clone(), "reply");
+                query.reply(Ok(sample)).res_sync().unwrap();
+            })
+            .res_async())
+        .unwrap();
+        task::sleep(SLEEP).await;
+
+        println!("[QT][02b] Tracing a query from peer02 session");
+        let replies = ztimeout!(peer02.get("test/trace").trace().res_async()).unwrap();
+        let reply = ztimeout!(replies.recv_async()).unwrap();
+        assert!(reply.sample.is_ok());
+        let trace = reply.trace().unwrap();
+        println!("[QT][03b] Query trace: {:?}", trace);
+        assert!(trace.hops.len() >= 2);
+        assert_eq!(trace.hops.first().unwrap().zid, peer02.zid());
+        assert_eq!(trace.hops.last().unwrap().zid, peer01.zid());
+
+        println!("[QT][04b] Querying without tracing");
+        let replies = ztimeout!(peer02.get("test/trace").res_async()).unwrap();
+        let reply = ztimeout!(replies.recv_async()).unwrap();
+        assert!(reply.trace().is_none());
+
+        ztimeout!(queryable.undeclare().res_async()).unwrap();
+        close_session(peer01, peer02).await;
+    });
+}
+
+#[test]
+fn zenoh_session_query_timeout() {
+    task::block_on(async {
+        zasync_executor_init!();
+        let _ = env_logger::try_init();
+
+        let (peer01, peer02) = open_session(&["tcp/127.0.0.1:17461"]).await;
+
+        println!("[QO][01b] Declaring a queryable never finishing its queries on peer01 session");
+        let pending = Arc::new(std::sync::Mutex::new(vec![]));
+        let queryable = ztimeout!(peer01
+            .declare_queryable("test/timeout")
+            .callback({
+                let pending = pending.clone();
+                move |query| pending.lock().unwrap().push(query)
+            })
+            .res_async())
+        .unwrap();
+        task::sleep(SLEEP).await;
+
+        println!("[QO][02b] Querying with a timeout from peer02 session");
+        let replies = ztimeout!(peer02
+            .get("test/timeout")
+            .timeout(Duration::from_millis(500))
+            .res_async())
+        .unwrap();
+        let reply = ztimeout!(replies.recv_async()).unwrap();
+        assert!(reply.sample.is_err());
+        // The receiver is closed after the timeout
+        assert!(ztimeout!(replies.recv_async()).is_err());
+        assert_eq!(pending.lock().unwrap().len(), 1);
+
+        pending.lock().unwrap().clear();
+        ztimeout!(queryable.undeclare().res_async()).unwrap();
+        close_session(peer01, peer02).await;
+    });
+}
+
+#[cfg(feature = "unstable")]
+#[test]
+fn zenoh_session_attachment() {
+    use zenoh_core::SyncResolve;
+
+    task::block_on(async {
+        zasync_executor_init!();
+        let _ = env_logger::try_init();
+
+        let (peer01, peer02) = open_session(&["tcp/127.0.0.1:17462"]).await;
+
+        println!("[AT][01b] Declaring a subscriber on peer01 session");
+        let sub = ztimeout!(peer01.declare_subscriber("test/attachment").res_async()).unwrap();
+        task::sleep(SLEEP).await;
+
+        println!("[AT][02b] Putting with an attachment from peer02 session");
+        ztimeout!(peer02
+            .put("test/attachment", "value")
+            .with_attachment(b"put".to_vec())
+            .res_async())
+        .unwrap();
+        let sample = ztimeout!(sub.recv_async()).unwrap();
+        assert_eq!(sample.value.to_string(), "value");
+        assert_eq!(
+            sample.attachment.as_ref().unwrap().contiguous().as_ref(),
+            b"put"
+        );
+
+        println!("[AT][03b] Publishing with and without an attachment from peer02 session");
+        let publisher = ztimeout!(peer02.declare_publisher("test/attachment").res_async()).unwrap();
+        ztimeout!(publisher
+            .put("value")
+            .with_attachment(b"publication".to_vec())
+            .res_async())
+        .unwrap();
+        let sample = ztimeout!(sub.recv_async()).unwrap();
+        assert_eq!(
+            sample.attachment.as_ref().unwrap().contiguous().as_ref(),
+            b"publication"
+        );
+        ztimeout!(publisher.put("value").res_async()).unwrap();
+        let sample = ztimeout!(sub.recv_async()).unwrap();
+        assert!(sample.attachment.is_none());
+
+        println!("[AT][04b] Declaring a queryable echoing the attachments on peer01 session");
+        let queryable = ztimeout!(peer01
+            .declare_queryable("test/attachment")
+            .callback(|query| {
+                let mut sample = Sample::new(query.key_expr().clone(), "reply");
+                if let Some(attachment) = query.attachment() {
+                    assert!(query.value().is_none());
+                    sample = sample.with_attachment(attachment.clone());
+                }
+                query.reply(Ok(sample)).res_sync().unwrap();
+            })
+            .res_async())
+        .unwrap();
+        task::sleep(SLEEP).await;
+
+        println!("[AT][05b] Querying with an attachment from peer02 session");
+        let replies = ztimeout!(peer02
+            .get("test/attachment")
+            .with_attachment(b"query".to_vec())
+            .res_async())
+        .unwrap();
+        let sample = ztimeout!(replies.recv_async()).unwrap().sample.unwrap();
+        assert_eq!(
+            sample.attachment.as_ref().unwrap().contiguous().as_ref(),
+            b"query"
+        );
+
+        ztimeout!(queryable.undeclare().res_async()).unwrap();
+        ztimeout!(publisher.undeclare().res_async()).unwrap();
+        ztimeout!(sub.undeclare().res_async()).unwrap();
         close_session(peer01, peer02).await;
     });
 }
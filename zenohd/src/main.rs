@@ -18,7 +18,8 @@ use git_version::git_version;
 use zenoh::config::{
     Config, EndPoint, ModeDependentValue, PermissionsConf, PluginLoad, ValidatedMap,
 };
-use zenoh::plugins::PluginsManager;
+#[allow(unused_imports)]
+use zenoh::plugins::{Plugin, PluginsManager};
 use zenoh::runtime::{AdminSpace, Runtime};
 
 const GIT_VERSION: &str = git_version!(prefix = "v", cargo_prefix = "v");
@@ -69,21 +70,38 @@ r#"Allows arbitrary configuration changes as column-separated KEY:VALUE pairs, w
 Examples:
 --cfg='startup/subscribe:["demo/**"]'
 --cfg='plugins/storage_manager/storages/demo:{key_expr:"demo/example/**",volume:"memory"}'"#),
+clap::arg!(--"config-schema" "Print the JSON schema of the configuration file and exit."),
 clap::Arg::new("adminspace-permissions").long("adminspace-permissions").value_name("[r|w|rw|none]").help(r"Configure the read and/or write permissions on the admin space. Default is read only."),
                 ]
             );
         let args = app.get_matches();
+        if args.is_present("config-schema") {
+            println!("{:#}", zenoh::config::schema::config());
+            return;
+        }
         let config = config_from_args(&args);
         log::info!("Initial conf: {}", &config);
 
-        let mut plugins = PluginsManager::dynamic(config.libloader());
-        // Static plugins are to be added here, with `.add_static::<PluginType>()`
+        let mut plugins = if cfg!(feature = "dynamic_plugins") {
+            PluginsManager::dynamic(config.libloader())
+        } else {
+            PluginsManager::static_plugins_only()
+        };
         for plugin_load in config.plugins().load_requests() {
             let PluginLoad {
                 name,
                 paths,
                 required,
+                ..
             } = plugin_load;
+            if paths.is_none() {
+                let (manager, added) = add_static_plugin(plugins, &name);
+                plugins = manager;
+                if added {
+                    log::info!("Using the statically linked plugin {}", name);
+                    continue;
+                }
+            }
             if let Err(e) = match paths {
                 None => plugins.load_plugin_by_name(name),
                 Some(paths) => plugins.load_plugin_by_paths(name, &paths),
@@ -133,6 +151,33 @@ clap::Arg::new("adminspace-permissions").long("adminspace-permissions").value_na
     });
 }
 
+// The registry of the plugins compiled in zenohd: adds the plugin `name` to the manager if it's one of them.
+#[allow(unused_mut)]
+fn add_static_plugin(mut plugins: PluginsManager, name: &str) -> (PluginsManager, bool) {
+    #[cfg(feature = "plugin_grpc")]
+    if name == <zenoh_plugin_grpc::GrpcPlugin as Plugin>::STATIC_NAME {
+        plugins = plugins.add_static::<zenoh_plugin_grpc::GrpcPlugin>();
+        return (plugins, true);
+    }
+    #[cfg(feature = "plugin_rest")]
+    if name == <zenoh_plugin_rest::RestPlugin as Plugin>::STATIC_NAME {
+        plugins = plugins.add_static::<zenoh_plugin_rest::RestPlugin>();
+        return (plugins, true);
+    }
+    #[cfg(feature = "plugin_storage_manager")]
+    if name == <zenoh_plugin_storage_manager::StoragesPlugin as Plugin>::STATIC_NAME {
+        plugins = plugins.add_static::<zenoh_plugin_storage_manager::StoragesPlugin>();
+        return (plugins, true);
+    }
+    #[cfg(feature = "plugin_webui")]
+    if name == <zenoh_plugin_webui::WebUiPlugin as Plugin>::STATIC_NAME {
+        plugins = plugins.add_static::<zenoh_plugin_webui::WebUiPlugin>();
+        return (plugins, true);
+    }
+    let _ = name;
+    (plugins, false)
+}
+
 fn config_from_args(args: &ArgMatches) -> Config {
     let mut config = args
         .value_of("config")